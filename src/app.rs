@@ -1,2 +1,5 @@
 pub(crate) mod app_settings;
 pub(crate) mod app_state;
+pub(crate) mod manifest_prefs;
+pub(crate) mod session;
+pub(crate) mod storage;
@@ -1,4 +1,5 @@
 use bevy::prelude::Resource;
+use serde::{Deserialize, Serialize};
 
 pub(crate) struct PanOrbitSettings {
     /// World units per pixel of mouse motion
@@ -16,6 +17,207 @@ impl Default for PanOrbitSettings {
     }
 }
 
+/// Backdrop and ground of the 3D models.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct ModelStageSettings {
+    /// Show a studio backdrop graded from the bottom to the top instead of the clear colour.
+    pub(crate) backdrop: bool,
+    /// Show a ground under the model with its shadow.
+    pub(crate) ground_shadow: bool,
+}
+
+impl Default for ModelStageSettings {
+    fn default() -> Self {
+        ModelStageSettings {
+            backdrop: true,
+            ground_shadow: true,
+        }
+    }
+}
+
+/// Speed of the panning and zooming while the modifier keys are held.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct PrecisionSettings {
+    /// Multiplier of the panning and zooming speed while Shift is held, for the fine framing.
+    pub(crate) precision_multiplier: f32,
+    /// Number of tiles panned by an arrow key while Ctrl is held.
+    pub(crate) tile_step_multiplier: f32,
+}
+
+impl Default for PrecisionSettings {
+    fn default() -> Self {
+        PrecisionSettings {
+            precision_multiplier: 0.1,
+            tile_step_multiplier: 1.0,
+        }
+    }
+}
+
+/// Zoom by the mouse wheel or the scroll of the trackpad.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct WheelSettings {
+    /// Multiplier of the zoom by a step of the wheel.
+    pub(crate) sensitivity: f32,
+    /// Zoom out by scrolling up instead of in.
+    pub(crate) invert: bool,
+    /// Zoom by the fractions of the steps reported by the wheel instead of a whole step per
+    /// notch. The scroll of the trackpad is always smooth.
+    pub(crate) smooth: bool,
+}
+
+impl Default for WheelSettings {
+    fn default() -> Self {
+        WheelSettings {
+            sensitivity: 1.0,
+            invert: false,
+            smooth: false,
+        }
+    }
+}
+
+/// Accessibility mode for the low vision.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct LowVisionSettings {
+    /// Larger fonts and high-contrast colors of the UI.
+    pub(crate) enabled: bool,
+    /// Show a lens magnifying the image under the cursor in the low-vision mode.
+    pub(crate) magnifier: bool,
+    /// Magnification of the lens, from 2 to 4.
+    pub(crate) magnification: f32,
+}
+
+impl Default for LowVisionSettings {
+    fn default() -> Self {
+        LowVisionSettings {
+            enabled: false,
+            magnifier: true,
+            magnification: 3.0,
+        }
+    }
+}
+
+/// Grid and rulers over the image, e.g. to transcribe or to align the crops of the derivatives.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct GridSettings {
+    /// Show the lines of the grid over the image.
+    pub(crate) show_grid: bool,
+    /// Pixels of the full size image between the lines of the grid.
+    pub(crate) grid_spacing: u32,
+    /// Show the rulers in the pixels of the full size image along the top and the left of the
+    /// viewport.
+    pub(crate) show_rulers: bool,
+}
+
+impl Default for GridSettings {
+    fn default() -> Self {
+        GridSettings {
+            show_grid: false,
+            grid_spacing: 100,
+            show_rulers: false,
+        }
+    }
+}
+
+/// Style of the tiles of the levels other than the current one, shown underneath it while its
+/// tiles are loading.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct TileLevelSettings {
+    /// Draw the tiles of the other levels translucent, e.g. to debug which level is loading.
+    /// Fully opaque if not.
+    pub(crate) dim_other_levels: bool,
+    /// Opacity of the tiles of the other levels when dimmed.
+    pub(crate) other_level_alpha: f32,
+    /// Depth of the tiles of the other levels from the current level, plus the index of their
+    /// level so that the finer levels are over the coarser ones.
+    pub(crate) other_level_z: f32,
+}
+
+impl Default for TileLevelSettings {
+    fn default() -> Self {
+        TileLevelSettings {
+            dim_other_levels: false,
+            other_level_alpha: 0.75,
+            other_level_z: -100.0,
+        }
+    }
+}
+
+/// How the image is fitted to the viewport when it is added, or on demand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum FitMode {
+    /// Show the whole image.
+    Whole,
+    /// Fit the width of the image, from its top.
+    Width,
+    /// Fit the height of the image.
+    Height,
+    /// Fill the viewport, cropping the image.
+    Fill,
+    /// One image pixel to one physical pixel of the screen.
+    Actual,
+}
+
+impl FitMode {
+    pub(crate) const ALL: [FitMode; 5] = [
+        FitMode::Whole,
+        FitMode::Width,
+        FitMode::Height,
+        FitMode::Fill,
+        FitMode::Actual,
+    ];
+
+    /// Get the label of the mode.
+    pub(crate) fn label(&self) -> &'static str {
+        match self {
+            FitMode::Whole => "Fit whole",
+            FitMode::Width => "Fit width",
+            FitMode::Height => "Fit height",
+            FitMode::Fill => "Fill",
+            FitMode::Actual => "100%",
+        }
+    }
+
+    /// Get the short label of the mode on the toolbar.
+    pub(crate) fn icon(&self) -> &'static str {
+        match self {
+            FitMode::Whole => "⛶",
+            FitMode::Width => "↔",
+            FitMode::Height => "↕",
+            FitMode::Fill => "▣",
+            FitMode::Actual => "1:1",
+        }
+    }
+
+    /// Get the next mode to cycle through them.
+    pub(crate) fn next(&self) -> Self {
+        let index = Self::ALL.iter().position(|x| x == self).unwrap_or_default();
+
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+/// Dark or light theme of the UI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ThemeMode {
+    /// Follow the preference of the system.
+    System,
+    Dark,
+    Light,
+}
+
+impl ThemeMode {
+    pub(crate) const ALL: [ThemeMode; 3] = [ThemeMode::System, ThemeMode::Dark, ThemeMode::Light];
+
+    /// Get the label of the mode.
+    pub(crate) fn label(&self) -> &'static str {
+        match self {
+            ThemeMode::System => "System",
+            ThemeMode::Dark => "Dark",
+            ThemeMode::Light => "Light",
+        }
+    }
+}
+
 #[derive(Resource)]
 pub(crate) struct AppSettings {
     /// Max number of items in the tile cache.
@@ -27,13 +229,66 @@ pub(crate) struct AppSettings {
     pub(crate) min_camera_zoom_scale: f32,
     /// Min image size allowed when zoom-out.
     pub(crate) min_image_size: f32,
-    /// User language setting, e.g. "en", "fr".
+    /// User language preferences in order as BCP 47 tags separated by commas, e.g. "en-GB, en, fr".
     pub(crate) language: String,
     /// Camera 3D pan orbit settings.
     pub(crate) pan_orbit_settings: PanOrbitSettings,
+    /// Backdrop and ground of the 3D models.
+    pub(crate) model_stage_settings: ModelStageSettings,
+    /// Prefix of the CORS proxy for the manifest, image info and tile URLs. Empty if not proxied.
+    pub(crate) proxy_prefix: String,
+    /// How the image is fitted to the viewport when it is added.
+    pub(crate) fit_mode: FitMode,
+    /// Pixels of the overlap requested around the tiles and cropped, to hide the seams between
+    /// them. 0 for no overlap.
+    pub(crate) tile_overlap: u32,
+    /// Save the data, e.g. on mobile, by skipping the full size level, requesting JPEG, limiting
+    /// the downloads at once and not prefetching the next canvas.
+    pub(crate) data_saver: bool,
+    /// Seconds to wait for the manifest or the image info before requesting it again.
+    pub(crate) download_timeout_secs: f32,
+    /// Number of the requests sent again automatically when the download times out.
+    pub(crate) download_retries: u32,
+    /// Convert the colors of the tiles with an embedded ICC profile to sRGB, for the color
+    /// fidelity of the artworks at the cost of decoding.
+    pub(crate) color_management: bool,
+    /// Milliseconds to wait between the tile requests to a host in the polite mode, to avoid
+    /// hammering the institutional servers. 0 if not polite.
+    pub(crate) polite_delay_ms: u32,
+    /// Dark or light theme of the UI.
+    pub(crate) theme_mode: ThemeMode,
+    /// Accent color of the UI in sRGB, e.g. of the selection and the view rect of the minimap.
+    /// `None` for the color of the theme.
+    pub(crate) accent_color: Option<[u8; 3]>,
+    /// Speed of the panning and zooming while the modifier keys are held.
+    pub(crate) precision_settings: PrecisionSettings,
+    /// Grid and rulers over the image.
+    pub(crate) grid_settings: GridSettings,
+    /// Zoom by the mouse wheel or the scroll of the trackpad.
+    pub(crate) wheel_settings: WheelSettings,
+    /// Larger fonts, high-contrast colors and the magnifier lens.
+    pub(crate) low_vision_settings: LowVisionSettings,
+    /// Offer to open the IIIF URLs copied to the clipboard, natively only.
+    pub(crate) watch_clipboard: bool,
+    /// Seconds between the automatic reloads of the manifest shown, e.g. while editing it. 0 if
+    /// not reloaded.
+    pub(crate) reload_interval_secs: u32,
+    /// Number of the first canvases whose image info is fetched ahead when a manifest is shown,
+    /// so that turning their pages skips the info requests.
+    pub(crate) prefetch_info_canvases: u32,
+    /// Color in sRGB of the extent of the canvas drawn behind the image, e.g. to see the
+    /// transparent parts of the image. `None` if not drawn.
+    pub(crate) canvas_background: Option<[u8; 3]>,
+    /// Path of the WGSL file of the custom effect applied to the view, natively only. Empty if
+    /// none.
+    #[cfg_attr(any(target_arch = "wasm32", target_os = "android"), allow(dead_code))]
+    pub(crate) effect_shader_path: String,
+    /// Style of the tiles of the levels other than the current one.
+    pub(crate) tile_level_settings: TileLevelSettings,
 }
 
 impl AppSettings {
+    #[allow(clippy::too_many_arguments)]
     fn new(
         max_cache_items: usize,
         thumbnail_size: f32,
@@ -41,6 +296,27 @@ impl AppSettings {
         min_image_size: f32,
         language: String,
         pan_orbit_settings: PanOrbitSettings,
+        model_stage_settings: ModelStageSettings,
+        proxy_prefix: String,
+        fit_mode: FitMode,
+        tile_overlap: u32,
+        data_saver: bool,
+        download_timeout_secs: f32,
+        download_retries: u32,
+        color_management: bool,
+        polite_delay_ms: u32,
+        theme_mode: ThemeMode,
+        accent_color: Option<[u8; 3]>,
+        precision_settings: PrecisionSettings,
+        grid_settings: GridSettings,
+        wheel_settings: WheelSettings,
+        low_vision_settings: LowVisionSettings,
+        watch_clipboard: bool,
+        reload_interval_secs: u32,
+        prefetch_info_canvases: u32,
+        canvas_background: Option<[u8; 3]>,
+        effect_shader_path: String,
+        tile_level_settings: TileLevelSettings,
     ) -> Self {
         Self {
             max_cache_items,
@@ -49,6 +325,27 @@ impl AppSettings {
             min_image_size,
             language,
             pan_orbit_settings,
+            model_stage_settings,
+            proxy_prefix,
+            fit_mode,
+            tile_overlap,
+            data_saver,
+            download_timeout_secs,
+            download_retries,
+            color_management,
+            polite_delay_ms,
+            theme_mode,
+            accent_color,
+            precision_settings,
+            grid_settings,
+            wheel_settings,
+            low_vision_settings,
+            watch_clipboard,
+            reload_interval_secs,
+            prefetch_info_canvases,
+            canvas_background,
+            effect_shader_path,
+            tile_level_settings,
         }
     }
 }
@@ -62,6 +359,38 @@ impl Default for AppSettings {
             256.0,
             crate::iiif::manifest::language::EN.to_string(),
             PanOrbitSettings::default(),
+            ModelStageSettings::default(),
+            "".to_string(),
+            FitMode::Whole,
+            0,
+            false,
+            30.0,
+            2,
+            false,
+            0,
+            ThemeMode::System,
+            None,
+            PrecisionSettings::default(),
+            GridSettings::default(),
+            WheelSettings::default(),
+            LowVisionSettings::default(),
+            false,
+            0,
+            10,
+            Some(crate::rendering::canvas_background::DEFAULT_CANVAS_BACKGROUND),
+            "".to_string(),
+            TileLevelSettings::default(),
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fit_mode_next() {
+        assert_eq!(FitMode::Whole.next(), FitMode::Width);
+        assert_eq!(FitMode::Actual.next(), FitMode::Whole);
+    }
+}
@@ -1,20 +1,52 @@
-use bevy::prelude::{Resource, Vec2};
+use crate::capability_cache::CapabilityCache;
+use crate::host_stats::HostStats;
+use crate::http_cache::HttpCache;
+use crate::iiif::image::ImageOrientation;
+use crate::rendering::comparison::ImageComparison;
+use bevy::platform::time::Instant;
+use bevy::prelude::{Rect, Resource, Vec2};
 use std::sync::{Arc, Mutex};
 
+#[derive(Clone)]
 pub(crate) struct ManifestDownloadInfo {
     pub(crate) url: String,
 }
 
+#[derive(Clone)]
 pub(crate) struct ImageDownloadInfo {
     pub(crate) iiif_endpoint: String,
+    pub(crate) sequence_index: usize,
     pub(crate) canvas_index: usize,
+    pub(crate) choice_index: usize,
+    /// Keep the current camera view when the image is added, e.g. when switching between choices.
+    pub(crate) preserve_view: bool,
+    /// Orientation of the image on the canvas.
+    pub(crate) orientation: ImageOrientation,
 }
 
 pub(crate) enum DownloadState<T> {
     None,
-    InProgress { url: String },
-    Done { json: String, info: T },
-    Error { url: String, msg: String },
+    /// Requested at the time, to time it out if the server hangs, with the info to request it
+    /// again.
+    InProgress {
+        url: String,
+        started: Instant,
+        info: T,
+    },
+    Done {
+        json: String,
+        info: T,
+    },
+    Error {
+        url: String,
+        msg: String,
+    },
+}
+
+impl<T> DownloadState<T> {
+    pub(crate) fn is_in_progress(&self) -> bool {
+        matches!(self, Self::InProgress { .. })
+    }
 }
 
 #[derive(Resource)]
@@ -23,32 +55,101 @@ pub(crate) struct AppState {
     pub(crate) level: usize,
     /// Current presentation manifest URL.
     pub(crate) presentation_url: String,
-    /// Current canvas index.
+    /// Current sequence index, e.g. of an alternate order of the canvases in a v2 manifest.
+    pub(crate) sequence_index: usize,
+    /// Current canvas index in the sequence.
     pub(crate) canvas_index: usize,
+    /// Current image choice index in the canvas.
+    pub(crate) choice_index: usize,
+    /// Keep the current camera view when the next tiled image is added.
+    pub(crate) preserve_view: bool,
+    /// Image choice index in the canvas drawn over the current image.
+    pub(crate) overlay_choice_index: Option<usize>,
+    /// Opacity of the overlay image.
+    pub(crate) overlay_opacity: f32,
+    /// Position of the curtain as a fraction of the viewport width, with the current image on
+    /// the left and the overlay on the right. `None` if not in the curtain mode.
+    pub(crate) curtain_position: Option<f32>,
+    /// Comparison of the current image with the overlay by blinking or their difference.
+    pub(crate) comparison: ImageComparison,
     /// Current manifest json download state.
     pub(crate) manifest_json_download_state: Arc<Mutex<DownloadState<ManifestDownloadInfo>>>,
     // Current image json download state.
     pub(crate) image_json_download_state: Arc<Mutex<DownloadState<ImageDownloadInfo>>>,
+    /// Current overlay image json download state.
+    pub(crate) overlay_json_download_state: Arc<Mutex<DownloadState<ImageDownloadInfo>>>,
     /// Current image max size in world space.
     pub(crate) world_image_max_size: Vec2,
+    /// Position of the world origin from the top left of the images in world space, moved near
+    /// the camera when it is far away to keep the tiles precise at deep zoom.
+    pub(crate) world_origin: Vec2,
+    /// Cache of the manifest and image info responses.
+    pub(crate) http_cache: HttpCache,
+    /// Requests to the hosts, shared with the loader of the tiles.
+    pub(crate) host_stats: HostStats,
+    /// Image info and quirks of the image services, kept across the sessions.
+    pub(crate) capability_cache: CapabilityCache,
+    /// Canvas to load instead of the first one when the next manifest is loaded, e.g. of a bookmark.
+    pub(crate) target_canvas_id: Option<String>,
+    /// Index of the canvas to load instead of the first one when the next manifest is loaded,
+    /// e.g. from the command line.
+    pub(crate) target_canvas_index: Option<usize>,
+    /// Region in the full size image to fit the view to when the next tiled image is added.
+    pub(crate) target_region: Option<Rect>,
+    /// Scale factor from the logical to the physical pixels of the window, e.g. 2 on a high-DPI
+    /// display, to pick the level matching the physical pixels.
+    pub(crate) scale_factor: f32,
 }
 
 impl AppState {
+    #[allow(clippy::too_many_arguments)]
     fn new(
         level: usize,
         presentation_url: String,
+        sequence_index: usize,
         canvas_index: usize,
+        choice_index: usize,
+        preserve_view: bool,
+        overlay_choice_index: Option<usize>,
+        overlay_opacity: f32,
+        curtain_position: Option<f32>,
+        comparison: ImageComparison,
         manifest_json_download_state: Arc<Mutex<DownloadState<ManifestDownloadInfo>>>,
         image_json_download_state: Arc<Mutex<DownloadState<ImageDownloadInfo>>>,
+        overlay_json_download_state: Arc<Mutex<DownloadState<ImageDownloadInfo>>>,
         world_image_max_size: Vec2,
+        world_origin: Vec2,
+        http_cache: HttpCache,
+        host_stats: HostStats,
+        capability_cache: CapabilityCache,
+        target_canvas_id: Option<String>,
+        target_canvas_index: Option<usize>,
+        target_region: Option<Rect>,
+        scale_factor: f32,
     ) -> Self {
         Self {
             level,
             presentation_url,
+            sequence_index,
             canvas_index,
+            choice_index,
+            preserve_view,
+            overlay_choice_index,
+            overlay_opacity,
+            curtain_position,
+            comparison,
             manifest_json_download_state,
             image_json_download_state,
+            overlay_json_download_state,
             world_image_max_size,
+            world_origin,
+            http_cache,
+            host_stats,
+            capability_cache,
+            target_canvas_id,
+            target_canvas_index,
+            target_region,
+            scale_factor,
         }
     }
 }
@@ -59,9 +160,25 @@ impl Default for AppState {
             0,
             "".to_string(),
             0,
+            0,
+            0,
+            false,
+            None,
+            0.5,
+            None,
+            ImageComparison::default(),
+            Arc::new(Mutex::new(DownloadState::None)),
             Arc::new(Mutex::new(DownloadState::None)),
             Arc::new(Mutex::new(DownloadState::None)),
             Vec2::ZERO,
+            Vec2::ZERO,
+            HttpCache::default(),
+            HostStats::default(),
+            CapabilityCache::default(),
+            None,
+            None,
+            None,
+            1.0,
         )
     }
 }
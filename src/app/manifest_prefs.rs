@@ -0,0 +1,171 @@
+use crate::{
+    app::{
+        app_settings::{AppSettings, FitMode},
+        app_state::AppState,
+        storage,
+    },
+    iiif::IiifError,
+};
+use bevy::prelude::{Res, ResMut, Resource, info, warn};
+use serde::{Deserialize, Serialize};
+
+/// Key of the viewing preferences of the manifests in the storage.
+const MANIFEST_PREFS_KEY: &str = "manifest_prefs";
+/// Max number of the manifests whose preferences are kept, the least recently viewed dropped.
+const MAX_MANIFEST_PREFS: usize = 200;
+
+/// Viewing preferences of a manifest, restored when it is opened again.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct ManifestPrefs {
+    /// Id of the manifest, or its URL if it has none.
+    pub(crate) id: String,
+    pub(crate) sequence_index: usize,
+    pub(crate) canvas_index: usize,
+    pub(crate) fit_mode: FitMode,
+    /// Whether the image is requested at the lower quality of the data saver.
+    pub(crate) data_saver: bool,
+}
+
+/// Viewing preferences of the manifests viewed, kept in the storage.
+#[derive(Resource)]
+pub(crate) struct ManifestPrefsStore {
+    /// Preferences of the manifests, the most recently viewed last.
+    items: Vec<ManifestPrefs>,
+    /// Id of the manifest shown, whose preferences are kept as they change.
+    current_id: Option<String>,
+    /// Whether to keep the preferences, e.g. not for the windows following another one.
+    enabled: bool,
+}
+
+impl ManifestPrefsStore {
+    /// Create the store with the preferences saved in the previous runs if enabled.
+    pub(crate) fn new(enabled: bool) -> Self {
+        let items = if enabled {
+            storage::read(MANIFEST_PREFS_KEY)
+                .and_then(|json| {
+                    serde_json::from_str::<Vec<ManifestPrefs>>(&json)
+                        .inspect_err(|e| warn!("Ignore the saved manifest preferences. {}", e))
+                        .ok()
+                })
+                .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+
+        Self {
+            items,
+            current_id: None,
+            enabled,
+        }
+    }
+
+    /// Get the preferences of the manifest being opened, or `None` if it was not viewed before.
+    /// The preferences of the manifest are kept from now on.
+    pub(crate) fn open(&mut self, id: &str) -> Option<&ManifestPrefs> {
+        self.current_id = Some(id.to_string());
+        self.items.iter().find(|x| x.id == id)
+    }
+
+    /// Keep the preferences as the most recently viewed. Whether they are changed.
+    fn update(&mut self, prefs: ManifestPrefs) -> bool {
+        if self.items.last() == Some(&prefs) {
+            return false;
+        }
+
+        self.items.retain(|x| x.id != prefs.id);
+        self.items.push(prefs);
+
+        if self.items.len() > MAX_MANIFEST_PREFS {
+            self.items.drain(..self.items.len() - MAX_MANIFEST_PREFS);
+        }
+
+        true
+    }
+
+    /// Save the preferences in the storage.
+    fn save(&self) {
+        match serde_json::to_string(&self.items)
+            .map_err(IiifError::from)
+            .and_then(|json| storage::write(MANIFEST_PREFS_KEY, &json))
+        {
+            Ok(_) => info!("Preferences saved for {} manifests", self.items.len()),
+            Err(e) => warn!("Failed to save the manifest preferences. {}", e),
+        }
+    }
+}
+
+/// Keep the preferences of the manifest shown as they change, e.g. when the page is turned.
+pub(crate) fn manifest_prefs_system(
+    mut manifest_prefs: ResMut<ManifestPrefsStore>,
+    app_state: Res<AppState>,
+    app_settings: Res<AppSettings>,
+) {
+    let Some(id) = manifest_prefs
+        .current_id
+        .clone()
+        .filter(|_| manifest_prefs.enabled)
+    else {
+        return;
+    };
+
+    let prefs = ManifestPrefs {
+        id,
+        sequence_index: app_state.sequence_index,
+        canvas_index: app_state.canvas_index,
+        fit_mode: app_settings.fit_mode,
+        data_saver: app_settings.data_saver,
+    };
+
+    if manifest_prefs.update(prefs) {
+        manifest_prefs.save();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_prefs(id: &str, canvas_index: usize) -> ManifestPrefs {
+        ManifestPrefs {
+            id: id.to_string(),
+            sequence_index: 0,
+            canvas_index,
+            fit_mode: FitMode::Width,
+            data_saver: false,
+        }
+    }
+
+    #[test]
+    fn test_manifest_prefs_json() {
+        let prefs = get_prefs("https://example.org/manifest.json", 3);
+        let json = serde_json::to_string(&prefs).expect("should serialise the preferences");
+
+        assert_eq!(
+            serde_json::from_str::<ManifestPrefs>(&json).expect("should parse the preferences"),
+            prefs
+        );
+    }
+
+    #[test]
+    fn test_manifest_prefs_update() {
+        let mut manifest_prefs = ManifestPrefsStore::new(false);
+
+        assert!(manifest_prefs.update(get_prefs("a", 1)));
+        assert!(manifest_prefs.update(get_prefs("b", 2)));
+        assert!(!manifest_prefs.update(get_prefs("b", 2)));
+
+        // The manifest viewed again is moved to the end with its new preferences.
+        assert!(manifest_prefs.update(get_prefs("a", 5)));
+        assert_eq!(manifest_prefs.items, [get_prefs("b", 2), get_prefs("a", 5)]);
+        assert_eq!(manifest_prefs.open("a"), Some(&get_prefs("a", 5)));
+        assert_eq!(manifest_prefs.open("c"), None);
+        assert_eq!(manifest_prefs.current_id.as_deref(), Some("c"));
+
+        for i in 0..MAX_MANIFEST_PREFS {
+            manifest_prefs.update(get_prefs(&i.to_string(), 0));
+        }
+
+        assert_eq!(manifest_prefs.items.len(), MAX_MANIFEST_PREFS);
+        assert_eq!(manifest_prefs.items[0].id, "0");
+    }
+}
@@ -0,0 +1,251 @@
+use crate::{
+    app::{app_state::AppState, storage},
+    camera::{main_camera::MainCamera2d, relative_view::RelativeView},
+    iiif::IiifError,
+    presentation::ui::EguiUiState,
+    rendering::tiled_image::TiledImage,
+};
+use bevy::{
+    app::AppExit,
+    platform::time::Instant,
+    prelude::{
+        MessageReader, Projection, Query, Res, ResMut, Resource, Single, Transform, Vec2, With,
+        info, warn,
+    },
+};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Key of the session in the storage.
+const SESSION_KEY: &str = "session";
+/// Min interval between saving the changed session, so that it is kept even if the app is not
+/// exited normally, e.g. closing the browser tab in wasm.
+const SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Camera view of the session relative to the image size.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub(crate) struct SessionView {
+    x: f32,
+    y: f32,
+    scale: f32,
+}
+
+impl From<RelativeView> for SessionView {
+    fn from(value: RelativeView) -> Self {
+        Self {
+            x: value.centre.x,
+            y: value.centre.y,
+            scale: value.scale,
+        }
+    }
+}
+
+impl From<SessionView> for RelativeView {
+    fn from(value: SessionView) -> Self {
+        Self {
+            centre: Vec2::new(value.x, value.y),
+            scale: value.scale,
+        }
+    }
+}
+
+/// Viewing session to be resumed in the next run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Session {
+    pub(crate) presentation_url: String,
+    /// Sequence index, defaulting to the first one for the sessions saved before it was kept.
+    #[serde(default)]
+    pub(crate) sequence_index: usize,
+    pub(crate) canvas_index: usize,
+    pub(crate) choice_index: usize,
+    /// Camera view. `None` if not viewing an image, e.g. a 3D model.
+    pub(crate) view: Option<SessionView>,
+    pub(crate) open_left_panel: bool,
+    pub(crate) open_notifications: bool,
+    pub(crate) open_settings: bool,
+}
+
+/// Saved session of the previous run and the one being restored.
+#[derive(Resource)]
+pub(crate) struct SessionState {
+    /// Session of the previous run offered to be restored. The session is not saved until this
+    /// is restored or dismissed, so that it is not overwritten by the startup manifest.
+    pub(crate) previous: Option<Session>,
+    /// Session being restored, with its canvas applied when the manifest is loaded and its view
+    /// when the image is added.
+    pub(crate) restoring: Option<Session>,
+    /// Whether to save the session, e.g. not for the windows following another one.
+    enabled: bool,
+    last_saved: Option<Session>,
+    last_saved_time: Instant,
+}
+
+impl SessionState {
+    /// Create the state with the session saved in the previous run if enabled.
+    pub(crate) fn new(enabled: bool) -> Self {
+        let previous = if enabled {
+            storage::read(SESSION_KEY).and_then(|json| {
+                serde_json::from_str::<Session>(&json)
+                    .inspect_err(|e| warn!("Ignore the saved session. {}", e))
+                    .ok()
+            })
+        } else {
+            None
+        };
+
+        Self {
+            previous,
+            restoring: None,
+            enabled,
+            last_saved: None,
+            last_saved_time: Instant::now(),
+        }
+    }
+
+    /// Get the canvas and choice indices to restore for the manifest loaded from the URL, or stop
+    /// restoring if another manifest is loaded.
+    pub(crate) fn get_restoring_canvas(&mut self, presentation_url: &str) -> Option<&Session> {
+        if self
+            .restoring
+            .as_ref()
+            .is_some_and(|x| x.presentation_url != presentation_url)
+        {
+            self.restoring = None;
+        }
+
+        self.restoring.as_ref()
+    }
+
+    /// Take the camera view to restore when the image is added.
+    pub(crate) fn take_restoring_view(&mut self) -> Option<RelativeView> {
+        self.restoring.take()?.view.map(RelativeView::from)
+    }
+
+    /// Save the session in the storage.
+    fn save(&mut self, session: Session) {
+        match serde_json::to_string(&session)
+            .map_err(IiifError::from)
+            .and_then(|json| storage::write(SESSION_KEY, &json))
+        {
+            Ok(_) => info!("Session saved for {}", session.presentation_url),
+            Err(e) => warn!("Failed to save the session. {}", e),
+        }
+
+        self.last_saved = Some(session);
+        self.last_saved_time = Instant::now();
+    }
+}
+
+/// Save the session when the app exits, and also when it changes at most every few seconds.
+pub(crate) fn save_session_system(
+    mut app_exit_reader: MessageReader<AppExit>,
+    mut session_state: ResMut<SessionState>,
+    app_state: Res<AppState>,
+    egui_ui_state: Res<EguiUiState>,
+    camera: Single<(&Transform, &Projection), With<MainCamera2d>>,
+    tiled_image_query: Query<(), With<TiledImage>>,
+) {
+    let exiting = app_exit_reader.read().count() > 0;
+
+    if !session_state.enabled
+        || session_state.previous.is_some()
+        || app_state.presentation_url.is_empty()
+    {
+        return;
+    }
+
+    let (transform, projection) = camera.into_inner();
+    let view = if tiled_image_query.is_empty() {
+        None
+    } else {
+        RelativeView::from_camera(
+            transform,
+            projection,
+            app_state.world_image_max_size,
+            app_state.world_origin,
+        )
+        .map(SessionView::from)
+    };
+
+    let session = Session {
+        presentation_url: app_state.presentation_url.to_string(),
+        sequence_index: app_state.sequence_index,
+        canvas_index: app_state.canvas_index,
+        choice_index: app_state.choice_index,
+        view,
+        open_left_panel: egui_ui_state.open_left_panel,
+        open_notifications: egui_ui_state.open_notifications,
+        open_settings: egui_ui_state.open_settings,
+    };
+
+    if session_state.last_saved.as_ref() == Some(&session)
+        || (!exiting && session_state.last_saved_time.elapsed() < SAVE_INTERVAL)
+    {
+        return;
+    }
+
+    session_state.save(session);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_json() {
+        let session = Session {
+            presentation_url: "https://example.org/manifest.json".to_string(),
+            sequence_index: 1,
+            canvas_index: 3,
+            choice_index: 1,
+            view: Some(SessionView::from(RelativeView {
+                centre: Vec2::new(0.5, -0.25),
+                scale: 0.125,
+            })),
+            open_left_panel: true,
+            open_notifications: false,
+            open_settings: false,
+        };
+
+        let json = serde_json::to_string(&session).expect("should serialise the session");
+
+        assert_eq!(
+            serde_json::from_str::<Session>(&json).expect("should parse the session"),
+            session
+        );
+        assert_eq!(
+            session.view.map(RelativeView::from),
+            Some(RelativeView {
+                centre: Vec2::new(0.5, -0.25),
+                scale: 0.125,
+            })
+        );
+    }
+
+    #[test]
+    fn test_restoring_canvas() {
+        let session = Session {
+            presentation_url: "https://example.org/manifest.json".to_string(),
+            sequence_index: 1,
+            canvas_index: 3,
+            choice_index: 1,
+            view: None,
+            open_left_panel: true,
+            open_notifications: false,
+            open_settings: false,
+        };
+        let mut session_state = SessionState::new(false);
+
+        session_state.restoring = Some(session.clone());
+
+        assert_eq!(
+            session_state.get_restoring_canvas("https://example.org/manifest.json"),
+            Some(&session)
+        );
+        assert_eq!(
+            session_state.get_restoring_canvas("https://example.org/other.json"),
+            None
+        );
+        assert!(session_state.restoring.is_none());
+    }
+}
@@ -0,0 +1,60 @@
+use crate::iiif::IiifError;
+
+/// Name of the folder of the stored values, or the prefix of their keys in the browser storage.
+const STORAGE_NAME: &str = "rs-iiif-browser";
+
+/// Get the folder of the stored values, e.g. "~/.config/rs-iiif-browser".
+#[cfg(not(target_arch = "wasm32"))]
+fn get_storage_dir() -> Option<std::path::PathBuf> {
+    #[cfg(target_os = "android")]
+    let config_dir = bevy::android::ANDROID_APP.get()?.internal_data_path();
+    #[cfg(target_os = "windows")]
+    let config_dir = std::env::var_os("APPDATA").map(std::path::PathBuf::from);
+    #[cfg(not(any(target_os = "android", target_os = "windows")))]
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(std::path::PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|x| std::path::PathBuf::from(x).join(".config")));
+
+    config_dir.map(|x| x.join(STORAGE_NAME))
+}
+
+/// Read the stored value of the key, e.g. the session. `None` if not stored.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn read(key: &str) -> Option<String> {
+    std::fs::read_to_string(get_storage_dir()?.join(format!("{key}.json"))).ok()
+}
+
+/// Store the value of the key.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn write(key: &str, value: &str) -> Result<(), IiifError> {
+    let storage_dir = get_storage_dir().ok_or(IiifError::IiifMissingInfo(
+        "no folder to store the settings".to_string(),
+    ))?;
+
+    std::fs::create_dir_all(&storage_dir)?;
+    std::fs::write(storage_dir.join(format!("{key}.json")), value)?;
+
+    Ok(())
+}
+
+/// Read the stored value of the key from the local storage of the browser.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn read(key: &str) -> Option<String> {
+    web_sys::window()?
+        .local_storage()
+        .ok()??
+        .get_item(&format!("{STORAGE_NAME}.{key}"))
+        .ok()?
+}
+
+/// Store the value of the key in the local storage of the browser.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn write(key: &str, value: &str) -> Result<(), IiifError> {
+    web_sys::window()
+        .and_then(|x| x.local_storage().ok().flatten())
+        .ok_or(IiifError::IiifMissingInfo(
+            "no local storage in the browser".to_string(),
+        ))?
+        .set_item(&format!("{STORAGE_NAME}.{key}"), value)
+        .map_err(|e| IiifError::IiifUnsupportedError(format!("{:?}", e)))
+}
@@ -1,12 +1,21 @@
-use crate::rendering::{
-    model_image::ModelLoading,
-    tile::{Tile, TileCache, TileLoading, TileModState},
+use crate::{
+    TileLoadFailed,
+    capability_cache::CapabilityCache,
+    host_stats::HostStats,
+    rendering::{
+        model_image::ModelLoading,
+        overlay_image::OverlayImage,
+        tile::{Tile, TileCache, TileLayer, TileLoading, TileModState},
+        tile_format::{TileFormats, get_url_format, is_client_error},
+        tiled_image::TiledImage,
+    },
 };
 use bevy::{
     asset::LoadState,
     prelude::{
         AssetServer, Assets, ColorMaterial, Commands, Entity, Mesh, Mesh2d, MeshMaterial2d,
-        MessageWriter, Query, Rectangle, Res, ResMut, Transform, Visibility, With, default, warn,
+        MessageWriter, Query, Rectangle, Res, ResMut, Transform, Visibility, With, default, info,
+        warn,
     },
     window::RequestRedraw,
 };
@@ -22,12 +31,20 @@ pub(crate) fn asset_event_system(
     mut materials: ResMut<Assets<ColorMaterial>>,
     mut tile_mod_state: ResMut<TileModState>,
     mut redraw_request_writer: MessageWriter<RequestRedraw>,
+    mut tiled_image_query: Query<&mut TiledImage>,
+    mut overlay_image_query: Query<&mut OverlayImage>,
+    mut tile_formats: ResMut<TileFormats>,
+    host_stats: Res<HostStats>,
+    capability_cache: Res<CapabilityCache>,
+    mut tile_load_failed_writer: MessageWriter<TileLoadFailed>,
 ) {
     // Keep polling if tiles or models are being loaded.
-    if !tiles.is_empty() || !models.is_empty() {
+    if !tiles.is_empty() || !models.is_empty() || tile_cache.has_requests() {
         redraw_request_writer.write(RequestRedraw);
     }
 
+    tile_cache.finish_requests(&asset_server);
+
     for (entity, tile) in tiles.iter() {
         match asset_server
             .get_load_state(tile.bevy_image.as_ref().expect("tile should have an image"))
@@ -44,16 +61,79 @@ pub(crate) fn asset_event_system(
                     ))),
                     MeshMaterial2d(materials.add(ColorMaterial {
                         texture: tile.bevy_image.clone(),
+                        uv_transform: tile.uv_transform,
                         ..default()
                     })),
                     Visibility::Hidden,
                 ));
                 tile_mod_state.invalidate();
             }
-            Some(LoadState::Failed(_)) => {
+            Some(LoadState::Failed(error)) if is_client_error(&error) => {
+                let handle = tile.bevy_image.as_ref().expect("tile should have an image");
+                let url = asset_server
+                    .get_path(handle.id())
+                    .map(|x| x.to_string())
+                    .unwrap_or_default();
+
+                host_stats.record_error(&url);
+
+                let image = match tile.layer {
+                    TileLayer::Base => tiled_image_query.iter_mut().next().map(|x| x.into_inner()),
+                    TileLayer::Overlay => overlay_image_query
+                        .iter_mut()
+                        .next()
+                        .map(|x| &mut x.into_inner().0),
+                }
+                .filter(|x| x.get_endpoint() == tile.endpoint);
+                let format = image
+                    .zip(get_url_format(&url))
+                    .and_then(|(image, failed_format)| image.fall_back_format(failed_format));
+
+                tile_load_failed_writer.write(TileLoadFailed {
+                    url: url.to_string(),
+                    error: error.to_string(),
+                    is_retried: format.is_some(),
+                });
+
+                match format {
+                    Some(format) => {
+                        info!("Failed to load tile {}. Request it in {}", url, format);
+                        tile_formats.insert(&tile.endpoint, &format);
+                        capability_cache.set_working_format(
+                            &TiledImage::get_image_info_url(&tile.endpoint),
+                            &format,
+                        );
+                        commands.entity(entity).despawn();
+                        tile_cache.remove(&tile.get_key());
+                        tile_mod_state.invalidate();
+                    }
+                    None => {
+                        // Kept in the cache so that it is not requested again endlessly.
+                        warn!("Failed to load tile {} in any format. {}", url, error);
+                        commands.entity(entity).remove::<TileLoading>();
+                    }
+                }
+            }
+            Some(LoadState::Failed(error)) => {
+                let url = tile
+                    .bevy_image
+                    .as_ref()
+                    .and_then(|x| x.path())
+                    .map(|x| x.to_string());
+
+                if let Some(url) = &url {
+                    host_stats.record_error(url);
+                }
+
+                tile_load_failed_writer.write(TileLoadFailed {
+                    url: url.unwrap_or_default(),
+                    error: error.to_string(),
+                    is_retried: true,
+                });
+
                 warn!("failed to load tile at {:?}. retry...", tile.index);
                 commands.entity(entity).despawn();
-                tile_cache.remove(&tile.index);
+                tile_cache.remove(&tile.get_key());
                 tile_mod_state.invalidate();
             }
             None => {}
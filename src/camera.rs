@@ -2,3 +2,4 @@ pub(crate) mod camera_ext;
 pub(crate) mod main_camera;
 pub(crate) mod pan_orbit_state_3d;
 pub(crate) mod pan_zoom_state_2d;
+pub(crate) mod relative_view;
@@ -1,14 +1,33 @@
 use crate::{
-    app::{app_settings::AppSettings, app_state::AppState},
-    camera::camera_ext::get_world_viewport_rect,
-    rendering::tiled_image::TiledImage,
+    app::{
+        app_settings::{AppSettings, FitMode},
+        app_state::AppState,
+    },
+    camera::{
+        camera_ext::get_world_viewport_rect,
+        pan_orbit_state_3d::{PanOrbitState3d, ViewPreset},
+    },
+    presentation::tour::Tour,
+    rendering::{
+        overlay_image::OverlayImage,
+        tile::{Tile, TileModState},
+        tiled_image::TiledImage,
+    },
 };
-use bevy::prelude::{
-    Camera, Component, GlobalTransform, Projection, Query, Rect, Single, Transform, Vec2, Vec3,
-    With,
+use bevy::{
+    prelude::{
+        ButtonInput, Camera, Component, GlobalTransform, Message, MessageReader, MessageWriter,
+        MouseButton, Projection, Query, Rect, Res, ResMut, Single, Touches, Transform, Vec2, Vec3,
+        Window, With, Without, debug,
+    },
+    window::{PrimaryWindow, RequestRedraw},
 };
 use bitflags::bitflags;
 
+/// Distance of the camera from the world origin in screen pixels beyond which the world is
+/// recentred, keeping the f32 world space precise to a fraction of a pixel at any zoom.
+const RECENTRE_SCREEN_DISTANCE: f32 = 65536.0;
+
 #[derive(Component)]
 pub(crate) struct MainCamera2d;
 
@@ -20,6 +39,7 @@ bitflags! {
         const Pan = 0b00000001;
         const Zoom = 0b00000010;
         const Orbit = 0b00000100;
+        const Roll = 0b00001000;
     }
 }
 
@@ -44,6 +64,7 @@ pub(crate) trait ApplyCameraState {
     /// * `viewport_centre` - Centre of the camera viewport.
     /// * `delta_zoom` - Amount of zoom changed of the operation.
     /// * `delta_move` - Amount of move changed of the operation.
+    /// * `delta_roll` - Angle in radians twisted clockwise on the screen by the operation.
     /// * `app_settings` - Application settings.
     /// * `app_state` - Application state.
     /// * `transform` - Camera transform to be updated.  
@@ -58,6 +79,7 @@ pub(crate) trait ApplyCameraState {
         viewport_centre: Vec2,
         delta_zoom: f32,
         delta_move: Vec3,
+        delta_roll: f32,
         app_settings: &AppSettings,
         app_state: &mut AppState,
         tiled_image: Query<&TiledImage>,
@@ -67,6 +89,103 @@ pub(crate) trait ApplyCameraState {
     );
 }
 
+/// Command to move the 2D camera on demand, e.g. from the toolbar.
+#[derive(Message, Debug, Clone, Copy, PartialEq)]
+pub(crate) enum CameraCommand {
+    /// Fit the image to the viewport by the mode.
+    Fit(FitMode),
+    /// Zoom to the region of the full size image, e.g. a bookmark or a selection.
+    ZoomToImageRect(Rect),
+    /// Zoom in by the factor around the center of the view, or out if less than 1.
+    Zoom(f32),
+}
+
+/// Command to move the 3D camera on demand, e.g. from the toolbar.
+#[derive(Message, Debug, Clone, Copy, PartialEq)]
+pub(crate) enum CameraCommand3d {
+    /// Go back to the initial view of the model.
+    Reset,
+    /// Look at the model from the side.
+    Preset(ViewPreset),
+    /// Look at the point on the model closer, e.g. double-clicked.
+    Focus(Vec3),
+}
+
+/// Apply the camera commands to the 3D camera.
+pub(crate) fn camera_command_3d_system(
+    mut command_reader: MessageReader<CameraCommand3d>,
+    mut transform: Single<&mut Transform, With<MainCamera3d>>,
+    mut pan_orbit_state: ResMut<PanOrbitState3d>,
+    mut redraw_request_writer: MessageWriter<RequestRedraw>,
+) {
+    for command in command_reader.read() {
+        match command {
+            CameraCommand3d::Reset => pan_orbit_state.reset(),
+            CameraCommand3d::Preset(preset) => pan_orbit_state.set_preset(*preset),
+            CameraCommand3d::Focus(point) => pan_orbit_state.focus(*point),
+        }
+
+        pan_orbit_state.update_transform(&mut transform);
+        redraw_request_writer.write(RequestRedraw);
+    }
+}
+
+/// Apply the camera commands to the 2D camera.
+pub(crate) fn camera_command_system(
+    mut command_reader: MessageReader<CameraCommand>,
+    camera: Single<(&Camera, &mut Transform, &mut Projection), With<MainCamera2d>>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    tiled_image: Option<Single<&TiledImage>>,
+    app_settings: Res<AppSettings>,
+    mut app_state: ResMut<AppState>,
+    mut tile_mod_state: ResMut<TileModState>,
+    mut redraw_request_writer: MessageWriter<RequestRedraw>,
+) {
+    let Some(tiled_image) = tiled_image else {
+        command_reader.clear();
+        return;
+    };
+    let (camera, mut transform, mut projection) = camera.into_inner();
+    let Projection::Orthographic(orthographic) = projection.as_mut() else {
+        return;
+    };
+    let viewport_size = camera
+        .logical_viewport_size()
+        .unwrap_or_else(|| window.size());
+
+    for command in command_reader.read() {
+        match command {
+            CameraCommand::Fit(fit_mode) => tiled_image.fit_camera(
+                *fit_mode,
+                viewport_size,
+                app_state.scale_factor,
+                &mut transform,
+                orthographic,
+            ),
+            CameraCommand::ZoomToImageRect(image_rect) => tiled_image.zoom_camera_to_image_rect(
+                *image_rect,
+                viewport_size,
+                &app_settings,
+                &mut transform,
+                orthographic,
+            ),
+            CameraCommand::Zoom(factor) => {
+                let max_camera_zoom_scale =
+                    tiled_image.get_world_max_size_rect().size().max_element()
+                        / app_settings.min_image_size;
+
+                orthographic.scale = (orthographic.scale / factor.max(f32::EPSILON))
+                    .max(app_settings.min_camera_zoom_scale)
+                    .min(max_camera_zoom_scale);
+            }
+        }
+
+        app_state.level = tiled_image.get_level_at(orthographic.scale, app_state.scale_factor);
+        tile_mod_state.invalidate();
+        redraw_request_writer.write(RequestRedraw);
+    }
+}
+
 /// Keep the image within the viewport.
 pub(crate) fn translation_bounding_system(
     camera: Single<(&Camera, &GlobalTransform, &mut Transform), With<MainCamera2d>>,
@@ -111,3 +230,87 @@ pub(crate) fn translation_bounding_system(
 
     transform.translation = bounded_translation.extend(transform.translation.z);
 }
+
+/// Get the move of the world origin to the camera if the camera is too far away from the origin
+/// for the zoom scale.
+fn get_recentring_delta(translation: Vec2, scale: f32) -> Option<Vec2> {
+    (translation.length() > scale * RECENTRE_SCREEN_DISTANCE).then_some(translation)
+}
+
+/// Move the world origin to the camera when it is far away, as the tiles would jitter at deep
+/// zoom with the f32 world space otherwise.
+///
+/// The images, the tiles and the camera are all moved by the same amount, so nothing moves on
+/// the screen.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn recentre_world_system(
+    camera: Single<(&mut Transform, &Projection), With<MainCamera2d>>,
+    mut tiled_image_query: Query<&mut TiledImage>,
+    mut overlay_image_query: Query<&mut OverlayImage>,
+    mut tile_query: Query<(&mut Tile, Option<&mut Transform>), Without<MainCamera2d>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    touches: Res<Touches>,
+    tour: Res<Tour>,
+    mut app_state: ResMut<AppState>,
+    mut tile_mod_state: ResMut<TileModState>,
+) {
+    // The drags and the tour transitions move the camera from the translations they started with.
+    if mouse.get_pressed().next().is_some()
+        || touches.iter().next().is_some()
+        || tour.is_transitioning()
+    {
+        return;
+    }
+
+    let (mut transform, projection) = camera.into_inner();
+    let Projection::Orthographic(orthographic) = projection else {
+        return;
+    };
+    let Some(delta) = get_recentring_delta(transform.translation.truncate(), orthographic.scale)
+    else {
+        return;
+    };
+
+    app_state.world_origin += delta;
+    debug!("World recentred at {:?}", app_state.world_origin);
+
+    transform.translation -= delta.extend(0.0);
+
+    for mut tiled_image in &mut tiled_image_query {
+        tiled_image.set_world_origin(app_state.world_origin);
+    }
+    for mut overlay_image in &mut overlay_image_query {
+        overlay_image.0.set_world_origin(app_state.world_origin);
+    }
+    // Including the tiles being loaded and of the other images kept in the cache.
+    for (mut tile, tile_transform) in &mut tile_query {
+        tile.world_position = Rect::from_corners(
+            tile.world_position.min - delta,
+            tile.world_position.max - delta,
+        );
+
+        if let Some(mut tile_transform) = tile_transform {
+            tile_transform.translation -= delta.extend(0.0);
+        }
+    }
+
+    tile_mod_state.invalidate();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_recentring_delta() {
+        // Kept while the camera is close for the zoom.
+        assert_eq!(
+            get_recentring_delta(Vec2::new(30000.0, -40000.0), 1.0),
+            None
+        );
+        assert_eq!(
+            get_recentring_delta(Vec2::new(300.0, -400.0), 0.001),
+            Some(Vec2::new(300.0, -400.0))
+        );
+    }
+}
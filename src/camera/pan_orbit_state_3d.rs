@@ -7,13 +7,54 @@ use bevy::prelude::{EulerRot, Projection, Quat, Query, Resource, Transform, Vec2
 use bitflags::Flags;
 use std::f32::consts::{FRAC_PI_2, PI, TAU};
 
+/// Min distance of the camera from the centre as a fraction of the initial distance.
+const MIN_RADIUS_FRACTION: f32 = 0.02;
+/// Max distance of the camera from the centre as a multiple of the initial distance.
+const MAX_RADIUS_MULTIPLE: f32 = 10.0;
+/// Change of the distance of the camera when focusing on a point.
+const FOCUS_ZOOM: f32 = 0.5;
+
+/// Preset view of the model from one of its sides.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ViewPreset {
+    Front,
+    Side,
+    Top,
+}
+
+impl ViewPreset {
+    pub(crate) const ALL: [ViewPreset; 3] = [Self::Front, Self::Side, Self::Top];
+
+    pub(crate) fn label(&self) -> &'static str {
+        match self {
+            Self::Front => "Front",
+            Self::Side => "Side",
+            Self::Top => "Top",
+        }
+    }
+
+    /// Get the pitch and the yaw of the camera looking at the side.
+    fn get_pitch_yaw(&self) -> (f32, f32) {
+        match self {
+            Self::Front => (0.0, 0.0),
+            Self::Side => (0.0, FRAC_PI_2),
+            Self::Top => (-FRAC_PI_2, 0.0),
+        }
+    }
+}
+
 #[derive(Resource, Clone)]
 pub(crate) struct PanOrbitState3d {
     pub(crate) center: Vec3,
     pub(crate) radius: f32,
     pub(crate) pitch: f32,
     pub(crate) yaw: f32,
+    /// Angle of the camera rolled around its view direction, e.g. by twisting two fingers.
+    pub(crate) roll: f32,
     pub(crate) is_added: bool,
+    /// Centre and distance of the initial view to reset to, also limiting the zoom.
+    home_center: Vec3,
+    home_radius: f32,
 }
 
 impl PanOrbitState3d {
@@ -23,20 +64,57 @@ impl PanOrbitState3d {
             radius,
             pitch,
             yaw,
+            roll: 0.0,
             is_added,
+            home_center: center,
+            home_radius: radius,
         }
     }
+
+    /// Limit the distance so that the camera cannot go too close or too far from the model.
+    fn clamp_radius(&self, radius: f32) -> f32 {
+        radius.clamp(
+            self.home_radius * MIN_RADIUS_FRACTION,
+            self.home_radius * MAX_RADIUS_MULTIPLE,
+        )
+    }
+
+    /// Go back to the initial view.
+    pub(crate) fn reset(&mut self) {
+        *self = Self::new(self.home_center, self.home_radius, 0.0, 0.0, true);
+    }
+
+    /// Look at the whole model from the side.
+    pub(crate) fn set_preset(&mut self, preset: ViewPreset) {
+        (self.pitch, self.yaw) = preset.get_pitch_yaw();
+        self.roll = 0.0;
+        self.center = self.home_center;
+        self.radius = self.home_radius;
+        self.is_added = true;
+    }
+
+    /// Look at the point closer from the same direction.
+    pub(crate) fn focus(&mut self, point: Vec3) {
+        self.center = point;
+        self.radius = self.clamp_radius(self.radius * FOCUS_ZOOM);
+        self.is_added = true;
+    }
+
+    /// Update the camera transform to the state.
+    pub(crate) fn update_transform(&mut self, transform: &mut Transform) {
+        // YXZ Euler Rotation performs yaw/pitch/roll.
+        transform.rotation = Quat::from_euler(EulerRot::YXZ, self.yaw, self.pitch, self.roll);
+        // To position the camera, get the backward direction vector
+        // and place the camera at the desired radius from the center.
+        transform.translation = self.center + transform.back() * self.radius;
+
+        self.is_added = false;
+    }
 }
 
 impl Default for PanOrbitState3d {
     fn default() -> Self {
-        PanOrbitState3d {
-            center: Vec3::ZERO,
-            radius: 1.0,
-            pitch: 0.0,
-            yaw: 0.0,
-            is_added: true,
-        }
+        PanOrbitState3d::new(Vec3::ZERO, 1.0, 0.0, 0.0, true)
     }
 }
 
@@ -53,6 +131,7 @@ impl ApplyCameraState for PanOrbitState3d {
         _: Vec2,
         delta_zoom: f32,
         delta_move: Vec3,
+        delta_roll: f32,
         app_settings: &AppSettings,
         _: &mut AppState,
         _: Query<&TiledImage>,
@@ -66,6 +145,11 @@ impl ApplyCameraState for PanOrbitState3d {
         if mode.intersects(CameraMode::Orbit) && delta_move != Vec3::ZERO {
             any = true;
 
+            // Orbit along the drag on the rolled screen, whose y axis is downwards.
+            let delta_move = Vec2::from_angle(-initial_state.roll)
+                .rotate(delta_move.truncate())
+                .extend(0.0);
+
             // If we are upside down, reverse the X orbiting
             let delta_move_x =
                 if initial_state.pitch < -FRAC_PI_2 || initial_state.pitch > FRAC_PI_2 {
@@ -103,7 +187,13 @@ impl ApplyCameraState for PanOrbitState3d {
 
             // so we compute the exponential of our
             // accumulated value and multiply by that
-            self.radius = initial_state.radius * delta_zoom;
+            self.radius = self.clamp_radius(initial_state.radius * delta_zoom);
+        }
+
+        // Twisting clockwise rolls the camera the other way to turn the model with the fingers.
+        if mode.intersects(CameraMode::Roll) && delta_roll != 0.0 {
+            any = true;
+            self.roll = (initial_state.roll + delta_roll + PI).rem_euclid(TAU) - PI;
         }
 
         // To PAN, we can get the UP and RIGHT direction
@@ -128,15 +218,60 @@ impl ApplyCameraState for PanOrbitState3d {
         // controller was just added and thus we are running
         // for the first time and need to initialize)
         if any || self.is_added {
-            // YXZ Euler Rotation performs yaw/pitch/roll.
-            transform.rotation = Quat::from_euler(EulerRot::YXZ, self.yaw, self.pitch, 0.0);
-            // To position the camera, get the backward direction vector
-            // and place the camera at the desired radius from the center.
-            transform.translation = self.center + transform.back() * self.radius;
-
-            self.is_added = false;
+            self.update_transform(transform);
         }
 
         invalidate.clear();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_view_commands() {
+        let mut state = PanOrbitState3d::new(Vec3::new(1.0, 2.0, 3.0), 10.0, 0.3, 0.4, false);
+        let mut transform = Transform::default();
+
+        state.focus(Vec3::new(2.0, 2.0, 3.0));
+        assert_eq!(state.center, Vec3::new(2.0, 2.0, 3.0));
+        assert_eq!(state.radius, 5.0);
+
+        state.set_preset(ViewPreset::Top);
+        state.update_transform(&mut transform);
+        assert!(
+            transform
+                .translation
+                .abs_diff_eq(Vec3::new(1.0, 12.0, 3.0), 1e-4)
+        );
+
+        state.radius = 0.01;
+        state.reset();
+        assert_eq!(
+            (state.center, state.radius),
+            (Vec3::new(1.0, 2.0, 3.0), 10.0)
+        );
+        assert_eq!((state.pitch, state.yaw, state.roll), (0.0, 0.0, 0.0));
+        assert_eq!(state.clamp_radius(1000.0), 100.0);
+        assert_eq!(state.clamp_radius(0.0), 0.2);
+    }
+
+    #[test]
+    fn test_roll() {
+        let mut state = PanOrbitState3d::new(Vec3::ZERO, 10.0, 0.0, 0.0, false);
+        let mut transform = Transform::default();
+
+        state.roll = FRAC_PI_2;
+        state.update_transform(&mut transform);
+        assert!(transform.up().abs_diff_eq(Vec3::NEG_X, 1e-4));
+        assert!(
+            transform
+                .translation
+                .abs_diff_eq(Vec3::new(0.0, 0.0, 10.0), 1e-4)
+        );
+
+        state.set_preset(ViewPreset::Front);
+        assert_eq!(state.roll, 0.0);
+    }
+}
@@ -32,6 +32,7 @@ impl ApplyCameraState for PanZoomState2d {
         viewport_centre: Vec2,
         delta_zoom: f32,
         delta_move: Vec3,
+        _: f32,
         app_settings: &AppSettings,
         app_state: &mut AppState,
         tiled_image: Query<&TiledImage>,
@@ -81,7 +82,11 @@ impl ApplyCameraState for PanZoomState2d {
             orthogonal.scale = scale;
 
             if let Ok(tiled_image) = tiled_image.single() {
-                app_state.level = tiled_image.get_level_at(orthogonal.scale);
+                app_state.level = tiled_image.get_level_with_hysteresis(
+                    app_state.level,
+                    orthogonal.scale,
+                    app_state.scale_factor,
+                );
             }
 
             transform.translation = initial_state.translation
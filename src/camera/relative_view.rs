@@ -0,0 +1,124 @@
+use bevy::prelude::{OrthographicProjection, Projection, Transform, Vec2};
+
+/// Camera view relative to the image size, so that it can be applied to the images of other sizes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct RelativeView {
+    /// Centre of the view as a fraction of the image size in world space.
+    pub(crate) centre: Vec2,
+    /// Camera zoom scale as a fraction of the image width in world space.
+    pub(crate) scale: f32,
+}
+
+impl RelativeView {
+    /// Get the view of the camera relative to the image size, with the world origin from the top
+    /// left of the image.
+    pub(crate) fn from_camera(
+        transform: &Transform,
+        projection: &Projection,
+        world_image_max_size: Vec2,
+        world_origin: Vec2,
+    ) -> Option<Self> {
+        let Projection::Orthographic(orthographic) = projection else {
+            return None;
+        };
+
+        if world_image_max_size.x <= 0.0 || world_image_max_size.y <= 0.0 {
+            return None;
+        }
+
+        Some(Self {
+            centre: (transform.translation.truncate() + world_origin) / world_image_max_size,
+            scale: orthographic.scale / world_image_max_size.x,
+        })
+    }
+
+    /// Apply the view to the camera for the image size and the world origin.
+    pub(crate) fn apply(
+        &self,
+        transform: &mut Transform,
+        projection: &mut Projection,
+        world_image_max_size: Vec2,
+        world_origin: Vec2,
+    ) {
+        let Projection::Orthographic(orthographic) = projection else {
+            return;
+        };
+
+        self.apply_orthographic(transform, orthographic, world_image_max_size, world_origin);
+    }
+
+    /// Apply the view to the camera with the orthographic projection for the image size and the
+    /// world origin.
+    pub(crate) fn apply_orthographic(
+        &self,
+        transform: &mut Transform,
+        orthographic: &mut OrthographicProjection,
+        world_image_max_size: Vec2,
+        world_origin: Vec2,
+    ) {
+        orthographic.scale = self.scale * world_image_max_size.x;
+        transform.translation =
+            (self.centre * world_image_max_size - world_origin).extend(transform.translation.z);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::prelude::Vec3;
+
+    #[test]
+    fn test_relative_view_camera() {
+        let transform = Transform::from_xyz(512.0, -256.0, 0.0);
+        let projection = Projection::Orthographic(OrthographicProjection {
+            scale: 2.0,
+            ..OrthographicProjection::default_2d()
+        });
+
+        let view = RelativeView::from_camera(
+            &transform,
+            &projection,
+            Vec2::new(1024.0, 512.0),
+            Vec2::ZERO,
+        )
+        .expect("should have a view");
+
+        assert_eq!(view.centre, Vec2::new(0.5, -0.5));
+        assert_eq!(view.scale, 1.0 / 512.0);
+
+        let mut transform = Transform::default();
+        let mut projection = projection.clone();
+
+        view.apply(
+            &mut transform,
+            &mut projection,
+            Vec2::new(2048.0, 1024.0),
+            Vec2::ZERO,
+        );
+
+        assert_eq!(transform.translation, Vec3::new(1024.0, -512.0, 0.0));
+
+        // The same view with the world moved near the camera.
+        view.apply(
+            &mut transform,
+            &mut projection,
+            Vec2::new(2048.0, 1024.0),
+            Vec2::new(1000.0, -500.0),
+        );
+
+        assert_eq!(transform.translation, Vec3::new(24.0, -12.0, 0.0));
+        assert_eq!(
+            RelativeView::from_camera(
+                &transform,
+                &projection,
+                Vec2::new(2048.0, 1024.0),
+                Vec2::new(1000.0, -500.0),
+            ),
+            Some(view)
+        );
+        let Projection::Orthographic(orthographic) = projection else {
+            panic!("should be orthographic");
+        };
+        assert_eq!(orthographic.scale, 4.0);
+    }
+}
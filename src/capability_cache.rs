@@ -0,0 +1,285 @@
+use crate::{
+    app::storage,
+    http_cache::{Validators, make_room},
+};
+use bevy::prelude::{Resource, warn};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+/// Key of the capability cache in the storage.
+const CAPABILITY_CACHE_KEY: &str = "capabilities";
+
+/// Maximum number of image services kept, the least recently updated dropped first.
+const MAX_CACHED_SERVICES: usize = 100;
+
+/// How long the image info is kept to be revalidated instead of downloaded again, as it rarely
+/// changes.
+const MAX_INFO_AGE: TimeDelta = TimeDelta::days(7);
+
+/// How long an image service is known to need the authentication before it is requested again.
+const MAX_AUTH_REQUIRED_AGE: TimeDelta = TimeDelta::hours(1);
+
+/// Image info JSON with the headers to revalidate it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct CachedInfo {
+    pub(crate) json: String,
+    pub(crate) validators: Validators,
+    /// When it was received, kept while it is not changed.
+    received: DateTime<Utc>,
+}
+
+/// What is learned about an image service, e.g. its image info and its quirks.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct CachedCapabilities {
+    info: Option<CachedInfo>,
+    /// Format of the tiles working after the preferred one failed.
+    working_format: Option<String>,
+    /// When the image info was refused for the lack of authentication.
+    auth_required: Option<DateTime<Utc>>,
+    updated: DateTime<Utc>,
+}
+
+/// Image info and quirks of the image services by the URL of their image info, kept in the
+/// storage across the sessions to skip the request of the image info of the frequently visited
+/// repositories.
+#[derive(Resource, Clone)]
+pub(crate) struct CapabilityCache {
+    services: Arc<Mutex<HashMap<String, CachedCapabilities>>>,
+    /// Whether the changes are kept in the storage, which is not the case in the tests.
+    is_stored: bool,
+}
+
+impl Default for CapabilityCache {
+    fn default() -> Self {
+        Self {
+            services: Arc::default(),
+            is_stored: true,
+        }
+    }
+}
+
+impl CapabilityCache {
+    /// Create an empty cache not kept in the storage, e.g. of the image info of a mock server.
+    #[cfg(test)]
+    pub(crate) fn new_in_memory() -> Self {
+        Self {
+            is_stored: false,
+            ..Default::default()
+        }
+    }
+
+    /// Read the capabilities stored in the previous sessions.
+    pub(crate) fn load() -> Self {
+        let services = storage::read(CAPABILITY_CACHE_KEY)
+            .and_then(|json| {
+                serde_json::from_str(&json)
+                    .inspect_err(|e| warn!("Ignore the saved capabilities. {}", e))
+                    .ok()
+            })
+            .unwrap_or_default();
+
+        Self {
+            services: Arc::new(Mutex::new(services)),
+            is_stored: true,
+        }
+    }
+
+    /// Get the image info of the URL to revalidate it, unless it is too old.
+    pub(crate) fn get_info(&self, info_url: &str) -> Option<CachedInfo> {
+        self.get_info_at(info_url, Utc::now())
+    }
+
+    fn get_info_at(&self, info_url: &str, now: DateTime<Utc>) -> Option<CachedInfo> {
+        let cache = self
+            .services
+            .lock()
+            .expect("should be able to lock the capability cache");
+        let info = cache.get(info_url)?.info.as_ref()?;
+
+        (now - info.received < MAX_INFO_AGE).then(|| info.clone())
+    }
+
+    /// Keep the image info of the URL with the headers to revalidate it. Its age is kept if it
+    /// is not changed, so that it is downloaded again in time.
+    pub(crate) fn store_info(&self, info_url: &str, json: &str, validators: Validators) {
+        self.update(info_url, |x| {
+            let received = x
+                .info
+                .as_ref()
+                .filter(|cached| cached.json == json)
+                .map_or_else(Utc::now, |cached| cached.received);
+
+            x.info = Some(CachedInfo {
+                json: json.to_string(),
+                validators,
+                received,
+            });
+            x.auth_required = None;
+        });
+    }
+
+    /// Forget the image info of the URL, e.g. when it cannot be parsed.
+    pub(crate) fn remove_info(&self, info_url: &str) {
+        self.update(info_url, |x| x.info = None);
+    }
+
+    /// Get the format of the tiles working for the image service if it is not the preferred one.
+    pub(crate) fn get_working_format(&self, info_url: &str) -> Option<String> {
+        self.services
+            .lock()
+            .expect("should be able to lock the capability cache")
+            .get(info_url)?
+            .working_format
+            .clone()
+    }
+
+    /// Remember the format of the tiles working for the image service.
+    pub(crate) fn set_working_format(&self, info_url: &str, format: &str) {
+        self.update(info_url, |x| x.working_format = Some(format.to_string()));
+    }
+
+    /// Whether the image info of the URL was refused for the lack of authentication
+    /// recently.
+    pub(crate) fn is_auth_required(&self, info_url: &str) -> bool {
+        self.is_auth_required_at(info_url, Utc::now())
+    }
+
+    fn is_auth_required_at(&self, info_url: &str, now: DateTime<Utc>) -> bool {
+        self.services
+            .lock()
+            .expect("should be able to lock the capability cache")
+            .get(info_url)
+            .and_then(|x| x.auth_required)
+            .is_some_and(|x| now - x < MAX_AUTH_REQUIRED_AGE)
+    }
+
+    /// Remember that the image info of the URL was refused for the lack of authentication.
+    pub(crate) fn set_auth_required(&self, info_url: &str) {
+        self.update(info_url, |x| {
+            x.info = None;
+            x.auth_required = Some(Utc::now());
+        });
+    }
+
+    /// Forget that the image services refused the image info for the lack of authentication,
+    /// e.g. when the user reloads after logging in.
+    pub(crate) fn clear_auth_required(&self) {
+        let mut cache = self
+            .services
+            .lock()
+            .expect("should be able to lock the capability cache");
+        let mut is_changed = false;
+
+        for capabilities in cache.values_mut() {
+            if capabilities.auth_required.take().is_some() {
+                capabilities.updated = Utc::now();
+                is_changed = true;
+            }
+        }
+
+        if is_changed {
+            self.save(&cache);
+        }
+    }
+
+    /// Update the capabilities of the image service and keep them in the storage if changed.
+    fn update(&self, info_url: &str, f: impl FnOnce(&mut CachedCapabilities)) {
+        let mut cache = self
+            .services
+            .lock()
+            .expect("should be able to lock the capability cache");
+        let mut capabilities = cache.get(info_url).cloned().unwrap_or_default();
+        let previous = capabilities.clone();
+
+        f(&mut capabilities);
+
+        if cache.contains_key(info_url) && capabilities == previous {
+            return;
+        }
+
+        capabilities.updated = Utc::now();
+        make_room(&mut cache, info_url, MAX_CACHED_SERVICES, |x| x.updated);
+        cache.insert(info_url.to_string(), capabilities);
+
+        self.save(&cache);
+    }
+
+    /// Keep the capabilities in the storage, unless the cache is not stored.
+    fn save(&self, cache: &HashMap<String, CachedCapabilities>) {
+        if !self.is_stored {
+            return;
+        }
+
+        let json = serde_json::to_string(cache).unwrap_or_default();
+
+        if let Err(e) = storage::write(CAPABILITY_CACHE_KEY, &json) {
+            warn!("Failed to save the capabilities. {}", e);
+        }
+    }
+}
+
+/// Whether the request failed for the lack of authentication, by the status of the error, e.g.
+/// "401 Unauthorized".
+pub(crate) fn is_auth_error(msg: &str) -> bool {
+    matches!(msg.split_whitespace().next(), Some("401" | "403"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capability_cache() {
+        let cache = CapabilityCache::new_in_memory();
+        let info_url = "https://example.org/iiif/1/info.json";
+        let now = Utc::now();
+
+        assert_eq!(cache.get_info_at(info_url, now), None);
+
+        let validators = Validators {
+            etag: Some("\"abc\"".to_string()),
+            last_modified: None,
+        };
+
+        cache.store_info(info_url, "{}", validators.clone());
+
+        let info = cache.get_info_at(info_url, Utc::now()).unwrap();
+
+        assert_eq!(info.json, "{}");
+        assert_eq!(info.validators, validators);
+        assert_eq!(
+            cache.get_info_at(
+                info_url,
+                info.received + MAX_INFO_AGE + TimeDelta::seconds(1)
+            ),
+            None
+        );
+
+        // The age is kept while unchanged.
+        cache.store_info(info_url, "{}", Validators::default());
+        assert_eq!(
+            cache.get_info_at(info_url, Utc::now()).unwrap().received,
+            info.received
+        );
+
+        cache.set_auth_required(info_url);
+        assert!(cache.is_auth_required_at(info_url, Utc::now()));
+        assert!(!cache.is_auth_required_at(info_url, Utc::now() + MAX_AUTH_REQUIRED_AGE));
+        assert_eq!(cache.get_info_at(info_url, Utc::now()), None);
+
+        cache.clear_auth_required();
+        assert!(!cache.is_auth_required_at(info_url, Utc::now()));
+    }
+
+    #[test]
+    fn test_is_auth_error() {
+        assert!(is_auth_error("401 Unauthorized"));
+        assert!(is_auth_error("403 Forbidden"));
+        assert!(!is_auth_error("404 Not Found"));
+        assert!(!is_auth_error(""));
+    }
+}
@@ -0,0 +1,255 @@
+use crate::{
+    notification::UserNotification, rendering::tiled_image::TiledImage,
+    tilesource::ServiceCapabilities,
+};
+use bevy::{
+    prelude::{Added, MessageWriter, Query, ResMut, Resource, Single, With, debug},
+    window::RequestRedraw,
+};
+use std::sync::{Arc, Mutex};
+
+/// Response to the request of the sample tile.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) enum ProbeResult {
+    #[default]
+    Pending,
+    /// Served with the content type, empty if not known.
+    Served(String),
+    /// Failed with the status or the error.
+    Failed(String),
+}
+
+/// Capabilities of the image service of the current image, and whether a sample tile is actually
+/// served as advertised.
+#[derive(Resource, Default)]
+pub(crate) struct CapabilityProbe {
+    /// Endpoint of the image probed.
+    endpoint: String,
+    pub(crate) capabilities: Option<ServiceCapabilities>,
+    /// URL of the sample tile requested.
+    pub(crate) tile_url: String,
+    result: Arc<Mutex<ProbeResult>>,
+    /// Whether the user has been warned of the probe.
+    notified: bool,
+}
+
+impl CapabilityProbe {
+    /// Get the response to the request of the sample tile.
+    pub(crate) fn get_result(&self) -> ProbeResult {
+        self.result.lock().map(|x| x.clone()).unwrap_or_default()
+    }
+
+    /// Get the warning if the server does not serve the sample tile as advertised.
+    pub(crate) fn get_warning(&self) -> Option<String> {
+        get_probe_warning(
+            self.capabilities.as_ref()?,
+            &self.tile_url,
+            &self.get_result(),
+        )
+    }
+}
+
+/// Get the media type of the image format, e.g. "image/jpeg" for "jpg".
+fn get_media_type(format: &str) -> Option<&'static str> {
+    match format {
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "png" => Some("image/png"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        "tif" | "tiff" => Some("image/tiff"),
+        "jp2" | "jpg2" => Some("image/jp2"),
+        _ => None,
+    }
+}
+
+/// Get the warning if the sample tile failed, or was not served in the format requested.
+fn get_probe_warning(
+    capabilities: &ServiceCapabilities,
+    tile_url: &str,
+    result: &ProbeResult,
+) -> Option<String> {
+    match result {
+        ProbeResult::Pending => None,
+        ProbeResult::Failed(msg) if capabilities.tiles => Some(format!(
+            "The tiles are advertised, but the sample tile failed. {}",
+            msg
+        )),
+        ProbeResult::Failed(msg) => Some(format!("The sample image failed. {}", msg)),
+        ProbeResult::Served(content_type) => {
+            // Without the parameters, e.g. "image/jpeg;charset=UTF-8".
+            let content_type = content_type
+                .split(';')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_lowercase();
+
+            if content_type.is_empty() || content_type == "application/octet-stream" {
+                return None;
+            }
+
+            if !content_type.starts_with("image/") {
+                return Some(format!(
+                    "The sample tile is served as '{}' instead of an image.",
+                    content_type
+                ));
+            }
+
+            let path = tile_url.split(['?', '#']).next().unwrap_or_default();
+            let format = path.rsplit_once('.').map(|(_, x)| x.to_lowercase())?;
+            let media_type = get_media_type(&format)?;
+
+            (content_type != media_type).then(|| {
+                format!(
+                    "The sample tile is requested as {} but served as '{}'.",
+                    format, content_type
+                )
+            })
+        }
+    }
+}
+
+/// Request the sample tile, with GET if the server does not allow HEAD.
+fn probe(url: String, method: &'static str, result: Arc<Mutex<ProbeResult>>) {
+    let request = ehttp::Request {
+        method: method.to_string(),
+        ..ehttp::Request::get(&url)
+    };
+
+    ehttp::fetch(request, move |response| {
+        let probe_result = match response {
+            Ok(response) if method == "HEAD" && matches!(response.status, 405 | 501) => {
+                debug!("Request the sample tile {} without HEAD", url);
+                probe(url, "GET", result);
+                return;
+            }
+            Ok(response) if response.ok => {
+                ProbeResult::Served(response.content_type().unwrap_or_default().to_string())
+            }
+            Ok(response) => {
+                ProbeResult::Failed(format!("{} {}", response.status, response.status_text))
+            }
+            Err(e) => ProbeResult::Failed(e),
+        };
+
+        if let Ok(mut x) = result.lock() {
+            *x = probe_result;
+        }
+    });
+}
+
+/// Get the capabilities of the image service when the image is added and request a sample tile,
+/// to warn the user early if the tiles are not served as advertised.
+pub(crate) fn capability_probe_system(
+    tiled_image: Option<Single<&TiledImage, Added<TiledImage>>>,
+    image_query: Query<(), With<TiledImage>>,
+    mut probe_state: ResMut<CapabilityProbe>,
+    mut messages: MessageWriter<UserNotification>,
+    mut redraw_request_writer: MessageWriter<RequestRedraw>,
+) {
+    if let Some(tiled_image) = tiled_image
+        && tiled_image.get_endpoint() != probe_state.endpoint
+    {
+        let tile_url = tiled_image.get_sample_tile_url();
+        let result = Arc::new(Mutex::new(ProbeResult::Pending));
+
+        // The local files, e.g. the exported tiles, are not requested.
+        if tile_url.starts_with("file://") {
+            *result
+                .lock()
+                .expect("should be able to lock the probe result") =
+                ProbeResult::Served("".to_string());
+        } else {
+            probe(tile_url.to_string(), "HEAD", Arc::clone(&result));
+        }
+
+        *probe_state = CapabilityProbe {
+            endpoint: tiled_image.get_endpoint().to_string(),
+            capabilities: Some(tiled_image.get_capabilities()),
+            tile_url,
+            result,
+            notified: false,
+        };
+        return;
+    }
+
+    // Hide the badge once the image is gone, e.g. for a 3D model.
+    if image_query.is_empty() {
+        if probe_state.capabilities.is_some() {
+            *probe_state = CapabilityProbe::default();
+        }
+        return;
+    }
+
+    if probe_state.notified || probe_state.capabilities.is_none() {
+        return;
+    }
+
+    match probe_state.get_result() {
+        ProbeResult::Pending => return,
+        ProbeResult::Failed(_) => {
+            messages.write(UserNotification::Network {
+                url: probe_state.tile_url.to_string(),
+                msg: probe_state.get_warning().unwrap_or_default(),
+            });
+        }
+        ProbeResult::Served(_) => {}
+    }
+
+    // Show the badge of the probe.
+    probe_state.notified = true;
+    redraw_request_writer.write(RequestRedraw);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_probe_warning() {
+        let capabilities = ServiceCapabilities {
+            protocol: "IIIF 3".to_string(),
+            level: Some(2),
+            tiles: true,
+            formats: vec!["png".to_string()],
+        };
+        let tile_url = "https://example.org/iiif/1/0,0,512,512/512,512/0/default.png";
+
+        assert_eq!(
+            get_probe_warning(&capabilities, tile_url, &ProbeResult::Pending),
+            None
+        );
+        assert_eq!(
+            get_probe_warning(
+                &capabilities,
+                tile_url,
+                &ProbeResult::Served("image/png; charset=UTF-8".to_string())
+            ),
+            None
+        );
+        assert_eq!(
+            get_probe_warning(
+                &capabilities,
+                tile_url,
+                &ProbeResult::Served("image/jpeg".to_string())
+            ),
+            Some("The sample tile is requested as png but served as 'image/jpeg'.".to_string())
+        );
+        assert_eq!(
+            get_probe_warning(
+                &capabilities,
+                tile_url,
+                &ProbeResult::Served("text/html".to_string())
+            ),
+            Some("The sample tile is served as 'text/html' instead of an image.".to_string())
+        );
+        assert_eq!(
+            get_probe_warning(
+                &capabilities,
+                tile_url,
+                &ProbeResult::Failed("404 Not Found".to_string())
+            ),
+            Some("The tiles are advertised, but the sample tile failed. 404 Not Found".to_string())
+        );
+    }
+}
@@ -0,0 +1,107 @@
+use crate::{app::app_settings::AppSettings, redraw_governor::RedrawGovernor};
+use bevy::prelude::{Res, ResMut, Resource, Time};
+use bevy_egui::EguiClipboard;
+use std::time::Duration;
+
+/// Interval between the reads of the clipboard while watching it.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Watch of the clipboard for the URLs of the IIIF manifests or images copied, e.g. from a
+/// browser, offered to be opened.
+#[derive(Resource, Default)]
+pub(crate) struct ClipboardWatch {
+    /// Text last read from the clipboard, `None` until it is first read.
+    last_text: Option<String>,
+    /// Time of the next read of the clipboard.
+    next_poll: Duration,
+    /// URL copied offered to be opened, until it is opened or dismissed.
+    pub(crate) offered_url: Option<String>,
+}
+
+/// Get the URL of a IIIF manifest or image information in the text, e.g.
+/// "https://example.org/iiif/book1/manifest". `None` if the text is not a single URL.
+pub(crate) fn get_iiif_url(text: &str) -> Option<&str> {
+    let text = text.trim();
+
+    if text.is_empty() || text.contains(char::is_whitespace) {
+        return None;
+    }
+
+    let path = text
+        .strip_prefix("https://")
+        .or_else(|| text.strip_prefix("http://"))?;
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+    let path = path.to_lowercase();
+
+    (path.contains("manifest") || path.contains("/iiif/") || path.ends_with("info.json"))
+        .then_some(text)
+}
+
+/// Read the clipboard at the interval when watching it is enabled, and offer the URL when a new
+/// IIIF URL is copied. The text on the clipboard at the start is not offered.
+pub(crate) fn clipboard_watch_system(
+    mut clipboard_watch: ResMut<ClipboardWatch>,
+    clipboard: Option<ResMut<EguiClipboard>>,
+    app_settings: Res<AppSettings>,
+    time: Res<Time>,
+    mut redraw_governor: ResMut<RedrawGovernor>,
+) {
+    let Some(mut clipboard) = clipboard.filter(|_| app_settings.watch_clipboard) else {
+        if clipboard_watch.last_text.is_some() {
+            clipboard_watch.last_text = None;
+            clipboard_watch.offered_url = None;
+        }
+        return;
+    };
+
+    let is_due = time.elapsed() >= clipboard_watch.next_poll;
+
+    if is_due {
+        clipboard_watch.next_poll = time.elapsed() + POLL_INTERVAL;
+    }
+
+    // Wake up for the next read in time, e.g. while the browser is focused to copy a URL.
+    redraw_governor.wake_up_in(clipboard_watch.next_poll.saturating_sub(time.elapsed()));
+
+    if !is_due {
+        return;
+    }
+
+    let text = clipboard.get_text().unwrap_or_default();
+
+    if clipboard_watch.last_text.as_deref() == Some(text.as_str()) {
+        return;
+    }
+
+    if clipboard_watch.last_text.is_some() {
+        clipboard_watch.offered_url = get_iiif_url(&text).map(|x| x.to_string());
+    }
+
+    clipboard_watch.last_text = Some(text);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_iiif_url() {
+        assert_eq!(
+            get_iiif_url(" https://example.org/iiif/book1/manifest\n"),
+            Some("https://example.org/iiif/book1/manifest")
+        );
+        assert_eq!(
+            get_iiif_url("http://example.org/images/p1/info.json"),
+            Some("http://example.org/images/p1/info.json")
+        );
+        assert_eq!(
+            get_iiif_url("https://example.org/Manifest.json?lang=en"),
+            Some("https://example.org/Manifest.json?lang=en")
+        );
+        assert_eq!(get_iiif_url("https://example.org/about"), None);
+        assert_eq!(get_iiif_url("https://example.org/?q=manifest"), None);
+        assert_eq!(get_iiif_url("see https://example.org/iiif/manifest"), None);
+        assert_eq!(get_iiif_url("ftp://example.org/iiif/manifest"), None);
+        assert_eq!(get_iiif_url(""), None);
+    }
+}
@@ -0,0 +1,678 @@
+use crate::{
+    app::{app_settings::AppSettings, app_state::AppState},
+    export, fonts,
+    iiif::IiifError,
+    notification::UserNotification,
+    presentation::{manifest::Manifest, model::IsCanvas},
+    stitch::StitchProgress,
+    tilesource, web,
+};
+use ab_glyph::{Font, FontRef, GlyphId, PxScale, ScaleFont, point};
+use bevy::{
+    prelude::{MessageWriter, Res, ResMut, Resource, Single, info, warn},
+    window::RequestRedraw,
+};
+use image::{DynamicImage, ImageFormat, Rgb, RgbImage, imageops::FilterType};
+use std::{
+    io::Cursor,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, atomic::Ordering},
+};
+
+/// Maximum number of the pixels of the contact sheet, which is kept in memory until saved.
+const MAX_SHEET_PIXELS: f64 = 2.0e8;
+
+/// Range of the number of the columns of the contact sheet.
+pub(crate) const COLUMNS_RANGE: std::ops::RangeInclusive<u32> = 1..=20;
+
+/// Range of the size in pixels of the thumbnails of the contact sheet.
+pub(crate) const THUMBNAIL_SIZE_RANGE: std::ops::RangeInclusive<u32> = 64..=1024;
+
+/// Color of the background of the contact sheet.
+const SHEET_BACKGROUND: Rgb<u8> = Rgb([255, 255, 255]);
+
+/// Color of the cell of a canvas whose thumbnail is not available.
+const MISSING_THUMBNAIL: Rgb<u8> = Rgb([224, 224, 224]);
+
+/// Color of the labels of the canvases.
+const LABEL_COLOR: Rgb<u8> = Rgb([32, 32, 32]);
+
+/// Pixels of the contact sheet per point of the PDF page, i.e. at 96 DPI.
+const PIXELS_PER_POINT: f32 = 96.0 / 72.0;
+
+/// Result of the contact sheet finished in the background.
+#[derive(Debug)]
+enum ContactSheetResult {
+    Saved(PathBuf),
+    Cancelled,
+    Failed(IiifError),
+}
+
+/// Action requested from the contact sheet window.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ContactSheetRequest {
+    Start,
+    Cancel,
+}
+
+/// Contact sheet of the thumbnails of the canvases of the sequence shown with their labels, e.g.
+/// for the documentation of a collection, and the export in progress.
+#[derive(Resource)]
+pub(crate) struct ContactSheetExport {
+    pub(crate) columns: u32,
+    /// Size in pixels of the square each thumbnail is fitted in.
+    pub(crate) thumbnail_size: u32,
+    /// Number of the first canvases on the sheet. All of them if 0.
+    pub(crate) max_canvases: u32,
+    /// Path of the saved sheet, a PDF, or an image such as PNG by its extension.
+    pub(crate) file_path: String,
+    /// Number of the canvases of the sequence shown, as shown in the window.
+    pub(crate) canvas_count: usize,
+    /// Progress of the export in progress.
+    pub(crate) progress: Option<Arc<StitchProgress>>,
+    /// Path of the sheet saved last.
+    pub(crate) saved_path: Option<PathBuf>,
+    result: Arc<Mutex<Option<ContactSheetResult>>>,
+    pub(crate) request: Option<ContactSheetRequest>,
+}
+
+impl Default for ContactSheetExport {
+    fn default() -> Self {
+        Self {
+            columns: 5,
+            thumbnail_size: 256,
+            max_canvases: 0,
+            file_path: "contact_sheet.png".to_string(),
+            canvas_count: 0,
+            progress: None,
+            saved_path: None,
+            result: Arc::new(Mutex::new(None)),
+            request: None,
+        }
+    }
+}
+
+/// Canvas on the contact sheet.
+struct SheetItem {
+    /// URL of the thumbnail. `None` if the canvas has none.
+    url: Option<String>,
+    label: String,
+}
+
+/// Cells of the canvases on the contact sheet, row by row.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SheetLayout {
+    columns: u32,
+    rows: u32,
+    thumbnail_size: u32,
+    /// Height of the label under each thumbnail.
+    label_height: u32,
+    /// Space around and between the cells.
+    margin: u32,
+}
+
+impl SheetLayout {
+    fn new(count: usize, columns: u32, thumbnail_size: u32) -> Self {
+        let columns = columns.clamp(*COLUMNS_RANGE.start(), *COLUMNS_RANGE.end());
+        let thumbnail_size =
+            thumbnail_size.clamp(*THUMBNAIL_SIZE_RANGE.start(), *THUMBNAIL_SIZE_RANGE.end());
+
+        Self {
+            columns,
+            rows: (count as u32).div_ceil(columns),
+            thumbnail_size,
+            label_height: (get_label_scale(thumbnail_size) * 1.5).ceil() as u32,
+            margin: (thumbnail_size / 16).max(8),
+        }
+    }
+
+    /// Get the size in pixels of the contact sheet.
+    fn get_size(&self) -> (u32, u32) {
+        (
+            self.columns * (self.thumbnail_size + self.margin) + self.margin,
+            self.rows * (self.thumbnail_size + self.label_height + self.margin) + self.margin,
+        )
+    }
+
+    /// Get the top left corner of the cell of the canvas at the index on the sheet.
+    fn get_cell_origin(&self, index: usize) -> (u32, u32) {
+        let (column, row) = (index as u32 % self.columns, index as u32 / self.columns);
+
+        (
+            self.margin + column * (self.thumbnail_size + self.margin),
+            self.margin + row * (self.thumbnail_size + self.label_height + self.margin),
+        )
+    }
+}
+
+/// Get the height in pixels of the font of the labels for the size of the thumbnails.
+fn get_label_scale(thumbnail_size: u32) -> f32 {
+    (thumbnail_size as f32 / 12.0).clamp(12.0, 32.0)
+}
+
+/// Get the URL of the thumbnail of the canvas fitting in the square of the size, preferring its
+/// image service at the size to the thumbnail given. `None` if the canvas has none.
+fn get_thumbnail_url(canvas: &dyn IsCanvas, size: u32) -> Option<String> {
+    let canvas = web::get_displayed_canvas(canvas);
+    let service = canvas
+        .get_image(0)
+        .ok()
+        .filter(|x| x.get_type() != "Model")
+        .map(|x| x.get_service().to_string())
+        .unwrap_or_default();
+
+    if !service.is_empty() && !tilesource::is_image_url(&service) {
+        return Some(format!(
+            "{}/full/!{},{}/0/default.jpg",
+            service.trim_end_matches('/'),
+            size,
+            size
+        ));
+    }
+
+    let thumbnail = canvas.get_thumbnail();
+
+    if !thumbnail.is_empty() {
+        return Some(thumbnail.to_string());
+    }
+
+    // A plain image, fetched whole.
+    (!service.is_empty()).then_some(service)
+}
+
+/// Download the thumbnail, or read it if exported locally.
+fn fetch_thumbnail(url: &str, proxy_prefix: &str) -> Result<DynamicImage, IiifError> {
+    let bytes = match web::get_file_path(url) {
+        Some(path) => std::fs::read(path)?,
+        None => export::fetch_bytes(url, proxy_prefix)?,
+    };
+
+    Ok(image::load_from_memory(&bytes)?)
+}
+
+/// Get the glyphs of the text in a line with their positions from its start, and its width.
+fn get_glyphs(font: &FontRef, scale: f32, text: &str) -> (Vec<(GlyphId, f32)>, f32) {
+    let scaled_font = font.as_scaled(PxScale::from(scale));
+    let mut caret = 0.0;
+    let mut previous = None;
+    let glyphs = text
+        .chars()
+        .map(|c| {
+            let id = font.glyph_id(c);
+
+            if let Some(previous) = previous {
+                caret += scaled_font.kern(previous, id);
+            }
+
+            let position = caret;
+
+            caret += scaled_font.h_advance(id);
+            previous = Some(id);
+
+            (id, position)
+        })
+        .collect();
+
+    (glyphs, caret)
+}
+
+/// Draw the text in a line from the top left corner, cut with an ellipsis to the max width.
+fn draw_label(
+    sheet: &mut RgbImage,
+    font: &FontRef,
+    text: &str,
+    (x, y): (u32, u32),
+    max_width: f32,
+    scale: f32,
+) {
+    let scaled_font = font.as_scaled(PxScale::from(scale));
+    let (mut glyphs, width) = get_glyphs(font, scale, text);
+
+    if width > max_width {
+        let ellipsis = font.glyph_id('…');
+        let ellipsis_width = scaled_font.h_advance(ellipsis);
+
+        glyphs.retain(|(id, position)| {
+            position + scaled_font.h_advance(*id) + ellipsis_width <= max_width
+        });
+
+        let caret = glyphs
+            .last()
+            .map(|(id, position)| position + scaled_font.h_advance(*id))
+            .unwrap_or_default();
+
+        glyphs.push((ellipsis, caret));
+    }
+
+    let glyphs = glyphs.into_iter().map(|(id, position)| {
+        id.with_scale_and_position(
+            scale,
+            point(x as f32 + position, y as f32 + scaled_font.ascent()),
+        )
+    });
+
+    for glyph in glyphs {
+        let Some(outlined) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outlined.px_bounds();
+
+        outlined.draw(|gx, gy, coverage| {
+            let (px, py) = (
+                bounds.min.x as i64 + gx as i64,
+                bounds.min.y as i64 + gy as i64,
+            );
+
+            if px < 0 || py < 0 || px >= sheet.width() as i64 || py >= sheet.height() as i64 {
+                return;
+            }
+
+            let pixel = sheet.get_pixel_mut(px as u32, py as u32);
+            let coverage = coverage.min(1.0);
+
+            for (channel, text_channel) in pixel.0.iter_mut().zip(LABEL_COLOR.0) {
+                *channel = (*channel as f32 * (1.0 - coverage) + text_channel as f32 * coverage)
+                    .round() as u8;
+            }
+        });
+    }
+}
+
+/// Draw the thumbnails of the items fitted and centred in their cells with the labels under
+/// them. The cell of a thumbnail not available is left blank rather than failing the sheet.
+/// `None` if cancelled.
+fn render_sheet(
+    items: &[SheetItem],
+    layout: SheetLayout,
+    progress: &StitchProgress,
+    fetch: impl Fn(&str) -> Result<DynamicImage, IiifError>,
+) -> Result<Option<RgbImage>, IiifError> {
+    let font = FontRef::try_from_slice(fonts::NOTOSANSTC_REGULAR)
+        .map_err(|e| IiifError::IiifFormatError(e.to_string()))?;
+    let (width, height) = layout.get_size();
+    let mut sheet = RgbImage::from_pixel(width, height, SHEET_BACKGROUND);
+    let size = layout.thumbnail_size;
+
+    progress.total.store(items.len(), Ordering::Relaxed);
+
+    for (index, item) in items.iter().enumerate() {
+        if progress.is_cancelled() {
+            return Ok(None);
+        }
+
+        let (x, y) = layout.get_cell_origin(index);
+        let thumbnail = item.url.as_deref().map(&fetch).and_then(|x| {
+            x.inspect_err(|e| warn!("Failed to fetch the thumbnail of {:?}. {}", item.label, e))
+                .ok()
+        });
+
+        match thumbnail {
+            Some(thumbnail) => {
+                let thumbnail = thumbnail.resize(size, size, FilterType::Triangle).to_rgb8();
+                let offset_x = x + (size - thumbnail.width().min(size)) / 2;
+                let offset_y = y + (size - thumbnail.height().min(size)) / 2;
+
+                image::imageops::replace(&mut sheet, &thumbnail, offset_x as i64, offset_y as i64);
+            }
+            None => {
+                let blank = RgbImage::from_pixel(size, size, MISSING_THUMBNAIL);
+
+                image::imageops::replace(&mut sheet, &blank, x as i64, y as i64);
+            }
+        }
+
+        draw_label(
+            &mut sheet,
+            &font,
+            &item.label,
+            (x, y + size),
+            size as f32,
+            get_label_scale(size),
+        );
+        progress.done.fetch_add(1, Ordering::Relaxed);
+    }
+
+    Ok(Some(sheet))
+}
+
+/// Write a PDF of a single page of the JPEG image, in points of the size of the image at 96 DPI.
+fn write_pdf(jpeg: &[u8], width: u32, height: u32) -> Vec<u8> {
+    let (page_width, page_height) = (
+        width as f32 / PIXELS_PER_POINT,
+        height as f32 / PIXELS_PER_POINT,
+    );
+    let content = format!(
+        "q {:.2} 0 0 {:.2} 0 0 cm /Im0 Do Q",
+        page_width, page_height
+    );
+    let objects: [Vec<u8>; 5] = [
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_vec(),
+        format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] \
+            /Resources << /XObject << /Im0 5 0 R >> >> /Contents 4 0 R >>",
+            page_width, page_height
+        )
+        .into_bytes(),
+        [
+            format!("<< /Length {} >>\nstream\n", content.len()).as_bytes(),
+            content.as_bytes(),
+            b"\nendstream",
+        ]
+        .concat(),
+        [
+            format!(
+                "<< /Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceRGB \
+                /BitsPerComponent 8 /Filter /DCTDecode /Length {} >>\nstream\n",
+                width,
+                height,
+                jpeg.len()
+            )
+            .as_bytes(),
+            jpeg,
+            b"\nendstream",
+        ]
+        .concat(),
+    ];
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::new();
+
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n", index + 1).as_bytes());
+        pdf.extend_from_slice(object);
+        pdf.extend_from_slice(b"\nendobj\n");
+    }
+
+    let xref_offset = pdf.len();
+
+    pdf.extend_from_slice(
+        format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes(),
+    );
+    for offset in offsets {
+        pdf.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    pdf.extend_from_slice(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref_offset
+        )
+        .as_bytes(),
+    );
+
+    pdf
+}
+
+/// Save the sheet as a PDF of its JPEG, or as an image in the format of the extension.
+fn save_sheet(sheet: RgbImage, path: &Path) -> Result<(), IiifError> {
+    let is_pdf = path
+        .extension()
+        .is_some_and(|x| x.eq_ignore_ascii_case("pdf"));
+
+    if !is_pdf {
+        sheet.save(path)?;
+        return Ok(());
+    }
+
+    let (width, height) = sheet.dimensions();
+    let mut jpeg = Vec::new();
+
+    DynamicImage::ImageRgb8(sheet).write_to(&mut Cursor::new(&mut jpeg), ImageFormat::Jpeg)?;
+    std::fs::write(path, write_pdf(&jpeg, width, height))?;
+
+    Ok(())
+}
+
+/// Start rendering the contact sheet of the canvases of the sequence shown in the background.
+fn start(
+    contact_sheet: &mut ContactSheetExport,
+    presentation: &Manifest,
+    app_state: &AppState,
+    app_settings: &AppSettings,
+) -> Result<(), IiifError> {
+    let sequence = presentation
+        .model()
+        .get_sequence(app_state.sequence_index)?;
+    let max_canvases = match contact_sheet.max_canvases {
+        0 => usize::MAX,
+        x => x as usize,
+    };
+    let items = sequence
+        .get_canvases()
+        .take(max_canvases)
+        .enumerate()
+        .map(|(index, canvas)| SheetItem {
+            url: get_thumbnail_url(canvas, contact_sheet.thumbnail_size),
+            label: format!(
+                "({}) {}",
+                index + 1,
+                canvas
+                    .get_label(&app_settings.language)
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ),
+        })
+        .collect::<Vec<_>>();
+
+    if items.is_empty() {
+        return Err(IiifError::IiifMissingInfo(
+            "canvases of the contact sheet".to_string(),
+        ));
+    }
+
+    let layout = SheetLayout::new(
+        items.len(),
+        contact_sheet.columns,
+        contact_sheet.thumbnail_size,
+    );
+    let (width, height) = layout.get_size();
+
+    if width as f64 * height as f64 > MAX_SHEET_PIXELS {
+        return Err(IiifError::IiifUnsupportedError(format!(
+            "contact sheet of {}x{} pixels over the limit of {} megapixels",
+            width,
+            height,
+            MAX_SHEET_PIXELS / 1.0e6
+        )));
+    }
+
+    let path = PathBuf::from(contact_sheet.file_path.trim());
+    let proxy_prefix = app_settings.proxy_prefix.clone();
+    let progress = Arc::new(StitchProgress::default());
+    let result = Arc::clone(&contact_sheet.result);
+
+    info!(
+        "Render the contact sheet of {} canvases into {}x{} pixels to {:?}",
+        items.len(),
+        width,
+        height,
+        path
+    );
+
+    contact_sheet.progress = Some(Arc::clone(&progress));
+
+    std::thread::spawn(move || {
+        let fetch = |url: &str| fetch_thumbnail(url, &proxy_prefix);
+        let sheet_result = match render_sheet(&items, layout, &progress, fetch) {
+            Ok(Some(sheet)) => match save_sheet(sheet, &path) {
+                Ok(()) => ContactSheetResult::Saved(path),
+                Err(e) => ContactSheetResult::Failed(e),
+            },
+            Ok(None) => ContactSheetResult::Cancelled,
+            Err(e) => ContactSheetResult::Failed(e),
+        };
+
+        if let Ok(mut x) = result.lock() {
+            *x = Some(sheet_result);
+        }
+    });
+
+    Ok(())
+}
+
+/// Handle the action requested from the contact sheet window, and report the export when it
+/// finishes.
+pub(crate) fn contact_sheet_system(
+    mut contact_sheet: ResMut<ContactSheetExport>,
+    presentation: Option<Single<&Manifest>>,
+    app_state: Res<AppState>,
+    app_settings: Res<AppSettings>,
+    mut messages: MessageWriter<UserNotification>,
+    mut redraw_request_writer: MessageWriter<RequestRedraw>,
+) {
+    match contact_sheet.request.take() {
+        Some(ContactSheetRequest::Start) if contact_sheet.progress.is_none() => {
+            let Some(presentation) = presentation.as_ref() else {
+                return;
+            };
+
+            if let Err(e) = start(&mut contact_sheet, presentation, &app_state, &app_settings) {
+                messages.write(UserNotification::Error(format!(
+                    "Unable to export the contact sheet.\n{}",
+                    e
+                )));
+            }
+        }
+        Some(ContactSheetRequest::Cancel) => {
+            if let Some(progress) = &contact_sheet.progress {
+                progress.cancelled.store(true, Ordering::Relaxed);
+            }
+        }
+        _ => {}
+    }
+
+    let canvas_count = presentation
+        .as_ref()
+        .and_then(|x| x.model().get_sequence(app_state.sequence_index).ok())
+        .map(|x| x.get_canvases().len())
+        .unwrap_or_default();
+
+    if contact_sheet.canvas_count != canvas_count {
+        contact_sheet.canvas_count = canvas_count;
+    }
+
+    if contact_sheet.progress.is_none() {
+        return;
+    }
+
+    let result = contact_sheet.result.lock().ok().and_then(|mut x| x.take());
+
+    match result {
+        Some(ContactSheetResult::Saved(path)) => {
+            info!("Exported the contact sheet to {:?}", path);
+            contact_sheet.saved_path = Some(path);
+        }
+        Some(ContactSheetResult::Cancelled) => warn!("Cancelled the export of the contact sheet"),
+        Some(ContactSheetResult::Failed(e)) => {
+            messages.write(UserNotification::Error(format!(
+                "Failed to export the contact sheet.\n{}",
+                e
+            )));
+        }
+        // Keep the progress updated while rendering.
+        None => {
+            redraw_request_writer.write(RequestRedraw);
+            return;
+        }
+    }
+
+    contact_sheet.progress = None;
+    redraw_request_writer.write(RequestRedraw);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sheet_layout() {
+        let layout = SheetLayout::new(7, 3, 120);
+
+        assert_eq!(layout.rows, 3);
+        assert_eq!(layout.margin, 8);
+        assert_eq!(layout.label_height, 18);
+        assert_eq!(layout.get_size(), (3 * 128 + 8, 3 * (120 + 18 + 8) + 8));
+        assert_eq!(layout.get_cell_origin(0), (8, 8));
+        assert_eq!(layout.get_cell_origin(4), (8 + 128, 8 + 146));
+
+        // The columns and the size within the ranges.
+        let layout = SheetLayout::new(1, 0, 10);
+
+        assert_eq!(layout.columns, 1);
+        assert_eq!(layout.thumbnail_size, 64);
+    }
+
+    #[test]
+    fn test_render_sheet() {
+        let items = [
+            SheetItem {
+                url: Some("0".to_string()),
+                label: "(1) A very long label of the canvas cut to the width".to_string(),
+            },
+            SheetItem {
+                url: None,
+                label: "(2)".to_string(),
+            },
+        ];
+        let layout = SheetLayout::new(items.len(), 2, 64);
+        let progress = StitchProgress::default();
+        // Wider than the square, fitted to its width and centred.
+        let fetch = |_: &str| {
+            Ok::<_, IiifError>(DynamicImage::ImageRgb8(RgbImage::from_pixel(
+                128,
+                64,
+                Rgb([255, 0, 0]),
+            )))
+        };
+        let sheet = render_sheet(&items, layout, &progress, fetch)
+            .expect("should render the sheet")
+            .expect("should not be cancelled");
+        let (x, y) = layout.get_cell_origin(0);
+
+        assert_eq!(sheet.dimensions(), layout.get_size());
+        assert_eq!(sheet.get_pixel(x + 32, y + 32), &Rgb([255, 0, 0]));
+        assert_eq!(sheet.get_pixel(x + 32, y + 4), &SHEET_BACKGROUND);
+
+        let (x, y) = layout.get_cell_origin(1);
+
+        assert_eq!(sheet.get_pixel(x + 32, y + 32), &MISSING_THUMBNAIL);
+        assert_eq!(progress.get_counts(), (2, 2));
+
+        // Some pixels of the labels are drawn under the thumbnails.
+        let (x, y) = layout.get_cell_origin(0);
+
+        assert!(
+            (x..x + 64)
+                .flat_map(|px| (y + 64..y + 64 + layout.label_height).map(move |py| (px, py)))
+                .any(|(px, py)| sheet.get_pixel(px, py) != &SHEET_BACKGROUND)
+        );
+    }
+
+    #[test]
+    fn test_write_pdf() {
+        let pdf = write_pdf(b"JPEG", 96, 48);
+        let text = String::from_utf8_lossy(&pdf);
+
+        assert!(text.starts_with("%PDF-1.4\n"));
+        assert!(text.ends_with("%%EOF\n"));
+        assert!(text.contains("/MediaBox [0 0 72.00 36.00]"));
+        assert!(text.contains("/Width 96 /Height 48"));
+
+        // The cross-reference table points to the objects.
+        let xref = text
+            .split("startxref\n")
+            .nth(1)
+            .and_then(|x| x.lines().next())
+            .and_then(|x| x.parse::<usize>().ok())
+            .expect("should have the offset of the xref");
+
+        assert!(text[xref..].starts_with("xref\n0 6\n"));
+
+        for (index, line) in text[xref..].lines().skip(3).take(5).enumerate() {
+            let offset = line[..10].parse::<usize>().expect("should be an offset");
+
+            assert!(text[offset..].starts_with(&format!("{} 0 obj\n", index + 1)));
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use crate::{
+    CanvasChanged,
+    app::{app_settings::AppSettings, app_state::AppState},
+    camera::main_camera::CameraCommand,
+    notification::UserNotification,
+    presentation::manifest::Manifest,
+    rendering::model_image::ModelImage,
+    web,
+};
+use bevy::{
+    prelude::{
+        Commands, Entity, MessageReader, MessageWriter, Query, Rect, Res, ResMut, Single, With,
+        warn,
+    },
+    window::RequestRedraw,
+    winit::{EventLoopProxy, EventLoopProxyWrapper, WakeUp},
+};
+use std::cell::RefCell;
+use wasm_bindgen::{JsValue, prelude::wasm_bindgen};
+
+/// Name of the event dispatched on the window of the page when the canvas is changed.
+const CANVAS_CHANGED_EVENT: &str = "canvasChanged";
+
+/// Command from the host page embedding the viewer.
+#[derive(Debug, Clone, PartialEq)]
+enum EmbedCommand {
+    Load(String),
+    GoToCanvas(usize),
+    /// Zoom to the region of the full size image.
+    ZoomTo(Rect),
+}
+
+thread_local! {
+    /// Commands called by the page, applied in the next update.
+    static EMBED_COMMANDS: RefCell<Vec<EmbedCommand>> = const { RefCell::new(Vec::new()) };
+    /// Proxy to wake up the app, which does not update without any input in desktop mode.
+    static EVENT_LOOP_PROXY: RefCell<Option<EventLoopProxy<WakeUp>>> = const { RefCell::new(None) };
+}
+
+/// Queue the command from the page and wake up the app to apply it.
+fn push_command(command: EmbedCommand) {
+    EMBED_COMMANDS.with_borrow_mut(|x| x.push(command));
+    EVENT_LOOP_PROXY.with_borrow(|x| {
+        if let Some(proxy) = x {
+            let _ = proxy.send_event(WakeUp);
+        }
+    });
+}
+
+/// Load the IIIF manifest or collection at the URL.
+#[wasm_bindgen]
+pub fn load(manifest_url: String) {
+    push_command(EmbedCommand::Load(manifest_url));
+}
+
+/// Go to the canvas at the index, starting from 0, of the current sequence.
+#[wasm_bindgen(js_name = goToCanvas)]
+pub fn go_to_canvas(index: usize) {
+    push_command(EmbedCommand::GoToCanvas(index));
+}
+
+/// Zoom to the region of the full size image in pixels.
+#[wasm_bindgen(js_name = zoomTo)]
+pub fn zoom_to(x: f32, y: f32, w: f32, h: f32) {
+    push_command(EmbedCommand::ZoomTo(Rect::new(x, y, x + w, y + h)));
+}
+
+/// Keep the proxy to wake up the app when the page calls it.
+pub(crate) fn setup_embed(event_loop_proxy: Res<EventLoopProxyWrapper<WakeUp>>) {
+    let event_loop_proxy = (*event_loop_proxy).clone();
+
+    EVENT_LOOP_PROXY.with_borrow_mut(|x| *x = Some(event_loop_proxy));
+}
+
+/// Apply the commands called by the host page.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn embed_command_system(
+    mut commands: Commands,
+    presentation: Option<Single<&Manifest>>,
+    mut app_state: ResMut<AppState>,
+    app_settings: Res<AppSettings>,
+    model_image_query: Query<Entity, With<ModelImage>>,
+    mut camera_command_writer: MessageWriter<CameraCommand>,
+    mut redraw_request_writer: MessageWriter<RequestRedraw>,
+    mut messages: MessageWriter<UserNotification>,
+) {
+    let embed_commands = EMBED_COMMANDS.with_borrow_mut(std::mem::take);
+
+    for command in embed_commands {
+        match command {
+            EmbedCommand::Load(url) => {
+                web::load_presentation(&mut app_state, &app_settings, &url);
+            }
+            EmbedCommand::GoToCanvas(index) => {
+                let Some(presentation) = presentation.as_deref() else {
+                    warn!("Unable to go to the canvas {} without a manifest", index);
+                    continue;
+                };
+                let sequence_index = app_state.sequence_index;
+
+                if let Err(e) = web::load_canvas(
+                    &mut commands,
+                    presentation,
+                    &mut app_state,
+                    &app_settings,
+                    sequence_index,
+                    index,
+                    0,
+                    &model_image_query,
+                ) {
+                    messages.write(UserNotification::Error(format!(
+                        "Unable to load canvas.\n'{}'",
+                        e
+                    )));
+                }
+            }
+            EmbedCommand::ZoomTo(rect) => {
+                camera_command_writer.write(CameraCommand::ZoomToImageRect(rect));
+            }
+        }
+
+        redraw_request_writer.write(RequestRedraw);
+    }
+}
+
+/// Dispatch the canvas changed event on the window of the page, with the manifest URL and the
+/// canvas index in its detail.
+fn dispatch_canvas_changed(presentation_url: &str, canvas_index: usize) -> Result<(), JsValue> {
+    let Some(window) = web_sys::window() else {
+        return Ok(());
+    };
+    let detail = js_sys::Object::new();
+
+    js_sys::Reflect::set(&detail, &"manifestUrl".into(), &presentation_url.into())?;
+    js_sys::Reflect::set(&detail, &"canvasIndex".into(), &canvas_index.into())?;
+
+    let init = web_sys::CustomEventInit::new();
+    init.set_detail(&detail);
+
+    let event = web_sys::CustomEvent::new_with_event_init_dict(CANVAS_CHANGED_EVENT, &init)?;
+    window.dispatch_event(&event)?;
+
+    Ok(())
+}
+
+/// Tell the host page when another canvas or manifest is shown.
+pub(crate) fn canvas_changed_system(mut canvas_changed_reader: MessageReader<CanvasChanged>) {
+    for canvas_changed in canvas_changed_reader.read() {
+        if let Err(e) =
+            dispatch_canvas_changed(&canvas_changed.manifest_url, canvas_changed.canvas_index)
+        {
+            warn!(
+                "Unable to dispatch the {} event. {:?}",
+                CANVAS_CHANGED_EVENT, e
+            );
+        }
+    }
+}
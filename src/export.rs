@@ -0,0 +1,701 @@
+use crate::{
+    app::{app_settings::AppSettings, app_state::AppState},
+    iiif::IiifError,
+    notification::UserNotification,
+    presentation::{manifest::Manifest, source::get_synthetic_manifest_json},
+    rendering::tiled_image::{Size, TiledImage},
+    stitch::StitchProgress,
+    tilesource, web,
+};
+use bevy::{
+    prelude::{Commands, MessageWriter, Res, ResMut, Resource, Vec2, info, warn},
+    window::RequestRedraw,
+};
+use image::{GenericImageView, imageops::FilterType};
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, atomic::Ordering},
+};
+
+/// Tile size of the exported pyramid.
+const EXPORT_TILE_SIZE: u32 = 512;
+
+/// Get the scale factors from the full size down to the one fitting in a single tile.
+fn get_scale_factors(width: u32, height: u32, tile_size: u32) -> Vec<u32> {
+    let mut scale_factor = 1;
+    let mut scale_factors = vec![scale_factor];
+
+    while width.max(height).div_ceil(scale_factor) > tile_size {
+        scale_factor *= 2;
+        scale_factors.push(scale_factor);
+    }
+
+    scale_factors
+}
+
+/// Get the regions (left, top, width, height) of the tiles in the full size image at the scale factor.
+fn get_tile_regions(
+    width: u32,
+    height: u32,
+    tile_size: u32,
+    scale_factor: u32,
+) -> Vec<(u32, u32, u32, u32)> {
+    let region_size = tile_size * scale_factor;
+
+    (0..height.div_ceil(region_size))
+        .flat_map(|y| {
+            (0..width.div_ceil(region_size)).map(move |x| {
+                let left = x * region_size;
+                let top = y * region_size;
+
+                (
+                    left,
+                    top,
+                    region_size.min(width - left),
+                    region_size.min(height - top),
+                )
+            })
+        })
+        .collect()
+}
+
+/// Get the region in the image URL, which is "full" for the whole image as requested by the viewer.
+fn get_region_path(region: (u32, u32, u32, u32), width: u32, height: u32) -> String {
+    let (left, top, region_width, region_height) = region;
+
+    if left == 0 && top == 0 && region_width == width && region_height == height {
+        "full".to_string()
+    } else {
+        format!("{left},{top},{region_width},{region_height}")
+    }
+}
+
+/// Export the local image as a level 0 IIIF tile pyramid with its info.json, and a manifest
+/// with a single canvas for it, in the output folder.
+///
+/// Return the file URL of the manifest to open it in the viewer.
+pub(crate) fn export_tiles(image_path: &Path, out_dir: &Path) -> Result<String, IiifError> {
+    let image = image::open(image_path)?;
+    let (width, height) = image.dimensions();
+
+    std::fs::create_dir_all(out_dir)?;
+
+    let out_dir = std::fs::canonicalize(out_dir)?;
+    let service_url = web::get_file_url(&out_dir);
+    let scale_factors = get_scale_factors(width, height, EXPORT_TILE_SIZE);
+
+    info!(
+        "Export {:?} ({}x{}) with the scale factors {:?} to {:?}",
+        image_path, width, height, scale_factors, out_dir
+    );
+
+    let mut sizes = Vec::new();
+
+    for &scale_factor in &scale_factors {
+        let scaled_image = image.resize_exact(
+            width.div_ceil(scale_factor),
+            height.div_ceil(scale_factor),
+            FilterType::Triangle,
+        );
+
+        for region in get_tile_regions(width, height, EXPORT_TILE_SIZE, scale_factor) {
+            let (left, top, region_width, region_height) = region;
+            let tile_width = region_width.div_ceil(scale_factor);
+            let tile_height = region_height.div_ceil(scale_factor);
+            let tile_dir = out_dir
+                .join(get_region_path(region, width, height))
+                .join(format!("{tile_width},{tile_height}"))
+                .join("0");
+
+            std::fs::create_dir_all(&tile_dir)?;
+
+            scaled_image
+                .crop_imm(
+                    left / scale_factor,
+                    top / scale_factor,
+                    tile_width,
+                    tile_height,
+                )
+                .to_rgb8()
+                .save(tile_dir.join("default.jpg"))?;
+
+            // The whole image in a single tile can also be requested as one of the sizes.
+            if region == (0, 0, width, height) {
+                sizes.push(serde_json::json!({ "width": tile_width, "height": tile_height }));
+            }
+        }
+    }
+
+    let image_info = serde_json::json!({
+        "@context": "http://iiif.io/api/image/3/context.json",
+        "id": service_url,
+        "type": "ImageService3",
+        "protocol": "http://iiif.io/api/image",
+        "profile": "level0",
+        "width": width,
+        "height": height,
+        "sizes": sizes,
+        "tiles": [{
+            "width": EXPORT_TILE_SIZE,
+            "height": EXPORT_TILE_SIZE,
+            "scaleFactors": scale_factors
+        }]
+    });
+
+    std::fs::write(
+        out_dir.join("info.json"),
+        serde_json::to_string_pretty(&image_info)?,
+    )?;
+
+    let manifest_path = out_dir.join("manifest.json");
+    let manifest_url = web::get_file_url(&manifest_path);
+    let manifest =
+        get_synthetic_manifest_json(&manifest_url, std::iter::once(service_url.as_str()));
+
+    std::fs::write(&manifest_path, serde_json::to_string_pretty(&manifest)?)?;
+
+    Ok(manifest_url)
+}
+
+/// Parse the canvas numbers from 1 and their ranges, e.g. "1-3,5", into the canvas indices.
+pub(crate) fn parse_canvas_numbers(text: &str) -> Result<Vec<usize>, IiifError> {
+    let mut indices = Vec::new();
+
+    for part in text.split(',').map(str::trim).filter(|x| !x.is_empty()) {
+        let (first, last) = match part.split_once('-') {
+            Some((first, last)) => (
+                first.trim().parse::<usize>()?,
+                last.trim().parse::<usize>()?,
+            ),
+            None => {
+                let number = part.parse::<usize>()?;
+
+                (number, number)
+            }
+        };
+
+        if first == 0 || last < first {
+            return Err(IiifError::IiifFormatError(format!(
+                "invalid canvas numbers '{}'",
+                part
+            )));
+        }
+
+        indices.extend(first - 1..last);
+    }
+
+    Ok(indices)
+}
+
+/// Fetch the URL through the CORS proxy if any, waiting for the response.
+pub(crate) fn fetch_bytes(url: &str, proxy_prefix: &str) -> Result<Vec<u8>, IiifError> {
+    let response =
+        ehttp::fetch_blocking(&ehttp::Request::get(web::get_proxy_url(proxy_prefix, url)))
+            .map_err(|e| IiifError::IiifHttpError(format!("{} for '{}'", e, url)))?;
+
+    if !response.ok {
+        return Err(IiifError::IiifHttpError(format!(
+            "{} {} for '{}'",
+            response.status, response.status_text, url
+        )));
+    }
+
+    Ok(response.bytes)
+}
+
+/// Get the level 0 image info of the levels exported from the smallest, with the tiles of the
+/// same size as the source so that they are at the same indices.
+fn get_offline_image_info(
+    service_url: &str,
+    levels: &[Size],
+    tile_size: Size,
+) -> serde_json::Value {
+    let full_size = levels.last().copied().unwrap_or(tile_size);
+    // From the full size to the smallest as in the image info.
+    let scale_factors = levels
+        .iter()
+        .rev()
+        .map(|x| {
+            (full_size.width as f32 / x.width.max(1) as f32)
+                .round()
+                .max(1.0) as u32
+        })
+        .collect::<Vec<_>>();
+    let max_scale_factor = scale_factors.last().copied().unwrap_or(1);
+    // The smallest level is requested as a whole if it fits in a tile, e.g. for the thumbnail.
+    let sizes = if levels
+        .first()
+        .is_some_and(|x| x.width <= tile_size.width && x.height <= tile_size.height)
+    {
+        vec![serde_json::json!({
+            "width": full_size.width.div_ceil(max_scale_factor),
+            "height": full_size.height.div_ceil(max_scale_factor)
+        })]
+    } else {
+        Vec::new()
+    };
+
+    serde_json::json!({
+        "@context": "http://iiif.io/api/image/3/context.json",
+        "id": service_url,
+        "type": "ImageService3",
+        "protocol": "http://iiif.io/api/image",
+        "profile": "level0",
+        "width": full_size.width,
+        "height": full_size.height,
+        "sizes": sizes,
+        "tiles": [{
+            "width": tile_size.width,
+            "height": tile_size.height,
+            "scaleFactors": scale_factors
+        }]
+    })
+}
+
+/// Replace the strings of the image service URL, or its image info URL, with the local one in
+/// the JSON.
+fn replace_service_urls(value: &mut serde_json::Value, endpoint: &str, service_url: &str) {
+    match value {
+        serde_json::Value::String(x)
+            if x.trim_end_matches('/') == endpoint.trim_end_matches('/')
+                || *x == tilesource::get_info_url(endpoint) =>
+        {
+            *x = service_url.to_string();
+        }
+        serde_json::Value::Array(items) => items
+            .iter_mut()
+            .for_each(|x| replace_service_urls(x, endpoint, service_url)),
+        serde_json::Value::Object(map) => map
+            .values_mut()
+            .for_each(|x| replace_service_urls(x, endpoint, service_url)),
+        _ => {}
+    }
+}
+
+/// Export the tiles of the image up to the number of the levels from the smallest, as a level 0
+/// IIIF tile pyramid with its info.json in the output folder. The info.json is relative to its
+/// folder, so that it can be moved.
+///
+/// Return `false` if cancelled.
+fn export_image_offline(
+    endpoint: &str,
+    out_dir: &Path,
+    num_levels: Option<usize>,
+    proxy_prefix: &str,
+    progress: &StitchProgress,
+) -> Result<bool, IiifError> {
+    let info_url = tilesource::get_info_url(endpoint);
+    let info = String::from_utf8(fetch_bytes(&info_url, proxy_prefix)?)?;
+    let mut image = TiledImage::try_from_info(&info, endpoint)?;
+
+    image.set_proxy_prefix(proxy_prefix);
+
+    let all_levels = image.get_levels();
+    let levels = &all_levels[..num_levels
+        .unwrap_or(all_levels.len())
+        .clamp(1, all_levels.len())];
+
+    std::fs::create_dir_all(out_dir)?;
+
+    let out_dir = std::fs::canonicalize(out_dir)?;
+    let service_url = web::get_file_url(&out_dir);
+    let mut offline_info = get_offline_image_info(&service_url, levels, image.get_tile_size());
+    let offline_image = TiledImage::try_from_info(&offline_info.to_string(), &service_url)?;
+    // From the exported full size to the source one.
+    let scale = Vec2::from(*all_levels.last().expect("should have at least one level"))
+        / Vec2::from(*levels.last().expect("should have at least one level"));
+
+    info!(
+        "Export {} levels of {} to {:?}",
+        levels.len(),
+        endpoint,
+        out_dir
+    );
+
+    let world_rect = offline_image.get_world_max_size_rect();
+    let tiles = (0..levels.len())
+        .flat_map(|level| {
+            offline_image
+                .get_required_tiles(
+                    level,
+                    world_rect.min.extend(0.0),
+                    world_rect.max.extend(0.0),
+                )
+                .0
+        })
+        .collect::<Vec<_>>();
+
+    progress.total.fetch_add(tiles.len(), Ordering::Relaxed);
+
+    for tile in tiles {
+        if progress.is_cancelled() {
+            return Ok(false);
+        }
+
+        progress.done.fetch_add(1, Ordering::Relaxed);
+
+        let offline_url = offline_image.get_image_tile_url_at(tile.index, tile.image_position);
+        let tile_path = out_dir.join(
+            offline_url
+                .strip_prefix(&service_url)
+                .unwrap_or(&offline_url)
+                .trim_start_matches('/'),
+        );
+
+        // Already exported, e.g. by an interrupted export.
+        if tile_path.exists() {
+            continue;
+        }
+
+        let image_position = bevy::prelude::Rect::from_corners(
+            tile.image_position.min * scale,
+            tile.image_position.max * scale,
+        );
+        let url = image.get_image_tile_url_at(tile.index, image_position);
+
+        if let Some(tile_dir) = tile_path.parent() {
+            std::fs::create_dir_all(tile_dir)?;
+        }
+
+        // The tiles may be of any format, while they are read as JPEG locally. Written in full
+        // before being moved into place, so that an interrupted export leaves no partial tile.
+        let part_path = tile_path.with_extension("jpg.part");
+
+        image::load_from_memory(&fetch_bytes(&url, "")?)?
+            .to_rgb8()
+            .save_with_format(&part_path, image::ImageFormat::Jpeg)?;
+        std::fs::rename(&part_path, &tile_path)?;
+    }
+
+    // Relative to the folder of the info.json.
+    offline_info["id"] = serde_json::Value::String(".".to_string());
+
+    std::fs::write(
+        out_dir.join("info.json"),
+        serde_json::to_string_pretty(&offline_info)?,
+    )?;
+
+    Ok(true)
+}
+
+/// Export the manifest with the image info and the tiles of the canvases, up to the number of the
+/// levels from the smallest, to the output folder for viewing offline.
+///
+/// The image services of the exported canvases are replaced with the local ones in the manifest,
+/// relative to its folder so that it can be moved, while the other canvases are still loaded
+/// from the network. Return the file URL of the manifest to open it in the viewer, or `None` if
+/// cancelled.
+fn export_offline(
+    manifest_url: &str,
+    out_dir: &Path,
+    canvas_indices: Option<&[usize]>,
+    num_levels: Option<usize>,
+    proxy_prefix: &str,
+    progress: &StitchProgress,
+) -> Result<Option<String>, IiifError> {
+    let json = String::from_utf8(fetch_bytes(manifest_url, proxy_prefix)?)?;
+    let presentation = Manifest::try_from_json(&json)?;
+    let mut manifest_json: serde_json::Value = serde_json::from_str(&json)?;
+
+    std::fs::create_dir_all(out_dir)?;
+
+    let out_dir = std::fs::canonicalize(out_dir)?;
+    let canvases = presentation
+        .model()
+        .get_sequence(0)?
+        .get_canvases()
+        .enumerate()
+        .filter(|(index, _)| canvas_indices.is_none_or(|x| x.contains(index)))
+        .map(|(index, canvas)| (index, web::get_displayed_canvas(canvas)));
+
+    for (index, canvas) in canvases {
+        let Ok(image) = canvas.get_image(0) else {
+            continue;
+        };
+
+        if image.get_type() == "Model" {
+            continue;
+        }
+
+        let endpoint = image.get_service();
+        let folder = format!("canvas-{}", index + 1);
+
+        match export_image_offline(
+            &endpoint,
+            &out_dir.join(&folder),
+            num_levels,
+            proxy_prefix,
+            progress,
+        ) {
+            Ok(true) => {
+                replace_service_urls(&mut manifest_json, &endpoint, &format!("./{}", folder))
+            }
+            Ok(false) => return Ok(None),
+            Err(e) => warn!("Failed to export the canvas {}. {}", index + 1, e),
+        }
+    }
+
+    let manifest_path = out_dir.join("manifest.json");
+
+    std::fs::write(
+        &manifest_path,
+        serde_json::to_string_pretty(&manifest_json)?,
+    )?;
+
+    Ok(Some(web::get_file_url(&manifest_path)))
+}
+
+/// Result of the export for offline finished in the background.
+#[derive(Debug)]
+enum OfflineExportResult {
+    Exported(String),
+    Cancelled,
+    Failed(IiifError),
+}
+
+/// Export of the manifest for viewing offline in the background, opened once exported.
+#[derive(Resource)]
+pub(crate) struct OfflineExport {
+    /// URL of the manifest exported.
+    pub(crate) manifest_url: String,
+    /// Tiles downloaded of the export, and whether it is cancelled.
+    pub(crate) progress: Arc<StitchProgress>,
+    result: Arc<Mutex<Option<OfflineExportResult>>>,
+}
+
+impl OfflineExport {
+    /// Start to export the manifest to the output folder in the background, with the canvases of
+    /// the indices and the number of the levels from the smallest, all of them if not given.
+    pub(crate) fn start(
+        manifest_url: &str,
+        out_dir: PathBuf,
+        canvas_indices: Option<Vec<usize>>,
+        num_levels: Option<usize>,
+        proxy_prefix: &str,
+    ) -> Self {
+        let progress = Arc::new(StitchProgress::default());
+        let result = Arc::new(Mutex::new(None));
+        let thread_progress = Arc::clone(&progress);
+        let thread_result = Arc::clone(&result);
+        let thread_manifest_url = manifest_url.to_string();
+        let proxy_prefix = proxy_prefix.to_string();
+
+        std::thread::spawn(move || {
+            let exported = match export_offline(
+                &thread_manifest_url,
+                &out_dir,
+                canvas_indices.as_deref(),
+                num_levels,
+                &proxy_prefix,
+                &thread_progress,
+            ) {
+                Ok(Some(local_manifest_url)) => OfflineExportResult::Exported(local_manifest_url),
+                Ok(None) => OfflineExportResult::Cancelled,
+                Err(e) => OfflineExportResult::Failed(e),
+            };
+
+            *thread_result
+                .lock()
+                .expect("should be able to lock the offline export result") = Some(exported);
+        });
+
+        Self {
+            manifest_url: manifest_url.to_string(),
+            progress,
+            result,
+        }
+    }
+
+    /// Cancel the export, keeping the tiles exported to resume it later.
+    pub(crate) fn cancel(&self) {
+        self.progress.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Open the local copy of the manifest once exported for offline in the background.
+pub(crate) fn offline_export_system(
+    mut commands: Commands,
+    offline_export: Res<OfflineExport>,
+    mut app_state: ResMut<AppState>,
+    app_settings: Res<AppSettings>,
+    mut messages: MessageWriter<UserNotification>,
+    mut redraw_request_writer: MessageWriter<RequestRedraw>,
+) {
+    let result = offline_export.result.lock().ok().and_then(|mut x| x.take());
+
+    match result {
+        Some(OfflineExportResult::Exported(local_manifest_url)) => {
+            info!(
+                "Exported {} to {} for offline",
+                offline_export.manifest_url, local_manifest_url
+            );
+            web::load_presentation(&mut app_state, &app_settings, &local_manifest_url);
+        }
+        Some(OfflineExportResult::Cancelled) => {
+            warn!(
+                "Cancelled the export of {} for offline",
+                offline_export.manifest_url
+            )
+        }
+        Some(OfflineExportResult::Failed(e)) => {
+            messages.write(UserNotification::Error(format!(
+                "Failed to export '{}' for offline.\n{}",
+                offline_export.manifest_url, e
+            )));
+        }
+        // Keep the progress updated while exporting.
+        None => {
+            redraw_request_writer.write(RequestRedraw);
+            return;
+        }
+    }
+
+    commands.remove_resource::<OfflineExport>();
+    redraw_request_writer.write(RequestRedraw);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_scale_factors() {
+        assert_eq!(get_scale_factors(512, 300, 512), vec![1]);
+        assert_eq!(get_scale_factors(513, 300, 512), vec![1, 2]);
+        assert_eq!(get_scale_factors(2713, 1910, 512), vec![1, 2, 4, 8]);
+    }
+
+    #[test]
+    fn test_get_tile_regions() {
+        assert_eq!(
+            get_tile_regions(1000, 600, 512, 1),
+            vec![
+                (0, 0, 512, 512),
+                (512, 0, 488, 512),
+                (0, 512, 512, 88),
+                (512, 512, 488, 88)
+            ]
+        );
+        assert_eq!(get_tile_regions(1000, 600, 512, 2), vec![(0, 0, 1000, 600)]);
+        assert_eq!(get_region_path((0, 0, 1000, 600), 1000, 600), "full");
+        assert_eq!(
+            get_region_path((512, 0, 488, 512), 1000, 600),
+            "512,0,488,512"
+        );
+    }
+
+    #[test]
+    fn test_export_tiles() {
+        // Unique to the run, not to collide with the other runs in parallel.
+        let test_dir = std::env::temp_dir().join(format!(
+            "rs_iiif_browser_test_export_tiles_{}_{}",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos()
+        ));
+        let out_dir = test_dir.join("tiles");
+        let image_path = test_dir.join("image.png");
+
+        std::fs::create_dir_all(&test_dir).expect("should create the test folder");
+
+        image::RgbImage::new(700, 300)
+            .save(&image_path)
+            .expect("should save the test image");
+
+        let manifest_url = export_tiles(&image_path, &out_dir).expect("should export the tiles");
+        let out_dir = std::fs::canonicalize(&out_dir).expect("should have the output folder");
+
+        assert_eq!(
+            web::get_file_path(&manifest_url),
+            Some(out_dir.join("manifest.json"))
+        );
+        assert!(out_dir.join("0,0,512,300/512,300/0/default.jpg").exists());
+        assert!(out_dir.join("512,0,188,300/188,300/0/default.jpg").exists());
+        assert!(out_dir.join("full/350,150/0/default.jpg").exists());
+
+        let image_info: serde_json::Value = serde_json::from_str(
+            &std::fs::read_to_string(out_dir.join("info.json")).expect("should have the info"),
+        )
+        .expect("should parse the info");
+
+        assert_eq!(
+            image_info["tiles"][0]["scaleFactors"],
+            serde_json::json!([1, 2])
+        );
+        assert_eq!(
+            image_info["sizes"],
+            serde_json::json!([{ "width": 350, "height": 150 }])
+        );
+
+        let _ = std::fs::remove_dir_all(&test_dir);
+    }
+
+    #[test]
+    fn test_parse_canvas_numbers() {
+        assert_eq!(
+            parse_canvas_numbers("1-3, 5").expect("should parse the numbers"),
+            vec![0, 1, 2, 4]
+        );
+        assert!(parse_canvas_numbers("0").is_err());
+        assert!(parse_canvas_numbers("3-1").is_err());
+        assert!(parse_canvas_numbers("a").is_err());
+    }
+
+    #[test]
+    fn test_get_offline_image_info() {
+        let levels = [
+            Size::new(678, 478),
+            Size::new(1357, 955),
+            Size::new(2713, 1910),
+        ];
+        let image_info =
+            get_offline_image_info("file:///tiles", &levels[..2], Size::new(1024, 1024));
+
+        assert_eq!(image_info["width"], 1357);
+        assert_eq!(
+            image_info["tiles"][0]["scaleFactors"],
+            serde_json::json!([1, 2])
+        );
+        assert_eq!(
+            image_info["sizes"],
+            serde_json::json!([{ "width": 679, "height": 478 }])
+        );
+
+        // The exported levels are read back at the same sizes.
+        let image = TiledImage::try_from_info(&image_info.to_string(), "file:///tiles")
+            .expect("should read the offline image info");
+
+        assert_eq!(
+            image.get_levels(),
+            &[Size::new(678, 477), Size::new(1357, 955)]
+        );
+    }
+
+    #[test]
+    fn test_replace_service_urls() {
+        let mut manifest = serde_json::json!({
+            "id": "https://example.org/manifest",
+            "items": [{
+                "service": [{ "id": "https://example.org/iiif/1", "type": "ImageService3" }],
+                "body": { "id": "https://example.org/iiif/1/full/max/0/default.jpg" }
+            }]
+        });
+
+        replace_service_urls(
+            &mut manifest,
+            "https://example.org/iiif/1",
+            "file:///out/canvas-1",
+        );
+
+        assert_eq!(
+            manifest["items"][0]["service"][0]["id"],
+            "file:///out/canvas-1"
+        );
+        assert_eq!(
+            manifest["items"][0]["body"]["id"],
+            "https://example.org/iiif/1/full/max/0/default.jpg"
+        );
+    }
+}
@@ -0,0 +1,168 @@
+use crate::web::{get_host, percent_decode};
+use bevy::{platform::time::Instant, prelude::Resource};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// Requests to a host in this session.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct HostStat {
+    pub(crate) requests: u64,
+    pub(crate) errors: u64,
+    /// Bytes of the responses received.
+    pub(crate) bytes: u64,
+    last_request: Option<Instant>,
+}
+
+impl HostStat {
+    /// Get the fraction of the requests which failed.
+    pub(crate) fn get_error_rate(&self) -> f32 {
+        if self.requests == 0 {
+            0.0
+        } else {
+            self.errors as f32 / self.requests as f32
+        }
+    }
+}
+
+/// Get the host of the URL, or of the original URL if requested through the CORS proxy, e.g.
+/// "iiif.example.org" of "https://proxy.example.org/?url=https%3A%2F%2Fiiif.example.org%2F", so
+/// that the requests are counted and delayed for the IIIF server rather than the proxy.
+fn get_original_host(url: &str) -> String {
+    let rest = url.split_once("://").map_or("", |(_, x)| x);
+    let lower_rest = rest.to_ascii_lowercase();
+    // The original URL follows the prefix of the proxy as is, or percent-encoded after `=`.
+    let original = ["/http://", "/https://", "=http%3a%2f%2f", "=https%3a%2f%2f"]
+        .iter()
+        .filter_map(|x| lower_rest.find(x))
+        .min()
+        .map(|index| &rest[index + 1..]);
+
+    match original {
+        Some(original) => {
+            get_host(&percent_decode(original).unwrap_or_else(|_| original.to_string())).to_string()
+        }
+        None => get_host(url).to_string(),
+    }
+}
+
+/// Requests to the IIIF hosts in this session, shared with the downloads and the loader of the
+/// tiles to count their responses.
+#[derive(Resource, Clone, Default)]
+pub(crate) struct HostStats(Arc<Mutex<HashMap<String, HostStat>>>);
+
+impl HostStats {
+    /// Update the stat of the host of the URL.
+    fn update(&self, url: &str, f: impl FnOnce(&mut HostStat)) {
+        if let Ok(mut stats) = self.0.lock() {
+            f(stats.entry(get_original_host(url)).or_default());
+        }
+    }
+
+    /// Count the request sent to the URL.
+    pub(crate) fn record_request(&self, url: &str) {
+        self.update(url, |x| {
+            x.requests += 1;
+            x.last_request = Some(Instant::now());
+        });
+    }
+
+    /// Count the bytes received from the URL.
+    pub(crate) fn record_bytes(&self, url: &str, bytes: usize) {
+        self.update(url, |x| x.bytes += bytes as u64);
+    }
+
+    /// Count the request to the URL which failed.
+    pub(crate) fn record_error(&self, url: &str) {
+        self.update(url, |x| x.errors += 1);
+    }
+
+    /// Whether the URL can be requested with at least the delay after the previous request to
+    /// its host, to avoid hammering the server in the polite mode.
+    pub(crate) fn can_request(&self, url: &str, delay: Duration) -> bool {
+        if delay.is_zero() {
+            return true;
+        }
+
+        self.0
+            .lock()
+            .ok()
+            .and_then(|stats| stats.get(&get_original_host(url))?.last_request)
+            .is_none_or(|x| x.elapsed() >= delay)
+    }
+
+    /// Get the stats of the hosts, by the most requested first.
+    pub(crate) fn get_stats(&self) -> Vec<(String, HostStat)> {
+        let mut stats = self
+            .0
+            .lock()
+            .map(|x| {
+                x.iter()
+                    .map(|(host, stat)| (host.to_string(), stat.clone()))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        stats.sort_by(|a, b| b.1.requests.cmp(&a.1.requests).then(a.0.cmp(&b.0)));
+
+        stats
+    }
+
+    pub(crate) fn clear(&self) {
+        if let Ok(mut stats) = self.0.lock() {
+            stats.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_stats() {
+        let host_stats = HostStats::default();
+
+        host_stats.record_request("https://iiif.example.org/iiif/1/info.json");
+        host_stats.record_bytes("https://iiif.example.org/iiif/1/info.json", 100);
+        host_stats.record_request("https://iiif.example.org/iiif/1/full/max/0/default.jpg");
+        host_stats.record_error("https://iiif.example.org/iiif/1/full/max/0/default.jpg");
+        host_stats.record_request("https://other.example.org/manifest.json");
+
+        let stats = host_stats.get_stats();
+
+        assert_eq!(stats[0].0, "iiif.example.org");
+        assert_eq!(stats[0].1.requests, 2);
+        assert_eq!(stats[0].1.bytes, 100);
+        assert_eq!(stats[0].1.get_error_rate(), 0.5);
+        assert_eq!(stats[1].0, "other.example.org");
+
+        // Just requested.
+        assert!(
+            !host_stats.can_request("https://other.example.org/a.jpg", Duration::from_secs(60))
+        );
+        assert!(host_stats.can_request("https://other.example.org/a.jpg", Duration::ZERO));
+        assert!(host_stats.can_request("https://new.example.org/a.jpg", Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn test_get_original_host() {
+        assert_eq!(
+            get_original_host("https://iiif.example.org/iiif/1/info.json"),
+            "iiif.example.org"
+        );
+        assert_eq!(
+            get_original_host("https://proxy.example.org/https://iiif.example.org/iiif/1"),
+            "iiif.example.org"
+        );
+        assert_eq!(
+            get_original_host(
+                "https://proxy.example.org/?url=https%3A%2F%2Fiiif.example.org%2Fiiif%2F1"
+            ),
+            "iiif.example.org"
+        );
+        assert_eq!(get_original_host("file:///tiles/0/0.jpg"), "");
+    }
+}
@@ -0,0 +1,378 @@
+use bevy::platform::time::Instant;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// Maximum number of responses kept in the cache.
+const MAX_CACHED_RESPONSES: usize = 64;
+
+/// Directives of the `Cache-Control` response header which matter to the cache.
+#[derive(Debug, Default, PartialEq)]
+struct CacheControl {
+    /// The response must not be stored.
+    no_store: bool,
+    /// The response must be revalidated before it is used.
+    no_cache: bool,
+    /// How long the response is fresh without revalidation.
+    max_age: Option<Duration>,
+}
+
+impl CacheControl {
+    /// Parse the `Cache-Control` header value, e.g. "public, max-age=3600".
+    fn parse(value: &str) -> Self {
+        let mut cache_control = Self::default();
+
+        for directive in value.split(',').map(|x| x.trim().to_lowercase()) {
+            match directive.split_once('=') {
+                Some(("max-age", seconds)) => {
+                    cache_control.max_age = seconds
+                        .trim_matches('"')
+                        .parse::<u64>()
+                        .ok()
+                        .map(Duration::from_secs);
+                }
+                Some(_) => {}
+                None if directive == "no-store" => cache_control.no_store = true,
+                None if directive == "no-cache" => cache_control.no_cache = true,
+                None => {}
+            }
+        }
+
+        cache_control
+    }
+}
+
+/// Headers of a response to revalidate it with the server, e.g. in a later session.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Validators {
+    pub(crate) etag: Option<String>,
+    pub(crate) last_modified: Option<String>,
+}
+
+/// Response cached for the revalidation.
+struct CachedResponse {
+    json: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    /// The response can be used without revalidation until then.
+    fresh_until: Option<Instant>,
+    stored_at: Instant,
+}
+
+/// Cache of the manifest and image info responses by URL, revalidated with `ETag` and
+/// `Last-Modified` and kept fresh as long as `Cache-Control` allows.
+#[derive(Clone, Default)]
+pub(crate) struct HttpCache(Arc<Mutex<HashMap<String, CachedResponse>>>);
+
+impl HttpCache {
+    /// Get the cached JSON of the URL if it is still fresh.
+    pub(crate) fn get_fresh(&self, url: &str) -> Option<String> {
+        let cache = self
+            .0
+            .lock()
+            .expect("should be able to lock the http cache");
+        let cached = cache.get(url)?;
+
+        cached
+            .fresh_until
+            .is_some_and(|x| Instant::now() < x)
+            .then(|| cached.json.to_string())
+    }
+
+    /// Add the headers to revalidate the cached response of the URL if any.
+    ///
+    /// Skipped in wasm, where the browser revalidates with its own cache, and where these
+    /// headers would require a CORS preflight the IIIF servers may not allow.
+    pub(crate) fn add_conditional_headers(&self, url: &str, request: &mut ehttp::Request) {
+        if cfg!(target_arch = "wasm32") {
+            return;
+        }
+
+        let cache = self
+            .0
+            .lock()
+            .expect("should be able to lock the http cache");
+        let Some(cached) = cache.get(url) else {
+            return;
+        };
+
+        if let Some(etag) = &cached.etag {
+            request.headers.insert("If-None-Match", etag);
+        }
+        if let Some(last_modified) = &cached.last_modified {
+            request.headers.insert("If-Modified-Since", last_modified);
+        }
+    }
+
+    /// Get the cached JSON of the URL when the server responds that it is not modified, and
+    /// keep it fresh for the new `Cache-Control`.
+    pub(crate) fn revalidate(&self, url: &str, headers: &ehttp::Headers) -> Option<String> {
+        let mut cache = self
+            .0
+            .lock()
+            .expect("should be able to lock the http cache");
+        let cached = cache.get_mut(url)?;
+        let cache_control = CacheControl::parse(headers.get("Cache-Control").unwrap_or_default());
+
+        cached.fresh_until = get_fresh_until(&cache_control);
+
+        Some(cached.json.to_string())
+    }
+
+    /// Store the JSON of the URL unless `Cache-Control` forbids it, or there is nothing to
+    /// revalidate or keep it fresh with.
+    pub(crate) fn store(&self, url: &str, headers: &ehttp::Headers, json: &str) {
+        let mut cache = self
+            .0
+            .lock()
+            .expect("should be able to lock the http cache");
+        let cache_control = CacheControl::parse(headers.get("Cache-Control").unwrap_or_default());
+        let etag = headers.get("ETag").map(|x| x.to_string());
+        let last_modified = headers.get("Last-Modified").map(|x| x.to_string());
+        let fresh_until = get_fresh_until(&cache_control);
+
+        if cache_control.no_store
+            || (etag.is_none() && last_modified.is_none() && fresh_until.is_none())
+        {
+            cache.remove(url);
+            return;
+        }
+
+        make_room(&mut cache, url, MAX_CACHED_RESPONSES, |x| x.stored_at);
+        cache.insert(
+            url.to_string(),
+            CachedResponse {
+                json: json.to_string(),
+                etag,
+                last_modified,
+                fresh_until,
+                stored_at: Instant::now(),
+            },
+        );
+    }
+
+    /// Get the headers to revalidate the cached response of the URL, to keep them with it
+    /// elsewhere.
+    pub(crate) fn get_validators(&self, url: &str) -> Validators {
+        self.0
+            .lock()
+            .expect("should be able to lock the http cache")
+            .get(url)
+            .map(|x| Validators {
+                etag: x.etag.clone(),
+                last_modified: x.last_modified.clone(),
+            })
+            .unwrap_or_default()
+    }
+
+    /// Store the JSON of the URL kept elsewhere, e.g. from a previous session, so that it is
+    /// revalidated with the server when it is requested. The response cached in this session
+    /// takes precedence.
+    pub(crate) fn store_stale(&self, url: &str, json: &str, validators: &Validators) {
+        let mut cache = self
+            .0
+            .lock()
+            .expect("should be able to lock the http cache");
+
+        if cache.contains_key(url) || *validators == Validators::default() {
+            return;
+        }
+
+        make_room(&mut cache, url, MAX_CACHED_RESPONSES, |x| x.stored_at);
+        cache.insert(
+            url.to_string(),
+            CachedResponse {
+                json: json.to_string(),
+                etag: validators.etag.clone(),
+                last_modified: validators.last_modified.clone(),
+                fresh_until: None,
+                stored_at: Instant::now(),
+            },
+        );
+    }
+
+    /// Make the cached response of the URL stale, so that it is revalidated with the server when
+    /// it is requested again, e.g. to reload the manifest.
+    pub(crate) fn expire(&self, url: &str) {
+        let mut cache = self
+            .0
+            .lock()
+            .expect("should be able to lock the http cache");
+
+        if let Some(cached) = cache.get_mut(url) {
+            cached.fresh_until = None;
+        }
+    }
+
+    /// Store the JSON of the URL fetched ahead of its use, e.g. the image info of the next
+    /// canvas, and keep it fresh for the duration so that it is used without another request.
+    pub(crate) fn store_prefetched(&self, url: &str, json: &str, duration: Duration) {
+        let mut cache = self
+            .0
+            .lock()
+            .expect("should be able to lock the http cache");
+        let fresh_until = Instant::now() + duration;
+
+        make_room(&mut cache, url, MAX_CACHED_RESPONSES, |x| x.stored_at);
+
+        let cached = cache
+            .entry(url.to_string())
+            .or_insert_with(|| CachedResponse {
+                json: json.to_string(),
+                etag: None,
+                last_modified: None,
+                fresh_until: None,
+                stored_at: Instant::now(),
+            });
+
+        cached.json = json.to_string();
+        cached.fresh_until = cached.fresh_until.max(Some(fresh_until));
+    }
+}
+
+/// Drop the oldest entry by its age if the cache has the maximum number of entries and the key
+/// is not in it yet.
+pub(crate) fn make_room<V, K: Ord>(
+    cache: &mut HashMap<String, V>,
+    key: &str,
+    max: usize,
+    age: impl Fn(&V) -> K,
+) {
+    if cache.len() >= max
+        && !cache.contains_key(key)
+        && let Some(oldest) = cache
+            .iter()
+            .min_by_key(|(_, x)| age(x))
+            .map(|(key, _)| key.to_string())
+    {
+        cache.remove(&oldest);
+    }
+}
+
+/// Get until when a response is fresh without revalidation.
+fn get_fresh_until(cache_control: &CacheControl) -> Option<Instant> {
+    if cache_control.no_cache {
+        return None;
+    }
+
+    cache_control
+        .max_age
+        .filter(|x| !x.is_zero())
+        .map(|x| Instant::now() + x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cache_control() {
+        assert_eq!(CacheControl::parse(""), CacheControl::default());
+        assert_eq!(
+            CacheControl::parse("public, Max-Age=3600"),
+            CacheControl {
+                max_age: Some(Duration::from_secs(3600)),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            CacheControl::parse("no-cache, no-store, max-age=abc"),
+            CacheControl {
+                no_store: true,
+                no_cache: true,
+                max_age: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_http_cache() {
+        let cache = HttpCache::default();
+        let url = "https://example.org/manifest";
+
+        // Nothing to revalidate with.
+        cache.store(url, &ehttp::Headers::new(&[]), "{}");
+        assert!(cache.revalidate(url, &ehttp::Headers::new(&[])).is_none());
+
+        cache.store(
+            url,
+            &ehttp::Headers::new(&[("ETag", "\"abc\""), ("Cache-Control", "no-cache")]),
+            "{}",
+        );
+        assert_eq!(cache.get_fresh(url), None);
+
+        let mut request = ehttp::Request::get(url);
+        cache.add_conditional_headers(url, &mut request);
+        assert_eq!(request.headers.get("If-None-Match"), Some("\"abc\""));
+        assert_eq!(request.headers.get("If-Modified-Since"), None);
+
+        assert_eq!(
+            cache.revalidate(
+                url,
+                &ehttp::Headers::new(&[("Cache-Control", "max-age=60")])
+            ),
+            Some("{}".to_string())
+        );
+        assert_eq!(cache.get_fresh(url), Some("{}".to_string()));
+
+        cache.expire(url);
+        assert_eq!(cache.get_fresh(url), None);
+
+        cache.store(
+            url,
+            &ehttp::Headers::new(&[("ETag", "\"abc\""), ("Cache-Control", "no-store")]),
+            "{}",
+        );
+        assert!(cache.revalidate(url, &ehttp::Headers::new(&[])).is_none());
+    }
+
+    #[test]
+    fn test_store_prefetched() {
+        let cache = HttpCache::default();
+        let url = "https://example.org/iiif/image/info.json";
+
+        // Fresh for a while even without the cache headers.
+        cache.store_prefetched(url, "{}", Duration::from_secs(60));
+        assert_eq!(cache.get_fresh(url), Some("{}".to_string()));
+
+        cache.store_prefetched(url, "{\"a\":1}", Duration::ZERO);
+        assert_eq!(cache.get_fresh(url), Some("{\"a\":1}".to_string()));
+    }
+
+    #[test]
+    fn test_store_stale() {
+        let cache = HttpCache::default();
+        let url = "https://example.org/iiif/image/info.json";
+        let validators = Validators {
+            etag: Some("\"abc\"".to_string()),
+            last_modified: None,
+        };
+
+        // Nothing to revalidate with.
+        cache.store_stale(url, "{}", &Validators::default());
+        assert_eq!(cache.get_validators(url), Validators::default());
+
+        cache.store_stale(url, "{}", &validators);
+        assert_eq!(cache.get_fresh(url), None);
+        assert_eq!(cache.get_validators(url), validators);
+        assert_eq!(
+            cache.revalidate(url, &ehttp::Headers::new(&[])),
+            Some("{}".to_string())
+        );
+
+        // Not replacing the response of this session.
+        cache.store_stale(url, "{\"a\":1}", &Validators::default());
+        cache.store_stale(
+            url,
+            "{\"a\":1}",
+            &Validators {
+                etag: Some("\"def\"".to_string()),
+                last_modified: None,
+            },
+        );
+        assert_eq!(cache.get_validators(url), validators);
+    }
+}
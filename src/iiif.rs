@@ -25,11 +25,19 @@ pub enum IiifError {
     #[error("IIIF format error '{0}'")]
     IiifFormatError(String),
 
-    // #[error("IIIF unsupported error {0}")]
-    // IiifUnsupportedError(String),
+    #[error("IIIF unsupported error {0}")]
+    IiifUnsupportedError(String),
+
     #[error("std io error")]
     IiifStdIoError(#[from] std::io::Error),
 
     #[error("IIIF parse int error {0}")]
     IiifParseIntError(#[from] std::num::ParseIntError),
+
+    #[error("image error {0}")]
+    IiifImageError(#[from] image::ImageError),
+
+    #[error("HTTP error {0}")]
+    #[cfg_attr(any(target_arch = "wasm32", target_os = "android"), allow(dead_code))]
+    IiifHttpError(String),
 }
@@ -1,6 +1,9 @@
 use crate::iiif::{IiifError, image_v2, image_v3};
 use crate::rendering::model::IsImage;
-use bevy::prelude::debug;
+use bevy::{
+    math::Affine2,
+    prelude::{Vec2, debug},
+};
 use core::fmt;
 use serde::{Deserialize, Serialize};
 
@@ -111,3 +114,283 @@ impl IiifImageInfo {
         Ok(output)
     }
 }
+
+/// Orientation of the image painted on the canvas, mirrored first and then rotated clockwise
+/// as the rotation parameter of the image API.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub(crate) struct ImageOrientation {
+    /// Rotation in degrees clockwise, one of 0, 90, 180 and 270.
+    pub(crate) rotation: u32,
+    pub(crate) mirrored: bool,
+}
+
+impl ImageOrientation {
+    /// Parse the rotation parameter of the image API, e.g. "90" or "!180" for mirroring.
+    /// Arbitrary rotations are rounded to the nearest multiple of 90 degrees.
+    pub(crate) fn try_from_rotation(rotation: &str) -> Option<Self> {
+        let rotation = rotation.trim();
+        let (mirrored, degrees) = match rotation.strip_prefix('!') {
+            Some(degrees) => (true, degrees),
+            None => (false, rotation),
+        };
+        let degrees = degrees.trim().parse::<f32>().ok()?;
+
+        Some(Self {
+            mirrored,
+            ..Self::default().rotate(degrees)
+        })
+    }
+
+    /// Parse the CSS transform of the class in the stylesheet, e.g.
+    /// ".rotated { transform: rotate(90deg) scaleX(-1); }".
+    pub(crate) fn try_from_css(stylesheet: &str, class: &str) -> Option<Self> {
+        let selector = format!(".{}", class.trim());
+        let (_, rule) = stylesheet.split_once(selector.as_str())?;
+        let (_, declarations) = rule.split_once('{')?;
+        let (declarations, _) = declarations.split_once('}')?;
+        let transform = declarations
+            .split(';')
+            .filter_map(|x| x.split_once(':'))
+            .find(|(name, _)| name.trim() == "transform")
+            .map(|(_, value)| value)?;
+
+        let mut orientation = Self::default();
+
+        // The functions apply to the element from right to left, so compose them from left.
+        for function in transform.split(')') {
+            let Some((name, args)) = function.split_once('(') else {
+                continue;
+            };
+            let args = args.split(',').map(|x| x.trim()).collect::<Vec<_>>();
+
+            match (name.trim(), args.as_slice()) {
+                ("rotate", [angle]) => {
+                    orientation = orientation.rotate(parse_css_angle(angle)?);
+                }
+                ("scaleX", ["-1"]) | ("scale", ["-1"] | ["-1", "1"]) => {
+                    orientation = orientation.mirror();
+                }
+                ("scaleY", ["-1"]) | ("scale", ["1", "-1"]) => {
+                    orientation = orientation.mirror().rotate(180.0);
+                }
+                _ => {}
+            }
+        }
+
+        Some(orientation)
+    }
+
+    /// Apply a rotation in degrees clockwise before this orientation.
+    fn rotate(self, degrees: f32) -> Self {
+        let quarters = (degrees / 90.0).round() as i64;
+        let quarters = if self.mirrored { -quarters } else { quarters };
+
+        Self {
+            rotation: ((self.rotation as i64 / 90 + quarters).rem_euclid(4) * 90) as u32,
+            mirrored: self.mirrored,
+        }
+    }
+
+    /// Apply a horizontal mirroring before this orientation.
+    fn mirror(self) -> Self {
+        Self {
+            rotation: self.rotation,
+            mirrored: !self.mirrored,
+        }
+    }
+
+    /// Get the rotation parameter of the image API, e.g. "90" or "!180" for mirroring.
+    pub(crate) fn get_rotation(&self) -> String {
+        format!("{}{}", if self.mirrored { "!" } else { "" }, self.rotation)
+    }
+
+    /// Whether the width and height of the image are swapped on the canvas.
+    pub(crate) fn is_swapped(&self) -> bool {
+        self.rotation % 180 != 0
+    }
+
+    /// Get the size on the canvas of the image of the size.
+    pub(crate) fn to_canvas_size(&self, size: Vec2) -> Vec2 {
+        if self.is_swapped() {
+            Vec2::new(size.y, size.x)
+        } else {
+            size
+        }
+    }
+
+    /// Convert from the image to the canvas position, for the image of the size.
+    pub(crate) fn to_canvas(&self, p: Vec2, size: Vec2) -> Vec2 {
+        let p = if self.mirrored {
+            Vec2::new(size.x - p.x, p.y)
+        } else {
+            p
+        };
+
+        match self.rotation {
+            90 => Vec2::new(size.y - p.y, p.x),
+            180 => size - p,
+            270 => Vec2::new(p.y, size.x - p.x),
+            _ => p,
+        }
+    }
+
+    /// Convert from the canvas to the image position, for the image of the size.
+    pub(crate) fn to_image(&self, p: Vec2, size: Vec2) -> Vec2 {
+        let p = match self.rotation {
+            90 => Vec2::new(p.y, size.y - p.x),
+            180 => size - p,
+            270 => Vec2::new(size.x - p.y, p.x),
+            _ => p,
+        };
+
+        if self.mirrored {
+            Vec2::new(size.x - p.x, p.y)
+        } else {
+            p
+        }
+    }
+
+    /// Get the transform from the UV of a tile on the canvas to the UV of its texture.
+    pub(crate) fn get_uv_transform(&self) -> Affine2 {
+        let origin = self.to_image(Vec2::ZERO, Vec2::ONE);
+
+        Affine2::from_cols(
+            self.to_image(Vec2::X, Vec2::ONE) - origin,
+            self.to_image(Vec2::Y, Vec2::ONE) - origin,
+            origin,
+        )
+    }
+}
+
+/// Selector of the image API parameters of the image painted on the canvas.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct ImageApiSelector {
+    /// Rotation as a string as "!90", or a number.
+    pub(crate) rotation: Option<serde_json::Value>,
+}
+
+impl ImageApiSelector {
+    /// Get the orientation of the rotation, or `None` if not rotated.
+    pub(crate) fn get_orientation(&self) -> Option<ImageOrientation> {
+        match self.rotation.as_ref()? {
+            serde_json::Value::String(v) => ImageOrientation::try_from_rotation(v),
+            serde_json::Value::Number(v) => ImageOrientation::try_from_rotation(&v.to_string()),
+            _ => None,
+        }
+    }
+}
+
+/// Parse the CSS angle in degrees, e.g. "90deg", "0.25turn" or "1.5708rad".
+fn parse_css_angle(angle: &str) -> Option<f32> {
+    if let Some(v) = angle.strip_suffix("deg") {
+        v.trim().parse().ok()
+    } else if let Some(v) = angle.strip_suffix("turn") {
+        v.trim().parse::<f32>().ok().map(|x| x * 360.0)
+    } else if let Some(v) = angle.strip_suffix("rad") {
+        v.trim().parse::<f32>().ok().map(f32::to_degrees)
+    } else {
+        angle.parse().ok().filter(|x: &f32| *x == 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_orientation_from_rotation() {
+        assert_eq!(
+            ImageOrientation::try_from_rotation("90"),
+            Some(ImageOrientation {
+                rotation: 90,
+                mirrored: false
+            })
+        );
+        assert_eq!(
+            ImageOrientation::try_from_rotation("!270"),
+            Some(ImageOrientation {
+                rotation: 270,
+                mirrored: true
+            })
+        );
+        assert_eq!(
+            ImageOrientation::try_from_rotation("-90"),
+            Some(ImageOrientation {
+                rotation: 270,
+                mirrored: false
+            })
+        );
+        assert_eq!(
+            ImageOrientation::try_from_rotation("22.5"),
+            Some(ImageOrientation::default())
+        );
+        assert_eq!(ImageOrientation::try_from_rotation("up"), None);
+        assert_eq!(
+            ImageOrientation::try_from_rotation("!270").map(|x| x.get_rotation()),
+            Some("!270".to_string())
+        );
+    }
+
+    #[test]
+    fn test_orientation_from_css() {
+        let stylesheet = ".other { transform: rotate(180deg) } \
+            .rotated { transform-origin: center; transform: rotate(90deg); }";
+
+        assert_eq!(
+            ImageOrientation::try_from_css(stylesheet, "rotated"),
+            Some(ImageOrientation {
+                rotation: 90,
+                mirrored: false
+            })
+        );
+        // Mirrored first and then rotated.
+        assert_eq!(
+            ImageOrientation::try_from_css(".m { transform: rotate(0.25turn) scaleX(-1) }", "m"),
+            Some(ImageOrientation {
+                rotation: 90,
+                mirrored: true
+            })
+        );
+        // Rotated first and then mirrored.
+        assert_eq!(
+            ImageOrientation::try_from_css(".m { transform: scaleX(-1) rotate(90deg) }", "m"),
+            Some(ImageOrientation {
+                rotation: 270,
+                mirrored: true
+            })
+        );
+        assert_eq!(ImageOrientation::try_from_css(stylesheet, "missing"), None);
+    }
+
+    #[test]
+    fn test_orientation_position() {
+        let size = Vec2::new(200.0, 100.0);
+        let p = Vec2::new(20.0, 10.0);
+
+        for rotation in [0, 90, 180, 270] {
+            for mirrored in [false, true] {
+                let orientation = ImageOrientation { rotation, mirrored };
+
+                assert_eq!(
+                    orientation.to_image(orientation.to_canvas(p, size), size),
+                    p
+                );
+            }
+        }
+
+        let orientation = ImageOrientation {
+            rotation: 90,
+            mirrored: false,
+        };
+
+        // The top left corner is rotated to the top right.
+        assert_eq!(orientation.to_canvas_size(size), Vec2::new(100.0, 200.0));
+        assert_eq!(orientation.to_canvas(p, size), Vec2::new(90.0, 20.0));
+        assert_eq!(
+            orientation
+                .get_uv_transform()
+                .transform_point2(Vec2::new(1.0, 0.0)),
+            Vec2::ZERO
+        );
+    }
+}
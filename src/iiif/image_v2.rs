@@ -235,6 +235,24 @@ impl IsImage for ImageInfo {
     fn get_height(&self) -> u32 {
         self.iiif_image_info.height
     }
+
+    fn get_api_version(&self) -> Option<u32> {
+        Some(2)
+    }
+
+    fn get_compliance_level(&self) -> Option<u32> {
+        // E.g. "http://iiif.io/api/image/2/level2.json".
+        self.iiif_image_info.profile.iter().find_map(|x| match x {
+            IiifProfileInfo::Url(url) => url
+                .rsplit('/')
+                .next()?
+                .strip_prefix("level")?
+                .strip_suffix(".json")?
+                .parse()
+                .ok(),
+            IiifProfileInfo::ProfileDetails(_) => None,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -223,6 +223,18 @@ impl IsImage for ImageInfo {
     fn get_height(&self) -> u32 {
         self.iiif_image_info.height
     }
+
+    fn get_api_version(&self) -> Option<u32> {
+        Some(3)
+    }
+
+    fn get_compliance_level(&self) -> Option<u32> {
+        self.iiif_image_info
+            .profile
+            .strip_prefix("level")?
+            .parse()
+            .ok()
+    }
 }
 
 #[cfg(test)]
@@ -1,6 +1,6 @@
 use crate::{
     iiif::{IiifError, manifest_v2, manifest_v3},
-    presentation::model::IsManifest,
+    presentation::model::{IsCanvas, IsCollection, IsManifest},
 };
 use bevy::prelude::debug;
 use serde::{Deserialize, Serialize};
@@ -12,6 +12,89 @@ pub(crate) mod language {
     pub(crate) const FR: &str = "fr";
     pub(crate) const DE: &str = "de";
     pub(crate) const ZH: &str = "zh";
+
+    /// Get how well the BCP 47 language tag matches the preferred languages in order separated by
+    /// commas, e.g. "en-GB, en, fr", lower is better. `None` if it matches none of them.
+    ///
+    /// A tag matches a preference if they are the same, or one is a more specific form of the
+    /// other, e.g. "zh-Hant" matches "zh" and "en" matches "en-GB", with the exact match first.
+    pub(crate) fn get_match_rank(preferences: &str, tag: &str) -> Option<usize> {
+        let tag = tag.trim().to_lowercase();
+
+        preferences
+            .split(',')
+            .map(|x| x.trim().to_lowercase())
+            .filter(|x| !x.is_empty())
+            .enumerate()
+            .find_map(|(index, preference)| {
+                let quality = if tag == preference {
+                    0
+                } else if tag.starts_with(&format!("{}-", preference)) {
+                    1
+                } else if preference.starts_with(&format!("{}-", tag)) {
+                    2
+                } else {
+                    return None;
+                };
+
+                Some(index * 3 + quality)
+            })
+    }
+
+    /// Get the tag among the tags which best matches the preferred languages, the first one if
+    /// more than one match as well. `None` if none of them matches.
+    pub(crate) fn get_best_match<'a>(
+        preferences: &str,
+        tags: impl Iterator<Item = &'a str>,
+    ) -> Option<&'a str> {
+        tags.filter(|x| *x != NONE)
+            .filter_map(|x| get_match_rank(preferences, x).map(|rank| (rank, x)))
+            .min_by_key(|(rank, _)| *rank)
+            .map(|(_, x)| x)
+    }
+}
+
+/// Get the size of the canvas from the width and the height in the manifest, which are meant to
+/// be integers. `None` if either is missing or not positive.
+pub(crate) fn get_canvas_size(width: Option<f64>, height: Option<f64>) -> Option<(u32, u32)> {
+    let (width, height) = (width?.round(), height?.round());
+
+    (width >= 1.0 && height >= 1.0 && width <= u32::MAX as f64 && height <= u32::MAX as f64)
+        .then_some((width as u32, height as u32))
+}
+
+/// Get the media fragment of the target of an annotation, of the canvas or by the selector, e.g.
+/// "xywh=10,20,100,50".
+pub(crate) fn get_media_fragment(target: &serde_json::Value) -> Option<&str> {
+    match target.as_str() {
+        Some(target) => target.split_once('#').map(|(_, x)| x),
+        None => target["selector"]["value"].as_str().or_else(|| {
+            let source = &target["source"];
+
+            source
+                .as_str()
+                .or(source["id"].as_str())
+                .and_then(|x| x.split_once('#'))
+                .map(|(_, x)| x)
+        }),
+    }
+}
+
+/// Get the region of the canvas the image is painted on from the target of its annotation, e.g.
+/// "xywh=10,20,100,50". `None` if the whole canvas.
+pub(crate) fn get_image_target_fragment(target: &serde_json::Value) -> Option<String> {
+    get_media_fragment(target)
+        .and_then(|x| x.split('&').find(|x| x.starts_with("xywh=")))
+        .map(String::from)
+}
+
+/// Error of a canvas which cannot be shown, e.g. without any image, found when parsing the
+/// manifest.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CanvasError {
+    pub(crate) sequence_index: usize,
+    pub(crate) canvas_index: usize,
+    pub(crate) msg: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -22,11 +105,15 @@ pub(crate) enum Manifest {
 }
 
 impl Manifest {
-    /// Build from a Json string.
+    /// Build from a Json string, with the errors of the canvases which cannot be shown.
+    ///
+    /// The parsing is lenient so that the usable canvases are still shown, and it only fails if
+    /// none of the canvases in the first sequence can be shown.
     pub(crate) fn try_from_json(
         info_json: &str,
-    ) -> core::result::Result<Box<dyn IsManifest>, IiifError> {
-        let iiif_presentation_info: Manifest = serde_json::from_str(info_json)?;
+    ) -> core::result::Result<(Box<dyn IsManifest>, Vec<CanvasError>), IiifError> {
+        let iiif_presentation_info: Manifest =
+            serde_json::from_str(info_json).map_err(|_| get_version_error(info_json))?;
         debug!("iiif_image_info {:?}", iiif_presentation_info);
 
         let output = match iiif_presentation_info {
@@ -34,8 +121,119 @@ impl Manifest {
             Manifest::Version3(v) => Box::new(v) as Box<dyn IsManifest>,
         };
 
-        // Check if we can get at least one sequence, one canvas and one image.
-        output.get_sequence(0)?.get_canvas(0)?.get_image(0)?;
+        let canvas_errors = get_canvas_errors(output.as_ref());
+        let num_canvases = output.get_sequence(0)?.get_canvases().len();
+
+        // Fail with the error of the first canvas if none can be shown, or there is no canvas.
+        if canvas_errors
+            .iter()
+            .filter(|x| x.sequence_index == 0)
+            .count()
+            == num_canvases
+        {
+            output.get_sequence(0)?.get_canvas(0)?.get_image(0)?;
+        }
+
+        Ok((output, canvas_errors))
+    }
+}
+
+/// Get the error of the canvas if it cannot be shown, i.e. neither the canvas nor its
+/// placeholder canvas has an image.
+fn get_canvas_error(canvas: &dyn IsCanvas) -> Option<IiifError> {
+    let error = canvas.get_image(0).err()?;
+
+    if canvas
+        .get_placeholder_canvas()
+        .is_some_and(|x| x.get_image(0).is_ok())
+    {
+        None
+    } else {
+        Some(error)
+    }
+}
+
+/// Get the errors of the canvases in all the sequences which cannot be shown.
+fn get_canvas_errors(manifest: &dyn IsManifest) -> Vec<CanvasError> {
+    manifest
+        .get_sequences()
+        .enumerate()
+        .flat_map(|(sequence_index, sequence)| {
+            sequence
+                .get_canvases()
+                .enumerate()
+                .filter_map(move |(canvas_index, canvas)| {
+                    get_canvas_error(canvas).map(|e| CanvasError {
+                        sequence_index,
+                        canvas_index,
+                        msg: e.to_string(),
+                    })
+                })
+        })
+        .collect()
+}
+
+/// Get the error of the manifest for its API version.
+///
+/// The untagged enum does not tell where the JSON fails, so parse it again as the version in
+/// the context for a precise error.
+fn get_version_error(info_json: &str) -> IiifError {
+    let value: serde_json::Value = match serde_json::from_str(info_json) {
+        Ok(value) => value,
+        Err(e) => return e.into(),
+    };
+
+    let contexts: Vec<&str> = match value.get("@context") {
+        Some(serde_json::Value::String(context)) => vec![context.as_str()],
+        Some(serde_json::Value::Array(contexts)) => {
+            contexts.iter().filter_map(|x| x.as_str()).collect()
+        }
+        _ => vec![],
+    };
+
+    let result = if contexts.iter().any(|x| x.contains("/presentation/2/")) {
+        serde_json::from_str::<manifest_v2::Manifest>(info_json).map(|_| ())
+    } else if contexts.iter().any(|x| x.contains("/presentation/3/")) {
+        serde_json::from_str::<manifest_v3::Manifest>(info_json).map(|_| ())
+    } else {
+        return IiifError::IiifUnsupportedError(format!(
+            "unsupported presentation API context '{}'",
+            contexts.join(", ")
+        ));
+    };
+
+    match result {
+        Err(e) => e.into(),
+        Ok(_) => IiifError::IiifFormatError("manifest not matching its API version".to_string()),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum Collection {
+    Version2(manifest_v2::Collection),
+    Version3(manifest_v3::Collection),
+}
+
+impl Collection {
+    /// Build from a Json string.
+    pub(crate) fn try_from_json(
+        info_json: &str,
+    ) -> core::result::Result<Box<dyn IsCollection>, IiifError> {
+        let iiif_collection: Collection = serde_json::from_str(info_json)?;
+        debug!("iiif_collection {:?}", iiif_collection);
+
+        let output = match iiif_collection {
+            Collection::Version2(v) if v.type_ == manifest_v2::ManifestType::Collection => {
+                Box::new(v) as Box<dyn IsCollection>
+            }
+            Collection::Version3(v) if v.type_ == manifest_v3::ManifestType::Collection => {
+                Box::new(v) as Box<dyn IsCollection>
+            }
+            _ => {
+                return Err(IiifError::IiifFormatError("not a collection".to_string()));
+            }
+        };
 
         Ok(output)
     }
@@ -45,6 +243,89 @@ impl Manifest {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_language_match() {
+        assert_eq!(language::get_match_rank("en", "en"), Some(0));
+        assert_eq!(language::get_match_rank("en", "EN-gb"), Some(1));
+        assert_eq!(language::get_match_rank("en-GB", "en"), Some(2));
+        assert_eq!(language::get_match_rank("fr, zh", "zh-Hant"), Some(4));
+        assert_eq!(language::get_match_rank("en", "eng"), None);
+        assert_eq!(language::get_match_rank("", "en"), None);
+
+        let tags = ["de", "en-US", "en", "zh-Hant", language::NONE];
+
+        assert_eq!(
+            language::get_best_match("en-GB, en", tags.into_iter()),
+            Some("en")
+        );
+        assert_eq!(
+            language::get_best_match("zh, en", tags.into_iter()),
+            Some("zh-Hant")
+        );
+        assert_eq!(
+            language::get_best_match("en-US", tags.into_iter()),
+            Some("en-US")
+        );
+        assert_eq!(language::get_best_match("fr, none", tags.into_iter()), None);
+    }
+
+    #[test]
+    fn test_get_canvas_size() {
+        assert_eq!(
+            get_canvas_size(Some(750.0), Some(1000.4)),
+            Some((750, 1000))
+        );
+        assert_eq!(get_canvas_size(Some(750.0), None), None);
+        assert_eq!(get_canvas_size(Some(0.0), Some(1000.0)), None);
+        assert_eq!(get_canvas_size(Some(-1.0), Some(1000.0)), None);
+    }
+
+    #[test]
+    fn test_get_image_target_fragment() {
+        assert_eq!(
+            get_image_target_fragment(&serde_json::json!(
+                "https://example.org/canvas/1#xywh=100,200,300,400"
+            )),
+            Some("xywh=100,200,300,400".to_string())
+        );
+        assert_eq!(
+            get_image_target_fragment(&serde_json::json!("https://example.org/canvas/1")),
+            None
+        );
+        assert_eq!(get_image_target_fragment(&serde_json::Value::Null), None);
+    }
+
+    #[test]
+    fn test_version_error() {
+        let json = r#"{
+            "@context": "http://iiif.io/api/presentation/2/context.json",
+            "@id": "https://example.org/iiif/book1/manifest",
+            "@type": "sc:Manifest",
+            "label": "Book 1",
+            "sequences": 1
+        }"#;
+
+        assert!(matches!(
+            Manifest::try_from_json(json),
+            Err(IiifError::Deserialization(e)) if e.line() == 6
+        ));
+
+        let json = r#"{
+            "@context": "http://iiif.io/api/presentation/1/context.json",
+            "@id": "https://example.org/iiif/book1/manifest"
+        }"#;
+
+        assert!(matches!(
+            Manifest::try_from_json(json),
+            Err(IiifError::IiifUnsupportedError(_))
+        ));
+
+        assert!(matches!(
+            Manifest::try_from_json("{"),
+            Err(IiifError::Deserialization(_))
+        ));
+    }
+
     #[test]
     fn test_from_json() {
         let json = r#"
@@ -278,4 +559,103 @@ mod tests {
 
         assert!(Manifest::try_from_json(json).is_ok());
     }
+
+    #[test]
+    fn test_collection_from_json() {
+        let json = r#"{
+          "@context": "http://iiif.io/api/presentation/2/context.json",
+          "@id": "http://example.org/iiif/collection/top",
+          "@type": "sc:Collection",
+          "label": "Top Level Collection",
+          "manifests": [
+            {
+              "@id": "http://example.org/iiif/book1/manifest",
+              "@type": "sc:Manifest",
+              "label": "Book 1"
+            }
+          ]
+        }"#;
+
+        let collection = Collection::try_from_json(json).unwrap();
+
+        assert_eq!(collection.get_members().len(), 1);
+        assert!(Manifest::try_from_json(json).is_err());
+
+        let json = r#"{
+          "@context": "http://iiif.io/api/presentation/2/context.json",
+          "@id": "http://example.org/iiif/book1/manifest",
+          "@type": "sc:Manifest",
+          "label": "Book 1",
+          "sequences": []
+        }"#;
+
+        assert!(Collection::try_from_json(json).is_err());
+    }
+
+    #[test]
+    fn test_from_json_with_broken_canvases() {
+        let json = r#"{
+          "@context": "http://iiif.io/api/presentation/3/context.json",
+          "id": "https://example.org/iiif/book1/manifest",
+          "type": "Manifest",
+          "label": { "none": [ "Book 1" ] },
+          "items": [
+            {
+              "id": "https://example.org/iiif/book1/canvas/p1",
+              "type": "Canvas"
+            },
+            {
+              "id": "https://example.org/iiif/book1/canvas/p2",
+              "type": "Canvas",
+              "items": [
+                {
+                  "id": "https://example.org/iiif/book1/page/p2",
+                  "type": "AnnotationPage",
+                  "items": [
+                    {
+                      "id": "https://example.org/iiif/book1/annotation/p2",
+                      "type": "Annotation",
+                      "body": {
+                        "id": "https://example.org/iiif/book1/p2/full/max/0/default.jpg",
+                        "type": "Image",
+                        "service": [
+                          {
+                            "id": "https://example.org/iiif/book1/p2",
+                            "type": "ImageService3",
+                            "profile": "level1"
+                          }
+                        ]
+                      }
+                    }
+                  ]
+                }
+              ]
+            }
+          ]
+        }"#;
+
+        let (manifest, canvas_errors) =
+            Manifest::try_from_json(json).expect("should load the usable canvas");
+
+        assert_eq!(manifest.get_sequence(0).unwrap().get_canvases().len(), 2);
+        assert_eq!(canvas_errors.len(), 1);
+        assert_eq!(canvas_errors[0].sequence_index, 0);
+        assert_eq!(canvas_errors[0].canvas_index, 0);
+
+        // Refuse the manifest if none of the canvases can be shown.
+        let json = r#"{
+          "@context": "http://iiif.io/api/presentation/3/context.json",
+          "id": "https://example.org/iiif/book1/manifest",
+          "type": "Manifest",
+          "label": { "none": [ "Book 1" ] },
+          "items": [
+            {
+              "id": "https://example.org/iiif/book1/canvas/p1",
+              "type": "Canvas"
+            }
+          ]
+        }"#;
+
+        assert!(Manifest::try_from_json(json).is_err());
+    }
 }
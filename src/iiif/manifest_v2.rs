@@ -1,7 +1,11 @@
 use crate::iiif::IiifError;
-use crate::iiif::manifest::language;
+use crate::iiif::image::{ImageApiSelector, ImageOrientation};
+use crate::iiif::manifest::{get_canvas_size, get_image_target_fragment, language};
 use crate::iiif::one_or_many::OneTypeOrMany;
-use crate::presentation::model::{IsCanvas, IsImage, IsManifest, IsSequence};
+use crate::presentation::model::{
+    CanvasHighlight, ExternalLink, ExternalLinkKind, IsCanvas, IsCollection, IsCollectionMember,
+    IsImage, IsManifest, IsSequence, PageBehavior, Rendering,
+};
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 
@@ -67,10 +71,18 @@ impl LabelText {
         }
         // If any of the values have a language associated with them,
         // the client must display all of the values associated with the language that best matches the language preference.
-        else if lvp.iter().any(|x| x.get().language == lang) {
+        else if let Some(best_language) = language::get_best_match(
+            lang,
+            lvp.iter().map(|x| match x {
+                LabelTextValue::SimpleText(_) => language::NONE,
+                LabelTextValue::LanguageValuePair(v) => v.language.as_str(),
+            }),
+        )
+        .map(|x| x.to_string())
+        {
             output = lvp
                 .into_iter()
-                .filter(|x| x.get().language == lang)
+                .filter(|x| x.get().language == best_language)
                 .collect();
         }
         // If all of the values have a language associated with them,
@@ -108,6 +120,12 @@ impl LabelText {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct MetadataItem {
+    label: LabelText,
+    value: LabelText,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct Sequence {
@@ -115,32 +133,64 @@ pub(crate) struct Sequence {
     type_: ManifestType,
     pub(crate) label: Option<LabelText>,
     pub(crate) canvases: Vec<Canvas>,
+    /// Alternative representations of the sequence, e.g. a PDF of the whole book.
+    pub(crate) rendering: Option<OneTypeOrMany<ExternalResource>>,
+    /// Hint of the presentation of the sequence, e.g. "paged".
+    pub(crate) viewing_hint: Option<OneTypeOrMany<String>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct Canvas {
+    #[serde(rename = "@id", default)]
+    pub(crate) id: String,
     #[serde(rename = "@type")]
     type_: ManifestType,
     pub(crate) label: LabelText,
+    /// Images, empty if missing so that the other canvases can still be shown.
+    #[serde(default)]
     pub(crate) images: Vec<Image>,
     pub(crate) thumbnail: Option<OneTypeOrMany<UriLink>>,
+    /// Alternative representations of the canvas, e.g. a PDF.
+    pub(crate) rendering: Option<OneTypeOrMany<ExternalResource>>,
+    /// Hint of the presentation of the canvas, e.g. "non-paged".
+    pub(crate) viewing_hint: Option<OneTypeOrMany<String>>,
+    /// Size of the canvas, as numbers of any kind so that a canvas with a fractional size can
+    /// still be shown.
+    pub(crate) width: Option<f64>,
+    pub(crate) height: Option<f64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct Image {
     pub(crate) resource: ImageResource,
+    /// Canvas with the region the image is painted on as the fragment.
+    #[serde(default)]
+    pub(crate) on: serde_json::Value,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct ImageResource {
-    #[serde(rename = "@id")]
+    #[serde(rename = "@id", default)]
     pub(crate) id: String,
     #[serde(rename = "@type")]
     pub(crate) type_: String,
-    pub(crate) service: Service,
+    pub(crate) service: Option<Service>,
+    /// Full image of a specific resource, e.g. painted with a rotation.
+    pub(crate) full: Option<Box<ImageResource>>,
+    pub(crate) selector: Option<ImageApiSelector>,
+}
+
+impl ImageResource {
+    /// Get the image with the service, which is the full image of a specific resource.
+    fn get_image(&self) -> &ImageResource {
+        match &self.full {
+            Some(full) if self.service.is_none() => full,
+            _ => self,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -160,6 +210,60 @@ pub(crate) enum UriLink {
     },
 }
 
+/// External resource linked by its URL, with an optional label.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum ExternalResource {
+    StringType(String),
+    IdType {
+        #[serde(rename = "@id")]
+        id: String,
+        label: Option<LabelText>,
+        /// Media type of the resource, e.g. "application/pdf".
+        format: Option<String>,
+    },
+}
+
+impl ExternalResource {
+    /// Get the link of the kind to the resource.
+    fn to_link(&self, kind: ExternalLinkKind, language: &str) -> ExternalLink {
+        match self {
+            Self::StringType(url) => ExternalLink {
+                kind,
+                url: url.to_string(),
+                label: "".to_string(),
+            },
+            Self::IdType { id, label, .. } => ExternalLink {
+                kind,
+                url: id.to_string(),
+                label: label
+                    .as_ref()
+                    .map(|x| x.get(language).join(","))
+                    .unwrap_or_default(),
+            },
+        }
+    }
+
+    /// Get the resource as an alternative representation to download.
+    fn to_rendering(&self, language: &str) -> Rendering {
+        match self {
+            Self::StringType(url) => Rendering {
+                url: url.to_string(),
+                label: "".to_string(),
+                format: "".to_string(),
+            },
+            Self::IdType { id, label, format } => Rendering {
+                url: id.to_string(),
+                label: label
+                    .as_ref()
+                    .map(|x| x.get(language).join(","))
+                    .unwrap_or_default(),
+                format: format.clone().unwrap_or_default(),
+            },
+        }
+    }
+}
+
 impl UriLink {
     pub(crate) fn id(&self) -> &str {
         match self {
@@ -169,6 +273,14 @@ impl UriLink {
     }
 }
 
+/// Get the URL of the first of the thumbnails, empty if none.
+fn get_thumbnail_url(thumbnail: &Option<OneTypeOrMany<UriLink>>) -> Cow<'_, str> {
+    thumbnail
+        .as_ref()
+        .and_then(|x| x.iter().next())
+        .map_or(Cow::from(""), |x| Cow::from(x.id()))
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct Manifest {
@@ -181,10 +293,52 @@ pub(crate) struct Manifest {
     pub(crate) license: Option<OneTypeOrMany<UriLink>>,
     pub(crate) logo: Option<OneTypeOrMany<UriLink>>,
     pub(crate) description: Option<LabelText>,
+    pub(crate) metadata: Option<Vec<MetadataItem>>,
     pub(crate) sequences: Vec<Sequence>,
+    /// Collections the manifest is part of.
+    pub(crate) within: Option<OneTypeOrMany<UriLink>>,
+    pub(crate) related: Option<OneTypeOrMany<ExternalResource>>,
+    pub(crate) see_also: Option<OneTypeOrMany<ExternalResource>>,
+    /// Alternative representations of the whole manifest, e.g. a PDF.
+    pub(crate) rendering: Option<OneTypeOrMany<ExternalResource>>,
+    /// Thumbnail of the whole manifest, e.g. of the cover.
+    pub(crate) thumbnail: Option<OneTypeOrMany<UriLink>>,
+    /// Hint of the presentation, e.g. "paged".
+    pub(crate) viewing_hint: Option<OneTypeOrMany<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CollectionMember {
+    #[serde(rename = "@type")]
+    pub(crate) type_: ManifestType,
+    #[serde(rename = "@id")]
+    pub(crate) id: String,
+    pub(crate) label: Option<LabelText>,
+    pub(crate) thumbnail: Option<OneTypeOrMany<UriLink>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Collection {
+    #[serde(rename = "@type")]
+    pub(crate) type_: ManifestType,
+    #[serde(rename = "@id")]
+    pub(crate) id: String,
+    pub(crate) label: LabelText,
+    #[serde(default)]
+    pub(crate) collections: Vec<CollectionMember>,
+    #[serde(default)]
+    pub(crate) manifests: Vec<CollectionMember>,
+    #[serde(default)]
+    pub(crate) members: Vec<CollectionMember>,
 }
 
 impl IsManifest for Manifest {
+    fn get_id(&self) -> Cow<'_, str> {
+        Cow::from(&self.id)
+    }
+
     fn get_title(&self, language: &str) -> Cow<'_, str> {
         Cow::from(self.label.get(language).join("\n"))
     }
@@ -209,6 +363,24 @@ impl IsManifest for Manifest {
         }
     }
 
+    fn get_metadata(&self, language: &str) -> Box<dyn Iterator<Item = Cow<'_, str>> + '_> {
+        match &self.metadata {
+            None => Box::new(std::iter::empty::<Cow<str>>()),
+            Some(v) => Box::new(
+                v.iter()
+                    .map(|x| {
+                        Cow::from(format!(
+                            "{}: {}",
+                            x.label.get(language).join(", "),
+                            x.value.get(language).join(", ")
+                        ))
+                    })
+                    .collect::<Vec<_>>()
+                    .into_iter(),
+            ),
+        }
+    }
+
     fn get_license(&self) -> Box<dyn Iterator<Item = Cow<'_, str>> + '_> {
         if let Some(content) = &self.license {
             Box::new(content.iter().map(|y| Cow::from(y.id())))
@@ -229,6 +401,55 @@ impl IsManifest for Manifest {
         Box::new(self.sequences.iter().map(|b| b as &dyn IsSequence))
     }
 
+    fn get_parent_collections(&self) -> Box<dyn Iterator<Item = &dyn IsCollectionMember> + '_> {
+        if let Some(content) = &self.within {
+            Box::new(content.iter().map(|x| x as &dyn IsCollectionMember))
+        } else {
+            Box::new(std::iter::empty())
+        }
+    }
+
+    fn get_external_links(&self, language: &str) -> Vec<ExternalLink> {
+        let related = self
+            .related
+            .iter()
+            .flat_map(|x| x.iter())
+            .map(|x| x.to_link(ExternalLinkKind::Related, language));
+        let see_also = self
+            .see_also
+            .iter()
+            .flat_map(|x| x.iter())
+            .map(|x| x.to_link(ExternalLinkKind::SeeAlso, language));
+
+        related.chain(see_also).collect()
+    }
+
+    fn get_renderings(&self, language: &str) -> Vec<Rendering> {
+        // The renderings of the whole book are often given by the sequence in v2.
+        self.rendering
+            .iter()
+            .flat_map(|x| x.iter())
+            .chain(
+                self.sequences
+                    .iter()
+                    .flat_map(|x| x.rendering.iter().flat_map(|y| y.iter())),
+            )
+            .map(|x| x.to_rendering(language))
+            .collect()
+    }
+
+    fn get_thumbnail(&self) -> Cow<'_, str> {
+        get_thumbnail_url(&self.thumbnail)
+    }
+
+    fn is_paged(&self) -> bool {
+        // The hint is often given by the sequence in v2.
+        std::iter::once(&self.viewing_hint)
+            .chain(self.sequences.iter().map(|x| &x.viewing_hint))
+            .flatten()
+            .any(|x| x.iter().any(|y| y == "paged"))
+    }
+
     fn get_sequence(&self, index: usize) -> Result<&dyn IsSequence, IiifError> {
         self.sequences
             .get(index)
@@ -240,6 +461,64 @@ impl IsManifest for Manifest {
     }
 }
 
+impl IsCollection for Collection {
+    fn get_title(&self, language: &str) -> Cow<'_, str> {
+        Cow::from(self.label.get(language).join("\n"))
+    }
+
+    fn get_members(&self) -> Box<dyn ExactSizeIterator<Item = &dyn IsCollectionMember> + '_> {
+        // "members" is the ordered list of both, otherwise the sub-collections come first.
+        let members = if self.members.is_empty() {
+            self.collections.iter().chain(&self.manifests).collect()
+        } else {
+            self.members.iter().collect::<Vec<_>>()
+        };
+
+        Box::new(members.into_iter().map(|x| x as &dyn IsCollectionMember))
+    }
+}
+
+impl IsCollectionMember for CollectionMember {
+    fn get_id(&self) -> Cow<'_, str> {
+        Cow::from(&self.id)
+    }
+
+    fn get_label(&self, language: &str) -> Box<dyn Iterator<Item = Cow<'_, str>> + '_> {
+        if let Some(content) = &self.label {
+            Box::new(content.get(language).into_iter())
+        } else {
+            Box::new(std::iter::empty::<Cow<str>>())
+        }
+    }
+
+    fn is_collection(&self) -> bool {
+        self.type_ == ManifestType::Collection
+    }
+
+    fn get_thumbnail(&self) -> Cow<'_, str> {
+        get_thumbnail_url(&self.thumbnail)
+    }
+}
+
+/// Collection linked by `within`, which has no label.
+impl IsCollectionMember for UriLink {
+    fn get_id(&self) -> Cow<'_, str> {
+        Cow::from(self.id())
+    }
+
+    fn get_label(&self, _: &str) -> Box<dyn Iterator<Item = Cow<'_, str>> + '_> {
+        Box::new(std::iter::empty::<Cow<str>>())
+    }
+
+    fn is_collection(&self) -> bool {
+        true
+    }
+
+    fn get_thumbnail(&self) -> Cow<'_, str> {
+        Cow::from("")
+    }
+}
+
 impl IsSequence for Sequence {
     fn get_label(&self, language: &str) -> Box<dyn Iterator<Item = Cow<'_, str>> + '_> {
         if let Some(content) = &self.label {
@@ -265,6 +544,10 @@ impl IsSequence for Sequence {
 }
 
 impl IsCanvas for Canvas {
+    fn get_id(&self) -> Cow<'_, str> {
+        Cow::from(&self.id)
+    }
+
     fn get_label(&self, language: &str) -> Box<dyn Iterator<Item = Cow<'_, str>> + '_> {
         Box::new(self.label.get(language).into_iter())
     }
@@ -289,6 +572,10 @@ impl IsCanvas for Canvas {
         }
     }
 
+    fn get_size(&self) -> Option<(u32, u32)> {
+        get_canvas_size(self.width, self.height)
+    }
+
     // fn get_images(&self) -> Box<dyn ExactSizeIterator<Item = &dyn IsImage> + '_> {
     //     Box::new(self.images.iter().map(|b| b as &dyn IsImage))
     // }
@@ -302,6 +589,64 @@ impl IsCanvas for Canvas {
                 index
             )))
     }
+
+    fn get_image_choices(
+        &self,
+        index: usize,
+    ) -> Result<Box<dyn ExactSizeIterator<Item = &dyn IsImage> + '_>, IiifError> {
+        let image = self.get_image(index)?;
+
+        Ok(Box::new(vec![image].into_iter()))
+    }
+
+    fn get_image_target_fragment(&self, index: usize) -> Option<String> {
+        get_image_target_fragment(&self.images.get(index)?.on)
+    }
+
+    fn get_image_orientation(&self, index: usize, _: usize) -> ImageOrientation {
+        self.images
+            .get(index)
+            .and_then(|x| x.resource.selector.as_ref())
+            .and_then(|x| x.get_orientation())
+            .unwrap_or_default()
+    }
+
+    fn get_placeholder_canvas(&self) -> Option<&dyn IsCanvas> {
+        None
+    }
+
+    fn get_accompanying_canvas(&self) -> Option<&dyn IsCanvas> {
+        None
+    }
+
+    fn get_highlights(&self, _language: &str) -> Vec<CanvasHighlight> {
+        Vec::new()
+    }
+
+    fn get_duration(&self) -> Option<f64> {
+        None
+    }
+
+    fn get_timed_annotations(&self, _language: &str) -> Vec<CanvasHighlight> {
+        Vec::new()
+    }
+
+    fn get_renderings(&self, language: &str) -> Vec<Rendering> {
+        self.rendering
+            .iter()
+            .flat_map(|x| x.iter())
+            .map(|x| x.to_rendering(language))
+            .collect()
+    }
+
+    fn get_page_behavior(&self) -> PageBehavior {
+        PageBehavior::from_hints(
+            self.viewing_hint
+                .iter()
+                .flat_map(|x| x.iter())
+                .map(|x| x.as_str()),
+        )
+    }
 }
 
 impl IsImage for Image {
@@ -312,15 +657,23 @@ impl IsImage for Image {
     //     self.resource.width
     // }
     fn get_service(&self) -> Cow<'_, str> {
-        Cow::from(&self.resource.service.id)
+        self.resource
+            .get_image()
+            .service
+            .as_ref()
+            .map_or(Cow::from(""), |x| Cow::from(&x.id))
     }
 
     fn get_id(&self) -> Cow<'_, str> {
-        Cow::from(&self.resource.id)
+        Cow::from(&self.resource.get_image().id)
     }
 
     fn get_type(&self) -> Cow<'_, str> {
-        Cow::from(&self.resource.type_)
+        Cow::from(&self.resource.get_image().type_)
+    }
+
+    fn get_label(&self, _: &str) -> Box<dyn Iterator<Item = Cow<'_, str>> + '_> {
+        Box::new(std::iter::empty::<Cow<str>>())
     }
 }
 
@@ -521,6 +874,12 @@ mod tests {
             presentation_info.label.get(language::EN).join("\n"),
             "Book 1"
         );
+        assert_eq!(
+            presentation_info
+                .get_metadata(language::EN)
+                .collect::<Vec<_>>(),
+            vec!["Author: Anne Author", "Published: Paris, circa 1400"]
+        );
         let license: Vec<_> = presentation_info
             .license
             .as_ref()
@@ -566,7 +925,7 @@ mod tests {
 
             let resource = &image.resource;
 
-            let service = &resource.service;
+            let service = resource.service.as_ref().expect("should have a service");
             assert_eq!(
                 service.id,
                 format!("http://www.example.org/images/book1-page{num}")
@@ -575,6 +934,189 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_within_and_links_json() {
+        let json = r#"{
+          "@context": "http://iiif.io/api/presentation/2/context.json",
+          "@id": "http://example.org/iiif/book1/manifest",
+          "@type": "sc:Manifest",
+          "label": "Book 1",
+          "within": "http://example.org/iiif/collection/top",
+          "related": {
+            "@id": "http://example.org/info/book1/",
+            "label": "Home page for Book 1",
+            "format": "text/html"
+          },
+          "seeAlso": ["http://example.org/library/catalog/book1.xml"],
+          "sequences": []
+        }"#;
+
+        let manifest: Manifest = serde_json::from_str(json).unwrap();
+        let parents = manifest.get_parent_collections().collect::<Vec<_>>();
+
+        assert_eq!(parents.len(), 1);
+        assert_eq!(
+            parents[0].get_id(),
+            "http://example.org/iiif/collection/top"
+        );
+        assert!(parents[0].is_collection());
+        assert_eq!(
+            manifest.get_external_links(language::EN),
+            vec![
+                ExternalLink {
+                    kind: ExternalLinkKind::Related,
+                    url: "http://example.org/info/book1/".to_string(),
+                    label: "Home page for Book 1".to_string(),
+                },
+                ExternalLink {
+                    kind: ExternalLinkKind::SeeAlso,
+                    url: "http://example.org/library/catalog/book1.xml".to_string(),
+                    label: "".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rendering_json() {
+        let json = r#"{
+          "@context": "http://iiif.io/api/presentation/2/context.json",
+          "@id": "http://example.org/iiif/book1/manifest",
+          "@type": "sc:Manifest",
+          "label": "Book 1",
+          "sequences": [
+            {
+              "@type": "sc:Sequence",
+              "rendering": {
+                "@id": "http://example.org/iiif/book1.pdf",
+                "label": "Download as PDF",
+                "format": "application/pdf"
+              },
+              "canvases": [
+                {
+                  "@id": "http://example.org/iiif/book1/canvas/p1",
+                  "@type": "sc:Canvas",
+                  "label": "p. 1",
+                  "rendering": ["http://example.org/iiif/book1/p1.txt"]
+                }
+              ]
+            }
+          ]
+        }"#;
+
+        let manifest: Manifest = serde_json::from_str(json).unwrap();
+
+        assert_eq!(
+            manifest.get_renderings(language::EN),
+            vec![Rendering {
+                url: "http://example.org/iiif/book1.pdf".to_string(),
+                label: "Download as PDF".to_string(),
+                format: "application/pdf".to_string(),
+            }]
+        );
+        assert_eq!(
+            manifest.sequences[0].canvases[0].get_renderings(language::EN),
+            vec![Rendering {
+                url: "http://example.org/iiif/book1/p1.txt".to_string(),
+                label: "".to_string(),
+                format: "".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_viewing_hint_json() {
+        let json = r#"{
+          "@context": "http://iiif.io/api/presentation/2/context.json",
+          "@id": "http://example.org/iiif/book1/manifest",
+          "@type": "sc:Manifest",
+          "label": "Book 1",
+          "sequences": [
+            {
+              "@type": "sc:Sequence",
+              "viewingHint": "paged",
+              "canvases": [
+                {
+                  "@id": "http://example.org/iiif/book1/canvas/cover",
+                  "@type": "sc:Canvas",
+                  "label": "Cover",
+                  "viewingHint": "non-paged"
+                },
+                {
+                  "@id": "http://example.org/iiif/book1/canvas/p1",
+                  "@type": "sc:Canvas",
+                  "label": "p. 1"
+                },
+                {
+                  "@id": "http://example.org/iiif/book1/canvas/p2-3",
+                  "@type": "sc:Canvas",
+                  "label": "pp. 2-3",
+                  "viewingHint": ["facing-pages"]
+                }
+              ]
+            }
+          ]
+        }"#;
+
+        let manifest: Manifest = serde_json::from_str(json).unwrap();
+        let canvases = &manifest.sequences[0].canvases;
+
+        assert!(manifest.is_paged());
+        assert_eq!(canvases[0].get_page_behavior(), PageBehavior::NonPaged);
+        assert_eq!(canvases[1].get_page_behavior(), PageBehavior::Paged);
+        assert_eq!(canvases[2].get_page_behavior(), PageBehavior::FacingPages);
+    }
+
+    #[test]
+    fn test_collection_json() {
+        let json = r#"{
+          "@context": "http://iiif.io/api/presentation/2/context.json",
+          "@id": "http://example.org/iiif/collection/top",
+          "@type": "sc:Collection",
+          "label": "Top Level Collection for Example Organization",
+          "collections": [
+            {
+              "@id": "http://example.org/iiif/collection/part1",
+              "@type": "sc:Collection",
+              "label": "Sub-Collection 1"
+            }
+          ],
+          "manifests": [
+            {
+              "@id": "http://example.org/iiif/book1/manifest",
+              "@type": "sc:Manifest",
+              "label": "Book 1"
+            }
+          ]
+        }"#;
+
+        let collection: Collection = serde_json::from_str(json).unwrap();
+
+        assert_eq!(collection.type_, ManifestType::Collection);
+        assert_eq!(
+            collection.get_title(language::EN),
+            "Top Level Collection for Example Organization"
+        );
+
+        let members = collection.get_members().collect::<Vec<_>>();
+
+        assert_eq!(members.len(), 2);
+        assert_eq!(
+            members[0].get_id(),
+            "http://example.org/iiif/collection/part1"
+        );
+        assert!(members[0].is_collection());
+        assert_eq!(
+            members[1].get_id(),
+            "http://example.org/iiif/book1/manifest"
+        );
+        assert_eq!(
+            members[1].get_label(language::EN).collect::<Vec<_>>(),
+            vec!["Book 1"]
+        );
+        assert!(!members[1].is_collection());
+    }
+
     #[test]
     fn test_ham_json() {
         let json = r#"
@@ -704,11 +1246,68 @@ mod tests {
 
         let resource = &image.resource;
 
-        let service = &resource.service;
+        let service = resource.service.as_ref().expect("should have a service");
         assert_eq!(service.id, "https://ids.lib.harvard.edu/ids/iiif/11927378");
         assert_eq!(service.profile, "http://iiif.io/api/image/2/level2.json");
     }
 
+    #[test]
+    fn test_rotated_image_json() {
+        let json = r#"{
+            "@context": "http://iiif.io/api/presentation/2/context.json",
+            "@id": "http://www.example.org/iiif/book1/manifest",
+            "@type": "sc:Manifest",
+            "label": "Book 1",
+            "sequences": [{
+                "@type": "sc:Sequence",
+                "canvases": [{
+                    "@id": "http://www.example.org/iiif/book1/canvas/p1",
+                    "@type": "sc:Canvas",
+                    "label": "p. 1",
+                    "images": [{
+                        "@type": "oa:Annotation",
+                        "motivation": "sc:painting",
+                        "resource": {
+                            "@id": "http://www.example.org/iiif/book1/res/page1.jpg",
+                            "@type": "oa:SpecificResource",
+                            "full": {
+                                "@id": "http://www.example.org/iiif/book1/full/full/0/default.jpg",
+                                "@type": "dctypes:Image",
+                                "service": {
+                                    "@id": "http://www.example.org/iiif/book1",
+                                    "profile": "http://iiif.io/api/image/2/level2.json"
+                                }
+                            },
+                            "selector": {
+                                "@context": "http://iiif.io/api/annex/openannotation/context.json",
+                                "@type": "iiif:ImageApiSelector",
+                                "rotation": "!90"
+                            }
+                        },
+                        "on": "http://www.example.org/iiif/book1/canvas/p1"
+                    }]
+                }]
+            }]
+        }"#;
+
+        let manifest: Manifest = serde_json::from_str(json).expect("should parse the manifest");
+        let canvas = manifest
+            .get_sequence(0)
+            .and_then(|x| x.get_canvas(0))
+            .expect("should have a canvas");
+        let image = canvas.get_image(0).expect("should have an image");
+
+        assert_eq!(image.get_service(), "http://www.example.org/iiif/book1");
+        assert_eq!(image.get_type(), "dctypes:Image");
+        assert_eq!(
+            canvas.get_image_orientation(0, 0),
+            ImageOrientation {
+                rotation: 90,
+                mirrored: true
+            }
+        );
+    }
+
     #[test]
     fn test_text_simple_one() {
         let text = LabelText(OneTypeOrMany::<LabelTextValue>::One(
@@ -1,14 +1,24 @@
 use crate::{
     iiif::{
         IiifError,
-        manifest::language::{self},
+        image::{ImageApiSelector, ImageOrientation},
+        manifest::{
+            get_canvas_size, get_image_target_fragment, get_media_fragment,
+            language::{self},
+        },
         one_or_many::OneTypeOrMany,
     },
-    presentation::model::{IsCanvas, IsImage, IsManifest, IsSequence},
+    presentation::model::{
+        CanvasHighlight, ExternalLink, ExternalLinkKind, IsCanvas, IsCollection,
+        IsCollectionMember, IsImage, IsManifest, IsSequence, PageBehavior, Rendering,
+    },
 };
 use serde::{Deserialize, Serialize};
 use std::{borrow::Cow, collections::HashMap, vec};
 
+/// Height of the thumbnails requested from their image services, e.g. of the manifests.
+const THUMBNAIL_SERVICE_HEIGHT: u32 = 256;
+
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub(crate) enum ManifestType {
     Manifest,
@@ -22,6 +32,19 @@ pub(crate) enum LabelText {
     Text(OneTypeOrMany<String>),
 }
 
+/// Get the values of the language which best matches the language preference.
+fn get_best_match<'a>(
+    map: &'a HashMap<String, Vec<String>>,
+    lang: &str,
+) -> Option<&'a Vec<String>> {
+    let mut keys = map.keys().map(|x| x.as_str()).collect::<Vec<_>>();
+
+    // Hash map doesn't guarantee the order, so sort to be consistent if more than one match.
+    keys.sort();
+
+    language::get_best_match(lang, keys.into_iter()).and_then(|x| map.get(x))
+}
+
 impl LabelText {
     fn get(&self, lang: &str) -> Vec<&str> {
         match self {
@@ -42,7 +65,7 @@ impl LabelText {
                 }
                 // If any of the values have a language associated with them,
                 // the client must display all of the values associated with the language that best matches the language preference.
-                else if let Some(v) = map.get(lang) {
+                else if let Some(v) = get_best_match(map, lang) {
                     v.iter().map(|x| x.as_str()).collect()
                 }
                 // If all of the values have a language associated with them, and none match the language preference,
@@ -113,6 +136,30 @@ pub(crate) struct Thumbnail {
     id: String,
     #[serde(rename = "type")]
     type_: DataType,
+    /// Image service of the thumbnail, to request it in the size needed.
+    service: Option<Vec<Service>>,
+}
+
+impl Thumbnail {
+    /// Get the URL of the thumbnail, from its image service if any.
+    fn get_url(&self) -> Cow<'_, str> {
+        match self.service.iter().flatten().next() {
+            Some(service) if !service.get_id().is_empty() => Cow::from(format!(
+                "{}/full/,{}/0/default.jpg",
+                service.get_id().trim_end_matches('/'),
+                THUMBNAIL_SERVICE_HEIGHT
+            )),
+            _ => Cow::from(&self.id),
+        }
+    }
+}
+
+/// Get the URL of the first of the thumbnails, empty if none.
+fn get_thumbnail_url(thumbnail: &Option<OneTypeOrMany<Thumbnail>>) -> Cow<'_, str> {
+    thumbnail
+        .as_ref()
+        .and_then(|x| x.iter().next())
+        .map_or(Cow::from(""), |x| x.get_url())
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -126,13 +173,112 @@ pub(crate) struct Provider {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub(crate) struct CanvasItem {
     id: String,
     #[serde(rename = "type")]
     type_: String,
     label: Option<LabelText>,
     thumbnail: Option<OneTypeOrMany<Thumbnail>>,
+    /// Annotation pages, empty if missing so that the other canvases can still be shown.
+    #[serde(default)]
     items: Vec<AnnotationPageItem>,
+    /// Canvas to show while the content is not available, e.g. a poster image of a video.
+    placeholder_canvas: Option<Box<CanvasItem>>,
+    /// Canvas to show along with the content, e.g. a score of an audio recording.
+    accompanying_canvas: Option<Box<CanvasItem>>,
+    /// Embedded pages of the non-painting annotations, e.g. the highlighted regions.
+    #[serde(default)]
+    annotations: Vec<CanvasAnnotationPage>,
+    /// Alternative representations of the canvas, e.g. a PDF.
+    rendering: Option<Vec<ExternalResource>>,
+    /// Behaviors of the canvas, e.g. "non-paged".
+    behavior: Option<OneTypeOrMany<String>>,
+    /// Length in seconds of a time-based canvas, e.g. of a video.
+    duration: Option<f64>,
+    /// Size of the canvas, as numbers of any kind so that a canvas with a fractional size can
+    /// still be shown.
+    width: Option<f64>,
+    height: Option<f64>,
+}
+
+/// Page of the non-painting annotations on the canvas.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct CanvasAnnotationPage {
+    /// Annotations, empty if the page is only referenced.
+    #[serde(default)]
+    items: Vec<CanvasAnnotation>,
+}
+
+/// Non-painting annotation on the canvas, e.g. a comment or a highlighted region.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct CanvasAnnotation {
+    motivation: Option<OneTypeOrMany<String>>,
+    label: Option<LabelText>,
+    body: Option<OneTypeOrMany<TextualBody>>,
+    /// Canvas with the region as the fragment, or a specific resource with a selector.
+    #[serde(default)]
+    target: serde_json::Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct TextualBody {
+    #[serde(default)]
+    value: String,
+    language: Option<String>,
+}
+
+impl CanvasAnnotation {
+    fn has_motivation(&self, motivation: &str) -> bool {
+        self.motivation
+            .as_ref()
+            .is_some_and(|x| x.iter().any(|x| x == motivation))
+    }
+
+    /// Get the media fragment of the target region, e.g. "xywh=10,20,100,50".
+    fn get_target_fragment(&self) -> Option<String> {
+        self.get_target_media_fragment()
+            .filter(|x| x.starts_with("xywh="))
+            .map(String::from)
+    }
+
+    /// Get the media fragment of the target with the interval, e.g. "xywh=10,20,100,50&t=5,10"
+    /// or "t=5,10".
+    fn get_target_timed_fragment(&self) -> Option<String> {
+        self.get_target_media_fragment()
+            .filter(|x| x.split('&').any(|x| x.starts_with("t=")))
+            .map(String::from)
+    }
+
+    /// Get the media fragment of the target, of the canvas or by the selector.
+    fn get_target_media_fragment(&self) -> Option<&str> {
+        get_media_fragment(&self.target)
+    }
+
+    /// Get the label, or the text of the body in the language which best matches the language
+    /// preference.
+    fn get_text(&self, lang: &str) -> String {
+        if let Some(label) = &self.label {
+            let label = label.get(lang).join(" ");
+
+            if !label.is_empty() {
+                return label;
+            }
+        }
+
+        let Some(body) = &self.body else {
+            return String::new();
+        };
+
+        let best =
+            language::get_best_match(lang, body.iter().filter_map(|x| x.language.as_deref()));
+
+        body.iter()
+            .find(|x| best.is_some() && x.language.as_deref() == best)
+            .or_else(|| body.iter().next())
+            .map(|x| x.value.clone())
+            .unwrap_or_default()
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -140,6 +286,7 @@ pub(crate) struct AnnotationPageItem {
     id: String,
     #[serde(rename = "type")]
     type_: String,
+    #[serde(default)]
     items: Vec<AnnotationItem>,
 }
 
@@ -148,15 +295,96 @@ pub(crate) struct AnnotationItem {
     id: String,
     #[serde(rename = "type")]
     type_: String,
-    body: AnnotationItemBody,
+    body: AnnotationBody,
+    /// CSS stylesheet of the style classes of the body, embedded as an object with a value or
+    /// linked.
+    stylesheet: Option<serde_json::Value>,
+    /// Canvas with the region the image is painted on as the fragment, or with a selector.
+    #[serde(default)]
+    target: serde_json::Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum AnnotationBody {
+    Choice(AnnotationChoiceBody),
+    Resource(AnnotationItemBody),
+}
+
+impl AnnotationBody {
+    /// Get the default resource, which is the first item for a choice.
+    fn get_default(&self) -> Option<&AnnotationItemBody> {
+        match self {
+            Self::Choice(v) => v.items.first(),
+            Self::Resource(v) => Some(v),
+        }
+    }
+
+    /// Get all the resources, which is one for a non-choice body.
+    fn get_choices(&self) -> &[AnnotationItemBody] {
+        match self {
+            Self::Choice(v) => &v.items,
+            Self::Resource(v) => std::slice::from_ref(v),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct AnnotationChoiceBody {
+    #[serde(rename = "type")]
+    type_: String,
+    items: Vec<AnnotationItemBody>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub(crate) struct AnnotationItemBody {
+    #[serde(default)]
     id: String,
     #[serde(rename = "type")]
     type_: String,
+    label: Option<LabelText>,
     service: Option<Vec<Service>>,
+    /// Source of a specific resource, e.g. the image painted with a rotation.
+    source: Option<ResourceSource>,
+    selector: Option<OneTypeOrMany<ImageApiSelector>>,
+    style_class: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum ResourceSource {
+    Resource(Box<AnnotationItemBody>),
+    Link(String),
+}
+
+impl AnnotationItemBody {
+    /// Get the resource with the content, which is the source of a specific resource.
+    fn get_resource(&self) -> &AnnotationItemBody {
+        match &self.source {
+            Some(ResourceSource::Resource(source)) => source,
+            _ => self,
+        }
+    }
+
+    /// Get the orientation by the image API selector, or the CSS transform of the style class
+    /// in the stylesheet.
+    fn get_orientation(&self, stylesheet: Option<&str>) -> Option<ImageOrientation> {
+        self.selector
+            .iter()
+            .flatten()
+            .find_map(|x| x.get_orientation())
+            .or_else(|| {
+                self.style_class
+                    .as_deref()
+                    .zip(stylesheet)
+                    .and_then(|(class, stylesheet)| {
+                        class
+                            .split_whitespace()
+                            .find_map(|x| ImageOrientation::try_from_css(stylesheet, x))
+                    })
+            })
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -166,15 +394,97 @@ pub(crate) struct Manifest {
     context: OneTypeOrMany<String>,
     #[serde(rename = "type")]
     manifest_type: ManifestType,
+    id: Option<String>,
     label: LabelText,
     summary: Option<OneTypeOrMany<LabelText>>,
+    metadata: Option<Vec<LabelValue>>,
     rights: Option<String>,
     required_statement: Option<LabelValue>,
     provider: Option<Vec<Provider>>,
     items: Vec<CanvasItem>,
+    /// Resources the manifest is part of, e.g. collections.
+    part_of: Option<Vec<PartOf>>,
+    homepage: Option<Vec<ExternalResource>>,
+    see_also: Option<Vec<ExternalResource>>,
+    /// Alternative representations of the whole manifest, e.g. a PDF.
+    rendering: Option<Vec<ExternalResource>>,
+    /// Thumbnail of the whole manifest, e.g. of the cover.
+    thumbnail: Option<OneTypeOrMany<Thumbnail>>,
+    /// Behaviors of the presentation, e.g. "paged".
+    behavior: Option<OneTypeOrMany<String>>,
+}
+
+/// External resource about the manifest, e.g. its homepage.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct ExternalResource {
+    id: String,
+    label: Option<LabelText>,
+    /// Media type of the resource, e.g. "application/pdf".
+    format: Option<String>,
+}
+
+impl ExternalResource {
+    /// Get the link of the kind to the resource.
+    fn to_link(&self, kind: ExternalLinkKind, language: &str) -> ExternalLink {
+        ExternalLink {
+            kind,
+            url: self.id.to_string(),
+            label: self
+                .label
+                .as_ref()
+                .map(|x| x.get(language).join(","))
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Get the resource as an alternative representation to download.
+    fn to_rendering(&self, language: &str) -> Rendering {
+        Rendering {
+            url: self.id.to_string(),
+            label: self
+                .label
+                .as_ref()
+                .map(|x| x.get(language).join(","))
+                .unwrap_or_default(),
+            format: self.format.clone().unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct PartOf {
+    id: String,
+    #[serde(rename = "type")]
+    type_: String,
+    label: Option<LabelText>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CollectionMember {
+    id: String,
+    #[serde(rename = "type")]
+    type_: ManifestType,
+    label: Option<LabelText>,
+    thumbnail: Option<OneTypeOrMany<Thumbnail>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Collection {
+    #[serde(rename = "@context")]
+    context: OneTypeOrMany<String>,
+    #[serde(rename = "type")]
+    pub(crate) type_: ManifestType,
+    label: LabelText,
+    items: Vec<CollectionMember>,
 }
 
 impl IsManifest for Manifest {
+    fn get_id(&self) -> Cow<'_, str> {
+        self.id.as_deref().map_or(Cow::from(""), Cow::from)
+    }
+
     fn get_title(&self, language: &str) -> Cow<'_, str> {
         Cow::from(self.label.get(language).join("\n"))
     }
@@ -224,6 +534,24 @@ impl IsManifest for Manifest {
         }
     }
 
+    fn get_metadata(&self, language: &str) -> Box<dyn Iterator<Item = Cow<'_, str>> + '_> {
+        match &self.metadata {
+            None => Box::new(Vec::new().into_iter()),
+            Some(v) => Box::new(
+                v.iter()
+                    .map(|x| {
+                        Cow::from(format!(
+                            "{}: {}",
+                            x.label.get(language).join(", "),
+                            x.value.get(language).join(", ")
+                        ))
+                    })
+                    .collect::<Vec<_>>()
+                    .into_iter(),
+            ),
+        }
+    }
+
     fn get_license(&self) -> Box<dyn Iterator<Item = Cow<'_, str>> + '_> {
         match &self.rights {
             None => Box::new(Vec::new().into_iter()),
@@ -251,6 +579,102 @@ impl IsManifest for Manifest {
     fn get_sequence(&self, _: usize) -> Result<&dyn IsSequence, IiifError> {
         Ok(self as &dyn IsSequence)
     }
+
+    fn get_external_links(&self, language: &str) -> Vec<ExternalLink> {
+        let homepage = self
+            .homepage
+            .iter()
+            .flatten()
+            .map(|x| x.to_link(ExternalLinkKind::Homepage, language));
+        let see_also = self
+            .see_also
+            .iter()
+            .flatten()
+            .map(|x| x.to_link(ExternalLinkKind::SeeAlso, language));
+
+        homepage.chain(see_also).collect()
+    }
+
+    fn get_renderings(&self, language: &str) -> Vec<Rendering> {
+        self.rendering
+            .iter()
+            .flatten()
+            .map(|x| x.to_rendering(language))
+            .collect()
+    }
+
+    fn get_thumbnail(&self) -> Cow<'_, str> {
+        get_thumbnail_url(&self.thumbnail)
+    }
+
+    fn is_paged(&self) -> bool {
+        self.behavior
+            .iter()
+            .flat_map(|x| x.iter())
+            .any(|x| x == "paged")
+    }
+
+    fn get_parent_collections(&self) -> Box<dyn Iterator<Item = &dyn IsCollectionMember> + '_> {
+        Box::new(
+            self.part_of
+                .iter()
+                .flatten()
+                .filter(|x| x.is_collection())
+                .map(|x| x as &dyn IsCollectionMember),
+        )
+    }
+}
+
+impl IsCollection for Collection {
+    fn get_title(&self, language: &str) -> Cow<'_, str> {
+        Cow::from(self.label.get(language).join("\n"))
+    }
+
+    fn get_members(&self) -> Box<dyn ExactSizeIterator<Item = &dyn IsCollectionMember> + '_> {
+        Box::new(self.items.iter().map(|x| x as &dyn IsCollectionMember))
+    }
+}
+
+impl IsCollectionMember for CollectionMember {
+    fn get_id(&self) -> Cow<'_, str> {
+        Cow::from(&self.id)
+    }
+
+    fn get_label(&self, language: &str) -> Box<dyn Iterator<Item = Cow<'_, str>> + '_> {
+        match &self.label {
+            None => Box::new(Vec::new().into_iter()),
+            Some(v) => Box::new(v.get(language).into_iter().map(Cow::from)),
+        }
+    }
+
+    fn is_collection(&self) -> bool {
+        self.type_ == ManifestType::Collection
+    }
+
+    fn get_thumbnail(&self) -> Cow<'_, str> {
+        get_thumbnail_url(&self.thumbnail)
+    }
+}
+
+impl IsCollectionMember for PartOf {
+    fn get_id(&self) -> Cow<'_, str> {
+        Cow::from(&self.id)
+    }
+
+    fn get_label(&self, language: &str) -> Box<dyn Iterator<Item = Cow<'_, str>> + '_> {
+        match &self.label {
+            None => Box::new(Vec::new().into_iter()),
+            Some(v) => Box::new(v.get(language).into_iter().map(Cow::from)),
+        }
+    }
+
+    fn is_collection(&self) -> bool {
+        self.type_ == "Collection"
+    }
+
+    fn get_thumbnail(&self) -> Cow<'_, str> {
+        Cow::from("")
+    }
 }
 
 impl IsSequence for Manifest {
@@ -274,6 +698,10 @@ impl IsSequence for Manifest {
 }
 
 impl IsCanvas for CanvasItem {
+    fn get_id(&self) -> Cow<'_, str> {
+        Cow::from(&self.id)
+    }
+
     fn get_label(&self, language: &str) -> Box<dyn Iterator<Item = Cow<'_, str>> + '_> {
         if let Some(label) = &self.label {
             Box::new(
@@ -308,7 +736,114 @@ impl IsCanvas for CanvasItem {
         }
     }
 
+    fn get_size(&self) -> Option<(u32, u32)> {
+        get_canvas_size(self.width, self.height)
+    }
+
     fn get_image(&self, index: usize) -> Result<&dyn IsImage, IiifError> {
+        self.get_annotation_item(index).map(|x| x as &dyn IsImage)
+    }
+
+    fn get_image_choices(
+        &self,
+        index: usize,
+    ) -> Result<Box<dyn ExactSizeIterator<Item = &dyn IsImage> + '_>, IiifError> {
+        let annotation_item = self.get_annotation_item(index)?;
+
+        Ok(Box::new(
+            annotation_item
+                .body
+                .get_choices()
+                .iter()
+                .map(|x| x as &dyn IsImage),
+        ))
+    }
+
+    fn get_image_target_fragment(&self, index: usize) -> Option<String> {
+        get_image_target_fragment(&self.get_annotation_item(index).ok()?.target)
+    }
+
+    fn get_image_orientation(&self, index: usize, choice_index: usize) -> ImageOrientation {
+        let Ok(annotation_item) = self.get_annotation_item(index) else {
+            return ImageOrientation::default();
+        };
+        let stylesheet = annotation_item
+            .stylesheet
+            .as_ref()
+            .and_then(|x| x["value"].as_str());
+
+        annotation_item
+            .body
+            .get_choices()
+            .get(choice_index)
+            .and_then(|x| x.get_orientation(stylesheet))
+            .unwrap_or_default()
+    }
+
+    fn get_placeholder_canvas(&self) -> Option<&dyn IsCanvas> {
+        self.placeholder_canvas
+            .as_deref()
+            .map(|x| x as &dyn IsCanvas)
+    }
+
+    fn get_accompanying_canvas(&self) -> Option<&dyn IsCanvas> {
+        self.accompanying_canvas
+            .as_deref()
+            .map(|x| x as &dyn IsCanvas)
+    }
+
+    fn get_highlights(&self, language: &str) -> Vec<CanvasHighlight> {
+        self.annotations
+            .iter()
+            .flat_map(|x| &x.items)
+            .filter(|x| x.has_motivation("highlighting"))
+            .filter_map(|x| {
+                Some(CanvasHighlight {
+                    label: x.get_text(language),
+                    fragment: x.get_target_fragment()?,
+                })
+            })
+            .collect()
+    }
+
+    fn get_duration(&self) -> Option<f64> {
+        self.duration.filter(|x| *x > 0.0)
+    }
+
+    fn get_timed_annotations(&self, language: &str) -> Vec<CanvasHighlight> {
+        self.annotations
+            .iter()
+            .flat_map(|x| &x.items)
+            .filter_map(|x| {
+                Some(CanvasHighlight {
+                    label: x.get_text(language),
+                    fragment: x.get_target_timed_fragment()?,
+                })
+            })
+            .collect()
+    }
+
+    fn get_renderings(&self, language: &str) -> Vec<Rendering> {
+        self.rendering
+            .iter()
+            .flatten()
+            .map(|x| x.to_rendering(language))
+            .collect()
+    }
+
+    fn get_page_behavior(&self) -> PageBehavior {
+        PageBehavior::from_hints(
+            self.behavior
+                .iter()
+                .flat_map(|x| x.iter())
+                .map(|x| x.as_str()),
+        )
+    }
+}
+
+impl CanvasItem {
+    /// Get the first annotation item in the annotation page at the index.
+    fn get_annotation_item(&self, index: usize) -> Result<&AnnotationItem, IiifError> {
         self.items
             .get(index)
             .map(|x| x.items.first())
@@ -316,7 +851,6 @@ impl IsCanvas for CanvasItem {
                 "missing annotation page at pos '{}'",
                 index
             )))?
-            .map(|x| x as &dyn IsImage)
             .ok_or(IiifError::IiifMissingInfo(format!(
                 "missing annotation item at pos '{}'",
                 index
@@ -326,7 +860,34 @@ impl IsCanvas for CanvasItem {
 
 impl IsImage for AnnotationItem {
     fn get_service(&self) -> Cow<'_, str> {
-        if let Some(services) = &self.body.service
+        self.body
+            .get_default()
+            .map_or(Cow::from(""), |x| x.get_service())
+    }
+
+    fn get_id(&self) -> Cow<'_, str> {
+        self.body
+            .get_default()
+            .map_or(Cow::from(""), |x| x.get_id())
+    }
+
+    fn get_type(&self) -> Cow<'_, str> {
+        self.body
+            .get_default()
+            .map_or(Cow::from(""), |x| x.get_type())
+    }
+
+    fn get_label(&self, language: &str) -> Box<dyn Iterator<Item = Cow<'_, str>> + '_> {
+        match self.body.get_default() {
+            Some(v) => v.get_label(language),
+            None => Box::new(std::iter::empty::<Cow<str>>()),
+        }
+    }
+}
+
+impl IsImage for AnnotationItemBody {
+    fn get_service(&self) -> Cow<'_, str> {
+        if let Some(services) = &self.get_resource().service
             && let Some(service) = services.first()
         {
             Cow::from(service.get_id())
@@ -336,11 +897,26 @@ impl IsImage for AnnotationItem {
     }
 
     fn get_id(&self) -> Cow<'_, str> {
-        Cow::from(&self.body.id)
+        Cow::from(&self.get_resource().id)
     }
 
     fn get_type(&self) -> Cow<'_, str> {
-        Cow::from(&self.body.type_)
+        Cow::from(&self.get_resource().type_)
+    }
+
+    fn get_label(&self, language: &str) -> Box<dyn Iterator<Item = Cow<'_, str>> + '_> {
+        if let Some(label) = self.label.as_ref().or(self.get_resource().label.as_ref()) {
+            Box::new(
+                label
+                    .get(language)
+                    .into_iter()
+                    .map(Cow::from)
+                    .collect::<Vec<_>>()
+                    .into_iter(),
+            )
+        } else {
+            Box::new(std::iter::empty::<Cow<str>>())
+        }
     }
 }
 
@@ -677,6 +1253,10 @@ mod tests {
 
         assert_eq!(presentation_info.label.get(language::EN).join(""), "Book 1");
         assert_eq!(presentation_info.manifest_type, ManifestType::Manifest);
+        assert_eq!(
+            presentation_info.get_id(),
+            "https://example.org/iiif/book1/manifest"
+        );
 
         assert_eq!(
             presentation_info.context.iter().collect::<Vec<_>>(),
@@ -699,6 +1279,18 @@ mod tests {
 
         assert_eq!(presentation_info.get_title(language::EN), "Book 1");
 
+        assert_eq!(
+            presentation_info
+                .get_metadata(language::EN)
+                .collect::<Vec<_>>(),
+            vec![
+                "Author: Anne Author",
+                "Published: Paris, circa 1400",
+                "Notes: Text of note 1, Text of note 2",
+                "Source: <span>From: <a href=\"https://example.org/db/1.html\">Some Collection</a></span>"
+            ]
+        );
+
         assert_eq!(
             presentation_info.get_logo().collect::<Vec<_>>(),
             vec!["https://example.org/service/inst1/full/max/0/default.png"]
@@ -765,21 +1357,662 @@ mod tests {
     }
 
     #[test]
-    fn test_label_text_plain_text() {
-        let label = LabelText::Text(OneTypeOrMany::<String>::One("text".to_string()));
-
-        assert_eq!(label.get(EN), vec!["text"]);
-        assert_eq!(label.get(DE), vec!["text"]);
-    }
-
-    #[test]
-    fn test_label_text_plain_texts() {
-        let label = LabelText::Text(OneTypeOrMany::<String>::Many(vec![
-            "text".to_string(),
-            "test".to_string(),
-        ]));
-
-        assert_eq!(label.get(EN), vec!["text", "test"]);
+    fn test_choice_json() {
+        let json = r#"{
+          "id": "https://example.org/iiif/painting/canvas/p1",
+          "type": "Canvas",
+          "height": 1271,
+          "width": 2000,
+          "items": [
+            {
+              "id": "https://example.org/iiif/painting/page/p1/1",
+              "type": "AnnotationPage",
+              "items": [
+                {
+                  "id": "https://example.org/iiif/painting/annotation/p0001-image",
+                  "type": "Annotation",
+                  "motivation": "painting",
+                  "body": {
+                    "type": "Choice",
+                    "items": [
+                      {
+                        "id": "https://example.org/iiif/painting/natural/full/max/0/default.jpg",
+                        "type": "Image",
+                        "label": { "en": [ "Natural Light" ] },
+                        "service": [
+                          {
+                            "id": "https://example.org/iiif/painting/natural",
+                            "type": "ImageService3",
+                            "profile": "level1"
+                          }
+                        ]
+                      },
+                      {
+                        "id": "https://example.org/iiif/painting/xray/full/max/0/default.jpg",
+                        "type": "Image",
+                        "label": { "en": [ "X-Ray" ] },
+                        "service": [
+                          {
+                            "id": "https://example.org/iiif/painting/xray",
+                            "type": "ImageService3",
+                            "profile": "level1"
+                          }
+                        ]
+                      }
+                    ]
+                  },
+                  "target": "https://example.org/iiif/painting/canvas/p1"
+                }
+              ]
+            }
+          ]
+        }"#;
+
+        let canvas: CanvasItem = serde_json::from_str(json).unwrap();
+
+        let image = canvas.get_image(0).unwrap();
+
+        assert_eq!(
+            image.get_service(),
+            "https://example.org/iiif/painting/natural"
+        );
+        assert_eq!(
+            image.get_label(language::EN).collect::<Vec<_>>(),
+            vec!["Natural Light"]
+        );
+
+        let choices = canvas.get_image_choices(0).unwrap().collect::<Vec<_>>();
+
+        assert_eq!(choices.len(), 2);
+        assert_eq!(
+            choices[1].get_service(),
+            "https://example.org/iiif/painting/xray"
+        );
+        assert_eq!(
+            choices[1].get_id(),
+            "https://example.org/iiif/painting/xray/full/max/0/default.jpg"
+        );
+        assert_eq!(
+            choices[1].get_label(language::EN).collect::<Vec<_>>(),
+            vec!["X-Ray"]
+        );
+        assert_eq!(choices[1].get_type(), "Image");
+
+        assert!(canvas.get_image_choices(1).is_err());
+    }
+
+    #[test]
+    fn test_rotated_image_json() {
+        let json = r#"{
+          "id": "https://example.org/iiif/rotation/canvas/p1",
+          "type": "Canvas",
+          "items": [
+            {
+              "id": "https://example.org/iiif/rotation/page/p1",
+              "type": "AnnotationPage",
+              "items": [
+                {
+                  "id": "https://example.org/iiif/rotation/annotation/p1",
+                  "type": "Annotation",
+                  "motivation": "painting",
+                  "stylesheet": {
+                    "type": "CssStylesheet",
+                    "value": ".rotated { transform-origin: center; transform: rotate(90deg); }"
+                  },
+                  "body": {
+                    "type": "SpecificResource",
+                    "styleClass": "rotated",
+                    "source": {
+                      "id": "https://example.org/iiif/rotation/full/max/0/default.jpg",
+                      "type": "Image",
+                      "service": [
+                        {
+                          "id": "https://example.org/iiif/rotation",
+                          "type": "ImageService3",
+                          "profile": "level1"
+                        }
+                      ]
+                    }
+                  },
+                  "target": "https://example.org/iiif/rotation/canvas/p1"
+                }
+              ]
+            },
+            {
+              "id": "https://example.org/iiif/rotation/page/p2",
+              "type": "AnnotationPage",
+              "items": [
+                {
+                  "id": "https://example.org/iiif/rotation/annotation/p2",
+                  "type": "Annotation",
+                  "motivation": "painting",
+                  "body": {
+                    "type": "SpecificResource",
+                    "source": {
+                      "id": "https://example.org/iiif/rotation/full/max/0/default.jpg",
+                      "type": "Image"
+                    },
+                    "selector": [{ "type": "ImageApiSelector", "rotation": "!180" }]
+                  },
+                  "target": "https://example.org/iiif/rotation/canvas/p1"
+                }
+              ]
+            }
+          ]
+        }"#;
+
+        let canvas: CanvasItem = serde_json::from_str(json).unwrap();
+        let image = canvas.get_image(0).unwrap();
+
+        assert_eq!(image.get_service(), "https://example.org/iiif/rotation");
+        assert_eq!(image.get_type(), "Image");
+        assert_eq!(
+            canvas.get_image_orientation(0, 0),
+            ImageOrientation {
+                rotation: 90,
+                mirrored: false
+            }
+        );
+        assert_eq!(
+            canvas.get_image_orientation(1, 0),
+            ImageOrientation {
+                rotation: 180,
+                mirrored: true
+            }
+        );
+        assert_eq!(
+            canvas.get_image_orientation(1, 1),
+            ImageOrientation::default()
+        );
+    }
+
+    #[test]
+    fn test_placeholder_canvas_json() {
+        let json = r#"{
+          "id": "https://example.org/iiif/video/canvas/p1",
+          "type": "Canvas",
+          "duration": 600,
+          "items": [
+            {
+              "id": "https://example.org/iiif/video/page/p1",
+              "type": "AnnotationPage",
+              "items": [
+                {
+                  "id": "https://example.org/iiif/video/annotation/p1",
+                  "type": "Annotation",
+                  "motivation": "painting",
+                  "body": {
+                    "id": "https://example.org/iiif/video/video.mp4",
+                    "type": "Video",
+                    "format": "video/mp4"
+                  },
+                  "target": "https://example.org/iiif/video/canvas/p1"
+                }
+              ]
+            }
+          ],
+          "placeholderCanvas": {
+            "id": "https://example.org/iiif/video/canvas/p1/placeholder",
+            "type": "Canvas",
+            "items": [
+              {
+                "id": "https://example.org/iiif/video/page/p2",
+                "type": "AnnotationPage",
+                "items": [
+                  {
+                    "id": "https://example.org/iiif/video/annotation/p2",
+                    "type": "Annotation",
+                    "motivation": "painting",
+                    "body": {
+                      "id": "https://example.org/iiif/poster/full/max/0/default.jpg",
+                      "type": "Image",
+                      "service": [
+                        {
+                          "id": "https://example.org/iiif/poster",
+                          "type": "ImageService3",
+                          "profile": "level1"
+                        }
+                      ]
+                    },
+                    "target": "https://example.org/iiif/video/canvas/p1/placeholder"
+                  }
+                ]
+              }
+            ]
+          }
+        }"#;
+
+        let canvas: CanvasItem = serde_json::from_str(json).unwrap();
+
+        assert_eq!(canvas.get_image(0).unwrap().get_type(), "Video");
+        assert!(canvas.get_accompanying_canvas().is_none());
+
+        let placeholder = canvas.get_placeholder_canvas().unwrap();
+
+        assert_eq!(
+            placeholder.get_id(),
+            "https://example.org/iiif/video/canvas/p1/placeholder"
+        );
+        assert_eq!(
+            placeholder.get_image(0).unwrap().get_service(),
+            "https://example.org/iiif/poster"
+        );
+    }
+
+    #[test]
+    fn test_highlighting_annotations_json() {
+        let json = r##"{
+          "id": "https://example.org/iiif/canvas/p1",
+          "type": "Canvas",
+          "items": [],
+          "annotations": [
+            {
+              "id": "https://example.org/iiif/page/a1",
+              "type": "AnnotationPage",
+              "items": [
+                {
+                  "id": "https://example.org/iiif/annotation/h1",
+                  "type": "Annotation",
+                  "motivation": "highlighting",
+                  "label": { "en": ["Signature"] },
+                  "target": "https://example.org/iiif/canvas/p1#xywh=10,20,300,40"
+                },
+                {
+                  "id": "https://example.org/iiif/annotation/h2",
+                  "type": "Annotation",
+                  "motivation": ["highlighting"],
+                  "body": [
+                    { "type": "TextualBody", "value": "Le sceau", "language": "fr" },
+                    { "type": "TextualBody", "value": "The seal", "language": "en" }
+                  ],
+                  "target": {
+                    "type": "SpecificResource",
+                    "source": "https://example.org/iiif/canvas/p1",
+                    "selector": { "type": "FragmentSelector", "value": "xywh=400,500,60,60" }
+                  }
+                },
+                {
+                  "id": "https://example.org/iiif/annotation/c1",
+                  "type": "Annotation",
+                  "motivation": "commenting",
+                  "body": { "type": "TextualBody", "value": "A comment" },
+                  "target": "https://example.org/iiif/canvas/p1#xywh=0,0,10,10"
+                }
+              ]
+            },
+            {
+              "id": "https://example.org/iiif/page/a2",
+              "type": "AnnotationPage"
+            }
+          ]
+        }"##;
+
+        let canvas: CanvasItem = serde_json::from_str(json).unwrap();
+
+        assert_eq!(
+            canvas.get_highlights("en"),
+            vec![
+                CanvasHighlight {
+                    label: "Signature".to_string(),
+                    fragment: "xywh=10,20,300,40".to_string(),
+                },
+                CanvasHighlight {
+                    label: "The seal".to_string(),
+                    fragment: "xywh=400,500,60,60".to_string(),
+                },
+            ]
+        );
+        assert_eq!(canvas.get_highlights("fr")[1].label, "Le sceau");
+        assert!(canvas.get_timed_annotations("en").is_empty());
+    }
+
+    #[test]
+    fn test_timed_annotations_json() {
+        let json = r##"{
+          "id": "https://example.org/iiif/video/canvas/p1",
+          "type": "Canvas",
+          "width": 640,
+          "height": 360,
+          "duration": 120.5,
+          "items": [],
+          "annotations": [
+            {
+              "id": "https://example.org/iiif/video/page/a1",
+              "type": "AnnotationPage",
+              "items": [
+                {
+                  "id": "https://example.org/iiif/video/annotation/a1",
+                  "type": "Annotation",
+                  "motivation": "commenting",
+                  "body": { "type": "TextualBody", "value": "The bell rings" },
+                  "target": "https://example.org/iiif/video/canvas/p1#xywh=10,20,100,50&t=5,10"
+                },
+                {
+                  "id": "https://example.org/iiif/video/annotation/a2",
+                  "type": "Annotation",
+                  "motivation": "supplementing",
+                  "body": { "type": "TextualBody", "value": "Applause" },
+                  "target": {
+                    "type": "SpecificResource",
+                    "source": "https://example.org/iiif/video/canvas/p1",
+                    "selector": { "type": "FragmentSelector", "value": "t=60" }
+                  }
+                },
+                {
+                  "id": "https://example.org/iiif/video/annotation/a3",
+                  "type": "Annotation",
+                  "motivation": "highlighting",
+                  "target": "https://example.org/iiif/video/canvas/p1#xywh=0,0,10,10"
+                }
+              ]
+            }
+          ]
+        }"##;
+
+        let canvas: CanvasItem = serde_json::from_str(json).unwrap();
+
+        assert_eq!(canvas.get_duration(), Some(120.5));
+        assert_eq!(canvas.get_size(), Some((640, 360)));
+        assert_eq!(
+            canvas.get_timed_annotations("en"),
+            vec![
+                CanvasHighlight {
+                    label: "The bell rings".to_string(),
+                    fragment: "xywh=10,20,100,50&t=5,10".to_string(),
+                },
+                CanvasHighlight {
+                    label: "Applause".to_string(),
+                    fragment: "t=60".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_part_of_and_links_json() {
+        let json = r#"{
+          "@context": "http://iiif.io/api/presentation/3/context.json",
+          "id": "https://example.org/iiif/1/manifest",
+          "type": "Manifest",
+          "label": { "en": [ "Example Manifest 1" ] },
+          "items": [],
+          "partOf": [
+            {
+              "id": "https://example.org/iiif/collection/top",
+              "type": "Collection",
+              "label": { "en": [ "Top collection" ] }
+            },
+            {
+              "id": "https://example.org/iiif/annotations/1",
+              "type": "AnnotationCollection"
+            }
+          ],
+          "homepage": [
+            {
+              "id": "https://example.org/info/book1/",
+              "type": "Text",
+              "label": { "en": [ "Home page for Book 1" ] },
+              "format": "text/html"
+            }
+          ],
+          "seeAlso": [
+            {
+              "id": "https://example.org/library/catalog/book1.xml",
+              "type": "Dataset",
+              "format": "text/xml",
+              "profile": "https://example.org/profiles/bibliographic"
+            }
+          ]
+        }"#;
+
+        let manifest: Manifest = serde_json::from_str(json).unwrap();
+        let parents = manifest.get_parent_collections().collect::<Vec<_>>();
+
+        assert_eq!(parents.len(), 1);
+        assert_eq!(
+            parents[0].get_id(),
+            "https://example.org/iiif/collection/top"
+        );
+        assert_eq!(
+            parents[0].get_label(EN).collect::<Vec<_>>(),
+            vec!["Top collection"]
+        );
+        assert_eq!(
+            manifest.get_external_links(EN),
+            vec![
+                ExternalLink {
+                    kind: ExternalLinkKind::Homepage,
+                    url: "https://example.org/info/book1/".to_string(),
+                    label: "Home page for Book 1".to_string(),
+                },
+                ExternalLink {
+                    kind: ExternalLinkKind::SeeAlso,
+                    url: "https://example.org/library/catalog/book1.xml".to_string(),
+                    label: "".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rendering_json() {
+        let json = r#"{
+          "@context": "http://iiif.io/api/presentation/3/context.json",
+          "id": "https://example.org/iiif/1/manifest",
+          "type": "Manifest",
+          "label": { "en": [ "Example Manifest 1" ] },
+          "items": [
+            {
+              "id": "https://example.org/iiif/book1/canvas/p1",
+              "type": "Canvas",
+              "rendering": [
+                {
+                  "id": "https://example.org/iiif/book1/p1.txt",
+                  "type": "Text",
+                  "format": "text/plain"
+                }
+              ]
+            }
+          ],
+          "rendering": [
+            {
+              "id": "https://example.org/iiif/book1.pdf",
+              "type": "Text",
+              "label": { "en": [ "Download as PDF" ] },
+              "format": "application/pdf"
+            }
+          ]
+        }"#;
+
+        let manifest: Manifest = serde_json::from_str(json).unwrap();
+
+        assert_eq!(
+            manifest.get_renderings(EN),
+            vec![Rendering {
+                url: "https://example.org/iiif/book1.pdf".to_string(),
+                label: "Download as PDF".to_string(),
+                format: "application/pdf".to_string(),
+            }]
+        );
+        assert_eq!(
+            manifest
+                .get_sequence(0)
+                .unwrap()
+                .get_canvas(0)
+                .unwrap()
+                .get_renderings(EN),
+            vec![Rendering {
+                url: "https://example.org/iiif/book1/p1.txt".to_string(),
+                label: "".to_string(),
+                format: "text/plain".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_behavior_json() {
+        let json = r#"{
+          "@context": "http://iiif.io/api/presentation/3/context.json",
+          "id": "https://example.org/iiif/book1/manifest",
+          "type": "Manifest",
+          "label": { "en": [ "Book 1" ] },
+          "behavior": [ "paged" ],
+          "items": [
+            {
+              "id": "https://example.org/iiif/book1/canvas/chart",
+              "type": "Canvas",
+              "behavior": [ "non-paged" ]
+            },
+            {
+              "id": "https://example.org/iiif/book1/canvas/p1",
+              "type": "Canvas"
+            },
+            {
+              "id": "https://example.org/iiif/book1/canvas/p2-3",
+              "type": "Canvas",
+              "behavior": [ "facing-pages" ]
+            }
+          ]
+        }"#;
+
+        let manifest: Manifest = serde_json::from_str(json).unwrap();
+        let behaviors = manifest
+            .get_canvases()
+            .map(|x| x.get_page_behavior())
+            .collect::<Vec<_>>();
+
+        assert!(manifest.is_paged());
+        assert_eq!(
+            behaviors,
+            vec![
+                PageBehavior::NonPaged,
+                PageBehavior::Paged,
+                PageBehavior::FacingPages
+            ]
+        );
+    }
+
+    #[test]
+    fn test_manifest_thumbnail_json() {
+        let json = r#"{
+          "@context": "http://iiif.io/api/presentation/3/context.json",
+          "id": "https://example.org/iiif/1/manifest",
+          "type": "Manifest",
+          "label": { "en": [ "Example Manifest 1" ] },
+          "items": [],
+          "thumbnail": [
+            {
+              "id": "https://example.org/iiif/1/thumb.jpg",
+              "type": "Image",
+              "service": [
+                {
+                  "id": "https://example.org/iiif/1/thumb/",
+                  "type": "ImageService3",
+                  "profile": "level1"
+                }
+              ]
+            }
+          ]
+        }"#;
+
+        let manifest: Manifest = serde_json::from_str(json).unwrap();
+
+        assert_eq!(
+            manifest.get_thumbnail(),
+            "https://example.org/iiif/1/thumb/full/,256/0/default.jpg"
+        );
+
+        let json = r#"{
+          "@context": "http://iiif.io/api/presentation/3/context.json",
+          "id": "https://example.org/iiif/collection/top",
+          "type": "Collection",
+          "label": { "en": [ "Collection for Example Organization" ] },
+          "items": [
+            {
+              "id": "https://example.org/iiif/1/manifest",
+              "type": "Manifest",
+              "thumbnail": [
+                { "id": "https://example.org/iiif/1/thumb.jpg", "type": "Image" }
+              ]
+            },
+            {
+              "id": "https://example.org/iiif/2/manifest",
+              "type": "Manifest"
+            }
+          ]
+        }"#;
+
+        let collection: Collection = serde_json::from_str(json).unwrap();
+        let members = collection.get_members().collect::<Vec<_>>();
+
+        assert_eq!(
+            members[0].get_thumbnail(),
+            "https://example.org/iiif/1/thumb.jpg"
+        );
+        assert_eq!(members[1].get_thumbnail(), "");
+    }
+
+    #[test]
+    fn test_collection_json() {
+        let json = r#"{
+          "@context": "http://iiif.io/api/presentation/3/context.json",
+          "id": "https://example.org/iiif/collection/top",
+          "type": "Collection",
+          "label": { "en": [ "Collection for Example Organization" ] },
+          "items": [
+            {
+              "id": "https://example.org/iiif/1/manifest",
+              "type": "Manifest",
+              "label": { "en": [ "Example Manifest 1" ] }
+            },
+            {
+              "id": "https://example.org/iiif/collection/sub",
+              "type": "Collection",
+              "label": { "en": [ "Sub-collection" ] }
+            }
+          ]
+        }"#;
+
+        let collection: Collection = serde_json::from_str(json).unwrap();
+
+        assert_eq!(collection.type_, ManifestType::Collection);
+        assert_eq!(
+            collection.get_title(language::EN),
+            "Collection for Example Organization"
+        );
+
+        let members = collection.get_members().collect::<Vec<_>>();
+
+        assert_eq!(members.len(), 2);
+        assert_eq!(members[0].get_id(), "https://example.org/iiif/1/manifest");
+        assert_eq!(
+            members[0].get_label(language::EN).collect::<Vec<_>>(),
+            vec!["Example Manifest 1"]
+        );
+        assert!(!members[0].is_collection());
+        assert_eq!(
+            members[1].get_id(),
+            "https://example.org/iiif/collection/sub"
+        );
+        assert!(members[1].is_collection());
+    }
+
+    #[test]
+    fn test_label_text_plain_text() {
+        let label = LabelText::Text(OneTypeOrMany::<String>::One("text".to_string()));
+
+        assert_eq!(label.get(EN), vec!["text"]);
+        assert_eq!(label.get(DE), vec!["text"]);
+    }
+
+    #[test]
+    fn test_label_text_plain_texts() {
+        let label = LabelText::Text(OneTypeOrMany::<String>::Many(vec![
+            "text".to_string(),
+            "test".to_string(),
+        ]));
+
+        assert_eq!(label.get(EN), vec!["text", "test"]);
         assert_eq!(label.get(DE), vec!["text", "test"]);
     }
 
@@ -819,4 +2052,18 @@ mod tests {
         assert_eq!(label.get(FR), vec!["none"]);
         assert_eq!(label.get(NONE), vec!["none"]);
     }
+
+    #[test]
+    fn test_label_text_map_with_language_preferences() {
+        let label = LabelText::Map(HashMap::from([
+            ("en".to_string(), vec!["text".to_string()]),
+            ("zh-Hant".to_string(), vec!["zh".to_string()]),
+            (DE.to_string(), vec!["de".to_string()]),
+        ]));
+
+        assert_eq!(label.get("zh"), vec!["zh"]);
+        assert_eq!(label.get("en-GB, fr"), vec!["text"]);
+        assert_eq!(label.get("fr, de, en"), vec!["de"]);
+        assert_eq!(label.get("zh-Hans, en"), vec!["text"]);
+    }
 }
@@ -1,3 +1,4 @@
+pub(crate) mod gesture;
 pub(crate) mod keyboard;
 pub(crate) mod mouse;
 pub(crate) mod touch;
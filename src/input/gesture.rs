@@ -0,0 +1,93 @@
+use crate::{
+    app::{app_settings::AppSettings, app_state::AppState},
+    camera::main_camera::{ApplyCameraState, CameraMode, Invalidate},
+    rendering::{tile::TileModState, tiled_image::TiledImage},
+};
+use bevy::{
+    ecs::{component::Component, resource::Resource},
+    input::gestures::PinchGesture,
+    prelude::{
+        Camera, Local, MessageReader, MessageWriter, Projection, Query, Res, ResMut, Single,
+        Transform, Vec3, Window, With,
+    },
+    window::{PrimaryWindow, RequestRedraw},
+};
+
+/// Get the change of the camera scale by the magnification of the pinch, which is positive to
+/// zoom in. Bounded as the trackpad may report a large magnification at once.
+fn get_pinch_zoom(magnification: f32) -> f32 {
+    (1.0 / (1.0 + magnification.max(-0.5))).clamp(0.5, 2.0)
+}
+
+/// Pinch gesture system for 2D and 3D to zoom about the cursor by the trackpad on desktop, as
+/// many laptops have no mouse wheel.
+///
+/// The pinch is reported as a gesture on macOS. Elsewhere, it is usually reported as the scroll
+/// of the trackpad in pixels, which is handled with the mouse wheel.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn pinch_gesture_system<
+    T: Component,
+    S: Resource + Clone + Default + ApplyCameraState,
+>(
+    camera_query: Single<(&mut Transform, &Camera, &mut Projection), With<T>>,
+    mut pinch_gesture_reader: MessageReader<PinchGesture>,
+    app_settings: Res<AppSettings>,
+    mut app_state: ResMut<AppState>,
+    mut initial_state: Local<S>,
+    mut current_state: ResMut<S>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    mut tile_mod_state: ResMut<TileModState>,
+    mut redraw_request_writer: MessageWriter<RequestRedraw>,
+    tiled_image: Query<&TiledImage>,
+) {
+    let magnification: f32 = pinch_gesture_reader.read().map(|x| x.0).sum();
+
+    if magnification == 0.0 {
+        return;
+    }
+
+    let (mut transform, camera, mut projection) = camera_query.into_inner();
+
+    let Some(viewport_centre) = camera.logical_viewport_rect().map(|x| x.center()) else {
+        return;
+    };
+
+    let mut invalidate = Invalidate::empty();
+
+    *initial_state = current_state.get_initial_state(&transform, &projection);
+
+    current_state.apply(
+        CameraMode::Zoom,
+        &initial_state,
+        window.cursor_position().unwrap_or(viewport_centre),
+        viewport_centre,
+        get_pinch_zoom(magnification),
+        Vec3::ZERO,
+        0.0,
+        &app_settings,
+        &mut app_state,
+        tiled_image,
+        &mut transform,
+        &mut projection,
+        &mut invalidate,
+    );
+
+    if !invalidate.is_empty() {
+        tile_mod_state.invalidate();
+        redraw_request_writer.write(RequestRedraw);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_pinch_zoom() {
+        assert_eq!(get_pinch_zoom(0.0), 1.0);
+        assert_eq!(get_pinch_zoom(0.25), 0.8);
+        assert_eq!(get_pinch_zoom(-0.5), 2.0);
+        assert_eq!(get_pinch_zoom(5.0), 0.5);
+        assert_eq!(get_pinch_zoom(-1.0), 2.0);
+    }
+}
@@ -6,11 +6,14 @@ use crate::{
 };
 use bevy::{
     prelude::{
-        ButtonInput, KeyCode, MessageWriter, Projection, Res, ResMut, Single, Transform, Vec3, With,
+        ButtonInput, KeyCode, MessageWriter, Projection, Res, ResMut, Single, Transform, Vec2,
+        Vec3, With,
     },
     window::RequestRedraw,
 };
 
+/// Pan the view while the arrow keys are held, which are not actions of the registry as they are
+/// continuous. The zoom by the keys is of the actions.
 pub(crate) fn keyboard_input_system(
     camera: Single<(&mut Transform, &mut Projection), With<MainCamera2d>>,
     mut app_state: ResMut<AppState>,
@@ -26,21 +29,39 @@ pub(crate) fn keyboard_input_system(
         return;
     };
     let mut direction = Vec3::new(0.0, 0.0, 0.0);
-    let mut scale = 1.0;
     let mut key_pressed = true;
 
-    if kb_input.pressed(KeyCode::ArrowUp) {
-        direction.y += 5.0;
-    } else if kb_input.pressed(KeyCode::ArrowDown) {
-        direction.y -= 5.0;
-    } else if kb_input.pressed(KeyCode::ArrowLeft) {
-        direction.x += 5.0;
-    } else if kb_input.pressed(KeyCode::ArrowRight) {
-        direction.x -= 5.0;
-    } else if kb_input.just_pressed(KeyCode::KeyZ) {
-        scale *= 0.9;
-    } else if kb_input.just_pressed(KeyCode::KeyX) {
-        scale *= 1.1;
+    // Slow down the panning with Shift for the fine framing.
+    let speed = if kb_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        app_settings.precision_settings.precision_multiplier
+    } else {
+        1.0
+    };
+
+    // Pan by exactly a tile per key press with Ctrl, or while the key is held otherwise.
+    let tile_step = kb_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    let step = if tile_step {
+        tiled_image.get_world_tile_size(app_state.level)
+            * app_settings.precision_settings.tile_step_multiplier
+    } else {
+        Vec2::splat(5.0 * speed)
+    };
+    let is_arrow_pressed = |key| {
+        if tile_step {
+            kb_input.just_pressed(key)
+        } else {
+            kb_input.pressed(key)
+        }
+    };
+
+    if is_arrow_pressed(KeyCode::ArrowUp) {
+        direction.y += step.y;
+    } else if is_arrow_pressed(KeyCode::ArrowDown) {
+        direction.y -= step.y;
+    } else if is_arrow_pressed(KeyCode::ArrowLeft) {
+        direction.x += step.x;
+    } else if is_arrow_pressed(KeyCode::ArrowRight) {
+        direction.x -= step.x;
     } else {
         key_pressed = false;
     }
@@ -48,14 +69,11 @@ pub(crate) fn keyboard_input_system(
     if key_pressed {
         transform.translation += direction;
 
-        let max_camera_zoom_scale = tiled_image.get_world_max_size_rect().size().max_element()
-            / app_settings.min_image_size;
-
-        orthogonal.scale = (orthogonal.scale * scale)
-            .max(app_settings.min_camera_zoom_scale)
-            .min(max_camera_zoom_scale);
-
-        app_state.level = tiled_image.get_level_at(orthogonal.scale);
+        app_state.level = tiled_image.get_level_with_hysteresis(
+            app_state.level,
+            orthogonal.scale,
+            app_state.scale_factor,
+        );
 
         tile_mod_state.invalidate();
         redraw_request_writer.write(RequestRedraw);
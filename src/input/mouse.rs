@@ -1,18 +1,92 @@
 use crate::{
     AppState,
-    app::app_settings::AppSettings,
-    camera::main_camera::{ApplyCameraState, CameraMode, Invalidate},
-    rendering::{tile::TileModState, tiled_image::TiledImage},
+    app::app_settings::{AppSettings, WheelSettings},
+    camera::main_camera::{
+        ApplyCameraState, CameraCommand3d, CameraMode, Invalidate, MainCamera3d,
+    },
+    rendering::{model_stage::ModelStage, tile::TileModState, tiled_image::TiledImage},
 };
 use bevy::{
-    input::mouse::MouseWheel,
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    picking::mesh_picking::ray_cast::{MeshRayCast, MeshRayCastSettings},
     prelude::{
-        ButtonInput, Camera, Component, Local, MessageReader, MessageWriter, MouseButton,
-        Projection, Query, Res, ResMut, Resource, Single, Time, Transform, Vec2, Window, With,
+        ButtonInput, Camera, Component, Entity, GlobalTransform, KeyCode, Local, MessageReader,
+        MessageWriter, MouseButton, Projection, Query, Res, ResMut, Resource, Single, Time,
+        Transform, Vec2, Window, With,
     },
     window::{CursorMoved, PrimaryWindow, RequestRedraw},
 };
 
+/// Max interval between the clicks of a double-click.
+const DOUBLE_CLICK_SECS: f32 = 0.3;
+
+/// Pixels of the scroll of a trackpad zooming as much as a step of the mouse wheel.
+const PIXELS_PER_WHEEL_STEP: f32 = 50.0;
+
+/// Zoom of the camera by a step of the wheel.
+pub(crate) const WHEEL_STEP_ZOOM: f32 = 0.1;
+
+/// Get the steps of the wheel zoom of the scroll, positive to zoom in, by the wheel settings.
+pub(crate) fn get_wheel_steps(
+    unit: MouseScrollUnit,
+    y: f32,
+    wheel_settings: &WheelSettings,
+) -> f32 {
+    let steps = match unit {
+        MouseScrollUnit::Line if wheel_settings.smooth => y,
+        MouseScrollUnit::Line if y != 0.0 => y.signum(),
+        MouseScrollUnit::Line => 0.0,
+        // A trackpad scrolls by the pixels in small increments, which are always smooth.
+        MouseScrollUnit::Pixel => y / PIXELS_PER_WHEEL_STEP,
+    } * wheel_settings.sensitivity;
+
+    if wheel_settings.invert { -steps } else { steps }
+}
+
+/// Focus on the point of the 3D model double-clicked.
+pub(crate) fn double_click_focus_system(
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera3d>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    time: Res<Time>,
+    mut last_click: Local<Option<f32>>,
+    mut mesh_ray_cast: MeshRayCast,
+    stage_query: Query<(), With<ModelStage>>,
+    mut camera_command_writer: MessageWriter<CameraCommand3d>,
+) {
+    let (camera, global_transform) = camera.into_inner();
+
+    if !camera.is_active || !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+
+    let now = time.elapsed_secs();
+
+    if last_click.is_none_or(|x| now - x > DOUBLE_CLICK_SECS) {
+        *last_click = Some(now);
+        return;
+    }
+
+    *last_click = None;
+
+    let Some(ray) = window
+        .cursor_position()
+        .and_then(|x| camera.viewport_to_world(global_transform, x).ok())
+    else {
+        return;
+    };
+
+    // Only the model, not the backdrop or the ground.
+    let filter = |entity| !stage_query.contains(entity);
+
+    if let Some((_, hit)) = mesh_ray_cast
+        .cast_ray(ray, &MeshRayCastSettings::default().with_filter(&filter))
+        .first()
+    {
+        camera_command_writer.write(CameraCommand3d::Focus(hit.point));
+    }
+}
+
 /// Mouse input system for 3D.
 #[allow(clippy::too_many_arguments)]
 pub(crate) fn mouse_input_system<T: Component, S: Resource + Clone + Default + ApplyCameraState>(
@@ -20,11 +94,12 @@ pub(crate) fn mouse_input_system<T: Component, S: Resource + Clone + Default + A
     mouse: Res<ButtonInput<MouseButton>>,
     mut evr_motion: MessageReader<CursorMoved>,
     mut evr_scroll: MessageReader<MouseWheel>,
+    kb_input: Res<ButtonInput<KeyCode>>,
     app_settings: Res<AppSettings>,
     mut app_state: ResMut<AppState>,
     mut initial_state: Local<S>,
     mut current_state: ResMut<S>,
-    window: Single<&Window, With<PrimaryWindow>>,
+    window: Single<(Entity, &Window), With<PrimaryWindow>>,
     mut tile_mod_state: ResMut<TileModState>,
     mut redraw_request_writer: MessageWriter<RequestRedraw>,
     time: Res<Time>,
@@ -41,6 +116,7 @@ pub(crate) fn mouse_input_system<T: Component, S: Resource + Clone + Default + A
     }
 
     let (mut transform, camera, mut projection) = camera_query.into_inner();
+    let (window_entity, window) = window.into_inner();
 
     let viewport_centre = camera
         .logical_viewport_rect()
@@ -48,18 +124,34 @@ pub(crate) fn mouse_input_system<T: Component, S: Resource + Clone + Default + A
         .center();
 
     // First, accumulate the total amount of
-    // mouse motion and scroll, from all pending events:
+    // mouse motion and scroll, from all pending events of the main window, as the other windows
+    // move their own cameras:
     let delta_motion: Vec2 = evr_motion
         .read()
+        .filter(|ev| ev.window == window_entity)
         .map(|ev| ev.delta.unwrap_or_default())
         .sum();
+    // Slow down the panning and zooming with Shift for the fine framing.
+    let precision = kb_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let speed = if precision {
+        app_settings.precision_settings.precision_multiplier
+    } else {
+        1.0
+    };
     let delta_wheel: f32 = evr_scroll
         .read()
-        .map(|ev| if ev.y != 0.0 { ev.y.signum() } else { 0.0 })
+        .filter(|ev| ev.window == window_entity)
+        .map(|ev| {
+            // Some systems scroll horizontally while Shift is held.
+            let y = if precision && ev.y == 0.0 { ev.x } else { ev.y };
+
+            get_wheel_steps(ev.unit, y, &app_settings.wheel_settings)
+        })
         .sum();
 
-    let delta_zoom = 1.0 - delta_wheel * 0.1;
-    let delta_move = delta_motion.extend(0.0);
+    // Bounded as the trackpad may scroll many steps at once.
+    let delta_zoom = (1.0 - delta_wheel * WHEEL_STEP_ZOOM * speed).clamp(0.5, 2.0);
+    let delta_move = (delta_motion * speed).extend(0.0);
 
     let mut invalidate = Invalidate::empty();
 
@@ -82,6 +174,7 @@ pub(crate) fn mouse_input_system<T: Component, S: Resource + Clone + Default + A
         viewport_centre,
         delta_zoom,
         delta_move,
+        0.0,
         &app_settings,
         &mut app_state,
         tiled_image,
@@ -98,3 +191,41 @@ pub(crate) fn mouse_input_system<T: Component, S: Resource + Clone + Default + A
         redraw_request_writer.write(RequestRedraw);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_wheel_steps() {
+        let wheel_settings = WheelSettings::default();
+
+        assert_eq!(
+            get_wheel_steps(MouseScrollUnit::Line, 3.0, &wheel_settings),
+            1.0
+        );
+        assert_eq!(
+            get_wheel_steps(MouseScrollUnit::Line, -0.5, &wheel_settings),
+            -1.0
+        );
+        assert_eq!(
+            get_wheel_steps(MouseScrollUnit::Pixel, 25.0, &wheel_settings),
+            0.5
+        );
+
+        let wheel_settings = WheelSettings {
+            sensitivity: 2.0,
+            invert: true,
+            smooth: true,
+        };
+
+        assert_eq!(
+            get_wheel_steps(MouseScrollUnit::Line, 0.5, &wheel_settings),
+            -1.0
+        );
+        assert_eq!(
+            get_wheel_steps(MouseScrollUnit::Line, 0.0, &wheel_settings),
+            0.0
+        );
+    }
+}
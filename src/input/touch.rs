@@ -27,6 +27,28 @@ fn compute_centre_and_distance_squared(touches: [&Touch; 2]) -> (Vec2, f32) {
     (centre, distance)
 }
 
+/// Compute the angle in radians twisted clockwise on the screen by the two fingers since they
+/// were pressed, 0 if either is no longer pressed.
+fn compute_twist_angle(touches: &Touches, initial_touches: [&Touch; 2]) -> f32 {
+    let Some(current_touches) = initial_touches
+        .map(|x| touches.get_pressed(x.id()))
+        .into_iter()
+        .collect::<Option<Vec<_>>>()
+    else {
+        return 0.0;
+    };
+
+    let initial = initial_touches[1].position() - initial_touches[0].position();
+    let current = current_touches[1].position() - current_touches[0].position();
+
+    if initial == Vec2::ZERO || current == Vec2::ZERO {
+        return 0.0;
+    }
+
+    // The y axis of the screen is downwards, so the positive angle is clockwise.
+    initial.angle_to(current)
+}
+
 /// Touch input system for 2D and 3D to collect and process the touch events.
 ///
 /// The function handles the logic to collect and process the touch events,
@@ -119,14 +141,17 @@ pub(crate) fn touch_input_system<T: Component, S: Resource + Clone + Default + A
 
         let delta_zoom = start_distance_squared / current_distance_squared.max(0.01);
         let delta_move = (current_centre - initial_centre).extend(0.0);
+        let delta_roll = compute_twist_angle(&touches, [&initial_touch_1, &initial_touch_2]);
 
+        // Drag to pan, pinch to zoom, or dolly in 3D, and twist to roll in 3D.
         current_state.apply(
-            CameraMode::Pan | CameraMode::Zoom,
+            CameraMode::Pan | CameraMode::Zoom | CameraMode::Roll,
             &initial_state,
             current_centre,
             viewport_centre,
             delta_zoom,
             delta_move,
+            delta_roll,
             &app_settings,
             &mut app_state,
             tiled_image,
@@ -151,6 +176,7 @@ pub(crate) fn touch_input_system<T: Component, S: Resource + Clone + Default + A
             viewport_centre,
             1.0,
             delta_move,
+            0.0,
             &app_settings,
             &mut app_state,
             tiled_image,
@@ -1,236 +1,361 @@
 use crate::app::app_settings::AppSettings;
 use crate::app::app_state::AppState;
-use crate::rendering::tile::{TileCache, TileModState, TilePruneState};
-use bevy::asset::AssetMetaCheck;
-use bevy::asset::io::web::WebAssetPlugin;
-use bevy::camera::visibility::RenderLayers;
+use crate::notification::UserNotification;
 use bevy::prelude::*;
-use bevy::render::RenderApp;
-use bevy::render::render_resource::BlendState;
-use bevy::winit::WinitSettings;
-use bevy_egui::input::{egui_wants_any_keyboard_input, egui_wants_any_pointer_input};
-use bevy_egui::{EguiGlobalSettings, EguiPlugin, EguiPrimaryContextPass, PrimaryEguiContext};
-use clap::Parser;
+use bevy::window::WindowResolution;
+use clap::{Parser, Subcommand};
 
 mod app;
 mod asset_loading;
 mod camera;
+mod capability_cache;
+mod capability_probe;
+#[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+mod clipboard_watch;
+#[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+mod contact_sheet;
+#[cfg(target_arch = "wasm32")]
+mod embed;
+#[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+mod export;
 mod fonts;
+mod host_stats;
+mod http_cache;
 mod iiif;
 mod input;
+mod load_trace;
 mod minimap;
+#[cfg(test)]
+mod mock_server;
+mod notification;
+mod plugin;
+mod prefetch;
 mod presentation;
+#[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+mod protocol_handler;
+mod redraw_governor;
 mod rendering;
+#[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+mod screenshot;
+#[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+mod stitch;
+mod tilesource;
+mod view_clipboard;
+mod watchdog;
 mod web;
+#[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+mod window_sync;
+#[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+mod window_title;
 
-/// User notification message.
+pub use plugin::{
+    CanvasChanged, CanvasLoaded, IiifViewerPlugin, LoadManifest, ManifestLoaded, RegionChanged,
+    TileLoadFailed, ViewportChanged, default_plugins,
+};
+
+/// Message to open a manifest in another window.
 #[derive(Message)]
-struct UserNotification(String);
+#[cfg_attr(any(target_arch = "wasm32", target_os = "android"), allow(dead_code))]
+struct OpenWindow {
+    manifest_url: String,
+    /// Follow the camera of this window.
+    follow: bool,
+}
 
-#[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
+#[derive(Parser, Resource, Debug, Clone)]
+#[command(version, about, long_about = None, args_conflicts_with_subcommands = true)]
 struct Args {
+    #[command(subcommand)]
+    #[cfg_attr(any(target_arch = "wasm32", target_os = "android"), allow(dead_code))]
+    command: Option<Command>,
+
     /// URL of the IIIF manfifest.
     #[arg(short, long)]
     manifest: Option<String>,
+
+    /// Link of the manifest to open, e.g. "iiif+https://example.org/manifest.json" passed by the protocol handler, or a manifest file.
+    #[arg(value_name = "LINK")]
+    #[cfg_attr(any(target_arch = "wasm32", target_os = "android"), allow(dead_code))]
+    link: Option<String>,
+
+    /// Prefix of the CORS proxy for the manifest, image info and tile URLs.
+    #[arg(long)]
+    proxy: Option<String>,
+
+    /// Export a local image as a level 0 IIIF tile pyramid with its info.json to the folder, and open it.
+    #[arg(long, num_args = 2, value_names = ["IMAGE", "OUT_DIR"])]
+    #[cfg_attr(any(target_arch = "wasm32", target_os = "android"), allow(dead_code))]
+    export_tiles: Option<Vec<std::path::PathBuf>>,
+
+    /// Export the manifest with the image info and the tiles of its canvases to the folder for viewing offline, and open it.
+    #[arg(long, num_args = 2, value_names = ["MANIFEST", "OUT_DIR"])]
+    #[cfg_attr(any(target_arch = "wasm32", target_os = "android"), allow(dead_code))]
+    export_offline: Option<Vec<String>>,
+
+    /// Canvases to export offline by their numbers from 1, e.g. "1-3,5". All of them if not given.
+    #[arg(long, value_name = "CANVASES", requires = "export_offline")]
+    #[cfg_attr(any(target_arch = "wasm32", target_os = "android"), allow(dead_code))]
+    export_canvases: Option<String>,
+
+    /// Number of the levels to export offline from the smallest. All of them if not given.
+    #[arg(long, value_name = "LEVELS", requires = "export_offline")]
+    #[cfg_attr(any(target_arch = "wasm32", target_os = "android"), allow(dead_code))]
+    export_levels: Option<usize>,
+
+    /// Number of the canvas to open from 1. The first one if not given.
+    #[arg(long, value_name = "CANVAS", value_parser = clap::value_parser!(u64).range(1..))]
+    canvas: Option<u64>,
+
+    /// Language preferences in order as BCP 47 tags separated by commas, e.g. "en-GB, en, fr".
+    #[arg(long)]
+    language: Option<String>,
+
+    /// Title of the window instead of the one of the manifest and the canvas shown.
+    #[arg(long)]
+    #[cfg_attr(any(target_arch = "wasm32", target_os = "android"), allow(dead_code))]
+    title: Option<String>,
+
+    /// Size of the window in pixels, e.g. "1280x720".
+    #[arg(long, value_name = "WIDTHxHEIGHT", value_parser = parse_window_size)]
+    window_size: Option<(u32, u32)>,
+
+    /// Write a PNG of the canvas at the fitted view to the file once its tiles are loaded, and exit.
+    #[arg(long, value_name = "PATH")]
+    #[cfg_attr(any(target_arch = "wasm32", target_os = "android"), allow(dead_code))]
+    screenshot: Option<std::path::PathBuf>,
+
+    /// WGSL file of the custom effect applied to the view, e.g. a false-color mapping. It is loaded again when changed.
+    ///
+    /// The file is the fragment shader of a 2D material, importing `bevy_sprite::mesh2d_vertex_output::VertexOutput`, with the bindings of the `#{MATERIAL_BIND_GROUP}` group: the `texture_2d<f32>` of the tiles drawn in the view at 0, its `sampler` at 1, and a `vec4<f32>` uniform with the size of the viewport in pixels in x and y at 2.
+    #[arg(long, value_name = "PATH")]
+    #[cfg_attr(any(target_arch = "wasm32", target_os = "android"), allow(dead_code))]
+    effect_shader: Option<std::path::PathBuf>,
+
+    /// IIIF content state of the view to open, either encoded in base64url, as JSON or a manifest URL.
+    #[arg(long, value_name = "CONTENT_STATE")]
+    content_state: Option<String>,
+}
+
+/// Commands run instead of opening the viewer.
+#[derive(Subcommand, Debug, Clone, PartialEq)]
+#[cfg_attr(any(target_arch = "wasm32", target_os = "android"), allow(dead_code))]
+enum Command {
+    /// Register the viewer as the handler of the iiif://, iiif+http:// and iiif+https:// links for the user.
+    InstallHandler,
+}
+
+/// Parse the window size from its width and height separated by "x", e.g. "1280x720".
+fn parse_window_size(value: &str) -> Result<(u32, u32), String> {
+    let size = value.split_once(['x', 'X']).and_then(|(width, height)| {
+        Some((width.trim().parse().ok()?, height.trim().parse().ok()?))
+    });
+
+    match size {
+        Some((width, height)) if width > 0 && height > 0 => Ok((width, height)),
+        _ => Err(format!("'{}' is not a window size like 1280x720", value)),
+    }
+}
+
+/// Get the parameter of the page URL in wasm, e.g. "proxy" for the CORS proxy prefix.
+#[cfg(target_arch = "wasm32")]
+fn get_page_param(name: &str) -> Option<String> {
+    let search = web_sys::window()?.location().search().ok()?;
+
+    web_sys::UrlSearchParams::new_with_str(&search)
+        .ok()?
+        .get(name)
 }
 
 // the `bevy_main` proc_macro generates the required boilerplate for Android
 #[bevy_main]
 pub fn main() {
-    let mut app = App::new();
+    let args = Args::parse();
+
+    #[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+    if args.command == Some(Command::InstallHandler) {
+        match protocol_handler::register() {
+            Ok(location) => println!("Registered the handler of the IIIF links in {}", location),
+            Err(e) => {
+                eprintln!("Failed to register the handler of the IIIF links. {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
 
-    app.add_message::<UserNotification>()
+    App::new()
         .add_plugins(
-            DefaultPlugins
-                // Meta data is not expected for IIIF.
-                .set(AssetPlugin {
-                    meta_check: AssetMetaCheck::Never,
-                    ..default()
-                })
+            default_plugins()
                 // Required for the pasting to egui (prevent_default_event_handling to false) in wasm.
                 .set(WindowPlugin {
                     primary_window: Some(Window {
                         // You may want this set to `true` if you need virtual keyboard work in mobile browsers.
                         prevent_default_event_handling: false,
                         fit_canvas_to_parent: true,
+                        resolution: args
+                            .window_size
+                            .map(WindowResolution::from)
+                            .unwrap_or_default(),
                         ..default()
                     }),
                     ..default()
-                })
-                // Understood the warning.
-                .set(WebAssetPlugin {
-                    silence_startup_warning: true,
-                })
-                // Use nearest for the tiling for now. Will probably need to use virtual texture for the linear interpolation.
-                .set(ImagePlugin::default_nearest()),
-        )
-        .add_plugins(EguiPlugin::default())
-        // Desktop mode to reduce CPU usage.
-        .insert_resource(WinitSettings::desktop_app())
-        .add_systems(
-            Startup,
-            (
-                setup,
-                minimap::setup,
-                presentation::ui::setup,
-                setup_initial_presentation,
-            )
-                .chain(),
-        )
-        .add_systems(
-            Update,
-            (
-                input::keyboard::keyboard_input_system.run_if(not(egui_wants_any_keyboard_input)),
-                input::mouse::mouse_input_system::<
-                    camera::main_camera::MainCamera2d,
-                    camera::pan_zoom_state_2d::PanZoomState2d,
-                >
-                    .run_if(not(egui_wants_any_pointer_input))
-                    .run_if(not(minimap::ui_has_mouse_input)),
-                input::mouse::mouse_input_system::<
-                    camera::main_camera::MainCamera3d,
-                    camera::pan_orbit_state_3d::PanOrbitState3d,
-                >
-                    .run_if(not(egui_wants_any_pointer_input)),
-                input::touch::touch_input_system::<
-                    camera::main_camera::MainCamera2d,
-                    camera::pan_zoom_state_2d::PanZoomState2d,
-                >,
-                input::touch::touch_input_system::<
-                    camera::main_camera::MainCamera3d,
-                    camera::pan_orbit_state_3d::PanOrbitState3d,
-                >,
-                minimap::mouse_input_system,
-                web::load_presentation_system,
-                web::load_canvas_system,
-            ),
+                }),
         )
-        .add_systems(
-            EguiPrimaryContextPass,
-            presentation::ui::presentation_ui_system,
-        )
-        .add_systems(
-            PostUpdate,
-            (camera::main_camera::translation_bounding_system),
-        )
-        .add_systems(
-            Last,
-            (
-                asset_loading::asset_event_system,
-                minimap::update_view_rect_system,
-                rendering::tile::prune_tiles_system.run_if(resource_changed::<TilePruneState>),
-                rendering::pipeline_checker::pipeline_refresh_system
-                    .run_if(resource_changed::<rendering::pipeline_checker::PipelinesModCount>),
-                rendering::tile::update_tiles_system.run_if(resource_changed::<TileModState>),
-            ),
-        )
-        .add_observer(presentation::manifest::on_remove_manifest)
-        .add_observer(rendering::tile::on_remove_tiled_image)
-        .add_observer(rendering::model_image::on_remove_model_image)
-        .add_observer(minimap::on_remove_tiled_image)
-        .add_observer(rendering::model_image::on_remove_model_loading)
-        .add_observer(rendering::tiled_image::on_add_tiled_image)
-        .add_observer(rendering::model_image::on_add_model_image)
-        .add_observer(minimap::on_add_tiled_image);
-
-    // In desktop mode, systems are not always run.
-    // We subscribe to the ExtractSchedule to check the status of the pipeline.
-    // and will refresh until all are ready.
-    // https://github.com/rparrett/bevy_pipelines_ready
-    app.sub_app_mut(RenderApp).add_systems(
-        ExtractSchedule,
-        rendering::pipeline_checker::check_pipelines_ready_system,
-    );
-
-    app.run();
+        .add_plugins(IiifViewerPlugin::default())
+        // Manifest, view and exports of the command line, applied once the viewer is set up.
+        .insert_resource(args)
+        .run();
 }
 
-/// Set up the camera.
-fn setup(mut commands: Commands, mut egui_global_settings: ResMut<EguiGlobalSettings>) -> Result {
-    // Disable the automatic creation of a primary context to set it up manually for the camera we need.
-    egui_global_settings.auto_create_primary_context = false;
-
-    // Main camera
-    commands.spawn((
-        camera::main_camera::MainCamera2d,
-        Camera2d,
-        Camera {
-            is_active: false,
-            ..default()
-        },
-    ));
+fn setup_initial_presentation(
+    mut commands: Commands,
+    mut app_state: ResMut<AppState>,
+    mut app_settings: ResMut<AppSettings>,
+    mut messages: MessageWriter<UserNotification>,
+    args: Option<Res<Args>>,
+) -> Result {
+    // The apps embedding the viewer load the manifests with the messages and keep their sessions.
+    let Some(args) = args.map(|x| x.clone()) else {
+        commands.insert_resource(app::session::SessionState::new(false));
+        commands.insert_resource(app::manifest_prefs::ManifestPrefsStore::new(false));
+        return Ok(());
+    };
 
-    commands.spawn((
-        camera::main_camera::MainCamera3d,
-        Camera3d::default(),
-        Camera {
-            is_active: false,
-            ..default()
-        },
-        Transform::from_xyz(0.0, 0.0, 10.0).looking_at(Vec3::new(0.0, 0.0, 0.0), Vec3::Y),
+    // The screenshots do not save their sessions, nor the preferences of the manifests.
+    commands.insert_resource(app::session::SessionState::new(args.screenshot.is_none()));
+    commands.insert_resource(app::manifest_prefs::ManifestPrefsStore::new(
+        args.screenshot.is_none(),
     ));
 
-    // Default ambient light.
-    commands.insert_resource(AmbientLight {
-        brightness: 2000.0,
-        ..default()
-    });
+    #[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+    if let Some(path) = args.screenshot.clone() {
+        commands.insert_resource(screenshot::ScreenshotRequest::new(path));
+    }
 
-    // Tile cache resource.
-    commands.insert_resource(TileCache::new());
-
-    // App state.
-    commands.insert_resource(AppState::default());
-
-    // Pipeline mod count.
-    commands.insert_resource(rendering::pipeline_checker::PipelinesModCount::default());
-
-    // Tile mod state.
-    commands.insert_resource(TileModState::new());
-
-    // App settings.
-    commands.insert_resource(AppSettings::default());
-
-    // Tile mod state.
-    commands.insert_resource(TilePruneState::new());
-
-    // Camera 3D pan orbit state.
-    commands.insert_resource(camera::pan_orbit_state_3d::PanOrbitState3d::default());
-
-    // Camera 2D pan zoom state.
-    commands.insert_resource(camera::pan_zoom_state_2d::PanZoomState2d::default());
-
-    // Egui camera.
-    commands.spawn((
-        // The `PrimaryEguiContext` component requires everything needed to render a primary context.
-        PrimaryEguiContext,
-        Camera2d,
-        // Setting RenderLayers to none makes sure we won't render anything apart from the UI.
-        RenderLayers::none(),
-        Camera {
-            order: 1,
-            output_mode: bevy::camera::CameraOutputMode::Write {
-                blend_state: Some(BlendState::ALPHA_BLENDING),
-                clear_color: ClearColorConfig::None,
-            },
-            clear_color: ClearColorConfig::Custom(Color::NONE),
-            ..default()
-        },
-    ));
+    if let Some(language) = args.language {
+        app_settings.language = language;
+    }
 
-    Ok(())
-}
+    #[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+    if let Some(title) = &args.title {
+        commands.insert_resource(window_title::WindowTitle::new(Some(title.to_string())));
+    }
 
-fn setup_initial_presentation(mut app_state: ResMut<AppState>) -> Result {
-    let args = Args::parse();
+    #[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+    if let Some(path) = &args.effect_shader {
+        app_settings.effect_shader_path = path.display().to_string();
+    }
+
+    // The canvas is numbered from 1 in the command line.
+    app_state.target_canvas_index = args.canvas.and_then(|x| usize::try_from(x - 1).ok());
+
+    // The page URL parameter takes precedence in wasm.
+    #[cfg(target_arch = "wasm32")]
+    let proxy = get_page_param("proxy").or(args.proxy);
+    #[cfg(not(target_arch = "wasm32"))]
+    let proxy = args.proxy;
+
+    if let Some(proxy) = proxy {
+        app_settings.proxy_prefix = proxy;
+    }
+
+    // Export the tiles of the local image and open them instead.
+    #[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+    if let Some([image_path, out_dir]) = args.export_tiles.as_deref() {
+        match export::export_tiles(image_path, out_dir) {
+            Ok(manifest_url) => {
+                info!("Exported the tiles of {:?} to {:?}", image_path, out_dir);
+                web::load_presentation(&mut app_state, &app_settings, &manifest_url);
+                return Ok(());
+            }
+            Err(e) => {
+                messages.write(UserNotification::Error(format!(
+                    "Failed to export the tiles of '{}'.\n{}",
+                    image_path.display(),
+                    e
+                )));
+            }
+        }
+    }
+
+    // Export the manifest for viewing offline in the background, which opens the local copy
+    // instead once exported.
+    #[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+    if let Some([manifest_url, out_dir]) = args.export_offline.as_deref() {
+        match args
+            .export_canvases
+            .as_deref()
+            .map(export::parse_canvas_numbers)
+            .transpose()
+        {
+            Ok(canvas_indices) => {
+                commands.insert_resource(export::OfflineExport::start(
+                    manifest_url,
+                    std::path::PathBuf::from(out_dir),
+                    canvas_indices,
+                    args.export_levels,
+                    &app_settings.proxy_prefix,
+                ));
+                return Ok(());
+            }
+            Err(e) => {
+                messages.write(UserNotification::Error(format!(
+                    "Failed to export '{}' for offline.\n{}",
+                    manifest_url, e
+                )));
+            }
+        }
+    }
+
+    // Open the shared view instead. The page URL parameter takes precedence in wasm.
+    #[cfg(target_arch = "wasm32")]
+    let content_state =
+        get_page_param(presentation::content_state::CONTENT_STATE_PARAM).or(args.content_state);
+    #[cfg(not(target_arch = "wasm32"))]
+    let content_state = args.content_state;
+
+    if let Some(content_state) = content_state {
+        match presentation::content_state::ContentState::try_from_param(&content_state) {
+            Ok(content_state) => {
+                content_state.load(&mut app_state, &app_settings);
+                return Ok(());
+            }
+            Err(e) => {
+                messages.write(UserNotification::Error(format!(
+                    "Unable to open the content state.\n{}",
+                    e
+                )));
+            }
+        }
+    }
+
+    // The link passed by the protocol handler or the file association if no manifest is given.
+    #[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+    let manifest = match (args.manifest, &args.link) {
+        (None, Some(link)) => {
+            let manifest = protocol_handler::get_manifest_url(link);
+
+            if manifest.is_none() {
+                messages.write(UserNotification::Error(format!(
+                    "Unable to open the link '{}'.",
+                    link
+                )));
+            }
+
+            manifest
+        }
+        (manifest, _) => manifest,
+    };
+    #[cfg(any(target_arch = "wasm32", target_os = "android"))]
+    let manifest = args.manifest;
 
     // Try to read the manifest URL from the command line.
-    if let Some(presentation_url) = args.manifest {
-        web::load_presentation(&mut app_state, &presentation_url);
+    if let Some(presentation_url) = manifest {
+        web::load_presentation(&mut app_state, &app_settings, &presentation_url);
     } else {
         web::load_presentation(
             &mut app_state,
+            &app_settings,
             "https://iiif.harvardartmuseums.org/manifests/object/21116",
         );
         // web::load_presentation(
@@ -241,3 +366,17 @@ fn setup_initial_presentation(mut app_state: ResMut<AppState>) -> Result {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_window_size() {
+        assert_eq!(parse_window_size("1280x720"), Ok((1280, 720)));
+        assert_eq!(parse_window_size("800 X 600"), Ok((800, 600)));
+        assert!(parse_window_size("1280").is_err());
+        assert!(parse_window_size("0x720").is_err());
+        assert!(parse_window_size("widexhigh").is_err());
+    }
+}
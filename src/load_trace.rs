@@ -0,0 +1,315 @@
+use crate::{
+    app::app_state::{AppState, DownloadState},
+    notification::UserNotification,
+    rendering::tile::TileLoading,
+    web,
+};
+use bevy::prelude::{Added, MessageWriter, Query, Res, ResMut, Resource, Time, With, info};
+use serde_json::json;
+use std::{collections::VecDeque, sync::Mutex};
+
+/// Maximum number of the recent spans kept in the trace.
+const MAX_TRACE_SPANS: usize = 200;
+
+/// Stage of loading a canvas, from its manifest to the tiles in view.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum LoadStage {
+    Manifest,
+    ImageInfo,
+    /// Tiles loading until all of them in view are loaded.
+    Tiles,
+}
+
+impl LoadStage {
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Self::Manifest => "manifest",
+            Self::ImageInfo => "image info",
+            Self::Tiles => "tiles",
+        }
+    }
+
+    /// Get the lane of the stage in the exported trace.
+    fn lane(&self) -> u32 {
+        match self {
+            Self::Manifest => 1,
+            Self::ImageInfo => 2,
+            Self::Tiles => 3,
+        }
+    }
+}
+
+/// Timing of a stage of loading.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TraceSpan {
+    pub(crate) stage: LoadStage,
+    /// URL requested. Empty for the tiles.
+    pub(crate) url: String,
+    /// Seconds since the app started.
+    pub(crate) start_secs: f64,
+    pub(crate) duration_secs: f64,
+    pub(crate) ok: bool,
+    /// E.g. the number of the tiles or the error.
+    pub(crate) detail: String,
+}
+
+impl TraceSpan {
+    /// Get the host of the URL, e.g. "iiif.example.org", to compare the servers.
+    pub(crate) fn get_host(&self) -> &str {
+        web::get_host(&self.url)
+    }
+}
+
+/// Action requested from the diagnostics window.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum TraceRequest {
+    /// Export the trace to the file.
+    Export,
+    Clear,
+}
+
+/// Timings of the recent stages of loading, to find the slow servers.
+#[derive(Resource)]
+pub(crate) struct LoadTrace {
+    /// Recent spans, the oldest first.
+    pub(crate) spans: VecDeque<TraceSpan>,
+    /// URL and start of the manifest download in progress.
+    manifest: Option<(String, f64)>,
+    /// URL and start of the image info download in progress.
+    image_info: Option<(String, f64)>,
+    /// Start of the tiles loading, and the number of them.
+    tiles: Option<(f64, usize)>,
+    /// Path of the exported trace.
+    pub(crate) file_path: String,
+    pub(crate) request: Option<TraceRequest>,
+}
+
+impl Default for LoadTrace {
+    fn default() -> Self {
+        Self {
+            spans: VecDeque::new(),
+            manifest: None,
+            image_info: None,
+            tiles: None,
+            file_path: "trace.json".to_string(),
+            request: None,
+        }
+    }
+}
+
+impl LoadTrace {
+    /// Keep the span, and log it with its fields.
+    fn push(&mut self, span: TraceSpan) {
+        info!(
+            stage = span.stage.name(),
+            url = %span.url,
+            duration_ms = (span.duration_secs * 1000.0).round(),
+            ok = span.ok,
+            "Loaded the {} in {:.0} ms. {}",
+            span.stage.name(),
+            span.duration_secs * 1000.0,
+            span.detail
+        );
+
+        if self.spans.len() >= MAX_TRACE_SPANS {
+            self.spans.pop_front();
+        }
+        self.spans.push_back(span);
+    }
+
+    /// Get the trace in the Chrome trace event format, e.g. for Perfetto.
+    pub(crate) fn to_json(&self) -> String {
+        let events = self
+            .spans
+            .iter()
+            .map(|x| {
+                json!({
+                    "name": x.stage.name(),
+                    "cat": "load",
+                    "ph": "X",
+                    "ts": (x.start_secs * 1.0e6).round(),
+                    "dur": (x.duration_secs * 1.0e6).round(),
+                    "pid": 1,
+                    "tid": x.stage.lane(),
+                    "args": {
+                        "url": x.url,
+                        "host": x.get_host(),
+                        "ok": x.ok,
+                        "detail": x.detail
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        serde_json::to_string_pretty(&json!({
+            "traceEvents": events,
+            "displayTimeUnit": "ms"
+        }))
+        .unwrap_or_default()
+    }
+}
+
+/// Follow the download from its start to its end, and get its span when it ends.
+fn trace_download<T>(
+    stage: LoadStage,
+    current: &mut Option<(String, f64)>,
+    download_state: &Mutex<DownloadState<T>>,
+    now: f64,
+) -> Option<TraceSpan> {
+    let download_state = download_state.lock().ok()?;
+    let (ok, detail) = match &*download_state {
+        DownloadState::InProgress { url, started, .. } => {
+            if current.as_ref().is_none_or(|(x, _)| x != url) {
+                *current = Some((url.to_string(), now - started.elapsed().as_secs_f64()));
+            }
+            return None;
+        }
+        DownloadState::Done { .. } => (true, "".to_string()),
+        DownloadState::Error { msg, .. } => (false, msg.to_string()),
+        DownloadState::None => {
+            // Cancelled.
+            *current = None;
+            return None;
+        }
+    };
+    let (url, start_secs) = current.take()?;
+
+    Some(TraceSpan {
+        stage,
+        url,
+        start_secs,
+        duration_secs: now - start_secs,
+        ok,
+        detail,
+    })
+}
+
+/// Time the downloads of the manifest and the image info, and the tiles loading afterwards.
+///
+/// Run before the downloads are handled, which resets them.
+pub(crate) fn load_trace_system(
+    mut load_trace: ResMut<LoadTrace>,
+    app_state: Res<AppState>,
+    loading_tiles: Query<(), With<TileLoading>>,
+    added_tiles: Query<(), Added<TileLoading>>,
+    time: Res<Time>,
+    mut messages: MessageWriter<UserNotification>,
+) {
+    let now = time.elapsed_secs_f64();
+    let load_trace = load_trace.as_mut();
+
+    if let Some(span) = trace_download(
+        LoadStage::Manifest,
+        &mut load_trace.manifest,
+        &app_state.manifest_json_download_state,
+        now,
+    ) {
+        load_trace.push(span);
+    }
+
+    if let Some(span) = trace_download(
+        LoadStage::ImageInfo,
+        &mut load_trace.image_info,
+        &app_state.image_json_download_state,
+        now,
+    ) {
+        load_trace.push(span);
+    }
+
+    let num_added = added_tiles.iter().count();
+
+    match load_trace.tiles.as_mut() {
+        Some((_, num_tiles)) if !loading_tiles.is_empty() => *num_tiles += num_added,
+        Some((start_secs, num_tiles)) => {
+            let span = TraceSpan {
+                stage: LoadStage::Tiles,
+                url: "".to_string(),
+                start_secs: *start_secs,
+                duration_secs: now - *start_secs,
+                ok: true,
+                detail: format!("{} tiles", num_tiles),
+            };
+
+            load_trace.tiles = None;
+            load_trace.push(span);
+        }
+        None if !loading_tiles.is_empty() => load_trace.tiles = Some((now, num_added)),
+        None => {}
+    }
+
+    match load_trace.request.take() {
+        Some(TraceRequest::Export) => {
+            let file_path = load_trace.file_path.trim().to_string();
+
+            match std::fs::write(&file_path, load_trace.to_json()) {
+                Ok(_) => info!("Exported the load trace to {}", file_path),
+                Err(e) => {
+                    messages.write(UserNotification::Error(format!(
+                        "Failed to export the load trace to '{}'.\n{}",
+                        file_path, e
+                    )));
+                }
+            }
+        }
+        Some(TraceRequest::Clear) => load_trace.spans.clear(),
+        None => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::platform::time::Instant;
+
+    #[test]
+    fn test_trace_download() {
+        let download_state = Mutex::new(DownloadState::InProgress {
+            url: "https://iiif.example.org/manifest.json".to_string(),
+            started: Instant::now(),
+            info: (),
+        });
+        let mut current = None;
+
+        assert_eq!(
+            trace_download(LoadStage::Manifest, &mut current, &download_state, 1.0),
+            None
+        );
+
+        *download_state.lock().unwrap() = DownloadState::Error {
+            url: "https://iiif.example.org/manifest.json".to_string(),
+            msg: "404 Not Found".to_string(),
+        };
+
+        let span = trace_download(LoadStage::Manifest, &mut current, &download_state, 3.0)
+            .expect("should end the span");
+
+        assert!(!span.ok);
+        assert_eq!(span.detail, "404 Not Found");
+        assert_eq!(span.get_host(), "iiif.example.org");
+        assert!(span.duration_secs >= 2.0);
+        assert_eq!(current, None);
+    }
+
+    #[test]
+    fn test_to_json() {
+        let mut load_trace = LoadTrace::default();
+
+        load_trace.spans.push_back(TraceSpan {
+            stage: LoadStage::ImageInfo,
+            url: "https://iiif.example.org/iiif/1/info.json".to_string(),
+            start_secs: 1.5,
+            duration_secs: 0.25,
+            ok: true,
+            detail: "".to_string(),
+        });
+
+        let trace = serde_json::from_str::<serde_json::Value>(&load_trace.to_json())
+            .expect("should be JSON");
+        let event = &trace["traceEvents"][0];
+
+        assert_eq!(event["name"], "image info");
+        assert_eq!(event["ts"], 1.5e6);
+        assert_eq!(event["dur"], 2.5e5);
+        assert_eq!(event["args"]["host"], "iiif.example.org");
+    }
+}
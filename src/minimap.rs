@@ -1,5 +1,7 @@
 use crate::{
-    camera::camera_ext, camera::main_camera::MainCamera2d, rendering::tile::TileModState,
+    camera::camera_ext,
+    camera::main_camera::MainCamera2d,
+    rendering::tile::{TileCache, TileModState},
     rendering::tiled_image::TiledImage,
 };
 use bevy::{
@@ -10,10 +12,11 @@ use bevy::{
         Add, AlignSelf, AssetServer, BackgroundColor, BorderColor, Button, Camera, Changed, Color,
         Commands, Component, Display, Entity, GlobalTransform, ImageNode, Interaction,
         JustifyContent, MessageWriter, Node, On, PositionType, Query, Rect, Remove, Res, ResMut,
-        Result, Single, SpawnRelated, Transform, UiRect, Val, Vec2, With, children, default, info,
+        Result, Rot2, Single, SpawnRelated, Transform, UiRect, UiScale, UiTransform, Val, Vec2,
+        With, children, default, info,
     },
     ui::RelativeCursorPosition,
-    window::RequestRedraw,
+    window::{PrimaryWindow, RequestRedraw, Window},
 };
 
 #[derive(Component)]
@@ -22,8 +25,15 @@ pub(crate) struct MinimapContainer;
 #[derive(Component)]
 pub(crate) struct MinimapViewRect;
 
+/// Frame of the minimap around the thumbnail, styled in the theme.
 #[derive(Component)]
-pub(crate) struct MinimapImage;
+pub(crate) struct MinimapFrame;
+
+#[derive(Component, Default)]
+pub(crate) struct MinimapImage {
+    /// Size of the thumbnail requested in physical pixels, or 0 if none is requested yet.
+    thumbnail_pixels: u32,
+}
 
 const BORDER_SIZE: f32 = 2.0;
 const MINIMAP_SIZE: f32 = 200.0;
@@ -40,6 +50,7 @@ pub(crate) fn setup(mut commands: Commands) {
     };
 
     let thumbnail_container = (
+        MinimapFrame,
         BackgroundColor(Color::srgba(0.2, 0.2, 0.2, MINIMAP_ALPHA)),
         BorderColor::all(Color::srgba(0.5, 0.5, 0.5, 1.0)),
         Node {
@@ -52,7 +63,7 @@ pub(crate) fn setup(mut commands: Commands) {
     );
 
     let thumbnail_image = (
-        MinimapImage,
+        MinimapImage::default(),
         Button,
         RelativeCursorPosition::default(),
         Node {
@@ -113,35 +124,76 @@ pub(crate) fn on_remove_tiled_image(
 /// Triggered when tiled image is added to update the minimap.
 pub(crate) fn on_add_tiled_image(
     add: On<Add, TiledImage>,
-    minimap_image_query: Single<(&mut ImageNode, &mut Node), With<MinimapImage>>,
-    tiled_image: Single<&TiledImage>,
-    asset_server: Res<AssetServer>,
+    mut minimap_image: Single<&mut MinimapImage>,
     mut commands: Commands,
     minimap_container_query: Single<Entity, With<MinimapContainer>>,
 ) {
     info!("Tiled image added (minimap). {:?}", add.entity);
 
-    let (thumbnail_url, thumbnail_size) = tiled_image.get_image_thumbnail(256);
-    let (thumbnail_scale, offset) =
-        get_thumbnail_scale_and_offset(Rect::from_corners(Vec2::ZERO, thumbnail_size));
-    let thumbnail_rect = Rect::from_corners(
-        Vec2::ZERO + offset,
-        thumbnail_size * thumbnail_scale + offset,
-    );
+    // Request the thumbnail of the new image.
+    minimap_image.thumbnail_pixels = 0;
 
     let minimap_container_entity = minimap_container_query.into_inner();
 
     commands
         .entity(minimap_container_entity)
         .insert((Visibility::Visible,));
+}
 
-    let (mut minimap_image, mut minimap_node) = minimap_image_query.into_inner();
+/// Get the size of the thumbnail in physical pixels at the scale of the window and the UI.
+fn get_thumbnail_pixels(scale_factor: f32, ui_scale: f32) -> u32 {
+    (THUMBNAIL_SIZE * scale_factor * ui_scale).round() as u32
+}
 
-    minimap_image.image = asset_server.load(thumbnail_url);
+/// Request the thumbnail of the size of the minimap in physical pixels, for the image added and
+/// when the scale of the window or the UI is changed.
+pub(crate) fn thumbnail_system(
+    window: Single<&Window, With<PrimaryWindow>>,
+    ui_scale: Res<UiScale>,
+    minimap_image_query: Single<(
+        &mut MinimapImage,
+        &mut ImageNode,
+        &mut Node,
+        &mut UiTransform,
+    )>,
+    tiled_image: Single<&TiledImage>,
+    asset_server: Res<AssetServer>,
+    mut tile_cache: ResMut<TileCache>,
+) {
+    let thumbnail_pixels = get_thumbnail_pixels(window.scale_factor(), ui_scale.0);
+    let (mut minimap_image, mut image_node, mut minimap_node, mut minimap_transform) =
+        minimap_image_query.into_inner();
+
+    if minimap_image.thumbnail_pixels == thumbnail_pixels {
+        return;
+    }
+
+    info!("Minimap thumbnail of {} pixels", thumbnail_pixels);
+    minimap_image.thumbnail_pixels = thumbnail_pixels;
+
+    let (thumbnail_url, _) = tiled_image.get_image_thumbnail(thumbnail_pixels);
+    let orientation = tiled_image.get_orientation();
+    let canvas_size = tiled_image.get_image_max_size_rect().size();
+    let (thumbnail_scale, offset) =
+        get_thumbnail_scale_and_offset(Rect::from_corners(Vec2::ZERO, canvas_size));
+    // The thumbnail is of the source, stretched to the canvas and rotated around its centre to
+    // the canvas.
+    let thumbnail_rect = Rect::from_center_size(
+        (canvas_size * thumbnail_scale / 2.0) + offset,
+        orientation.to_canvas_size(canvas_size) * thumbnail_scale,
+    );
+
+    // The thumbnail may be the same image as a tile at the lowest level.
+    image_node.image = tile_cache.load_image(&asset_server, thumbnail_url);
     minimap_node.left = Val::Px(thumbnail_rect.min.x);
     minimap_node.top = Val::Px(thumbnail_rect.min.y);
     minimap_node.width = Val::Px(thumbnail_rect.width());
     minimap_node.height = Val::Px(thumbnail_rect.height());
+    *minimap_transform = UiTransform {
+        scale: Vec2::new(if orientation.mirrored { -1.0 } else { 1.0 }, 1.0),
+        rotation: Rot2::degrees(orientation.rotation as f32),
+        ..default()
+    };
 }
 
 fn get_thumbnail_scale_and_offset(image_size: Rect) -> (f32, Vec2) {
@@ -172,8 +224,9 @@ pub(crate) fn update_view_rect_system(
 
     // Convert the viewport in world space to image viewport in image space.
     let image_max_size = tiled_image.get_image_max_size_rect();
-    let image_pos_min = tiled_image.world_to_image(world_pos_min);
-    let image_pos_max = tiled_image.world_to_image(world_pos_max);
+    // From the top left of the image, which may be painted on a region of the canvas.
+    let image_pos_min = tiled_image.world_to_image(world_pos_min) - image_max_size.min;
+    let image_pos_max = tiled_image.world_to_image(world_pos_max) - image_max_size.min;
 
     // Scale to the thumbnail size and add into the offset.
     let (scale, offset) = get_thumbnail_scale_and_offset(image_max_size);
@@ -221,8 +274,13 @@ pub(crate) fn mouse_input_system(
         let Some(cursor) = cursor.normalized else {
             return;
         };
-        let image_pos =
-            tiled_image.get_image_max_size_rect().max * Vec2::new(cursor.x + 0.5, cursor.y + 0.5);
+        // The cursor is relative to the thumbnail of the source before its rotation.
+        let image_rect = tiled_image.get_image_max_size_rect();
+        let image_pos = image_rect.min
+            + image_rect.size()
+                * tiled_image
+                    .get_orientation()
+                    .to_canvas(Vec2::new(cursor.x + 0.5, cursor.y + 0.5), Vec2::ONE);
 
         let world_pos = tiled_image.image_to_world(image_pos);
 
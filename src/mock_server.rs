@@ -0,0 +1,299 @@
+use image::{DynamicImage, ImageFormat, Rgb, RgbImage};
+use std::{
+    io::{BufRead, BufReader, Cursor, Write},
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+/// Size of the full size images served.
+pub(crate) const IMAGE_WIDTH: u32 = 2048;
+pub(crate) const IMAGE_HEIGHT: u32 = 1536;
+
+/// Size of the tiles in the image info.
+pub(crate) const TILE_SIZE: u32 = 512;
+
+/// Identifiers of the images of the canvases of the manifest, in order.
+pub(crate) const IMAGE_IDS: [&str; 2] = ["page1", "page2"];
+
+/// Local IIIF server of a manifest with a canvas for each image, serving the image info of the
+/// images and their tiles generated at any region and size, to test the viewer without the
+/// network.
+pub(crate) struct MockIiifServer {
+    base_url: String,
+    /// Paths requested, in order.
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockIiifServer {
+    /// Start the server on a free port of the local host. It stops with the tests.
+    pub(crate) fn start() -> Self {
+        let listener =
+            TcpListener::bind("127.0.0.1:0").expect("should be able to bind the mock server");
+        let base_url = format!(
+            "http://{}",
+            listener
+                .local_addr()
+                .expect("should have the address of the mock server")
+        );
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        {
+            let base_url = base_url.clone();
+            let requests = Arc::clone(&requests);
+
+            thread::spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    let base_url = base_url.clone();
+                    let requests = Arc::clone(&requests);
+
+                    thread::spawn(move || handle_connection(stream, &base_url, &requests));
+                }
+            });
+        }
+
+        Self { base_url, requests }
+    }
+
+    pub(crate) fn get_manifest_url(&self) -> String {
+        format!("{}/manifest.json", self.base_url)
+    }
+
+    /// Get the endpoint of the image service of the image.
+    pub(crate) fn get_image_endpoint(&self, image_id: &str) -> String {
+        get_image_endpoint(&self.base_url, image_id)
+    }
+
+    /// Get the tiles requested of the image by their regions and sizes, e.g. "0,0,512,512/512,".
+    pub(crate) fn get_tile_requests(&self, image_id: &str) -> Vec<String> {
+        let prefix = format!("/iiif/{}/", image_id);
+
+        self.requests
+            .lock()
+            .expect("should be able to lock the requests of the mock server")
+            .iter()
+            .filter_map(|x| x.strip_prefix(&prefix))
+            .filter(|x| *x != "info.json")
+            .filter_map(|x| x.rsplitn(3, '/').nth(2))
+            .map(|x| x.to_string())
+            .collect()
+    }
+}
+
+fn get_image_endpoint(base_url: &str, image_id: &str) -> String {
+    format!("{}/iiif/{}", base_url, image_id)
+}
+
+/// Get the IIIF manifest with a canvas for each image.
+fn get_manifest_json(base_url: &str) -> String {
+    let canvases = IMAGE_IDS
+        .iter()
+        .map(|image_id| {
+            let canvas_id = format!("{}/canvas/{}", base_url, image_id);
+            let endpoint = get_image_endpoint(base_url, image_id);
+
+            serde_json::json!({
+                "id": canvas_id,
+                "type": "Canvas",
+                "width": IMAGE_WIDTH,
+                "height": IMAGE_HEIGHT,
+                "items": [{
+                    "id": format!("{}/page", canvas_id),
+                    "type": "AnnotationPage",
+                    "items": [{
+                        "id": format!("{}/annotation", canvas_id),
+                        "type": "Annotation",
+                        "motivation": "painting",
+                        "body": {
+                            "id": format!("{}/full/max/0/default.jpg", endpoint),
+                            "type": "Image",
+                            "format": "image/jpeg",
+                            "width": IMAGE_WIDTH,
+                            "height": IMAGE_HEIGHT,
+                            "service": [{
+                                "id": endpoint,
+                                "type": "ImageService2",
+                                "profile": "http://iiif.io/api/image/2/level2.json"
+                            }]
+                        },
+                        "target": canvas_id
+                    }]
+                }]
+            })
+        })
+        .collect::<Vec<_>>();
+
+    serde_json::json!({
+        "@context": "http://iiif.io/api/presentation/3/context.json",
+        "id": format!("{}/manifest.json", base_url),
+        "type": "Manifest",
+        "label": { "en": [ "Mock manifest" ] },
+        "items": canvases
+    })
+    .to_string()
+}
+
+/// Get the image info of the image, with the tiles at three levels.
+fn get_image_info_json(base_url: &str, image_id: &str) -> String {
+    serde_json::json!({
+        "@context": "http://iiif.io/api/image/2/context.json",
+        "@id": get_image_endpoint(base_url, image_id),
+        "protocol": "http://iiif.io/api/image",
+        "width": IMAGE_WIDTH,
+        "height": IMAGE_HEIGHT,
+        "tiles": [{ "width": TILE_SIZE, "scaleFactors": [1, 2, 4] }],
+        "profile": [ "http://iiif.io/api/image/2/level2.json" ]
+    })
+    .to_string()
+}
+
+/// Get the size of the image requested by the IIIF image API, e.g. "!256,256" for the thumbnail.
+fn get_image_size(region: &str, size: &str) -> Option<(u32, u32)> {
+    let (region_width, region_height) = match region {
+        "full" => (IMAGE_WIDTH, IMAGE_HEIGHT),
+        _ => {
+            let values = region
+                .split(',')
+                .map(|x| x.parse::<u32>().ok())
+                .collect::<Option<Vec<_>>>()?;
+            let [x, y, width, height] = values[..] else {
+                return None;
+            };
+
+            (
+                width.min(IMAGE_WIDTH.checked_sub(x)?),
+                height.min(IMAGE_HEIGHT.checked_sub(y)?),
+            )
+        }
+    };
+
+    if region_width == 0 || region_height == 0 {
+        return None;
+    }
+
+    let scale = |value: u32, from: u32, to: u32| (value as u64 * to as u64 / from as u64) as u32;
+
+    let size = match size.trim_start_matches('!').split_once(',') {
+        _ if matches!(size, "full" | "max") => (region_width, region_height),
+        Some((width, "")) => {
+            let width = width.parse().ok()?;
+
+            (width, scale(width, region_width, region_height))
+        }
+        Some(("", height)) => {
+            let height = height.parse().ok()?;
+
+            (scale(height, region_height, region_width), height)
+        }
+        // Fit in the size if it starts with "!".
+        Some((width, height)) if size.starts_with('!') => {
+            let (width, height) = (width.parse::<u32>().ok()?, height.parse::<u32>().ok()?);
+
+            if width as u64 * region_height as u64 <= height as u64 * region_width as u64 {
+                (width, scale(width, region_width, region_height))
+            } else {
+                (scale(height, region_height, region_width), height)
+            }
+        }
+        Some((width, height)) => (width.parse().ok()?, height.parse().ok()?),
+        None => return None,
+    };
+
+    Some((size.0.max(1), size.1.max(1)))
+}
+
+/// Get the content type and the body of the image info or the tile at the path of the image,
+/// e.g. "page1/0,0,512,512/512,/0/default.jpg".
+fn get_image_response(base_url: &str, path: &str) -> Option<(&'static str, Vec<u8>)> {
+    let parts = path.split('/').collect::<Vec<_>>();
+
+    match parts[..] {
+        [image_id, "info.json"] if IMAGE_IDS.contains(&image_id) => Some((
+            "application/json",
+            get_image_info_json(base_url, image_id).into_bytes(),
+        )),
+        [image_id, region, size, "0", "default.jpg"] if IMAGE_IDS.contains(&image_id) => {
+            let (width, height) = get_image_size(region, size)?;
+            // A color of each image to tell them apart.
+            let shade = IMAGE_IDS.iter().position(|x| *x == image_id)? as u8 * 100;
+            let image = RgbImage::from_pixel(width, height, Rgb([shade, 128, 255 - shade]));
+            let mut bytes = Vec::new();
+
+            DynamicImage::ImageRgb8(image)
+                .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Jpeg)
+                .ok()?;
+
+            Some(("image/jpeg", bytes))
+        }
+        _ => None,
+    }
+}
+
+/// Reply to the request of the connection, which is closed after it.
+fn handle_connection(mut stream: TcpStream, base_url: &str, requests: &Mutex<Vec<String>>) {
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+
+    // Skip the headers.
+    let mut header = String::new();
+
+    while reader.read_line(&mut header).is_ok_and(|x| x > 2) {
+        header.clear();
+    }
+
+    let path = request_line
+        .split_whitespace()
+        .nth(1)
+        .unwrap_or_default()
+        .to_string();
+    let response = match path.as_str() {
+        "/manifest.json" => Some(("application/json", get_manifest_json(base_url).into_bytes())),
+        _ => path
+            .strip_prefix("/iiif/")
+            .and_then(|x| get_image_response(base_url, x)),
+    };
+
+    requests
+        .lock()
+        .expect("should be able to lock the requests of the mock server")
+        .push(path);
+
+    let (status, content_type, body) = match response {
+        Some((content_type, body)) => ("200 OK", content_type, body),
+        None => ("404 Not Found", "text/plain", b"Not Found".to_vec()),
+    };
+    let header = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\
+        Access-Control-Allow-Origin: *\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    );
+
+    let _ = stream
+        .write_all(header.as_bytes())
+        .and_then(|_| stream.write_all(&body));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_image_size() {
+        assert_eq!(get_image_size("full", "max"), Some((2048, 1536)));
+        assert_eq!(get_image_size("0,0,1024,1024", "512,512"), Some((512, 512)));
+        assert_eq!(
+            get_image_size("1024,1024,1024,1024", "512,"),
+            Some((512, 256))
+        );
+        assert_eq!(get_image_size("full", ",384"), Some((512, 384)));
+        assert_eq!(get_image_size("full", "!256,256"), Some((256, 192)));
+        assert_eq!(get_image_size("4096,0,512,512", "512,"), None);
+        assert_eq!(get_image_size("full", "pct:50"), None);
+    }
+}
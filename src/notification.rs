@@ -0,0 +1,278 @@
+use crate::iiif::IiifError;
+use bevy::prelude::Message;
+
+/// User notification message.
+#[derive(Message, Debug, Clone, PartialEq)]
+pub(crate) enum UserNotification {
+    /// The request failed or the server responded with an error.
+    Network { url: String, msg: String },
+    /// The content from the server is not valid, with the JSON pointer to where it fails if known.
+    Parse {
+        url: String,
+        pointer: Option<String>,
+        msg: String,
+    },
+    /// The content is valid but not supported, e.g. the API version.
+    Unsupported { url: String, msg: String },
+    /// Other errors of the browser.
+    Error(String),
+    /// Information about the browser, e.g. of a limit of the graphics, not an error.
+    Info(String),
+}
+
+impl UserNotification {
+    /// Create the notification for the error of processing the JSON from the URL.
+    pub(crate) fn from_iiif_error(url: &str, json: &str, error: &IiifError) -> Self {
+        match error {
+            IiifError::Deserialization(e) => Self::Parse {
+                url: url.to_string(),
+                pointer: Some(json_pointer_at(json, e.line(), e.column())),
+                msg: e.to_string(),
+            },
+            IiifError::IiifUnsupportedError(msg) => Self::Unsupported {
+                url: url.to_string(),
+                msg: msg.to_string(),
+            },
+            e => Self::Parse {
+                url: url.to_string(),
+                pointer: None,
+                msg: e.to_string(),
+            },
+        }
+    }
+
+    /// Get the one-line summary.
+    pub(crate) fn summary(&self) -> String {
+        match self {
+            Self::Network { url, .. } => format!("Failed to load '{}'.", url),
+            Self::Parse { url, .. } => format!("Invalid content from '{}'.", url),
+            Self::Unsupported { url, .. } => format!("Unsupported content from '{}'.", url),
+            Self::Error(msg) | Self::Info(msg) => msg.to_string(),
+        }
+    }
+
+    /// Get the hint of what is at fault.
+    pub(crate) fn hint(&self) -> Option<&'static str> {
+        match self {
+            Self::Network { .. } => Some(if cfg!(target_arch = "wasm32") {
+                "The server may be down, or may not allow the access from this site (CORS)."
+            } else {
+                "The server may be down, or the URL may be wrong."
+            }),
+            Self::Parse { .. } => {
+                Some("The server responded, but the manifest or the image info is not valid IIIF.")
+            }
+            Self::Unsupported { .. } => {
+                Some("The content may be valid, but it is not supported by the browser.")
+            }
+            Self::Error(_) | Self::Info(_) => None,
+        }
+    }
+
+    /// Get the technical details.
+    pub(crate) fn details(&self) -> String {
+        match self {
+            Self::Network { msg, .. } | Self::Unsupported { msg, .. } => msg.to_string(),
+            Self::Parse {
+                pointer: Some(pointer),
+                msg,
+                ..
+            } => format!("{}\nat '{}'", msg, pointer),
+            Self::Parse { msg, .. } => msg.to_string(),
+            Self::Error(_) | Self::Info(_) => "".to_string(),
+        }
+    }
+}
+
+/// A JSON container being scanned.
+enum JsonFrame {
+    Object { key: Option<String>, in_key: bool },
+    Array { index: usize },
+}
+
+/// Get the JSON pointer (RFC 6901) to the value at the line and column (both 1-based) reported
+/// by serde_json, e.g. "/sequences/0/canvases/3".
+fn json_pointer_at(json: &str, line: usize, column: usize) -> String {
+    let line_start: usize = json
+        .split_inclusive('\n')
+        .take(line.saturating_sub(1))
+        .map(|x| x.len())
+        .sum();
+    let offset = line_start + column.saturating_sub(1);
+
+    let mut frames = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut string = String::new();
+    // Missing fields are reported at the end of the object, so point to the object instead.
+    let mut at_end = false;
+
+    for (index, c) in json.char_indices() {
+        if index >= offset {
+            at_end = c == '}' || c == ']';
+            break;
+        }
+
+        if in_string {
+            if escaped {
+                escaped = false;
+                string.push(c);
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+
+                if let Some(JsonFrame::Object { key, in_key: true }) = frames.last_mut() {
+                    *key = Some(std::mem::take(&mut string));
+                }
+            } else {
+                string.push(c);
+            }
+            continue;
+        }
+
+        match c {
+            '"' => {
+                in_string = true;
+                string.clear();
+            }
+            '{' => frames.push(JsonFrame::Object {
+                key: None,
+                in_key: true,
+            }),
+            '[' => frames.push(JsonFrame::Array { index: 0 }),
+            '}' | ']' => {
+                frames.pop();
+            }
+            ':' => {
+                if let Some(JsonFrame::Object { in_key, .. }) = frames.last_mut() {
+                    *in_key = false;
+                }
+            }
+            ',' => match frames.last_mut() {
+                Some(JsonFrame::Object { key, in_key }) => {
+                    *key = None;
+                    *in_key = true;
+                }
+                Some(JsonFrame::Array { index }) => *index += 1,
+                None => {}
+            },
+            _ => {}
+        }
+    }
+
+    if at_end {
+        frames.pop();
+        frames.push(JsonFrame::Object {
+            key: None,
+            in_key: true,
+        });
+    }
+
+    let mut pointer = String::new();
+
+    for frame in frames {
+        match frame {
+            JsonFrame::Object { key: Some(key), .. } => {
+                pointer.push('/');
+                pointer.push_str(&key.replace('~', "~0").replace('/', "~1"));
+            }
+            JsonFrame::Object { key: None, .. } => break,
+            JsonFrame::Array { index } => {
+                pointer.push('/');
+                pointer.push_str(&index.to_string());
+            }
+        }
+    }
+
+    pointer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Get the 1-based line and column of the text in the JSON.
+    fn position_of(json: &str, text: &str) -> (usize, usize) {
+        let offset = json.find(text).expect("should have the text");
+        let line_start = json[..offset].rfind('\n').map_or(0, |x| x + 1);
+
+        (
+            json[..offset].matches('\n').count() + 1,
+            offset - line_start + 1,
+        )
+    }
+
+    #[test]
+    fn test_json_pointer_at() {
+        let json = r#"{
+  "label": "Book 1",
+  "sequences": [
+    {
+      "canvases": [
+        { "label": "p. 1", "images": [] },
+        { "label": 2, "images": [] }
+      ]
+    }
+  ],
+  "a/b": { "c~d": [true, false] }
+}"#;
+
+        let (line, column) = position_of(json, "\"Book 1\"");
+        assert_eq!(json_pointer_at(json, line, column), "/label");
+
+        let (line, column) = position_of(json, "2,");
+        assert_eq!(
+            json_pointer_at(json, line, column),
+            "/sequences/0/canvases/1/label"
+        );
+
+        let (line, column) = position_of(json, "] }");
+        assert_eq!(
+            json_pointer_at(json, line, column),
+            "/sequences/0/canvases/0/images"
+        );
+
+        let (line, column) = position_of(json, "false");
+        assert_eq!(json_pointer_at(json, line, column), "/a~1b/c~0d/1");
+
+        // Missing fields are reported at the end of the object.
+        let (line, column) = position_of(json, "}\n      ]");
+        assert_eq!(
+            json_pointer_at(json, line, column),
+            "/sequences/0/canvases/1"
+        );
+
+        assert_eq!(json_pointer_at(json, 1, 1), "");
+        assert_eq!(json_pointer_at(json, 100, 100), "");
+    }
+
+    #[test]
+    fn test_from_iiif_error() {
+        let json = "{\n  \"label\": 2\n}";
+        let error = serde_json::from_str::<std::collections::HashMap<String, String>>(json)
+            .map_err(IiifError::from)
+            .unwrap_err();
+
+        let UserNotification::Parse { url, pointer, .. } =
+            UserNotification::from_iiif_error("https://example.org", json, &error)
+        else {
+            panic!("should be a parse error");
+        };
+
+        assert_eq!(url, "https://example.org");
+        assert_eq!(pointer, Some("/label".to_string()));
+
+        assert_eq!(
+            UserNotification::from_iiif_error(
+                "https://example.org",
+                json,
+                &IiifError::IiifUnsupportedError("v1".to_string())
+            ),
+            UserNotification::Unsupported {
+                url: "https://example.org".to_string(),
+                msg: "v1".to_string()
+            }
+        );
+    }
+}
@@ -0,0 +1,1049 @@
+#[cfg(target_arch = "wasm32")]
+use crate::embed;
+use crate::{
+    app::{self, app_settings::AppSettings, app_state::AppState},
+    asset_loading, camera,
+    camera::{camera_ext::get_world_viewport_rect, main_camera::MainCamera2d},
+    capability_cache, capability_probe, host_stats, input, load_trace, minimap,
+    notification::UserNotification,
+    prefetch, presentation, redraw_governor, rendering,
+    rendering::{
+        tile::{TileCache, TileModState, TilePruneState},
+        tiled_image::TiledImage,
+    },
+    view_clipboard, watchdog, web,
+};
+#[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+use crate::{
+    clipboard_watch, contact_sheet, export, screenshot, stitch, window_sync, window_title,
+};
+use bevy::{
+    app::PluginGroupBuilder,
+    asset::{AssetMetaCheck, UnapprovedPathMode, io::web::WebAssetPlugin},
+    camera::visibility::RenderLayers,
+    prelude::*,
+    render::{RenderApp, render_resource::BlendState},
+    winit::WinitSettings,
+};
+use bevy_egui::{
+    EguiGlobalSettings, EguiPlugin, EguiPostUpdateSet, EguiPrimaryContextPass, PrimaryEguiContext,
+    input::{egui_wants_any_keyboard_input, egui_wants_any_pointer_input},
+};
+
+/// Message to load the IIIF manifest or collection at the URL.
+#[derive(Message, Debug, Clone)]
+pub struct LoadManifest {
+    pub manifest_url: String,
+}
+
+/// Message sent when another canvas or manifest is shown.
+#[derive(Message, Debug, Clone, PartialEq)]
+pub struct CanvasChanged {
+    pub manifest_url: String,
+    /// Index of the canvas in the current sequence, starting from 0.
+    pub canvas_index: usize,
+}
+
+/// Message sent when the region of the image in the view is changed, e.g. by panning or zooming.
+#[derive(Message, Debug, Clone, PartialEq)]
+pub struct RegionChanged {
+    /// Region of the full size image in pixels, within the image.
+    pub region: Rect,
+}
+
+/// Message sent when a manifest is loaded, before its canvas is shown.
+#[derive(Message, Debug, Clone, PartialEq)]
+pub struct ManifestLoaded {
+    pub manifest_url: String,
+    /// Title of the manifest in the language of the settings.
+    pub title: String,
+}
+
+/// Message sent when the image of a canvas is loaded from its image info, before its tiles.
+#[derive(Message, Debug, Clone, PartialEq)]
+pub struct CanvasLoaded {
+    pub manifest_url: String,
+    /// Index of the canvas in the current sequence, starting from 0.
+    pub canvas_index: usize,
+    /// Size of the full size image on the canvas, in the units of the canvas.
+    pub image_size: Vec2,
+}
+
+/// Message sent when a tile fails to load.
+#[derive(Message, Debug, Clone, PartialEq)]
+pub struct TileLoadFailed {
+    pub url: String,
+    pub error: String,
+    /// Whether the tile is requested again, e.g. in another format.
+    pub is_retried: bool,
+}
+
+/// Message sent when the size of the view or the level of the tiles in it is changed.
+#[derive(Message, Debug, Clone, PartialEq)]
+pub struct ViewportChanged {
+    /// Size of the view in logical pixels.
+    pub size: Vec2,
+    /// Index of the level of the tiles in the view, from the smallest size of the image.
+    pub level: usize,
+}
+
+/// Plugin of the IIIF viewer, for the apps with the [`default_plugins`] or the same settings of
+/// the assets and the images.
+///
+/// The manifests are loaded with the [`LoadManifest`] message, and the view is followed with the
+/// [`CanvasChanged`] and [`RegionChanged`] messages. The loading is followed with the
+/// [`ManifestLoaded`], [`CanvasLoaded`] and [`TileLoadFailed`] messages.
+#[derive(Default)]
+pub struct IiifViewerPlugin {
+    /// WGSL source of the shader of the custom effect registered by the app, natively only.
+    #[cfg_attr(any(target_arch = "wasm32", target_os = "android"), allow(dead_code))]
+    effect_shader: Option<String>,
+}
+
+impl IiifViewerPlugin {
+    /// Apply the fragment shader of the WGSL source to the tiles drawn in the view, e.g. a
+    /// false-color mapping, natively on desktop. The shader file of the settings takes precedence
+    /// while set.
+    ///
+    /// The bindings of the shader are the same as of the `--effect-shader` file of the command
+    /// line: the texture of the tiles drawn in the view, its sampler and the size of the viewport
+    /// in pixels.
+    pub fn with_effect_shader(mut self, source: impl Into<String>) -> Self {
+        self.effect_shader = Some(source.into());
+        self
+    }
+}
+
+impl Plugin for IiifViewerPlugin {
+    fn build(&self, app: &mut App) {
+        let color_management = rendering::color_profile::ColorManagement::default();
+        let host_stats = host_stats::HostStats::default();
+
+        // The app embedding the viewer may have added it for its own UI.
+        if !app.is_plugin_added::<EguiPlugin>() {
+            app.add_plugins(EguiPlugin::default());
+        }
+
+        app.add_message::<UserNotification>()
+            .add_message::<LoadManifest>()
+            .add_message::<CanvasChanged>()
+            .add_message::<RegionChanged>()
+            .add_message::<ManifestLoaded>()
+            .add_message::<CanvasLoaded>()
+            .add_message::<TileLoadFailed>()
+            .add_message::<ViewportChanged>()
+            .add_message::<crate::OpenWindow>()
+            .add_message::<presentation::actions::ViewerAction>()
+            .add_message::<camera::main_camera::CameraCommand>()
+            .add_message::<camera::main_camera::CameraCommand3d>()
+            // Material of the difference view of the overlay.
+            .add_plugins(rendering::comparison::DifferenceMaterialPlugin)
+            // Shared by the settings and the loader of the tiles.
+            .insert_resource(color_management.clone())
+            // Shared by the downloads, the tile cache and the loader of the tiles.
+            .insert_resource(host_stats.clone())
+            .register_asset_loader(rendering::color_profile::IccImageLoader::new(
+                color_management,
+                host_stats,
+            ))
+            // Desktop mode to reduce CPU usage.
+            .insert_resource(WinitSettings::desktop_app())
+            .add_systems(
+                Startup,
+                (
+                    setup,
+                    rendering::comparison::setup_comparison,
+                    minimap::setup,
+                    presentation::ui::setup,
+                    crate::setup_initial_presentation,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                Update,
+                (
+                    input::keyboard::keyboard_input_system
+                        .run_if(not(egui_wants_any_keyboard_input)),
+                    input::mouse::mouse_input_system::<
+                        camera::main_camera::MainCamera2d,
+                        camera::pan_zoom_state_2d::PanZoomState2d,
+                    >
+                        .run_if(not(egui_wants_any_pointer_input))
+                        .run_if(not(minimap::ui_has_mouse_input)),
+                    input::mouse::mouse_input_system::<
+                        camera::main_camera::MainCamera3d,
+                        camera::pan_orbit_state_3d::PanOrbitState3d,
+                    >
+                        .run_if(not(egui_wants_any_pointer_input)),
+                    (
+                        input::touch::touch_input_system::<
+                            camera::main_camera::MainCamera2d,
+                            camera::pan_zoom_state_2d::PanZoomState2d,
+                        >,
+                        input::touch::touch_input_system::<
+                            camera::main_camera::MainCamera3d,
+                            camera::pan_orbit_state_3d::PanOrbitState3d,
+                        >,
+                        input::gesture::pinch_gesture_system::<
+                            camera::main_camera::MainCamera2d,
+                            camera::pan_zoom_state_2d::PanZoomState2d,
+                        >
+                            .run_if(not(egui_wants_any_pointer_input)),
+                        input::gesture::pinch_gesture_system::<
+                            camera::main_camera::MainCamera3d,
+                            camera::pan_orbit_state_3d::PanOrbitState3d,
+                        >
+                            .run_if(not(egui_wants_any_pointer_input)),
+                    ),
+                    minimap::mouse_input_system,
+                    (
+                        load_trace::load_trace_system
+                            .before(web::load_presentation_system)
+                            .before(web::load_canvas_system),
+                        load_manifest_system,
+                        web::load_presentation_system,
+                        watchdog::download_watchdog_system,
+                    ),
+                    (
+                        web::load_canvas_system,
+                        capability_probe::capability_probe_system,
+                        canvas_changed_system,
+                        presentation::timeline::timeline_system.after(canvas_changed_system),
+                    ),
+                    web::load_overlay_system,
+                    (
+                        prefetch::prefetch_next_canvas_system,
+                        prefetch::prefetch_info_system,
+                    ),
+                    presentation::collection::collection_search_system,
+                    (
+                        presentation::bookmarks::bookmarks_system,
+                        presentation::content_state::content_state_system,
+                        presentation::console::console_system,
+                        presentation::actions::action_system,
+                        presentation::reload::reload_system,
+                    ),
+                    (
+                        presentation::tour::tour_system,
+                        rendering::comparison::blink_system,
+                        view_clipboard::view_clipboard_system,
+                    ),
+                    (
+                        rendering::tiled_image::scale_factor_system,
+                        minimap::thumbnail_system,
+                        rendering::tiled_image::data_saver_system
+                            .run_if(resource_changed::<AppSettings>),
+                        rendering::color_profile::color_management_system
+                            .run_if(resource_changed::<AppSettings>),
+                        (
+                            presentation::theme::system_theme_system,
+                            presentation::theme::theme_system.run_if(
+                                resource_changed::<AppSettings>
+                                    .or(resource_changed::<presentation::theme::SystemTheme>),
+                            ),
+                        )
+                            .chain(),
+                    ),
+                    camera::main_camera::camera_command_system,
+                    camera::main_camera::camera_command_3d_system,
+                    input::mouse::double_click_focus_system
+                        .run_if(not(egui_wants_any_pointer_input)),
+                    (
+                        rendering::grid::grid_system,
+                        rendering::magnifier::magnifier_system,
+                        rendering::eyedropper::eyedropper_system,
+                    ),
+                    rendering::model_image::model_inspection_system,
+                    rendering::model_stage::model_stage_system,
+                    rendering::model_animation::model_animation_system,
+                ),
+            )
+            .add_systems(
+                EguiPrimaryContextPass,
+                (
+                    (
+                        presentation::ui::action_shortcut_ui_system,
+                        presentation::ui::command_palette_ui_system,
+                    ),
+                    presentation::ui::presentation_ui_system,
+                    presentation::ui::annotation_ui_system,
+                    presentation::ui::tour_ui_system,
+                    presentation::ui::share_ui_system,
+                    presentation::ui::console_ui_system,
+                    presentation::ui::diagnostics_ui_system,
+                    presentation::ui::highlights_ui_system,
+                    presentation::ui::timeline_ui_system,
+                    presentation::ui::view_toolbar_ui_system,
+                    presentation::ui::model_toolbar_ui_system,
+                    presentation::ui::progress_ui_system,
+                    presentation::ui::region_info_ui_system,
+                    presentation::ui::rulers_ui_system,
+                    (
+                        presentation::ui::magnifier_ui_system,
+                        presentation::ui::eyedropper_ui_system,
+                    ),
+                    (
+                        presentation::ui::fallback_banner_ui_system,
+                        presentation::ui::error_page_ui_system,
+                    ),
+                    presentation::ui::zoom_selection_ui_system,
+                    presentation::ui::collection_ui_system,
+                    presentation::ui::session_ui_system,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                PostUpdate,
+                (
+                    camera::main_camera::translation_bounding_system,
+                    camera::main_camera::recentre_world_system,
+                    rendering::comparison::difference_view_system,
+                )
+                    .chain()
+                    .before(TransformSystems::Propagate),
+            )
+            .add_systems(
+                PostUpdate,
+                region_changed_system.after(TransformSystems::Propagate),
+            )
+            .add_systems(
+                PostUpdate,
+                redraw_governor::ui_repaint_delay_system
+                    .after(EguiPostUpdateSet::EndPass)
+                    .before(EguiPostUpdateSet::ProcessOutput),
+            )
+            .add_systems(
+                Last,
+                (
+                    asset_loading::asset_event_system,
+                    rendering::tile_placeholder::tile_placeholder_system
+                        .after(asset_loading::asset_event_system),
+                    rendering::canvas_background::canvas_background_system,
+                    minimap::update_view_rect_system,
+                    rendering::tile::prune_tiles_system.run_if(resource_changed::<TilePruneState>),
+                    rendering::pipeline_checker::pipeline_refresh_system
+                        .run_if(resource_changed::<rendering::pipeline_checker::PipelinesModCount>),
+                    rendering::tile::update_tiles_system.run_if(resource_changed::<TileModState>),
+                    rendering::tile_format::tile_format_system
+                        .before(rendering::tile::update_tiles_system),
+                    app::session::save_session_system,
+                    app::manifest_prefs::manifest_prefs_system
+                        .run_if(resource_changed::<AppState>.or(resource_changed::<AppSettings>)),
+                    redraw_governor::redraw_governor_system,
+                    rendering::gpu_capabilities::detect_gpu_capabilities_system.run_if(not(
+                        resource_exists::<rendering::gpu_capabilities::GpuCapabilities>,
+                    )),
+                ),
+            )
+            .add_observer(presentation::manifest::on_remove_manifest)
+            .add_observer(rendering::tile::on_remove_tiled_image)
+            .add_observer(rendering::tile::on_remove_overlay_image)
+            .add_observer(rendering::model_image::on_remove_model_image)
+            .add_observer(minimap::on_remove_tiled_image)
+            .add_observer(rendering::model_image::on_remove_model_loading)
+            .add_observer(rendering::tiled_image::on_add_tiled_image)
+            .add_observer(rendering::model_image::on_add_model_image)
+            .add_observer(minimap::on_add_tiled_image);
+
+        // Wireframes of the 3D models are only supported natively.
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_plugins(bevy::pbr::wireframe::WireframePlugin::default());
+
+        // Other windows of the app with their own cameras and tiles, optionally following the
+        // main camera.
+        #[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+        app.init_resource::<window_sync::ViewerWindows>()
+            .add_observer(window_sync::on_remove_viewer_window)
+            .add_systems(
+                Update,
+                (
+                    window_sync::open_window_system,
+                    window_sync::load_window_system,
+                    window_sync::window_input_system,
+                    window_sync::follow_main_camera_system,
+                    window_sync::update_window_tiles_system,
+                )
+                    .chain(),
+            );
+
+        // The host page embedding the viewer controls it through the JS API.
+        #[cfg(target_arch = "wasm32")]
+        app.add_systems(Startup, embed::setup_embed).add_systems(
+            Update,
+            (
+                embed::embed_command_system,
+                embed::canvas_changed_system.after(canvas_changed_system),
+            ),
+        );
+
+        // Regions are stitched from the tiles for printing only natively on desktop.
+        #[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+        app.init_resource::<stitch::StitchExport>()
+            .add_systems(Update, stitch::stitch_system)
+            .add_systems(
+                EguiPrimaryContextPass,
+                presentation::ui::print_export_ui_system,
+            );
+
+        // Contact sheets of the thumbnails are exported only natively on desktop.
+        #[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+        app.init_resource::<contact_sheet::ContactSheetExport>()
+            .add_systems(Update, contact_sheet::contact_sheet_system)
+            .add_systems(
+                EguiPrimaryContextPass,
+                presentation::ui::contact_sheet_ui_system,
+            );
+
+        // Manifests are exported for offline in the background only natively on desktop.
+        #[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+        app.add_systems(
+            Update,
+            export::offline_export_system.run_if(resource_exists::<export::OfflineExport>),
+        )
+        .add_systems(
+            EguiPrimaryContextPass,
+            presentation::ui::offline_export_ui_system
+                .run_if(resource_exists::<export::OfflineExport>),
+        );
+
+        // The title and the icon of the window follow the manifest shown only natively on desktop.
+        #[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+        app.init_resource::<window_title::WindowTitle>()
+            .add_systems(
+                Update,
+                (
+                    window_title::window_title_system,
+                    window_title::window_icon_system,
+                )
+                    .chain(),
+            );
+
+        // The clipboard is only watched natively on desktop.
+        #[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+        app.init_resource::<clipboard_watch::ClipboardWatch>()
+            .add_systems(Update, clipboard_watch::clipboard_watch_system)
+            .add_systems(
+                EguiPrimaryContextPass,
+                presentation::ui::clipboard_offer_ui_system,
+            );
+
+        // The custom effect is loaded from a file or registered by the app only natively on
+        // desktop.
+        #[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+        app.add_plugins(rendering::custom_effect::EffectMaterialPlugin)
+            .insert_resource(rendering::custom_effect::CustomEffect::new(
+                self.effect_shader.clone(),
+            ))
+            .add_systems(
+                Startup,
+                rendering::custom_effect::setup_custom_effect.after(setup),
+            )
+            .add_systems(
+                PostUpdate,
+                rendering::custom_effect::custom_effect_system.before(TransformSystems::Propagate),
+            );
+
+        // Exit once the screenshot is written in the screenshot mode.
+        #[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+        app.add_systems(
+            Last,
+            screenshot::screenshot_system.run_if(resource_exists::<screenshot::ScreenshotRequest>),
+        );
+
+        // In desktop mode, systems are not always run.
+        // We subscribe to the ExtractSchedule to check the status of the pipeline.
+        // and will refresh until all are ready.
+        // https://github.com/rparrett/bevy_pipelines_ready
+        // Nothing is rendered without a graphics device, e.g. in the headless tests.
+        if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app.add_systems(
+                ExtractSchedule,
+                rendering::pipeline_checker::check_pipelines_ready_system,
+            );
+        }
+    }
+}
+
+/// Get the default plugins with the settings of the assets and the images needed by the viewer.
+pub fn default_plugins() -> PluginGroupBuilder {
+    DefaultPlugins
+        // Meta data is not expected for IIIF.
+        .set(AssetPlugin {
+            meta_check: AssetMetaCheck::Never,
+            // Local files are only loaded explicitly, e.g. the exported tiles.
+            unapproved_path_mode: UnapprovedPathMode::Deny,
+            ..default()
+        })
+        // Understood the warning.
+        .set(WebAssetPlugin {
+            silence_startup_warning: true,
+        })
+        // Use nearest for the tiling for now. Will probably need to use virtual texture for the linear interpolation.
+        .set(ImagePlugin::default_nearest())
+}
+
+/// Set up the cameras and the resources of the viewer.
+fn setup(
+    mut commands: Commands,
+    mut egui_global_settings: ResMut<EguiGlobalSettings>,
+    host_stats: Res<host_stats::HostStats>,
+) -> Result {
+    // Disable the automatic creation of a primary context to set it up manually for the camera we need.
+    egui_global_settings.auto_create_primary_context = false;
+
+    // Main camera
+    commands.spawn((
+        camera::main_camera::MainCamera2d,
+        Camera2d,
+        Camera {
+            is_active: false,
+            ..default()
+        },
+    ));
+
+    commands.spawn((
+        camera::main_camera::MainCamera3d,
+        Camera3d::default(),
+        Camera {
+            is_active: false,
+            ..default()
+        },
+        Transform::from_xyz(0.0, 0.0, 10.0).looking_at(Vec3::new(0.0, 0.0, 0.0), Vec3::Y),
+    ));
+
+    // Default ambient light.
+    commands.insert_resource(AmbientLight {
+        brightness: 2000.0,
+        ..default()
+    });
+
+    // Tile cache resource.
+    commands.insert_resource(TileCache::new(host_stats.clone()));
+
+    // Image info and quirks of the image services learned in the previous sessions.
+    let capability_cache = capability_cache::CapabilityCache::load();
+
+    commands.insert_resource(capability_cache.clone());
+
+    // App state.
+    commands.insert_resource(AppState {
+        host_stats: host_stats.clone(),
+        capability_cache,
+        ..default()
+    });
+
+    // Collection search.
+    commands.insert_resource(presentation::collection::CollectionSearch::default());
+
+    // Manifest sources.
+    commands.insert_resource(presentation::source::ManifestSources::default());
+
+    // Bookmarks.
+    commands.insert_resource(presentation::bookmarks::Bookmarks::default());
+
+    // Annotations drawn by the user.
+    commands.insert_resource(presentation::annotations::AnnotationAuthoring::default());
+
+    // Tour.
+    commands.insert_resource(presentation::tour::Tour::default());
+
+    // Content state of the view to share.
+    commands.insert_resource(presentation::content_state::ContentStateShare::default());
+
+    // Timeline of the time-based canvases.
+    commands.insert_resource(presentation::timeline::Timeline::default());
+
+    // Console of the commands typed by the user.
+    commands.insert_resource(presentation::console::Console::default());
+
+    // Command palette of the actions of the viewer.
+    commands.insert_resource(presentation::actions::CommandPalette::default());
+
+    // Error of the manifest which cannot be shown, in place of the empty viewport.
+    commands.insert_resource(presentation::error_page::ManifestErrorPage::default());
+
+    // Reload of the manifest shown, e.g. while it is edited.
+    commands.insert_resource(presentation::reload::ManifestReload::default());
+
+    // Watchdog of the downloads which may never finish.
+    commands.insert_resource(watchdog::DownloadWatchdog::default());
+
+    // Capabilities of the image service of the current image.
+    commands.insert_resource(capability_probe::CapabilityProbe::default());
+
+    // Timings of the loading for the diagnostics.
+    commands.insert_resource(load_trace::LoadTrace::default());
+
+    // Dark or light preference of the system for the theme.
+    commands.insert_resource(presentation::theme::SystemTheme::default());
+
+    // Cadence of the frames by the activities.
+    commands.insert_resource(redraw_governor::RedrawGovernor::default());
+
+    // Lens of the magnifier in the low-vision mode.
+    commands.insert_resource(rendering::magnifier::Magnifier::default());
+
+    // Eyedropper reading the pixel under the cursor.
+    commands.insert_resource(rendering::eyedropper::Eyedropper::default());
+
+    // Copy of the view to the clipboard.
+    commands.insert_resource(view_clipboard::ViewClipboard::default());
+
+    // Prefetch of the next canvas.
+    commands.insert_resource(prefetch::CanvasPrefetch::default());
+
+    // Prefetch of the image info of the first canvases.
+    commands.insert_resource(prefetch::InfoPrefetch::default());
+
+    // Pipeline mod count.
+    commands.insert_resource(rendering::pipeline_checker::PipelinesModCount::default());
+
+    // Tile mod state.
+    commands.insert_resource(TileModState::new());
+
+    // Formats of the tiles working for the endpoints.
+    commands.insert_resource(rendering::tile_format::TileFormats::default());
+
+    // Colors of the loaded tiles for the placeholders of the tiles loading.
+    commands.insert_resource(rendering::tile_placeholder::TileColors::default());
+
+    // App settings.
+    commands.insert_resource(AppSettings::default());
+
+    // Tile mod state.
+    commands.insert_resource(TilePruneState::new());
+
+    // Inspection tools of the 3D model.
+    commands.insert_resource(rendering::model_image::ModelInspection::default());
+
+    // Animations of the 3D model.
+    commands.insert_resource(rendering::model_animation::ModelAnimations::default());
+
+    // Camera 3D pan orbit state.
+    commands.insert_resource(camera::pan_orbit_state_3d::PanOrbitState3d::default());
+
+    // Camera 2D pan zoom state.
+    commands.insert_resource(camera::pan_zoom_state_2d::PanZoomState2d::default());
+
+    // Egui camera.
+    commands.spawn((
+        // The `PrimaryEguiContext` component requires everything needed to render a primary context.
+        PrimaryEguiContext,
+        Camera2d,
+        // Setting RenderLayers to none makes sure we won't render anything apart from the UI.
+        RenderLayers::none(),
+        Camera {
+            order: 1,
+            output_mode: bevy::camera::CameraOutputMode::Write {
+                blend_state: Some(BlendState::ALPHA_BLENDING),
+                clear_color: ClearColorConfig::None,
+            },
+            clear_color: ClearColorConfig::Custom(Color::NONE),
+            ..default()
+        },
+    ));
+
+    Ok(())
+}
+
+/// Load the manifests requested by the app embedding the viewer.
+fn load_manifest_system(
+    mut load_manifest_reader: MessageReader<LoadManifest>,
+    mut app_state: ResMut<AppState>,
+    app_settings: Res<AppSettings>,
+) {
+    for load_manifest in load_manifest_reader.read() {
+        web::load_presentation(&mut app_state, &app_settings, &load_manifest.manifest_url);
+    }
+}
+
+/// Tell when another canvas or manifest is shown.
+fn canvas_changed_system(
+    app_state: Res<AppState>,
+    mut last_canvas: Local<Option<CanvasChanged>>,
+    mut canvas_changed_writer: MessageWriter<CanvasChanged>,
+) {
+    if !app_state.is_changed() {
+        return;
+    }
+
+    let canvas = CanvasChanged {
+        manifest_url: app_state.presentation_url.to_string(),
+        canvas_index: app_state.canvas_index,
+    };
+
+    // Nothing is shown before the first manifest is loaded.
+    if canvas.manifest_url.is_empty() || last_canvas.as_ref() == Some(&canvas) {
+        return;
+    }
+
+    canvas_changed_writer.write(canvas.clone());
+    *last_canvas = Some(canvas);
+}
+
+/// Tell when the region of the image in the view is changed.
+fn region_changed_system(
+    tiled_image: Option<Single<&TiledImage>>,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera2d>>,
+    mut last_region: Local<Option<Rect>>,
+    mut region_changed_writer: MessageWriter<RegionChanged>,
+) {
+    let (camera, global_transform) = camera.into_inner();
+    let region = tiled_image
+        .filter(|_| camera.is_active)
+        .zip(get_world_viewport_rect(camera, global_transform))
+        .map(|(tiled_image, (world_pos_min, world_pos_max))| {
+            tiled_image
+                .world_to_image_rect(Rect::from_corners(
+                    world_pos_min.truncate(),
+                    world_pos_max.truncate(),
+                ))
+                .intersect(tiled_image.get_image_max_size_rect())
+        });
+
+    if region == *last_region {
+        return;
+    }
+
+    if let Some(region) = region {
+        region_changed_writer.write(RegionChanged { region });
+    }
+
+    *last_region = region;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        app::app_settings::FitMode,
+        capability_cache::CapabilityCache,
+        mock_server::{IMAGE_HEIGHT, IMAGE_IDS, IMAGE_WIDTH, MockIiifServer, TILE_SIZE},
+        presentation::manifest::Manifest,
+        rendering::{
+            model_image::ModelImage,
+            tile::{Tile, TileKey, TileLayer, TileLoading},
+        },
+    };
+    use bevy::{
+        ecs::system::RunSystemOnce,
+        log::LogPlugin,
+        render::{RenderPlugin, settings::WgpuSettings},
+        window::WindowResolution,
+        winit::WinitPlugin,
+    };
+    use std::time::Duration;
+
+    /// Max number of the updates waiting for the downloads from the mock server.
+    const MAX_UPDATES: usize = 1000;
+
+    /// Size of the window, smaller than the full size image.
+    const WINDOW_SIZE: (u32, u32) = (1024, 768);
+
+    /// Create the viewer with a window without any surface, and without any graphics device.
+    fn create_headless_app() -> App {
+        let mut app = App::new();
+
+        app.add_plugins(
+            default_plugins()
+                .set(WindowPlugin {
+                    primary_window: Some(Window {
+                        resolution: WindowResolution::from(WINDOW_SIZE),
+                        ..default()
+                    }),
+                    ..default()
+                })
+                .set(RenderPlugin {
+                    render_creation: WgpuSettings {
+                        backends: None,
+                        ..default()
+                    }
+                    .into(),
+                    ..default()
+                })
+                .disable::<WinitPlugin>()
+                .disable::<LogPlugin>(),
+        )
+        .add_plugins(IiifViewerPlugin::default());
+
+        app.finish();
+        app.cleanup();
+        app.update();
+
+        // The image info of the mock server is not kept in the storage.
+        let capability_cache = CapabilityCache::new_in_memory();
+
+        app.world_mut().resource_mut::<AppState>().capability_cache = capability_cache.clone();
+        app.insert_resource(capability_cache);
+        app.world_mut().resource_mut::<AppSettings>().fit_mode = FitMode::Whole;
+
+        app
+    }
+
+    /// Update the app until the condition is met, waiting for the downloads from the mock server.
+    fn update_until(app: &mut App, mut condition: impl FnMut(&mut World) -> bool) -> bool {
+        for _ in 0..MAX_UPDATES {
+            app.update();
+
+            if condition(app.world_mut()) {
+                return true;
+            }
+
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        false
+    }
+
+    /// Get the regions of the loaded tiles of the image at the level of the view.
+    fn get_loaded_tile_regions(world: &mut World, endpoint: &str) -> Vec<Rect> {
+        let level = world.resource::<AppState>().level;
+
+        world
+            .query_filtered::<&Tile, Without<TileLoading>>()
+            .iter(world)
+            .filter(|x| x.endpoint == endpoint && x.index.level() == level)
+            .map(|x| x.image_position)
+            .collect()
+    }
+
+    /// Get the number of the tiles covering the whole image at the level of the view.
+    fn get_level_tile_count(world: &mut World) -> usize {
+        let level = world.resource::<AppState>().level;
+        let level_width = world
+            .query::<&TiledImage>()
+            .single(world)
+            .expect("should have an image")
+            .get_levels()[level]
+            .width;
+        let tile_size = TILE_SIZE * IMAGE_WIDTH / level_width;
+
+        (IMAGE_WIDTH.div_ceil(tile_size) * IMAGE_HEIGHT.div_ceil(tile_size)) as usize
+    }
+
+    /// Whether the image is shown with all the tiles at the level of the view loaded.
+    fn is_image_loaded(world: &mut World, endpoint: &str) -> bool {
+        let is_current_image = world
+            .query::<&TiledImage>()
+            .single(world)
+            .is_ok_and(|x| x.get_endpoint() == endpoint);
+
+        is_current_image
+            && get_loaded_tile_regions(world, endpoint).len() == get_level_tile_count(world)
+    }
+
+    #[test]
+    fn test_headless_tile_selection() {
+        let server = MockIiifServer::start();
+        let endpoint = server.get_image_endpoint(IMAGE_IDS[0]);
+        let mut app = create_headless_app();
+
+        app.world_mut().write_message(LoadManifest {
+            manifest_url: server.get_manifest_url(),
+        });
+
+        assert!(update_until(&mut app, |world| is_image_loaded(
+            world, &endpoint
+        )));
+
+        let world = app.world_mut();
+        let mut regions = get_loaded_tile_regions(world, &endpoint)
+            .iter()
+            .map(|x| {
+                (
+                    x.min.x as u32,
+                    x.min.y as u32,
+                    x.width() as u32,
+                    x.height() as u32,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        regions.sort();
+
+        // The full size image is whole in the view of its half size, i.e. the middle level,
+        // where a tile covers twice its size of the full size image.
+        assert_eq!(world.resource::<AppState>().level, 1);
+        assert_eq!(
+            regions,
+            vec![
+                (0, 0, 2 * TILE_SIZE, 2 * TILE_SIZE),
+                (
+                    0,
+                    2 * TILE_SIZE,
+                    2 * TILE_SIZE,
+                    IMAGE_HEIGHT - 2 * TILE_SIZE
+                ),
+                (2 * TILE_SIZE, 0, IMAGE_WIDTH - 2 * TILE_SIZE, 2 * TILE_SIZE),
+                (
+                    2 * TILE_SIZE,
+                    2 * TILE_SIZE,
+                    IMAGE_WIDTH - 2 * TILE_SIZE,
+                    IMAGE_HEIGHT - 2 * TILE_SIZE
+                ),
+            ]
+        );
+
+        let requests = server.get_tile_requests(IMAGE_IDS[0]);
+
+        for (x, y, width, height) in regions {
+            let region = format!("{},{},{},{}/", x, y, width, height);
+
+            assert!(
+                requests.iter().any(|x| x.starts_with(&region)),
+                "{} should be requested",
+                region
+            );
+        }
+        assert!(server.get_tile_requests(IMAGE_IDS[1]).is_empty());
+    }
+
+    #[test]
+    fn test_headless_prefetch() {
+        let server = MockIiifServer::start();
+        let endpoints = IMAGE_IDS.map(|x| server.get_image_endpoint(x));
+        let mut app = create_headless_app();
+
+        app.world_mut().write_message(LoadManifest {
+            manifest_url: server.get_manifest_url(),
+        });
+
+        assert!(update_until(&mut app, |world| is_image_loaded(
+            world,
+            &endpoints[0]
+        )));
+
+        // The next canvas is prefetched once nothing is loading for a while.
+        let get_prefetched_keys = |world: &mut World| {
+            world
+                .query::<&Tile>()
+                .iter(world)
+                .filter(|x| x.endpoint == endpoints[1])
+                .map(|x| x.get_key())
+                .collect::<Vec<_>>()
+        };
+
+        assert!(update_until(&mut app, |world| !get_prefetched_keys(world).is_empty()));
+
+        let prefetched_keys = get_prefetched_keys(app.world_mut());
+
+        app.world_mut()
+            .run_system_once(
+                |mut commands: Commands,
+                 presentation: Single<&Manifest>,
+                 mut app_state: ResMut<AppState>,
+                 app_settings: Res<AppSettings>,
+                 model_image_query: Query<Entity, With<ModelImage>>| {
+                    web::load_canvas(
+                        &mut commands,
+                        &presentation,
+                        &mut app_state,
+                        &app_settings,
+                        0,
+                        1,
+                        0,
+                        &model_image_query,
+                    )
+                },
+            )
+            .expect("should be able to run the system")
+            .expect("should be able to load the canvas");
+
+        assert!(update_until(&mut app, |world| is_image_loaded(
+            world,
+            &endpoints[1]
+        )));
+
+        // The prefetched tiles are those of the canvas shown.
+        let world = app.world_mut();
+        let placement = world
+            .query::<&TiledImage>()
+            .single(world)
+            .expect("should have an image")
+            .get_tile_placement();
+
+        assert!(placement.canvas_region.is_some());
+
+        for key in prefetched_keys {
+            assert_eq!(
+                key,
+                TileKey {
+                    layer: TileLayer::Base,
+                    endpoint: endpoints[1].clone(),
+                    placement,
+                    index: key.index,
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_headless_canvas_switching() {
+        let server = MockIiifServer::start();
+        let endpoints = IMAGE_IDS.map(|x| server.get_image_endpoint(x));
+        let mut app = create_headless_app();
+
+        app.world_mut().write_message(LoadManifest {
+            manifest_url: server.get_manifest_url(),
+        });
+
+        assert!(update_until(&mut app, |world| is_image_loaded(
+            world,
+            &endpoints[0]
+        )));
+
+        // Keep only the tiles in view in the cache.
+        app.world_mut()
+            .resource_mut::<AppSettings>()
+            .max_cache_items = 0;
+        app.world_mut()
+            .run_system_once(
+                |mut commands: Commands,
+                 presentation: Single<&Manifest>,
+                 mut app_state: ResMut<AppState>,
+                 app_settings: Res<AppSettings>,
+                 model_image_query: Query<Entity, With<ModelImage>>| {
+                    web::load_canvas(
+                        &mut commands,
+                        &presentation,
+                        &mut app_state,
+                        &app_settings,
+                        0,
+                        1,
+                        0,
+                        &model_image_query,
+                    )
+                },
+            )
+            .expect("should be able to run the system")
+            .expect("should be able to load the canvas");
+
+        assert!(update_until(&mut app, |world| is_image_loaded(
+            world,
+            &endpoints[1]
+        )));
+        assert_eq!(app.world().resource::<AppState>().canvas_index, 1);
+
+        // The tiles of the previous canvas are pruned, but not those in view.
+        let num_tiles = get_loaded_tile_regions(app.world_mut(), &endpoints[1]).len();
+
+        app.world_mut()
+            .resource_mut::<TilePruneState>()
+            .invalidate();
+        app.update();
+        app.update();
+
+        let world = app.world_mut();
+        let tile_endpoints = world
+            .query::<&Tile>()
+            .iter(world)
+            .map(|x| x.endpoint.to_string())
+            .collect::<Vec<_>>();
+
+        assert!(!tile_endpoints.contains(&endpoints[0]));
+        assert_eq!(
+            get_loaded_tile_regions(world, &endpoints[1]).len(),
+            num_tiles
+        );
+    }
+}
@@ -0,0 +1,361 @@
+use crate::{
+    app::{
+        app_settings::AppSettings,
+        app_state::{AppState, DownloadState, ImageDownloadInfo},
+    },
+    presentation::{manifest::Manifest, ui::EguiUiState},
+    rendering::{
+        tile::{self, TileCache, TileLoading},
+        tiled_image::TiledImage,
+    },
+    tilesource, web,
+};
+use bevy::{
+    prelude::{
+        AssetServer, Commands, DetectChanges, Local, MessageWriter, Query, Ref, Res, ResMut,
+        Resource, Single, Time, With, debug,
+    },
+    window::RequestRedraw,
+};
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// Seconds without any loading before the next canvas is prefetched.
+const IDLE_SECS: f64 = 2.0;
+
+/// How long the prefetched image info is used without another request.
+const PREFETCH_FRESH_DURATION: Duration = Duration::from_secs(120);
+
+/// Image of the next canvas fetched ahead, so that turning the page shows it at once.
+#[derive(Resource)]
+pub(crate) struct CanvasPrefetch {
+    /// Manifest URL, sequence and canvas index of the canvas prefetched or being prefetched.
+    target: Option<(String, usize, usize)>,
+    download_state: Arc<Mutex<DownloadState<ImageDownloadInfo>>>,
+}
+
+impl Default for CanvasPrefetch {
+    fn default() -> Self {
+        Self {
+            target: None,
+            download_state: Arc::new(Mutex::new(DownloadState::None)),
+        }
+    }
+}
+
+/// Max number of the image info of the first canvases fetched at once.
+const MAX_INFO_PREFETCHES: usize = 4;
+
+/// Image info of the first canvases of the manifest fetched in parallel when it is shown, so
+/// that turning the pages uses the capability cache without the info requests.
+#[derive(Resource, Default)]
+pub(crate) struct InfoPrefetch {
+    /// Endpoints of the canvases left to fetch, in order.
+    queue: VecDeque<(String, ImageDownloadInfo)>,
+    /// Downloads in progress, at most the max number at once.
+    downloads: Vec<Arc<Mutex<DownloadState<ImageDownloadInfo>>>>,
+}
+
+impl InfoPrefetch {
+    /// Queue the image services of the first canvases of the sequence shown, other than the one
+    /// of the current canvas and those already in the capability cache.
+    fn start(&mut self, presentation: &Manifest, app_state: &AppState, app_settings: &AppSettings) {
+        self.queue.clear();
+        // The responses of the previous manifest are dropped with the states.
+        self.downloads.clear();
+
+        if app_settings.data_saver {
+            return;
+        }
+
+        let Ok(sequence) = presentation.model().get_sequence(app_state.sequence_index) else {
+            return;
+        };
+        let mut endpoints = HashSet::new();
+
+        for (canvas_index, canvas) in sequence
+            .get_canvases()
+            .enumerate()
+            .take(app_settings.prefetch_info_canvases as usize)
+        {
+            let canvas = web::get_displayed_canvas(canvas);
+            let Ok(image) = canvas.get_image(0) else {
+                continue;
+            };
+            let endpoint = image.get_service().to_string();
+            let url = TiledImage::get_image_info_url(&endpoint);
+
+            // Plain images are fetched whole to measure them, so only when shown.
+            if image.get_type() == "Model"
+                || endpoint.is_empty()
+                || tilesource::is_image_url(&endpoint)
+                || canvas_index == app_state.canvas_index
+                || app_state.capability_cache.get_info(&url).is_some()
+                || !endpoints.insert(endpoint.to_string())
+            {
+                continue;
+            }
+
+            self.queue.push_back((
+                url,
+                ImageDownloadInfo {
+                    iiif_endpoint: endpoint,
+                    sequence_index: app_state.sequence_index,
+                    canvas_index,
+                    choice_index: 0,
+                    preserve_view: false,
+                    orientation: canvas.get_image_orientation(0, 0),
+                },
+            ));
+        }
+
+        debug!("Prefetch the image info of {} canvases", self.queue.len());
+    }
+}
+
+/// Start to fetch the image info of the canvas at the index if it is a tiled image.
+fn prefetch_canvas(
+    prefetch: &CanvasPrefetch,
+    presentation: &Manifest,
+    app_state: &AppState,
+    app_settings: &AppSettings,
+    canvas_index: usize,
+) {
+    let Ok(canvas) = presentation
+        .model()
+        .get_sequence(app_state.sequence_index)
+        .and_then(|x| x.get_canvas(canvas_index))
+        .map(web::get_displayed_canvas)
+    else {
+        return;
+    };
+    let Ok(image) = canvas.get_image(0) else {
+        return;
+    };
+
+    if image.get_type() == "Model" {
+        return;
+    }
+
+    let iiif_endpoint = image.get_service();
+
+    debug!("Prefetch the canvas {} at {}", canvas_index, iiif_endpoint);
+
+    web::load_image_info(
+        app_state,
+        &app_settings.proxy_prefix,
+        Arc::clone(&prefetch.download_state),
+        ImageDownloadInfo {
+            iiif_endpoint: iiif_endpoint.to_string(),
+            sequence_index: app_state.sequence_index,
+            canvas_index,
+            choice_index: 0,
+            preserve_view: false,
+            orientation: canvas.get_image_orientation(0, 0),
+        },
+    );
+}
+
+/// Prefetch the image info and the lowest level tiles of the next canvas when the user hovers
+/// the next page button, or when nothing has been loading for a while.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn prefetch_next_canvas_system(
+    mut commands: Commands,
+    mut prefetch: ResMut<CanvasPrefetch>,
+    mut tile_cache: ResMut<TileCache>,
+    app_state: Res<AppState>,
+    app_settings: Res<AppSettings>,
+    egui_ui_state: Res<EguiUiState>,
+    presentation: Option<Single<&Manifest>>,
+    loading_tiles: Query<(), With<TileLoading>>,
+    asset_server: Res<AssetServer>,
+    time: Res<Time>,
+    mut redraw_request_writer: MessageWriter<RequestRedraw>,
+    mut last_busy_secs: Local<f64>,
+) {
+    let now = time.elapsed_secs_f64();
+    let download_state = Arc::clone(&prefetch.download_state);
+    let mut download_state = download_state
+        .lock()
+        .expect("should be able to lock the prefetch download state mutex");
+
+    match &*download_state {
+        DownloadState::Done { json, info } => {
+            match TiledImage::try_from_info(json, &info.iiif_endpoint) {
+                Ok(mut image) => {
+                    if let Some(presentation) = &presentation {
+                        web::setup_canvas_image(
+                            &mut image,
+                            info,
+                            presentation,
+                            &app_state,
+                            &app_settings,
+                        );
+                    }
+
+                    let url = TiledImage::get_image_info_url(&info.iiif_endpoint);
+
+                    // The canvas loads the same image info without a request.
+                    app_state
+                        .http_cache
+                        .store_prefetched(&url, json, PREFETCH_FRESH_DURATION);
+                    app_state.capability_cache.store_info(
+                        &url,
+                        json,
+                        app_state.http_cache.get_validators(&url),
+                    );
+                    tile::prefetch_tiles(
+                        &mut commands,
+                        &mut tile_cache,
+                        &asset_server,
+                        &image,
+                        now,
+                    );
+                }
+                Err(e) => debug!("Unable to prefetch {}. {:?}", info.iiif_endpoint, e),
+            }
+            *download_state = DownloadState::None;
+        }
+        DownloadState::Error { url, msg } => {
+            // Reported when the canvas is loaded instead.
+            debug!("Unable to prefetch {}. {}", url, msg);
+            *download_state = DownloadState::None;
+        }
+        DownloadState::InProgress { .. } => {
+            redraw_request_writer.write(RequestRedraw);
+        }
+        DownloadState::None => {}
+    }
+
+    let is_busy = download_state.is_in_progress()
+        || !loading_tiles.is_empty()
+        || tile_cache.has_requests()
+        || app_state
+            .image_json_download_state
+            .lock()
+            .is_ok_and(|x| x.is_in_progress());
+
+    drop(download_state);
+
+    if is_busy {
+        *last_busy_secs = now;
+        return;
+    }
+
+    let Some(presentation) = presentation else {
+        return;
+    };
+    let next_index = app_state.canvas_index + 1;
+    let num_canvases = presentation
+        .model()
+        .get_sequence(app_state.sequence_index)
+        .map(|x| x.get_canvases().len())
+        .unwrap_or_default();
+    let target = (
+        app_state.presentation_url.to_string(),
+        app_state.sequence_index,
+        next_index,
+    );
+
+    // Only the canvases shown are loaded to save the data.
+    if app_settings.data_saver
+        || next_index >= num_canvases
+        || prefetch.target.as_ref() == Some(&target)
+    {
+        return;
+    }
+
+    if egui_ui_state.hovering_next_page || now - *last_busy_secs > IDLE_SECS {
+        prefetch_canvas(
+            &prefetch,
+            &presentation,
+            &app_state,
+            &app_settings,
+            next_index,
+        );
+        prefetch.target = Some(target);
+    }
+}
+
+/// Prefetch the image info of the first canvases when a manifest is shown, a few at once, and
+/// keep the valid ones in the capability cache.
+pub(crate) fn prefetch_info_system(
+    mut info_prefetch: ResMut<InfoPrefetch>,
+    app_state: Res<AppState>,
+    app_settings: Res<AppSettings>,
+    presentation: Option<Single<Ref<Manifest>>>,
+    mut redraw_request_writer: MessageWriter<RequestRedraw>,
+) {
+    let Some(presentation) = presentation else {
+        if !info_prefetch.queue.is_empty() || !info_prefetch.downloads.is_empty() {
+            *info_prefetch = InfoPrefetch::default();
+        }
+        return;
+    };
+
+    // Shown or reloaded.
+    if presentation.is_changed() {
+        info_prefetch.start(&presentation, &app_state, &app_settings);
+    }
+
+    info_prefetch.downloads.retain(|download_state| {
+        let mut download_state = download_state
+            .lock()
+            .expect("should be able to lock the prefetch download state mutex");
+
+        match &*download_state {
+            DownloadState::Done { json, info } => {
+                let url = TiledImage::get_image_info_url(&info.iiif_endpoint);
+
+                match TiledImage::try_from_info(json, &info.iiif_endpoint) {
+                    Ok(_) => {
+                        app_state
+                            .http_cache
+                            .store_prefetched(&url, json, PREFETCH_FRESH_DURATION);
+                        app_state.capability_cache.store_info(
+                            &url,
+                            json,
+                            app_state.http_cache.get_validators(&url),
+                        );
+                    }
+                    Err(e) => debug!("Unable to prefetch {}. {:?}", url, e),
+                }
+                *download_state = DownloadState::None;
+                false
+            }
+            DownloadState::Error { url, msg } => {
+                // Reported when the canvas is loaded instead.
+                debug!("Unable to prefetch {}. {}", url, msg);
+                false
+            }
+            DownloadState::InProgress { .. } => true,
+            DownloadState::None => false,
+        }
+    });
+
+    while info_prefetch.downloads.len() < MAX_INFO_PREFETCHES
+        && let Some((url, info)) = info_prefetch.queue.pop_front()
+    {
+        // Cached by the canvas shown meanwhile.
+        if app_state.capability_cache.get_info(&url).is_some() {
+            continue;
+        }
+
+        let download_state = Arc::new(Mutex::new(DownloadState::None));
+
+        web::load_image_info(
+            &app_state,
+            &app_settings.proxy_prefix,
+            Arc::clone(&download_state),
+            info,
+        );
+        info_prefetch.downloads.push(download_state);
+    }
+
+    if !info_prefetch.downloads.is_empty() {
+        redraw_request_writer.write(RequestRedraw);
+    }
+}
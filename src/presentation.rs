@@ -1,3 +1,18 @@
+pub(crate) mod actions;
+pub(crate) mod annotations;
+pub(crate) mod bookmarks;
+pub(crate) mod collection;
+pub(crate) mod console;
+pub(crate) mod content_state;
+pub(crate) mod error_page;
+pub(crate) mod html;
 pub(crate) mod manifest;
 pub(crate) mod model;
+pub(crate) mod reload;
+pub(crate) mod rights;
+pub(crate) mod source;
+pub(crate) mod spreads;
+pub(crate) mod theme;
+pub(crate) mod timeline;
+pub(crate) mod tour;
 pub(crate) mod ui;
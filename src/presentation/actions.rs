@@ -0,0 +1,426 @@
+use crate::{
+    app::{app_settings::AppSettings, app_state::AppState},
+    camera::main_camera::CameraCommand,
+    notification::UserNotification,
+    presentation::{manifest::Manifest, reload::ManifestReload, ui::EguiUiState},
+    rendering::{comparison::ComparisonMode, eyedropper::Eyedropper, model_image::ModelImage},
+    view_clipboard::ViewClipboard,
+    web,
+};
+use bevy::{
+    prelude::{
+        Commands, Entity, Message, MessageReader, MessageWriter, Query, ResMut, Resource, Single,
+        With,
+    },
+    window::RequestRedraw,
+};
+use bevy_egui::egui::{Key, KeyboardShortcut, Modifiers};
+
+/// Fraction of the view zoomed by a step of the zoom actions.
+const ZOOM_STEP: f32 = 0.1;
+
+/// Bonus of a char of the query matched right after the previous one.
+const CONSECUTIVE_BONUS: i32 = 2;
+
+/// Bonus of a char of the query matched at the start of a word of the name.
+const WORD_START_BONUS: i32 = 3;
+
+/// Action of the viewer run by its key binding or from the command palette.
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ViewerAction {
+    CommandPalette,
+    /// Focus the address bar to type the URL of a manifest.
+    OpenManifest,
+    ReloadManifest,
+    PreviousPage,
+    NextPage,
+    ZoomIn,
+    ZoomOut,
+    /// Zoom in by the precision speed of the settings, for the fine framing.
+    ZoomInFinely,
+    ZoomOutFinely,
+    CycleFitMode,
+    ToggleSidePanel,
+    ToggleSettings,
+    ToggleNotifications,
+    ToggleBookmarks,
+    ToggleAnnotations,
+    ToggleTour,
+    ToggleHighlights,
+    ToggleShare,
+    ToggleDiagnostics,
+    ToggleConsole,
+    TogglePrintExport,
+    ToggleContactSheet,
+    ToggleDataSaver,
+    ToggleGrid,
+    ToggleRulers,
+    /// Report the color of the pixel under the cursor.
+    ToggleEyedropper,
+    CopyView,
+    /// Blink between the image and the overlay chosen to compare them.
+    ToggleBlink,
+}
+
+/// Action in the registry with its name in the command palette and its key binding, if any.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ActionEntry {
+    pub(crate) action: ViewerAction,
+    pub(crate) name: &'static str,
+    pub(crate) shortcut: Option<KeyboardShortcut>,
+}
+
+impl ActionEntry {
+    const fn new(action: ViewerAction, name: &'static str) -> Self {
+        Self {
+            action,
+            name,
+            shortcut: None,
+        }
+    }
+
+    const fn with_shortcut(mut self, modifiers: Modifiers, key: Key) -> Self {
+        self.shortcut = Some(KeyboardShortcut::new(modifiers, key));
+        self
+    }
+}
+
+/// Registry of the actions of the viewer, in the order listed in the command palette. The panning
+/// by the arrow keys is not an action, as it is continuous while the keys are held.
+pub(crate) const ACTIONS: &[ActionEntry] = &[
+    ActionEntry::new(ViewerAction::CommandPalette, "Command palette")
+        .with_shortcut(Modifiers::COMMAND, Key::K),
+    // Ctrl+L (Cmd+L on macOS) as in the web browsers.
+    ActionEntry::new(ViewerAction::OpenManifest, "Open manifest")
+        .with_shortcut(Modifiers::COMMAND, Key::L),
+    ActionEntry::new(ViewerAction::ReloadManifest, "Reload manifest"),
+    ActionEntry::new(ViewerAction::PreviousPage, "Previous page")
+        .with_shortcut(Modifiers::NONE, Key::PageUp),
+    ActionEntry::new(ViewerAction::NextPage, "Next page")
+        .with_shortcut(Modifiers::NONE, Key::PageDown),
+    ActionEntry::new(ViewerAction::ZoomIn, "Zoom in").with_shortcut(Modifiers::NONE, Key::Z),
+    ActionEntry::new(ViewerAction::ZoomOut, "Zoom out").with_shortcut(Modifiers::NONE, Key::X),
+    ActionEntry::new(ViewerAction::ZoomInFinely, "Zoom in finely")
+        .with_shortcut(Modifiers::SHIFT, Key::Z),
+    ActionEntry::new(ViewerAction::ZoomOutFinely, "Zoom out finely")
+        .with_shortcut(Modifiers::SHIFT, Key::X),
+    ActionEntry::new(ViewerAction::CycleFitMode, "Cycle fit modes")
+        .with_shortcut(Modifiers::NONE, Key::F),
+    ActionEntry::new(ViewerAction::ToggleSidePanel, "Toggle side panel"),
+    ActionEntry::new(ViewerAction::ToggleSettings, "Toggle settings"),
+    ActionEntry::new(ViewerAction::ToggleNotifications, "Toggle notifications"),
+    ActionEntry::new(ViewerAction::ToggleBookmarks, "Toggle bookmarks"),
+    ActionEntry::new(ViewerAction::ToggleAnnotations, "Toggle annotations"),
+    ActionEntry::new(ViewerAction::ToggleTour, "Toggle tour"),
+    ActionEntry::new(ViewerAction::ToggleHighlights, "Toggle regions of interest"),
+    ActionEntry::new(ViewerAction::ToggleShare, "Toggle share"),
+    ActionEntry::new(ViewerAction::ToggleDiagnostics, "Toggle diagnostics"),
+    ActionEntry::new(ViewerAction::ToggleConsole, "Toggle console"),
+    ActionEntry::new(ViewerAction::TogglePrintExport, "Export for printing"),
+    ActionEntry::new(ViewerAction::ToggleContactSheet, "Export contact sheet"),
+    ActionEntry::new(ViewerAction::ToggleDataSaver, "Toggle data saver"),
+    ActionEntry::new(ViewerAction::ToggleGrid, "Toggle grid"),
+    ActionEntry::new(ViewerAction::ToggleRulers, "Toggle rulers"),
+    ActionEntry::new(ViewerAction::ToggleEyedropper, "Toggle eyedropper")
+        .with_shortcut(Modifiers::NONE, Key::I),
+    ActionEntry::new(ViewerAction::CopyView, "Copy view to clipboard"),
+    ActionEntry::new(ViewerAction::ToggleBlink, "Compare by blinking the overlay"),
+];
+
+/// Whether the key binding is of a key as typed, without a modifier or with Shift only, which is
+/// matched exactly and left to the widget with the keyboard focus.
+pub(crate) fn is_typed_shortcut(shortcut: &KeyboardShortcut) -> bool {
+    shortcut.modifiers.is_none() || shortcut.modifiers == Modifiers::SHIFT
+}
+
+impl ViewerAction {
+    /// Whether the action is supported on the platform, e.g. the export for printing only
+    /// natively on desktop.
+    pub(crate) fn is_supported(&self) -> bool {
+        match self {
+            Self::TogglePrintExport | Self::ToggleContactSheet => {
+                cfg!(not(any(target_arch = "wasm32", target_os = "android")))
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Command palette listing the actions of the viewer by their names matching the query.
+#[derive(Resource, Debug, Default)]
+pub(crate) struct CommandPalette {
+    pub(crate) open: bool,
+    pub(crate) query: String,
+    /// Index of the selected action among the matching ones.
+    pub(crate) selected: usize,
+}
+
+impl CommandPalette {
+    /// Open the palette with an empty query, or close it.
+    fn toggle(&mut self) {
+        self.open = !self.open;
+        self.query.clear();
+        self.selected = 0;
+    }
+
+    /// Get the supported actions matching the query, the best match first, other than the palette
+    /// itself.
+    pub(crate) fn get_matches(&self) -> Vec<&'static ActionEntry> {
+        let mut matches = ACTIONS
+            .iter()
+            .filter(|x| x.action.is_supported() && x.action != ViewerAction::CommandPalette)
+            .filter_map(|x| Some((fuzzy_score(&self.query, x.name)?, x)))
+            .collect::<Vec<_>>();
+
+        // Stable to keep the order of the registry for the same scores.
+        matches.sort_by_key(|(score, _)| -score);
+        matches.into_iter().map(|(_, x)| x).collect()
+    }
+}
+
+/// Get the score of the name matching the query, e.g. "tg" for "Toggle grid", or `None` if the
+/// chars of the query are not all in the name in order. The matches at the starts of the words
+/// and right after each other score higher. The case and the spaces in the query are ignored.
+pub(crate) fn fuzzy_score(query: &str, name: &str) -> Option<i32> {
+    let query = query
+        .chars()
+        .filter(|x| !x.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect::<Vec<_>>();
+    let name = name
+        .chars()
+        .flat_map(char::to_lowercase)
+        .collect::<Vec<_>>();
+
+    let Some((first, rest)) = query.split_first() else {
+        return Some(0);
+    };
+
+    let get_char_score = |index: usize| {
+        if index == 0 || !name[index - 1].is_alphanumeric() {
+            1 + WORD_START_BONUS
+        } else {
+            1
+        }
+    };
+
+    // Best score of the query so far with its last char matched at each char of the name.
+    let mut scores = (0..name.len())
+        .map(|i| (name[i] == *first).then(|| get_char_score(i)))
+        .collect::<Vec<_>>();
+
+    for c in rest {
+        // Best score with the last char matched before the previous char of the name.
+        let mut best_before = None;
+
+        scores = (0..name.len())
+            .map(|i| {
+                let previous = i.checked_sub(1).and_then(|x| scores[x]);
+                let score = best_before.max(previous.map(|x| x + CONSECUTIVE_BONUS));
+
+                best_before = best_before.max(previous);
+
+                score
+                    .filter(|_| name[i] == *c)
+                    .map(|x| x + get_char_score(i))
+            })
+            .collect();
+    }
+
+    scores.into_iter().flatten().max()
+}
+
+/// Run the actions from the key bindings and the command palette.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn action_system(
+    mut commands: Commands,
+    mut action_reader: MessageReader<ViewerAction>,
+    mut command_palette: ResMut<CommandPalette>,
+    mut egui_ui_state: ResMut<EguiUiState>,
+    mut app_state: ResMut<AppState>,
+    mut app_settings: ResMut<AppSettings>,
+    mut view_clipboard: ResMut<ViewClipboard>,
+    presentation: Option<Single<&Manifest>>,
+    model_image_query: Query<Entity, With<ModelImage>>,
+    mut camera_command_writer: MessageWriter<CameraCommand>,
+    mut notification_writer: MessageWriter<UserNotification>,
+    mut redraw_request_writer: MessageWriter<RequestRedraw>,
+    mut manifest_reload: ResMut<ManifestReload>,
+    mut eyedropper: ResMut<Eyedropper>,
+) {
+    for action in action_reader.read() {
+        let state = &mut *egui_ui_state;
+
+        match action {
+            ViewerAction::CommandPalette => command_palette.toggle(),
+            ViewerAction::OpenManifest => {
+                state.open_address_bar = true;
+                state.focus_address_bar = true;
+            }
+            ViewerAction::ReloadManifest => manifest_reload.requested = true,
+            ViewerAction::PreviousPage | ViewerAction::NextPage => {
+                let Some(presentation) = &presentation else {
+                    continue;
+                };
+                let sequence_index = app_state.sequence_index;
+                let num_canvases = presentation
+                    .model()
+                    .get_sequence(sequence_index)
+                    .map(|x| x.get_canvases().len())
+                    .unwrap_or_default();
+                let canvas_index = match action {
+                    ViewerAction::PreviousPage => app_state.canvas_index.saturating_sub(1),
+                    _ => (app_state.canvas_index + 1).min(num_canvases.saturating_sub(1)),
+                };
+
+                if canvas_index == app_state.canvas_index {
+                    continue;
+                }
+
+                if let Err(e) = web::load_canvas(
+                    &mut commands,
+                    presentation,
+                    &mut app_state,
+                    &app_settings,
+                    sequence_index,
+                    canvas_index,
+                    0,
+                    &model_image_query,
+                ) {
+                    notification_writer.write(UserNotification::Error(format!(
+                        "Unable to load canvas.\n'{}'",
+                        e
+                    )));
+                }
+            }
+            ViewerAction::ZoomIn
+            | ViewerAction::ZoomOut
+            | ViewerAction::ZoomInFinely
+            | ViewerAction::ZoomOutFinely => {
+                let speed = match action {
+                    ViewerAction::ZoomInFinely | ViewerAction::ZoomOutFinely => {
+                        app_settings.precision_settings.precision_multiplier
+                    }
+                    _ => 1.0,
+                };
+                let scale = match action {
+                    ViewerAction::ZoomIn | ViewerAction::ZoomInFinely => 1.0 - ZOOM_STEP * speed,
+                    _ => 1.0 + ZOOM_STEP * speed,
+                };
+
+                camera_command_writer.write(CameraCommand::Zoom(1.0 / scale));
+            }
+            ViewerAction::CycleFitMode => {
+                app_settings.fit_mode = app_settings.fit_mode.next();
+                camera_command_writer.write(CameraCommand::Fit(app_settings.fit_mode));
+            }
+            ViewerAction::ToggleSidePanel => state.open_left_panel = !state.open_left_panel,
+            ViewerAction::ToggleSettings => {
+                state.open_settings = !state.open_settings;
+                state.proxy_prefix = app_settings.proxy_prefix.to_string();
+                state.languages = app_settings.language.to_string();
+            }
+            ViewerAction::ToggleNotifications => {
+                state.open_notifications = !state.open_notifications
+            }
+            ViewerAction::ToggleBookmarks => state.open_bookmarks = !state.open_bookmarks,
+            ViewerAction::ToggleAnnotations => state.open_annotations = !state.open_annotations,
+            ViewerAction::ToggleTour => state.open_tour = !state.open_tour,
+            ViewerAction::ToggleHighlights => state.open_highlights = !state.open_highlights,
+            ViewerAction::ToggleShare => state.open_share = !state.open_share,
+            ViewerAction::ToggleDiagnostics => state.open_diagnostics = !state.open_diagnostics,
+            ViewerAction::ToggleConsole => state.open_console = !state.open_console,
+            ViewerAction::TogglePrintExport => state.open_print_export = !state.open_print_export,
+            ViewerAction::ToggleContactSheet => {
+                state.open_contact_sheet = !state.open_contact_sheet
+            }
+            ViewerAction::ToggleDataSaver => app_settings.data_saver = !app_settings.data_saver,
+            ViewerAction::ToggleGrid => {
+                app_settings.grid_settings.show_grid = !app_settings.grid_settings.show_grid
+            }
+            ViewerAction::ToggleRulers => {
+                app_settings.grid_settings.show_rulers = !app_settings.grid_settings.show_rulers
+            }
+            ViewerAction::ToggleEyedropper => eyedropper.enabled = !eyedropper.enabled,
+            ViewerAction::CopyView => view_clipboard.requested = true,
+            ViewerAction::ToggleBlink => {
+                if app_state.overlay_choice_index.is_none() {
+                    notification_writer.write(UserNotification::Error(
+                        "Choose an image to overlay to compare with.".to_string(),
+                    ));
+                    continue;
+                }
+
+                let comparison = &mut app_state.comparison;
+
+                comparison.mode = match comparison.mode {
+                    ComparisonMode::Blink => ComparisonMode::Off,
+                    _ => ComparisonMode::Blink,
+                };
+            }
+        }
+
+        redraw_request_writer.write(RequestRedraw);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert_eq!(fuzzy_score("", "Next page"), Some(0));
+        assert_eq!(fuzzy_score("np", "Next page"), Some(8));
+        // The word start is preferred to the first "g" of "Toggle".
+        assert_eq!(fuzzy_score("tg", "Toggle grid"), Some(8));
+        assert_eq!(fuzzy_score("GRID", "Toggle grid"), Some(13));
+        assert_eq!(fuzzy_score("tog grid", "Toggle grid"), Some(23));
+        assert_eq!(fuzzy_score("dg", "Toggle grid"), None);
+        assert_eq!(fuzzy_score("xyz", "Next page"), None);
+    }
+
+    #[test]
+    fn test_is_typed_shortcut() {
+        assert!(is_typed_shortcut(&KeyboardShortcut::new(
+            Modifiers::NONE,
+            Key::F
+        )));
+        assert!(is_typed_shortcut(&KeyboardShortcut::new(
+            Modifiers::SHIFT,
+            Key::Z
+        )));
+        assert!(!is_typed_shortcut(&KeyboardShortcut::new(
+            Modifiers::COMMAND,
+            Key::K
+        )));
+    }
+
+    #[test]
+    fn test_command_palette_matches() {
+        let mut command_palette = CommandPalette::default();
+
+        assert_eq!(
+            command_palette.get_matches().len(),
+            ACTIONS.iter().filter(|x| x.action.is_supported()).count() - 1
+        );
+
+        command_palette.query = "rulers".to_string();
+        assert_eq!(
+            command_palette.get_matches()[0].action,
+            ViewerAction::ToggleRulers
+        );
+
+        command_palette.query = "zoom in".to_string();
+        assert_eq!(
+            command_palette.get_matches()[0].action,
+            ViewerAction::ZoomIn
+        );
+
+        command_palette.query = "np".to_string();
+        assert_eq!(
+            command_palette.get_matches()[0].action,
+            ViewerAction::NextPage
+        );
+    }
+}
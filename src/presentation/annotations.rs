@@ -0,0 +1,263 @@
+use crate::{
+    app::storage,
+    iiif::IiifError,
+    presentation::bookmarks::{get_xywh, parse_xywh},
+};
+use bevy::prelude::{Rect, Resource, Vec2, warn};
+use serde_json::json;
+
+/// Key of the authored annotations in the storage.
+const ANNOTATIONS_KEY: &str = "annotations";
+
+/// Rectangle drawn by the user on a canvas with a comment.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct AuthoredAnnotation {
+    pub(crate) presentation_url: String,
+    pub(crate) canvas_id: String,
+    /// Region of the rectangle in the full size image.
+    pub(crate) region: Rect,
+    pub(crate) comment: String,
+}
+
+impl AuthoredAnnotation {
+    /// Get the annotation as a W3C web annotation commenting on the region of the canvas.
+    fn to_annotation(&self, index: usize) -> serde_json::Value {
+        json!({
+            "id": format!("{}/annotation/authored-{}", self.canvas_id, index + 1),
+            "type": "Annotation",
+            "motivation": "commenting",
+            "body": {
+                "type": "TextualBody",
+                "value": self.comment,
+                "format": "text/plain"
+            },
+            "target": {
+                "type": "SpecificResource",
+                "source": {
+                    "id": self.canvas_id,
+                    "type": "Canvas",
+                    "partOf": [{ "id": self.presentation_url, "type": "Manifest" }]
+                },
+                "selector": {
+                    "type": "FragmentSelector",
+                    "conformsTo": "http://www.w3.org/TR/media-frags/",
+                    "value": get_xywh(self.region)
+                }
+            }
+        })
+    }
+
+    /// Get the annotation from a W3C web annotation, or `None` if it does not target a region of
+    /// a canvas in a manifest.
+    fn from_annotation(annotation: &serde_json::Value) -> Option<Self> {
+        let target = &annotation["target"];
+        let region = target["selector"]["value"].as_str().and_then(parse_xywh)?;
+
+        Some(Self {
+            presentation_url: target["source"]["partOf"][0]["id"].as_str()?.to_string(),
+            canvas_id: target["source"]["id"].as_str()?.to_string(),
+            region,
+            comment: annotation["body"]["value"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+        })
+    }
+}
+
+/// Get the annotations as the JSON of an annotation page.
+fn to_annotation_page<'a>(annotations: impl Iterator<Item = &'a AuthoredAnnotation>) -> String {
+    let page = json!({
+        "@context": "http://iiif.io/api/presentation/3/context.json",
+        "type": "AnnotationPage",
+        "items": annotations
+            .enumerate()
+            .map(|(index, x)| x.to_annotation(index))
+            .collect::<Vec<_>>()
+    });
+
+    serde_json::to_string_pretty(&page).unwrap_or_default()
+}
+
+/// Get the annotations from the JSON of an annotation page, skipping the ones not targeting
+/// a region of a canvas.
+fn try_from_annotation_page(json: &str) -> Result<Vec<AuthoredAnnotation>, IiifError> {
+    let page: serde_json::Value = serde_json::from_str(json)?;
+    let items = page["items"].as_array().ok_or(IiifError::IiifMissingInfo(
+        "missing 'items' of the annotation page".to_string(),
+    ))?;
+
+    Ok(items
+        .iter()
+        .filter_map(AuthoredAnnotation::from_annotation)
+        .collect())
+}
+
+/// Annotations drawn by the user, kept in the storage.
+#[derive(Resource)]
+pub(crate) struct AnnotationAuthoring {
+    pub(crate) items: Vec<AuthoredAnnotation>,
+    /// Whether dragging on the image draws a rectangle instead of panning.
+    pub(crate) drawing: bool,
+    /// Start and end of the rectangle being drawn in the full size image.
+    pub(crate) drag: Option<(Vec2, Vec2)>,
+    /// Path of the file to export to.
+    pub(crate) file_path: String,
+}
+
+impl Default for AnnotationAuthoring {
+    fn default() -> Self {
+        let items = storage::read(ANNOTATIONS_KEY)
+            .and_then(|json| {
+                try_from_annotation_page(&json)
+                    .inspect_err(|e| warn!("Ignore the saved annotations. {}", e))
+                    .ok()
+            })
+            .unwrap_or_default();
+
+        Self {
+            items,
+            drawing: false,
+            drag: None,
+            file_path: "annotations.json".to_string(),
+        }
+    }
+}
+
+impl AnnotationAuthoring {
+    /// Keep the annotations in the storage.
+    pub(crate) fn save(&self) {
+        if let Err(e) = storage::write(ANNOTATIONS_KEY, &to_annotation_page(self.items.iter())) {
+            warn!("Failed to save the annotations. {}", e);
+        }
+    }
+
+    /// Get the indices of the annotations on the canvas.
+    pub(crate) fn get_canvas_items(&self, presentation_url: &str, canvas_id: &str) -> Vec<usize> {
+        self.items
+            .iter()
+            .enumerate()
+            .filter(|(_, x)| x.presentation_url == presentation_url && x.canvas_id == canvas_id)
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Add the rectangle being drawn with an empty comment if it is not empty, and get its index.
+    pub(crate) fn finish_drawing(
+        &mut self,
+        presentation_url: &str,
+        canvas_id: &str,
+        image_rect: Rect,
+    ) -> Option<usize> {
+        let (start, end) = self.drag.take()?;
+        let region = Rect::from_corners(start, end).intersect(image_rect);
+
+        if region.width() < 1.0 || region.height() < 1.0 {
+            return None;
+        }
+
+        self.items.push(AuthoredAnnotation {
+            presentation_url: presentation_url.to_string(),
+            canvas_id: canvas_id.to_string(),
+            region,
+            comment: "".to_string(),
+        });
+
+        Some(self.items.len() - 1)
+    }
+
+    /// Get the annotations of the manifest as the JSON of an annotation page.
+    pub(crate) fn get_annotation_page(&self, presentation_url: &str) -> String {
+        to_annotation_page(
+            self.items
+                .iter()
+                .filter(|x| x.presentation_url == presentation_url),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_authoring() -> AnnotationAuthoring {
+        AnnotationAuthoring {
+            items: vec![],
+            drawing: true,
+            drag: None,
+            file_path: "".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_finish_drawing() {
+        let mut authoring = get_authoring();
+        let image_rect = Rect::new(0.0, 0.0, 1000.0, 800.0);
+
+        authoring.drag = Some((Vec2::new(300.0, 500.0), Vec2::new(-50.0, 200.0)));
+        assert_eq!(
+            authoring.finish_drawing(
+                "https://example.org/m",
+                "https://example.org/c1",
+                image_rect
+            ),
+            Some(0)
+        );
+        assert_eq!(
+            authoring.items[0].region,
+            Rect::new(0.0, 200.0, 300.0, 500.0)
+        );
+        assert!(authoring.drag.is_none());
+
+        // A click without dragging does not add an annotation.
+        authoring.drag = Some((Vec2::new(300.0, 500.0), Vec2::new(300.0, 500.0)));
+        assert_eq!(
+            authoring.finish_drawing(
+                "https://example.org/m",
+                "https://example.org/c1",
+                image_rect
+            ),
+            None
+        );
+        assert_eq!(
+            authoring.get_canvas_items("https://example.org/m", "https://example.org/c1"),
+            vec![0]
+        );
+        assert!(
+            authoring
+                .get_canvas_items("https://example.org/m", "https://example.org/c2")
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_annotation_page() {
+        let mut authoring = get_authoring();
+        let annotation = AuthoredAnnotation {
+            presentation_url: "https://example.org/m".to_string(),
+            canvas_id: "https://example.org/c1".to_string(),
+            region: Rect::new(10.0, 20.0, 110.0, 70.0),
+            comment: "Signature".to_string(),
+        };
+
+        authoring.items.push(annotation.clone());
+        authoring.items.push(AuthoredAnnotation {
+            presentation_url: "https://example.org/other".to_string(),
+            ..annotation.clone()
+        });
+
+        let json = authoring.get_annotation_page("https://example.org/m");
+        let page: serde_json::Value = serde_json::from_str(&json).expect("should be JSON");
+
+        assert_eq!(page["items"].as_array().map(|x| x.len()), Some(1));
+        assert_eq!(page["items"][0]["motivation"], "commenting");
+        assert_eq!(
+            page["items"][0]["target"]["selector"]["value"],
+            "xywh=10,20,100,50"
+        );
+        assert_eq!(
+            try_from_annotation_page(&json).expect("should parse the annotations"),
+            vec![annotation]
+        );
+    }
+}
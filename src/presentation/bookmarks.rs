@@ -0,0 +1,453 @@
+use crate::{
+    app::{app_settings::AppSettings, app_state::AppState, storage},
+    camera::{
+        camera_ext::get_world_viewport_rect,
+        main_camera::{CameraCommand, MainCamera2d},
+    },
+    iiif::IiifError,
+    notification::UserNotification,
+    presentation::manifest::Manifest,
+    rendering::{model_image::ModelImage, tiled_image::TiledImage},
+    web,
+};
+use bevy::{
+    prelude::{
+        Camera, Commands, Entity, GlobalTransform, MessageWriter, Query, Rect, Res, ResMut,
+        Resource, Result, Single, With, info, warn,
+    },
+    window::RequestRedraw,
+};
+use serde_json::json;
+
+/// Key of the bookmarks in the storage.
+const BOOKMARKS_KEY: &str = "bookmarks";
+
+/// Region of a canvas saved by the user with a note.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Bookmark {
+    pub(crate) name: String,
+    pub(crate) note: String,
+    pub(crate) presentation_url: String,
+    pub(crate) canvas_id: String,
+    /// Region of the view in the full size image.
+    pub(crate) region: Rect,
+    /// Thumbnail URL of the canvas.
+    pub(crate) thumbnail: String,
+}
+
+impl Bookmark {
+    /// Get the bookmark as a W3C web annotation with a fragment selector of the region.
+    fn to_annotation(&self) -> serde_json::Value {
+        json!({
+            "type": "Annotation",
+            "motivation": "bookmarking",
+            "label": { "none": [self.name] },
+            "body": {
+                "type": "TextualBody",
+                "value": self.note,
+                "format": "text/plain"
+            },
+            "target": {
+                "type": "SpecificResource",
+                "source": {
+                    "id": self.canvas_id,
+                    "type": "Canvas",
+                    "partOf": [{ "id": self.presentation_url, "type": "Manifest" }]
+                },
+                "selector": {
+                    "type": "FragmentSelector",
+                    "conformsTo": "http://www.w3.org/TR/media-frags/",
+                    "value": get_xywh(self.region)
+                }
+            },
+            "thumbnail": [{ "id": self.thumbnail, "type": "Image" }]
+        })
+    }
+
+    /// Get the bookmark from a W3C web annotation, or `None` if it does not target a region of
+    /// a canvas in a manifest.
+    fn from_annotation(annotation: &serde_json::Value) -> Option<Self> {
+        let target = &annotation["target"];
+        let region = target["selector"]["value"].as_str().and_then(parse_xywh)?;
+
+        Some(Self {
+            name: annotation["label"]
+                .as_object()
+                .and_then(|x| x.values().next())
+                .and_then(|x| x[0].as_str())
+                .unwrap_or_default()
+                .to_string(),
+            note: annotation["body"]["value"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            presentation_url: target["source"]["partOf"][0]["id"].as_str()?.to_string(),
+            canvas_id: target["source"]["id"].as_str()?.to_string(),
+            region,
+            thumbnail: annotation["thumbnail"][0]["id"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+        })
+    }
+}
+
+/// Get the media fragment of the region in whole pixels, e.g. "xywh=10,20,100,50".
+pub(crate) fn get_xywh(region: Rect) -> String {
+    format!(
+        "xywh={},{},{},{}",
+        region.min.x.round(),
+        region.min.y.round(),
+        region.width().round(),
+        region.height().round()
+    )
+}
+
+/// Parse the region of a media fragment, e.g. "xywh=10,20,100,50".
+pub(crate) fn parse_xywh(value: &str) -> Option<Rect> {
+    let values = value
+        .strip_prefix("xywh=")?
+        .trim_start_matches("pixel:")
+        .split(',')
+        .map(|x| x.trim().parse::<f32>().ok())
+        .collect::<Option<Vec<_>>>()?;
+
+    let [x, y, w, h] = values[..] else {
+        return None;
+    };
+
+    Some(Rect::new(x, y, x + w, y + h))
+}
+
+/// Get the bookmarks as the JSON of an annotation page.
+fn to_annotation_page(bookmarks: &[Bookmark]) -> String {
+    let page = json!({
+        "@context": "http://iiif.io/api/presentation/3/context.json",
+        "type": "AnnotationPage",
+        "items": bookmarks.iter().map(Bookmark::to_annotation).collect::<Vec<_>>()
+    });
+
+    serde_json::to_string_pretty(&page).unwrap_or_default()
+}
+
+/// Get the bookmarks from the JSON of an annotation page, skipping the annotations which are not
+/// bookmarks.
+fn try_from_annotation_page(json: &str) -> Result<Vec<Bookmark>, IiifError> {
+    let page: serde_json::Value = serde_json::from_str(json)?;
+    let items = page["items"].as_array().ok_or(IiifError::IiifMissingInfo(
+        "missing 'items' of the annotation page".to_string(),
+    ))?;
+
+    Ok(items.iter().filter_map(Bookmark::from_annotation).collect())
+}
+
+/// Action requested from the bookmarks panel.
+pub(crate) enum BookmarkRequest {
+    /// Add the current view with the name and the note being edited.
+    Add,
+    /// Jump to the bookmark at the index.
+    Jump(usize),
+    /// Remove the bookmark at the index.
+    Remove(usize),
+    /// Export the bookmarks to the file.
+    Export,
+    /// Import the bookmarks from the file.
+    Import,
+}
+
+/// Bookmarks of the user, kept in the storage.
+#[derive(Resource)]
+pub(crate) struct Bookmarks {
+    pub(crate) items: Vec<Bookmark>,
+    /// Name of the new bookmark being edited.
+    pub(crate) name: String,
+    /// Note of the new bookmark being edited.
+    pub(crate) note: String,
+    /// Path of the file to export to or import from.
+    pub(crate) file_path: String,
+    /// Action requested from the panel, handled with the access to the camera and the image.
+    pub(crate) request: Option<BookmarkRequest>,
+}
+
+impl Default for Bookmarks {
+    fn default() -> Self {
+        let items = storage::read(BOOKMARKS_KEY)
+            .and_then(|json| {
+                try_from_annotation_page(&json)
+                    .inspect_err(|e| warn!("Ignore the saved bookmarks. {}", e))
+                    .ok()
+            })
+            .unwrap_or_default();
+
+        Self {
+            items,
+            name: "".to_string(),
+            note: "".to_string(),
+            file_path: "bookmarks.json".to_string(),
+            request: None,
+        }
+    }
+}
+
+impl Bookmarks {
+    /// Keep the bookmarks in the storage.
+    fn save(&self) {
+        if let Err(e) = storage::write(BOOKMARKS_KEY, &to_annotation_page(&self.items)) {
+            warn!("Failed to save the bookmarks. {}", e);
+        }
+    }
+
+    /// Add the bookmarks which are not there yet.
+    fn merge(&mut self, bookmarks: Vec<Bookmark>) -> usize {
+        let num_items = self.items.len();
+
+        for bookmark in bookmarks {
+            if !self.items.contains(&bookmark) {
+                self.items.push(bookmark);
+            }
+        }
+
+        self.items.len() - num_items
+    }
+}
+
+/// Handle the action requested from the bookmarks panel.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn bookmarks_system(
+    mut commands: Commands,
+    mut bookmarks: ResMut<Bookmarks>,
+    mut app_state: ResMut<AppState>,
+    app_settings: Res<AppSettings>,
+    presentation: Option<Single<&Manifest>>,
+    tiled_image: Option<Single<&TiledImage>>,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera2d>>,
+    model_image_query: Query<Entity, With<ModelImage>>,
+    mut camera_command_writer: MessageWriter<CameraCommand>,
+    mut messages: MessageWriter<UserNotification>,
+    mut redraw_request_writer: MessageWriter<RequestRedraw>,
+) -> Result {
+    if bookmarks.request.is_none() {
+        return Ok(());
+    }
+
+    let (camera, global_transform) = camera.into_inner();
+
+    match bookmarks.request.take() {
+        Some(BookmarkRequest::Add) => {
+            let (Some(presentation), Some(tiled_image)) = (presentation, tiled_image) else {
+                messages.write(UserNotification::Error(
+                    "Only the views of images can be bookmarked.".to_string(),
+                ));
+                return Ok(());
+            };
+            let Some((world_pos_min, world_pos_max)) =
+                get_world_viewport_rect(camera, global_transform)
+            else {
+                return Ok(());
+            };
+
+            let canvas = presentation
+                .model()
+                .get_sequence(app_state.sequence_index)?
+                .get_canvas(app_state.canvas_index)?;
+            let region = tiled_image
+                .world_to_image_rect(Rect::from_corners(
+                    world_pos_min.truncate(),
+                    world_pos_max.truncate(),
+                ))
+                .intersect(tiled_image.get_image_max_size_rect());
+            let name = if bookmarks.name.trim().is_empty() {
+                format!(
+                    "({}) {}",
+                    app_state.canvas_index + 1,
+                    canvas
+                        .get_label(&app_settings.language)
+                        .collect::<Vec<_>>()
+                        .join(",")
+                )
+            } else {
+                bookmarks.name.trim().to_string()
+            };
+
+            let bookmark = Bookmark {
+                name,
+                note: bookmarks.note.trim().to_string(),
+                presentation_url: app_state.presentation_url.to_string(),
+                canvas_id: canvas.get_id().to_string(),
+                region,
+                thumbnail: Some(canvas.get_thumbnail())
+                    .filter(|x| !x.is_empty())
+                    .unwrap_or_else(|| presentation.model().get_thumbnail())
+                    .to_string(),
+            };
+
+            bookmarks.items.push(bookmark);
+            bookmarks.name.clear();
+            bookmarks.note.clear();
+            bookmarks.save();
+        }
+        Some(BookmarkRequest::Jump(index)) => {
+            let Some(bookmark) = bookmarks.items.get(index) else {
+                return Ok(());
+            };
+
+            if bookmark.presentation_url != app_state.presentation_url {
+                app_state.target_canvas_id = Some(bookmark.canvas_id.to_string());
+                app_state.target_region = Some(bookmark.region);
+                web::load_presentation(&mut app_state, &app_settings, &bookmark.presentation_url);
+                return Ok(());
+            }
+
+            let Some(presentation) = presentation else {
+                return Ok(());
+            };
+            let Some((sequence_index, canvas_index)) = web::get_canvas_index_by_id(
+                &presentation,
+                app_state.sequence_index,
+                &bookmark.canvas_id,
+            ) else {
+                messages.write(UserNotification::Error(format!(
+                    "Canvas '{}' of the bookmark is not in the manifest.",
+                    bookmark.canvas_id
+                )));
+                return Ok(());
+            };
+
+            if sequence_index != app_state.sequence_index
+                || canvas_index != app_state.canvas_index
+                || tiled_image.is_none()
+            {
+                app_state.target_region = Some(bookmark.region);
+                web::load_canvas(
+                    &mut commands,
+                    &presentation,
+                    &mut app_state,
+                    &app_settings,
+                    sequence_index,
+                    canvas_index,
+                    0,
+                    &model_image_query,
+                )?;
+            } else {
+                camera_command_writer.write(CameraCommand::ZoomToImageRect(bookmark.region));
+            }
+        }
+        Some(BookmarkRequest::Remove(index)) => {
+            if index < bookmarks.items.len() {
+                bookmarks.items.remove(index);
+                bookmarks.save();
+            }
+        }
+        Some(BookmarkRequest::Export) => {
+            let file_path = bookmarks.file_path.trim().to_string();
+
+            match std::fs::write(&file_path, to_annotation_page(&bookmarks.items)) {
+                Ok(_) => {
+                    info!(
+                        "Exported {} bookmarks to {}",
+                        bookmarks.items.len(),
+                        file_path
+                    );
+                }
+                Err(e) => {
+                    messages.write(UserNotification::Error(format!(
+                        "Failed to export the bookmarks to '{}'.\n{}",
+                        file_path, e
+                    )));
+                }
+            }
+        }
+        Some(BookmarkRequest::Import) => {
+            let file_path = bookmarks.file_path.trim().to_string();
+
+            match std::fs::read_to_string(&file_path)
+                .map_err(IiifError::from)
+                .and_then(|json| try_from_annotation_page(&json))
+            {
+                Ok(items) => {
+                    let num_added = bookmarks.merge(items);
+
+                    bookmarks.save();
+                    info!("Imported {} bookmarks from {}", num_added, file_path);
+                }
+                Err(e) => {
+                    messages.write(UserNotification::Error(format!(
+                        "Failed to import the bookmarks from '{}'.\n{}",
+                        file_path, e
+                    )));
+                }
+            }
+        }
+        None => {}
+    }
+
+    redraw_request_writer.write(RequestRedraw);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_bookmark() -> Bookmark {
+        Bookmark {
+            name: "Initial".to_string(),
+            note: "Decorated initial on the first page".to_string(),
+            presentation_url: "https://example.org/manifest.json".to_string(),
+            canvas_id: "https://example.org/canvas/1".to_string(),
+            region: Rect::new(10.0, 20.0, 110.0, 70.0),
+            thumbnail: "https://example.org/thumbnail.jpg".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_xywh() {
+        assert_eq!(
+            parse_xywh("xywh=10,20,100,50"),
+            Some(Rect::new(10.0, 20.0, 110.0, 70.0))
+        );
+        assert_eq!(
+            parse_xywh("xywh=pixel:10,20,100,50"),
+            Some(Rect::new(10.0, 20.0, 110.0, 70.0))
+        );
+        assert_eq!(parse_xywh("xywh=10,20,100"), None);
+        assert_eq!(parse_xywh("t=10"), None);
+    }
+
+    #[test]
+    fn test_annotation_page() {
+        let bookmark = get_bookmark();
+        let json = to_annotation_page(std::slice::from_ref(&bookmark));
+
+        assert!(json.contains("\"value\": \"xywh=10,20,100,50\""));
+        assert_eq!(
+            try_from_annotation_page(&json).expect("should parse the bookmarks"),
+            vec![bookmark]
+        );
+
+        let json = r#"{ "type": "AnnotationPage", "items": [{ "type": "Annotation", "target": "https://example.org/canvas/1" }] }"#;
+
+        assert_eq!(
+            try_from_annotation_page(json).expect("should parse the annotation page"),
+            vec![]
+        );
+        assert!(try_from_annotation_page("{}").is_err());
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut bookmarks = Bookmarks {
+            items: vec![get_bookmark()],
+            name: "".to_string(),
+            note: "".to_string(),
+            file_path: "".to_string(),
+            request: None,
+        };
+        let mut other = get_bookmark();
+        other.name = "Other".to_string();
+
+        assert_eq!(bookmarks.merge(vec![get_bookmark(), other]), 1);
+        assert_eq!(bookmarks.items.len(), 2);
+    }
+}
@@ -0,0 +1,190 @@
+use crate::{
+    app::app_settings::AppSettings,
+    iiif::{IiifError, manifest},
+    presentation::{
+        manifest::Manifest,
+        model::{IsCollection, IsManifest},
+    },
+};
+use bevy::{
+    prelude::{Component, MessageWriter, Res, Resource, Single, debug},
+    window::RequestRedraw,
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+/// Maximum number of member manifests fetched at the same time for the search.
+const MAX_CONCURRENT_FETCHES: usize = 4;
+
+#[derive(Component)]
+/// Presentation collection.
+pub(crate) struct Collection {
+    inner: Box<dyn IsCollection>,
+    url: String,
+}
+
+impl Collection {
+    /// Get the reference of the inner collection.
+    pub(crate) fn model(&self) -> &dyn IsCollection {
+        self.inner.as_ref()
+    }
+
+    /// Get the URL of the collection.
+    pub(crate) fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Try to create the collection from JSON.
+    pub(crate) fn try_from_json(json: &str, url: &str) -> core::result::Result<Self, IiifError> {
+        Ok(Self {
+            inner: manifest::Collection::try_from_json(json)?,
+            url: url.to_string(),
+        })
+    }
+}
+
+/// Searchable text of a member manifest.
+enum MemberText {
+    Loading,
+    Loaded(String),
+    Failed,
+}
+
+/// Search across the labels and the metadata of the member manifests in the collection.
+#[derive(Resource, Default)]
+pub(crate) struct CollectionSearch {
+    /// Search query. The member manifests are fetched when it is not empty.
+    pub(crate) query: String,
+    /// Searchable text of the member manifests by URL.
+    member_texts: Arc<Mutex<HashMap<String, MemberText>>>,
+}
+
+impl CollectionSearch {
+    /// Reset the search for another collection.
+    pub(crate) fn clear(&mut self) {
+        self.query.clear();
+        // Replace the map so that the responses of the pending requests are dropped.
+        self.member_texts = Arc::default();
+    }
+
+    /// Get the indices of the members matching the query, and the number of the member
+    /// manifests searched so far.
+    pub(crate) fn get_matches(
+        &self,
+        collection: &Collection,
+        language: &str,
+    ) -> (Vec<usize>, usize) {
+        let query = self.query.to_lowercase();
+        let member_texts = self
+            .member_texts
+            .lock()
+            .expect("should be able to lock the member texts mutex");
+        let mut num_searched = 0;
+
+        let matches = collection
+            .model()
+            .get_members()
+            .enumerate()
+            .filter(|(_, member)| {
+                let member_text = member_texts.get(member.get_id().as_ref());
+
+                if matches!(
+                    member_text,
+                    Some(MemberText::Loaded(_)) | Some(MemberText::Failed)
+                ) {
+                    num_searched += 1;
+                }
+
+                member
+                    .get_label(language)
+                    .any(|x| x.to_lowercase().contains(&query))
+                    || matches!(member_text, Some(MemberText::Loaded(text)) if text.contains(&query))
+            })
+            .map(|(index, _)| index)
+            .collect();
+
+        (matches, num_searched)
+    }
+}
+
+/// Get the lowercase text of a manifest to search in.
+fn get_searchable_text(manifest: &dyn IsManifest, language: &str) -> String {
+    std::iter::once(manifest.get_title(language))
+        .chain(manifest.get_description(language))
+        .chain(manifest.get_attribution(language))
+        .chain(manifest.get_metadata(language))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .to_lowercase()
+}
+
+/// Fetch the member manifests lazily for the search, with a limit of concurrent requests.
+pub(crate) fn collection_search_system(
+    collection: Single<&Collection>,
+    collection_search: Res<CollectionSearch>,
+    app_settings: Res<AppSettings>,
+    mut redraw_request_writer: MessageWriter<RequestRedraw>,
+) {
+    if collection_search.query.is_empty() {
+        return;
+    }
+
+    let mut member_texts = collection_search
+        .member_texts
+        .lock()
+        .expect("should be able to lock the member texts mutex");
+    let num_loading = member_texts
+        .values()
+        .filter(|x| matches!(x, MemberText::Loading))
+        .count();
+    let mut num_available = MAX_CONCURRENT_FETCHES.saturating_sub(num_loading);
+
+    for member in collection.model().get_members() {
+        if num_available == 0 {
+            break;
+        }
+
+        let url = member.get_id().to_string();
+
+        if member.is_collection() || member_texts.contains_key(&url) {
+            continue;
+        }
+
+        debug!("Fetch member manifest {} for the search", url);
+
+        member_texts.insert(url.to_string(), MemberText::Loading);
+        num_available -= 1;
+
+        let shared_member_texts = Arc::clone(&collection_search.member_texts);
+        let language = app_settings.language.to_string();
+
+        let request =
+            ehttp::Request::get(crate::web::get_proxy_url(&app_settings.proxy_prefix, &url));
+
+        ehttp::fetch(request, move |result| {
+            let member_text = result
+                .ok()
+                .filter(|response| response.ok)
+                .and_then(|response| String::from_utf8(response.bytes).ok())
+                .and_then(|json| Manifest::try_from_json(&json).ok())
+                .map_or(MemberText::Failed, |manifest| {
+                    MemberText::Loaded(get_searchable_text(manifest.model(), &language))
+                });
+
+            shared_member_texts
+                .lock()
+                .expect("should be able to lock the member texts mutex")
+                .insert(url, member_text);
+        });
+    }
+
+    // Keep polling until all the requests are done.
+    if member_texts
+        .values()
+        .any(|x| matches!(x, MemberText::Loading))
+    {
+        redraw_request_writer.write(RequestRedraw);
+    }
+}
@@ -0,0 +1,247 @@
+use crate::{
+    app::{
+        app_settings::AppSettings,
+        app_state::{AppState, DownloadState},
+    },
+    camera::main_camera::CameraCommand,
+    iiif::IiifError,
+    presentation::manifest::Manifest,
+    rendering::model_image::ModelImage,
+    web,
+};
+use bevy::{
+    prelude::{Commands, Entity, MessageWriter, Query, Rect, Res, ResMut, Resource, Single, With},
+    window::RequestRedraw,
+};
+use std::collections::VecDeque;
+
+/// Max number of lines kept in the output of the console.
+const MAX_OUTPUT_LINES: usize = 200;
+
+/// Command typed in the console, run through the same commands as the UI.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ConsoleCommand {
+    /// Open the manifest or the collection at the URL.
+    Open(String),
+    /// Go to the canvas by its number, starting at 1.
+    GoTo(usize),
+    /// Zoom in by the factor, or out if less than 1.
+    Zoom(f32),
+    /// Zoom to the region of the full size image, by its x, y, width and height.
+    Region(Rect),
+}
+
+impl ConsoleCommand {
+    /// Parse the command from a line, e.g. "goto 12" or "region 100,200,400,300".
+    pub(crate) fn try_from_line(line: &str) -> Result<Self, IiifError> {
+        let (name, argument) = line
+            .trim()
+            .split_once(char::is_whitespace)
+            .map(|(name, argument)| (name, argument.trim()))
+            .unwrap_or((line.trim(), ""));
+        let missing = || IiifError::IiifFormatError(format!("argument of '{}' missing", name));
+
+        match name.to_lowercase().as_str() {
+            "open" if argument.is_empty() => Err(missing()),
+            "open" => Ok(Self::Open(argument.to_string())),
+            "goto" if argument.is_empty() => Err(missing()),
+            "goto" => match argument.parse::<usize>()? {
+                0 => Err(IiifError::IiifFormatError(
+                    "canvas numbers start at 1".to_string(),
+                )),
+                number => Ok(Self::GoTo(number)),
+            },
+            "zoom" => match argument.parse::<f32>() {
+                Ok(factor) if factor > 0.0 && factor.is_finite() => Ok(Self::Zoom(factor)),
+                _ => Err(IiifError::IiifFormatError(format!(
+                    "zoom factor '{}' not a positive number",
+                    argument
+                ))),
+            },
+            "region" => {
+                let values = argument
+                    .split(',')
+                    .map(|x| x.trim().parse::<f32>().ok())
+                    .collect::<Option<Vec<_>>>();
+
+                match values.as_deref() {
+                    Some(&[x, y, w, h]) if w > 0.0 && h > 0.0 => {
+                        Ok(Self::Region(Rect::new(x, y, x + w, y + h)))
+                    }
+                    _ => Err(IiifError::IiifFormatError(format!(
+                        "region '{}' not as x,y,width,height",
+                        argument
+                    ))),
+                }
+            }
+            _ => Err(IiifError::IiifUnsupportedError(format!(
+                "unknown command '{}'",
+                name
+            ))),
+        }
+    }
+}
+
+/// Commands typed by the user and their output, to script the viewer, e.g. to go through the
+/// same regions of several manifests.
+#[derive(Resource, Default)]
+pub(crate) struct Console {
+    pub(crate) input: String,
+    pub(crate) output: VecDeque<String>,
+    /// Lines submitted and not run yet, run one at a time once the previous one is loaded.
+    pending: VecDeque<String>,
+}
+
+impl Console {
+    /// Submit the lines of the input, separated by new lines or semicolons.
+    pub(crate) fn submit(&mut self) {
+        let input = std::mem::take(&mut self.input);
+
+        self.pending.extend(
+            input
+                .split(['\n', ';'])
+                .map(|x| x.trim())
+                .filter(|x| !x.is_empty())
+                .map(|x| x.to_string()),
+        );
+    }
+
+    fn print(&mut self, line: String) {
+        if self.output.len() >= MAX_OUTPUT_LINES {
+            self.output.pop_front();
+        }
+
+        self.output.push_back(line);
+    }
+}
+
+/// Run the next command submitted in the console once nothing is loading, so that the commands
+/// after `open` or `goto` apply to the loaded canvas.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn console_system(
+    mut commands: Commands,
+    mut console: ResMut<Console>,
+    mut app_state: ResMut<AppState>,
+    app_settings: Res<AppSettings>,
+    presentation: Option<Single<&Manifest>>,
+    model_image_query: Query<Entity, With<ModelImage>>,
+    mut camera_command_writer: MessageWriter<CameraCommand>,
+    mut redraw_request_writer: MessageWriter<RequestRedraw>,
+) {
+    if console.pending.is_empty() {
+        return;
+    }
+
+    let is_loading = [
+        &app_state.manifest_json_download_state,
+        &app_state.image_json_download_state,
+        &app_state.overlay_json_download_state,
+    ]
+    .iter()
+    .any(|x| {
+        x.lock()
+            .is_ok_and(|x| !matches!(*x, DownloadState::None | DownloadState::Error { .. }))
+    });
+
+    if is_loading {
+        return;
+    }
+
+    let Some(line) = console.pending.pop_front() else {
+        return;
+    };
+
+    console.print(format!("> {}", line));
+
+    let result = match ConsoleCommand::try_from_line(&line) {
+        Ok(ConsoleCommand::Open(url)) => {
+            web::load_presentation(&mut app_state, &app_settings, &url);
+            Ok(())
+        }
+        Ok(ConsoleCommand::GoTo(number)) => match presentation {
+            Some(presentation) => {
+                let sequence_index = app_state.sequence_index;
+
+                web::load_canvas(
+                    &mut commands,
+                    &presentation,
+                    &mut app_state,
+                    &app_settings,
+                    sequence_index,
+                    number - 1,
+                    0,
+                    &model_image_query,
+                )
+                .map_err(|e| e.to_string())
+            }
+            None => Err("No manifest is loaded.".to_string()),
+        },
+        Ok(ConsoleCommand::Zoom(factor)) => {
+            camera_command_writer.write(CameraCommand::Zoom(factor));
+            Ok(())
+        }
+        Ok(ConsoleCommand::Region(region)) => {
+            camera_command_writer.write(CameraCommand::ZoomToImageRect(region));
+            Ok(())
+        }
+        Err(e) => Err(e.to_string()),
+    };
+
+    if let Err(e) = result {
+        console.print(e);
+    }
+
+    redraw_request_writer.write(RequestRedraw);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_console_command_from_line() {
+        assert_eq!(
+            ConsoleCommand::try_from_line(" open https://example.org/manifest.json ").ok(),
+            Some(ConsoleCommand::Open(
+                "https://example.org/manifest.json".to_string()
+            ))
+        );
+        assert_eq!(
+            ConsoleCommand::try_from_line("goto 12").ok(),
+            Some(ConsoleCommand::GoTo(12))
+        );
+        assert_eq!(
+            ConsoleCommand::try_from_line("ZOOM 2.0").ok(),
+            Some(ConsoleCommand::Zoom(2.0))
+        );
+        assert_eq!(
+            ConsoleCommand::try_from_line("region 100, 200, 400, 300").ok(),
+            Some(ConsoleCommand::Region(Rect::new(
+                100.0, 200.0, 500.0, 500.0
+            )))
+        );
+
+        assert!(ConsoleCommand::try_from_line("open").is_err());
+        assert!(ConsoleCommand::try_from_line("goto 0").is_err());
+        assert!(ConsoleCommand::try_from_line("goto x").is_err());
+        assert!(ConsoleCommand::try_from_line("zoom -1").is_err());
+        assert!(ConsoleCommand::try_from_line("region 1,2,3").is_err());
+        assert!(ConsoleCommand::try_from_line("rotate 90").is_err());
+    }
+
+    #[test]
+    fn test_console_submit() {
+        let mut console = Console {
+            input: "open https://example.org/m.json; goto 2\n\n zoom 2 ".to_string(),
+            ..Default::default()
+        };
+
+        console.submit();
+
+        assert!(console.input.is_empty());
+        assert_eq!(
+            console.pending,
+            ["open https://example.org/m.json", "goto 2", "zoom 2"]
+        );
+    }
+}
@@ -0,0 +1,349 @@
+use crate::{
+    app::{app_settings::AppSettings, app_state::AppState},
+    camera::{camera_ext::get_world_viewport_rect, main_camera::MainCamera2d},
+    iiif::IiifError,
+    notification::UserNotification,
+    presentation::{
+        bookmarks::{get_xywh, parse_xywh},
+        manifest::Manifest,
+    },
+    rendering::tiled_image::TiledImage,
+    web,
+};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use bevy::{
+    prelude::{
+        Camera, GlobalTransform, MessageWriter, Rect, Res, ResMut, Resource, Result, Single, With,
+    },
+    window::RequestRedraw,
+};
+use serde_json::json;
+
+/// Name of the query parameter of the content state, e.g. of the page URL in wasm.
+pub(crate) const CONTENT_STATE_PARAM: &str = "iiif-content";
+
+/// View of a manifest shared as an IIIF content state, i.e. the canvas and its region.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ContentState {
+    pub(crate) presentation_url: String,
+    /// Canvas to show. The first one if `None`.
+    pub(crate) canvas_id: Option<String>,
+    /// Region of the full size image to zoom to. The whole image if `None`.
+    pub(crate) region: Option<Rect>,
+}
+
+impl ContentState {
+    /// Get the content state as an annotation targeting the canvas, or the manifest if no
+    /// canvas.
+    fn to_annotation(&self) -> serde_json::Value {
+        let part_of = json!([{ "id": self.presentation_url, "type": "Manifest" }]);
+        let target = match &self.canvas_id {
+            Some(canvas_id) => json!({
+                "id": match self.region {
+                    Some(region) => format!("{}#{}", canvas_id, get_xywh(region)),
+                    None => canvas_id.to_string(),
+                },
+                "type": "Canvas",
+                "partOf": part_of
+            }),
+            None => json!({ "id": self.presentation_url, "type": "Manifest" }),
+        };
+
+        json!({
+            "@context": "http://iiif.io/api/presentation/3/context.json",
+            "type": "Annotation",
+            "motivation": ["contentState"],
+            "target": target
+        })
+    }
+
+    /// Get the content state as JSON.
+    pub(crate) fn to_json(&self) -> String {
+        serde_json::to_string(&self.to_annotation()).unwrap_or_default()
+    }
+
+    /// Parse the content state from an annotation, or from its target alone, targeting a
+    /// manifest or a canvas of a manifest, with the region as a fragment or a selector.
+    fn try_from_json(json: &str) -> core::result::Result<Self, IiifError> {
+        let value = serde_json::from_str::<serde_json::Value>(json)?;
+        let target = match value.get("target") {
+            Some(serde_json::Value::Array(targets)) => targets.first().unwrap_or(&value),
+            Some(target) => target,
+            None => &value,
+        };
+
+        // The target can be a specific resource with the canvas as the source.
+        let (resource, selector) = match target.get("source") {
+            Some(source) => (source, target["selector"]["value"].as_str()),
+            None => (target, None),
+        };
+
+        let id = resource["id"]
+            .as_str()
+            .or_else(|| resource["@id"].as_str())
+            .or_else(|| resource.as_str())
+            .ok_or(IiifError::IiifMissingInfo(
+                "target of the content state".to_string(),
+            ))?;
+
+        if resource.is_string() || resource["type"].as_str() == Some("Manifest") {
+            return Ok(Self {
+                presentation_url: id.to_string(),
+                canvas_id: None,
+                region: None,
+            });
+        }
+
+        let (canvas_id, fragment) = match id.split_once('#') {
+            Some((canvas_id, fragment)) => (canvas_id, Some(fragment)),
+            None => (id, None),
+        };
+        let presentation_url = resource["partOf"][0]["id"]
+            .as_str()
+            .or_else(|| resource["partOf"][0]["@id"].as_str())
+            .ok_or(IiifError::IiifMissingInfo(
+                "manifest of the canvas of the content state".to_string(),
+            ))?;
+
+        Ok(Self {
+            presentation_url: presentation_url.to_string(),
+            canvas_id: Some(canvas_id.to_string()),
+            region: selector.or(fragment).and_then(parse_xywh),
+        })
+    }
+
+    /// Parse the content state from the value of a parameter, either encoded, as JSON or the URL
+    /// of a manifest.
+    pub(crate) fn try_from_param(value: &str) -> core::result::Result<Self, IiifError> {
+        let value = value.trim();
+
+        if value.starts_with("http://") || value.starts_with("https://") {
+            Ok(Self {
+                presentation_url: value.to_string(),
+                canvas_id: None,
+                region: None,
+            })
+        } else if value.starts_with('{') {
+            Self::try_from_json(value)
+        } else {
+            Self::try_from_json(&decode_content_state(value)?)
+        }
+    }
+
+    /// Load the manifest of the content state, and then its canvas and region.
+    pub(crate) fn load(&self, app_state: &mut ResMut<AppState>, app_settings: &AppSettings) {
+        app_state.target_canvas_id = self.canvas_id.clone();
+        app_state.target_region = self.region;
+        web::load_presentation(app_state, app_settings, &self.presentation_url);
+    }
+}
+
+/// Encode the content state JSON as in a URL parameter, percent-encoded as by
+/// `encodeURIComponent` and then as base64url without the padding.
+pub(crate) fn encode_content_state(json: &str) -> String {
+    URL_SAFE_NO_PAD.encode(web::percent_encode(json, b"!*'()").as_bytes())
+}
+
+/// Decode the content state JSON from base64url, with or without the padding, and then from
+/// the percent-encoding if any.
+fn decode_content_state(encoded: &str) -> core::result::Result<String, IiifError> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(encoded.trim_end_matches('='))
+        .map_err(|e| {
+            IiifError::IiifFormatError(format!("content state not in base64url. {}", e))
+        })?;
+    let decoded = String::from_utf8(bytes)?;
+
+    if decoded.starts_with('%') {
+        web::percent_decode(&decoded)
+    } else {
+        Ok(decoded)
+    }
+}
+
+/// Action requested from the share window.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ShareRequest {
+    /// Get the content state and the citation of the current view.
+    Update,
+    /// Open the content state pasted by the user.
+    Open,
+}
+
+/// Content state and citation of the current view to copy, and the content state to open.
+#[derive(Resource, Default)]
+pub(crate) struct ContentStateShare {
+    /// Content state as JSON.
+    pub(crate) json: String,
+    /// Content state encoded as the URL parameter, after the page URL in wasm, e.g.
+    /// "iiif-content=eyJAY29...".
+    pub(crate) link: String,
+    pub(crate) citation: String,
+    /// Content state pasted by the user to open.
+    pub(crate) input: String,
+    pub(crate) request: Option<ShareRequest>,
+}
+
+/// Get the link to the view of the content state, the page URL with its parameter in wasm.
+fn get_share_link(encoded: &str) -> String {
+    #[cfg(target_arch = "wasm32")]
+    if let Some(window) = web_sys::window()
+        && let (Ok(origin), Ok(path)) = (window.location().origin(), window.location().pathname())
+    {
+        return format!("{}{}?{}={}", origin, path, CONTENT_STATE_PARAM, encoded);
+    }
+
+    format!("{}={}", CONTENT_STATE_PARAM, encoded)
+}
+
+/// Handle the action requested from the share window.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn content_state_system(
+    mut share: ResMut<ContentStateShare>,
+    mut app_state: ResMut<AppState>,
+    app_settings: Res<AppSettings>,
+    presentation: Option<Single<&Manifest>>,
+    tiled_image: Option<Single<&TiledImage>>,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera2d>>,
+    mut messages: MessageWriter<UserNotification>,
+    mut redraw_request_writer: MessageWriter<RequestRedraw>,
+) -> Result {
+    match share.request.take() {
+        Some(ShareRequest::Update) => {
+            let Some(presentation) = presentation else {
+                return Ok(());
+            };
+            let canvas = presentation
+                .model()
+                .get_sequence(app_state.sequence_index)?
+                .get_canvas(app_state.canvas_index)?;
+            let (camera, global_transform) = camera.into_inner();
+            let region = tiled_image
+                .zip(get_world_viewport_rect(camera, global_transform))
+                .map(|(tiled_image, (world_pos_min, world_pos_max))| {
+                    tiled_image
+                        .world_to_image_rect(Rect::from_corners(
+                            world_pos_min.truncate(),
+                            world_pos_max.truncate(),
+                        ))
+                        .intersect(tiled_image.get_image_max_size_rect())
+                });
+            let content_state = ContentState {
+                presentation_url: app_state.presentation_url.to_string(),
+                canvas_id: Some(canvas.get_id().to_string()),
+                region,
+            };
+            let json = content_state.to_json();
+            let link = get_share_link(&encode_content_state(&json));
+
+            share.citation = format!(
+                "{}, {}. {} ({}). Accessed {}.",
+                presentation.model().get_title(&app_settings.language),
+                canvas
+                    .get_label(&app_settings.language)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                app_state.presentation_url,
+                link,
+                chrono::Local::now().format("%Y-%m-%d")
+            );
+            share.link = link;
+            share.json = json;
+        }
+        Some(ShareRequest::Open) => match ContentState::try_from_param(&share.input) {
+            Ok(content_state) => {
+                content_state.load(&mut app_state, &app_settings);
+                share.input.clear();
+            }
+            Err(e) => {
+                messages.write(UserNotification::Error(format!(
+                    "Unable to open the content state.\n{}",
+                    e
+                )));
+            }
+        },
+        None => return Ok(()),
+    }
+
+    redraw_request_writer.write(RequestRedraw);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_state_round_trip() {
+        let content_state = ContentState {
+            presentation_url: "https://example.org/manifest.json".to_string(),
+            canvas_id: Some("https://example.org/canvas/1".to_string()),
+            region: Some(Rect::new(10.0, 20.0, 110.0, 70.0)),
+        };
+        let json = content_state.to_json();
+
+        assert!(json.contains("\"https://example.org/canvas/1#xywh=10,20,100,50\""));
+        assert_eq!(
+            ContentState::try_from_param(&encode_content_state(&json)).ok(),
+            Some(content_state.clone())
+        );
+        assert_eq!(
+            ContentState::try_from_param(&json).ok(),
+            Some(content_state)
+        );
+    }
+
+    #[test]
+    fn test_encode_content_state() {
+        // The example of the encoding in the spec.
+        let json = r#"{"id":"https://example.org/object1/canvas7#xywh=1000,2000,1000,2000","type":"Canvas","partOf":[{"id":"https://example.org/object1/manifest","type":"Manifest"}]}"#;
+        let encoded = "JTdCJTIyaWQlMjIlM0ElMjJodHRwcyUzQSUyRiUyRmV4YW1wbGUub3JnJTJGb2JqZWN0MSUyRmNhbnZhczclMjN4eXdoJTNEMTAwMCUyQzIwMDAlMkMxMDAwJTJDMjAwMCUyMiUyQyUyMnR5cGUlMjIlM0ElMjJDYW52YXMlMjIlMkMlMjJwYXJ0T2YlMjIlM0ElNUIlN0IlMjJpZCUyMiUzQSUyMmh0dHBzJTNBJTJGJTJGZXhhbXBsZS5vcmclMkZvYmplY3QxJTJGbWFuaWZlc3QlMjIlMkMlMjJ0eXBlJTIyJTNBJTIyTWFuaWZlc3QlMjIlN0QlNUQlN0Q";
+
+        assert_eq!(encode_content_state(json), encoded);
+        assert_eq!(decode_content_state(encoded).ok().as_deref(), Some(json));
+
+        // A percent sign in the JSON is kept.
+        let json = r#"{"id":"https://example.org/p%201"}"#;
+
+        assert_eq!(
+            decode_content_state(&encode_content_state(json))
+                .ok()
+                .as_deref(),
+            Some(json)
+        );
+    }
+
+    #[test]
+    fn test_content_state_from_param() {
+        // A manifest URL alone.
+        assert_eq!(
+            ContentState::try_from_param("https://example.org/manifest.json").ok(),
+            Some(ContentState {
+                presentation_url: "https://example.org/manifest.json".to_string(),
+                canvas_id: None,
+                region: None,
+            })
+        );
+
+        // A specific resource with a selector, percent-encoded before base64url with the padding.
+        let json = r#"{"type":"Annotation","motivation":["contentState"],"target":{"type":"SpecificResource","source":{"id":"https://example.org/canvas/2","type":"Canvas","partOf":[{"id":"https://example.org/manifest.json","type":"Manifest"}]},"selector":{"type":"FragmentSelector","value":"xywh=1,2,3,4"}}}"#;
+        let percent_encoded = json
+            .bytes()
+            .map(|x| format!("%{:02X}", x))
+            .collect::<String>();
+        let encoded = base64::engine::general_purpose::URL_SAFE.encode(percent_encoded);
+
+        assert_eq!(
+            ContentState::try_from_param(&encoded).ok(),
+            Some(ContentState {
+                presentation_url: "https://example.org/manifest.json".to_string(),
+                canvas_id: Some("https://example.org/canvas/2".to_string()),
+                region: Some(Rect::new(1.0, 2.0, 4.0, 6.0)),
+            })
+        );
+
+        assert!(ContentState::try_from_param("not base64!").is_err());
+        assert!(ContentState::try_from_param(r#"{"target":{"id":"c1"}}"#).is_err());
+    }
+}
@@ -0,0 +1,82 @@
+use crate::notification::UserNotification;
+use bevy::prelude::Resource;
+
+/// Manifest offered on the error page to get started.
+pub(crate) struct SampleManifest {
+    pub(crate) label: &'static str,
+    pub(crate) url: &'static str,
+}
+
+/// Manifests offered on the error page, of the images and of the 3D models.
+pub(crate) const SAMPLE_MANIFESTS: [SampleManifest; 5] = [
+    SampleManifest {
+        label: "Painting (Harvard Art Museums)",
+        url: "https://iiif.harvardartmuseums.org/manifests/object/21116",
+    },
+    SampleManifest {
+        label: "Book of several pages (IIIF Cookbook)",
+        url: "https://iiif.io/api/cookbook/recipe/0009-book-1/manifest.json",
+    },
+    SampleManifest {
+        label: "Map (Library of Congress)",
+        url: "https://www.loc.gov/item/00007086/manifest.json",
+    },
+    SampleManifest {
+        label: "Image in a single canvas (IIIF Cookbook)",
+        url: "https://iiif.io/api/cookbook/recipe/0001-mvm-image/manifest.json",
+    },
+    SampleManifest {
+        label: "3D model (IIIF 3D)",
+        url: "https://iiif.github.io/3d/manifests/1_basic_model_in_scene/model_origin.json",
+    },
+];
+
+/// Error of the manifest which cannot be shown, e.g. not valid or without any canvas, shown on
+/// a page in place of the empty viewport rather than in a notification.
+#[derive(Resource, Default)]
+pub(crate) struct ManifestErrorPage {
+    pub(crate) error: Option<UserNotification>,
+}
+
+impl ManifestErrorPage {
+    /// Show the error on the page if nothing is shown, or else return it to be notified, as the
+    /// manifest shown is kept.
+    pub(crate) fn report(
+        &mut self,
+        error: UserNotification,
+        is_viewport_empty: bool,
+    ) -> Option<UserNotification> {
+        if !is_viewport_empty {
+            return Some(error);
+        }
+
+        self.error = Some(error);
+        None
+    }
+
+    /// Remove the error once a manifest or a collection is shown.
+    pub(crate) fn clear(&mut self) {
+        self.error = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let mut error_page = ManifestErrorPage::default();
+        let error = UserNotification::Error("No canvas".to_string());
+
+        // Notified while the manifest shown is kept.
+        assert_eq!(error_page.report(error.clone(), false), Some(error.clone()));
+        assert_eq!(error_page.error, None);
+
+        assert_eq!(error_page.report(error.clone(), true), None);
+        assert_eq!(error_page.error, Some(error));
+
+        error_page.clear();
+        assert_eq!(error_page.error, None);
+    }
+}
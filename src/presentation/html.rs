@@ -0,0 +1,638 @@
+/// Tags whose content is dropped with them, e.g. scripts.
+const DROPPED_TAGS: [&str; 2] = ["script", "style"];
+
+/// Run of text with the same style in the HTML subset allowed in the IIIF values.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct HtmlSpan {
+    pub(crate) text: String,
+    pub(crate) bold: bool,
+    pub(crate) italic: bool,
+    /// URL of the link the text is in.
+    pub(crate) link: Option<String>,
+}
+
+/// Whether the value is HTML, i.e. starting with '<' and ending with '>' as required by the IIIF
+/// presentation API.
+pub(crate) fn is_html(text: &str) -> bool {
+    let text = text.trim();
+
+    text.starts_with('<') && text.ends_with('>')
+}
+
+/// Decode the character references, e.g. "&amp;".
+fn decode_entities(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let decoded = rest.find(';').filter(|x| *x <= 10).and_then(|end| {
+            let c = match &rest[1..end] {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some('\u{a0}'),
+                x if x.starts_with("#x") || x.starts_with("#X") => u32::from_str_radix(&x[2..], 16)
+                    .ok()
+                    .and_then(char::from_u32),
+                x if x.starts_with('#') => x[1..].parse().ok().and_then(char::from_u32),
+                _ => None,
+            };
+
+            c.map(|c| (c, end))
+        });
+
+        match decoded {
+            Some((c, end)) => {
+                output.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                output.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    output.push_str(rest);
+    output
+}
+
+/// Get the value of the attribute of the tag, e.g. "href" of "a href='...'".
+fn get_attribute(tag: &str, name: &str) -> Option<String> {
+    let mut rest = tag;
+
+    while let Some(index) = rest.to_ascii_lowercase().find(name) {
+        let after = rest[index + name.len()..].trim_start();
+        let preceded_by_space = rest[..index].ends_with(char::is_whitespace);
+
+        if preceded_by_space && let Some(value) = after.strip_prefix('=') {
+            let value = value.trim_start();
+            let value = match value.chars().next() {
+                Some(quote @ ('"' | '\'')) => value[1..].split(quote).next(),
+                _ => value.split(char::is_whitespace).next(),
+            };
+
+            return value.map(|x| decode_entities(x.trim()));
+        }
+
+        rest = &rest[index + name.len()..];
+    }
+
+    None
+}
+
+/// Get the link URL if it is allowed, i.e. not a script.
+fn sanitize_link(url: &str) -> Option<String> {
+    let lower = url.to_lowercase();
+
+    (lower.starts_with("http://") || lower.starts_with("https://") || lower.starts_with("mailto:"))
+        .then(|| url.to_string())
+}
+
+/// Get the nesting depth of the tag after the opening or closing tag.
+fn get_depth(depth: usize, closing: bool) -> usize {
+    if closing {
+        depth.saturating_sub(1)
+    } else {
+        depth + 1
+    }
+}
+
+/// Add the text to the last span if it has the same style, or as a new span.
+fn push_text(
+    spans: &mut Vec<HtmlSpan>,
+    text: &str,
+    bold: bool,
+    italic: bool,
+    link: &Option<String>,
+) {
+    if text.is_empty() {
+        return;
+    }
+
+    match spans.last_mut() {
+        Some(last) if last.bold == bold && last.italic == italic && &last.link == link => {
+            last.text.push_str(text)
+        }
+        _ => spans.push(HtmlSpan {
+            text: text.to_string(),
+            bold,
+            italic,
+            link: link.clone(),
+        }),
+    }
+}
+
+/// Parse the HTML subset allowed in the IIIF values into the runs of styled text.
+///
+/// Bold, italic, links and line breaks are kept. Other tags are removed with their text kept,
+/// except the scripts and styles which are removed with their content.
+pub(crate) fn parse_html(html: &str) -> Vec<HtmlSpan> {
+    let mut spans: Vec<HtmlSpan> = vec![];
+    let mut bold = 0_usize;
+    let mut italic = 0_usize;
+    let mut dropped = 0_usize;
+    let mut link: Option<String> = None;
+    let mut rest = html.trim();
+
+    while !rest.is_empty() {
+        // Comments.
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.split_once("-->").map(|(_, x)| x).unwrap_or("");
+            continue;
+        }
+
+        let Some(tag) = rest.strip_prefix('<').and_then(|x| x.split_once('>')) else {
+            // Text up to the next tag with the whitespace collapsed as in HTML.
+            let first = rest.chars().next().map(char::len_utf8).unwrap_or_default();
+            let end = rest[first..]
+                .find('<')
+                .map(|x| x + first)
+                .unwrap_or(rest.len());
+
+            if dropped == 0 {
+                let text = decode_entities(&rest[..end]);
+                let mut collapsed = String::with_capacity(text.len());
+
+                for c in text.chars() {
+                    if c.is_whitespace() && c != '\u{a0}' {
+                        let previous = if collapsed.is_empty() {
+                            spans.last().and_then(|x| x.text.chars().last())
+                        } else {
+                            collapsed.chars().last()
+                        };
+
+                        if previous.is_some_and(|x| x != ' ' && x != '\n') {
+                            collapsed.push(' ');
+                        }
+                    } else {
+                        collapsed.push(c);
+                    }
+                }
+
+                push_text(&mut spans, &collapsed, bold > 0, italic > 0, &link);
+            }
+
+            rest = &rest[end..];
+            continue;
+        };
+
+        let (tag, after) = tag;
+        let closing = tag.starts_with('/');
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+
+        rest = after;
+
+        if DROPPED_TAGS.contains(&name.as_str()) {
+            dropped = get_depth(dropped, closing);
+            continue;
+        }
+
+        if dropped > 0 {
+            continue;
+        }
+
+        match name.as_str() {
+            "b" | "strong" => bold = get_depth(bold, closing),
+            "i" | "em" => italic = get_depth(italic, closing),
+            "a" => {
+                link = if closing {
+                    None
+                } else {
+                    get_attribute(tag, "href").and_then(|x| sanitize_link(&x))
+                }
+            }
+            "br" => push_text(&mut spans, "\n", bold > 0, italic > 0, &link),
+            "p" if closing => push_text(&mut spans, "\n", bold > 0, italic > 0, &link),
+            _ => {}
+        }
+    }
+
+    // Trailing line breaks of the paragraphs.
+    if let Some(last) = spans.last_mut() {
+        last.text.truncate(last.text.trim_end().len());
+
+        if last.text.is_empty() {
+            spans.pop();
+        }
+    }
+
+    spans
+}
+
+/// Parse the basic markdown of a plain text value into the runs of styled text, e.g. of the long
+/// descriptions.
+///
+/// Bold, italic, code, links, the headings as bold and the list items as bullets are kept. The
+/// line breaks are kept as in the plain text, with the blank lines between the paragraphs
+/// collapsed into one.
+pub(crate) fn parse_markdown(text: &str) -> Vec<HtmlSpan> {
+    let mut spans: Vec<HtmlSpan> = vec![];
+    let mut is_after_blank = false;
+
+    for line in text.trim().lines() {
+        let line = line.trim();
+
+        if line.is_empty() {
+            is_after_blank = true;
+            continue;
+        }
+
+        if !spans.is_empty() {
+            let line_break = if is_after_blank { "\n\n" } else { "\n" };
+
+            push_text(&mut spans, line_break, false, false, &None);
+        }
+        is_after_blank = false;
+
+        let heading = line.trim_start_matches('#');
+        let heading_level = line.len() - heading.len();
+
+        if (1..=6).contains(&heading_level) && heading.starts_with(' ') {
+            push_markdown_inline(&mut spans, heading.trim(), true);
+        } else if let Some(item) = ["- ", "* ", "+ "].iter().find_map(|x| line.strip_prefix(x)) {
+            push_text(&mut spans, "• ", false, false, &None);
+            push_markdown_inline(&mut spans, item, false);
+        } else {
+            push_markdown_inline(&mut spans, line, false);
+        }
+    }
+
+    spans
+}
+
+/// Add the text of the line with the inline markdown, i.e. the emphasis, the code and the links.
+fn push_markdown_inline(spans: &mut Vec<HtmlSpan>, line: &str, is_heading: bool) {
+    let mut bold = false;
+    let mut italic = false;
+    let mut text = String::new();
+    let mut previous = None;
+    let mut rest = line;
+
+    while let Some(c) = rest.chars().next() {
+        let after = &rest[c.len_utf8()..];
+        let next = after.chars().next();
+
+        // Escaped punctuation, e.g. "\*".
+        if c == '\\'
+            && let Some(escaped) = next.filter(char::is_ascii_punctuation)
+        {
+            text.push(escaped);
+            previous = Some(escaped);
+            rest = &after[1..];
+            continue;
+        }
+
+        // Code as is.
+        if c == '`'
+            && let Some((code, tail)) = after.split_once('`')
+        {
+            text.push_str(code);
+            previous = Some('`');
+            rest = tail;
+            continue;
+        }
+
+        // Links, as text only if not allowed.
+        if c == '['
+            && let Some((label, tail)) = after.split_once("](")
+            && !label.contains(']')
+            && let Some((url, tail)) = tail.split_once(')')
+        {
+            push_text(spans, &text, bold || is_heading, italic, &None);
+            push_text(
+                spans,
+                label,
+                bold || is_heading,
+                italic,
+                &sanitize_link(url.trim()),
+            );
+            text.clear();
+            previous = Some(')');
+            rest = tail;
+            continue;
+        }
+
+        let is_double = (c == '*' || c == '_') && next == Some(c);
+        let is_word = |x: Option<char>| x.is_some_and(char::is_alphanumeric);
+        // The "_" inside the words, e.g. of the identifiers, is not a delimiter.
+        let is_delimiter = match c {
+            _ if is_double => {
+                let delimiter = &rest[..2];
+
+                bold || after[1..].contains(delimiter)
+            }
+            '*' => italic || (next.is_some_and(|x| !x.is_whitespace()) && after.contains('*')),
+            '_' if italic => !is_word(next),
+            '_' => {
+                !is_word(previous)
+                    && next.is_some_and(|x| !x.is_whitespace())
+                    && after.contains('_')
+            }
+            _ => false,
+        };
+
+        if is_delimiter {
+            push_text(spans, &text, bold || is_heading, italic, &None);
+            text.clear();
+
+            if is_double {
+                bold = !bold;
+                rest = &after[1..];
+            } else {
+                italic = !italic;
+                rest = after;
+            }
+            previous = Some(c);
+            continue;
+        }
+
+        text.push(c);
+        previous = Some(c);
+        rest = after;
+    }
+
+    push_text(spans, &text, bold || is_heading, italic, &None);
+}
+
+/// Whether the content is a web page rather than a value or a descriptor, e.g. a catalog page.
+pub(crate) fn is_html_page(content: &str) -> bool {
+    let start = content
+        .trim_start()
+        .chars()
+        .take(1024)
+        .collect::<String>()
+        .to_ascii_lowercase();
+
+    start.starts_with('<') && (start.contains("<!doctype html") || start.contains("<html"))
+}
+
+/// Resolve the URL relative to the URL of the page, e.g. "/iiif/1/manifest".
+fn resolve_url(url: &str, page_url: &str) -> String {
+    let (scheme, rest) = page_url.split_once("://").unwrap_or(("https", page_url));
+    let origin_len = scheme.len() + 3 + rest.find('/').unwrap_or(rest.len());
+
+    if url.contains("://") {
+        url.to_string()
+    } else if let Some(url) = url.strip_prefix("//") {
+        format!("{}://{}", scheme, url)
+    } else if url.starts_with('/') {
+        format!("{}{}", &page_url[..origin_len], url)
+    } else {
+        let path = page_url.split(['?', '#']).next().unwrap_or_default();
+        let dir = &path[..path
+            .rfind('/')
+            .filter(|x| *x >= origin_len)
+            .unwrap_or(path.len())];
+
+        format!("{}/{}", dir, url)
+    }
+}
+
+/// Find the URLs of the IIIF manifests the web page at the URL refers to, e.g. a catalog page,
+/// by its `<link rel="alternate">` JSON-LD tags and the manifest URLs in its links or scripts.
+///
+/// Empty if the content is not a web page.
+pub(crate) fn find_manifest_urls(content: &str, page_url: &str) -> Vec<String> {
+    if !is_html_page(content) {
+        return Vec::new();
+    }
+
+    let lower = content.to_ascii_lowercase();
+    let links = lower.match_indices("<link").filter_map(|(start, _)| {
+        let tag = &content[start..start + lower[start..].find('>')?];
+        let rel = get_attribute(tag, "rel")?.to_ascii_lowercase();
+        let type_ = get_attribute(tag, "type")?.to_ascii_lowercase();
+
+        if !rel.split_whitespace().any(|x| x == "alternate") || !type_.contains("json") {
+            return None;
+        }
+
+        Some(resolve_url(&get_attribute(tag, "href")?, page_url)).filter(|x| x.starts_with("http"))
+    });
+
+    // The URLs escaped in the scripts are found as well, e.g. "https:\/\/example.org".
+    let text = decode_entities(content).replace("\\/", "/");
+    let references = ["https://", "http://"]
+        .into_iter()
+        .flat_map(|scheme| text.match_indices(scheme).map(|(start, _)| start))
+        .map(|start| {
+            let url = &text[start..];
+            let end = url
+                .find(|c: char| c.is_whitespace() || "\"'<>\\`".contains(c))
+                .unwrap_or(url.len());
+
+            url[..end].trim_end_matches(['.', ',', ';', ')'])
+        })
+        .filter(|url| {
+            let path = url.split(['?', '#']).next().unwrap_or_default();
+
+            path.to_ascii_lowercase().contains("manifest")
+        })
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>();
+
+    let mut urls: Vec<String> = Vec::new();
+
+    for url in links.chain(references) {
+        if url != page_url && !urls.contains(&url) {
+            urls.push(url);
+        }
+    }
+
+    urls
+}
+
+/// Get the title of the web page, e.g. to label the manifests found on it.
+pub(crate) fn get_page_title(content: &str) -> Option<String> {
+    let lower = content.to_ascii_lowercase();
+    let start = lower.find("<title")?;
+    let start = start + lower[start..].find('>')? + 1;
+    let end = start + lower[start..].find("</title")?;
+    let title = decode_entities(content[start..end].trim());
+
+    (!title.is_empty()).then_some(title)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_html() {
+        assert_eq!(
+            parse_html(
+                "<p>Written by <b>J. Smith</b>,<br/>see <a href=\"https://example.org/a?x=1&amp;y=2\">\
+                the <i>record</i></a>.</p>"
+            ),
+            vec![
+                HtmlSpan {
+                    text: "Written by ".to_string(),
+                    ..Default::default()
+                },
+                HtmlSpan {
+                    text: "J. Smith".to_string(),
+                    bold: true,
+                    ..Default::default()
+                },
+                HtmlSpan {
+                    text: ",\nsee ".to_string(),
+                    ..Default::default()
+                },
+                HtmlSpan {
+                    text: "the ".to_string(),
+                    link: Some("https://example.org/a?x=1&y=2".to_string()),
+                    ..Default::default()
+                },
+                HtmlSpan {
+                    text: "record".to_string(),
+                    italic: true,
+                    link: Some("https://example.org/a?x=1&y=2".to_string()),
+                    ..Default::default()
+                },
+                HtmlSpan {
+                    text: ".".to_string(),
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_html_sanitization() {
+        assert_eq!(
+            parse_html(
+                "<span class='x'>Safe <script>alert(1)</script><a href='javascript:alert(1)'>\
+                link</a> <img src='x.png' onerror='alert(1)'/>&lt;tag&gt;<!-- note --></span>"
+            ),
+            vec![HtmlSpan {
+                text: "Safe link <tag>".to_string(),
+                ..Default::default()
+            }]
+        );
+        assert!(is_html(" <span>text</span>"));
+        assert!(!is_html("1 < 2 > 0.5 text"));
+    }
+
+    #[test]
+    fn test_find_manifest_urls() {
+        let page = r#"<!DOCTYPE html>
+            <html><head>
+                <title>Book of Hours &amp; Psalter</title>
+                <link rel="alternate" type="application/ld+json;profile=&quot;iiif&quot;"
+                    href="/iiif/book1/manifest.json">
+                <link rel="stylesheet" type="text/css" href="/style.css">
+            </head><body>
+                <a href="https://viewer.example.org/?manifest=https://example.org/iiif/book1/manifest.json">View</a>
+                <a href="https://example.org/about">About</a>
+                <script>var m = {"manifest": "https:\/\/example.org\/iiif\/book2\/manifest"};</script>
+            </body></html>"#;
+
+        assert_eq!(
+            find_manifest_urls(page, "https://example.org/catalog/item/1"),
+            [
+                "https://example.org/iiif/book1/manifest.json",
+                "https://example.org/iiif/book2/manifest",
+            ]
+        );
+        assert_eq!(
+            get_page_title(page).as_deref(),
+            Some("Book of Hours & Psalter")
+        );
+        // Not a web page.
+        assert!(
+            find_manifest_urls(
+                r#"{"id": "https://example.org/iiif/book1/manifest"}"#,
+                "https://example.org/x"
+            )
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_resolve_url() {
+        let page_url = "https://example.org/catalog/item?id=1";
+
+        assert_eq!(
+            resolve_url("/iiif/manifest", page_url),
+            "https://example.org/iiif/manifest"
+        );
+        assert_eq!(
+            resolve_url("manifest.json", page_url),
+            "https://example.org/catalog/manifest.json"
+        );
+        assert_eq!(
+            resolve_url("//cdn.example.org/manifest", page_url),
+            "https://cdn.example.org/manifest"
+        );
+        assert_eq!(
+            resolve_url("manifest.json", "https://example.org"),
+            "https://example.org/manifest.json"
+        );
+    }
+
+    #[test]
+    fn test_parse_markdown() {
+        assert_eq!(
+            parse_markdown(
+                "# History\n\n\nBound in **red** *morocco*, see \
+                [the record](https://example.org/1).\n- file_name_1\n- `a*b`"
+            ),
+            vec![
+                HtmlSpan {
+                    text: "History".to_string(),
+                    bold: true,
+                    ..Default::default()
+                },
+                HtmlSpan {
+                    text: "\n\nBound in ".to_string(),
+                    ..Default::default()
+                },
+                HtmlSpan {
+                    text: "red".to_string(),
+                    bold: true,
+                    ..Default::default()
+                },
+                HtmlSpan {
+                    text: " ".to_string(),
+                    ..Default::default()
+                },
+                HtmlSpan {
+                    text: "morocco".to_string(),
+                    italic: true,
+                    ..Default::default()
+                },
+                HtmlSpan {
+                    text: ", see ".to_string(),
+                    ..Default::default()
+                },
+                HtmlSpan {
+                    text: "the record".to_string(),
+                    link: Some("https://example.org/1".to_string()),
+                    ..Default::default()
+                },
+                HtmlSpan {
+                    text: ".\n• file_name_1\n• a*b".to_string(),
+                    ..Default::default()
+                },
+            ]
+        );
+        assert_eq!(
+            parse_markdown("2 * 3 = 6, [x](javascript:void)"),
+            vec![HtmlSpan {
+                text: "2 * 3 = 6, x".to_string(),
+                ..Default::default()
+            }]
+        );
+    }
+}
@@ -1,22 +1,36 @@
 use crate::{
-    iiif::{IiifError, manifest},
+    iiif::{
+        IiifError,
+        manifest::{self, CanvasError},
+    },
     presentation::model::IsManifest,
-    rendering::{model_image::ModelImage, tiled_image::TiledImage},
+    rendering::{model_image::ModelImage, overlay_image::OverlayImage, tiled_image::TiledImage},
 };
 use bevy::prelude::{
     Camera, Commands, Component, Entity, On, Query, Remove, Result, With, Without, info,
 };
 use bevy_egui::EguiContext;
 
+/// Max number of the canvas errors listed in the summary.
+const MAX_LISTED_ERRORS: usize = 5;
+
+/// Max number of the canvases listed by their labels matching the text.
+const MAX_LABEL_MATCHES: usize = 10;
+
 #[derive(Component)]
 /// Presentation manifest.
 pub(crate) struct Manifest {
     inner: Box<dyn IsManifest>,
+    /// Errors of the canvases which cannot be shown.
+    canvas_errors: Vec<CanvasError>,
 }
 
 impl Manifest {
     fn new(inner: Box<dyn IsManifest>) -> Self {
-        Self { inner }
+        Self {
+            inner,
+            canvas_errors: Vec::new(),
+        }
     }
 
     /// Get the reference of the inner manifest.
@@ -24,11 +38,140 @@ impl Manifest {
         self.inner.as_ref()
     }
 
-    /// Try to create the manifest from JSON.
+    /// Try to create the manifest from JSON, keeping the errors of the canvases which cannot be
+    /// shown.
     pub(crate) fn try_from_json(json: &str) -> core::result::Result<Self, IiifError> {
-        let iiif_manifest = manifest::Manifest::try_from_json(json)?;
+        let (iiif_manifest, canvas_errors) = manifest::Manifest::try_from_json(json)?;
+
+        Ok(Self {
+            inner: iiif_manifest,
+            canvas_errors,
+        })
+    }
+
+    /// Get the error of the canvas if it cannot be shown.
+    pub(crate) fn get_canvas_error(
+        &self,
+        sequence_index: usize,
+        canvas_index: usize,
+    ) -> Option<&CanvasError> {
+        self.canvas_errors
+            .iter()
+            .find(|x| x.sequence_index == sequence_index && x.canvas_index == canvas_index)
+    }
+
+    /// Get the index of the canvas to show in the sequence, which is the next one that can be
+    /// shown if the canvas at the index cannot.
+    pub(crate) fn get_usable_canvas_index(
+        &self,
+        sequence_index: usize,
+        canvas_index: usize,
+    ) -> usize {
+        let num_canvases = self
+            .inner
+            .get_sequence(sequence_index)
+            .map_or(0, |x| x.get_canvases().len());
 
-        Ok(Manifest::from(iiif_manifest))
+        (canvas_index..num_canvases)
+            .chain(0..canvas_index)
+            .find(|x| self.get_canvas_error(sequence_index, *x).is_none())
+            .unwrap_or(canvas_index)
+    }
+
+    /// Get the summary of the canvases which cannot be shown, or `None` if all can be shown.
+    pub(crate) fn get_canvas_errors_summary(&self) -> Option<String> {
+        let first = self.canvas_errors.first()?;
+        let num_canvases: usize = self
+            .inner
+            .get_sequences()
+            .map(|x| x.get_canvases().len())
+            .sum();
+        let mut lines = vec![format!(
+            "{} of {} canvases cannot be shown.",
+            self.canvas_errors.len(),
+            num_canvases
+        )];
+        let show_sequence = self
+            .canvas_errors
+            .iter()
+            .any(|x| x.sequence_index != first.sequence_index);
+
+        for canvas_error in self.canvas_errors.iter().take(MAX_LISTED_ERRORS) {
+            lines.push(if show_sequence {
+                format!(
+                    "Sequence {} canvas {}: {}",
+                    canvas_error.sequence_index + 1,
+                    canvas_error.canvas_index + 1,
+                    canvas_error.msg
+                )
+            } else {
+                format!(
+                    "Canvas {}: {}",
+                    canvas_error.canvas_index + 1,
+                    canvas_error.msg
+                )
+            });
+        }
+
+        if self.canvas_errors.len() > MAX_LISTED_ERRORS {
+            lines.push("...".to_string());
+        }
+
+        Some(lines.join("\n"))
+    }
+
+    /// Find the canvases in the sequence by their labels containing the text, e.g. "12r" for
+    /// "f. 12r", with the closest matches first.
+    pub(crate) fn find_canvases_by_label(
+        &self,
+        sequence_index: usize,
+        text: &str,
+        language: &str,
+    ) -> Vec<(usize, String)> {
+        let Ok(sequence) = self.inner.get_sequence(sequence_index) else {
+            return Vec::new();
+        };
+        let mut matches = sequence
+            .get_canvases()
+            .enumerate()
+            .filter_map(|(index, canvas)| {
+                let label = canvas.get_label(language).collect::<Vec<_>>().join(",");
+
+                Some((get_label_match_rank(&label, text)?, index, label))
+            })
+            .collect::<Vec<_>>();
+
+        matches.sort_by_key(|(rank, index, _)| (*rank, *index));
+
+        matches
+            .into_iter()
+            .take(MAX_LABEL_MATCHES)
+            .map(|(_, index, label)| (index, label))
+            .collect()
+    }
+}
+
+/// Get the rank of the label matching the text regardless of the case, lower for a closer match,
+/// or `None` if it does not match. E.g. "12r" matches "f. 12r" better than "f. 112r".
+fn get_label_match_rank(label: &str, text: &str) -> Option<u8> {
+    let label = label.trim().to_lowercase();
+    let text = text.trim().to_lowercase();
+
+    if text.is_empty() || !label.contains(&text) {
+        return None;
+    }
+
+    if label == text {
+        Some(0)
+    } else if label
+        .split(|x: char| !x.is_alphanumeric())
+        .any(|x| x == text)
+    {
+        Some(1)
+    } else if label.starts_with(&text) {
+        Some(2)
+    } else {
+        Some(3)
     }
 }
 
@@ -43,6 +186,7 @@ pub(crate) fn on_remove_manifest(
     remove: On<Remove, Manifest>,
     camera_query: Query<&mut Camera, Without<EguiContext>>,
     tiled_image_query: Query<Entity, With<TiledImage>>,
+    overlay_image_query: Query<Entity, With<OverlayImage>>,
     model_image_query: Query<Entity, With<ModelImage>>,
     mut commands: Commands,
 ) -> Result {
@@ -57,6 +201,9 @@ pub(crate) fn on_remove_manifest(
     for image_entity in tiled_image_query {
         commands.entity(image_entity).despawn();
     }
+    for image_entity in overlay_image_query {
+        commands.entity(image_entity).despawn();
+    }
     for image_entity in model_image_query {
         commands.entity(image_entity).despawn();
     }
@@ -284,4 +431,79 @@ mod tests {
             "http://www.example.org/images/book1-page1"
         );
     }
+
+    #[test]
+    fn test_canvas_errors() {
+        let json = r#"{
+          "@context":"http://iiif.io/api/presentation/2/context.json",
+          "@type":"sc:Manifest",
+          "@id":"https://example.org/manifest",
+          "label":"Book",
+          "sequences": [
+            {"@type":"sc:Sequence", "canvases": [
+              {"@id":"https://example.org/c1", "@type":"sc:Canvas", "label":"1"},
+              {"@id":"https://example.org/c2", "@type":"sc:Canvas", "label":"2", "images":[
+                {"resource": {
+                  "@id":"https://example.org/images/c2/full/full/0/default.jpg",
+                  "@type":"dctypes:Image",
+                  "service": {"@id":"https://example.org/images/c2", "profile":"level1"}
+                }}
+              ]},
+              {"@id":"https://example.org/c3", "@type":"sc:Canvas", "label":"3", "images":[]}
+            ]}
+          ]
+        }"#;
+        let manifest = Manifest::try_from_json(json).expect("should load the usable canvas");
+
+        assert!(manifest.get_canvas_error(0, 0).is_some());
+        assert!(manifest.get_canvas_error(0, 1).is_none());
+        assert!(manifest.get_canvas_error(0, 2).is_some());
+        assert_eq!(manifest.get_usable_canvas_index(0, 0), 1);
+        assert_eq!(manifest.get_usable_canvas_index(0, 2), 1);
+        assert_eq!(
+            manifest.get_canvas_errors_summary(),
+            Some(
+                "2 of 3 canvases cannot be shown.\n\
+                 Canvas 1: IIIF missing info 'missing image at pos '0''\n\
+                 Canvas 3: IIIF missing info 'missing image at pos '0''"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_find_canvases_by_label() {
+        let json = r#"{
+          "@context":"http://iiif.io/api/presentation/2/context.json",
+          "@type":"sc:Manifest",
+          "@id":"https://example.org/manifest",
+          "label":"Book",
+          "sequences": [
+            {"@type":"sc:Sequence", "canvases": [
+              {"@id":"https://example.org/c1", "@type":"sc:Canvas", "label":"f. 112r"},
+              {"@id":"https://example.org/c2", "@type":"sc:Canvas", "label":"f. 12R"},
+              {"@id":"https://example.org/c3", "@type":"sc:Canvas", "label":"12r", "images":[
+                {"resource": {
+                  "@id":"https://example.org/images/c3/full/full/0/default.jpg",
+                  "@type":"dctypes:Image",
+                  "service": {"@id":"https://example.org/images/c3", "profile":"level1"}
+                }}
+              ]},
+              {"@id":"https://example.org/c4", "@type":"sc:Canvas", "label":"f. 12v"}
+            ]}
+          ]
+        }"#;
+        let manifest = Manifest::try_from_json(json).expect("should load the manifest");
+
+        assert_eq!(
+            manifest.find_canvases_by_label(0, "12r", "en"),
+            vec![
+                (2, "12r".to_string()),
+                (1, "f. 12R".to_string()),
+                (0, "f. 112r".to_string())
+            ]
+        );
+        assert!(manifest.find_canvases_by_label(0, "13r", "en").is_empty());
+        assert!(manifest.find_canvases_by_label(0, " ", "en").is_empty());
+    }
 }
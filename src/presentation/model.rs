@@ -1,8 +1,89 @@
-use crate::iiif::IiifError;
+use crate::iiif::{IiifError, image::ImageOrientation};
 use std::borrow::Cow;
 
+/// Kind of the link to an external resource about the manifest.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ExternalLinkKind {
+    /// Web page about the object, e.g. `homepage` in v3.
+    Homepage,
+    /// Resource related to the object, e.g. `related` in v2.
+    Related,
+    /// Machine readable description of the object, e.g. a catalogue record.
+    SeeAlso,
+}
+
+impl ExternalLinkKind {
+    pub(crate) fn label(&self) -> &'static str {
+        match self {
+            Self::Homepage => "Homepage",
+            Self::Related => "Related",
+            Self::SeeAlso => "See also",
+        }
+    }
+}
+
+/// Link to an external resource about the manifest.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ExternalLink {
+    pub(crate) kind: ExternalLinkKind,
+    pub(crate) url: String,
+    /// Label of the resource, empty if none.
+    pub(crate) label: String,
+}
+
+/// Alternative representation of the manifest or the canvas to download, e.g. a PDF by
+/// `rendering`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Rendering {
+    pub(crate) url: String,
+    /// Label of the resource, empty if none.
+    pub(crate) label: String,
+    /// Media type of the resource, e.g. "application/pdf". Empty if not given.
+    pub(crate) format: String,
+}
+
+/// Behavior of a canvas in the spreads of a paged manifest, by `behavior` in v3 or
+/// `viewingHint` in v2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum PageBehavior {
+    /// Page shown with its facing page.
+    #[default]
+    Paged,
+    /// Both facing pages in one canvas, shown alone.
+    FacingPages,
+    /// Not a page, e.g. a cover or a color chart, skipped by the spreads.
+    NonPaged,
+}
+
+impl PageBehavior {
+    /// Get the behavior by the values of `behavior` or `viewingHint`.
+    pub(crate) fn from_hints<'a>(hints: impl IntoIterator<Item = &'a str>) -> Self {
+        hints
+            .into_iter()
+            .find_map(|x| match x {
+                "facing-pages" => Some(Self::FacingPages),
+                "non-paged" => Some(Self::NonPaged),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Region of interest on a canvas provided by the publisher, e.g. by a highlighting annotation,
+/// or an annotation during an interval of a time-based canvas.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CanvasHighlight {
+    /// Label or text of the annotation, which may be HTML. Empty if none.
+    pub(crate) label: String,
+    /// Media fragment of the region on the canvas, e.g. "xywh=10,20,100,50", or of the interval
+    /// in seconds with or without the region, e.g. "xywh=10,20,100,50&t=5,12.5".
+    pub(crate) fragment: String,
+}
+
 /// Trait that represents an IIIF manifest needed by the UI.
 pub(crate) trait IsManifest: Send + Sync {
+    /// Get the id of the manifest. Empty if none.
+    fn get_id(&self) -> Cow<'_, str>;
     fn get_title(&self, language: &str) -> Cow<'_, str>;
     fn get_attribution(&self, language: &str) -> Box<dyn Iterator<Item = Cow<'_, str>> + '_>;
     fn get_required_statements(
@@ -10,10 +91,38 @@ pub(crate) trait IsManifest: Send + Sync {
         language: &str,
     ) -> Box<dyn Iterator<Item = Cow<'_, str>> + '_>;
     fn get_description(&self, language: &str) -> Box<dyn Iterator<Item = Cow<'_, str>> + '_>;
+    /// Get the metadata as "label: value" texts.
+    fn get_metadata(&self, language: &str) -> Box<dyn Iterator<Item = Cow<'_, str>> + '_>;
     fn get_license(&self) -> Box<dyn Iterator<Item = Cow<'_, str>> + '_>;
     fn get_logo(&self) -> Box<dyn Iterator<Item = Cow<'_, str>> + '_>;
     fn get_sequences(&self) -> Box<dyn ExactSizeIterator<Item = &dyn IsSequence> + '_>;
     fn get_sequence(&self, index: usize) -> Result<&dyn IsSequence, IiifError>;
+    /// Get the collections the manifest is part of, e.g. by `partOf` in v3 or `within` in v2.
+    fn get_parent_collections(&self) -> Box<dyn Iterator<Item = &dyn IsCollectionMember> + '_>;
+    /// Get the links to the external resources about the manifest, e.g. the homepage.
+    fn get_external_links(&self, language: &str) -> Vec<ExternalLink>;
+    /// Get the alternative representations of the whole manifest to download, e.g. a PDF.
+    fn get_renderings(&self, language: &str) -> Vec<Rendering>;
+    /// Get the URL of the thumbnail of the whole manifest, from its image service if any. Empty
+    /// if none.
+    fn get_thumbnail(&self) -> Cow<'_, str>;
+    /// Whether the canvases are the pages of a bound volume to be shown in the spreads.
+    fn is_paged(&self) -> bool;
+}
+
+/// Trait that represents an IIIF collection needed by the UI.
+pub(crate) trait IsCollection: Send + Sync {
+    fn get_title(&self, language: &str) -> Cow<'_, str>;
+    fn get_members(&self) -> Box<dyn ExactSizeIterator<Item = &dyn IsCollectionMember> + '_>;
+}
+
+/// Trait that represents a manifest or a sub-collection in an IIIF collection needed by the UI.
+pub(crate) trait IsCollectionMember {
+    fn get_id(&self) -> Cow<'_, str>;
+    fn get_label(&self, language: &str) -> Box<dyn Iterator<Item = Cow<'_, str>> + '_>;
+    fn is_collection(&self) -> bool;
+    /// Get the URL of the thumbnail of the member, empty if none.
+    fn get_thumbnail(&self) -> Cow<'_, str>;
 }
 
 /// Trait that represents a sequence in IIIF manifest needed by the UI.
@@ -25,10 +134,43 @@ pub(crate) trait IsSequence {
 
 /// Trait that represents a canvas in a sequence in IIIF manifest needed by the UI.
 pub(crate) trait IsCanvas {
+    fn get_id(&self) -> Cow<'_, str>;
     fn get_label(&self, language: &str) -> Box<dyn Iterator<Item = Cow<'_, str>> + '_>;
     fn get_thumbnail(&self) -> Cow<'_, str>;
+    /// Get the width and the height of the canvas the images are painted on. `None` if not
+    /// given.
+    fn get_size(&self) -> Option<(u32, u32)>;
     // fn get_images(&self) -> Box<dyn ExactSizeIterator<Item = &dyn IsImage> + '_>;
     fn get_image(&self, index: usize) -> Result<&dyn IsImage, IiifError>;
+    /// Get the choices of the image at the index. A non-choice image has only one choice.
+    fn get_image_choices(
+        &self,
+        index: usize,
+    ) -> Result<Box<dyn ExactSizeIterator<Item = &dyn IsImage> + '_>, IiifError>;
+    /// Get the media fragment of the region of the canvas the image at the index is painted on,
+    /// e.g. "xywh=10,20,100,50". `None` if painted on the whole canvas.
+    fn get_image_target_fragment(&self, index: usize) -> Option<String>;
+    /// Get the orientation of the image choice at the index painted on the canvas, e.g. rotated
+    /// by the selector of the annotation.
+    fn get_image_orientation(&self, index: usize, choice_index: usize) -> ImageOrientation;
+    /// Get the canvas to show while the content of the canvas is not available, e.g. a poster
+    /// image of a video.
+    fn get_placeholder_canvas(&self) -> Option<&dyn IsCanvas>;
+    /// Get the canvas to show along with the content of the canvas, e.g. a score of an audio
+    /// recording.
+    fn get_accompanying_canvas(&self) -> Option<&dyn IsCanvas>;
+    /// Get the regions of interest of the canvas, e.g. by the highlighting annotations.
+    fn get_highlights(&self, language: &str) -> Vec<CanvasHighlight>;
+    /// Get the length in seconds of a time-based canvas, e.g. of a video. `None` if not
+    /// time-based.
+    fn get_duration(&self) -> Option<f64>;
+    /// Get the annotations of the canvas targeting an interval of its playback, e.g. the
+    /// captions or the regions commented on in a video.
+    fn get_timed_annotations(&self, language: &str) -> Vec<CanvasHighlight>;
+    /// Get the alternative representations of the canvas to download, e.g. a PDF or its text.
+    fn get_renderings(&self, language: &str) -> Vec<Rendering>;
+    /// Get the behavior of the canvas in the spreads of a paged manifest.
+    fn get_page_behavior(&self) -> PageBehavior;
 }
 
 /// Trait that represents a image in a canvas in a sequence in IIIF manifest needed by the UI.
@@ -36,6 +178,7 @@ pub(crate) trait IsImage {
     fn get_service(&self) -> Cow<'_, str>;
     fn get_id(&self) -> Cow<'_, str>;
     fn get_type(&self) -> Cow<'_, str>;
+    fn get_label(&self, language: &str) -> Box<dyn Iterator<Item = Cow<'_, str>> + '_>;
     // fn get_width(&self) -> u32;
     // fn get_height(&self) -> u32;
 }
@@ -0,0 +1,194 @@
+use crate::{
+    app::{app_settings::AppSettings, app_state::AppState},
+    camera::{camera_ext, main_camera::MainCamera2d},
+    presentation::manifest::Manifest,
+    redraw_governor::RedrawGovernor,
+    rendering::tiled_image::TiledImage,
+    web,
+};
+use bevy::prelude::{
+    Camera, GlobalTransform, Rect, Res, ResMut, Resource, Single, Time, With, debug,
+};
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    time::Duration,
+};
+
+/// Canvas and view shown when the manifest is reloaded, kept if the manifest still has the
+/// canvas.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ReloadTarget {
+    pub(crate) url: String,
+    pub(crate) canvas_id: String,
+    /// Endpoint of the image shown, kept without loading it again if it is unchanged.
+    pub(crate) endpoint: String,
+    /// Region of the full size image in view, if any.
+    pub(crate) region: Option<Rect>,
+}
+
+/// Reload of the manifest shown, on request or at the interval of the settings, e.g. for the
+/// curators editing it.
+#[derive(Resource, Default)]
+pub(crate) struct ManifestReload {
+    pub(crate) requested: bool,
+    /// Time of the next automatic reload, if enabled.
+    next_reload: Option<Duration>,
+    pending: Option<ReloadTarget>,
+    /// Hash of the JSON of the manifest last loaded, to skip the reload if it is unchanged.
+    json_hash: Option<u64>,
+}
+
+/// Get the hash of the JSON.
+fn get_hash(json: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+
+    json.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl ManifestReload {
+    /// Whether the manifest at the URL is reloaded with the same JSON, which ends the reload.
+    pub(crate) fn is_unchanged(&mut self, url: &str, json: &str) -> bool {
+        let unchanged = self.pending.as_ref().is_some_and(|x| x.url == url)
+            && self.json_hash == Some(get_hash(json));
+
+        if unchanged {
+            self.pending = None;
+        }
+
+        unchanged
+    }
+
+    /// Take the target of the reload when the manifest at the URL is loaded with the JSON.
+    /// `None` if it is not reloaded, e.g. another manifest is opened.
+    pub(crate) fn finish(&mut self, url: &str, json: &str) -> Option<ReloadTarget> {
+        self.json_hash = Some(get_hash(json));
+        self.pending.take().filter(|x| x.url == url)
+    }
+}
+
+/// Reload the manifest shown when requested or at the interval, remembering the canvas and the
+/// view to keep them.
+///
+/// The cached manifest is revalidated, so that an unchanged one is not downloaded again if the
+/// server supports it.
+pub(crate) fn reload_system(
+    mut manifest_reload: ResMut<ManifestReload>,
+    mut app_state: ResMut<AppState>,
+    app_settings: Res<AppSettings>,
+    time: Res<Time>,
+    presentation: Option<Single<&Manifest>>,
+    tiled_image: Option<Single<&TiledImage>>,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera2d>>,
+    mut redraw_governor: ResMut<RedrawGovernor>,
+) {
+    let Some(presentation) = presentation else {
+        manifest_reload.requested = false;
+        manifest_reload.next_reload = None;
+        return;
+    };
+
+    let interval = Duration::from_secs(app_settings.reload_interval_secs.into());
+
+    manifest_reload.next_reload = match manifest_reload.next_reload {
+        _ if interval.is_zero() => None,
+        None => Some(time.elapsed() + interval),
+        // Sooner if the interval is shortened.
+        Some(x) => Some(x.min(time.elapsed() + interval)),
+    };
+
+    let is_due = manifest_reload
+        .next_reload
+        .is_some_and(|x| time.elapsed() >= x);
+
+    // Wake up for the reload in time, e.g. while the window is unfocused.
+    if let Some(next_reload) = manifest_reload.next_reload {
+        redraw_governor.wake_up_in(next_reload.saturating_sub(time.elapsed()));
+    }
+    let is_loading = app_state
+        .manifest_json_download_state
+        .lock()
+        .expect("should be able to lock the presentation download state mutex")
+        .is_in_progress();
+
+    if !(manifest_reload.requested || is_due) || is_loading {
+        return;
+    }
+
+    // The user may have logged in to the image services refusing the image info before.
+    if manifest_reload.requested {
+        app_state.capability_cache.clear_auth_required();
+    }
+
+    manifest_reload.requested = false;
+    manifest_reload.next_reload = None;
+
+    let canvas_id = presentation
+        .model()
+        .get_sequence(app_state.sequence_index)
+        .and_then(|x| x.get_canvas(app_state.canvas_index))
+        .map(|x| x.get_id().to_string())
+        .unwrap_or_default();
+    let (camera, global_transform) = camera.into_inner();
+    let (endpoint, region) = match tiled_image {
+        Some(tiled_image) => (
+            tiled_image.get_endpoint().to_string(),
+            camera_ext::get_world_viewport_rect(camera, global_transform).map(|(min, max)| {
+                tiled_image.world_to_image_rect(Rect::from_corners(min.truncate(), max.truncate()))
+            }),
+        ),
+        None => (String::new(), None),
+    };
+    let url = app_state.presentation_url.to_string();
+
+    debug!("Reload the manifest {}", url);
+
+    manifest_reload.pending = Some(ReloadTarget {
+        url: url.to_string(),
+        canvas_id,
+        endpoint,
+        region,
+    });
+    app_state.http_cache.expire(&url);
+    web::load_presentation(&mut app_state, &app_settings, &url);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_reload() {
+        let url = "https://example.org/manifest";
+        let mut manifest_reload = ManifestReload::default();
+
+        assert_eq!(manifest_reload.finish(url, "{}"), None);
+
+        let target = ReloadTarget {
+            url: url.to_string(),
+            canvas_id: "https://example.org/canvas/2".to_string(),
+            endpoint: "https://example.org/iiif/2".to_string(),
+            region: None,
+        };
+
+        manifest_reload.pending = Some(target.clone());
+        assert!(manifest_reload.is_unchanged(url, "{}"));
+        assert_eq!(manifest_reload.pending, None);
+
+        manifest_reload.pending = Some(target.clone());
+        assert!(!manifest_reload.is_unchanged(url, "{\"a\":1}"));
+        assert!(!manifest_reload.is_unchanged("https://example.org/other", "{}"));
+        assert_eq!(
+            manifest_reload.finish(url, "{\"a\":1}"),
+            Some(target.clone())
+        );
+
+        // Another manifest opened while reloading.
+        manifest_reload.pending = Some(target);
+        assert_eq!(
+            manifest_reload.finish("https://example.org/other", "{}"),
+            None
+        );
+        assert_eq!(manifest_reload.pending, None);
+    }
+}
@@ -0,0 +1,136 @@
+use bevy_egui::egui;
+
+/// Badge of the rights statement, bundled as an icon.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum RightsBadge {
+    CreativeCommons,
+    PublicDomain,
+    InCopyright,
+    NoCopyright,
+    /// Copyright not evaluated, undetermined or not known.
+    Other,
+}
+
+impl RightsBadge {
+    /// Get the icon of the badge.
+    pub(crate) fn image(&self) -> egui::ImageSource<'static> {
+        match self {
+            Self::CreativeCommons => egui::include_image!("rights/cc.svg"),
+            Self::PublicDomain => egui::include_image!("rights/public-domain.svg"),
+            Self::InCopyright => egui::include_image!("rights/in-copyright.svg"),
+            Self::NoCopyright => egui::include_image!("rights/no-copyright.svg"),
+            Self::Other => egui::include_image!("rights/other.svg"),
+        }
+    }
+}
+
+/// Rights statement recognised from its URI, e.g. a Creative Commons licence.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RightsStatement {
+    /// Short name, e.g. "CC BY-SA 4.0".
+    pub(crate) name: String,
+    pub(crate) badge: RightsBadge,
+}
+
+impl RightsStatement {
+    /// Get the statement from the URI of a Creative Commons licence or a RightsStatements.org
+    /// statement, or `None` if the URI is not recognised.
+    pub(crate) fn try_from_uri(uri: &str) -> Option<Self> {
+        let path = uri
+            .trim()
+            .strip_prefix("https://")
+            .or_else(|| uri.trim().strip_prefix("http://"))?;
+        let path = path.strip_prefix("www.").unwrap_or(path);
+        let parts = path
+            .split(['/', '?', '#'])
+            .filter(|x| !x.is_empty())
+            .collect::<Vec<_>>();
+
+        match parts.as_slice() {
+            ["creativecommons.org", "licenses", code, version, ..] => Some(Self {
+                name: format!("CC {} {}", code.to_uppercase(), version),
+                badge: RightsBadge::CreativeCommons,
+            }),
+            ["creativecommons.org", "publicdomain", "zero", version, ..] => Some(Self {
+                name: format!("CC0 {}", version),
+                badge: RightsBadge::PublicDomain,
+            }),
+            ["creativecommons.org", "publicdomain", "mark", version, ..] => Some(Self {
+                name: format!("Public Domain Mark {}", version),
+                badge: RightsBadge::PublicDomain,
+            }),
+            ["rightsstatements.org", "vocab" | "page", code, ..] => {
+                let name = match *code {
+                    "InC" => "In Copyright",
+                    "InC-OW-EU" => "In Copyright - EU Orphan Work",
+                    "InC-EDU" => "In Copyright - Educational Use Permitted",
+                    "InC-NC" => "In Copyright - Non-Commercial Use Permitted",
+                    "InC-RUU" => "In Copyright - Rights-holder(s) Unlocatable or Unidentifiable",
+                    "NoC-CR" => "No Copyright - Contractual Restrictions",
+                    "NoC-NC" => "No Copyright - Non-Commercial Use Only",
+                    "NoC-OKLR" => "No Copyright - Other Known Legal Restrictions",
+                    "NoC-US" => "No Copyright - United States",
+                    "CNE" => "Copyright Not Evaluated",
+                    "UND" => "Copyright Undetermined",
+                    "NKC" => "No Known Copyright",
+                    _ => return None,
+                };
+                let badge = if code.starts_with("InC") {
+                    RightsBadge::InCopyright
+                } else if code.starts_with("NoC") {
+                    RightsBadge::NoCopyright
+                } else {
+                    RightsBadge::Other
+                };
+
+                Some(Self {
+                    name: name.to_string(),
+                    badge,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_try_from_uri() {
+        assert_eq!(
+            RightsStatement::try_from_uri("http://creativecommons.org/licenses/by-sa/4.0/"),
+            Some(RightsStatement {
+                name: "CC BY-SA 4.0".to_string(),
+                badge: RightsBadge::CreativeCommons,
+            })
+        );
+        assert_eq!(
+            RightsStatement::try_from_uri("https://creativecommons.org/publicdomain/zero/1.0/"),
+            Some(RightsStatement {
+                name: "CC0 1.0".to_string(),
+                badge: RightsBadge::PublicDomain,
+            })
+        );
+        assert_eq!(
+            RightsStatement::try_from_uri("http://rightsstatements.org/vocab/NoC-US/1.0/"),
+            Some(RightsStatement {
+                name: "No Copyright - United States".to_string(),
+                badge: RightsBadge::NoCopyright,
+            })
+        );
+        assert_eq!(
+            RightsStatement::try_from_uri("http://rightsstatements.org/vocab/UND/1.0/"),
+            Some(RightsStatement {
+                name: "Copyright Undetermined".to_string(),
+                badge: RightsBadge::Other,
+            })
+        );
+        assert_eq!(
+            RightsStatement::try_from_uri("https://example.org/licence"),
+            None
+        );
+        assert_eq!(RightsStatement::try_from_uri("All rights reserved"), None);
+    }
+}
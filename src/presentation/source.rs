@@ -0,0 +1,398 @@
+use crate::{iiif::IiifError, presentation::manifest::Manifest, tilesource};
+use bevy::prelude::{Resource, debug};
+
+/// A backend converting the content fetched from a URL into a manifest, so that non-IIIF
+/// content can be opened through the same UI.
+pub(crate) trait ManifestSource: Send + Sync {
+    /// Name of the source for the logs.
+    fn name(&self) -> &'static str;
+
+    /// Whether the content fetched from the URL is handled by this source.
+    fn accepts(&self, url: &str, content: &str) -> bool;
+
+    /// Convert the content fetched from the URL into a manifest.
+    fn try_into_manifest(&self, url: &str, content: &str) -> Result<Manifest, IiifError>;
+}
+
+/// IIIF presentation manifest in JSON.
+struct IiifSource;
+
+impl ManifestSource for IiifSource {
+    fn name(&self) -> &'static str {
+        "IIIF"
+    }
+
+    fn accepts(&self, _url: &str, content: &str) -> bool {
+        content.trim_start().starts_with('{')
+    }
+
+    fn try_into_manifest(&self, _url: &str, content: &str) -> Result<Manifest, IiifError> {
+        Manifest::try_from_json(content)
+    }
+}
+
+/// Image information (info.json) of a IIIF image service, converted into a synthetic manifest
+/// with a single canvas of the size of the image. The base URL of the service is opened too, as
+/// it redirects to the image information.
+struct ImageInfoSource;
+
+impl ImageInfoSource {
+    /// Get the image information in the JSON, or `None` if it is not of an image service.
+    fn get_image_info(content: &str) -> Option<ImageInfoJson> {
+        serde_json::from_str::<ImageInfoJson>(content)
+            .ok()
+            .filter(|x| {
+                x.protocol == "http://iiif.io/api/image"
+                    || x.context.to_string().contains("iiif.io/api/image/")
+            })
+    }
+}
+
+/// Only the context, the ID and the size of the image information are needed.
+#[derive(serde::Deserialize)]
+struct ImageInfoJson {
+    #[serde(rename = "@context", default)]
+    context: serde_json::Value,
+    #[serde(default)]
+    protocol: String,
+    #[serde(alias = "@id", default)]
+    id: String,
+    #[serde(default)]
+    width: u32,
+    #[serde(default)]
+    height: u32,
+}
+
+impl ManifestSource for ImageInfoSource {
+    fn name(&self) -> &'static str {
+        "IIIF image"
+    }
+
+    fn accepts(&self, _url: &str, content: &str) -> bool {
+        content.trim_start().starts_with('{') && Self::get_image_info(content).is_some()
+    }
+
+    fn try_into_manifest(&self, url: &str, content: &str) -> Result<Manifest, IiifError> {
+        let image_info = Self::get_image_info(content).ok_or(IiifError::IiifUnsupportedError(
+            "not the image information of a IIIF image service".to_string(),
+        ))?;
+        // Use the URL fetched if the ID is missing, e.g. of the exported tiles.
+        let service = match image_info.id.trim_end_matches('/') {
+            "" => url.trim_end_matches("/info.json").trim_end_matches('/'),
+            id => id,
+        };
+        let mut json = get_synthetic_manifest_json(service, std::iter::once(service));
+
+        if image_info.width > 0 && image_info.height > 0 {
+            json["items"][0]["width"] = image_info.width.into();
+            json["items"][0]["height"] = image_info.height.into();
+        }
+
+        Manifest::try_from_json(&json.to_string())
+    }
+}
+
+/// Plain text list of IIIF image service URLs, one per line, converted into a synthetic
+/// manifest with a canvas for each image. Empty lines and lines starting with '#' are ignored.
+struct ImageListSource;
+
+impl ImageListSource {
+    /// Get the image service URLs in the list.
+    fn get_services(content: &str) -> impl Iterator<Item = &str> {
+        content
+            .lines()
+            .map(|x| x.trim())
+            .filter(|x| !x.is_empty() && !x.starts_with('#'))
+            .map(|x| x.trim_end_matches("/info.json").trim_end_matches('/'))
+    }
+}
+
+impl ManifestSource for ImageListSource {
+    fn name(&self) -> &'static str {
+        "image list"
+    }
+
+    fn accepts(&self, _url: &str, content: &str) -> bool {
+        let mut services = Self::get_services(content).peekable();
+
+        services.peek().is_some()
+            && services.all(|x| x.starts_with("https://") || x.starts_with("http://"))
+    }
+
+    fn try_into_manifest(&self, url: &str, content: &str) -> Result<Manifest, IiifError> {
+        get_synthetic_manifest(url, Self::get_services(content))
+    }
+}
+
+/// DeepZoom (DZI) or Zoomify descriptor XML, converted into a synthetic manifest with a single
+/// canvas for the image.
+struct TileDescriptorSource;
+
+impl ManifestSource for TileDescriptorSource {
+    fn name(&self) -> &'static str {
+        "DeepZoom/Zoomify"
+    }
+
+    fn accepts(&self, _url: &str, content: &str) -> bool {
+        let content = content.trim_start();
+
+        content.starts_with('<')
+            && (tilesource::get_xml_attribute(content, "Image", "TileSize").is_some()
+                || tilesource::get_xml_attribute(content, "IMAGE_PROPERTIES", "WIDTH").is_some())
+    }
+
+    fn try_into_manifest(&self, url: &str, _content: &str) -> Result<Manifest, IiifError> {
+        // The descriptor is the image info, so the URL is used as the image service.
+        get_synthetic_manifest(url, std::iter::once(url))
+    }
+}
+
+/// Get a manifest with a canvas for each of the image services.
+fn get_synthetic_manifest<'a>(
+    url: &str,
+    services: impl Iterator<Item = &'a str>,
+) -> Result<Manifest, IiifError> {
+    Manifest::try_from_json(&get_synthetic_manifest_json(url, services).to_string())
+}
+
+/// Get the JSON of a IIIF v3 manifest at the URL with a canvas for each of the image services.
+pub(crate) fn get_synthetic_manifest_json<'a>(
+    url: &str,
+    services: impl Iterator<Item = &'a str>,
+) -> serde_json::Value {
+    let canvases = services
+        .enumerate()
+        .map(|(index, service)| {
+            let canvas_id = format!("{}#canvas-{}", url, index + 1);
+            let image_id =
+                if tilesource::is_descriptor_url(service) || tilesource::is_image_url(service) {
+                    service.to_string()
+                } else {
+                    format!("{}/full/max/0/default.jpg", service)
+                };
+
+            serde_json::json!({
+                "id": canvas_id,
+                "type": "Canvas",
+                "label": { "none": [(index + 1).to_string()] },
+                "items": [{
+                    "id": format!("{}/page", canvas_id),
+                    "type": "AnnotationPage",
+                    "items": [{
+                        "id": format!("{}/image", canvas_id),
+                        "type": "Annotation",
+                        "body": {
+                            "id": image_id,
+                            "type": "Image",
+                            "service": [{
+                                "id": service,
+                                "type": "ImageService3",
+                                "profile": "level0"
+                            }]
+                        }
+                    }]
+                }]
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let title = url.rsplit('/').find(|x| !x.is_empty()).unwrap_or(url);
+    serde_json::json!({
+        "@context": "http://iiif.io/api/presentation/3/context.json",
+        "id": url,
+        "type": "Manifest",
+        "label": { "none": [title] },
+        "items": canvases
+    })
+}
+
+/// Get the JSON of a IIIF v3 collection at the URL with the title, listing the manifests, e.g.
+/// found on a web page.
+pub(crate) fn get_synthetic_collection_json(
+    url: &str,
+    title: &str,
+    manifest_urls: &[String],
+) -> serde_json::Value {
+    let members = manifest_urls
+        .iter()
+        .map(|x| serde_json::json!({ "id": x, "type": "Manifest" }))
+        .collect::<Vec<_>>();
+
+    serde_json::json!({
+        "@context": "http://iiif.io/api/presentation/3/context.json",
+        "id": url,
+        "type": "Collection",
+        "label": { "none": [title] },
+        "items": members
+    })
+}
+
+/// Manifest sources tried in order for the fetched content.
+#[derive(Resource)]
+pub(crate) struct ManifestSources(Vec<Box<dyn ManifestSource>>);
+
+impl Default for ManifestSources {
+    fn default() -> Self {
+        Self(vec![
+            // Before the manifest, which accepts any JSON.
+            Box::new(ImageInfoSource),
+            Box::new(IiifSource),
+            Box::new(ImageListSource),
+            Box::new(TileDescriptorSource),
+        ])
+    }
+}
+
+impl ManifestSources {
+    /// Convert the content fetched from the URL into a manifest with the first source accepting it.
+    pub(crate) fn try_into_manifest(
+        &self,
+        url: &str,
+        content: &str,
+    ) -> Result<Manifest, IiifError> {
+        let source = self.0.iter().find(|x| x.accepts(url, content)).ok_or(
+            IiifError::IiifUnsupportedError(
+                "content not handled by any manifest source".to_string(),
+            ),
+        )?;
+
+        debug!("Open {} with the {} source", url, source.name());
+
+        source.try_into_manifest(url, content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_image_list_source() {
+        let content =
+            "# Scans\nhttps://example.org/iiif/p1/info.json\n\nhttps://example.org/iiif/p2/\n";
+
+        assert!(ImageListSource.accepts("https://example.org/book.txt", content));
+        assert!(!ImageListSource.accepts("https://example.org/book.txt", "\n# Nothing\n"));
+        assert!(!ImageListSource.accepts("https://example.org/book.txt", "{}"));
+        assert!(!IiifSource.accepts("https://example.org/book.txt", content));
+
+        let manifest = ManifestSources::default()
+            .try_into_manifest("https://example.org/book.txt", content)
+            .expect("should convert the image list into a manifest");
+
+        assert_eq!(manifest.model().get_title("en"), "book.txt");
+
+        let sequence = manifest
+            .model()
+            .get_sequence(0)
+            .expect("should have a sequence");
+        assert_eq!(sequence.get_canvases().len(), 2);
+        assert_eq!(
+            sequence
+                .get_canvas(1)
+                .and_then(|x| x.get_image(0))
+                .expect("should have an image")
+                .get_service(),
+            "https://example.org/iiif/p2"
+        );
+    }
+
+    #[test]
+    fn test_tile_descriptor_source() {
+        let url = "https://example.org/dzi/image.dzi";
+        let content = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <Image Format="jpg" Overlap="1" TileSize="254"><Size Width="1000" Height="600"/></Image>"#;
+
+        assert!(TileDescriptorSource.accepts(url, content));
+        assert!(!ImageListSource.accepts(url, content));
+        assert!(!TileDescriptorSource.accepts(url, "<html></html>"));
+
+        let manifest = ManifestSources::default()
+            .try_into_manifest(url, content)
+            .expect("should convert the descriptor into a manifest");
+
+        let sequence = manifest
+            .model()
+            .get_sequence(0)
+            .expect("should have a sequence");
+        assert_eq!(sequence.get_canvases().len(), 1);
+        assert_eq!(
+            sequence
+                .get_canvas(0)
+                .and_then(|x| x.get_image(0))
+                .expect("should have an image")
+                .get_service(),
+            url
+        );
+    }
+
+    #[test]
+    fn test_image_info_source() {
+        let url = "https://example.org/iiif/p1/info.json";
+        let content = r#"{
+            "@context": "http://iiif.io/api/image/3/context.json",
+            "id": "https://example.org/iiif/p1",
+            "type": "ImageService3",
+            "protocol": "http://iiif.io/api/image",
+            "profile": "level1",
+            "width": 6000,
+            "height": 4000
+        }"#;
+
+        assert!(ImageInfoSource.accepts(url, content));
+        assert!(!ImageInfoSource.accepts(url, r#"{"type": "Manifest"}"#));
+        assert!(!ImageInfoSource.accepts(url, "not json"));
+
+        let manifest = ManifestSources::default()
+            .try_into_manifest(url, content)
+            .expect("should convert the image info into a manifest");
+
+        assert_eq!(manifest.model().get_title("en"), "p1");
+
+        let sequence = manifest
+            .model()
+            .get_sequence(0)
+            .expect("should have a sequence");
+        assert_eq!(sequence.get_canvases().len(), 1);
+
+        let canvas = sequence.get_canvas(0).expect("should have a canvas");
+        assert_eq!(canvas.get_size(), Some((6000, 4000)));
+        assert_eq!(
+            canvas
+                .get_image(0)
+                .expect("should have an image")
+                .get_service(),
+            "https://example.org/iiif/p1"
+        );
+
+        // Image API 2 with the ID missing.
+        let content = r#"{
+            "@context": "http://iiif.io/api/image/2/context.json",
+            "protocol": "http://iiif.io/api/image",
+            "width": 100,
+            "height": 200
+        }"#;
+        let manifest = ManifestSources::default()
+            .try_into_manifest(url, content)
+            .expect("should convert the image info into a manifest");
+
+        assert_eq!(
+            manifest
+                .model()
+                .get_sequence(0)
+                .and_then(|x| x.get_canvas(0))
+                .and_then(|x| x.get_image(0))
+                .expect("should have an image")
+                .get_service(),
+            "https://example.org/iiif/p1"
+        );
+    }
+
+    #[test]
+    fn test_no_source() {
+        assert!(matches!(
+            ManifestSources::default().try_into_manifest("https://example.org", "<html></html>"),
+            Err(IiifError::IiifUnsupportedError(_))
+        ));
+    }
+}
@@ -0,0 +1,72 @@
+use crate::presentation::model::PageBehavior;
+
+/// Get the indices of the canvases in each spread of a paged manifest, in the order shown.
+///
+/// The first page is shown alone as the first recto of a book, and the following ones are paired
+/// with their facing page. The canvases showing both facing pages are shown alone, and those not
+/// being pages, e.g. a color chart, are shown alone without breaking the pairs around them.
+pub(crate) fn get_spreads(behaviors: &[PageBehavior]) -> Vec<Vec<usize>> {
+    let mut spreads = Vec::new();
+    let mut verso = None;
+    // Canvases not being pages after the verso, shown once it is paired.
+    let mut deferred = Vec::new();
+    let mut is_first_page = true;
+
+    for (index, behavior) in behaviors.iter().enumerate() {
+        match behavior {
+            PageBehavior::NonPaged if verso.is_some() => deferred.push(vec![index]),
+            PageBehavior::NonPaged => spreads.push(vec![index]),
+            PageBehavior::FacingPages => {
+                if let Some(verso) = verso.take() {
+                    spreads.push(vec![verso]);
+                }
+                spreads.append(&mut deferred);
+                spreads.push(vec![index]);
+                is_first_page = false;
+            }
+            PageBehavior::Paged if is_first_page => {
+                spreads.push(vec![index]);
+                is_first_page = false;
+            }
+            PageBehavior::Paged => match verso.take() {
+                Some(verso) => {
+                    spreads.push(vec![verso, index]);
+                    spreads.append(&mut deferred);
+                }
+                None => verso = Some(index),
+            },
+        }
+    }
+
+    if let Some(verso) = verso {
+        spreads.push(vec![verso]);
+    }
+    spreads.append(&mut deferred);
+
+    spreads
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_spreads() {
+        use PageBehavior::{FacingPages, NonPaged, Paged};
+
+        assert_eq!(get_spreads(&[]), Vec::<Vec<usize>>::new());
+        assert_eq!(
+            get_spreads(&[Paged, Paged, Paged, Paged]),
+            vec![vec![0], vec![1, 2], vec![3]]
+        );
+        // The color chart does not break the pair around it.
+        assert_eq!(
+            get_spreads(&[NonPaged, Paged, Paged, NonPaged, Paged]),
+            vec![vec![0], vec![1], vec![2, 4], vec![3]]
+        );
+        assert_eq!(
+            get_spreads(&[Paged, Paged, FacingPages, Paged, Paged]),
+            vec![vec![0], vec![1], vec![2], vec![3, 4]]
+        );
+    }
+}
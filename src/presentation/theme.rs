@@ -0,0 +1,220 @@
+use crate::{
+    app::app_settings::{AppSettings, ThemeMode},
+    minimap::{MinimapFrame, MinimapViewRect},
+};
+use bevy::{
+    prelude::{
+        BackgroundColor, BorderColor, Color, MessageReader, Res, ResMut, Resource, Result, Single,
+        With, Without, info,
+    },
+    window::{PrimaryWindow, Window, WindowTheme, WindowThemeChanged},
+};
+use bevy_egui::{
+    EguiContexts,
+    egui::{self, Color32},
+};
+use std::collections::BTreeMap;
+
+/// Alpha of the background of the minimap, to see the image under it.
+const MINIMAP_BACKGROUND_ALPHA: f32 = 0.75;
+
+/// Scale of the fonts of the UI in the low-vision mode.
+const LOW_VISION_FONT_SCALE: f32 = 1.4;
+
+/// Dark or light preference of the system, if it is known.
+#[derive(Resource, Default)]
+pub(crate) struct SystemTheme(Option<WindowTheme>);
+
+/// Colors of the minimap in the theme.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct MinimapColors {
+    background: Color,
+    border: Color,
+    view_rect: Color,
+}
+
+/// Get the theme of the UI in the mode, which is dark if the preference of the system is not
+/// known.
+fn get_theme(theme_mode: ThemeMode, system_theme: Option<WindowTheme>) -> egui::Theme {
+    match (theme_mode, system_theme) {
+        (ThemeMode::Dark, _) => egui::Theme::Dark,
+        (ThemeMode::Light, _) => egui::Theme::Light,
+        (ThemeMode::System, Some(WindowTheme::Light)) => egui::Theme::Light,
+        (ThemeMode::System, _) => egui::Theme::Dark,
+    }
+}
+
+/// Get the colors of the minimap in the theme, with the view rect in the accent color if any.
+fn get_minimap_colors(theme: egui::Theme, accent_color: Option<[u8; 3]>) -> MinimapColors {
+    let (background, border, view_rect) = match theme {
+        egui::Theme::Dark => (0.2, 0.5, Color::srgb(0.0, 0.5, 0.5)),
+        egui::Theme::Light => (0.9, 0.6, Color::srgb(0.0, 0.4, 0.4)),
+    };
+
+    MinimapColors {
+        background: Color::srgba(background, background, background, MINIMAP_BACKGROUND_ALPHA),
+        border: Color::srgb(border, border, border),
+        view_rect: accent_color.map_or(view_rect, |[r, g, b]| Color::srgb_u8(r, g, b)),
+    }
+}
+
+/// Make the visuals high-contrast for the low vision, with the text and the strokes in white on
+/// black in the dark theme, or in black on white in the light one.
+fn apply_high_contrast(visuals: &mut egui::Visuals) {
+    let (fg, bg, selection) = if visuals.dark_mode {
+        (
+            Color32::WHITE,
+            Color32::BLACK,
+            Color32::from_rgb(0, 70, 180),
+        )
+    } else {
+        (
+            Color32::BLACK,
+            Color32::WHITE,
+            Color32::from_rgb(255, 230, 0),
+        )
+    };
+
+    visuals.override_text_color = Some(fg);
+    visuals.panel_fill = bg;
+    visuals.window_fill = bg;
+    visuals.extreme_bg_color = bg;
+    visuals.faint_bg_color = bg;
+    visuals.window_stroke = egui::Stroke::new(2.0, fg);
+    visuals.hyperlink_color = fg;
+    visuals.selection.bg_fill = selection;
+    visuals.selection.stroke = egui::Stroke::new(2.0, fg);
+
+    for widget in [
+        &mut visuals.widgets.noninteractive,
+        &mut visuals.widgets.inactive,
+        &mut visuals.widgets.hovered,
+        &mut visuals.widgets.active,
+        &mut visuals.widgets.open,
+    ] {
+        widget.fg_stroke = egui::Stroke::new(widget.fg_stroke.width.max(1.5), fg);
+        widget.bg_stroke = egui::Stroke::new(widget.bg_stroke.width.max(1.0), fg);
+    }
+}
+
+/// Get the text styles of the UI with the fonts scaled from the default sizes.
+fn get_text_styles(font_scale: f32) -> BTreeMap<egui::TextStyle, egui::FontId> {
+    egui::Style::default()
+        .text_styles
+        .into_iter()
+        .map(|(style, mut font_id)| {
+            font_id.size *= font_scale;
+            (style, font_id)
+        })
+        .collect()
+}
+
+/// Keep the dark or light preference of the system, from the window when it is created and when
+/// it is changed.
+pub(crate) fn system_theme_system(
+    window: Single<&Window, With<PrimaryWindow>>,
+    mut theme_changed_reader: MessageReader<WindowThemeChanged>,
+    mut system_theme: ResMut<SystemTheme>,
+) {
+    let theme = theme_changed_reader
+        .read()
+        .last()
+        .map(|x| x.theme)
+        .or(system_theme.0)
+        .or(window.window_theme);
+
+    if theme != system_theme.0 {
+        info!("System theme {:?}", theme);
+        system_theme.0 = theme;
+    }
+}
+
+/// Style the egui panels and the minimap in the theme of the settings, with the accent color if
+/// any, or with the larger fonts and the high contrast in the low-vision mode.
+pub(crate) fn theme_system(
+    mut contexts: EguiContexts,
+    app_settings: Res<AppSettings>,
+    system_theme: Res<SystemTheme>,
+    minimap_frame: Single<(&mut BackgroundColor, &mut BorderColor), With<MinimapFrame>>,
+    mut minimap_view_rect: Single<&mut BorderColor, (With<MinimapViewRect>, Without<MinimapFrame>)>,
+) -> Result {
+    let ctx = contexts.ctx_mut()?;
+    let theme = get_theme(app_settings.theme_mode, system_theme.0);
+    let mut visuals = theme.default_visuals();
+
+    let low_vision = app_settings.low_vision_settings.enabled;
+
+    if let Some([r, g, b]) = app_settings.accent_color {
+        visuals.selection.bg_fill = Color32::from_rgb(r, g, b);
+    }
+    if low_vision {
+        apply_high_contrast(&mut visuals);
+    }
+
+    let text_styles = get_text_styles(if low_vision {
+        LOW_VISION_FONT_SCALE
+    } else {
+        1.0
+    });
+
+    ctx.set_theme(theme);
+    ctx.set_visuals_of(theme, visuals);
+    ctx.all_styles_mut(|style| style.text_styles = text_styles.clone());
+
+    let minimap_colors = get_minimap_colors(theme, app_settings.accent_color);
+    let (mut background_color, mut border_color) = minimap_frame.into_inner();
+
+    background_color.set_if_neq(BackgroundColor(minimap_colors.background));
+    border_color.set_if_neq(BorderColor::all(minimap_colors.border));
+    minimap_view_rect.set_if_neq(BorderColor::all(minimap_colors.view_rect));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_theme() {
+        assert_eq!(get_theme(ThemeMode::System, None), egui::Theme::Dark);
+        assert_eq!(
+            get_theme(ThemeMode::System, Some(WindowTheme::Light)),
+            egui::Theme::Light
+        );
+        assert_eq!(
+            get_theme(ThemeMode::Dark, Some(WindowTheme::Light)),
+            egui::Theme::Dark
+        );
+        assert_eq!(get_theme(ThemeMode::Light, None), egui::Theme::Light);
+    }
+
+    #[test]
+    fn test_low_vision() {
+        let mut visuals = egui::Theme::Dark.default_visuals();
+
+        apply_high_contrast(&mut visuals);
+
+        assert_eq!(visuals.override_text_color, Some(Color32::WHITE));
+        assert_eq!(visuals.panel_fill, Color32::BLACK);
+
+        let default_size = egui::Style::default().text_styles[&egui::TextStyle::Body].size;
+
+        assert_eq!(
+            get_text_styles(2.0)[&egui::TextStyle::Body].size,
+            default_size * 2.0
+        );
+    }
+
+    #[test]
+    fn test_get_minimap_colors() {
+        assert_eq!(
+            get_minimap_colors(egui::Theme::Light, Some([255, 0, 0])).view_rect,
+            Color::srgb_u8(255, 0, 0)
+        );
+        assert_eq!(
+            get_minimap_colors(egui::Theme::Dark, None).view_rect,
+            Color::srgb(0.0, 0.5, 0.5)
+        );
+    }
+}
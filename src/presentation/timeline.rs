@@ -0,0 +1,183 @@
+use crate::{
+    CanvasChanged,
+    app::app_state::AppState,
+    presentation::{bookmarks::parse_xywh, manifest::Manifest, model::CanvasHighlight},
+};
+use bevy::prelude::{MessageReader, Rect, Res, ResMut, Resource, Single};
+
+/// Timeline of a time-based canvas, e.g. a video, scrubbed by the user to show its timed
+/// annotations at the position. The media itself is not played.
+#[derive(Resource, Debug, Default)]
+pub(crate) struct Timeline {
+    /// Position in seconds from the start of the canvas.
+    pub(crate) position: f64,
+    /// Length in seconds of the canvas, 0 if it is not time-based.
+    pub(crate) duration: f64,
+}
+
+impl Timeline {
+    /// Whether the current canvas is time-based.
+    pub(crate) fn is_time_based(&self) -> bool {
+        self.duration > 0.0
+    }
+
+    /// Move to the start of a canvas of the length.
+    fn reset(&mut self, duration: f64) {
+        self.position = 0.0;
+        self.duration = duration;
+    }
+}
+
+/// Annotation of a time-based canvas with its interval and region.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TimedAnnotation {
+    /// Label or text of the annotation, which may be HTML.
+    pub(crate) label: String,
+    /// Region of the canvas, or `None` if it is the whole canvas, e.g. a caption.
+    pub(crate) region: Option<Rect>,
+    pub(crate) start: f64,
+    /// End in seconds, or `None` until the end of the canvas.
+    pub(crate) end: Option<f64>,
+}
+
+impl TimedAnnotation {
+    /// Get the annotation of the media fragment with the interval, e.g. "xywh=1,2,3,4&t=5,10".
+    pub(crate) fn from_highlight(highlight: &CanvasHighlight) -> Option<Self> {
+        let mut parts = highlight.fragment.split('&');
+        let (start, end) = parts
+            .clone()
+            .find_map(|x| x.strip_prefix("t="))
+            .and_then(parse_temporal_fragment)?;
+        let region = parts.find(|x| x.starts_with("xywh=")).and_then(parse_xywh);
+
+        Some(Self {
+            label: highlight.label.to_string(),
+            region,
+            start,
+            end,
+        })
+    }
+
+    /// Whether the annotation is shown at the position in seconds.
+    pub(crate) fn is_active_at(&self, position: f64) -> bool {
+        position >= self.start && self.end.is_none_or(|end| position < end)
+    }
+}
+
+/// Parse the interval in seconds of the value of a temporal media fragment, e.g. "5,10", "npt:5"
+/// or ",1:30". The end is `None` if open.
+fn parse_temporal_fragment(value: &str) -> Option<(f64, Option<f64>)> {
+    let value = value.strip_prefix("npt:").unwrap_or(value);
+    let (start, end) = value.split_once(',').unwrap_or((value, ""));
+    let start = match start {
+        "" => 0.0,
+        _ => parse_npt_time(start)?,
+    };
+    let end = match end {
+        "" => None,
+        _ => Some(parse_npt_time(end).filter(|x| *x > start)?),
+    };
+
+    Some((start, end))
+}
+
+/// Parse the normal play time in seconds, e.g. "90.5", "1:30.5" or "0:01:30.5".
+fn parse_npt_time(value: &str) -> Option<f64> {
+    value
+        .split(':')
+        .try_fold(0.0, |total, x| {
+            let x = x.trim().parse::<f64>().ok()?;
+
+            (x >= 0.0).then_some(total * 60.0 + x)
+        })
+        .filter(|x: &f64| x.is_finite())
+}
+
+/// Format the position in seconds as minutes and seconds, e.g. "1:05".
+pub(crate) fn format_position(secs: f64) -> String {
+    let secs = secs.max(0.0) as u64;
+
+    match secs / 3600 {
+        0 => format!("{}:{:02}", secs / 60, secs % 60),
+        hours => format!("{}:{:02}:{:02}", hours, secs / 60 % 60, secs % 60),
+    }
+}
+
+/// Reset the timeline to the length of the canvas shown.
+pub(crate) fn timeline_system(
+    mut canvas_changed_reader: MessageReader<CanvasChanged>,
+    mut timeline: ResMut<Timeline>,
+    app_state: Res<AppState>,
+    presentation: Option<Single<&Manifest>>,
+) {
+    if canvas_changed_reader.read().last().is_some() {
+        let duration = presentation
+            .and_then(|x| {
+                x.model()
+                    .get_sequence(app_state.sequence_index)
+                    .ok()?
+                    .get_canvas(app_state.canvas_index)
+                    .ok()?
+                    .get_duration()
+            })
+            .unwrap_or_default();
+
+        timeline.reset(duration);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_temporal_fragment() {
+        assert_eq!(parse_temporal_fragment("5,10"), Some((5.0, Some(10.0))));
+        assert_eq!(parse_temporal_fragment("npt:60"), Some((60.0, None)));
+        assert_eq!(parse_temporal_fragment(",1:30"), Some((0.0, Some(90.0))));
+        assert_eq!(
+            parse_temporal_fragment("0:01:00.5,0:02:00"),
+            Some((60.5, Some(120.0)))
+        );
+        assert_eq!(parse_temporal_fragment("10,5"), None);
+        assert_eq!(parse_temporal_fragment("a,b"), None);
+        assert_eq!(parse_temporal_fragment("-5"), None);
+    }
+
+    #[test]
+    fn test_timed_annotation() {
+        let annotation = TimedAnnotation::from_highlight(&CanvasHighlight {
+            label: "Bell".to_string(),
+            fragment: "xywh=10,20,100,50&t=5,10".to_string(),
+        })
+        .unwrap();
+
+        assert_eq!(annotation.region, Some(Rect::new(10.0, 20.0, 110.0, 70.0)));
+        assert!(!annotation.is_active_at(4.9));
+        assert!(annotation.is_active_at(5.0));
+        assert!(!annotation.is_active_at(10.0));
+
+        let annotation = TimedAnnotation::from_highlight(&CanvasHighlight {
+            label: "Applause".to_string(),
+            fragment: "t=60".to_string(),
+        })
+        .unwrap();
+
+        assert_eq!(annotation.region, None);
+        assert!(annotation.is_active_at(600.0));
+    }
+
+    #[test]
+    fn test_timeline() {
+        let mut timeline = Timeline::default();
+
+        assert!(!timeline.is_time_based());
+
+        timeline.position = 4.0;
+        timeline.reset(10.0);
+        assert!(timeline.is_time_based());
+        assert_eq!(timeline.position, 0.0);
+        assert_eq!(format_position(3725.0), "1:02:05");
+        assert_eq!(format_position(65.0), "1:05");
+    }
+}
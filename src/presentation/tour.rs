@@ -0,0 +1,517 @@
+use crate::{
+    app::{
+        app_settings::AppSettings,
+        app_state::{AppState, DownloadState},
+    },
+    camera::main_camera::MainCamera2d,
+    iiif::IiifError,
+    notification::UserNotification,
+    presentation::{bookmarks::parse_xywh, manifest::Manifest},
+    redraw_governor::{RedrawActivity, RedrawGovernor},
+    rendering::{
+        model_image::ModelImage,
+        tile::TileModState,
+        tiled_image::{TiledImage, fit_camera_to_rect},
+    },
+    web,
+};
+use bevy::{
+    platform::time::Instant,
+    prelude::{
+        Camera, Commands, Entity, MessageWriter, OrthographicProjection, Projection, Query, Rect,
+        Res, ResMut, Resource, Result, Single, Transform, Vec3, With, info,
+    },
+};
+use serde::Deserialize;
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// Duration of the camera transition between the steps on the same canvas.
+const TRANSITION_DURATION: Duration = Duration::from_millis(1500);
+
+/// Step of a tour showing a region of a canvas with a caption.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TourStep {
+    /// Manifest of the canvas. `None` for the current manifest.
+    pub(crate) presentation_url: Option<String>,
+    pub(crate) canvas_id: String,
+    /// Region in the full size image. `None` for the whole image.
+    pub(crate) region: Option<Rect>,
+    pub(crate) caption: String,
+}
+
+/// Step in the simple tour file.
+#[derive(Deserialize)]
+struct TourStepJson {
+    manifest: Option<String>,
+    /// Canvas ID, optionally with the region as the fragment, e.g. "canvas/1#xywh=0,0,10,10".
+    canvas: String,
+    /// Region, e.g. "0,0,10,10", taking precedence over the one in the canvas ID.
+    xywh: Option<String>,
+    #[serde(default)]
+    caption: String,
+}
+
+/// Simple tour file with the default manifest of the steps.
+#[derive(Deserialize)]
+struct TourJson {
+    manifest: Option<String>,
+    steps: Vec<TourStepJson>,
+}
+
+/// Split the canvas ID with a media fragment into the ID and the region.
+fn split_canvas_fragment(canvas: &str) -> (String, Option<Rect>) {
+    match canvas.split_once('#') {
+        Some((canvas_id, fragment)) => (canvas_id.to_string(), parse_xywh(fragment)),
+        None => (canvas.to_string(), None),
+    }
+}
+
+impl TourStep {
+    /// Get the step from an annotation targeting a canvas or a region of it, e.g. an exported
+    /// bookmark, with its text body or label as the caption.
+    fn from_annotation(annotation: &serde_json::Value) -> Option<Self> {
+        let target = &annotation["target"];
+
+        let (presentation_url, canvas_id, region) = match target.as_str() {
+            Some(target) => {
+                let (canvas_id, region) = split_canvas_fragment(target);
+                (None, canvas_id, region)
+            }
+            None => {
+                let source = &target["source"];
+                let (canvas_id, fragment_region) =
+                    split_canvas_fragment(source.as_str().or(source["id"].as_str())?);
+
+                (
+                    source["partOf"][0]["id"].as_str().map(String::from),
+                    canvas_id,
+                    target["selector"]["value"]
+                        .as_str()
+                        .and_then(parse_xywh)
+                        .or(fragment_region),
+                )
+            }
+        };
+
+        let caption = annotation["body"]["value"]
+            .as_str()
+            .filter(|x| !x.is_empty())
+            .or_else(|| {
+                annotation["label"]
+                    .as_object()
+                    .and_then(|x| x.values().next())
+                    .and_then(|x| x[0].as_str())
+            })
+            .unwrap_or_default()
+            .to_string();
+
+        Some(Self {
+            presentation_url,
+            canvas_id,
+            region,
+            caption,
+        })
+    }
+}
+
+/// Get the steps from the simple tour file, or from an annotation page, e.g. the exported
+/// bookmarks.
+fn try_from_json(json: &str) -> Result<Vec<TourStep>, IiifError> {
+    let value: serde_json::Value = serde_json::from_str(json)?;
+
+    if let Some(items) = value["items"].as_array() {
+        return Ok(items.iter().filter_map(TourStep::from_annotation).collect());
+    }
+
+    let tour: TourJson = serde_json::from_value(value)?;
+
+    Ok(tour
+        .steps
+        .into_iter()
+        .map(|step| {
+            let (canvas_id, fragment_region) = split_canvas_fragment(&step.canvas);
+
+            TourStep {
+                presentation_url: step.manifest.or_else(|| tour.manifest.clone()),
+                canvas_id,
+                region: step
+                    .xywh
+                    .and_then(|x| parse_xywh(&format!("xywh={}", x.trim_start_matches("xywh="))))
+                    .or(fragment_region),
+                caption: step.caption,
+            }
+        })
+        .collect())
+}
+
+/// Camera transition between the views of the steps on the same canvas.
+struct CameraTransition {
+    from: Vec3,
+    from_scale: f32,
+    to: Vec3,
+    to_scale: f32,
+    start_time: Instant,
+}
+
+impl CameraTransition {
+    /// Get the camera translation and scale at the time, and whether the transition is done.
+    fn get_view(&self, now: Instant) -> (Vec3, f32, bool) {
+        let t = (now.duration_since(self.start_time).as_secs_f32()
+            / TRANSITION_DURATION.as_secs_f32())
+        .min(1.0);
+        // Ease in and out.
+        let eased = t * t * (3.0 - 2.0 * t);
+        // Zoom at a constant rate.
+        let scale =
+            (self.from_scale.ln() + (self.to_scale.ln() - self.from_scale.ln()) * eased).exp();
+
+        (self.from.lerp(self.to, eased), scale, t >= 1.0)
+    }
+}
+
+/// Action requested from the tour controls.
+pub(crate) enum TourRequest {
+    /// Load the tour from the URL or the file.
+    Load,
+    /// Go to the step at the index.
+    Go(usize),
+    /// Stop playing the tour.
+    Stop,
+}
+
+/// Tour playing back the steps in order.
+#[derive(Resource)]
+pub(crate) struct Tour {
+    pub(crate) steps: Vec<TourStep>,
+    /// Index of the current step. `None` if not playing.
+    pub(crate) current: Option<usize>,
+    /// URL, or path natively, of the tour being edited.
+    pub(crate) url: String,
+    /// Action requested from the controls, handled with the access to the camera and the image.
+    pub(crate) request: Option<TourRequest>,
+    download_state: Arc<Mutex<DownloadState<()>>>,
+    transition: Option<CameraTransition>,
+}
+
+impl Default for Tour {
+    fn default() -> Self {
+        Self {
+            steps: Vec::new(),
+            current: None,
+            url: "".to_string(),
+            request: None,
+            download_state: Arc::new(Mutex::new(DownloadState::None)),
+            transition: None,
+        }
+    }
+}
+
+impl Tour {
+    /// Get the current step.
+    pub(crate) fn get_current_step(&self) -> Option<&TourStep> {
+        self.steps.get(self.current?)
+    }
+
+    /// Whether the camera is moving to a step.
+    pub(crate) fn is_transitioning(&self) -> bool {
+        self.transition.is_some()
+    }
+}
+
+/// Load the tour, go to its steps, and animate the camera between the steps on the same canvas.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn tour_system(
+    mut commands: Commands,
+    mut tour: ResMut<Tour>,
+    mut app_state: ResMut<AppState>,
+    app_settings: Res<AppSettings>,
+    presentation: Option<Single<&Manifest>>,
+    tiled_image: Option<Single<&TiledImage>>,
+    camera: Single<(&Camera, &mut Transform, &mut Projection), With<MainCamera2d>>,
+    model_image_query: Query<Entity, With<ModelImage>>,
+    mut tile_mod_state: ResMut<TileModState>,
+    mut messages: MessageWriter<UserNotification>,
+    mut redraw_governor: ResMut<RedrawGovernor>,
+) -> Result {
+    let (camera, mut transform, mut projection) = camera.into_inner();
+    let Projection::Orthographic(orthographic) = projection.as_mut() else {
+        return Ok(());
+    };
+
+    // Handle the loading of the tour.
+    let download_state = Arc::clone(&tour.download_state);
+    let mut download_state_mutex = download_state
+        .lock()
+        .expect("should be able to lock the tour download state mutex");
+
+    match &(*download_state_mutex) {
+        DownloadState::Done { json, .. } => {
+            match try_from_json(json) {
+                Ok(steps) if !steps.is_empty() => {
+                    info!("Loaded the tour of {} steps from {}", steps.len(), tour.url);
+                    tour.steps = steps;
+                    tour.request = Some(TourRequest::Go(0));
+                }
+                Ok(_) => {
+                    messages.write(UserNotification::Error(format!(
+                        "No steps in the tour '{}'.",
+                        tour.url
+                    )));
+                }
+                Err(e) => {
+                    messages.write(UserNotification::from_iiif_error(&tour.url, json, &e));
+                }
+            }
+            *download_state_mutex = DownloadState::None;
+        }
+        DownloadState::InProgress { .. } => {
+            redraw_governor.report(RedrawActivity::Loading);
+        }
+        DownloadState::Error { url, msg } => {
+            messages.write(UserNotification::Network {
+                url: url.to_string(),
+                msg: msg.to_string(),
+            });
+            *download_state_mutex = DownloadState::None;
+        }
+        DownloadState::None => {}
+    }
+
+    drop(download_state_mutex);
+
+    match tour.request.take() {
+        Some(TourRequest::Load) => {
+            let url = tour.url.trim();
+            // Paths are read as local files natively.
+            let url = if url.contains("://") || cfg!(target_arch = "wasm32") {
+                url.to_string()
+            } else {
+                web::get_file_url(
+                    &std::path::absolute(url).unwrap_or_else(|_| std::path::PathBuf::from(url)),
+                )
+            };
+
+            tour.transition = None;
+            web::load(
+                &url,
+                &app_settings.proxy_prefix,
+                &app_state.http_cache,
+                &app_state.host_stats,
+                Arc::clone(&tour.download_state),
+                (),
+            );
+        }
+        Some(TourRequest::Go(index)) => {
+            let Some(step) = tour.steps.get(index).cloned() else {
+                return Ok(());
+            };
+
+            tour.current = Some(index);
+            tour.transition = None;
+
+            if let Some(presentation_url) = &step.presentation_url
+                && *presentation_url != app_state.presentation_url
+            {
+                app_state.target_canvas_id = Some(step.canvas_id.to_string());
+                app_state.target_region = step.region;
+                web::load_presentation(&mut app_state, &app_settings, presentation_url);
+                return Ok(());
+            }
+
+            let Some(presentation) = presentation else {
+                return Ok(());
+            };
+            let Some((sequence_index, canvas_index)) = web::get_canvas_index_by_id(
+                &presentation,
+                app_state.sequence_index,
+                &step.canvas_id,
+            ) else {
+                messages.write(UserNotification::Error(format!(
+                    "Canvas '{}' of the tour step is not in the manifest.",
+                    step.canvas_id
+                )));
+                return Ok(());
+            };
+
+            if sequence_index != app_state.sequence_index
+                || canvas_index != app_state.canvas_index
+                || tiled_image.is_none()
+            {
+                app_state.target_region = step.region;
+                web::load_canvas(
+                    &mut commands,
+                    &presentation,
+                    &mut app_state,
+                    &app_settings,
+                    sequence_index,
+                    canvas_index,
+                    0,
+                    &model_image_query,
+                )?;
+            } else if let Some(tiled_image) = &tiled_image
+                && let Some(viewport_size) = camera.logical_viewport_size()
+            {
+                // Animate to the fitted view on the same canvas.
+                let mut to_transform = *transform;
+                let mut to_orthographic = OrthographicProjection {
+                    scale: orthographic.scale,
+                    ..OrthographicProjection::default_2d()
+                };
+
+                fit_camera_to_rect(
+                    step.region.map_or_else(
+                        || tiled_image.get_world_max_size_rect(),
+                        |x| tiled_image.image_to_world_rect(x),
+                    ),
+                    viewport_size,
+                    &mut to_transform,
+                    &mut to_orthographic,
+                );
+
+                tour.transition = Some(CameraTransition {
+                    from: transform.translation,
+                    from_scale: orthographic.scale,
+                    to: to_transform.translation,
+                    to_scale: to_orthographic.scale,
+                    start_time: Instant::now(),
+                });
+            }
+        }
+        Some(TourRequest::Stop) => {
+            tour.current = None;
+            tour.transition = None;
+        }
+        None => {}
+    }
+
+    // Move the camera along the transition.
+    if let Some(transition) = &tour.transition
+        && let Some(tiled_image) = tiled_image
+    {
+        let (translation, scale, done) = transition.get_view(Instant::now());
+
+        transform.translation = translation;
+        orthographic.scale = scale;
+        app_state.level = tiled_image.get_level_at(scale, app_state.scale_factor);
+        tile_mod_state.invalidate();
+        redraw_governor.report(RedrawActivity::Animation);
+
+        if done {
+            tour.transition = None;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tour_json() {
+        let json = r#"{
+            "manifest": "https://example.org/m",
+            "steps": [
+                { "canvas": "https://example.org/c1", "xywh": "10,20,100,50", "caption": "Initial" },
+                { "canvas": "https://example.org/c2#xywh=0,0,30,40" },
+                { "manifest": "https://example.org/other", "canvas": "https://example.org/c3" }
+            ]
+        }"#;
+
+        assert_eq!(
+            try_from_json(json).expect("should parse the tour"),
+            vec![
+                TourStep {
+                    presentation_url: Some("https://example.org/m".to_string()),
+                    canvas_id: "https://example.org/c1".to_string(),
+                    region: Some(Rect::new(10.0, 20.0, 110.0, 70.0)),
+                    caption: "Initial".to_string(),
+                },
+                TourStep {
+                    presentation_url: Some("https://example.org/m".to_string()),
+                    canvas_id: "https://example.org/c2".to_string(),
+                    region: Some(Rect::new(0.0, 0.0, 30.0, 40.0)),
+                    caption: "".to_string(),
+                },
+                TourStep {
+                    presentation_url: Some("https://example.org/other".to_string()),
+                    canvas_id: "https://example.org/c3".to_string(),
+                    region: None,
+                    caption: "".to_string(),
+                },
+            ]
+        );
+        assert!(try_from_json(r#"{ "steps": [{ "caption": "No canvas" }] }"#).is_err());
+    }
+
+    #[test]
+    fn test_tour_annotation_page() {
+        let json = r#"{
+            "type": "AnnotationPage",
+            "items": [
+                {
+                    "type": "Annotation",
+                    "label": { "en": ["Initial"] },
+                    "body": { "type": "TextualBody", "value": "" },
+                    "target": {
+                        "type": "SpecificResource",
+                        "source": {
+                            "id": "https://example.org/c1",
+                            "partOf": [{ "id": "https://example.org/m", "type": "Manifest" }]
+                        },
+                        "selector": { "type": "FragmentSelector", "value": "xywh=10,20,100,50" }
+                    }
+                },
+                {
+                    "type": "Annotation",
+                    "body": { "type": "TextualBody", "value": "Whole page" },
+                    "target": "https://example.org/c2"
+                }
+            ]
+        }"#;
+
+        assert_eq!(
+            try_from_json(json).expect("should parse the annotation page"),
+            vec![
+                TourStep {
+                    presentation_url: Some("https://example.org/m".to_string()),
+                    canvas_id: "https://example.org/c1".to_string(),
+                    region: Some(Rect::new(10.0, 20.0, 110.0, 70.0)),
+                    caption: "Initial".to_string(),
+                },
+                TourStep {
+                    presentation_url: None,
+                    canvas_id: "https://example.org/c2".to_string(),
+                    region: None,
+                    caption: "Whole page".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_camera_transition() {
+        let start_time = Instant::now();
+        let transition = CameraTransition {
+            from: Vec3::ZERO,
+            from_scale: 1.0,
+            to: Vec3::new(100.0, 50.0, 0.0),
+            to_scale: 0.25,
+            start_time,
+        };
+
+        let (translation, scale, done) = transition.get_view(start_time + TRANSITION_DURATION / 2);
+        assert!((translation - Vec3::new(50.0, 25.0, 0.0)).length() < 1e-3);
+        assert!((scale - 0.5).abs() < 1e-3);
+        assert!(!done);
+
+        let (translation, scale, done) = transition.get_view(start_time + TRANSITION_DURATION);
+        assert_eq!(translation, Vec3::new(100.0, 50.0, 0.0));
+        assert!((scale - 0.25).abs() < 1e-5);
+        assert!(done);
+    }
+}
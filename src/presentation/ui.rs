@@ -1,27 +1,268 @@
-use crate::UserNotification;
-use crate::app::app_settings::AppSettings;
-use crate::app::app_state::AppState;
+use crate::OpenWindow;
+use crate::app::app_settings::{AppSettings, FitMode, ThemeMode};
+use crate::app::app_state::{AppState, DownloadState};
+use crate::app::session::SessionState;
+use crate::camera::main_camera::{CameraCommand, CameraCommand3d, MainCamera2d};
+use crate::camera::pan_orbit_state_3d::ViewPreset;
+use crate::capability_probe::{CapabilityProbe, ProbeResult};
+#[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+use crate::clipboard_watch::ClipboardWatch;
+#[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+use crate::contact_sheet::{
+    COLUMNS_RANGE, ContactSheetExport, ContactSheetRequest, THUMBNAIL_SIZE_RANGE,
+};
+#[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+use crate::export::OfflineExport;
+use crate::host_stats::HostStats;
+use crate::load_trace::{LoadTrace, TraceRequest};
+use crate::notification::UserNotification;
+use crate::presentation::actions::{ACTIONS, CommandPalette, ViewerAction, is_typed_shortcut};
+use crate::presentation::annotations::AnnotationAuthoring;
+use crate::presentation::bookmarks::{BookmarkRequest, Bookmarks, parse_xywh};
+use crate::presentation::collection::{Collection, CollectionSearch};
+use crate::presentation::console::Console;
+use crate::presentation::content_state::{ContentStateShare, ShareRequest};
+use crate::presentation::error_page::{ManifestErrorPage, SAMPLE_MANIFESTS};
+use crate::presentation::html;
 use crate::presentation::manifest::Manifest;
-use crate::rendering::model_image::ModelImage;
+use crate::presentation::reload::ManifestReload;
+use crate::presentation::rights::RightsStatement;
+use crate::presentation::spreads;
+use crate::presentation::timeline::{TimedAnnotation, Timeline, format_position};
+use crate::presentation::tour::{Tour, TourRequest};
+use crate::redraw_governor::{RedrawActivity, RedrawGovernor};
+use crate::rendering::canvas_background;
+use crate::rendering::comparison::{ComparisonCamera, ComparisonMode};
+use crate::rendering::eyedropper::{Eyedropper, PixelSample};
+use crate::rendering::grid;
+use crate::rendering::magnifier::{self, Magnifier};
+use crate::rendering::model_animation::ModelAnimations;
+use crate::rendering::model_image::{ModelImage, ModelInspection};
+use crate::rendering::overlay_image::OverlayImage;
+use crate::rendering::tile::{TileLoading, TileModState};
+use crate::rendering::tiled_image::TiledImage;
+#[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+use crate::stitch::{StitchExport, StitchRequest};
+use crate::view_clipboard::{ViewClipboard, ViewClipboardCamera};
+use crate::watchdog::{DownloadWatchdog, StalledDownload, WatchdogRequest};
+use bevy::animation::AnimationPlayer;
+use bevy::asset::LoadState;
 use bevy::camera::Viewport;
+use bevy::pbr::wireframe::WireframeConfig;
 use bevy::prelude::{
-    Camera, Commands, Entity, MessageReader, MessageWriter, Query, Res, ResMut, Resource, Result,
-    Single, UVec2, Window, With, Without, default,
+    AssetServer, Camera, Commands, Entity, GlobalTransform, Local, MessageReader, MessageWriter,
+    Query, Rect, Res, ResMut, Resource, Result, Single, UVec2, Vec2, Window, With, Without,
+    default, info,
 };
 use bevy::window::{PrimaryWindow, RequestRedraw};
 use bevy_egui::egui::epaint::text::{FontInsert, FontPriority, InsertFontFamily};
 use bevy_egui::egui::text::LayoutJob;
 use bevy_egui::egui::{Button, Color32, FontData, FontFamily, FontId, Sense, Widget, vec2};
-use bevy_egui::{EguiContext, EguiContexts, egui};
+use bevy_egui::{EguiContext, EguiContexts, EguiTextureHandle, egui};
+use std::collections::VecDeque;
+use std::sync::Mutex;
 use std::time::Duration;
 
+/// Width of the image choice selector in the top panel.
+const CHOICE_SELECTOR_WIDTH: f32 = 120.0;
+
+/// Width of the badge of the capabilities of the image service in the top panel.
+const CAPABILITY_BADGE_WIDTH: f32 = 150.0;
+
+/// Delay between the tile requests to a host when the polite mode is turned on.
+const DEFAULT_POLITE_DELAY_MS: u32 = 250;
+
+/// Max delay between the tile requests to a host in the polite mode.
+const MAX_POLITE_DELAY_MS: u32 = 5000;
+
+/// Width of the draggable area of the curtain divider.
+const CURTAIN_HANDLE_WIDTH: f32 = 12.0;
+
+/// Default width of the collection window.
+const COLLECTION_WINDOW_WIDTH: f32 = 280.0;
+
+/// Size of the thumbnails of the members in the collection window.
+const COLLECTION_THUMBNAIL_SIZE: f32 = 48.0;
+
+/// Max size of the thumbnail of the manifest shown while the first canvas is loading.
+const LOADING_THUMBNAIL_SIZE: f32 = 256.0;
+
+/// Height of the progress bar under the top panel.
+const PROGRESS_BAR_HEIGHT: f32 = 3.0;
+
+/// Width of the moving segment of the progress bar as a fraction of the bar.
+const PROGRESS_SEGMENT_FRACTION: f32 = 0.3;
+
+/// Time for the segment of the progress bar to move across the bar.
+const PROGRESS_CYCLE_SECS: f64 = 1.5;
+
+/// Maximum pixels of the overlap around the tiles in the settings.
+const MAX_TILE_OVERLAP: u32 = 8;
+
+/// Maximum number of the recent notifications kept for the notifications window.
+const MAX_NOTIFICATIONS: usize = 20;
+
+/// Max width of the window in points for the compact layout, e.g. on a phone.
+const COMPACT_LAYOUT_MAX_WIDTH: f32 = 640.0;
+
+/// Height of the bottom sheet of the contents in the compact layout, as a fraction of the window.
+const BOTTOM_SHEET_FRACTION: f32 = 0.4;
+
+/// Accent color chosen first when the accent color is customized, as the selection of the dark
+/// theme.
+const DEFAULT_ACCENT_COLOR: [u8; 3] = [0, 92, 128];
+
+/// Colour of the outline flashed on the region of interest picked by the user.
+const HIGHLIGHT_COLOR: Color32 = Color32::from_rgb(255, 80, 200);
+
+/// Duration in seconds of the outline flashed on the region of interest.
+const HIGHLIGHT_FLASH_SECS: f64 = 2.0;
+
+/// Colour of the rectangles drawn by the user.
+const ANNOTATION_COLOR: Color32 = Color32::from_rgb(255, 200, 0);
+
+/// Colour of the rubber band to zoom to.
+const ZOOM_SELECTION_COLOR: Color32 = Color32::from_rgb(0, 160, 255);
+
+/// Colour of the mark of the canvases which cannot be shown.
+const BROKEN_CANVAS_COLOR: Color32 = Color32::from_rgb(255, 100, 100);
+
+/// Min size in points of the rubber band to zoom to, so that a click does not zoom.
+const MIN_ZOOM_SELECTION_SIZE: f32 = 4.0;
+
+/// Width in points of the rulers along the top and the left of the view.
+const RULER_WIDTH: f32 = 18.0;
+
+/// Min distance in points between the labelled ticks of the rulers.
+const RULER_TICK_SPACING: f32 = 80.0;
+
+/// Number of the segments of the circle of the magnifier lens.
+const LENS_SEGMENTS: usize = 64;
+
+/// Width of the command palette.
+const COMMAND_PALETTE_WIDTH: f32 = 360.0;
+
+/// Size in points of the badge of the rights statement.
+const RIGHTS_BADGE_SIZE: f32 = 20.0;
+
+/// Width in pixels of the image of the accompanying canvas requested from the image service.
+const ACCOMPANYING_CANVAS_WIDTH: u32 = 400;
+
+/// Number of rows of the description shown until it is expanded.
+const DESCRIPTION_ROWS: usize = 3;
+
+/// Max height of the expanded description, scrolled beyond it.
+const DESCRIPTION_MAX_HEIGHT: f32 = 300.0;
+
+/// Max width of the contents of the error page of the manifest which cannot be shown.
+const ERROR_PAGE_WIDTH: f32 = 420.0;
+
+/// Layout of the panels by the width of the window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum LayoutMode {
+    /// The contents on the left of the viewport and the address bar in the top bar.
+    #[default]
+    Wide,
+    /// The contents in a bottom sheet, the address bar behind a button and the larger touch
+    /// targets, e.g. on a phone.
+    Compact,
+}
+
+impl LayoutMode {
+    /// Get the layout for the width of the window in points.
+    fn from_width(width: f32) -> Self {
+        if width <= COMPACT_LAYOUT_MAX_WIDTH {
+            LayoutMode::Compact
+        } else {
+            LayoutMode::Wide
+        }
+    }
+
+    /// Set the spacing of the widgets for the layout, larger to touch in the compact layout.
+    fn apply_spacing(self, spacing: &mut egui::style::Spacing) {
+        let default_spacing = egui::style::Spacing::default();
+
+        match self {
+            LayoutMode::Wide => {
+                spacing.item_spacing = default_spacing.item_spacing;
+                spacing.button_padding = default_spacing.button_padding;
+                spacing.interact_size = default_spacing.interact_size;
+            }
+            LayoutMode::Compact => {
+                spacing.item_spacing = vec2(8.0, 6.0);
+                spacing.button_padding = vec2(8.0, 6.0);
+                spacing.interact_size = vec2(44.0, 32.0);
+            }
+        }
+    }
+}
+
 #[derive(Resource)]
 pub(crate) struct EguiUiState {
-    pub(crate) current_sequence: usize,
     pub(crate) presentation_url: String,
     pub(crate) toasts: egui_notify::Toasts,
     pub(crate) open_left_panel: bool,
+    /// Layout of the panels by the width of the window.
+    pub(crate) layout_mode: LayoutMode,
+    /// Whether the address bar is shown under the top bar in the compact layout.
+    pub(crate) open_address_bar: bool,
+    /// Whether the address bar is to be focused, e.g. by the action to open a manifest.
+    pub(crate) focus_address_bar: bool,
     pub(crate) canvas_index: String,
+    /// Canvases with the labels matching the text in the page field, e.g. "12r" for "f. 12r".
+    pub(crate) canvas_label_matches: Vec<(usize, String)>,
+    pub(crate) follow_new_window: bool,
+    /// Recent notifications, the latest first.
+    pub(crate) notifications: VecDeque<UserNotification>,
+    pub(crate) open_notifications: bool,
+    pub(crate) open_settings: bool,
+    /// CORS proxy prefix being edited in the settings window.
+    pub(crate) proxy_prefix: String,
+    /// Language preferences being edited in the settings window.
+    pub(crate) languages: String,
+    pub(crate) open_bookmarks: bool,
+    pub(crate) open_annotations: bool,
+    pub(crate) open_tour: bool,
+    pub(crate) open_highlights: bool,
+    pub(crate) open_share: bool,
+    pub(crate) open_diagnostics: bool,
+    pub(crate) open_print_export: bool,
+    pub(crate) open_contact_sheet: bool,
+    pub(crate) open_console: bool,
+    /// Manifest whose description is expanded by "Read more", so that it is collapsed again for
+    /// the next one.
+    pub(crate) expanded_description: Option<String>,
+    /// Whether the pointer is over the next page button, to prefetch the next canvas.
+    pub(crate) hovering_next_page: bool,
+    /// Text announced by the screen readers, e.g. of the page changes and the load errors.
+    pub(crate) announcement: String,
+}
+
+impl EguiUiState {
+    /// Show the notification as a toast with the severity of its kind, and keep it for the details.
+    fn notify(&mut self, notification: &UserNotification) {
+        let text = match notification.hint() {
+            Some(hint) => format!("{}\n{}", notification.summary(), hint),
+            None => notification.summary(),
+        };
+
+        let toast = match notification {
+            UserNotification::Network { .. } => self.toasts.error(text),
+            UserNotification::Parse { .. } | UserNotification::Error(_) => {
+                self.toasts.warning(text)
+            }
+            UserNotification::Unsupported { .. } | UserNotification::Info(_) => {
+                self.toasts.info(text)
+            }
+        };
+        toast
+            .show_progress_bar(true)
+            .duration(Duration::from_secs(5));
+
+        self.notifications.push_front(notification.clone());
+        self.notifications.truncate(MAX_NOTIFICATIONS);
+        self.announcement = notification.summary();
+    }
 }
 
 /// Set up egui.
@@ -32,11 +273,32 @@ pub(crate) fn setup(mut contexts: EguiContexts, mut commands: Commands) -> Resul
     egui_extras::install_image_loaders(ctx);
 
     commands.insert_resource(EguiUiState {
-        current_sequence: 0,
         presentation_url: "".to_string(),
         toasts: egui_notify::Toasts::default(),
         open_left_panel: false,
+        layout_mode: LayoutMode::default(),
+        open_address_bar: false,
+        focus_address_bar: false,
         canvas_index: "".to_string(),
+        canvas_label_matches: Vec::new(),
+        follow_new_window: true,
+        notifications: VecDeque::new(),
+        open_notifications: false,
+        open_settings: false,
+        proxy_prefix: "".to_string(),
+        languages: "".to_string(),
+        open_bookmarks: false,
+        open_annotations: false,
+        open_tour: false,
+        open_highlights: false,
+        open_share: false,
+        open_diagnostics: false,
+        open_print_export: false,
+        open_contact_sheet: false,
+        open_console: false,
+        expanded_description: None,
+        hovering_next_page: false,
+        announcement: "".to_string(),
     });
 
     // Add a CJK font.
@@ -80,67 +342,188 @@ pub(crate) fn setup(mut contexts: EguiContexts, mut commands: Commands) -> Resul
 #[allow(clippy::too_many_arguments)]
 pub(crate) fn presentation_ui_system(
     mut contexts: EguiContexts,
-    mut camera_query: Query<&mut Camera, Without<EguiContext>>,
+    mut camera_query: Query<
+        &mut Camera,
+        (
+            Without<EguiContext>,
+            Without<ComparisonCamera>,
+            Without<ViewClipboardCamera>,
+        ),
+    >,
     window: Single<&mut Window, With<PrimaryWindow>>,
     mut egui_ui_state: ResMut<EguiUiState>,
-    app_settings: Res<AppSettings>,
+    mut app_settings: ResMut<AppSettings>,
     mut app_state: ResMut<AppState>,
     presentation_query: Query<(Entity, &Manifest)>,
     mut redraw_request_writer: MessageWriter<RequestRedraw>,
     mut messages: MessageReader<UserNotification>,
     mut commands: Commands,
     model_image_query: Query<Entity, With<ModelImage>>,
+    overlay_image_query: Query<Entity, With<OverlayImage>>,
+    mut tile_mod_state: ResMut<TileModState>,
+    mut open_window_writer: MessageWriter<OpenWindow>,
+    mut bookmarks: ResMut<Bookmarks>,
+    capability_probe: Res<CapabilityProbe>,
 ) -> Result {
     let ctx = contexts.ctx_mut()?;
 
     // Display user notifications.
-    for UserNotification(msg) in messages.read() {
-        egui_ui_state
-            .toasts
-            .info(msg)
-            .show_progress_bar(true)
-            .duration(Duration::from_secs(5));
+    for notification in messages.read() {
+        egui_ui_state.notify(notification);
+    }
+
+    // Switch the layout at the breakpoint of the window width, e.g. on a phone.
+    let layout_mode = LayoutMode::from_width(ctx.content_rect().width());
+
+    if layout_mode != egui_ui_state.layout_mode {
+        egui_ui_state.layout_mode = layout_mode;
+        ctx.all_styles_mut(|style| layout_mode.apply_spacing(&mut style.spacing));
     }
 
     let mut top = egui::Panel::top("top_panel")
         .resizable(false)
         .show(ctx, |ui| {
             ui.horizontal(|ui| {
-                if Button::new("☰")
-                    .fill(Color32::from_black_alpha(0))
-                    .ui(ui)
-                    .clicked()
-                {
+                if add_icon_button(ui, "☰", "Contents").clicked() {
                     egui_ui_state.open_left_panel = !egui_ui_state.open_left_panel;
                     redraw_request_writer.write(RequestRedraw);
                 }
 
+                if !egui_ui_state.notifications.is_empty()
+                    && add_icon_button(ui, "⚠", "Notifications").clicked()
+                {
+                    egui_ui_state.open_notifications = !egui_ui_state.open_notifications;
+                }
+
+                if add_icon_button(ui, "⚙", "Settings").clicked() {
+                    egui_ui_state.open_settings = !egui_ui_state.open_settings;
+                    egui_ui_state.proxy_prefix = app_settings.proxy_prefix.to_string();
+                    egui_ui_state.languages = app_settings.language.to_string();
+                }
+
+                // Windows toggled from the top bar, in a menu in the compact layout.
+                let state = &mut *egui_ui_state;
+                let mut window_toggles = vec![
+                    ("🔖", "Bookmarks", &mut state.open_bookmarks),
+                    ("✏", "Annotations", &mut state.open_annotations),
+                    ("▶", "Tour", &mut state.open_tour),
+                    ("◎", "Regions of interest", &mut state.open_highlights),
+                    ("🔗", "Share", &mut state.open_share),
+                    ("⏱", "Diagnostics", &mut state.open_diagnostics),
+                    ("⌨", "Console", &mut state.open_console),
+                ];
+
+                // Stitching the tiles for printing is only supported natively on desktop.
+                if cfg!(not(any(target_arch = "wasm32", target_os = "android"))) {
+                    window_toggles.push(("🖨", "Export for printing", &mut state.open_print_export));
+                    window_toggles.push(("▦", "Contact sheet", &mut state.open_contact_sheet));
+                }
+
+                let mut toggled = false;
+
+                if state.layout_mode == LayoutMode::Compact {
+                    ui.menu_button("⋯", |ui| {
+                        for (icon, name, open) in window_toggles {
+                            if ui.button(format!("{} {}", icon, name)).clicked() {
+                                *open = !*open;
+                                toggled = true;
+                                ui.close();
+                            }
+                        }
+                    });
+                } else {
+                    for (icon, name, open) in window_toggles {
+                        if add_icon_button(ui, icon, name).clicked() {
+                            *open = !*open;
+                            toggled = true;
+                        }
+                    }
+                }
+
+                if toggled {
+                    redraw_request_writer.write(RequestRedraw);
+                }
+
                 let num_canvases = presentation_query
                     .iter()
                     .next()
                     .and_then(|(_, manifest)| {
                         (*manifest)
                             .model()
-                            .get_sequence(0)
+                            .get_sequence(app_state.sequence_index)
                             .ok()
                             .map(|x| x.get_canvases())
                     })
                     .map(|x| x.len())
                     .unwrap_or_default();
 
-                // Add address bar.
-                add_address_bar(
-                    ui,
-                    &mut egui_ui_state,
-                    &mut app_state,
-                    ui.available_width() - if num_canvases > 1 { 85.0 } else { 0.0 },
-                );
+                let choice_labels = presentation_query
+                    .iter()
+                    .next()
+                    .map(|(_, manifest)| {
+                        get_image_choice_labels(
+                            manifest,
+                            app_state.sequence_index,
+                            app_state.canvas_index,
+                            &app_settings.language,
+                        )
+                    })
+                    .unwrap_or_default();
+
+                // Add address bar, behind the search button in the compact layout.
+                if egui_ui_state.layout_mode == LayoutMode::Compact {
+                    let response = ui
+                        .selectable_label(egui_ui_state.open_address_bar, "🔍")
+                        .on_hover_text("Address bar");
+
+                    set_accessible_name(ui, &response, "Address bar");
+
+                    if response.clicked() {
+                        egui_ui_state.open_address_bar = !egui_ui_state.open_address_bar;
+                    }
+                } else {
+                    add_address_bar(
+                        ui,
+                        &mut egui_ui_state,
+                        &mut app_state,
+                        &app_settings,
+                        ui.available_width()
+                            - if num_canvases > 1 { 85.0 } else { 0.0 }
+                            - if choice_labels.len() > 1 {
+                                CHOICE_SELECTOR_WIDTH + 10.0
+                            } else {
+                                0.0
+                            }
+                            - if capability_probe.capabilities.is_some() {
+                                CAPABILITY_BADGE_WIDTH + 10.0
+                            } else {
+                                0.0
+                            },
+                    );
+
+                    add_capability_badge(ui, &capability_probe);
+                }
+
+                if choice_labels.len() > 1 {
+                    // Add image choice selector.
+                    add_choice_selector(
+                        &mut egui_ui_state,
+                        &mut app_state,
+                        &app_settings,
+                        presentation_query,
+                        ui,
+                        &choice_labels,
+                        &mut commands,
+                        &model_image_query,
+                    );
+                }
 
                 if num_canvases > 1 {
                     // Add page controls.
                     add_page_controls(
                         &mut egui_ui_state,
                         &mut app_state,
+                        &app_settings,
                         presentation_query,
                         ui,
                         num_canvases,
@@ -150,6 +533,25 @@ pub(crate) fn presentation_ui_system(
                 }
             });
 
+            if egui_ui_state.layout_mode == LayoutMode::Compact && egui_ui_state.open_address_bar {
+                ui.horizontal(|ui| {
+                    add_address_bar(
+                        ui,
+                        &mut egui_ui_state,
+                        &mut app_state,
+                        &app_settings,
+                        ui.available_width()
+                            - if capability_probe.capabilities.is_some() {
+                                CAPABILITY_BADGE_WIDTH + 10.0
+                            } else {
+                                0.0
+                            },
+                    );
+
+                    add_capability_badge(ui, &capability_probe);
+                });
+            }
+
             ui.add_space(1.0);
 
             // ui.allocate_rect(ui.available_rect_before_wrap(), egui::Sense::hover());
@@ -159,132 +561,219 @@ pub(crate) fn presentation_ui_system(
         .height(); // width is ignored, as the panel has a width of 100% of the screen
     // let mut top = 0.0;
 
-    let mut left = if egui_ui_state.open_left_panel {
-        egui::Panel::left("left_panel")
-            .resizable(true)
-            .show(ctx, |ui| -> Result {
-                // No need to build panel if no presentation.
-                let Some((_, presentation)) = presentation_query.iter().next() else {
-                    return Ok(());
-                };
+    // The contents of the manifest, on the left or in the bottom sheet in the compact layout.
+    let (open_left_panel, layout_mode) = (egui_ui_state.open_left_panel, egui_ui_state.layout_mode);
+    let add_contents = |ui: &mut egui::Ui| -> Result {
+        // No need to build panel if no presentation.
+        let Some((_, presentation)) = presentation_query.iter().next() else {
+            return Ok(());
+        };
 
-                // Manifest title.
-                add_text(
-                    ui,
-                    &presentation.model().get_title(&app_settings.language),
-                    Some(Color32::WHITE),
-                    2,
-                );
+        // Breadcrumb of the parent collections to navigate up to the siblings.
+        let mut parent_url = None;
+
+        for parent in presentation.model().get_parent_collections() {
+            let id = parent.get_id();
+            let label = parent
+                .get_label(&app_settings.language)
+                .collect::<Vec<_>>()
+                .join(",");
+            let text: &str = if label.is_empty() { &id } else { &label };
 
-                // Manifest description.
-                let description = presentation
-                    .model()
-                    .get_description(&app_settings.language)
-                    .collect::<Vec<_>>();
-                if !description.is_empty() {
-                    add_text(ui, &description.join("\n"), None, 3);
+            ui.horizontal_wrapped(|ui| {
+                ui.label("⬆");
+                if ui.link(text).on_hover_text(id.as_ref()).clicked() {
+                    parent_url = Some(id.to_string());
                 }
+            });
+        }
 
-                // Manifest attribution and licence.
-                let licence = presentation.model().get_license().collect::<Vec<_>>();
-                let license = if !licence.is_empty() {
-                    format!("(© {})", &licence.join(","))
-                } else {
-                    "".into()
-                };
-                let attribution = presentation
-                    .model()
-                    .get_attribution(&app_settings.language)
-                    .collect::<Vec<_>>()
-                    .join(",");
+        if let Some(parent_url) = parent_url {
+            crate::web::load_presentation(&mut app_state, &app_settings, &parent_url);
+        }
 
-                if !license.is_empty() || !attribution.is_empty() {
-                    add_text(ui, &format!("{} {}", attribution, license), None, 3);
-                }
+        // Manifest title.
+        let title_color = ui.visuals().strong_text_color();
 
-                let required_statements = presentation
-                    .model()
-                    .get_required_statements(&app_settings.language)
-                    .collect::<Vec<_>>()
-                    .join(". ");
+        add_text(
+            ui,
+            &presentation.model().get_title(&app_settings.language),
+            Some(title_color),
+            2,
+        );
 
-                if !required_statements.is_empty() {
-                    add_text(ui, &required_statements, None, 3);
-                }
+        // Manifest description.
+        let description = presentation
+            .model()
+            .get_description(&app_settings.language)
+            .collect::<Vec<_>>();
+        if !description.is_empty() {
+            let presentation_url = &app_state.presentation_url;
+            let mut expanded =
+                egui_ui_state.expanded_description.as_ref() == Some(presentation_url);
 
-                // Manifest provider logo.
-                for logo in presentation.model().get_logo() {
-                    ui.add_space(6.0);
-                    bevy_egui::egui::Image::new(logo).max_height(64.0).ui(ui);
-                }
-                ui.add_space(6.0);
+            add_description(ui, &description.join("\n"), &mut expanded);
+            egui_ui_state.expanded_description = expanded.then(|| presentation_url.to_string());
+        }
 
-                // Manifest sequence.
-                egui::ComboBox::from_id_salt("Sequences")
-                    .selected_text(
-                        presentation
-                            .model()
-                            .get_sequence(egui_ui_state.current_sequence)?
-                            .get_label(&app_settings.language)
-                            .collect::<Vec<_>>()
-                            .join(","),
-                    )
-                    .wrap_mode(egui::TextWrapMode::Wrap)
-                    .show_ui(ui, |ui| {
-                        for (index, seq) in presentation.model().get_sequences().enumerate() {
-                            ui.selectable_value(
-                                &mut egui_ui_state.current_sequence,
-                                index,
-                                seq.get_label(&app_settings.language)
-                                    .collect::<Vec<_>>()
-                                    .join(","),
-                            );
-                        }
-                    });
+        // Manifest attribution and licence.
+        let attribution = presentation
+            .model()
+            .get_attribution(&app_settings.language)
+            .collect::<Vec<_>>()
+            .join(",");
 
-                ui.separator();
-
-                // Canvas thumbnails.
-                add_canvas_thumbnails(
-                    ui,
-                    &mut egui_ui_state,
-                    app_settings,
-                    &mut app_state,
-                    presentation,
-                    &mut commands,
-                    &model_image_query,
-                )?;
-
-                // ui.allocate_rect(ui.available_rect_before_wrap(), egui::Sense::hover());
-                Ok(())
-            })
-            .response
-            .rect
-            .width() // height is ignored, as the panel has a height of 100% of the screen
+        if !attribution.is_empty() {
+            add_text(ui, &attribution, None, 3);
+        }
+
+        for licence in presentation.model().get_license() {
+            add_rights_statement(ui, &licence);
+        }
+
+        let required_statements = presentation
+            .model()
+            .get_required_statements(&app_settings.language)
+            .collect::<Vec<_>>()
+            .join(". ");
+
+        if !required_statements.is_empty() {
+            add_text(ui, &required_statements, None, 3);
+        }
+
+        // Links to the external resources, e.g. the catalogue record.
+        let external_links = presentation
+            .model()
+            .get_external_links(&app_settings.language);
+
+        if !external_links.is_empty() {
+            egui::CollapsingHeader::new("About this object")
+                .default_open(true)
+                .show(ui, |ui| {
+                    for link in &external_links {
+                        let text = format!(
+                            "{}: {}",
+                            link.kind.label(),
+                            if link.label.is_empty() {
+                                &link.url
+                            } else {
+                                &link.label
+                            }
+                        );
+
+                        ui.add(
+                            egui::Hyperlink::from_label_and_url(text, &link.url)
+                                .open_in_new_tab(true),
+                        )
+                        .on_hover_text(&link.url);
+                    }
+                });
+        }
+
+        // Other windows are only supported natively on desktop.
+        if cfg!(not(any(target_arch = "wasm32", target_os = "android"))) {
+            add_new_window_controls(ui, &mut egui_ui_state, &mut open_window_writer);
+        }
+
+        // Manifest provider logo.
+        for logo in presentation.model().get_logo() {
+            ui.add_space(6.0);
+            bevy_egui::egui::Image::new(logo).max_height(64.0).ui(ui);
+        }
+        ui.add_space(6.0);
+
+        add_accompanying_canvas(ui, presentation, &app_state, &app_settings.language);
+
+        // Overlay of another image choice.
+        let choice_labels = get_image_choice_labels(
+            presentation,
+            app_state.sequence_index,
+            app_state.canvas_index,
+            &app_settings.language,
+        );
+        if choice_labels.len() > 1 {
+            add_overlay_controls(
+                ui,
+                &mut egui_ui_state,
+                &mut app_state,
+                &app_settings,
+                presentation,
+                &choice_labels,
+                &mut commands,
+                &overlay_image_query,
+                &mut tile_mod_state,
+            );
+            ui.add_space(6.0);
+        }
+
+        // Manifest sequence, if there are alternate orders of the canvases.
+        if presentation.model().get_sequences().len() > 1 {
+            add_sequence_selector(
+                ui,
+                &mut egui_ui_state,
+                &app_settings,
+                &mut app_state,
+                presentation,
+                &mut commands,
+                &model_image_query,
+            );
+        }
+
+        ui.separator();
+
+        // Canvas thumbnails.
+        add_canvas_thumbnails(
+            ui,
+            &mut egui_ui_state,
+            &app_settings,
+            &mut app_state,
+            presentation,
+            &mut commands,
+            &model_image_query,
+        )?;
+
+        // ui.allocate_rect(ui.available_rect_before_wrap(), egui::Sense::hover());
+        Ok(())
+    };
+
+    let (mut left, mut bottom) = match (open_left_panel, layout_mode) {
+        (false, _) => (0.0, 0.0),
+        (true, LayoutMode::Wide) => (
+            egui::Panel::left("left_panel")
+                .resizable(true)
+                .show(ctx, add_contents)
+                .response
+                .rect
+                .width(), // height is ignored, as the panel has a height of 100% of the screen
+            0.0,
+        ),
+        (true, LayoutMode::Compact) => (
+            0.0,
+            egui::Panel::bottom("bottom_sheet")
+                .resizable(true)
+                .default_size(ctx.content_rect().height() * BOTTOM_SHEET_FRACTION)
+                .show(ctx, add_contents)
+                .response
+                .rect
+                .height(), // width is ignored, as the panel has a width of 100% of the screen
+        ),
+    };
+
+    let mut right = if egui_ui_state.open_bookmarks {
+        add_bookmarks_panel(ctx, &mut bookmarks, &app_settings)
     } else {
         0.0
     };
-    // let mut right = egui::SidePanel::right("right_panel")
-    //     .resizable(true)
-    //     .show(ctx, |ui| {
-    //         ui.label("Right resizeable panel");
-    //         ui.allocate_rect(ui.available_rect_before_wrap(), egui::Sense::hover());
-    //     })
-    //     .response
-    //     .rect
-    //     .width(); // height is ignored, as the panel has a height of 100% of the screen
-    let mut right = 0.0;
-
-    // let mut bottom = egui::TopBottomPanel::bottom("bottom_panel")
-    //     .resizable(true)
-    //     .show(ctx, |ui| {
-    //         ui.label("Bottom resizeable panel");
-    //         ui.allocate_rect(ui.available_rect_before_wrap(), egui::Sense::hover());
-    //     })
-    //     .response
-    //     .rect
-    //     .height(); // width is ignored, as the panel has a width of 100% of the screen
-    let mut bottom = 0.0;
+
+    // Curtain divider over the viewport.
+    if app_state.overlay_choice_index.is_some() {
+        add_curtain_divider(
+            ctx,
+            &mut app_state,
+            ctx.available_rect(),
+            &mut tile_mod_state,
+        );
+    }
 
     // Scale from logical units to physical units.
     left *= window.scale_factor();
@@ -332,184 +821,3620 @@ pub(crate) fn presentation_ui_system(
         });
     }
 
+    add_notifications_window(ctx, &mut egui_ui_state);
+    add_settings_window(
+        ctx,
+        &mut egui_ui_state,
+        &mut app_settings,
+        &mut app_state,
+        &mut tile_mod_state,
+    );
+    add_announcement(ctx, &egui_ui_state.announcement);
+
     egui_ui_state.toasts.show(ctx);
 
     Ok(())
 }
 
-/// Add controls to change pages.
-#[allow(clippy::too_many_arguments)]
-fn add_page_controls(
-    egui_ui_state: &mut ResMut<'_, EguiUiState>,
+/// Add the settings window.
+fn add_settings_window(
+    ctx: &egui::Context,
+    egui_ui_state: &mut EguiUiState,
+    app_settings: &mut ResMut<'_, AppSettings>,
     app_state: &mut ResMut<'_, AppState>,
-    presentation_query: Query<'_, '_, (Entity, &Manifest)>,
-    ui: &mut egui::Ui,
-    num_canvases: usize,
-    commands: &mut Commands,
-    model_image_query: &Query<Entity, With<ModelImage>>,
+    tile_mod_state: &mut ResMut<'_, TileModState>,
 ) {
-    ui.spacing_mut().item_spacing.x = 1.0;
+    let mut open = egui_ui_state.open_settings;
+    let mut apply = false;
 
-    let mut new_canvas_index = app_state.canvas_index;
-
-    if ui.button("<").clicked() {
-        new_canvas_index = app_state.canvas_index.saturating_sub(1);
-    }
+    egui::Window::new("Settings")
+        .id(egui::Id::new("settings_window"))
+        .open(&mut open)
+        .default_width(COLLECTION_WINDOW_WIDTH)
+        .show(ctx, |ui| {
+            ui.label("CORS proxy prefix");
+            ui.add(
+                egui::TextEdit::singleline(&mut egui_ui_state.proxy_prefix)
+                    .hint_text("https://proxy.example.org/?url="),
+            )
+            .on_hover_text(
+                "The manifest, image info and tile URLs are appended to the prefix, \
+                percent-encoded if the prefix ends with '='. Leave it empty for no proxy.",
+            );
 
-    let egui_index = egui_ui_state.canvas_index.clone();
-    let response = ui
-        .add(egui::TextEdit::singleline(&mut egui_ui_state.canvas_index).desired_width(30.0))
-        .on_hover_text(format!(
-            "Page {}/{}",
-            app_state.canvas_index.saturating_add(1),
-            num_canvases
-        ));
+            ui.label("Languages");
+            ui.add(
+                egui::TextEdit::singleline(&mut egui_ui_state.languages).hint_text("en-GB, en, fr"),
+            )
+            .on_hover_text(
+                "Preferred languages of the labels in order, separated by commas. \
+                A language also matches its regional or script variants, e.g. 'zh' matches 'zh-Hant'.",
+            );
 
-    if response.changed() && !egui_ui_state.canvas_index.is_empty() {
-        if let Ok(index) = egui_ui_state.canvas_index.parse::<usize>()
-            && index > 0
-            && index <= num_canvases
-        {
-            egui_ui_state.canvas_index = index.to_string();
-        } else {
-            egui_ui_state.canvas_index = egui_index;
-        }
-    }
+            if ui.button("Apply").clicked() {
+                apply = true;
+            }
 
-    if response.lost_focus() {
-        new_canvas_index = egui_ui_state
-            .canvas_index
-            .parse::<usize>()
-            .unwrap_or_default()
-            .saturating_sub(1);
-    }
-    if ui.button(">").clicked() {
-        new_canvas_index = (app_state.canvas_index.saturating_add(1)).min(num_canvases - 1);
-    }
+            ui.separator();
 
-    if new_canvas_index != app_state.canvas_index {
-        let (_, manifest) = presentation_query
-            .iter()
-            .next()
-            .expect("should have a manifest due to previous check on the number of canvas > 1");
+            ui.horizontal(|ui| {
+                ui.label("Initial view");
+                egui::ComboBox::from_id_salt("FitMode")
+                    .selected_text(app_settings.fit_mode.label())
+                    .show_ui(ui, |ui| {
+                        for fit_mode in FitMode::ALL {
+                            ui.selectable_value(
+                                &mut app_settings.fit_mode,
+                                fit_mode,
+                                fit_mode.label(),
+                            );
+                        }
+                    })
+                    .response
+                    .on_hover_text("How the image is fitted when it is shown. Press F to cycle.");
+            });
 
-        if let Err(err) = crate::web::load_canvas(
-            commands,
-            manifest,
-            app_state,
-            new_canvas_index,
-            model_image_query,
-        ) {
-            let msg = format!("Unable to load canvas.\n'{}'", err);
+            ui.horizontal(|ui| {
+                ui.label("Precision speed");
+                ui.add(
+                    egui::DragValue::new(&mut app_settings.precision_settings.precision_multiplier)
+                        .range(0.01..=1.0)
+                        .speed(0.01)
+                        .prefix("×"),
+                )
+                .on_hover_text("Speed of the panning and zooming while Shift is held.");
+                ui.label("Ctrl step");
+                ui.add(
+                    egui::DragValue::new(&mut app_settings.precision_settings.tile_step_multiplier)
+                        .range(0.25..=8.0)
+                        .speed(0.25)
+                        .suffix(" tiles"),
+                )
+                .on_hover_text("Tiles panned by an arrow key while Ctrl is held.");
+            });
 
-            egui_ui_state
-                .toasts
-                .warning(msg)
-                .show_progress_bar(true)
-                .duration(Duration::from_secs(5));
-        }
-    }
-}
+            ui.horizontal(|ui| {
+                ui.label("Wheel zoom");
+                ui.add(
+                    egui::DragValue::new(&mut app_settings.wheel_settings.sensitivity)
+                        .range(0.1..=5.0)
+                        .speed(0.05)
+                        .prefix("×"),
+                )
+                .on_hover_text("Zoom by a step of the mouse wheel or the scroll of the trackpad.");
+                ui.checkbox(&mut app_settings.wheel_settings.invert, "Invert")
+                    .on_hover_text("Zoom out by scrolling up.");
+                ui.checkbox(&mut app_settings.wheel_settings.smooth, "Smooth")
+                    .on_hover_text(
+                        "Zoom by the fractions of the steps of a smooth scrolling wheel instead \
+                        of a whole step per notch.",
+                    );
+            });
 
-/// Add the canvas thumbnail panel.
-#[allow(clippy::too_many_arguments)]
-fn add_canvas_thumbnails(
-    ui: &mut egui::Ui,
-    egui_ui_state: &mut ResMut<'_, EguiUiState>,
-    app_settings: Res<'_, AppSettings>,
-    app_state: &mut ResMut<'_, AppState>,
-    presentation: &Manifest,
-    commands: &mut Commands,
-    model_image_query: &Query<Entity, With<ModelImage>>,
-) -> Result {
-    let canvas_iter = presentation
-        .model()
-        .get_sequence(egui_ui_state.current_sequence)?
-        .get_canvases();
+            ui.horizontal(|ui| {
+                let low_vision_settings = &mut app_settings.low_vision_settings;
 
-    let thumbnail_size = app_settings.thumbnail_size;
-    let text_style = egui::TextStyle::Body;
-    let row_height = thumbnail_size + 3.0 * ui.text_style_height(&text_style);
-    let column_width = thumbnail_size;
+                ui.checkbox(&mut low_vision_settings.enabled, "Low vision")
+                    .on_hover_text("Larger fonts and high-contrast colors of the panels.");
+                ui.add_enabled(
+                    low_vision_settings.enabled,
+                    egui::Checkbox::new(&mut low_vision_settings.magnifier, "Magnifier"),
+                )
+                .on_hover_text("Show a lens magnifying the image under the cursor.");
+                ui.add_enabled(
+                    low_vision_settings.enabled && low_vision_settings.magnifier,
+                    egui::DragValue::new(&mut low_vision_settings.magnification)
+                        .range(magnifier::MAGNIFICATION_RANGE)
+                        .speed(0.05)
+                        .prefix("×"),
+                )
+                .on_hover_text("Magnification of the lens.");
+            });
 
-    let available_width = ui.available_width();
-    let item_spacing = ui.spacing().item_spacing;
-    let items_per_row = (available_width / (column_width + item_spacing.x))
-        .round()
-        .max(1.0) as usize;
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut app_settings.grid_settings.show_grid, "Grid every")
+                    .on_hover_text("Show a grid over the image, e.g. to transcribe a page.");
+                ui.add(
+                    egui::DragValue::new(&mut app_settings.grid_settings.grid_spacing)
+                        .range(1..=10000)
+                        .suffix(" px"),
+                )
+                .on_hover_text("Pixels of the full size image between the lines of the grid.");
+                ui.checkbox(&mut app_settings.grid_settings.show_rulers, "Rulers")
+                    .on_hover_text(
+                        "Show the rulers in the pixels of the full size image along the view.",
+                    );
+            });
 
-    let canvases: Vec<_> = canvas_iter.collect();
+            ui.horizontal(|ui| {
+                ui.label("Tile overlap");
+                ui.add(
+                    egui::DragValue::new(&mut app_settings.tile_overlap)
+                        .range(0..=MAX_TILE_OVERLAP)
+                        .suffix(" px"),
+                )
+                .on_hover_text(
+                    "Request the tiles larger by the pixels to hide the seams between them, \
+                    for the images loaded afterwards. Only for the IIIF image services.",
+                );
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Download timeout");
+                ui.add(
+                    egui::DragValue::new(&mut app_settings.download_timeout_secs)
+                        .range(5.0..=300.0)
+                        .suffix(" s"),
+                )
+                .on_hover_text("Request the manifest or the image info again if it takes longer.");
+                ui.label("Retries");
+                ui.add(egui::DragValue::new(&mut app_settings.download_retries).range(0..=5))
+                    .on_hover_text(
+                        "Number of the requests sent again before asking to retry or cancel.",
+                    );
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Auto reload");
+                ui.add(
+                    egui::DragValue::new(&mut app_settings.reload_interval_secs)
+                        .range(0..=3600)
+                        .suffix(" s"),
+                )
+                .on_hover_text(
+                    "Reload the manifest shown at the interval, keeping the canvas and the view, \
+                    e.g. while editing it. 0 for off.",
+                );
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Prefetch image info");
+                ui.add(
+                    egui::DragValue::new(&mut app_settings.prefetch_info_canvases)
+                        .range(0..=50)
+                        .suffix(" canvases"),
+                )
+                .on_hover_text(
+                    "Fetch the image info of the first canvases ahead when a manifest is shown, \
+                    so that turning their pages is faster. Not in the data saver mode.",
+                );
+            });
+
+            ui.checkbox(&mut app_settings.data_saver, "Data saver")
+                .on_hover_text(
+                    "Save the data, e.g. on mobile, by loading up to the level below the full size \
+                    in JPEG with fewer downloads at once, and not prefetching the next canvas.",
+                );
+
+            #[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+            ui.checkbox(&mut app_settings.watch_clipboard, "Watch the clipboard")
+                .on_hover_text("Offer to open the IIIF manifest or image URLs when they are copied.");
+
+            #[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+            ui.horizontal(|ui| {
+                ui.label("Effect shader");
+                ui.add(
+                    egui::TextEdit::singleline(&mut app_settings.effect_shader_path)
+                        .hint_text("Path of a WGSL file"),
+                )
+                .on_hover_text(
+                    "Apply the fragment shader of the file to the view, e.g. a false-color \
+                    mapping. It is loaded again when changed.",
+                );
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Theme");
+                egui::ComboBox::from_id_salt("ThemeMode")
+                    .selected_text(app_settings.theme_mode.label())
+                    .show_ui(ui, |ui| {
+                        for theme_mode in ThemeMode::ALL {
+                            ui.selectable_value(
+                                &mut app_settings.theme_mode,
+                                theme_mode,
+                                theme_mode.label(),
+                            );
+                        }
+                    })
+                    .response
+                    .on_hover_text("Follow the dark or light preference of the system, or choose one.");
+
+                let mut custom_accent = app_settings.accent_color.is_some();
+
+                if ui
+                    .checkbox(&mut custom_accent, "Accent color")
+                    .on_hover_text("Color of the selection and the view in the minimap.")
+                    .changed()
+                {
+                    app_settings.accent_color = custom_accent.then_some(DEFAULT_ACCENT_COLOR);
+                }
+
+                if let Some(accent_color) = app_settings.accent_color.as_mut() {
+                    ui.color_edit_button_srgb(accent_color);
+                }
+            });
+
+            ui.horizontal(|ui| {
+                let mut show_canvas = app_settings.canvas_background.is_some();
+
+                if ui
+                    .checkbox(&mut show_canvas, "Canvas background")
+                    .on_hover_text(
+                        "Shade the extent of the canvas behind the image, e.g. to see its \
+                        transparent parts.",
+                    )
+                    .changed()
+                {
+                    app_settings.canvas_background =
+                        show_canvas.then_some(canvas_background::DEFAULT_CANVAS_BACKGROUND);
+                }
+
+                if let Some(canvas_background) = app_settings.canvas_background.as_mut() {
+                    ui.color_edit_button_srgb(canvas_background);
+                }
+            });
+
+            ui.checkbox(&mut app_settings.color_management, "Color management")
+                .on_hover_text(
+                    "Convert the colors of the tiles with an embedded ICC profile to sRGB, e.g. to \
+                    compare the digitized artworks. The tiles shown are loaded again.",
+                );
+
+            ui.horizontal(|ui| {
+                let tile_level_settings = &mut app_settings.tile_level_settings;
+                let mut changed = ui
+                    .checkbox(&mut tile_level_settings.dim_other_levels, "Dim other levels")
+                    .on_hover_text(
+                        "Draw the tiles of the coarser and the finer levels translucent while the \
+                        tiles of the level are loading, e.g. to see which level is shown.",
+                    )
+                    .changed();
+
+                if tile_level_settings.dim_other_levels {
+                    changed |= ui
+                        .add(
+                            egui::DragValue::new(&mut tile_level_settings.other_level_alpha)
+                                .range(0.0..=1.0)
+                                .speed(0.01)
+                                .prefix("Opacity: "),
+                        )
+                        .changed();
+                }
+
+                // Style the tiles shown again.
+                if changed {
+                    tile_mod_state.invalidate();
+                }
+            });
+
+            ui.separator();
+
+            ui.label("3D models");
+            ui.checkbox(
+                &mut app_settings.model_stage_settings.backdrop,
+                "Studio backdrop",
+            );
+            ui.checkbox(
+                &mut app_settings.model_stage_settings.ground_shadow,
+                "Ground shadow",
+            );
+        });
+
+    if apply {
+        app_settings.proxy_prefix = egui_ui_state.proxy_prefix.trim().to_string();
+
+        let languages = egui_ui_state.languages.trim();
+
+        if !languages.is_empty() {
+            app_settings.language = languages.to_string();
+        }
+
+        // Reload the manifest in the address bar through the new proxy.
+        if !egui_ui_state.presentation_url.is_empty() {
+            crate::web::load_presentation(app_state, app_settings, &egui_ui_state.presentation_url);
+        }
+    }
+    egui_ui_state.open_settings = open;
+}
+
+/// Add the side panel of the bookmarks, and get its width.
+fn add_bookmarks_panel(
+    ctx: &egui::Context,
+    bookmarks: &mut Bookmarks,
+    app_settings: &AppSettings,
+) -> f32 {
+    let thumbnail_size = app_settings.thumbnail_size;
+
+    egui::Panel::right("bookmarks_panel")
+        .resizable(true)
+        .show(ctx, |ui| {
+            ui.add(egui::TextEdit::singleline(&mut bookmarks.name).hint_text("Name"));
+            ui.add(
+                egui::TextEdit::multiline(&mut bookmarks.note)
+                    .hint_text("Note")
+                    .desired_rows(2),
+            );
+            if ui.button("Add current view").clicked() {
+                bookmarks.request = Some(BookmarkRequest::Add);
+            }
+
+            // Export and import are only supported natively with the file system.
+            if cfg!(not(target_arch = "wasm32")) {
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut bookmarks.file_path)
+                            .hint_text("bookmarks.json")
+                            .desired_width(ui.available_width() - 110.0),
+                    );
+                    if ui.button("Export").clicked() {
+                        bookmarks.request = Some(BookmarkRequest::Export);
+                    }
+                    if ui.button("Import").clicked() {
+                        bookmarks.request = Some(BookmarkRequest::Import);
+                    }
+                });
+            }
+
+            ui.separator();
+
+            let mut request = None;
+
+            egui::ScrollArea::vertical()
+                .auto_shrink(false)
+                .show(ui, |ui| {
+                    for (index, bookmark) in bookmarks.items.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            let thumbnail = if !bookmark.thumbnail.is_empty() {
+                                ui.add_sized(
+                                    vec2(thumbnail_size, thumbnail_size),
+                                    bevy_egui::egui::Image::new(&bookmark.thumbnail)
+                                        .max_size(vec2(thumbnail_size, thumbnail_size))
+                                        .alt_text(&bookmark.name)
+                                        .sense(Sense::click()),
+                                )
+                            } else {
+                                ui.add_sized(
+                                    vec2(thumbnail_size, thumbnail_size),
+                                    egui::Label::new("🚫")
+                                        .selectable(false)
+                                        .sense(Sense::click()),
+                                )
+                            };
+
+                            ui.vertical(|ui| {
+                                let name = ui
+                                    .link(&bookmark.name)
+                                    .on_hover_text(&bookmark.presentation_url);
+
+                                if thumbnail.clicked() || name.clicked() {
+                                    request = Some(BookmarkRequest::Jump(index));
+                                }
+                                if !bookmark.note.is_empty() {
+                                    add_text(ui, &bookmark.note, None, 3);
+                                }
+                                if ui.small_button("Remove").clicked() {
+                                    request = Some(BookmarkRequest::Remove(index));
+                                }
+                            });
+                        });
+                    }
+                });
+
+            if request.is_some() {
+                bookmarks.request = request;
+            }
+        })
+        .response
+        .rect
+        .width() // height is ignored, as the panel has a height of 100% of the screen
+}
+
+/// Add the window listing the recent notifications with their details.
+fn add_notifications_window(ctx: &egui::Context, egui_ui_state: &mut EguiUiState) {
+    let mut open = egui_ui_state.open_notifications;
+    let mut clear = false;
+
+    egui::Window::new("Notifications")
+        .id(egui::Id::new("notifications_window"))
+        .open(&mut open)
+        .default_width(COLLECTION_WINDOW_WIDTH)
+        .show(ctx, |ui| {
+            if ui.button("Clear").clicked() {
+                clear = true;
+            }
+
+            ui.separator();
+
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for (index, notification) in egui_ui_state.notifications.iter().enumerate() {
+                    egui::CollapsingHeader::new(notification.summary())
+                        .id_salt(index)
+                        .show(ui, |ui| {
+                            if let Some(hint) = notification.hint() {
+                                ui.label(hint);
+                            }
+
+                            let details = notification.details();
+                            if !details.is_empty() {
+                                ui.add(
+                                    egui::Label::new(egui::RichText::new(details).monospace())
+                                        .wrap(),
+                                );
+                            }
+                        });
+                }
+            });
+        });
+
+    if clear {
+        egui_ui_state.notifications.clear();
+        open = false;
+    }
+    egui_ui_state.open_notifications = open;
+}
+
+/// Show the members of the loaded collection with the search across them.
+/// Closing the window leaves the collection.
+pub(crate) fn collection_ui_system(
+    mut contexts: EguiContexts,
+    collection_query: Single<(Entity, &Collection)>,
+    mut collection_search: ResMut<CollectionSearch>,
+    mut egui_ui_state: ResMut<EguiUiState>,
+    app_settings: Res<AppSettings>,
+    mut app_state: ResMut<AppState>,
+    mut commands: Commands,
+) -> Result {
+    let ctx = contexts.ctx_mut()?;
+    let (collection_entity, collection) = collection_query.into_inner();
+    let mut open = true;
+    let mut selected_url = None;
+
+    egui::Window::new("Collection")
+        .id(egui::Id::new("collection_window"))
+        .open(&mut open)
+        .default_width(COLLECTION_WINDOW_WIDTH)
+        .show(ctx, |ui| {
+            let title_color = ui.visuals().strong_text_color();
+
+            add_text(
+                ui,
+                &collection.model().get_title(&app_settings.language),
+                Some(title_color),
+                2,
+            );
+
+            ui.add(
+                egui::TextEdit::singleline(&mut collection_search.query)
+                    .hint_text("Search labels and metadata"),
+            )
+            .on_hover_text(collection.url());
+
+            let members = collection.model().get_members().collect::<Vec<_>>();
+            let matches = if collection_search.query.is_empty() {
+                (0..members.len()).collect()
+            } else {
+                let (matches, num_searched) =
+                    collection_search.get_matches(collection, &app_settings.language);
+                let num_manifests = members.iter().filter(|x| !x.is_collection()).count();
+
+                ui.label(format!(
+                    "{} found, {} of {} manifests searched.",
+                    matches.len(),
+                    num_searched,
+                    num_manifests
+                ));
+
+                matches
+            };
+
+            ui.separator();
+
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for member in matches.into_iter().filter_map(|index| members.get(index)) {
+                    let label = member
+                        .get_label(&app_settings.language)
+                        .collect::<Vec<_>>()
+                        .join(",");
+                    let label = if label.is_empty() {
+                        member.get_id().to_string()
+                    } else {
+                        label
+                    };
+                    let label = if member.is_collection() {
+                        format!("🗀 {}", label)
+                    } else {
+                        label
+                    };
+
+                    let thumbnail = member.get_thumbnail();
+
+                    ui.horizontal(|ui| {
+                        let size = vec2(COLLECTION_THUMBNAIL_SIZE, COLLECTION_THUMBNAIL_SIZE);
+
+                        // Keep the labels aligned whether the members have the thumbnails or not.
+                        let thumbnail_clicked = if thumbnail.is_empty() {
+                            ui.allocate_exact_size(size, egui::Sense::hover());
+                            false
+                        } else {
+                            ui.add_sized(
+                                size,
+                                bevy_egui::egui::Image::new(&*thumbnail)
+                                    .max_size(size)
+                                    .sense(egui::Sense::click()),
+                            )
+                            .clicked()
+                        };
+
+                        if ui.link(label).on_hover_text(member.get_id()).clicked()
+                            || thumbnail_clicked
+                        {
+                            selected_url = Some(member.get_id().to_string());
+                        }
+                    });
+                }
+            });
+        });
+
+    if !open {
+        commands.entity(collection_entity).despawn();
+        collection_search.clear();
+    }
+
+    if let Some(url) = selected_url {
+        egui_ui_state.presentation_url = url.to_string();
+        crate::web::load_presentation(&mut app_state, &app_settings, &url);
+    }
+
+    Ok(())
+}
+
+/// Offer to restore the session of the previous run.
+pub(crate) fn session_ui_system(
+    mut contexts: EguiContexts,
+    mut session_state: ResMut<SessionState>,
+    mut egui_ui_state: ResMut<EguiUiState>,
+    mut app_state: ResMut<AppState>,
+    app_settings: Res<AppSettings>,
+) -> Result {
+    let Some(previous) = &session_state.previous else {
+        return Ok(());
+    };
+
+    let ctx = contexts.ctx_mut()?;
+    let mut restore = None;
+
+    egui::Window::new("Restore previous session")
+        .id(egui::Id::new("session_window"))
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+        .show(ctx, |ui| {
+            ui.label(format!("Canvas {} of", previous.canvas_index + 1));
+            let url_color = ui.visuals().strong_text_color();
+
+            add_text(ui, &previous.presentation_url, Some(url_color), 2);
+
+            ui.horizontal(|ui| {
+                if ui.button("Restore").clicked() {
+                    restore = Some(true);
+                }
+                if ui.button("Dismiss").clicked() {
+                    restore = Some(false);
+                }
+            });
+        });
+
+    match restore {
+        Some(true) => {
+            let session = session_state
+                .previous
+                .take()
+                .expect("should have the previous session");
+
+            egui_ui_state.open_notifications = session.open_notifications;
+            egui_ui_state.open_settings = session.open_settings;
+            egui_ui_state.presentation_url = session.presentation_url.to_string();
+            crate::web::load_presentation(&mut app_state, &app_settings, &session.presentation_url);
+            session_state.restoring = Some(session);
+        }
+        Some(false) => {
+            session_state.previous = None;
+        }
+        None => {}
+    }
+
+    Ok(())
+}
+
+/// Show the rectangles drawn by the user over the image, and the window to draw more, write
+/// their comments and export them as an annotation page.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn annotation_ui_system(
+    mut contexts: EguiContexts,
+    mut authoring: ResMut<AnnotationAuthoring>,
+    mut egui_ui_state: ResMut<EguiUiState>,
+    app_state: Res<AppState>,
+    presentation: Option<Single<&Manifest>>,
+    tiled_image: Option<Single<&TiledImage>>,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera2d>>,
+    mut messages: MessageWriter<UserNotification>,
+) -> Result {
+    let ctx = contexts.ctx_mut()?;
+    let (camera, global_transform) = camera.into_inner();
+    let canvas_id = presentation.and_then(|x| {
+        x.model()
+            .get_sequence(app_state.sequence_index)
+            .ok()?
+            .get_canvas(app_state.canvas_index)
+            .ok()
+            .map(|x| x.get_id().to_string())
+    });
+    let canvas_items = canvas_id
+        .as_deref()
+        .map(|x| authoring.get_canvas_items(&app_state.presentation_url, x))
+        .unwrap_or_default();
+    let mut new_index = None;
+
+    if let (Some(tiled_image), Some(canvas_id)) = (tiled_image, canvas_id.as_deref()) {
+        let tiled_image = tiled_image.into_inner();
+        let viewport_rect = ctx.available_rect();
+        let to_screen = |image_pos: Vec2| {
+            camera
+                .world_to_viewport(global_transform, tiled_image.image_to_world(image_pos))
+                .ok()
+                .map(|x| egui::pos2(x.x, x.y))
+        };
+        let to_image = |screen_pos: egui::Pos2| {
+            camera
+                .viewport_to_world_2d(global_transform, Vec2::new(screen_pos.x, screen_pos.y))
+                .ok()
+                .map(|x| tiled_image.world_to_image(x.extend(0.0)))
+        };
+
+        // Dragging over the viewport draws a rectangle instead of panning the image.
+        if egui_ui_state.open_annotations && authoring.drawing {
+            egui::Area::new(egui::Id::new("annotation_drawing"))
+                .fixed_pos(viewport_rect.min)
+                .show(ctx, |ui| {
+                    let response = ui.allocate_response(viewport_rect.size(), Sense::drag());
+                    let pointer_pos = response.interact_pointer_pos().and_then(to_image);
+
+                    if response.hovered() {
+                        ctx.set_cursor_icon(egui::CursorIcon::Crosshair);
+                    }
+
+                    if response.drag_started() {
+                        authoring.drag = pointer_pos.map(|x| (x, x));
+                    } else if response.dragged()
+                        && let Some(pointer_pos) = pointer_pos
+                        && let Some((_, end)) = authoring.drag.as_mut()
+                    {
+                        *end = pointer_pos;
+                    }
+
+                    if response.drag_stopped() {
+                        new_index = authoring.finish_drawing(
+                            &app_state.presentation_url,
+                            canvas_id,
+                            tiled_image.get_image_max_size_rect(),
+                        );
+                    }
+                });
+        }
+
+        let painter = ctx
+            .layer_painter(egui::LayerId::background())
+            .with_clip_rect(viewport_rect);
+
+        for &index in canvas_items.iter().chain(new_index.iter()) {
+            let annotation = &authoring.items[index];
+            let (Some(min), Some(max)) = (
+                to_screen(annotation.region.min),
+                to_screen(annotation.region.max),
+            ) else {
+                continue;
+            };
+            let rect = egui::Rect::from_two_pos(min, max);
+
+            painter.rect_stroke(
+                rect,
+                0.0,
+                egui::Stroke::new(2.0, ANNOTATION_COLOR),
+                egui::StrokeKind::Outside,
+            );
+
+            // First line of the comment above the rectangle.
+            if let Some(line) = annotation.comment.lines().next() {
+                let galley = painter.layout_no_wrap(
+                    line.to_string(),
+                    FontId::proportional(14.0),
+                    ANNOTATION_COLOR,
+                );
+                let text_rect =
+                    egui::Align2::LEFT_BOTTOM.anchor_size(rect.left_top(), galley.size());
+
+                painter.rect_filled(text_rect.expand(2.0), 2.0, Color32::from_black_alpha(160));
+                painter.galley(text_rect.min, galley, ANNOTATION_COLOR);
+            }
+        }
+
+        // Rectangle being drawn.
+        if let Some((start, end)) = authoring.drag
+            && let (Some(start), Some(end)) = (to_screen(start), to_screen(end))
+        {
+            painter.rect_stroke(
+                egui::Rect::from_two_pos(start, end),
+                0.0,
+                egui::Stroke::new(1.0, Color32::WHITE),
+                egui::StrokeKind::Outside,
+            );
+        }
+    }
+
+    let mut open = egui_ui_state.open_annotations;
+    let mut remove = None;
+    let mut changed = false;
+    let mut export = false;
+
+    // Above the drawing area over the viewport.
+    egui::Window::new("Annotations")
+        .id(egui::Id::new("annotations_window"))
+        .open(&mut open)
+        .order(egui::Order::Foreground)
+        .default_width(COLLECTION_WINDOW_WIDTH)
+        .show(ctx, |ui| {
+            ui.checkbox(&mut authoring.drawing, "Draw rectangles")
+                .on_hover_text("Drag on the image to draw a rectangle, then write its comment.");
+
+            // Export is only supported natively with the file system.
+            if cfg!(not(target_arch = "wasm32")) {
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut authoring.file_path)
+                            .hint_text("annotations.json")
+                            .desired_width(ui.available_width() - 60.0),
+                    );
+                    if ui.button("Export").clicked() {
+                        export = true;
+                    }
+                });
+            }
+
+            ui.separator();
+
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for &index in canvas_items.iter().chain(new_index.iter()) {
+                    ui.horizontal(|ui| {
+                        let response = ui.add(
+                            egui::TextEdit::multiline(&mut authoring.items[index].comment)
+                                .hint_text("Comment")
+                                .desired_rows(1)
+                                .desired_width(ui.available_width() - 60.0),
+                        );
+
+                        if new_index == Some(index) {
+                            response.request_focus();
+                        }
+                        if response.lost_focus() {
+                            changed = true;
+                        }
+                        if ui.small_button("Remove").clicked() {
+                            remove = Some(index);
+                        }
+                    });
+                }
+            });
+        });
+
+    if let Some(index) = remove {
+        authoring.items.remove(index);
+        changed = true;
+    }
+    if changed || new_index.is_some() {
+        authoring.save();
+    }
+
+    if export {
+        let file_path = authoring.file_path.trim().to_string();
+
+        match std::fs::write(
+            &file_path,
+            authoring.get_annotation_page(&app_state.presentation_url),
+        ) {
+            Ok(_) => info!("Exported the annotations to {}", file_path),
+            Err(e) => {
+                messages.write(UserNotification::Error(format!(
+                    "Failed to export the annotations to '{}'.\n{}",
+                    file_path, e
+                )));
+            }
+        }
+    }
+
+    if !open {
+        authoring.drawing = false;
+        authoring.drag = None;
+    }
+    egui_ui_state.open_annotations = open;
+
+    Ok(())
+}
+
+/// Show the window to copy the citation and the content state of the current view, and to open
+/// the content state shared by others.
+pub(crate) fn share_ui_system(
+    mut contexts: EguiContexts,
+    mut share: ResMut<ContentStateShare>,
+    mut egui_ui_state: ResMut<EguiUiState>,
+    mut was_open: Local<bool>,
+) -> Result {
+    let ctx = contexts.ctx_mut()?;
+    let mut open = egui_ui_state.open_share;
+    let mut request = None;
+
+    // Get the content state of the view when the window is opened.
+    if open && !*was_open {
+        request = Some(ShareRequest::Update);
+    }
+
+    egui::Window::new("Share")
+        .id(egui::Id::new("share_window"))
+        .open(&mut open)
+        .default_width(COLLECTION_WINDOW_WIDTH)
+        .show(ctx, |ui| {
+            for (label, text) in [
+                ("Citation", &share.citation),
+                ("Link", &share.link),
+                ("Content state", &share.json),
+            ] {
+                ui.horizontal(|ui| {
+                    ui.strong(label);
+
+                    if add_icon_button(ui, "📋", &format!("Copy the {}", label.to_lowercase()))
+                        .clicked()
+                    {
+                        ui.ctx().copy_text(text.to_string());
+                    }
+                });
+                ui.label(egui::RichText::new(text).size(12.0).color(Color32::GRAY));
+            }
+
+            if ui.button("Update to the view").clicked() {
+                request = Some(ShareRequest::Update);
+            }
+
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut share.input)
+                        .hint_text("Content state or manifest URL")
+                        .desired_width(ui.available_width() - 50.0),
+                );
+                if ui.button("Open").clicked() {
+                    request = Some(ShareRequest::Open);
+                }
+            });
+        });
+
+    if request.is_some() {
+        share.request = request;
+    }
+
+    *was_open = open;
+    egui_ui_state.open_share = open;
+
+    Ok(())
+}
+
+/// Show the console to script the viewer with the commands, e.g. "open <url>; goto 12".
+pub(crate) fn console_ui_system(
+    mut contexts: EguiContexts,
+    mut console: ResMut<Console>,
+    mut egui_ui_state: ResMut<EguiUiState>,
+) -> Result {
+    let ctx = contexts.ctx_mut()?;
+    let mut open = egui_ui_state.open_console;
+    let mut is_submitted = false;
+
+    egui::Window::new("Console")
+        .id(egui::Id::new("console_window"))
+        .open(&mut open)
+        .default_width(COLLECTION_WINDOW_WIDTH)
+        .show(ctx, |ui| {
+            egui::ScrollArea::vertical()
+                .max_height(200.0)
+                .stick_to_bottom(true)
+                .auto_shrink([false, true])
+                .show(ui, |ui| {
+                    for line in &console.output {
+                        ui.label(egui::RichText::new(line).monospace().size(12.0));
+                    }
+                });
+
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut console.input)
+                        .hint_text("open <url>; goto <page>; zoom <factor>; region <x,y,w,h>")
+                        .code_editor()
+                        .desired_width(ui.available_width() - 50.0),
+                );
+
+                if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    is_submitted = true;
+                    response.request_focus();
+                }
+                if ui.button("Run").clicked() {
+                    is_submitted = true;
+                }
+            });
+        });
+
+    if is_submitted {
+        console.submit();
+    }
+
+    egui_ui_state.open_console = open;
+
+    Ok(())
+}
+
+/// Show the window of the timings of the recent loading stages, to find the slow servers, and to
+/// export them as a trace.
+pub(crate) fn diagnostics_ui_system(
+    mut contexts: EguiContexts,
+    mut load_trace: ResMut<LoadTrace>,
+    mut egui_ui_state: ResMut<EguiUiState>,
+    host_stats: Res<HostStats>,
+    mut app_settings: ResMut<AppSettings>,
+) -> Result {
+    let ctx = contexts.ctx_mut()?;
+    let mut open = egui_ui_state.open_diagnostics;
+    let mut request = None;
+    let mut polite_delay_ms = app_settings.polite_delay_ms;
+
+    egui::Window::new("Diagnostics")
+        .id(egui::Id::new("diagnostics_window"))
+        .open(&mut open)
+        .default_width(COLLECTION_WINDOW_WIDTH)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("Copy trace").clicked() {
+                    ui.ctx().copy_text(load_trace.to_json());
+                }
+                if ui.button("Clear").clicked() {
+                    request = Some(TraceRequest::Clear);
+                }
+            });
+
+            // Export is only supported natively with the file system.
+            if cfg!(not(target_arch = "wasm32")) {
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut load_trace.file_path)
+                            .hint_text("trace.json")
+                            .desired_width(ui.available_width() - 60.0),
+                    );
+                    if ui.button("Export").clicked() {
+                        request = Some(TraceRequest::Export);
+                    }
+                });
+            }
+
+            egui::CollapsingHeader::new("Hosts")
+                .default_open(true)
+                .show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        let mut polite = polite_delay_ms > 0;
+
+                        if ui
+                            .checkbox(&mut polite, "Polite mode")
+                            .on_hover_text(
+                                "Wait between the tile requests to each host, to avoid \
+                                hammering the institutional servers.",
+                            )
+                            .changed()
+                        {
+                            polite_delay_ms = if polite { DEFAULT_POLITE_DELAY_MS } else { 0 };
+                        }
+                        ui.add_enabled(
+                            polite,
+                            egui::DragValue::new(&mut polite_delay_ms)
+                                .range(0..=MAX_POLITE_DELAY_MS)
+                                .suffix(" ms"),
+                        );
+                        if ui.button("Reset").clicked() {
+                            host_stats.clear();
+                        }
+                    });
+
+                    egui::Grid::new("host_stats_grid")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for label in ["Host", "Requests", "Errors", "Received"] {
+                                ui.strong(label);
+                            }
+                            ui.end_row();
+
+                            for (host, stat) in host_stats.get_stats() {
+                                ui.label(if host.is_empty() { "(local)" } else { &host });
+                                ui.label(stat.requests.to_string());
+                                ui.label(format!(
+                                    "{} ({:.0}%)",
+                                    stat.errors,
+                                    stat.get_error_rate() * 100.0
+                                ));
+                                ui.label(format!("{:.1} MB", stat.bytes as f64 / 1.0e6));
+                                ui.end_row();
+                            }
+                        });
+                });
+
+            ui.separator();
+
+            if load_trace.spans.is_empty() {
+                ui.label("Nothing loaded yet.");
+            }
+
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                egui::Grid::new("diagnostics_grid")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for label in ["Stage", "Server", "Time", ""] {
+                            ui.strong(label);
+                        }
+                        ui.end_row();
+
+                        // The latest first.
+                        for span in load_trace.spans.iter().rev() {
+                            ui.label(span.stage.name());
+                            ui.label(span.get_host()).on_hover_text(&span.url);
+                            ui.label(format!("{:.0} ms", span.duration_secs * 1000.0));
+
+                            let status = if span.ok {
+                                egui::RichText::new("✔").color(Color32::GREEN)
+                            } else {
+                                egui::RichText::new("✖").color(Color32::RED)
+                            };
+                            let response = ui.label(status);
+
+                            if !span.detail.is_empty() {
+                                response.on_hover_text(&span.detail);
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+        });
+
+    if request.is_some() {
+        load_trace.request = request;
+    }
+    // Only when changed, not to trigger the systems following the settings every frame.
+    if polite_delay_ms != app_settings.polite_delay_ms {
+        app_settings.polite_delay_ms = polite_delay_ms;
+    }
+    egui_ui_state.open_diagnostics = open;
+
+    Ok(())
+}
+
+/// Show the window to export the region of the image at the full size for printing, stitched from
+/// the tiles, with the progress of the export.
+#[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+pub(crate) fn print_export_ui_system(
+    mut contexts: EguiContexts,
+    mut stitch: ResMut<StitchExport>,
+    mut egui_ui_state: ResMut<EguiUiState>,
+    mut was_open: Local<bool>,
+) -> Result {
+    let ctx = contexts.ctx_mut()?;
+    let mut open = egui_ui_state.open_print_export;
+    let mut request = None;
+
+    // Export the view when the window is opened.
+    if open && !*was_open {
+        request = Some(StitchRequest::UseView);
+    }
+
+    egui::Window::new("Export for printing")
+        .id(egui::Id::new("print_export_window"))
+        .open(&mut open)
+        .default_width(COLLECTION_WINDOW_WIDTH)
+        .show(ctx, |ui| {
+            let Some((width, height)) = stitch.size else {
+                ui.label("No image to export.");
+                return;
+            };
+
+            ui.horizontal(|ui| {
+                let region = if stitch.region.is_some() {
+                    "Region"
+                } else {
+                    "Whole image"
+                };
+
+                ui.label(format!("{}: {} × {} px", region, width, height));
+            });
+
+            ui.horizontal(|ui| {
+                if ui.button("Use the view").clicked() {
+                    request = Some(StitchRequest::UseView);
+                }
+                if ui.button("Whole image").clicked() {
+                    stitch.region = None;
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut stitch.file_path)
+                        .hint_text("export.tif")
+                        .desired_width(ui.available_width() - 60.0),
+                )
+                .on_hover_text("Saved as TIFF or PNG by the extension.");
+
+                if ui
+                    .add_enabled(stitch.progress.is_none(), Button::new("Export"))
+                    .clicked()
+                {
+                    request = Some(StitchRequest::Start);
+                }
+            });
+
+            if let Some(progress) = &stitch.progress {
+                let (done, total) = progress.get_counts();
+
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::ProgressBar::new(progress.get_fraction())
+                            .text(format!("{} / {} tiles", done, total))
+                            .desired_width(ui.available_width() - 60.0),
+                    );
+                    if ui.button("Cancel").clicked() {
+                        request = Some(StitchRequest::Cancel);
+                    }
+                });
+            } else if let Some(path) = &stitch.saved_path {
+                ui.label(format!("Saved to {}", path.display()));
+            }
+        });
+
+    if request.is_some() {
+        stitch.request = request;
+    }
+
+    *was_open = open;
+    egui_ui_state.open_print_export = open;
+
+    Ok(())
+}
+
+/// Show the window to export the contact sheet of the thumbnails of the canvases with their
+/// labels, with the progress of the export.
+#[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+pub(crate) fn contact_sheet_ui_system(
+    mut contexts: EguiContexts,
+    mut contact_sheet: ResMut<ContactSheetExport>,
+    mut egui_ui_state: ResMut<EguiUiState>,
+) -> Result {
+    let ctx = contexts.ctx_mut()?;
+    let mut open = egui_ui_state.open_contact_sheet;
+    let mut request = None;
+
+    egui::Window::new("Contact sheet")
+        .id(egui::Id::new("contact_sheet_window"))
+        .open(&mut open)
+        .default_width(COLLECTION_WINDOW_WIDTH)
+        .show(ctx, |ui| {
+            if contact_sheet.canvas_count == 0 {
+                ui.label("No canvases to export.");
+                return;
+            }
+
+            let canvas_count = contact_sheet.canvas_count as u32;
+
+            egui::Grid::new("contact_sheet_grid")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label("Columns");
+                    ui.add(egui::DragValue::new(&mut contact_sheet.columns).range(COLUMNS_RANGE));
+                    ui.end_row();
+
+                    ui.label("Thumbnail size");
+                    ui.add(
+                        egui::DragValue::new(&mut contact_sheet.thumbnail_size)
+                            .range(THUMBNAIL_SIZE_RANGE)
+                            .suffix(" px"),
+                    );
+                    ui.end_row();
+
+                    ui.label("Canvases");
+                    ui.add(
+                        egui::DragValue::new(&mut contact_sheet.max_canvases)
+                            .range(0..=canvas_count)
+                            .custom_formatter(|x, _| match x as u32 {
+                                0 => format!("All {}", canvas_count),
+                                x => format!("First {}", x),
+                            }),
+                    )
+                    .on_hover_text("Number of the first canvases on the sheet, 0 for all.");
+                    ui.end_row();
+                });
+
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut contact_sheet.file_path)
+                        .hint_text("contact_sheet.png")
+                        .desired_width(ui.available_width() - 60.0),
+                )
+                .on_hover_text("Saved as PDF or PNG by the extension.");
+
+                if ui
+                    .add_enabled(contact_sheet.progress.is_none(), Button::new("Export"))
+                    .clicked()
+                {
+                    request = Some(ContactSheetRequest::Start);
+                }
+            });
+
+            if let Some(progress) = &contact_sheet.progress {
+                let (done, total) = progress.get_counts();
+
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::ProgressBar::new(progress.get_fraction())
+                            .text(format!("{} / {} thumbnails", done, total))
+                            .desired_width(ui.available_width() - 60.0),
+                    );
+                    if ui.button("Cancel").clicked() {
+                        request = Some(ContactSheetRequest::Cancel);
+                    }
+                });
+            } else if let Some(path) = &contact_sheet.saved_path {
+                ui.label(format!("Saved to {}", path.display()));
+            }
+        });
+
+    if request.is_some() {
+        contact_sheet.request = request;
+    }
+
+    egui_ui_state.open_contact_sheet = open;
+
+    Ok(())
+}
+
+/// Show the progress of the export of the manifest for offline, which can be cancelled.
+#[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+pub(crate) fn offline_export_ui_system(
+    mut contexts: EguiContexts,
+    offline_export: Res<OfflineExport>,
+) -> Result {
+    let ctx = contexts.ctx_mut()?;
+
+    egui::Window::new("Export for offline")
+        .id(egui::Id::new("offline_export_window"))
+        .default_width(COLLECTION_WINDOW_WIDTH)
+        .show(ctx, |ui| {
+            let (done, total) = offline_export.progress.get_counts();
+
+            ui.label(&offline_export.manifest_url);
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::ProgressBar::new(offline_export.progress.get_fraction())
+                        .text(format!("{} / {} tiles", done, total))
+                        .desired_width(ui.available_width() - 60.0),
+                );
+                if ui.button("Cancel").clicked() {
+                    offline_export.cancel();
+                }
+            });
+        });
+
+    Ok(())
+}
+
+/// Show the window to load a tour and pick its steps, and the caption of the current step with
+/// the controls to move between the steps.
+pub(crate) fn tour_ui_system(
+    mut contexts: EguiContexts,
+    mut tour: ResMut<Tour>,
+    mut egui_ui_state: ResMut<EguiUiState>,
+) -> Result {
+    let ctx = contexts.ctx_mut()?;
+    let mut open = egui_ui_state.open_tour;
+    let mut request = None;
+
+    egui::Window::new("Tour")
+        .id(egui::Id::new("tour_window"))
+        .open(&mut open)
+        .default_width(COLLECTION_WINDOW_WIDTH)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut tour.url)
+                        .hint_text("https://example.org/tour.json")
+                        .desired_width(ui.available_width() - 50.0),
+                )
+                .on_hover_text(
+                    "A JSON file of the steps with 'canvas', 'xywh' and 'caption', \
+                    or an annotation page, e.g. the exported bookmarks.",
+                );
+                if ui.button("Load").clicked() {
+                    request = Some(TourRequest::Load);
+                }
+            });
+
+            ui.separator();
+
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for (index, step) in tour.steps.iter().enumerate() {
+                    let label = if step.caption.is_empty() {
+                        format!("{}. {}", index + 1, step.canvas_id)
+                    } else {
+                        format!(
+                            "{}. {}",
+                            index + 1,
+                            step.caption.lines().next().unwrap_or_default()
+                        )
+                    };
+
+                    if ui
+                        .selectable_label(tour.current == Some(index), label)
+                        .clicked()
+                    {
+                        request = Some(TourRequest::Go(index));
+                    }
+                }
+            });
+        });
+
+    egui_ui_state.open_tour = open;
+
+    // Caption bar of the current step.
+    if let Some(current) = tour.current
+        && let Some(step) = tour.get_current_step()
+    {
+        egui::Window::new("Tour step")
+            .id(egui::Id::new("tour_step_window"))
+            .title_bar(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_BOTTOM, egui::vec2(0.0, -24.0))
+            .show(ctx, |ui| {
+                ui.set_max_width(COLLECTION_WINDOW_WIDTH * 2.0);
+
+                if !step.caption.is_empty() {
+                    ui.label(&step.caption);
+                }
+
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(current > 0, Button::new("◀"))
+                        .on_hover_text("Previous step")
+                        .clicked()
+                    {
+                        request = Some(TourRequest::Go(current - 1));
+                    }
+                    ui.label(format!("{} / {}", current + 1, tour.steps.len()));
+                    if ui
+                        .add_enabled(current + 1 < tour.steps.len(), Button::new("▶"))
+                        .on_hover_text("Next step")
+                        .clicked()
+                    {
+                        request = Some(TourRequest::Go(current + 1));
+                    }
+                    if ui.button("Stop").clicked() {
+                        request = Some(TourRequest::Stop);
+                    }
+                });
+            });
+    }
+
+    if request.is_some() {
+        tour.request = request;
+    }
+
+    Ok(())
+}
+
+/// Get the plain text of the label of the region of interest, which may be HTML.
+fn get_highlight_label(label: &str, index: usize) -> String {
+    let text = if html::is_html(label) {
+        html::parse_html(label)
+            .into_iter()
+            .map(|x| x.text)
+            .collect::<String>()
+    } else {
+        label.to_string()
+    };
+
+    match text.lines().next().map(str::trim) {
+        Some(line) if !line.is_empty() => line.to_string(),
+        _ => format!("Region {}", index + 1),
+    }
+}
+
+/// Show the window of the regions of interest of the current canvas provided by the publisher,
+/// zooming to the one picked and flashing its outline.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn highlights_ui_system(
+    mut contexts: EguiContexts,
+    mut egui_ui_state: ResMut<EguiUiState>,
+    app_state: Res<AppState>,
+    app_settings: Res<AppSettings>,
+    presentation: Option<Single<&Manifest>>,
+    tiled_image: Option<Single<&TiledImage>>,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera2d>>,
+    mut camera_command_writer: MessageWriter<CameraCommand>,
+    mut flash: Local<Option<(Rect, f64)>>,
+) -> Result {
+    let ctx = contexts.ctx_mut()?;
+    let now = ctx.input(|i| i.time);
+
+    if egui_ui_state.open_highlights {
+        let highlights = presentation
+            .and_then(|x| {
+                x.model()
+                    .get_sequence(app_state.sequence_index)
+                    .ok()?
+                    .get_canvas(app_state.canvas_index)
+                    .ok()
+                    .map(|x| x.get_highlights(&app_settings.language))
+            })
+            .unwrap_or_default();
+        let mut open = true;
+
+        egui::Window::new("Regions of interest")
+            .id(egui::Id::new("highlights_window"))
+            .open(&mut open)
+            .default_width(COLLECTION_WINDOW_WIDTH)
+            .show(ctx, |ui| {
+                if highlights.is_empty() {
+                    ui.label("No regions of interest on this canvas.");
+                    return;
+                }
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for (index, highlight) in highlights.iter().enumerate() {
+                        // The canvas is taken as the full size image as usually painted.
+                        let Some(region) = parse_xywh(&highlight.fragment) else {
+                            continue;
+                        };
+
+                        if ui
+                            .selectable_label(
+                                flash.is_some_and(|(x, _)| x == region),
+                                get_highlight_label(&highlight.label, index),
+                            )
+                            .on_hover_text(&highlight.fragment)
+                            .clicked()
+                        {
+                            camera_command_writer.write(CameraCommand::ZoomToImageRect(region));
+                            *flash = Some((region, now));
+                        }
+                    }
+                });
+            });
+
+        egui_ui_state.open_highlights = open;
+    }
+
+    let Some((region, start)) = *flash else {
+        return Ok(());
+    };
+    let elapsed = now - start;
+
+    if elapsed > HIGHLIGHT_FLASH_SECS {
+        *flash = None;
+        return Ok(());
+    }
+
+    if let Some(tiled_image) = tiled_image {
+        let (camera, global_transform) = camera.into_inner();
+        let to_screen = |image_pos: Vec2| {
+            camera
+                .world_to_viewport(global_transform, tiled_image.image_to_world(image_pos))
+                .ok()
+                .map(|x| egui::pos2(x.x, x.y))
+        };
+
+        if let (Some(min), Some(max)) = (to_screen(region.min), to_screen(region.max)) {
+            let alpha = 1.0 - (elapsed / HIGHLIGHT_FLASH_SECS) as f32;
+
+            ctx.layer_painter(egui::LayerId::background())
+                .with_clip_rect(ctx.available_rect())
+                .rect_stroke(
+                    egui::Rect::from_two_pos(min, max),
+                    0.0,
+                    egui::Stroke::new(3.0, HIGHLIGHT_COLOR.gamma_multiply(alpha)),
+                    egui::StrokeKind::Outside,
+                );
+        }
+    }
+
+    // Fade out the outline.
+    ctx.request_repaint();
+
+    Ok(())
+}
+
+/// Show the timeline of a time-based canvas to scrub, with the outlines of the regions and the
+/// texts of the annotations at the position.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn timeline_ui_system(
+    mut contexts: EguiContexts,
+    mut timeline: ResMut<Timeline>,
+    app_state: Res<AppState>,
+    app_settings: Res<AppSettings>,
+    presentation: Option<Single<&Manifest>>,
+    tiled_image: Option<Single<&TiledImage>>,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera2d>>,
+) -> Result {
+    if !timeline.is_time_based() {
+        return Ok(());
+    }
+
+    let ctx = contexts.ctx_mut()?;
+    let annotations = presentation
+        .and_then(|x| {
+            x.model()
+                .get_sequence(app_state.sequence_index)
+                .ok()?
+                .get_canvas(app_state.canvas_index)
+                .ok()
+                .map(|x| x.get_timed_annotations(&app_settings.language))
+        })
+        .unwrap_or_default()
+        .iter()
+        .filter_map(TimedAnnotation::from_highlight)
+        .collect::<Vec<_>>();
+    let active = annotations
+        .iter()
+        .enumerate()
+        .filter(|(_, x)| x.is_active_at(timeline.position))
+        .collect::<Vec<_>>();
+
+    egui::Window::new("Timeline")
+        .id(egui::Id::new("timeline_window"))
+        .anchor(egui::Align2::CENTER_BOTTOM, egui::vec2(0.0, -10.0))
+        .title_bar(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                let duration = timeline.duration;
+
+                ui.add(
+                    egui::Slider::new(&mut timeline.position, 0.0..=duration)
+                        .show_value(false)
+                        .trailing_fill(true),
+                )
+                .on_hover_text("Drag to show the annotations at the time.");
+                ui.label(format!(
+                    "{} / {}",
+                    format_position(timeline.position),
+                    format_position(duration)
+                ));
+            });
+
+            for (index, annotation) in &active {
+                ui.label(get_highlight_label(&annotation.label, *index));
+            }
+        });
+
+    if let Some(tiled_image) = tiled_image {
+        let (camera, global_transform) = camera.into_inner();
+        let to_screen = |image_pos: Vec2| {
+            camera
+                .world_to_viewport(global_transform, tiled_image.image_to_world(image_pos))
+                .ok()
+                .map(|x| egui::pos2(x.x, x.y))
+        };
+        let painter = ctx
+            .layer_painter(egui::LayerId::background())
+            .with_clip_rect(ctx.available_rect());
+
+        // The canvas is taken as the full size image as usually painted.
+        for (index, annotation) in &active {
+            let Some(region) = annotation.region else {
+                continue;
+            };
+
+            if let (Some(min), Some(max)) = (to_screen(region.min), to_screen(region.max)) {
+                let rect = egui::Rect::from_two_pos(min, max);
+
+                painter.rect_stroke(
+                    rect,
+                    0.0,
+                    egui::Stroke::new(3.0, HIGHLIGHT_COLOR),
+                    egui::StrokeKind::Outside,
+                );
+                painter.text(
+                    rect.left_top() - egui::vec2(0.0, 4.0),
+                    egui::Align2::LEFT_BOTTOM,
+                    get_highlight_label(&annotation.label, *index),
+                    egui::FontId::proportional(14.0),
+                    HIGHLIGHT_COLOR,
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Show the toolbar to fit the image to the viewport over its top left corner, to copy the view
+/// to the clipboard, to pick the colors, to download the canvas and to reload the manifest.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn view_toolbar_ui_system(
+    mut contexts: EguiContexts,
+    mut app_settings: ResMut<AppSettings>,
+    app_state: Res<AppState>,
+    tiled_image: Option<Single<&TiledImage>>,
+    presentation: Option<Single<&Manifest>>,
+    mut camera_command_writer: MessageWriter<CameraCommand>,
+    mut view_clipboard: ResMut<ViewClipboard>,
+    mut egui_ui_state: ResMut<EguiUiState>,
+    mut manifest_reload: ResMut<ManifestReload>,
+    mut eyedropper: ResMut<Eyedropper>,
+) -> Result {
+    let Some(tiled_image) = tiled_image else {
+        return Ok(());
+    };
+
+    let ctx = contexts.ctx_mut()?;
+    let downloads = presentation
+        .map(|x| get_canvas_downloads(&x, &app_state, &tiled_image, &app_settings.language))
+        .unwrap_or_default();
+
+    if let Some(image) = view_clipboard.take_image() {
+        ctx.copy_image(image);
+        egui_ui_state
+            .toasts
+            .info("Copied the view to the clipboard.")
+            .duration(Duration::from_secs(3));
+    }
+
+    egui::Area::new(egui::Id::new("view_toolbar"))
+        .fixed_pos(ctx.available_rect().min + vec2(8.0, 8.0))
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    for fit_mode in FitMode::ALL {
+                        let response = ui
+                            .selectable_label(app_settings.fit_mode == fit_mode, fit_mode.icon())
+                            .on_hover_text(fit_mode.label());
+
+                        set_accessible_name(ui, &response, fit_mode.label());
+
+                        if response.clicked() {
+                            app_settings.fit_mode = fit_mode;
+                            camera_command_writer.write(CameraCommand::Fit(fit_mode));
+                        }
+                    }
+
+                    // The clipboard is not available on Android.
+                    if cfg!(not(target_os = "android")) {
+                        ui.separator();
+
+                        if add_icon_button(ui, "📋", "Copy the view to the clipboard").clicked() {
+                            view_clipboard.requested = true;
+                        }
+                    }
+
+                    let response = ui
+                        .selectable_label(eyedropper.enabled, "💧")
+                        .on_hover_text("Eyedropper (I)");
+
+                    set_accessible_name(ui, &response, "Eyedropper");
+
+                    if response.clicked() {
+                        eyedropper.enabled = !eyedropper.enabled;
+                    }
+
+                    // Downloaded by the browser.
+                    if !downloads.is_empty() {
+                        ui.separator();
+
+                        ui.menu_button("⬇", |ui| {
+                            for (label, url) in &downloads {
+                                ui.add(
+                                    egui::Hyperlink::from_label_and_url(label, url)
+                                        .open_in_new_tab(true),
+                                )
+                                .on_hover_text(url);
+                            }
+                        })
+                        .response
+                        .on_hover_text("Download");
+                    }
+
+                    ui.separator();
+
+                    let tooltip = match app_settings.reload_interval_secs {
+                        0 => "Reload the manifest".to_string(),
+                        secs => format!("Reload the manifest, which is reloaded every {} s", secs),
+                    };
+
+                    if add_icon_button(ui, "🔄", &tooltip).clicked() {
+                        manifest_reload.requested = true;
+                    }
+                });
+            });
+        });
+
+    Ok(())
+}
+
+/// Show the toolbar to view and inspect the 3D model over its top left corner, e.g. the preset
+/// views and the wireframe. Double-click on the model to focus on the point.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn model_toolbar_ui_system(
+    mut contexts: EguiContexts,
+    model_image_query: Query<(), With<ModelImage>>,
+    mut model_inspection: ResMut<ModelInspection>,
+    wireframe_config: Option<ResMut<WireframeConfig>>,
+    mut camera_command_writer: MessageWriter<CameraCommand3d>,
+    mut model_animations: ResMut<ModelAnimations>,
+    mut animation_player_query: Query<&mut AnimationPlayer>,
+) -> Result {
+    if model_image_query.is_empty() {
+        return Ok(());
+    }
+
+    let ctx = contexts.ctx_mut()?;
+
+    egui::Area::new(egui::Id::new("model_toolbar"))
+        .fixed_pos(ctx.available_rect().min + vec2(8.0, 8.0))
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    if add_icon_button(ui, "⟲", "Reset view").clicked() {
+                        camera_command_writer.write(CameraCommand3d::Reset);
+                    }
+
+                    for preset in ViewPreset::ALL {
+                        if ui
+                            .button(preset.label())
+                            .on_hover_text(format!("{} view", preset.label()))
+                            .clicked()
+                        {
+                            camera_command_writer.write(CameraCommand3d::Preset(preset));
+                        }
+                    }
+
+                    ui.separator();
+
+                    // Only available natively.
+                    if let Some(mut wireframe_config) = wireframe_config {
+                        let response = ui
+                            .selectable_label(wireframe_config.global, "▦")
+                            .on_hover_text("Wireframe");
+
+                        set_accessible_name(ui, &response, "Wireframe");
+
+                        if response.clicked() {
+                            wireframe_config.global = !wireframe_config.global;
+                        }
+                    }
+
+                    let response = ui
+                        .selectable_label(model_inspection.show_bounding_box, "⬚")
+                        .on_hover_text("Bounding box");
+
+                    set_accessible_name(ui, &response, "Bounding box");
+
+                    if response.clicked() {
+                        model_inspection.show_bounding_box = !model_inspection.show_bounding_box;
+                    }
+
+                    let response = ui
+                        .selectable_label(model_inspection.show_stats, "ℹ")
+                        .on_hover_text("Statistics");
+
+                    set_accessible_name(ui, &response, "Statistics");
+
+                    if response.clicked() {
+                        model_inspection.show_stats = !model_inspection.show_stats;
+                    }
+                });
+
+                if model_inspection.show_stats {
+                    match &model_inspection.stats {
+                        Some(stats) => {
+                            ui.label(format!("Meshes: {}", stats.meshes));
+                            ui.label(format!("Materials: {}", stats.materials));
+                            ui.label(format!("Triangles: {}", stats.triangles));
+
+                            if let Some(aabb) = &stats.aabb {
+                                let size = aabb.half_extents * 2.0;
+
+                                ui.label(format!(
+                                    "Size: {:.3} × {:.3} × {:.3}",
+                                    size.x, size.y, size.z
+                                ));
+                            }
+                        }
+                        None => {
+                            ui.label("Loading the model…");
+                        }
+                    }
+                }
+
+                add_animation_controls(ui, &mut model_animations, &mut animation_player_query);
+            });
+        });
+
+    Ok(())
+}
+
+/// Add the controls to select, play, pause and scrub the animations of the model if any.
+fn add_animation_controls(
+    ui: &mut egui::Ui,
+    model_animations: &mut ModelAnimations,
+    animation_player_query: &mut Query<&mut AnimationPlayer>,
+) {
+    let Some(animation) = model_animations.get_selected().cloned() else {
+        return;
+    };
+    let mut selected = model_animations.selected;
+    let playing = animation_player_query
+        .iter()
+        .any(|x| x.animation(animation.node).is_some_and(|x| !x.is_paused()));
+
+    ui.separator();
+
+    ui.horizontal(|ui| {
+        let (icon, name) = if playing {
+            ("⏸", "Pause")
+        } else {
+            ("▶", "Play")
+        };
+
+        if add_icon_button(ui, icon, name).clicked() {
+            for mut player in animation_player_query.iter_mut() {
+                match player.animation_mut(animation.node) {
+                    Some(active) if playing => {
+                        active.pause();
+                    }
+                    Some(active) => {
+                        active.resume();
+                    }
+                    None => {
+                        player.play(animation.node).repeat();
+                    }
+                }
+            }
+        }
+
+        if model_animations.items.len() > 1 {
+            egui::ComboBox::from_id_salt("ModelAnimation")
+                .selected_text(&animation.name)
+                .show_ui(ui, |ui| {
+                    for (index, item) in model_animations.items.iter().enumerate() {
+                        ui.selectable_value(&mut selected, index, &item.name);
+                    }
+                });
+        } else {
+            ui.label(&animation.name);
+        }
+    });
+
+    if selected != model_animations.selected {
+        model_animations.selected = selected;
+
+        for mut player in animation_player_query.iter_mut() {
+            player.stop_all();
+        }
+
+        return;
+    }
+
+    let mut seek_time = animation_player_query
+        .iter()
+        .find_map(|x| x.animation(animation.node).map(|x| x.seek_time()))
+        .unwrap_or_default();
+
+    if animation.duration > 0.0
+        && ui
+            .add(egui::Slider::new(&mut seek_time, 0.0..=animation.duration).suffix(" s"))
+            .changed()
+    {
+        for mut player in animation_player_query.iter_mut() {
+            let active = player.play(animation.node).repeat().seek_to(seek_time);
+
+            if !playing {
+                active.pause();
+            }
+        }
+    }
+
+    // Keep the animation and the slider moving in the desktop mode.
+    if playing {
+        ui.ctx().request_repaint();
+    }
+}
+
+/// Show a thin progress bar under the top panel while downloading the manifest or the image
+/// information, the thumbnail of the manifest until the first image is shown, and a spinner with
+/// the number of tiles loading.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn progress_ui_system(
+    mut contexts: EguiContexts,
+    app_state: Res<AppState>,
+    presentation: Option<Single<&Manifest>>,
+    tiled_image_query: Query<(), With<TiledImage>>,
+    model_image_query: Query<(), With<ModelImage>>,
+    tile_loading_query: Query<(), With<TileLoading>>,
+    mut watchdog: ResMut<DownloadWatchdog>,
+    mut redraw_governor: ResMut<RedrawGovernor>,
+) -> Result {
+    let ctx = contexts.ctx_mut()?;
+    let downloading = is_downloading(&app_state.manifest_json_download_state)
+        || is_downloading(&app_state.image_json_download_state)
+        || is_downloading(&app_state.overlay_json_download_state);
+    let num_tiles_loading = tile_loading_query.iter().count();
+    let viewport_rect = ctx.available_rect();
+
+    if downloading {
+        // Indeterminate as the size of the download is not known.
+        let painter = ctx.layer_painter(egui::LayerId::new(
+            egui::Order::Foreground,
+            egui::Id::new("progress_bar"),
+        ));
+        let width = viewport_rect.width();
+        let segment_width = width * PROGRESS_SEGMENT_FRACTION;
+        let t = (ctx.input(|i| i.time) / PROGRESS_CYCLE_SECS).fract() as f32;
+        let left = viewport_rect.left() - segment_width + t * (width + segment_width);
+        let bar_rect =
+            egui::Rect::from_min_size(viewport_rect.min, vec2(width, PROGRESS_BAR_HEIGHT));
+
+        painter.rect_filled(bar_rect, 0.0, ctx.style().visuals.extreme_bg_color);
+        painter.rect_filled(
+            egui::Rect::from_min_size(
+                egui::pos2(left, viewport_rect.top()),
+                vec2(segment_width, PROGRESS_BAR_HEIGHT),
+            )
+            .intersect(bar_rect),
+            0.0,
+            ctx.style().visuals.selection.bg_fill,
+        );
+    }
+
+    // Placeholder of the thumbnail of the whole manifest while the first canvas is loading.
+    if let Some(presentation) = presentation
+        && tiled_image_query.is_empty()
+        && model_image_query.is_empty()
+        && is_downloading(&app_state.image_json_download_state)
+        && let thumbnail = presentation.model().get_thumbnail()
+        && !thumbnail.is_empty()
+    {
+        egui::Area::new(egui::Id::new("loading_thumbnail"))
+            .fixed_pos(viewport_rect.center())
+            .pivot(egui::Align2::CENTER_CENTER)
+            .order(egui::Order::Background)
+            .interactable(false)
+            .show(ctx, |ui| {
+                ui.add(
+                    bevy_egui::egui::Image::new(&*thumbnail)
+                        .max_size(vec2(LOADING_THUMBNAIL_SIZE, LOADING_THUMBNAIL_SIZE)),
+                );
+            });
+    }
+
+    if num_tiles_loading > 0 {
+        egui::Area::new(egui::Id::new("tiles_loading"))
+            .fixed_pos(viewport_rect.right_bottom() - vec2(8.0, 8.0))
+            .pivot(egui::Align2::RIGHT_BOTTOM)
+            .interactable(false)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.add(egui::Spinner::new().size(12.0));
+                        ui.label(
+                            egui::RichText::new(if num_tiles_loading == 1 {
+                                "1 tile loading".to_string()
+                            } else {
+                                format!("{} tiles loading", num_tiles_loading)
+                            })
+                            .size(12.0)
+                            .color(Color32::GRAY),
+                        );
+                    });
+                });
+            });
+    }
+
+    // Let the user retry or cancel the download timed out after the retries.
+    let mut request = None;
+
+    if let Some((stalled, url)) = watchdog.get_stalled() {
+        let what = match stalled {
+            StalledDownload::Manifest => "The manifest",
+            StalledDownload::ImageInfo => "The image info",
+        };
+
+        egui::Area::new(egui::Id::new("stalled_download"))
+            .fixed_pos(viewport_rect.center_top() + vec2(0.0, 8.0 + PROGRESS_BAR_HEIGHT))
+            .pivot(egui::Align2::CENTER_TOP)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{} is taking too long.", what))
+                            .on_hover_text(url);
+
+                        if ui.button("Retry").clicked() {
+                            request = Some(WatchdogRequest::Retry);
+                        }
+
+                        if ui.button("Cancel").clicked() {
+                            request = Some(WatchdogRequest::Cancel);
+                        }
+                    });
+                });
+            });
+    }
+
+    if request.is_some() {
+        watchdog.request = request;
+    }
+
+    // Keep the progress indicators moving at the cadence of the loading.
+    if downloading || num_tiles_loading > 0 {
+        redraw_governor.report(RedrawActivity::Loading);
+    }
+
+    Ok(())
+}
+
+fn is_downloading<T>(download_state: &Mutex<DownloadState<T>>) -> bool {
+    download_state.lock().is_ok_and(|x| x.is_in_progress())
+}
+
+/// Show the position in the full size image under the pointer and the image API URL of the
+/// region in view over the bottom left corner, with a button to copy the URL, e.g. to cite it.
+pub(crate) fn region_info_ui_system(
+    mut contexts: EguiContexts,
+    tiled_image: Option<Single<&TiledImage>>,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera2d>>,
+) -> Result {
+    let Some(tiled_image) = tiled_image else {
+        return Ok(());
+    };
+
+    let ctx = contexts.ctx_mut()?;
+    let (camera, global_transform) = camera.into_inner();
+    let viewport_rect = ctx.available_rect();
+    let to_image = |screen_pos: egui::Pos2| {
+        camera
+            .viewport_to_world_2d(global_transform, Vec2::new(screen_pos.x, screen_pos.y))
+            .ok()
+            .map(|x| tiled_image.world_to_image(x.extend(0.0)))
+    };
+    let image_rect = tiled_image.get_image_max_size_rect();
+    let pointer_position = ctx
+        .input(|i| i.pointer.hover_pos())
+        .filter(|x| viewport_rect.contains(*x))
+        .and_then(to_image)
+        .filter(|x| image_rect.contains(*x));
+    let region_url = to_image(viewport_rect.min)
+        .zip(to_image(viewport_rect.max))
+        .and_then(|(min, max)| tiled_image.get_region_url(Rect::from_corners(min, max)));
+
+    egui::Area::new(egui::Id::new("region_info"))
+        .fixed_pos(viewport_rect.left_bottom() + vec2(8.0, -8.0))
+        .pivot(egui::Align2::LEFT_BOTTOM)
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    let position = pointer_position.map_or_else(
+                        || "–".to_string(),
+                        |x| format!("{}, {}", x.x as u32, x.y as u32),
+                    );
+
+                    ui.label(
+                        egui::RichText::new(position)
+                            .size(12.0)
+                            .color(Color32::GRAY),
+                    )
+                    .on_hover_text("Position in the full size image");
+
+                    if let Some(region_url) = region_url
+                        && add_icon_button(ui, "📋", "Copy the image URL of the region in view")
+                            .clicked()
+                    {
+                        ui.ctx().copy_text(region_url);
+                    }
+                });
+            });
+        });
+
+    Ok(())
+}
+
+/// Show the rulers in the pixels of the full size image along the top and the left of the view,
+/// with the ticks at a spacing readable at the zoom.
+pub(crate) fn rulers_ui_system(
+    mut contexts: EguiContexts,
+    app_settings: Res<AppSettings>,
+    tiled_image: Option<Single<&TiledImage>>,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera2d>>,
+) -> Result {
+    let Some(tiled_image) = tiled_image.filter(|_| app_settings.grid_settings.show_rulers) else {
+        return Ok(());
+    };
+
+    let ctx = contexts.ctx_mut()?;
+    let (camera, global_transform) = camera.into_inner();
+    let viewport_rect = ctx.available_rect();
+    let to_image = |screen_pos: egui::Pos2| {
+        camera
+            .viewport_to_world_2d(global_transform, Vec2::new(screen_pos.x, screen_pos.y))
+            .ok()
+            .map(|x| tiled_image.world_to_image(x.extend(0.0)))
+    };
+    let (Some(image_min), Some(image_max)) =
+        (to_image(viewport_rect.min), to_image(viewport_rect.max))
+    else {
+        return Ok(());
+    };
+    let image_size = image_max - image_min;
+
+    if image_size.x <= 0.0 || image_size.y <= 0.0 {
+        return Ok(());
+    }
+
+    // The image space is only scaled and moved from the screen.
+    let to_screen = |p: Vec2| {
+        viewport_rect.min
+            + vec2(
+                (p.x - image_min.x) / image_size.x * viewport_rect.width(),
+                (p.y - image_min.y) / image_size.y * viewport_rect.height(),
+            )
+    };
+    let spacing =
+        grid::get_ruler_spacing(RULER_TICK_SPACING * image_size.x / viewport_rect.width());
+    let image_rect = tiled_image
+        .get_image_max_size_rect()
+        .intersect(Rect::from_corners(image_min, image_max));
+    let painter = ctx.layer_painter(egui::LayerId::new(
+        egui::Order::Background,
+        egui::Id::new("rulers"),
+    ));
+    let fill = ctx.style().visuals.window_fill;
+    let stroke = egui::Stroke::new(1.0, ctx.style().visuals.text_color());
+    let font_id = FontId::monospace(10.0);
+    let max_ticks = (viewport_rect.width().max(viewport_rect.height()) / RULER_TICK_SPACING).ceil()
+        as usize
+        + 1;
+
+    painter.rect_filled(
+        egui::Rect::from_min_size(viewport_rect.min, vec2(viewport_rect.width(), RULER_WIDTH)),
+        0.0,
+        fill,
+    );
+    painter.rect_filled(
+        egui::Rect::from_min_size(viewport_rect.min, vec2(RULER_WIDTH, viewport_rect.height())),
+        0.0,
+        fill,
+    );
+
+    for x in grid::get_grid_positions(image_rect.min.x, image_rect.max.x, spacing, max_ticks) {
+        let screen_x = to_screen(Vec2::new(x, 0.0)).x;
+
+        painter.line_segment(
+            [
+                egui::pos2(screen_x, viewport_rect.top() + RULER_WIDTH / 2.0),
+                egui::pos2(screen_x, viewport_rect.top() + RULER_WIDTH),
+            ],
+            stroke,
+        );
+        painter.text(
+            egui::pos2(screen_x + 2.0, viewport_rect.top() + 1.0),
+            egui::Align2::LEFT_TOP,
+            x.to_string(),
+            font_id.clone(),
+            stroke.color,
+        );
+    }
+
+    for y in grid::get_grid_positions(image_rect.min.y, image_rect.max.y, spacing, max_ticks) {
+        let screen_y = to_screen(Vec2::new(0.0, y)).y;
+
+        painter.line_segment(
+            [
+                egui::pos2(viewport_rect.left() + RULER_WIDTH / 2.0, screen_y),
+                egui::pos2(viewport_rect.left() + RULER_WIDTH, screen_y),
+            ],
+            stroke,
+        );
+        painter.text(
+            egui::pos2(viewport_rect.left() + 1.0, screen_y + 1.0),
+            egui::Align2::LEFT_TOP,
+            y.to_string(),
+            font_id.clone(),
+            stroke.color,
+        );
+    }
+
+    Ok(())
+}
+
+/// Show a banner over the top of the view when the image stands in for the one of the image
+/// service which failed, as it may be at a lower quality and not zoom in further.
+pub(crate) fn fallback_banner_ui_system(
+    mut contexts: EguiContexts,
+    tiled_image: Option<Single<&TiledImage>>,
+) -> Result {
+    let Some(tiled_image) = tiled_image.filter(|x| x.is_fallback()) else {
+        return Ok(());
+    };
+
+    let ctx = contexts.ctx_mut()?;
+    let viewport_rect = ctx.available_rect();
+
+    egui::Area::new(egui::Id::new("fallback_banner"))
+        .fixed_pos(viewport_rect.center_top() + vec2(0.0, 8.0))
+        .pivot(egui::Align2::CENTER_TOP)
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.label(
+                    egui::RichText::new(
+                        "⚠ The image service is not available. A lower quality image is shown \
+                        instead.",
+                    )
+                    .color(ui.visuals().warn_fg_color),
+                )
+                .on_hover_text(tiled_image.get_endpoint());
+            });
+        });
+
+    Ok(())
+}
+
+/// Show the error of the manifest which cannot be shown in place of the empty viewport, with the
+/// sample manifests to open instead.
+pub(crate) fn error_page_ui_system(
+    mut contexts: EguiContexts,
+    mut error_page: ResMut<ManifestErrorPage>,
+    presentation: Option<Single<&Manifest>>,
+    mut app_state: ResMut<AppState>,
+    app_settings: Res<AppSettings>,
+    mut egui_ui_state: ResMut<EguiUiState>,
+) -> Result {
+    let (Some(error), None) = (&error_page.error, presentation) else {
+        return Ok(());
+    };
+
+    let ctx = contexts.ctx_mut()?;
+    let viewport_rect = ctx.available_rect();
+    let mut sample_url = None;
+
+    ctx.layer_painter(egui::LayerId::new(
+        egui::Order::Background,
+        egui::Id::new("error_page_background"),
+    ))
+    .rect_filled(viewport_rect, 0.0, ctx.style().visuals.extreme_bg_color);
+
+    egui::Area::new(egui::Id::new("error_page"))
+        .fixed_pos(viewport_rect.center())
+        .pivot(egui::Align2::CENTER_CENTER)
+        .order(egui::Order::Background)
+        .show(ctx, |ui| {
+            ui.set_max_width(ERROR_PAGE_WIDTH.min(viewport_rect.width() - 16.0));
+            ui.vertical_centered(|ui| {
+                ui.heading(
+                    egui::RichText::new("⚠ The manifest cannot be shown")
+                        .color(ui.visuals().warn_fg_color),
+                );
+                ui.label(error.summary());
+
+                if let Some(hint) = error.hint() {
+                    ui.label(egui::RichText::new(hint).weak());
+                }
+
+                let details = error.details();
+
+                if !details.is_empty() {
+                    ui.collapsing("Details", |ui| {
+                        ui.add(
+                            egui::Label::new(egui::RichText::new(details).monospace().small())
+                                .wrap(),
+                        );
+                    });
+                }
+
+                ui.add_space(16.0);
+                ui.label("Open a sample manifest to get started:");
+
+                for sample in &SAMPLE_MANIFESTS {
+                    if ui.link(sample.label).on_hover_text(sample.url).clicked() {
+                        sample_url = Some(sample.url);
+                    }
+                }
+            });
+        });
+
+    if let Some(url) = sample_url {
+        crate::web::load_presentation(&mut app_state, &app_settings, url);
+        egui_ui_state.presentation_url = url.to_string();
+        error_page.clear();
+    }
+
+    Ok(())
+}
+
+/// Show the IIIF URL copied to the clipboard with the buttons to open or dismiss it.
+#[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+pub(crate) fn clipboard_offer_ui_system(
+    mut contexts: EguiContexts,
+    mut clipboard_watch: ResMut<ClipboardWatch>,
+    mut app_state: ResMut<AppState>,
+    app_settings: Res<AppSettings>,
+    mut egui_ui_state: ResMut<EguiUiState>,
+) -> Result {
+    let Some(url) = clipboard_watch.offered_url.clone() else {
+        return Ok(());
+    };
+
+    let ctx = contexts.ctx_mut()?;
+    let viewport_rect = ctx.available_rect();
+    let mut close = false;
+
+    egui::Area::new(egui::Id::new("clipboard_offer"))
+        .fixed_pos(viewport_rect.right_bottom() - vec2(8.0, 8.0))
+        .pivot(egui::Align2::RIGHT_BOTTOM)
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.label("Open the IIIF URL copied to the clipboard?");
+                ui.add(egui::Label::new(egui::RichText::new(&url).small()).truncate())
+                    .on_hover_text(&url);
+                ui.horizontal(|ui| {
+                    if ui.button("Open").clicked() {
+                        crate::web::load_presentation(&mut app_state, &app_settings, &url);
+                        egui_ui_state.presentation_url = url.to_string();
+                        close = true;
+                    }
+                    if ui.button("Dismiss").clicked() {
+                        close = true;
+                    }
+                });
+            });
+        });
+
+    if close {
+        clipboard_watch.offered_url = None;
+    }
+
+    Ok(())
+}
+
+/// Draw the magnifier lens at the cursor over the image in the low-vision mode, from the tiles
+/// of the level for the magnified zoom clipped to the circle of the lens.
+pub(crate) fn magnifier_ui_system(
+    mut contexts: EguiContexts,
+    app_settings: Res<AppSettings>,
+    mut magnifier: ResMut<Magnifier>,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera2d>>,
+    asset_server: Res<AssetServer>,
+) -> Result {
+    let (camera, global_transform) = camera.into_inner();
+    // Textures of the loaded tiles by their indices.
+    let textures = magnifier
+        .tiles
+        .iter()
+        .enumerate()
+        .filter(|(_, x)| {
+            matches!(
+                asset_server.get_load_state(x.image.id()),
+                Some(LoadState::Loaded)
+            )
+        })
+        .map(|(i, x)| (i, contexts.add_image(EguiTextureHandle::Weak(x.image.id()))))
+        .collect::<Vec<_>>();
+    let ctx = contexts.ctx_mut()?;
+    let low_vision_settings = app_settings.low_vision_settings;
+
+    // The lens follows the cursor over the image, not over the panels.
+    let cursor = ctx.pointer_hover_pos().filter(|_| {
+        low_vision_settings.enabled && low_vision_settings.magnifier && !ctx.is_pointer_over_area()
+    });
+    let world_centre = cursor.and_then(|x| {
+        camera
+            .viewport_to_world_2d(global_transform, Vec2::new(x.x, x.y))
+            .ok()
+    });
+
+    if magnifier.world_centre != world_centre {
+        magnifier.world_centre = world_centre;
+    }
+
+    let Some(cursor) = cursor.filter(|_| world_centre.is_some()) else {
+        return Ok(());
+    };
+
+    let centre = Vec2::new(cursor.x, cursor.y);
+    let magnification = low_vision_settings.magnification.clamp(
+        *magnifier::MAGNIFICATION_RANGE.start(),
+        *magnifier::MAGNIFICATION_RANGE.end(),
+    );
+    let circle = magnifier::get_circle_points(centre, magnifier::LENS_RADIUS, LENS_SEGMENTS);
+    let painter = ctx.layer_painter(egui::LayerId::new(
+        egui::Order::Foreground,
+        egui::Id::new("magnifier"),
+    ));
+
+    painter.circle_filled(
+        cursor,
+        magnifier::LENS_RADIUS,
+        ctx.style().visuals.extreme_bg_color,
+    );
+
+    for (i, texture_id) in textures {
+        let tile = &magnifier.tiles[i];
+        let (Ok(p0), Ok(p1)) = (
+            camera.world_to_viewport(global_transform, tile.world_position.min.extend(0.0)),
+            camera.world_to_viewport(global_transform, tile.world_position.max.extend(0.0)),
+        ) else {
+            continue;
+        };
+        // The tile on the screen magnified about the cursor.
+        let screen_rect = Rect::from_corners(
+            centre + (p0 - centre) * magnification,
+            centre + (p1 - centre) * magnification,
+        );
+        let polygon = magnifier::clip_polygon_to_rect(&circle, screen_rect);
+
+        if polygon.len() < 3 {
+            continue;
+        }
+
+        let mut mesh = egui::Mesh::with_texture(texture_id);
+
+        for point in &polygon {
+            let uv = tile
+                .uv_transform
+                .transform_point2((*point - screen_rect.min) / screen_rect.size());
+
+            mesh.vertices.push(egui::epaint::Vertex {
+                pos: egui::pos2(point.x, point.y),
+                uv: egui::pos2(uv.x, uv.y),
+                color: Color32::WHITE,
+            });
+        }
+        for i in 1..polygon.len() as u32 - 1 {
+            mesh.add_triangle(0, i, i + 1);
+        }
+
+        painter.add(mesh);
+    }
+
+    painter.circle_stroke(
+        cursor,
+        magnifier::LENS_RADIUS,
+        egui::Stroke::new(3.0, ctx.style().visuals.text_color()),
+    );
+
+    Ok(())
+}
+
+/// Get the color of the sample for the swatch.
+fn get_swatch_color(sample: &PixelSample) -> Color32 {
+    let [r, g, b, a] = sample.color;
+
+    Color32::from_rgba_unmultiplied(r, g, b, a)
+}
+
+/// Add the swatch of the color of the sample, clicked to copy its hex.
+fn add_swatch(ui: &mut egui::Ui, sample: &PixelSample, size: f32) -> egui::Response {
+    let (rect, response) = ui.allocate_exact_size(vec2(size, size), egui::Sense::click());
+
+    ui.painter()
+        .rect_filled(rect, 2.0, get_swatch_color(sample));
+    ui.painter().rect_stroke(
+        rect,
+        2.0,
+        egui::Stroke::new(1.0, ui.visuals().text_color()),
+        egui::StrokeKind::Inside,
+    );
+
+    if response.clicked() {
+        ui.ctx().copy_text(sample.get_hex());
+    }
+
+    response.on_hover_text(format!(
+        "{} at {}, {}. Click to copy.",
+        sample.get_hex(),
+        sample.position.x,
+        sample.position.y
+    ))
+}
+
+/// Show the color and the position of the pixel under the cursor over the image while the
+/// eyedropper is on, above the region info, with the colors picked by the clicks.
+pub(crate) fn eyedropper_ui_system(
+    mut contexts: EguiContexts,
+    mut eyedropper: ResMut<Eyedropper>,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera2d>>,
+) -> Result {
+    let ctx = contexts.ctx_mut()?;
+    let (camera, global_transform) = camera.into_inner();
+
+    // Only over the image, not over the panels.
+    let cursor = ctx
+        .pointer_hover_pos()
+        .filter(|_| eyedropper.enabled && !ctx.is_pointer_over_area());
+    let world_position = cursor.and_then(|x| {
+        camera
+            .viewport_to_world_2d(global_transform, Vec2::new(x.x, x.y))
+            .ok()
+    });
+
+    if eyedropper.world_position != world_position {
+        eyedropper.world_position = world_position;
+    }
+
+    if !eyedropper.enabled {
+        return Ok(());
+    }
+
+    if cursor.is_some() && ctx.input(|i| i.pointer.primary_clicked()) {
+        eyedropper.pick();
+    }
+
+    let viewport_rect = ctx.available_rect();
+    let mut removed = None;
+
+    egui::Area::new(egui::Id::new("eyedropper"))
+        .fixed_pos(viewport_rect.left_bottom() + vec2(8.0, -48.0))
+        .pivot(egui::Align2::LEFT_BOTTOM)
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                match &eyedropper.sample {
+                    Some(sample) => {
+                        ui.horizontal(|ui| {
+                            add_swatch(ui, sample, 24.0);
+
+                            let [r, g, b, a] = sample.color;
+
+                            ui.vertical(|ui| {
+                                ui.label(format!("R {} G {} B {} A {}", r, g, b, a));
+                                ui.label(
+                                    egui::RichText::new(format!(
+                                        "{} at {}, {}",
+                                        sample.get_hex(),
+                                        sample.position.x,
+                                        sample.position.y
+                                    ))
+                                    .size(12.0)
+                                    .color(Color32::GRAY),
+                                );
+                            });
+                        });
+
+                        // Read from a lower-res level, e.g. until the full size tiles load.
+                        if sample.scale > 1.5 {
+                            ui.label(
+                                egui::RichText::new(format!(
+                                    "Sampled at 1:{:.0} of the full size",
+                                    sample.scale
+                                ))
+                                .size(12.0)
+                                .color(Color32::GRAY),
+                            );
+                        }
+                    }
+                    None => {
+                        ui.label("Point at the image to read its color. Click to pick.");
+                    }
+                }
+
+                if !eyedropper.history.is_empty() {
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        for (i, sample) in eyedropper.history.iter().enumerate() {
+                            if add_swatch(ui, sample, 16.0).secondary_clicked() {
+                                removed = Some(i);
+                            }
+                        }
+                    })
+                    .response
+                    .on_hover_text("Colors picked. Right-click to remove.");
+                }
+            });
+        });
+
+    if let Some(i) = removed {
+        eyedropper.history.remove(i);
+    }
+
+    Ok(())
+}
+
+/// Run the actions bound to the keys pressed. The keys as typed are left to the widget with the
+/// keyboard focus, e.g. a text field, and matched with their exact modifiers, e.g. F without Shift.
+pub(crate) fn action_shortcut_ui_system(
+    mut contexts: EguiContexts,
+    mut action_writer: MessageWriter<ViewerAction>,
+    mut redraw_request_writer: MessageWriter<RequestRedraw>,
+) -> Result {
+    let ctx = contexts.ctx_mut()?;
+    let wants_keyboard_input = ctx.wants_keyboard_input();
+
+    for entry in ACTIONS.iter().filter(|x| x.action.is_supported()) {
+        let Some(shortcut) = entry.shortcut else {
+            continue;
+        };
+        let is_typed = is_typed_shortcut(&shortcut);
+
+        if is_typed && wants_keyboard_input {
+            continue;
+        }
+
+        if ctx.input_mut(|i| {
+            if is_typed {
+                i.consume_key(shortcut.modifiers, shortcut.logical_key)
+            } else {
+                i.consume_shortcut(&shortcut)
+            }
+        }) {
+            action_writer.write(entry.action);
+            redraw_request_writer.write(RequestRedraw);
+        }
+    }
+
+    Ok(())
+}
+
+/// Show the command palette to find the actions by their names, chosen by the arrow keys and run
+/// by Enter.
+pub(crate) fn command_palette_ui_system(
+    mut contexts: EguiContexts,
+    mut command_palette: ResMut<CommandPalette>,
+    mut action_writer: MessageWriter<ViewerAction>,
+) -> Result {
+    if !command_palette.open {
+        return Ok(());
+    }
+
+    let ctx = contexts.ctx_mut()?;
+    let matches = command_palette.get_matches();
+    let (up, down, enter, escape) = ctx.input_mut(|i| {
+        (
+            i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+            i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+            i.consume_key(egui::Modifiers::NONE, egui::Key::Enter),
+            i.consume_key(egui::Modifiers::NONE, egui::Key::Escape),
+        )
+    });
+    let last = matches.len().saturating_sub(1);
+    let mut selected = command_palette.selected.min(last);
+
+    if up {
+        selected = selected.saturating_sub(1);
+    } else if down {
+        selected = (selected + 1).min(last);
+    }
+
+    let mut chosen = enter.then(|| matches.get(selected)).flatten();
+
+    egui::Window::new("Command palette")
+        .id(egui::Id::new("command_palette_window"))
+        .title_bar(false)
+        .collapsible(false)
+        .resizable(false)
+        .default_width(COMMAND_PALETTE_WIDTH)
+        .anchor(egui::Align2::CENTER_TOP, vec2(0.0, 60.0))
+        .show(ctx, |ui| {
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut command_palette.query)
+                    .hint_text("Type a command")
+                    .desired_width(f32::INFINITY),
+            );
+
+            response.request_focus();
+            set_accessible_name(ui, &response, "Command");
+
+            if response.changed() {
+                selected = 0;
+            }
+
+            ui.separator();
+
+            egui::ScrollArea::vertical()
+                .max_height(300.0)
+                .show(ui, |ui| {
+                    if matches.is_empty() {
+                        ui.weak("No matching commands");
+                    }
+
+                    for (index, entry) in matches.iter().enumerate() {
+                        let shortcut = entry
+                            .shortcut
+                            .map(|x| ui.ctx().format_shortcut(&x))
+                            .unwrap_or_default();
+                        let response = Button::new(entry.name)
+                            .shortcut_text(shortcut)
+                            .selected(index == selected)
+                            .frame(index == selected)
+                            .min_size(vec2(ui.available_width(), 0.0))
+                            .ui(ui);
+
+                        if index == selected && (up || down) {
+                            response.scroll_to_me(None);
+                        }
+                        if response.clicked() {
+                            chosen = Some(entry);
+                        }
+                    }
+                });
+        });
+
+    command_palette.selected = selected;
+
+    if let Some(entry) = chosen {
+        action_writer.write(entry.action);
+    }
+    if chosen.is_some() || escape {
+        command_palette.open = false;
+    }
+
+    Ok(())
+}
+
+/// Zoom to the rectangle dragged over the image while holding Shift.
+pub(crate) fn zoom_selection_ui_system(
+    mut contexts: EguiContexts,
+    tiled_image: Option<Single<&TiledImage>>,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera2d>>,
+    mut drag: Local<Option<(egui::Pos2, egui::Pos2)>>,
+    mut camera_command_writer: MessageWriter<CameraCommand>,
+) -> Result {
+    let ctx = contexts.ctx_mut()?;
+    let Some(tiled_image) = tiled_image else {
+        *drag = None;
+        return Ok(());
+    };
+
+    // Keep the rubber band even if Shift is released while dragging, but not typing with Shift.
+    if drag.is_none() && (!ctx.input(|i| i.modifiers.shift) || ctx.wants_keyboard_input()) {
+        return Ok(());
+    }
+
+    let (camera, global_transform) = camera.into_inner();
+    let viewport_rect = ctx.available_rect();
+    let to_image = |screen_pos: egui::Pos2| {
+        camera
+            .viewport_to_world_2d(global_transform, Vec2::new(screen_pos.x, screen_pos.y))
+            .ok()
+            .map(|x| tiled_image.world_to_image(x.extend(0.0)))
+    };
+    let mut selection = None;
+
+    egui::Area::new(egui::Id::new("zoom_selection"))
+        .fixed_pos(viewport_rect.min)
+        .show(ctx, |ui| {
+            let response = ui.allocate_response(viewport_rect.size(), Sense::drag());
+
+            ctx.set_cursor_icon(egui::CursorIcon::ZoomIn);
+
+            if response.drag_started() {
+                *drag = response.interact_pointer_pos().map(|x| (x, x));
+            } else if response.dragged()
+                && let Some(pointer_pos) = response.interact_pointer_pos()
+                && let Some((_, end)) = drag.as_mut()
+            {
+                *end = pointer_pos;
+            }
+
+            if response.drag_stopped() {
+                selection = drag.take();
+            }
+        });
+
+    if let Some((start, end)) = *drag {
+        ctx.layer_painter(egui::LayerId::background())
+            .with_clip_rect(viewport_rect)
+            .rect(
+                egui::Rect::from_two_pos(start, end),
+                0.0,
+                ZOOM_SELECTION_COLOR.gamma_multiply(0.2),
+                egui::Stroke::new(1.0, ZOOM_SELECTION_COLOR),
+                egui::StrokeKind::Inside,
+            );
+    }
+
+    if let Some((start, end)) = selection
+        && egui::Rect::from_two_pos(start, end).size().min_elem() >= MIN_ZOOM_SELECTION_SIZE
+        && let (Some(start), Some(end)) = (to_image(start), to_image(end))
+    {
+        camera_command_writer.write(CameraCommand::ZoomToImageRect(Rect::from_corners(
+            start, end,
+        )));
+    }
+
+    Ok(())
+}
+
+/// Add controls to change pages, by the number or a part of the label of the page.
+#[allow(clippy::too_many_arguments)]
+fn add_page_controls(
+    egui_ui_state: &mut ResMut<'_, EguiUiState>,
+    app_state: &mut ResMut<'_, AppState>,
+    app_settings: &AppSettings,
+    presentation_query: Query<'_, '_, (Entity, &Manifest)>,
+    ui: &mut egui::Ui,
+    num_canvases: usize,
+    commands: &mut Commands,
+    model_image_query: &Query<Entity, With<ModelImage>>,
+) {
+    ui.spacing_mut().item_spacing.x = 1.0;
+
+    let mut new_canvas_index = app_state.canvas_index;
+
+    let previous = ui.button("<");
+    set_accessible_name(ui, &previous, "Previous page");
+    if previous.clicked() {
+        new_canvas_index = app_state.canvas_index.saturating_sub(1);
+    }
+
+    let response = ui
+        .add(egui::TextEdit::singleline(&mut egui_ui_state.canvas_index).desired_width(30.0))
+        .on_hover_text(format!(
+            "Page {}/{}, or a part of the label of the page, e.g. 12r",
+            app_state.canvas_index.saturating_add(1),
+            num_canvases
+        ));
+    set_accessible_name(
+        ui,
+        &response,
+        &format!(
+            "Page {} of {}",
+            app_state.canvas_index.saturating_add(1),
+            num_canvases
+        ),
+    );
+
+    // The page number, or the labels of the pages containing the text.
+    let page_number = egui_ui_state
+        .canvas_index
+        .trim()
+        .parse::<usize>()
+        .ok()
+        .filter(|x| *x > 0 && *x <= num_canvases);
+
+    if response.changed() {
+        egui_ui_state.canvas_label_matches = match page_number {
+            Some(_) => Vec::new(),
+            None => presentation_query
+                .iter()
+                .next()
+                .map(|(_, manifest)| {
+                    manifest.find_canvases_by_label(
+                        app_state.sequence_index,
+                        &egui_ui_state.canvas_index,
+                        &app_settings.language,
+                    )
+                })
+                .unwrap_or_default(),
+        };
+    }
+
+    if response.lost_focus() {
+        let entered = ui.input(|i| i.key_pressed(egui::Key::Enter));
+
+        if let Some(page_number) = page_number {
+            new_canvas_index = page_number - 1;
+        } else if entered && let Some((index, _)) = egui_ui_state.canvas_label_matches.first() {
+            new_canvas_index = *index;
+        }
+
+        // Keep the matches to choose from unless it is entered.
+        if page_number.is_some() || entered {
+            egui_ui_state.canvas_label_matches.clear();
+        }
+        egui_ui_state.canvas_index = app_state.canvas_index.saturating_add(1).to_string();
+    }
+
+    if let Some(index) = add_canvas_label_matches(ui, &response, egui_ui_state) {
+        new_canvas_index = index;
+    }
+
+    let next = ui.button(">");
+    set_accessible_name(ui, &next, "Next page");
+    egui_ui_state.hovering_next_page = next.hovered();
+    if next.clicked() {
+        new_canvas_index = (app_state.canvas_index.saturating_add(1)).min(num_canvases - 1);
+    }
+
+    if new_canvas_index != app_state.canvas_index {
+        let (_, manifest) = presentation_query
+            .iter()
+            .next()
+            .expect("should have a manifest due to previous check on the number of canvas > 1");
+
+        if let Err(err) = crate::web::load_canvas(
+            commands,
+            manifest,
+            app_state,
+            app_settings,
+            app_state.sequence_index,
+            new_canvas_index,
+            0,
+            model_image_query,
+        ) {
+            let msg = format!("Unable to load canvas.\n'{}'", err);
+
+            egui_ui_state
+                .toasts
+                .warning(msg)
+                .show_progress_bar(true)
+                .duration(Duration::from_secs(5));
+        }
+    }
+}
+
+/// Add the dropdown of the canvases with the labels matching the text in the page field, and get
+/// the index of the canvas chosen.
+fn add_canvas_label_matches(
+    ui: &egui::Ui,
+    response: &egui::Response,
+    egui_ui_state: &mut ResMut<'_, EguiUiState>,
+) -> Option<usize> {
+    if egui_ui_state.canvas_label_matches.is_empty() {
+        return None;
+    }
+
+    let mut chosen = None;
+    let area_response = egui::Area::new(egui::Id::new("canvas_label_matches"))
+        .order(egui::Order::Foreground)
+        .fixed_pos(response.rect.left_bottom())
+        .show(ui.ctx(), |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                for (index, label) in &egui_ui_state.canvas_label_matches {
+                    if ui
+                        .selectable_label(false, format!("{}: {}", index + 1, label))
+                        .clicked()
+                    {
+                        chosen = Some(*index);
+                    }
+                }
+            });
+        })
+        .response;
+
+    // Close the dropdown when a page is chosen, or on a click elsewhere.
+    let clicked_elsewhere = ui.input(|i| i.pointer.any_click())
+        && !response.has_focus()
+        && !area_response.contains_pointer();
+
+    if chosen.is_some() || clicked_elsewhere {
+        egui_ui_state.canvas_label_matches.clear();
+    }
+
+    chosen
+}
+
+/// Get the labels and the URLs of the derivatives of the current canvas to download: the full
+/// size image from the image service, bounded by its max size, the renderings of the canvas and
+/// the manifest, e.g. a PDF, and the thumbnail.
+fn get_canvas_downloads(
+    manifest: &Manifest,
+    app_state: &AppState,
+    tiled_image: &TiledImage,
+    language: &str,
+) -> Vec<(String, String)> {
+    let mut downloads = Vec::new();
+
+    if let Some(url) = tiled_image.get_region_url(tiled_image.get_image_max_size_rect()) {
+        downloads.push(("Full size image".to_string(), url));
+    }
+
+    let Ok(canvas) = manifest
+        .model()
+        .get_sequence(app_state.sequence_index)
+        .and_then(|x| x.get_canvas(app_state.canvas_index))
+    else {
+        return downloads;
+    };
+
+    for rendering in canvas
+        .get_renderings(language)
+        .into_iter()
+        .chain(manifest.model().get_renderings(language))
+    {
+        let label = if rendering.label.is_empty() {
+            rendering
+                .url
+                .rsplit('/')
+                .next()
+                .unwrap_or(&rendering.url)
+                .to_string()
+        } else {
+            rendering.label
+        };
+        let label = if rendering.format.is_empty() {
+            label
+        } else {
+            format!("{} ({})", label, rendering.format)
+        };
+
+        downloads.push((label, rendering.url));
+    }
+
+    let thumbnail = canvas.get_thumbnail();
+
+    if !thumbnail.is_empty() {
+        downloads.push(("Thumbnail".to_string(), thumbnail.to_string()));
+    }
+
+    downloads
+}
+
+/// Get the labels of the image choices in the canvas.
+fn get_image_choice_labels(
+    manifest: &Manifest,
+    sequence_index: usize,
+    canvas_index: usize,
+    language: &str,
+) -> Vec<String> {
+    let Ok(choices) = manifest
+        .model()
+        .get_sequence(sequence_index)
+        .and_then(|x| x.get_canvas(canvas_index))
+        .map(crate::web::get_displayed_canvas)
+        .and_then(|x| x.get_image_choices(0))
+    else {
+        return Vec::new();
+    };
+
+    choices
+        .enumerate()
+        .map(|(index, choice)| {
+            let label = choice.get_label(language).collect::<Vec<_>>().join(",");
+
+            if label.is_empty() {
+                format!("Choice {}", index + 1)
+            } else {
+                label
+            }
+        })
+        .collect()
+}
+
+/// Add the selector to switch between the image choices of the current canvas.
+fn add_choice_selector(
+    egui_ui_state: &mut ResMut<'_, EguiUiState>,
+    app_state: &mut ResMut<'_, AppState>,
+    app_settings: &AppSettings,
+    presentation_query: Query<'_, '_, (Entity, &Manifest)>,
+    ui: &mut egui::Ui,
+    choice_labels: &[String],
+    commands: &mut Commands,
+    model_image_query: &Query<Entity, With<ModelImage>>,
+) {
+    let mut new_choice_index = app_state.choice_index;
+
+    egui::ComboBox::from_id_salt("Choices")
+        .width(CHOICE_SELECTOR_WIDTH)
+        .selected_text(
+            choice_labels
+                .get(app_state.choice_index)
+                .map_or("", |x| x.as_str()),
+        )
+        .show_ui(ui, |ui| {
+            for (index, label) in choice_labels.iter().enumerate() {
+                ui.selectable_value(&mut new_choice_index, index, label);
+            }
+        });
+
+    if new_choice_index != app_state.choice_index {
+        let (_, manifest) = presentation_query
+            .iter()
+            .next()
+            .expect("should have a manifest due to previous check on the number of choices > 1");
+        let sequence_index = app_state.sequence_index;
+        let canvas_index = app_state.canvas_index;
+
+        if let Err(err) = crate::web::load_canvas(
+            commands,
+            manifest,
+            app_state,
+            app_settings,
+            sequence_index,
+            canvas_index,
+            new_choice_index,
+            model_image_query,
+        ) {
+            let msg = format!("Unable to load image choice.\n'{}'", err);
+
+            egui_ui_state
+                .toasts
+                .warning(msg)
+                .show_progress_bar(true)
+                .duration(Duration::from_secs(5));
+        }
+    }
+}
+
+/// Add the controls to draw another image choice of the current canvas over the current image.
+#[allow(clippy::too_many_arguments)]
+fn add_overlay_controls(
+    ui: &mut egui::Ui,
+    egui_ui_state: &mut ResMut<'_, EguiUiState>,
+    app_state: &mut ResMut<'_, AppState>,
+    app_settings: &AppSettings,
+    presentation: &Manifest,
+    choice_labels: &[String],
+    commands: &mut Commands,
+    overlay_image_query: &Query<Entity, With<OverlayImage>>,
+    tile_mod_state: &mut ResMut<'_, TileModState>,
+) {
+    let mut new_overlay_choice_index = app_state.overlay_choice_index;
+
+    ui.horizontal(|ui| {
+        ui.label("Overlay");
+        egui::ComboBox::from_id_salt("Overlay")
+            .width(CHOICE_SELECTOR_WIDTH)
+            .selected_text(
+                new_overlay_choice_index
+                    .and_then(|index| choice_labels.get(index))
+                    .map_or("None", |x| x.as_str()),
+            )
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut new_overlay_choice_index, None, "None");
+                for (index, label) in choice_labels.iter().enumerate() {
+                    ui.selectable_value(&mut new_overlay_choice_index, Some(index), label);
+                }
+            });
+    });
+
+    if app_state.overlay_choice_index.is_some() {
+        let mut comparison = app_state.comparison;
+
+        ui.horizontal(|ui| {
+            ui.label("Compare");
+            ui.selectable_value(&mut comparison.mode, ComparisonMode::Off, "Off");
+            ui.selectable_value(&mut comparison.mode, ComparisonMode::Blink, "Blink")
+                .on_hover_text("Show and hide the overlay in turn");
+            ui.selectable_value(
+                &mut comparison.mode,
+                ComparisonMode::Difference,
+                "Difference",
+            )
+            .on_hover_text("Show the difference of the colors of the images");
+        });
+
+        match comparison.mode {
+            ComparisonMode::Off => {}
+            ComparisonMode::Blink => {
+                ui.add(
+                    egui::Slider::new(&mut comparison.blink_rate, 0.5..=10.0)
+                        .text("Blinks per second"),
+                );
+            }
+            ComparisonMode::Difference => {
+                ui.add(
+                    egui::Slider::new(&mut comparison.difference_gain, 1.0..=16.0)
+                        .logarithmic(true)
+                        .text("Gain"),
+                );
+            }
+        }
+
+        if comparison != app_state.comparison {
+            app_state.comparison = comparison;
+            tile_mod_state.invalidate();
+        }
+    }
+
+    // The curtain and the opacity do not apply to the comparison.
+    if app_state.overlay_choice_index.is_some() && app_state.comparison.mode == ComparisonMode::Off
+    {
+        let mut curtain = app_state.curtain_position.is_some();
+
+        if ui.checkbox(&mut curtain, "Curtain").changed() {
+            app_state.curtain_position = curtain.then_some(0.5);
+            tile_mod_state.invalidate();
+        }
+
+        if !curtain {
+            let mut opacity = app_state.overlay_opacity;
+
+            if ui
+                .add(egui::Slider::new(&mut opacity, 0.0..=1.0).text("Opacity"))
+                .changed()
+            {
+                app_state.overlay_opacity = opacity;
+                tile_mod_state.invalidate();
+            }
+        }
+    }
+    if new_overlay_choice_index == app_state.overlay_choice_index {
+        return;
+    }
+
+    match new_overlay_choice_index {
+        Some(choice_index) => {
+            if let Err(err) =
+                crate::web::load_overlay(presentation, app_state, app_settings, choice_index)
+            {
+                let msg = format!("Unable to load overlay image.\n'{}'", err);
+
+                egui_ui_state
+                    .toasts
+                    .warning(msg)
+                    .show_progress_bar(true)
+                    .duration(Duration::from_secs(5));
+            }
+        }
+        None => {
+            app_state.overlay_choice_index = None;
+            app_state.curtain_position = None;
+            *app_state
+                .overlay_json_download_state
+                .lock()
+                .expect("should be able to lock the overlay download state mutex") =
+                DownloadState::None;
+
+            for overlay_entity in overlay_image_query {
+                commands.entity(overlay_entity).despawn();
+            }
+        }
+    }
+}
+
+/// Add the draggable divider of the curtain between the current image and the overlay.
+fn add_curtain_divider(
+    ctx: &egui::Context,
+    app_state: &mut ResMut<'_, AppState>,
+    viewport_rect: egui::Rect,
+    tile_mod_state: &mut ResMut<'_, TileModState>,
+) {
+    let Some(curtain_position) = app_state
+        .curtain_position
+        .filter(|_| app_state.comparison.mode == ComparisonMode::Off)
+    else {
+        return;
+    };
+
+    let x = viewport_rect.left() + viewport_rect.width() * curtain_position;
+    let handle_rect = egui::Rect::from_x_y_ranges(
+        (x - CURTAIN_HANDLE_WIDTH / 2.0)..=(x + CURTAIN_HANDLE_WIDTH / 2.0),
+        viewport_rect.y_range(),
+    );
+
+    egui::Area::new(egui::Id::new("curtain_divider"))
+        .fixed_pos(handle_rect.min)
+        .show(ctx, |ui| {
+            let response = ui.allocate_response(handle_rect.size(), Sense::drag());
+
+            ui.painter().vline(
+                x,
+                viewport_rect.y_range(),
+                egui::Stroke::new(2.0, Color32::WHITE),
+            );
+
+            if response.hovered() || response.dragged() {
+                ctx.set_cursor_icon(egui::CursorIcon::ResizeHorizontal);
+            }
+
+            if response.dragged()
+                && let Some(pointer_pos) = response.interact_pointer_pos()
+            {
+                app_state.curtain_position = Some(
+                    ((pointer_pos.x - viewport_rect.left()) / viewport_rect.width())
+                        .clamp(0.0, 1.0),
+                );
+                tile_mod_state.invalidate();
+            }
+        });
+}
+
+/// Add the controls to open the manifest in the address bar in another window.
+fn add_new_window_controls(
+    ui: &mut egui::Ui,
+    egui_ui_state: &mut ResMut<'_, EguiUiState>,
+    open_window_writer: &mut MessageWriter<OpenWindow>,
+) {
+    ui.horizontal(|ui| {
+        if ui
+            .button("New window")
+            .on_hover_text("Open the manifest in the address bar in another window")
+            .clicked()
+        {
+            open_window_writer.write(OpenWindow {
+                manifest_url: egui_ui_state.presentation_url.to_string(),
+                follow: egui_ui_state.follow_new_window,
+            });
+        }
+        ui.checkbox(&mut egui_ui_state.follow_new_window, "Sync camera");
+    });
+}
+
+/// Add the rights statement or licence, with the badge and the short name if it is recognised,
+/// e.g. a Creative Commons licence, and linked if it is a URL.
+fn add_rights_statement(ui: &mut egui::Ui, licence: &str) {
+    let is_url = licence.starts_with("http://") || licence.starts_with("https://");
+
+    ui.horizontal_wrapped(|ui| match RightsStatement::try_from_uri(licence) {
+        Some(statement) => {
+            egui::Image::new(statement.badge.image())
+                .fit_to_exact_size(vec2(RIGHTS_BADGE_SIZE, RIGHTS_BADGE_SIZE))
+                .alt_text(&statement.name)
+                .ui(ui);
+            ui.add(
+                egui::Hyperlink::from_label_and_url(&statement.name, licence).open_in_new_tab(true),
+            )
+            .on_hover_text(licence);
+        }
+        None if is_url => {
+            ui.label("©");
+            ui.add(egui::Hyperlink::from_label_and_url(licence, licence).open_in_new_tab(true));
+        }
+        None => {
+            ui.label(format!("© {}", licence));
+        }
+    });
+}
+
+/// Add the image of the accompanying canvas of the current canvas, e.g. a score of an audio
+/// recording, in the panel as a secondary view.
+fn add_accompanying_canvas(
+    ui: &mut egui::Ui,
+    presentation: &Manifest,
+    app_state: &AppState,
+    language: &str,
+) {
+    let Some(canvas) = presentation
+        .model()
+        .get_sequence(app_state.sequence_index)
+        .and_then(|x| x.get_canvas(app_state.canvas_index))
+        .ok()
+        .and_then(|x| x.get_accompanying_canvas())
+    else {
+        return;
+    };
+    let Ok(image) = canvas.get_image(0) else {
+        return;
+    };
+
+    let service = image.get_service();
+    let url = if service.is_empty() {
+        image.get_id().to_string()
+    } else {
+        format!(
+            "{}/full/{},/0/default.jpg",
+            service, ACCOMPANYING_CANVAS_WIDTH
+        )
+    };
+    let label = canvas.get_label(language).collect::<Vec<_>>().join(",");
+    let label = if label.is_empty() {
+        "Accompanying canvas".to_string()
+    } else {
+        label
+    };
+
+    add_text(ui, &label, None, 2);
+    bevy_egui::egui::Image::new(url)
+        .max_width(ui.available_width())
+        .alt_text(&label)
+        .ui(ui);
+    ui.add_space(6.0);
+}
+
+/// Add the selector to switch between the sequences of the manifest, loading the first canvas of
+/// the selected one.
+fn add_sequence_selector(
+    ui: &mut egui::Ui,
+    egui_ui_state: &mut ResMut<'_, EguiUiState>,
+    app_settings: &AppSettings,
+    app_state: &mut ResMut<'_, AppState>,
+    presentation: &Manifest,
+    commands: &mut Commands,
+    model_image_query: &Query<Entity, With<ModelImage>>,
+) {
+    let labels = presentation
+        .model()
+        .get_sequences()
+        .enumerate()
+        .map(|(index, seq)| {
+            let label = seq
+                .get_label(&app_settings.language)
+                .collect::<Vec<_>>()
+                .join(",");
+
+            if label.is_empty() {
+                format!("Sequence {}", index + 1)
+            } else {
+                label
+            }
+        })
+        .collect::<Vec<_>>();
+    let mut new_sequence_index = app_state.sequence_index;
+
+    egui::ComboBox::from_id_salt("Sequences")
+        .selected_text(
+            labels
+                .get(app_state.sequence_index)
+                .map_or("", |x| x.as_str()),
+        )
+        .wrap_mode(egui::TextWrapMode::Wrap)
+        .show_ui(ui, |ui| {
+            for (index, label) in labels.iter().enumerate() {
+                ui.selectable_value(&mut new_sequence_index, index, label);
+            }
+        });
+
+    if new_sequence_index != app_state.sequence_index
+        && let Err(err) = crate::web::load_canvas(
+            commands,
+            presentation,
+            app_state,
+            app_settings,
+            new_sequence_index,
+            0,
+            0,
+            model_image_query,
+        )
+    {
+        let msg = format!("Unable to load sequence.\n'{}'", err);
+
+        egui_ui_state
+            .toasts
+            .warning(msg)
+            .show_progress_bar(true)
+            .duration(Duration::from_secs(5));
+    }
+}
+
+/// Add the canvas thumbnail panel.
+#[allow(clippy::too_many_arguments)]
+fn add_canvas_thumbnails(
+    ui: &mut egui::Ui,
+    egui_ui_state: &mut ResMut<'_, EguiUiState>,
+    app_settings: &AppSettings,
+    app_state: &mut ResMut<'_, AppState>,
+    presentation: &Manifest,
+    commands: &mut Commands,
+    model_image_query: &Query<Entity, With<ModelImage>>,
+) -> Result {
+    let sequence_index = app_state.sequence_index;
+    let canvas_iter = presentation
+        .model()
+        .get_sequence(sequence_index)?
+        .get_canvases();
+
+    let thumbnail_size = app_settings.thumbnail_size;
+    let text_style = egui::TextStyle::Body;
+    let row_height = thumbnail_size + 3.0 * ui.text_style_height(&text_style);
+    let column_width = thumbnail_size;
+
+    let available_width = ui.available_width();
+    let item_spacing = ui.spacing().item_spacing;
+    let items_per_row = (available_width / (column_width + item_spacing.x))
+        .round()
+        .max(1.0) as usize;
+
+    let canvases: Vec<_> = canvas_iter.collect();
+
+    // The pages of a bound volume are shown in the spreads if they fit side by side.
+    let rows = if items_per_row >= 2 && presentation.model().is_paged() {
+        spreads::get_spreads(
+            &canvases
+                .iter()
+                .map(|x| x.get_page_behavior())
+                .collect::<Vec<_>>(),
+        )
+    } else {
+        (0..canvases.len())
+            .collect::<Vec<_>>()
+            .chunks(items_per_row)
+            .map(|x| x.to_vec())
+            .collect()
+    };
 
     egui::ScrollArea::vertical().auto_shrink(false).show_rows(
         ui,
         row_height,
-        canvases.len().div_ceil(items_per_row).max(1),
+        rows.len().max(1),
         |ui, row_range| {
             egui::Grid::new("my_grid")
                 .min_col_width(column_width)
                 .max_col_width(column_width)
                 .min_row_height(row_height)
                 .show(ui, |ui| -> Result {
-                    let row_start = row_range.start;
-
-                    for (row_index, _) in canvases
-                        .iter()
-                        .skip(row_range.start * items_per_row)
-                        .take(row_range.count() * items_per_row)
-                        .step_by(items_per_row)
-                        .enumerate()
-                    {
-                        for col_index in 0..items_per_row {
-                            let canvas_index = (row_start + row_index) * items_per_row + col_index;
-
-                            if canvas_index < canvases.len() {
-                                let canvas = canvases[canvas_index];
-
-                                if ui
-                                    .vertical_centered(|ui| {
-                                        let canvas_thumbnail = canvas.get_thumbnail();
-
-                                        if !canvas_thumbnail.is_empty() {
-                                            ui.add_sized(
-                                                vec2(thumbnail_size, thumbnail_size),
-                                                bevy_egui::egui::Image::new(canvas_thumbnail)
-                                                    .max_size(vec2(thumbnail_size, thumbnail_size)),
-                                            );
-                                        } else {
-                                            ui.add_sized(
-                                                vec2(thumbnail_size, thumbnail_size),
-                                                egui::Label::new("🚫").selectable(false),
-                                            );
-                                        }
-                                        let label = format!(
-                                            "({}) {}",
-                                            canvas_index + 1,
-                                            canvas
-                                                .get_label(&app_settings.language)
-                                                .collect::<Vec<_>>()
-                                                .join(",")
+                    for row in rows.get(row_range).unwrap_or_default() {
+                        for &canvas_index in row {
+                            let canvas = canvases[canvas_index];
+                            let canvas_error =
+                                presentation.get_canvas_error(sequence_index, canvas_index);
+
+                            let label = format!(
+                                "({}) {}",
+                                canvas_index + 1,
+                                canvas
+                                    .get_label(&app_settings.language)
+                                    .collect::<Vec<_>>()
+                                    .join(",")
+                            );
+                            let response = ui
+                                .vertical_centered(|ui| {
+                                    let canvas_thumbnail = canvas.get_thumbnail();
+
+                                    if let Some(canvas_error) = canvas_error {
+                                        ui.add_sized(
+                                            vec2(thumbnail_size, thumbnail_size),
+                                            egui::Label::new(
+                                                egui::RichText::new("⚠")
+                                                    .size(thumbnail_size / 2.0)
+                                                    .color(BROKEN_CANVAS_COLOR),
+                                            )
+                                            .selectable(false),
+                                        )
+                                        .on_hover_text(&canvas_error.msg);
+                                    } else if !canvas_thumbnail.is_empty() {
+                                        ui.add_sized(
+                                            vec2(thumbnail_size, thumbnail_size),
+                                            bevy_egui::egui::Image::new(canvas_thumbnail)
+                                                .max_size(vec2(thumbnail_size, thumbnail_size))
+                                                .alt_text(&label),
                                         );
-                                        add_text(ui, &label, None, 3);
-                                    })
-                                    .response
-                                    .interact(Sense::CLICK)
-                                    .clicked()
-                                    && let Err(err) = crate::web::load_canvas(
-                                        commands,
-                                        presentation,
-                                        app_state,
-                                        canvas_index,
-                                        model_image_query,
-                                    )
-                                {
-                                    let msg = format!("Unable to load canvas.\n'{}'", err);
-
-                                    egui_ui_state
-                                        .toasts
-                                        .warning(msg)
-                                        .show_progress_bar(true)
-                                        .duration(Duration::from_secs(5));
-                                }
+                                    } else {
+                                        ui.add_sized(
+                                            vec2(thumbnail_size, thumbnail_size),
+                                            egui::Label::new("🚫").selectable(false),
+                                        );
+                                    }
+                                    add_text(ui, &label, None, 3);
+                                })
+                                .response
+                                // Focusable to be opened with the keyboard.
+                                .interact(Sense::click());
+
+                            set_accessible_name(ui, &response, &label);
+
+                            if response.clicked()
+                                && let Err(err) = crate::web::load_canvas(
+                                    commands,
+                                    presentation,
+                                    app_state,
+                                    app_settings,
+                                    sequence_index,
+                                    canvas_index,
+                                    0,
+                                    model_image_query,
+                                )
+                            {
+                                let msg = format!("Unable to load canvas.\n'{}'", err);
+
+                                egui_ui_state
+                                    .toasts
+                                    .warning(msg)
+                                    .show_progress_bar(true)
+                                    .duration(Duration::from_secs(5));
                             }
                         }
                         ui.end_row();
@@ -528,34 +4453,278 @@ fn add_address_bar(
     ui: &mut egui::Ui,
     egui_ui_state: &mut ResMut<'_, EguiUiState>,
     app_state: &mut ResMut<'_, AppState>,
+    app_settings: &AppSettings,
     width: f32,
 ) {
-    if ui
+    let response = ui
         .add(
             egui::TextEdit::singleline(&mut egui_ui_state.presentation_url)
                 .desired_width(width)
                 .hint_text("IIIF Manifest URL"),
         )
-        .on_hover_text(&egui_ui_state.presentation_url)
-        .lost_focus()
-        && egui_ui_state.presentation_url != app_state.presentation_url
-    {
+        .on_hover_text(&egui_ui_state.presentation_url);
+
+    set_accessible_name(ui, &response, "IIIF manifest URL");
+
+    if std::mem::take(&mut egui_ui_state.focus_address_bar) {
+        response.request_focus();
+    }
+
+    if response.lost_focus() && egui_ui_state.presentation_url != app_state.presentation_url {
         let presentation_url = egui_ui_state.presentation_url.to_string();
 
-        crate::web::load_presentation(app_state, &presentation_url);
+        crate::web::load_presentation(app_state, app_settings, &presentation_url);
+    }
+}
+
+/// Add the badge of the capabilities of the image service, e.g. "IIIF 3 · L2 · tiles · jpg png",
+/// with a warning if the sample tile is not served as advertised.
+fn add_capability_badge(ui: &mut egui::Ui, capability_probe: &CapabilityProbe) {
+    let Some(capabilities) = &capability_probe.capabilities else {
+        return;
+    };
+    let result = capability_probe.get_result();
+    let warning = capability_probe.get_warning();
+
+    let mut text = capabilities.protocol.to_string();
+
+    if let Some(level) = capabilities.level {
+        text += &format!(" · L{}", level);
+    }
+    text += if capabilities.tiles {
+        " · tiles"
+    } else {
+        " · no tiles"
+    };
+    text += &format!(" · {}", capabilities.formats.join(" "));
+
+    let color = match (&result, &warning) {
+        (_, Some(_)) => Color32::ORANGE,
+        (ProbeResult::Pending, _) => Color32::GRAY,
+        _ => Color32::LIGHT_GREEN,
+    };
+    let icon = if warning.is_some() { "⚠ " } else { "" };
+
+    let mut details = format!(
+        "Protocol: {}\nCompliance level: {}\nTiles: {}\nFormats: {}\nSample tile: {}",
+        capabilities.protocol,
+        capabilities
+            .level
+            .map_or("not declared".to_string(), |x| x.to_string()),
+        if capabilities.tiles { "yes" } else { "no" },
+        capabilities.formats.join(", "),
+        match &result {
+            ProbeResult::Pending => "checking".to_string(),
+            ProbeResult::Served(content_type) if content_type.is_empty() => "served".to_string(),
+            ProbeResult::Served(content_type) => format!("served as {}", content_type),
+            ProbeResult::Failed(msg) => format!("failed, {}", msg),
+        }
+    );
+
+    if let Some(warning) = &warning {
+        details += &format!("\n\n{}", warning);
+    }
+
+    let response = ui
+        .add_sized(
+            [CAPABILITY_BADGE_WIDTH, ui.spacing().interact_size.y],
+            egui::Label::new(
+                egui::RichText::new(format!("{}{}", icon, text))
+                    .small()
+                    .color(color),
+            )
+            .truncate(),
+        )
+        .on_hover_text(&details);
+
+    set_accessible_name(ui, &response, &format!("Image service: {}", details));
+}
+
+/// Add a button of the icon without a frame, named for the hover text and the screen readers.
+fn add_icon_button(ui: &mut egui::Ui, icon: &str, name: &str) -> egui::Response {
+    let response = Button::new(icon)
+        .fill(Color32::from_black_alpha(0))
+        .ui(ui)
+        .on_hover_text(name);
+
+    set_accessible_name(ui, &response, name);
+    response
+}
+
+/// Name the widget for the screen readers, e.g. an icon button or a text field without a label.
+fn set_accessible_name(ui: &egui::Ui, response: &egui::Response, name: &str) {
+    ui.ctx()
+        .accesskit_node_builder(response.id, |node| node.set_label(name));
+}
+
+/// Add the text announced by the screen readers when it changes, like a live region of a web
+/// page. It is visually hidden, as the changes are already shown by the controls and the toasts.
+fn add_announcement(ctx: &egui::Context, announcement: &str) {
+    if announcement.is_empty() {
+        return;
     }
+
+    egui::Area::new(egui::Id::new("announcement"))
+        .interactable(false)
+        .fixed_pos(egui::Pos2::ZERO)
+        .show(ctx, |ui| {
+            let response = ui.label(egui::RichText::new(announcement).color(Color32::TRANSPARENT));
+
+            ui.ctx().accesskit_node_builder(response.id, |node| {
+                node.set_live(egui::accesskit::Live::Polite)
+            });
+        });
+}
+
+/// Get the announcement of the canvas being shown, e.g. "Page 2 of 10: f. 1v".
+pub(crate) fn get_canvas_announcement(
+    presentation: &Manifest,
+    sequence_index: usize,
+    canvas_index: usize,
+    language: &str,
+) -> String {
+    let Ok(sequence) = presentation.model().get_sequence(sequence_index) else {
+        return "".to_string();
+    };
+    let label = sequence
+        .get_canvas(canvas_index)
+        .map(|x| x.get_label(language).collect::<Vec<_>>().join(","))
+        .unwrap_or_default();
+
+    format!(
+        "Page {} of {}: {}",
+        canvas_index + 1,
+        sequence.get_canvases().len(),
+        label
+    )
 }
 
 /// Add a multi-line wrapped text.
 fn add_text(ui: &mut egui::Ui, text: &str, color: Option<Color32>, max_rows: usize) {
-    let mut job = LayoutJob::simple_format(
-        text.to_owned(),
-        egui::TextFormat {
-            font_id: FontId::new(12.0, FontFamily::Proportional),
-            color: color.unwrap_or(Color32::GRAY),
-            ..Default::default()
+    let format = egui::TextFormat {
+        font_id: FontId::new(12.0, FontFamily::Proportional),
+        color: color.unwrap_or(Color32::GRAY),
+        ..Default::default()
+    };
+
+    if !html::is_html(text) {
+        add_layout_job(
+            ui,
+            LayoutJob::simple_format(text.to_owned(), format),
+            max_rows,
+        );
+        return;
+    }
+
+    add_spans(ui, &html::parse_html(text), &format, max_rows);
+}
+
+/// Get the format of the styled text run in the base format.
+fn get_span_format(
+    ui: &egui::Ui,
+    span: &html::HtmlSpan,
+    format: &egui::TextFormat,
+) -> egui::TextFormat {
+    egui::TextFormat {
+        // Coloured as the links by the hyperlink.
+        color: if span.link.is_some() {
+            Color32::PLACEHOLDER
+        } else if span.bold {
+            ui.visuals().strong_text_color()
+        } else {
+            format.color
         },
-    );
+        italics: span.italic,
+        ..format.clone()
+    }
+}
+
+/// Get the runs of styled text as one text, with the links as text only.
+fn get_spans_job(ui: &egui::Ui, spans: &[html::HtmlSpan], format: &egui::TextFormat) -> LayoutJob {
+    let mut job = LayoutJob::default();
+
+    for span in spans {
+        job.append(&span.text, 0.0, get_span_format(ui, span, format));
+    }
+
+    job
+}
+
+/// Add the runs of styled text, with the links as the hyperlinks.
+fn add_spans(
+    ui: &mut egui::Ui,
+    spans: &[html::HtmlSpan],
+    format: &egui::TextFormat,
+    max_rows: usize,
+) {
+    if spans.iter().any(|x| x.link.is_some()) {
+        // Links are separate widgets so the text is not limited to the max rows.
+        ui.horizontal_wrapped(|ui| {
+            ui.spacing_mut().item_spacing.x = 0.0;
+
+            for span in spans {
+                let job = LayoutJob::single_section(
+                    span.text.to_string(),
+                    get_span_format(ui, span, format),
+                );
+
+                if let Some(url) = &span.link {
+                    ui.add(egui::Hyperlink::from_label_and_url(job, url).open_in_new_tab(true))
+                        .on_hover_text(url);
+                } else {
+                    ui.label(job);
+                }
+            }
+        });
+    } else {
+        let job = get_spans_job(ui, spans, format);
+
+        add_layout_job(ui, job, max_rows);
+    }
+}
+
+/// Add the description, which may be long, with its first rows until it is expanded by "Read
+/// more", and scrolled if still too long. It is rendered as markdown unless it is HTML.
+fn add_description(ui: &mut egui::Ui, text: &str, expanded: &mut bool) {
+    let format = egui::TextFormat {
+        font_id: FontId::new(12.0, FontFamily::Proportional),
+        color: Color32::GRAY,
+        ..Default::default()
+    };
+    let spans = if html::is_html(text) {
+        html::parse_html(text)
+    } else {
+        html::parse_markdown(text)
+    };
+
+    let mut job = get_spans_job(ui, &spans, &format);
+    job.wrap.max_width = ui.available_width();
+    let num_rows = ui.painter().layout_job(job.clone()).rows.len();
+
+    if num_rows <= DESCRIPTION_ROWS {
+        add_spans(ui, &spans, &format, usize::MAX);
+        return;
+    }
+
+    if *expanded {
+        egui::ScrollArea::vertical()
+            .id_salt("description")
+            .max_height(DESCRIPTION_MAX_HEIGHT)
+            .show(ui, |ui| add_spans(ui, &spans, &format, usize::MAX));
+
+        if ui.link("Show less").clicked() {
+            *expanded = false;
+        }
+    } else {
+        add_layout_job(ui, job, DESCRIPTION_ROWS);
+
+        if ui.link("Read more").clicked() {
+            *expanded = true;
+        }
+    }
+}
+
+fn add_layout_job(ui: &mut egui::Ui, mut job: LayoutJob, max_rows: usize) {
     job.wrap = egui::text::TextWrapping {
         max_rows,
         break_anywhere: true,
@@ -0,0 +1,250 @@
+use crate::{iiif::IiifError, web};
+use std::path::Path;
+
+/// Schemes of the links opened by the viewer, e.g. "iiif+https://example.org/manifest.json" in
+/// the catalogs. The plain "iiif" is for the manifests served over HTTPS.
+const SCHEMES: [&str; 3] = ["iiif", "iiif+http", "iiif+https"];
+
+/// Name of the desktop entry of the handler of the links on Linux.
+#[cfg(target_os = "linux")]
+const DESKTOP_FILE_NAME: &str = "rs-iiif-browser-url.desktop";
+
+/// Get the URL of the manifest of the link passed to the viewer, either by the protocol handler,
+/// a file association or the command line. `None` if not a link to a manifest.
+pub(crate) fn get_manifest_url(link: &str) -> Option<String> {
+    let link = link.trim();
+
+    let Some((scheme, rest)) = link.split_once("://") else {
+        // A manifest file opened by the file association.
+        let path = Path::new(link);
+
+        return path
+            .is_file()
+            .then(|| std::path::absolute(path).ok())
+            .flatten()
+            .map(|x| web::get_file_url(&x));
+    };
+
+    let scheme = match scheme.to_ascii_lowercase().as_str() {
+        "iiif" | "iiif+https" | "https" => "https",
+        "iiif+http" | "http" => "http",
+        "file" => "file",
+        _ => return None,
+    };
+
+    (!rest.is_empty()).then(|| format!("{}://{}", scheme, rest))
+}
+
+/// Quote the argument of the command of a desktop entry, escaped for the quoting of the
+/// command and then for the string value of the key, e.g. a backslash as four of them.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn quote_desktop_exec_arg(arg: &str) -> String {
+    let mut quoted = String::from('"');
+
+    for c in arg.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\\\\\"),
+            '"' | '`' | '$' => {
+                quoted.push_str("\\\\");
+                quoted.push(c);
+            }
+            // Not a field code, e.g. "%u".
+            '%' => quoted.push_str("%%"),
+            _ => quoted.push(c),
+        }
+    }
+
+    quoted.push('"');
+    quoted
+}
+
+/// Get the desktop entry opening the links of the schemes with the viewer on Linux.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn get_desktop_entry(exe: &Path) -> String {
+    let mime_types = SCHEMES
+        .iter()
+        .map(|x| format!("x-scheme-handler/{};", x))
+        .collect::<String>();
+
+    format!(
+        "[Desktop Entry]\n\
+        Type=Application\n\
+        Name=RS IIIF Browser\n\
+        Exec={} %u\n\
+        Terminal=false\n\
+        NoDisplay=true\n\
+        MimeType={}\n",
+        quote_desktop_exec_arg(&exe.display().to_string()),
+        mime_types
+    )
+}
+
+/// Get the arguments of `reg add` of the keys opening the links of the schemes with the viewer
+/// for the user on Windows.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn get_registry_commands(exe: &Path) -> Vec<Vec<String>> {
+    let command = format!("\"{}\" \"%1\"", exe.display());
+
+    SCHEMES
+        .iter()
+        .flat_map(|scheme| {
+            let key = format!("HKCU\\Software\\Classes\\{}", scheme);
+
+            [
+                vec![
+                    key.clone(),
+                    "/ve".to_string(),
+                    "/d".to_string(),
+                    "URL:IIIF".to_string(),
+                ],
+                vec![
+                    key.clone(),
+                    "/v".to_string(),
+                    "URL Protocol".to_string(),
+                    "/d".to_string(),
+                    "".to_string(),
+                ],
+                vec![
+                    format!("{}\\shell\\open\\command", key),
+                    "/ve".to_string(),
+                    "/d".to_string(),
+                    command.clone(),
+                ],
+            ]
+        })
+        .collect()
+}
+
+/// Run the command, failing if it does not exit successfully.
+#[cfg_attr(not(any(target_os = "linux", target_os = "windows")), allow(dead_code))]
+fn run(program: &str, args: &[String]) -> Result<(), IiifError> {
+    let status = std::process::Command::new(program).args(args).status()?;
+
+    if !status.success() {
+        return Err(IiifError::IiifUnsupportedError(format!(
+            "'{} {}' exited with {}",
+            program,
+            args.join(" "),
+            status
+        )));
+    }
+
+    Ok(())
+}
+
+/// Register the desktop entry of the handler of the links for the user on Linux.
+#[cfg(target_os = "linux")]
+fn register_handler(exe: &Path) -> Result<String, IiifError> {
+    let data_dir = std::env::var_os("XDG_DATA_HOME")
+        .map(std::path::PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME").map(|x| std::path::PathBuf::from(x).join(".local/share"))
+        })
+        .ok_or_else(|| IiifError::IiifMissingInfo("home directory".to_string()))?;
+    let applications_dir = data_dir.join("applications");
+    let desktop_file = applications_dir.join(DESKTOP_FILE_NAME);
+
+    std::fs::create_dir_all(&applications_dir)?;
+    std::fs::write(&desktop_file, get_desktop_entry(exe))?;
+
+    for scheme in SCHEMES {
+        run(
+            "xdg-mime",
+            &[
+                "default".to_string(),
+                DESKTOP_FILE_NAME.to_string(),
+                format!("x-scheme-handler/{}", scheme),
+            ],
+        )?;
+    }
+
+    Ok(desktop_file.display().to_string())
+}
+
+/// Register the keys of the handler of the links for the user on Windows.
+#[cfg(target_os = "windows")]
+fn register_handler(exe: &Path) -> Result<String, IiifError> {
+    for args in get_registry_commands(exe) {
+        let args = std::iter::once("add".to_string())
+            .chain(args)
+            .chain(std::iter::once("/f".to_string()))
+            .collect::<Vec<_>>();
+
+        run("reg", &args)?;
+    }
+
+    Ok("HKCU\\Software\\Classes".to_string())
+}
+
+/// The handlers are declared in the Info.plist of the app bundle instead, e.g. on macOS.
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+fn register_handler(exe: &Path) -> Result<String, IiifError> {
+    Err(IiifError::IiifUnsupportedError(format!(
+        "registering {:?} for the links on this system, declare the URL types in its app bundle instead",
+        exe
+    )))
+}
+
+/// Register the viewer as the handler of the links of the schemes for the user, so that the
+/// links in the catalogs open in it. Return where it is registered.
+pub(crate) fn register() -> Result<String, IiifError> {
+    register_handler(&std::env::current_exe()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_manifest_url() {
+        assert_eq!(
+            get_manifest_url("iiif://example.org/manifest.json"),
+            Some("https://example.org/manifest.json".to_string())
+        );
+        assert_eq!(
+            get_manifest_url(" IIIF+http://localhost:8080/manifest.json?x=1 "),
+            Some("http://localhost:8080/manifest.json?x=1".to_string())
+        );
+        assert_eq!(
+            get_manifest_url("https://example.org/manifest.json"),
+            Some("https://example.org/manifest.json".to_string())
+        );
+        assert_eq!(get_manifest_url("iiif+https://"), None);
+        assert_eq!(get_manifest_url("mailto://someone"), None);
+        assert_eq!(get_manifest_url("no-such-manifest.json"), None);
+    }
+
+    #[test]
+    fn test_quote_desktop_exec_arg() {
+        assert_eq!(
+            quote_desktop_exec_arg(r#"/opt/a\b "c" `d` 100%"#),
+            r#""/opt/a\\\\b \\"c\\" \\`d\\` 100%%""#
+        );
+    }
+
+    #[test]
+    fn test_get_desktop_entry() {
+        let entry = get_desktop_entry(Path::new("/opt/iiif $viewer/rs-iiif-browser"));
+
+        assert!(entry.contains("Exec=\"/opt/iiif \\\\$viewer/rs-iiif-browser\" %u\n"));
+        assert!(entry.contains(
+            "MimeType=x-scheme-handler/iiif;x-scheme-handler/iiif+http;x-scheme-handler/iiif+https;\n"
+        ));
+    }
+
+    #[test]
+    fn test_get_registry_commands() {
+        let commands = get_registry_commands(Path::new("C:\\viewer\\rs-iiif-browser.exe"));
+
+        assert_eq!(commands.len(), 3 * SCHEMES.len());
+        assert_eq!(
+            commands[2],
+            [
+                "HKCU\\Software\\Classes\\iiif\\shell\\open\\command",
+                "/ve",
+                "/d",
+                "\"C:\\viewer\\rs-iiif-browser.exe\" \"%1\""
+            ]
+        );
+    }
+}
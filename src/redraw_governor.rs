@@ -0,0 +1,171 @@
+use bevy::{
+    animation::AnimationPlayer,
+    prelude::{ButtonInput, MouseButton, Query, Res, ResMut, Resource, Single, Touches, With},
+    winit::{UpdateMode, WinitSettings},
+};
+use bevy_egui::{EguiFullOutput, PrimaryEguiContext, egui};
+use std::time::Duration;
+
+/// Interval between the frames of a gesture or an animation, for the smooth movement.
+const ANIMATION_FRAME_INTERVAL: Duration = Duration::from_micros(16_667);
+
+/// Interval between the frames while loading, for the smooth progress indicators at less cost.
+const LOADING_FRAME_INTERVAL: Duration = Duration::from_micros(33_333);
+
+/// Max wait for an event while idle and focused, as in the desktop mode.
+const IDLE_FOCUSED_WAIT: Duration = Duration::from_secs(5);
+
+/// Max wait for an event while idle and unfocused, as in the desktop mode.
+const IDLE_UNFOCUSED_WAIT: Duration = Duration::from_secs(60);
+
+/// Activity needing the frames at a cadence instead of on the events only, from the least to the
+/// most urgent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum RedrawActivity {
+    /// Downloads in progress, e.g. the manifest or the tiles, shown by the progress indicators.
+    Loading,
+    /// Animation of the view or the UI, e.g. the tour or the blink comparison.
+    Animation,
+    /// Touches or mouse buttons held by the user.
+    Gesture,
+}
+
+impl RedrawActivity {
+    /// Get the interval between the frames while the activity lasts.
+    fn get_frame_interval(&self) -> Duration {
+        match self {
+            RedrawActivity::Loading => LOADING_FRAME_INTERVAL,
+            RedrawActivity::Animation | RedrawActivity::Gesture => ANIMATION_FRAME_INTERVAL,
+        }
+    }
+}
+
+/// Central control of the cadence of the frames, requesting them only while something is going
+/// on to save the battery, and often enough for it to progress smoothly.
+#[derive(Resource, Default)]
+pub(crate) struct RedrawGovernor {
+    /// Most urgent activity reported in the frame.
+    activity: Option<RedrawActivity>,
+    /// Delay of the next frame requested by the UI, e.g. to hide a toast.
+    ui_repaint_delay: Option<Duration>,
+    /// Delay of the next frame needed by the systems waiting for a time in the frame, e.g. the
+    /// next reload.
+    wake_up_delay: Option<Duration>,
+}
+
+impl RedrawGovernor {
+    /// Report the activity needing the frames in this frame. It stops once it is no longer
+    /// reported.
+    pub(crate) fn report(&mut self, activity: RedrawActivity) {
+        self.activity = self.activity.max(Some(activity));
+    }
+
+    /// Wake up for a frame in the delay at the latest, even while idle and unfocused, e.g. for
+    /// the reload due then. It is reported again in the next frame if still waiting.
+    pub(crate) fn wake_up_in(&mut self, delay: Duration) {
+        self.wake_up_delay = Some(self.wake_up_delay.map_or(delay, |x| x.min(delay)));
+    }
+
+    /// Get the max wait for the next frame, or `None` if idle.
+    fn get_wait(&self) -> Option<Duration> {
+        let activity_wait = self.activity.map(|x| x.get_frame_interval());
+
+        [activity_wait, self.ui_repaint_delay, self.wake_up_delay]
+            .into_iter()
+            .flatten()
+            .min()
+    }
+
+    /// Get the update mode of the event loop, waiting for the events while idle.
+    fn get_winit_settings(&self) -> (UpdateMode, UpdateMode) {
+        match self.get_wait() {
+            Some(wait) => (
+                UpdateMode::reactive(wait),
+                UpdateMode::reactive_low_power(wait),
+            ),
+            None => (
+                UpdateMode::reactive(IDLE_FOCUSED_WAIT),
+                UpdateMode::reactive_low_power(IDLE_UNFOCUSED_WAIT),
+            ),
+        }
+    }
+}
+
+/// Keep the delay of the next frame requested by the UI, which is otherwise only honoured if it
+/// is immediate, e.g. stalling the toasts until the next event.
+pub(crate) fn ui_repaint_delay_system(
+    mut redraw_governor: ResMut<RedrawGovernor>,
+    full_output: Single<&EguiFullOutput, With<PrimaryEguiContext>>,
+) {
+    redraw_governor.ui_repaint_delay = full_output
+        .0
+        .as_ref()
+        .and_then(|x| x.viewport_output.get(&egui::ViewportId::ROOT))
+        .map(|x| x.repaint_delay)
+        .filter(|x| *x < IDLE_FOCUSED_WAIT);
+}
+
+/// Set the cadence of the frames by the activities of the frame, e.g. the gestures and the
+/// animations of the 3D model, and those reported by the other systems.
+pub(crate) fn redraw_governor_system(
+    mut redraw_governor: ResMut<RedrawGovernor>,
+    mut winit_settings: ResMut<WinitSettings>,
+    touches: Res<Touches>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    animation_players: Query<&AnimationPlayer>,
+) {
+    if touches.iter().next().is_some() || mouse.get_pressed().next().is_some() {
+        redraw_governor.report(RedrawActivity::Gesture);
+    }
+
+    if animation_players
+        .iter()
+        .any(|x| !x.all_finished() && !x.all_paused())
+    {
+        redraw_governor.report(RedrawActivity::Animation);
+    }
+
+    let (focused_mode, unfocused_mode) = redraw_governor.get_winit_settings();
+
+    // Only change the settings when needed, as the event loop reads them every frame.
+    if winit_settings.focused_mode != focused_mode
+        || winit_settings.unfocused_mode != unfocused_mode
+    {
+        winit_settings.focused_mode = focused_mode;
+        winit_settings.unfocused_mode = unfocused_mode;
+    }
+
+    redraw_governor.activity = None;
+    redraw_governor.wake_up_delay = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_wait() {
+        let mut redraw_governor = RedrawGovernor::default();
+
+        assert_eq!(redraw_governor.get_wait(), None);
+
+        redraw_governor.ui_repaint_delay = Some(Duration::from_secs(1));
+        assert_eq!(redraw_governor.get_wait(), Some(Duration::from_secs(1)));
+
+        redraw_governor.report(RedrawActivity::Gesture);
+        redraw_governor.report(RedrawActivity::Loading);
+        assert_eq!(redraw_governor.get_wait(), Some(ANIMATION_FRAME_INTERVAL));
+
+        redraw_governor.activity = None;
+        redraw_governor.ui_repaint_delay = None;
+        redraw_governor.report(RedrawActivity::Loading);
+        assert_eq!(redraw_governor.get_wait(), Some(LOADING_FRAME_INTERVAL));
+
+        redraw_governor.wake_up_in(Duration::from_secs(30));
+        redraw_governor.wake_up_in(Duration::from_secs(20));
+        assert_eq!(redraw_governor.get_wait(), Some(LOADING_FRAME_INTERVAL));
+
+        redraw_governor.activity = None;
+        assert_eq!(redraw_governor.get_wait(), Some(Duration::from_secs(20)));
+    }
+}
@@ -1,5 +1,19 @@
+pub(crate) mod canvas_background;
+pub(crate) mod color_profile;
+pub(crate) mod comparison;
+#[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+pub(crate) mod custom_effect;
+pub(crate) mod eyedropper;
+pub(crate) mod gpu_capabilities;
+pub(crate) mod grid;
+pub(crate) mod magnifier;
 pub(crate) mod model;
+pub(crate) mod model_animation;
 pub(crate) mod model_image;
+pub(crate) mod model_stage;
+pub(crate) mod overlay_image;
 pub(crate) mod pipeline_checker;
 pub(crate) mod tile;
+pub(crate) mod tile_format;
+pub(crate) mod tile_placeholder;
 pub(crate) mod tiled_image;
@@ -0,0 +1,115 @@
+use crate::{
+    app::{app_settings::AppSettings, app_state::AppState},
+    presentation::manifest::Manifest,
+    rendering::{tile::TileLayer, tiled_image::TiledImage},
+    web,
+};
+use bevy::{
+    camera::visibility::RenderLayers,
+    prelude::{
+        Assets, Color, ColorMaterial, Commands, Component, DetectChangesMut, Mesh, Mesh2d,
+        MeshMaterial2d, Rect, Rectangle, Res, ResMut, Single, Transform, Vec2, Visibility, With,
+    },
+};
+
+/// Color in sRGB of the background of the canvas by default, subtly lighter than the dark theme.
+pub(crate) const DEFAULT_CANVAS_BACKGROUND: [u8; 3] = [48, 48, 48];
+
+/// Depth of the background, underneath the tiles of all the levels and their placeholders.
+const CANVAS_BACKGROUND_Z: f32 = -200.0;
+
+/// Rectangle of the extent of the canvas drawn behind the image, so that the transparent parts
+/// of the image and the tiles not loaded yet show where the canvas is.
+#[derive(Component)]
+pub(crate) struct CanvasBackground;
+
+/// Get the color of the background of the canvas by the settings. `None` if not shown.
+fn get_background_color(app_settings: &AppSettings) -> Option<Color> {
+    app_settings
+        .canvas_background
+        .map(|[r, g, b]| Color::srgb_u8(r, g, b))
+}
+
+/// Keep the background of the canvas under the tiled image, in the color of the settings.
+pub(crate) fn canvas_background_system(
+    mut commands: Commands,
+    background: Option<
+        Single<
+            (
+                &mut Transform,
+                &mut Visibility,
+                &mut RenderLayers,
+                &MeshMaterial2d<ColorMaterial>,
+            ),
+            With<CanvasBackground>,
+        >,
+    >,
+    tiled_image: Option<Single<&TiledImage>>,
+    presentation: Option<Single<&Manifest>>,
+    app_settings: Res<AppSettings>,
+    app_state: Res<AppState>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let Some(background) = background else {
+        // Scaled to the extent of the canvas from a unit square.
+        commands.spawn((
+            CanvasBackground,
+            Mesh2d(meshes.add(Rectangle::new(1.0, 1.0))),
+            MeshMaterial2d(materials.add(Color::BLACK)),
+            Transform::default(),
+            Visibility::Hidden,
+            RenderLayers::default(),
+        ));
+        return;
+    };
+    let (mut transform, mut visibility, mut render_layers, material) = background.into_inner();
+    let (Some(tiled_image), Some(color)) = (tiled_image, get_background_color(&app_settings))
+    else {
+        visibility.set_if_neq(Visibility::Hidden);
+        return;
+    };
+
+    // The image may be painted on a region of the canvas, e.g. a fragment of a page.
+    let world_rect = presentation
+        .and_then(|x| web::get_canvas_size(&x, app_state.sequence_index, app_state.canvas_index))
+        .map_or_else(
+            || tiled_image.get_world_max_size_rect(),
+            |x| tiled_image.image_to_world_rect(Rect::from_corners(Vec2::ZERO, Vec2::from(x))),
+        );
+
+    transform.set_if_neq(
+        Transform::from_translation(world_rect.center().extend(CANVAS_BACKGROUND_Z))
+            .with_scale(world_rect.size().extend(1.0)),
+    );
+    visibility.set_if_neq(Visibility::Visible);
+    render_layers.set_if_neq(app_state.comparison.get_render_layers(TileLayer::Base));
+
+    if let Some(color_material) = materials.get(material.id())
+        && color_material.color != color
+        && let Some(color_material) = materials.get_mut(material.id())
+    {
+        color_material.color = color;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_background_color() {
+        let mut app_settings = AppSettings {
+            canvas_background: None,
+            ..AppSettings::default()
+        };
+
+        assert_eq!(get_background_color(&app_settings), None);
+
+        app_settings.canvas_background = Some([255, 0, 0]);
+        assert_eq!(
+            get_background_color(&app_settings),
+            Some(Color::srgb(1.0, 0.0, 0.0))
+        );
+    }
+}
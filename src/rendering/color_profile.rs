@@ -0,0 +1,217 @@
+use crate::{app::app_settings::AppSettings, host_stats::HostStats, rendering::tile::Tile};
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    image::{
+        CompressedImageFormats, Image, ImageFormat, ImageFormatSetting, ImageLoaderSettings,
+        ImageType, TextureError,
+    },
+    prelude::{AssetServer, Query, Res, Resource, info, warn},
+};
+use image::{DynamicImage, ImageDecoder, ImageReader, RgbaImage};
+use moxcms::{CmsError, ColorProfile, Layout, TransformOptions};
+use std::{
+    io::Cursor,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+use thiserror::Error;
+
+/// Formats of the tiles decoded by the loader, which may embed an ICC profile.
+const ICC_IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "gif", "webp", "tif", "tiff"];
+
+/// Whether the tiles with an embedded ICC profile are converted to sRGB when loaded, shared with
+/// the loader of the tiles.
+#[derive(Resource, Clone, Default)]
+pub(crate) struct ColorManagement(Arc<AtomicBool>);
+
+impl ColorManagement {
+    fn is_enabled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Enable or disable the conversion, and get whether it is changed.
+    fn set_enabled(&self, enabled: bool) -> bool {
+        self.0.swap(enabled, Ordering::Relaxed) != enabled
+    }
+}
+
+#[derive(Debug, Error)]
+pub(crate) enum IccImageLoaderError {
+    #[error("failed to read the image. {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("failed to decode the image. {0}")]
+    Image(#[from] image::ImageError),
+
+    #[error("failed to load the texture. {0}")]
+    Texture(#[from] TextureError),
+}
+
+/// Loader of the images converting the colors of their embedded ICC profiles to sRGB if color
+/// management is enabled, and as the image loader of bevy otherwise.
+///
+/// Registered after the image loader of bevy, so that it is chosen for the extensions of both.
+pub(crate) struct IccImageLoader {
+    color_management: ColorManagement,
+    /// Counts the bytes of the tiles received from their hosts.
+    host_stats: HostStats,
+}
+
+impl IccImageLoader {
+    pub(crate) fn new(color_management: ColorManagement, host_stats: HostStats) -> Self {
+        Self {
+            color_management,
+            host_stats,
+        }
+    }
+}
+
+impl AssetLoader for IccImageLoader {
+    type Asset = Image;
+    type Settings = ImageLoaderSettings;
+    type Error = IccImageLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        settings: &ImageLoaderSettings,
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Image, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        self.host_stats
+            .record_bytes(&load_context.asset_path().to_string(), bytes.len());
+
+        if self.color_management.is_enabled()
+            && let Some(image) = decode_to_srgb(&bytes)?
+        {
+            let mut image = Image::from_dynamic(image, settings.is_srgb, settings.asset_usage);
+
+            image.sampler = settings.sampler.clone();
+
+            return Ok(image);
+        }
+
+        let extension = load_context
+            .path()
+            .extension()
+            .and_then(|x| x.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        let image_type = match &settings.format {
+            ImageFormatSetting::Format(format) => ImageType::Format(*format),
+            ImageFormatSetting::FromExtension if !extension.is_empty() => {
+                ImageType::Extension(&extension)
+            }
+            // The tile URLs may have no extension, e.g. through the proxy.
+            _ => ImageType::Format(
+                ImageFormat::from_image_crate_format(image::guess_format(&bytes)?)
+                    .ok_or_else(|| TextureError::UnsupportedTextureFormat(extension.to_string()))?,
+            ),
+        };
+
+        Ok(Image::from_buffer(
+            &bytes,
+            image_type,
+            CompressedImageFormats::NONE,
+            settings.is_srgb,
+            settings.sampler.clone(),
+            settings.asset_usage,
+        )?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        ICC_IMAGE_EXTENSIONS
+    }
+}
+
+/// Convert the colors of the image from the ICC profile to sRGB.
+fn convert_to_srgb(image: &mut RgbaImage, icc_profile: &[u8]) -> Result<(), CmsError> {
+    let source = ColorProfile::new_from_slice(icc_profile)?;
+    let transform = source.create_transform_8bit(
+        Layout::Rgba,
+        &ColorProfile::new_srgb(),
+        Layout::Rgba,
+        TransformOptions::default(),
+    )?;
+    let mut converted = vec![0; image.as_raw().len()];
+
+    transform.transform(image.as_raw(), &mut converted)?;
+    image.copy_from_slice(&converted);
+
+    Ok(())
+}
+
+/// Decode the image with its embedded ICC profile converted to sRGB. `None` if it has no profile.
+fn decode_to_srgb(bytes: &[u8]) -> Result<Option<DynamicImage>, image::ImageError> {
+    let mut decoder = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()?
+        .into_decoder()?;
+    let Some(icc_profile) = decoder.icc_profile()? else {
+        return Ok(None);
+    };
+    let mut image = DynamicImage::from_decoder(decoder)?.to_rgba8();
+
+    // E.g. a CMYK profile of a JPEG already converted to RGB by the decoder.
+    if let Err(e) = convert_to_srgb(&mut image, &icc_profile) {
+        warn!(
+            "Unable to convert the colors of the ICC profile to sRGB. {}",
+            e
+        );
+    }
+
+    Ok(Some(DynamicImage::ImageRgba8(image)))
+}
+
+/// Apply the color management setting to the loader of the tiles when it is changed, and load the
+/// tiles shown again with it.
+pub(crate) fn color_management_system(
+    app_settings: Res<AppSettings>,
+    color_management: Res<ColorManagement>,
+    tile_query: Query<&Tile>,
+    asset_server: Res<AssetServer>,
+) {
+    if !color_management.set_enabled(app_settings.color_management) {
+        return;
+    }
+
+    info!("Color management {}", app_settings.color_management);
+
+    for path in tile_query
+        .iter()
+        .filter_map(|x| x.bevy_image.as_ref()?.path())
+    {
+        asset_server.reload(path.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat as ImageCrateFormat, Rgba};
+
+    #[test]
+    fn test_decode_to_srgb_without_profile() {
+        let mut bytes = Vec::new();
+
+        DynamicImage::ImageRgba8(RgbaImage::new(2, 2))
+            .write_to(&mut Cursor::new(&mut bytes), ImageCrateFormat::Png)
+            .expect("should encode the image");
+
+        assert!(
+            decode_to_srgb(&bytes)
+                .expect("should decode the image")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_convert_to_srgb_with_invalid_profile() {
+        let mut image = RgbaImage::from_pixel(2, 2, Rgba([10, 20, 30, 255]));
+
+        assert!(convert_to_srgb(&mut image, b"not a profile").is_err());
+        assert_eq!(image.get_pixel(1, 1), &Rgba([10, 20, 30, 255]));
+    }
+}
@@ -0,0 +1,337 @@
+use crate::{
+    AppState,
+    camera::main_camera::MainCamera2d,
+    redraw_governor::{RedrawActivity, RedrawGovernor},
+    rendering::{overlay_image::OverlayImage, tile::TileLayer, tile::TileModState},
+};
+use bevy::{
+    asset::{Asset, AssetPath, embedded_asset, embedded_path},
+    camera::{ClearColorConfig, RenderTarget, ScalingMode, visibility::RenderLayers},
+    image::Image,
+    prelude::{
+        App, Assets, Camera, Camera2d, ChildOf, Color, Commands, Component, Entity, Handle,
+        LinearRgba, Local, Mesh, Mesh2d, MeshMaterial2d, Plugin, Projection, Query, Rectangle, Res,
+        ResMut, Single, Time, Transform, TypePath, Vec3, Visibility, With, Without,
+    },
+    render::render_resource::{AsBindGroup, Extent3d, TextureFormat},
+    shader::ShaderRef,
+    sprite_render::{AlphaMode2d, Material2d, Material2dPlugin},
+};
+
+/// Depth of the difference view in front of the tiles, relative to the main camera.
+const DIFFERENCE_VIEW_Z: f32 = 500.0;
+
+/// Render layer of the base image for the difference view.
+const BASE_RENDER_LAYER: usize = 1;
+
+/// Render layer of the overlay image for the difference view.
+const OVERLAY_RENDER_LAYER: usize = 2;
+
+/// Mode of comparing the current image with the overlay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum ComparisonMode {
+    /// The overlay is drawn with its opacity or the curtain.
+    #[default]
+    Off,
+    /// The overlay is shown and hidden in turn.
+    Blink,
+    /// The absolute difference of the colors of the two images.
+    Difference,
+}
+
+/// Comparison of the current image with the overlay, e.g. to spot the changes between imaging
+/// campaigns of an object.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct ImageComparison {
+    pub(crate) mode: ComparisonMode,
+    /// Number of times per second the blink switches between the images.
+    pub(crate) blink_rate: f32,
+    /// Multiplier of the difference, to make the small changes visible.
+    pub(crate) difference_gain: f32,
+}
+
+impl Default for ImageComparison {
+    fn default() -> Self {
+        Self {
+            mode: ComparisonMode::default(),
+            blink_rate: 2.0,
+            difference_gain: 4.0,
+        }
+    }
+}
+
+impl ImageComparison {
+    /// Whether the overlay is shown at the time in the blink mode.
+    pub(crate) fn is_blink_on(&self, elapsed_secs: f64) -> bool {
+        if self.blink_rate <= 0.0 {
+            return true;
+        }
+
+        (elapsed_secs * self.blink_rate as f64) as u64 % 2 == 0
+    }
+
+    /// Get the opacity of the overlay at the time, or `None` if it is not compared.
+    pub(crate) fn get_overlay_opacity(&self, elapsed_secs: f64) -> Option<f32> {
+        match self.mode {
+            ComparisonMode::Off => None,
+            ComparisonMode::Blink if self.is_blink_on(elapsed_secs) => Some(1.0),
+            ComparisonMode::Blink => Some(0.0),
+            ComparisonMode::Difference => Some(1.0),
+        }
+    }
+
+    /// Get the render layer of the tiles of the layer, drawn by the camera of the difference view
+    /// instead of the main camera.
+    pub(crate) fn get_render_layers(&self, layer: TileLayer) -> RenderLayers {
+        match (self.mode, layer) {
+            (ComparisonMode::Difference, TileLayer::Base) => RenderLayers::layer(BASE_RENDER_LAYER),
+            (ComparisonMode::Difference, TileLayer::Overlay) => {
+                RenderLayers::layer(OVERLAY_RENDER_LAYER)
+            }
+            _ => RenderLayers::default(),
+        }
+    }
+}
+
+/// Camera drawing the tiles of a layer into a texture for the difference view.
+#[derive(Component)]
+pub(crate) struct ComparisonCamera;
+
+/// Quad covering the viewport with the difference of the textures of the comparison cameras.
+#[derive(Component)]
+pub(crate) struct DifferenceView;
+
+/// Material drawing the absolute difference of the colors of the two textures.
+#[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
+pub(crate) struct DifferenceMaterial {
+    #[texture(0)]
+    #[sampler(1)]
+    base: Handle<Image>,
+    #[texture(2)]
+    #[sampler(3)]
+    overlay: Handle<Image>,
+    #[uniform(4)]
+    gain: LinearRgba,
+}
+
+impl Material2d for DifferenceMaterial {
+    fn fragment_shader() -> ShaderRef {
+        ShaderRef::Path(
+            AssetPath::from_path_buf(embedded_path!("comparison.wgsl")).with_source("embedded"),
+        )
+    }
+
+    fn alpha_mode(&self) -> AlphaMode2d {
+        AlphaMode2d::Opaque
+    }
+}
+
+/// Plugin of the material of the difference view.
+pub(crate) struct DifferenceMaterialPlugin;
+
+impl Plugin for DifferenceMaterialPlugin {
+    fn build(&self, app: &mut App) {
+        embedded_asset!(app, "comparison.wgsl");
+
+        app.add_plugins(Material2dPlugin::<DifferenceMaterial>::default());
+    }
+}
+
+/// Set up the cameras drawing the two images into the textures, and the quad drawing their
+/// difference in front of the main camera.
+pub(crate) fn setup_comparison(
+    mut commands: Commands,
+    main_camera: Single<Entity, With<MainCamera2d>>,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<DifferenceMaterial>>,
+) {
+    let base = images.add(Image::new_target_texture(
+        1,
+        1,
+        TextureFormat::Rgba8UnormSrgb,
+    ));
+    let overlay = images.add(Image::new_target_texture(
+        1,
+        1,
+        TextureFormat::Rgba8UnormSrgb,
+    ));
+
+    for (order, render_layer, image) in [
+        (-2, BASE_RENDER_LAYER, &base),
+        (-1, OVERLAY_RENDER_LAYER, &overlay),
+    ] {
+        commands.spawn((
+            ComparisonCamera,
+            Camera2d,
+            Camera {
+                target: RenderTarget::Image(image.clone().into()),
+                order,
+                is_active: false,
+                clear_color: ClearColorConfig::Custom(Color::BLACK),
+                ..Default::default()
+            },
+            RenderLayers::layer(render_layer),
+            ChildOf(*main_camera),
+        ));
+    }
+
+    commands.spawn((
+        DifferenceView,
+        Mesh2d(meshes.add(Rectangle::new(1.0, 1.0))),
+        MeshMaterial2d(materials.add(DifferenceMaterial {
+            base,
+            overlay,
+            gain: LinearRgba::WHITE,
+        })),
+        Transform::from_xyz(0.0, 0.0, DIFFERENCE_VIEW_Z),
+        Visibility::Hidden,
+        ChildOf(*main_camera),
+    ));
+}
+
+/// Update the tiles at each switch of the blink, and keep the frames going while blinking.
+pub(crate) fn blink_system(
+    app_state: Res<AppState>,
+    time: Res<Time>,
+    mut blink_on: Local<bool>,
+    mut tile_mod_state: ResMut<TileModState>,
+    mut redraw_governor: ResMut<RedrawGovernor>,
+) {
+    if app_state.comparison.mode != ComparisonMode::Blink
+        || app_state.overlay_choice_index.is_none()
+    {
+        return;
+    }
+
+    let is_on = app_state.comparison.is_blink_on(time.elapsed_secs_f64());
+
+    if is_on != *blink_on {
+        *blink_on = is_on;
+        tile_mod_state.invalidate();
+    }
+
+    redraw_governor.report(RedrawActivity::Animation);
+}
+
+/// Follow the view of the main camera with the comparison cameras and the difference view, with
+/// the textures of the size of the viewport.
+#[allow(clippy::type_complexity)]
+pub(crate) fn difference_view_system(
+    app_state: Res<AppState>,
+    overlay_query: Query<(), With<OverlayImage>>,
+    main_camera: Single<(&Camera, &Projection), With<MainCamera2d>>,
+    comparison_cameras: Query<
+        (&mut Camera, &mut Projection),
+        (With<ComparisonCamera>, Without<MainCamera2d>),
+    >,
+    difference_view: Single<
+        (
+            &mut Transform,
+            &mut Visibility,
+            &MeshMaterial2d<DifferenceMaterial>,
+        ),
+        With<DifferenceView>,
+    >,
+    mut images: ResMut<Assets<Image>>,
+    mut materials: ResMut<Assets<DifferenceMaterial>>,
+) {
+    let (main_camera, main_projection) = main_camera.into_inner();
+    let (mut transform, mut visibility, material) = difference_view.into_inner();
+    let is_active = main_camera.is_active
+        && app_state.comparison.mode == ComparisonMode::Difference
+        && !overlay_query.is_empty();
+
+    visibility.set_if_neq(if is_active {
+        Visibility::Visible
+    } else {
+        Visibility::Hidden
+    });
+
+    let (Projection::Orthographic(main_projection), Some(viewport_size)) =
+        (main_projection, main_camera.physical_viewport_size())
+    else {
+        return;
+    };
+
+    // Cover the area of the world in the viewport.
+    let area = main_projection.area;
+
+    transform.set_if_neq(
+        Transform::from_translation(area.center().extend(DIFFERENCE_VIEW_Z)).with_scale(Vec3::new(
+            area.width(),
+            area.height(),
+            1.0,
+        )),
+    );
+
+    let Some(gain) = materials.get(material.id()).map(|x| x.gain.red) else {
+        return;
+    };
+    let mut is_material_changed = gain != app_state.comparison.difference_gain;
+    let size = Extent3d {
+        width: viewport_size.x,
+        height: viewport_size.y,
+        ..Default::default()
+    };
+
+    for (mut camera, mut projection) in comparison_cameras {
+        camera.is_active = is_active;
+
+        if !is_active {
+            continue;
+        }
+
+        // The same area of the world as the main camera, regardless of the size of the texture.
+        if let Projection::Orthographic(projection) = projection.as_mut() {
+            projection.scaling_mode = ScalingMode::Fixed {
+                width: area.width(),
+                height: area.height(),
+            };
+            projection.scale = 1.0;
+            projection.viewport_origin = main_projection.viewport_origin;
+        }
+
+        if let RenderTarget::Image(target) = &camera.target
+            && let Some(image) = images.get_mut(target.handle.id())
+            && image.texture_descriptor.size != size
+        {
+            image.resize(size);
+            is_material_changed = true;
+        }
+    }
+
+    // Bind the resized textures and the gain again.
+    if is_material_changed && let Some(material) = materials.get_mut(material.id()) {
+        let gain = app_state.comparison.difference_gain;
+
+        material.gain = LinearRgba::rgb(gain, gain, gain);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_overlay_opacity() {
+        let mut comparison = ImageComparison {
+            mode: ComparisonMode::Blink,
+            blink_rate: 2.0,
+            difference_gain: 1.0,
+        };
+
+        assert_eq!(comparison.get_overlay_opacity(0.1), Some(1.0));
+        assert_eq!(comparison.get_overlay_opacity(0.6), Some(0.0));
+        assert_eq!(comparison.get_overlay_opacity(1.1), Some(1.0));
+
+        comparison.blink_rate = 0.0;
+        assert_eq!(comparison.get_overlay_opacity(0.6), Some(1.0));
+
+        comparison.mode = ComparisonMode::Difference;
+        assert_eq!(comparison.get_overlay_opacity(0.6), Some(1.0));
+
+        comparison.mode = ComparisonMode::Off;
+        assert_eq!(comparison.get_overlay_opacity(0.6), None);
+    }
+}
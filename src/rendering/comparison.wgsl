@@ -0,0 +1,17 @@
+// Absolute difference of the colors of the current image and the overlay, scaled by the gain.
+
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(0) var base_texture: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(1) var base_sampler: sampler;
+@group(#{MATERIAL_BIND_GROUP}) @binding(2) var overlay_texture: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(3) var overlay_sampler: sampler;
+@group(#{MATERIAL_BIND_GROUP}) @binding(4) var<uniform> gain: vec4<f32>;
+
+@fragment
+fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
+    let base = textureSample(base_texture, base_sampler, mesh.uv);
+    let overlay = textureSample(overlay_texture, overlay_sampler, mesh.uv);
+
+    return vec4(min(abs(base.rgb - overlay.rgb) * gain.rgb, vec3(1.0)), 1.0);
+}
@@ -0,0 +1,438 @@
+use crate::{
+    app::{app_settings::AppSettings, app_state::AppState},
+    camera::main_camera::MainCamera2d,
+    notification::UserNotification,
+    rendering::comparison::ComparisonMode,
+};
+use bevy::{
+    asset::{Asset, uuid_handle},
+    camera::{ClearColorConfig, RenderTarget, ScalingMode, visibility::RenderLayers},
+    image::Image,
+    prelude::{
+        App, Assets, Camera, Camera2d, ChildOf, Commands, Component, Entity, ExtractSchedule,
+        Handle, Mesh, Mesh2d, MeshMaterial2d, MessageWriter, Plugin, Projection, Rectangle, Res,
+        ResMut, Resource, Single, Time, Transform, TypePath, Vec3, Vec4, Visibility, With, Without,
+        debug, info, warn,
+    },
+    render::{
+        MainWorld, RenderApp,
+        render_resource::{
+            AsBindGroup, CachedPipeline, CachedPipelineState, Extent3d, PipelineCache,
+            PipelineDescriptor, TextureFormat,
+        },
+    },
+    shader::{Shader, ShaderRef},
+    sprite_render::{AlphaMode2d, Material2d, Material2dPlugin},
+};
+use std::time::{Duration, SystemTime};
+
+/// Interval between the checks of the file of the shader for the changes, to load it again as it
+/// is edited.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Depth of the view of the effect in front of the tiles and the difference view, relative to the
+/// main camera.
+const EFFECT_VIEW_Z: f32 = 600.0;
+
+/// Render layer of the view of the effect, drawn by the main camera but not by the camera of the
+/// effect drawing its texture.
+const EFFECT_VIEW_RENDER_LAYER: usize = 3;
+
+/// Order of the camera of the effect, before the main camera.
+const EFFECT_CAMERA_ORDER: isize = -3;
+
+/// Handle of the shader of the effect, replaced when its file is loaded.
+const EFFECT_SHADER_HANDLE: Handle<Shader> = uuid_handle!("6c1f3b0e-58d2-4a9e-9f51-2b7d0c4e8a13");
+
+/// State of the shader of the effect.
+#[derive(Debug, Clone, PartialEq, Default)]
+enum ShaderState {
+    /// No shader, and the effect is not applied.
+    #[default]
+    None,
+    /// The shader is loaded, and its pipelines are compiled as the effect is drawn.
+    Compiling {
+        /// Whether the pipelines are still of the previous shader, until the new one is
+        /// extracted to the render world.
+        is_stale: bool,
+    },
+    /// The pipelines of the shader are compiled, and the effect is applied.
+    Compiled,
+    /// The pipelines of the shader failed to compile, with the error to report.
+    Failed(String),
+}
+
+/// Name of the shader registered by the app, in place of the path of a file.
+const REGISTERED_SHADER_NAME: &str = "registered effect shader";
+
+/// Custom effect of the shader file of the settings, or else of the shader registered by the app.
+#[derive(Resource, Default)]
+pub(crate) struct CustomEffect {
+    /// WGSL source of the shader registered by the app, applied while no file is set.
+    registered: Option<String>,
+    /// Path of the shader file. Empty if none, and `None` until the settings are checked.
+    path: Option<String>,
+    /// Time of the last change of the file loaded, to load it again when it is changed.
+    modified: Option<SystemTime>,
+    /// Time of the next check of the file.
+    next_poll: Duration,
+    state: ShaderState,
+}
+
+impl CustomEffect {
+    /// Create the effect with the WGSL source of the shader registered by the app, if any.
+    pub(crate) fn new(registered: Option<String>) -> Self {
+        Self {
+            registered,
+            ..Default::default()
+        }
+    }
+}
+
+/// Camera drawing the tiles into the texture of the effect.
+#[derive(Component)]
+pub(crate) struct EffectCamera;
+
+/// Quad covering the viewport with the texture of the tiles drawn by the shader of the effect.
+#[derive(Component)]
+pub(crate) struct EffectView;
+
+/// Material drawing the texture of the tiles by the custom shader.
+#[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
+pub(crate) struct EffectMaterial {
+    #[texture(0)]
+    #[sampler(1)]
+    view: Handle<Image>,
+    /// Size of the viewport in pixels in x and y.
+    #[uniform(2)]
+    params: Vec4,
+}
+
+impl Material2d for EffectMaterial {
+    fn fragment_shader() -> ShaderRef {
+        ShaderRef::Handle(EFFECT_SHADER_HANDLE)
+    }
+
+    fn alpha_mode(&self) -> AlphaMode2d {
+        AlphaMode2d::Opaque
+    }
+}
+
+/// Plugin of the material of the custom effect, a WGSL shader applied to the tiles drawn in the
+/// view, e.g. a false-color mapping, from a file or registered by the app.
+pub(crate) struct EffectMaterialPlugin;
+
+impl Plugin for EffectMaterialPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(Material2dPlugin::<EffectMaterial>::default());
+
+        // Nothing is rendered without a graphics device, e.g. in the headless tests.
+        if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app.add_systems(ExtractSchedule, check_effect_pipelines_system);
+        }
+    }
+}
+
+/// Set up the camera drawing the tiles into the texture, and the quad drawing it by the shader in
+/// front of the main camera.
+pub(crate) fn setup_custom_effect(
+    mut commands: Commands,
+    main_camera: Single<Entity, With<MainCamera2d>>,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<EffectMaterial>>,
+) {
+    let view = images.add(Image::new_target_texture(
+        1,
+        1,
+        TextureFormat::Rgba8UnormSrgb,
+    ));
+
+    commands.spawn((
+        EffectCamera,
+        Camera2d,
+        Camera {
+            target: RenderTarget::Image(view.clone().into()),
+            order: EFFECT_CAMERA_ORDER,
+            is_active: false,
+            clear_color: ClearColorConfig::Default,
+            ..Default::default()
+        },
+        ChildOf(*main_camera),
+    ));
+
+    commands.spawn((
+        EffectView,
+        Mesh2d(meshes.add(Rectangle::new(1.0, 1.0))),
+        MeshMaterial2d(materials.add(EffectMaterial {
+            view,
+            params: Vec4::ZERO,
+        })),
+        Transform::from_xyz(0.0, 0.0, EFFECT_VIEW_Z),
+        Visibility::Hidden,
+        RenderLayers::layer(EFFECT_VIEW_RENDER_LAYER),
+        ChildOf(*main_camera),
+    ));
+
+    // The main camera draws the view of the effect over the tiles.
+    commands
+        .entity(*main_camera)
+        .insert(RenderLayers::default().with(EFFECT_VIEW_RENDER_LAYER));
+}
+
+/// Get the time of the last change of the file, if known.
+fn get_modified(path: &str) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|x| x.modified()).ok()
+}
+
+/// Load the shader of the file, replacing the one of the effect.
+fn load_shader(path: &str, shaders: &mut Assets<Shader>) -> Result<(), String> {
+    let source = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+
+    insert_shader(source, path, shaders)
+}
+
+/// Insert the WGSL source of the shader, replacing the one of the effect.
+fn insert_shader(source: String, path: &str, shaders: &mut Assets<Shader>) -> Result<(), String> {
+    shaders
+        .insert(
+            EFFECT_SHADER_HANDLE.id(),
+            Shader::from_wgsl(source, path.to_string()),
+        )
+        .map_err(|e| e.to_string())
+}
+
+/// Whether the pipeline draws by the shader of the effect.
+fn is_effect_pipeline(pipeline: &CachedPipeline) -> bool {
+    matches!(
+        &pipeline.descriptor,
+        PipelineDescriptor::RenderPipelineDescriptor(x)
+            if x.fragment.as_ref().is_some_and(|x| x.shader.id() == EFFECT_SHADER_HANDLE.id())
+    )
+}
+
+/// Follow the compilation of the pipelines of the shader of the effect in the render world, so
+/// that the effect is applied once they are compiled, or their errors are reported.
+pub(crate) fn check_effect_pipelines_system(
+    mut main_world: ResMut<MainWorld>,
+    cache: Res<PipelineCache>,
+) {
+    let Some(mut custom_effect) = main_world.get_resource_mut::<CustomEffect>() else {
+        return;
+    };
+    let ShaderState::Compiling { is_stale } = custom_effect.state else {
+        return;
+    };
+
+    // The shader is extracted after this, and the pipelines are compiled again from the next
+    // frame.
+    if is_stale {
+        custom_effect.state = ShaderState::Compiling { is_stale: false };
+        return;
+    }
+
+    let mut state = None;
+
+    for pipeline in cache.pipelines().filter(|x| is_effect_pipeline(x)) {
+        match &pipeline.state {
+            CachedPipelineState::Ok(_) => {
+                state.get_or_insert(ShaderState::Compiled);
+            }
+            CachedPipelineState::Err(e) => {
+                state = Some(ShaderState::Failed(e.to_string()));
+                break;
+            }
+            // Still compiling.
+            _ => return,
+        }
+    }
+
+    // None yet until the effect is drawn.
+    if let Some(state) = state {
+        if state == ShaderState::Compiled {
+            info!("Compiled the custom effect shader");
+        }
+        custom_effect.state = state;
+    }
+}
+
+/// Load the shader file of the settings when it is set or changed, or else the shader registered
+/// by the app, and follow the view of the main camera with the camera of the effect and its view,
+/// with the texture of the size of the viewport.
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+pub(crate) fn custom_effect_system(
+    mut custom_effect: ResMut<CustomEffect>,
+    app_settings: Res<AppSettings>,
+    app_state: Res<AppState>,
+    time: Res<Time>,
+    mut notification_writer: MessageWriter<UserNotification>,
+    main_camera: Single<(&Camera, &Projection), With<MainCamera2d>>,
+    effect_camera: Single<
+        (&mut Camera, &mut Projection),
+        (With<EffectCamera>, Without<MainCamera2d>),
+    >,
+    effect_view: Single<
+        (
+            &mut Transform,
+            &mut Visibility,
+            &MeshMaterial2d<EffectMaterial>,
+        ),
+        With<EffectView>,
+    >,
+    mut shaders: ResMut<Assets<Shader>>,
+    mut images: ResMut<Assets<Image>>,
+    mut materials: ResMut<Assets<EffectMaterial>>,
+) {
+    let path = app_settings.effect_shader_path.trim();
+    let name = if path.is_empty() {
+        REGISTERED_SHADER_NAME
+    } else {
+        path
+    };
+    let is_path_changed = custom_effect.path.as_deref() != Some(path);
+
+    if is_path_changed || (!path.is_empty() && time.elapsed() >= custom_effect.next_poll) {
+        custom_effect.next_poll = time.elapsed() + POLL_INTERVAL;
+
+        let modified = get_modified(path);
+
+        if is_path_changed || modified != custom_effect.modified {
+            custom_effect.path = Some(path.to_string());
+            custom_effect.modified = modified;
+            custom_effect.state = ShaderState::None;
+
+            let loaded = if path.is_empty() {
+                custom_effect.registered.clone().map(|source| {
+                    info!("Loading the custom effect shader of the app");
+                    insert_shader(source, name, &mut shaders)
+                })
+            } else if modified.is_none() {
+                // Not there yet, e.g. while the path is typed, and checked again at the interval.
+                debug!("Custom effect shader {:?} not found", path);
+                None
+            } else {
+                info!("Loading the custom effect shader {:?}", path);
+                Some(load_shader(path, &mut shaders))
+            };
+
+            match loaded {
+                Some(Ok(())) => custom_effect.state = ShaderState::Compiling { is_stale: true },
+                Some(Err(e)) => {
+                    warn!("Failed to load the custom effect shader {:?}. {}", name, e);
+                    notification_writer.write(UserNotification::Error(format!(
+                        "Failed to load the effect shader '{}'.\n{}",
+                        name, e
+                    )));
+                }
+                None => {}
+            }
+        }
+    }
+
+    if let ShaderState::Failed(e) = &custom_effect.state {
+        warn!(
+            "Failed to compile the custom effect shader {:?}. {}",
+            name, e
+        );
+        notification_writer.write(UserNotification::Error(format!(
+            "Failed to compile the effect shader '{}'.\n{}",
+            name, e
+        )));
+        custom_effect.state = ShaderState::None;
+    }
+
+    let (main_camera, main_projection) = main_camera.into_inner();
+    let (mut camera, mut projection) = effect_camera.into_inner();
+    let (mut transform, mut visibility, material) = effect_view.into_inner();
+    // The effect is drawn while its pipelines are compiled, so that they are queued. It is not
+    // applied to the difference view, whose tiles are not drawn by the camera of the effect.
+    let is_active = main_camera.is_active
+        && matches!(
+            custom_effect.state,
+            ShaderState::Compiling { .. } | ShaderState::Compiled
+        )
+        && app_state.comparison.mode != ComparisonMode::Difference;
+
+    camera.is_active = is_active;
+    visibility.set_if_neq(if is_active {
+        Visibility::Visible
+    } else {
+        Visibility::Hidden
+    });
+
+    let (true, Projection::Orthographic(main_projection), Some(viewport_size)) = (
+        is_active,
+        main_projection,
+        main_camera.physical_viewport_size(),
+    ) else {
+        return;
+    };
+
+    // Cover the area of the world in the viewport.
+    let area = main_projection.area;
+
+    transform.set_if_neq(
+        Transform::from_translation(area.center().extend(EFFECT_VIEW_Z)).with_scale(Vec3::new(
+            area.width(),
+            area.height(),
+            1.0,
+        )),
+    );
+
+    // The same area of the world as the main camera, regardless of the size of the texture.
+    if let Projection::Orthographic(projection) = projection.as_mut() {
+        projection.scaling_mode = ScalingMode::Fixed {
+            width: area.width(),
+            height: area.height(),
+        };
+        projection.scale = 1.0;
+        projection.viewport_origin = main_projection.viewport_origin;
+    }
+
+    let size = Extent3d {
+        width: viewport_size.x,
+        height: viewport_size.y,
+        ..Default::default()
+    };
+
+    // Bind the resized texture again.
+    if let RenderTarget::Image(target) = &camera.target
+        && let Some(image) = images.get_mut(target.handle.id())
+        && image.texture_descriptor.size != size
+    {
+        image.resize(size);
+
+        if let Some(material) = materials.get_mut(material.id()) {
+            material.params = Vec4::new(viewport_size.x as f32, viewport_size.y as f32, 0.0, 0.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_shader() {
+        let path = std::env::temp_dir().join("rs_iiif_browser_test_effect.wgsl");
+        let path = path.to_str().expect("temp dir should be UTF-8");
+        let mut shaders = Assets::<Shader>::default();
+
+        let _ = std::fs::remove_file(path);
+        assert!(get_modified(path).is_none());
+        assert!(load_shader(path, &mut shaders).is_err());
+        assert!(shaders.get(EFFECT_SHADER_HANDLE.id()).is_none());
+
+        std::fs::write(
+            path,
+            "@fragment fn fragment() -> @location(0) vec4<f32> { return vec4(1.0); }",
+        )
+        .expect("should be able to write the shader");
+
+        assert!(get_modified(path).is_some());
+        assert!(load_shader(path, &mut shaders).is_ok());
+        assert!(shaders.get(EFFECT_SHADER_HANDLE.id()).is_some());
+
+        let _ = std::fs::remove_file(path);
+    }
+}
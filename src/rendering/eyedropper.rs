@@ -0,0 +1,178 @@
+use crate::rendering::{
+    tile::{Tile, TileLayer, TileLoading},
+    tiled_image::TiledImage,
+};
+use bevy::prelude::{
+    Assets, Image, Query, Rect, Res, ResMut, Resource, Single, UVec2, Vec2, Without,
+};
+use std::collections::VecDeque;
+
+/// Max number of the colors picked kept in the history.
+const MAX_HISTORY: usize = 8;
+
+/// Color of a pixel of the image read back from the texture of a loaded tile.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct PixelSample {
+    /// Position of the pixel in the full size image.
+    pub(crate) position: UVec2,
+    /// Color in sRGB with the alpha.
+    pub(crate) color: [u8; 4],
+    /// Pixels of the full size image per pixel of the texture sampled, 1 at the full size level.
+    pub(crate) scale: f32,
+}
+
+impl PixelSample {
+    /// Get the color in hex, e.g. "#ff8000", with the alpha only if not opaque.
+    pub(crate) fn get_hex(&self) -> String {
+        let [r, g, b, a] = self.color;
+
+        match a {
+            255 => format!("#{:02x}{:02x}{:02x}", r, g, b),
+            a => format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a),
+        }
+    }
+}
+
+/// Eyedropper reporting the color and the position of the pixel of the image under the cursor,
+/// e.g. for the conservators checking the colors of a target.
+#[derive(Resource, Default)]
+pub(crate) struct Eyedropper {
+    pub(crate) enabled: bool,
+    /// Position of the cursor in the world, set by the UI over the image. `None` if the cursor is
+    /// elsewhere.
+    pub(crate) world_position: Option<Vec2>,
+    /// Pixel under the cursor, if its tile is loaded.
+    pub(crate) sample: Option<PixelSample>,
+    /// Colors picked, the latest first.
+    pub(crate) history: VecDeque<PixelSample>,
+}
+
+impl Eyedropper {
+    /// Keep the pixel under the cursor in the history, moving it to the front if already there.
+    pub(crate) fn pick(&mut self) {
+        let Some(sample) = self.sample else {
+            return;
+        };
+
+        self.history.retain(|x| x.position != sample.position);
+        self.history.push_front(sample);
+        self.history.truncate(MAX_HISTORY);
+    }
+}
+
+/// Get the pixel of the texture covering the requested region at the position in the full size
+/// image. `None` if the position is outside of the region.
+fn get_texture_pixel(request_position: Rect, texture_size: UVec2, position: Vec2) -> Option<UVec2> {
+    if !request_position.contains(position) || request_position.is_empty() {
+        return None;
+    }
+
+    let pixel =
+        (position - request_position.min) / request_position.size() * texture_size.as_vec2();
+
+    Some(
+        pixel
+            .as_uvec2()
+            .min(texture_size.saturating_sub(UVec2::ONE)),
+    )
+}
+
+/// Read the pixel under the cursor from the loaded tile of the finest level covering it.
+pub(crate) fn eyedropper_system(
+    mut eyedropper: ResMut<Eyedropper>,
+    tiled_image: Option<Single<&TiledImage>>,
+    tiles: Query<&Tile, Without<TileLoading>>,
+    images: Res<Assets<Image>>,
+) {
+    let (Some(tiled_image), Some(world_position), true) =
+        (tiled_image, eyedropper.world_position, eyedropper.enabled)
+    else {
+        if eyedropper.sample.is_some() {
+            eyedropper.sample = None;
+        }
+        return;
+    };
+
+    let position = tiled_image.world_to_source(world_position.extend(0.0));
+    let sample = tiles
+        .iter()
+        .filter(|x| {
+            x.layer == TileLayer::Base
+                && x.endpoint == tiled_image.get_endpoint()
+                && x.request_position.contains(position)
+        })
+        .filter_map(|tile| {
+            let image = images.get(tile.bevy_image.as_ref()?)?;
+            let texture_size = image.size();
+            let pixel = get_texture_pixel(tile.request_position, texture_size, position)?;
+            let color = image.get_color_at(pixel.x, pixel.y).ok()?;
+
+            Some(PixelSample {
+                position: position.as_uvec2(),
+                color: color.to_srgba().to_u8_array(),
+                scale: tile.request_position.width() / texture_size.x.max(1) as f32,
+            })
+        })
+        .min_by(|a, b| a.scale.total_cmp(&b.scale));
+
+    if eyedropper.sample != sample {
+        eyedropper.sample = sample;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_texture_pixel() {
+        let request_position = Rect::new(512.0, 0.0, 1024.0, 512.0);
+        let texture_size = UVec2::new(256, 256);
+
+        assert_eq!(
+            get_texture_pixel(request_position, texture_size, Vec2::new(512.0, 0.0)),
+            Some(UVec2::new(0, 0))
+        );
+        assert_eq!(
+            get_texture_pixel(request_position, texture_size, Vec2::new(700.0, 100.0)),
+            Some(UVec2::new(94, 50))
+        );
+        // The last pixel at the edge.
+        assert_eq!(
+            get_texture_pixel(request_position, texture_size, Vec2::new(1024.0, 512.0)),
+            Some(UVec2::new(255, 255))
+        );
+        assert_eq!(
+            get_texture_pixel(request_position, texture_size, Vec2::new(100.0, 100.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_pick() {
+        let mut eyedropper = Eyedropper::default();
+        let sample = |x: u32| PixelSample {
+            position: UVec2::new(x, 0),
+            color: [255, 128, 0, 255],
+            scale: 1.0,
+        };
+
+        eyedropper.pick();
+        assert!(eyedropper.history.is_empty());
+
+        for x in 0..10 {
+            eyedropper.sample = Some(sample(x));
+            eyedropper.pick();
+        }
+
+        assert_eq!(eyedropper.history.len(), MAX_HISTORY);
+        assert_eq!(eyedropper.history[0], sample(9));
+
+        // Moved to the front when picked again.
+        eyedropper.sample = Some(sample(5));
+        eyedropper.pick();
+        assert_eq!(eyedropper.history.len(), MAX_HISTORY);
+        assert_eq!(eyedropper.history[0], sample(5));
+        assert_eq!(eyedropper.history[0].get_hex(), "#ff8000");
+    }
+}
@@ -0,0 +1,118 @@
+use crate::{app::app_settings::AppSettings, notification::UserNotification};
+use bevy::{
+    prelude::{Commands, MessageWriter, Res, ResMut, Resource, info},
+    render::renderer::{RenderAdapterInfo, RenderDevice},
+};
+
+/// Max number of the tiles in the cache with WebGL2, which has less memory for the textures.
+const WEBGL2_MAX_CACHE_ITEMS: usize = 200;
+
+/// Graphics backend of the renderer which matters to the limits of the textures.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum GraphicsBackend {
+    WebGpu,
+    /// WebGL2 in the browser, or OpenGL natively.
+    Gl,
+    /// Vulkan, Metal or DirectX 12.
+    Native,
+}
+
+impl GraphicsBackend {
+    /// Get the backend from its wgpu name, e.g. "webgpu".
+    fn from_name(name: &str) -> Self {
+        match name {
+            "webgpu" => Self::WebGpu,
+            "gl" => Self::Gl,
+            _ => Self::Native,
+        }
+    }
+}
+
+/// Capabilities of the graphics backend detected when the renderer is ready.
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub(crate) struct GpuCapabilities {
+    pub(crate) backend: GraphicsBackend,
+    /// Max width and height of the textures, which limits the size of the tiles.
+    pub(crate) max_texture_size: u32,
+}
+
+impl GpuCapabilities {
+    /// Get the max number of the tiles in the cache for the backend.
+    fn get_max_cache_items(&self, max_cache_items: usize) -> usize {
+        if cfg!(target_arch = "wasm32") && self.backend == GraphicsBackend::Gl {
+            max_cache_items.min(WEBGL2_MAX_CACHE_ITEMS)
+        } else {
+            max_cache_items
+        }
+    }
+}
+
+/// Detect the capabilities of the graphics backend once it is ready, and limit the tile cache
+/// with a note when the browser runs with WebGL2 instead of WebGPU.
+pub(crate) fn detect_gpu_capabilities_system(
+    mut commands: Commands,
+    adapter_info: Option<Res<RenderAdapterInfo>>,
+    render_device: Option<Res<RenderDevice>>,
+    mut app_settings: ResMut<AppSettings>,
+    mut messages: MessageWriter<UserNotification>,
+) {
+    let (Some(adapter_info), Some(render_device)) = (adapter_info, render_device) else {
+        return;
+    };
+
+    let capabilities = GpuCapabilities {
+        backend: GraphicsBackend::from_name(adapter_info.backend.to_str()),
+        max_texture_size: render_device.limits().max_texture_dimension_2d,
+    };
+
+    info!(
+        "Graphics backend {:?} ({}) with the max texture size {}",
+        capabilities.backend, adapter_info.name, capabilities.max_texture_size
+    );
+
+    let max_cache_items = capabilities.get_max_cache_items(app_settings.max_cache_items);
+
+    if max_cache_items != app_settings.max_cache_items {
+        app_settings.max_cache_items = max_cache_items;
+        messages.write(UserNotification::Info(format!(
+            "The graphics run with WebGL2, so up to {} tiles are cached and the tiles are limited \
+            to {} px.",
+            max_cache_items, capabilities.max_texture_size
+        )));
+    }
+
+    commands.insert_resource(capabilities);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_graphics_backend() {
+        assert_eq!(
+            GraphicsBackend::from_name("webgpu"),
+            GraphicsBackend::WebGpu
+        );
+        assert_eq!(GraphicsBackend::from_name("gl"), GraphicsBackend::Gl);
+        assert_eq!(
+            GraphicsBackend::from_name("vulkan"),
+            GraphicsBackend::Native
+        );
+
+        let capabilities = GpuCapabilities {
+            backend: GraphicsBackend::Gl,
+            max_texture_size: 2048,
+        };
+
+        // Only limited in the browser.
+        assert_eq!(
+            capabilities.get_max_cache_items(1000),
+            if cfg!(target_arch = "wasm32") {
+                WEBGL2_MAX_CACHE_ITEMS
+            } else {
+                1000
+            }
+        );
+    }
+}
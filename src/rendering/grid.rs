@@ -0,0 +1,125 @@
+use crate::{
+    app::app_settings::AppSettings,
+    camera::{camera_ext, main_camera::MainCamera2d},
+    rendering::tiled_image::TiledImage,
+};
+use bevy::prelude::{Camera, Color, Gizmos, GlobalTransform, Rect, Res, Single, Vec2, With};
+
+/// Color of the lines of the grid, translucent to keep the image readable.
+const GRID_COLOR: Color = Color::srgba(0.0, 0.8, 1.0, 0.5);
+
+/// Max number of the lines of the grid along an axis, above which the spacing is doubled so
+/// that the image zoomed out is not covered by the lines.
+const MAX_GRID_LINES: usize = 200;
+
+/// Get the positions at the multiples of the spacing between the min and the max, the spacing
+/// doubled until there are fewer than the max number of positions.
+pub(crate) fn get_grid_positions(min: f32, max: f32, spacing: f32, max_lines: usize) -> Vec<f32> {
+    if spacing <= 0.0 || max < min {
+        return Vec::new();
+    }
+
+    let mut spacing = spacing;
+
+    while (max - min) / spacing >= max_lines.max(1) as f32 {
+        spacing *= 2.0;
+    }
+
+    let first = (min / spacing).ceil() as i64;
+    let last = (max / spacing).floor() as i64;
+
+    (first..=last).map(|x| x as f32 * spacing).collect()
+}
+
+/// Get the spacing of the ticks of the rulers in the steps of 1, 2 and 5, e.g. 50 or 100, at
+/// least the min spacing and 1 pixel.
+pub(crate) fn get_ruler_spacing(min_spacing: f32) -> f32 {
+    if !min_spacing.is_finite() || min_spacing <= 1.0 {
+        return 1.0;
+    }
+
+    let magnitude = 10f32.powi(min_spacing.log10().floor() as i32);
+
+    [1.0, 2.0, 5.0]
+        .into_iter()
+        .map(|x| x * magnitude)
+        .find(|x| *x >= min_spacing)
+        .unwrap_or(10.0 * magnitude)
+}
+
+/// Draw the grid over the image in view at the spacing of the settings, above the tiles.
+pub(crate) fn grid_system(
+    mut gizmos: Gizmos,
+    app_settings: Res<AppSettings>,
+    tiled_image: Option<Single<&TiledImage>>,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera2d>>,
+) {
+    let grid_settings = app_settings.grid_settings;
+    let Some(tiled_image) = tiled_image.filter(|_| grid_settings.show_grid) else {
+        return;
+    };
+    let (camera, global_transform) = camera.into_inner();
+    let Some((world_pos_min, world_pos_max)) =
+        camera_ext::get_world_viewport_rect(camera, global_transform)
+    else {
+        return;
+    };
+    let view_rect = tiled_image
+        .world_to_image_rect(Rect::from_corners(
+            world_pos_min.truncate(),
+            world_pos_max.truncate(),
+        ))
+        .intersect(tiled_image.get_image_max_size_rect());
+
+    if view_rect.is_empty() {
+        return;
+    }
+
+    let spacing = grid_settings.grid_spacing as f32;
+    let mut line = |start: Vec2, end: Vec2| {
+        gizmos.line_2d(
+            tiled_image.image_to_world(start).truncate(),
+            tiled_image.image_to_world(end).truncate(),
+            GRID_COLOR,
+        );
+    };
+
+    for x in get_grid_positions(view_rect.min.x, view_rect.max.x, spacing, MAX_GRID_LINES) {
+        line(Vec2::new(x, view_rect.min.y), Vec2::new(x, view_rect.max.y));
+    }
+
+    for y in get_grid_positions(view_rect.min.y, view_rect.max.y, spacing, MAX_GRID_LINES) {
+        line(Vec2::new(view_rect.min.x, y), Vec2::new(view_rect.max.x, y));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_grid_positions() {
+        assert_eq!(
+            get_grid_positions(50.0, 420.0, 100.0, 200),
+            [100.0, 200.0, 300.0, 400.0]
+        );
+        assert_eq!(get_grid_positions(0.0, 100.0, 100.0, 200), [0.0, 100.0]);
+        // Doubled to fewer than the max number of lines.
+        assert_eq!(
+            get_grid_positions(0.0, 1000.0, 100.0, 4),
+            [0.0, 400.0, 800.0]
+        );
+        assert!(get_grid_positions(0.0, 100.0, 0.0, 200).is_empty());
+        assert!(get_grid_positions(100.0, 0.0, 10.0, 200).is_empty());
+    }
+
+    #[test]
+    fn test_get_ruler_spacing() {
+        assert_eq!(get_ruler_spacing(0.3), 1.0);
+        assert_eq!(get_ruler_spacing(1.5), 2.0);
+        assert_eq!(get_ruler_spacing(42.0), 50.0);
+        assert_eq!(get_ruler_spacing(60.0), 100.0);
+        assert_eq!(get_ruler_spacing(200.0), 200.0);
+        assert_eq!(get_ruler_spacing(f32::INFINITY), 1.0);
+    }
+}
@@ -0,0 +1,188 @@
+use crate::{
+    app::{app_settings::AppSettings, app_state::AppState},
+    camera::main_camera::MainCamera2d,
+    rendering::{
+        tile::{TileCache, TileIndex},
+        tiled_image::TiledImage,
+    },
+};
+use bevy::{
+    math::Affine2,
+    prelude::{
+        AssetServer, Handle, Image, Projection, Rect, Res, ResMut, Resource, Single, Vec2, With,
+    },
+};
+use std::ops::RangeInclusive;
+
+/// Radius in points of the lens of the magnifier.
+pub(crate) const LENS_RADIUS: f32 = 120.0;
+
+/// Range of the magnification of the lens.
+pub(crate) const MAGNIFICATION_RANGE: RangeInclusive<f32> = 2.0..=4.0;
+
+/// Tile of the higher-res level under the lens.
+pub(crate) struct LensTile {
+    pub(crate) index: TileIndex,
+    pub(crate) world_position: Rect,
+    /// Transform from the UV of the tile in the world to its texture, e.g. for a rotated image.
+    pub(crate) uv_transform: Affine2,
+    pub(crate) image: Handle<Image>,
+}
+
+/// Lens magnifying the image under the cursor in the low-vision mode, drawn by the UI from the
+/// tiles of the level for the magnified zoom.
+#[derive(Resource, Default)]
+pub(crate) struct Magnifier {
+    /// Centre of the lens in the world, set by the UI at the cursor over the image. `None` if the
+    /// lens is hidden.
+    pub(crate) world_centre: Option<Vec2>,
+    /// Endpoint of the image of the tiles.
+    endpoint: String,
+    pub(crate) tiles: Vec<LensTile>,
+}
+
+/// Get the points of the circle, starting from the right and going around by the segments.
+pub(crate) fn get_circle_points(centre: Vec2, radius: f32, segments: usize) -> Vec<Vec2> {
+    (0..segments)
+        .map(|i| {
+            let angle = i as f32 / segments as f32 * std::f32::consts::TAU;
+
+            centre + Vec2::from_angle(angle) * radius
+        })
+        .collect()
+}
+
+/// Clip the convex polygon to the rect, by each of its edges in turn. Empty if the polygon is
+/// outside of the rect.
+pub(crate) fn clip_polygon_to_rect(polygon: &[Vec2], rect: Rect) -> Vec<Vec2> {
+    let mut points = polygon.to_vec();
+
+    for (axis, bound, sign) in [
+        (0, rect.min.x, 1.0),
+        (0, rect.max.x, -1.0),
+        (1, rect.min.y, 1.0),
+        (1, rect.max.y, -1.0),
+    ] {
+        // Distance inside the edge, negative if outside.
+        let distance = |p: Vec2| (p[axis] - bound) * sign;
+        let input = std::mem::take(&mut points);
+
+        for (i, current) in input.iter().enumerate() {
+            let previous = input[(i + input.len() - 1) % input.len()];
+            let (d0, d1) = (distance(previous), distance(*current));
+
+            if (d0 >= 0.0) != (d1 >= 0.0) {
+                points.push(previous + (*current - previous) * (d0 / (d0 - d1)));
+            }
+            if d1 >= 0.0 {
+                points.push(*current);
+            }
+        }
+    }
+
+    points
+}
+
+/// Load the tiles under the lens at the level for the magnified zoom, keeping those already
+/// loaded as the lens moves.
+pub(crate) fn magnifier_system(
+    mut magnifier: ResMut<Magnifier>,
+    mut tile_cache: ResMut<TileCache>,
+    asset_server: Res<AssetServer>,
+    app_settings: Res<AppSettings>,
+    app_state: Res<AppState>,
+    tiled_image: Option<Single<&TiledImage>>,
+    projection: Single<&Projection, With<MainCamera2d>>,
+) {
+    let low_vision_settings = app_settings.low_vision_settings;
+    let (Some(tiled_image), Some(world_centre), Projection::Orthographic(orthographic), true) = (
+        tiled_image,
+        magnifier.world_centre,
+        *projection,
+        low_vision_settings.enabled && low_vision_settings.magnifier,
+    ) else {
+        if !magnifier.tiles.is_empty() {
+            magnifier.tiles.clear();
+        }
+        return;
+    };
+
+    let magnification = low_vision_settings
+        .magnification
+        .clamp(*MAGNIFICATION_RANGE.start(), *MAGNIFICATION_RANGE.end());
+    let world_zoom_scale = orthographic.scale / magnification;
+    let level = tiled_image.get_level_at(world_zoom_scale, app_state.scale_factor);
+    let world_radius = Vec2::splat(LENS_RADIUS * world_zoom_scale);
+    let (required_tiles, _, _) = tiled_image.get_required_tiles(
+        level,
+        (world_centre - world_radius).extend(0.0),
+        (world_centre + world_radius).extend(0.0),
+    );
+    let endpoint = tiled_image.get_endpoint();
+    let mut previous_tiles = if magnifier.endpoint == endpoint {
+        std::mem::take(&mut magnifier.tiles)
+    } else {
+        Vec::new()
+    };
+
+    magnifier.endpoint = endpoint.to_string();
+    magnifier.tiles = required_tiles
+        .into_iter()
+        .map(|tile| {
+            let image = match previous_tiles.iter().position(|x| x.index == tile.index) {
+                Some(i) => previous_tiles.swap_remove(i).image,
+                None => tile_cache.load_image(
+                    &asset_server,
+                    tiled_image.get_image_tile_url_at(tile.index, tile.request_position),
+                ),
+            };
+
+            LensTile {
+                index: tile.index,
+                world_position: tile.world_position,
+                uv_transform: tile.uv_transform,
+                image,
+            }
+        })
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_circle_points() {
+        let points = get_circle_points(Vec2::new(10.0, 10.0), 2.0, 4);
+
+        assert_eq!(points.len(), 4);
+        assert!(points[0].abs_diff_eq(Vec2::new(12.0, 10.0), 1e-5));
+        assert!(points[1].abs_diff_eq(Vec2::new(10.0, 12.0), 1e-5));
+        assert!(points[2].abs_diff_eq(Vec2::new(8.0, 10.0), 1e-5));
+    }
+
+    #[test]
+    fn test_clip_polygon_to_rect() {
+        let square = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(2.0, 0.0),
+            Vec2::new(2.0, 2.0),
+            Vec2::new(0.0, 2.0),
+        ];
+
+        assert_eq!(
+            clip_polygon_to_rect(&square, Rect::new(1.0, -1.0, 3.0, 3.0)),
+            [
+                Vec2::new(1.0, 0.0),
+                Vec2::new(2.0, 0.0),
+                Vec2::new(2.0, 2.0),
+                Vec2::new(1.0, 2.0),
+            ]
+        );
+        assert_eq!(
+            clip_polygon_to_rect(&square, Rect::new(-1.0, -1.0, 3.0, 3.0)),
+            square
+        );
+        assert!(clip_polygon_to_rect(&square, Rect::new(5.0, 5.0, 6.0, 6.0)).is_empty());
+    }
+}
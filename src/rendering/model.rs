@@ -11,6 +11,16 @@ pub(crate) trait IsImage {
     fn get_optional_sizes(&self) -> Vec<Size>;
     fn get_width(&self) -> u32;
     fn get_height(&self) -> u32;
+
+    /// Get the major version of the image API, e.g. 3. `None` if not of an image service.
+    fn get_api_version(&self) -> Option<u32> {
+        None
+    }
+
+    /// Get the compliance level of the image service, e.g. 2 for "level2", if declared.
+    fn get_compliance_level(&self) -> Option<u32> {
+        None
+    }
 }
 
 /// Trait that represents the profile details in an IIIF image needed by the TiledImage.
@@ -0,0 +1,103 @@
+use bevy::{
+    animation::{AnimationClip, AnimationPlayer, graph::AnimationNodeIndex},
+    prelude::{
+        AnimationGraph, AnimationGraphHandle, Assets, Commands, Entity, Gltf, Handle, Query, Res,
+        ResMut, Resource, With, Without,
+    },
+};
+
+/// Animation of the glTF model.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ModelAnimation {
+    pub(crate) name: String,
+    pub(crate) node: AnimationNodeIndex,
+    /// Duration in seconds.
+    pub(crate) duration: f32,
+}
+
+/// Animations of the glTF model played by its animation players.
+#[derive(Resource, Default)]
+pub(crate) struct ModelAnimations {
+    /// glTF asset of the model to read its animations from, until they are read.
+    gltf: Option<Handle<Gltf>>,
+    /// Graph of the animations given to the animation players of the model.
+    graph: Option<Handle<AnimationGraph>>,
+    pub(crate) items: Vec<ModelAnimation>,
+    /// Index of the animation being played or paused.
+    pub(crate) selected: usize,
+}
+
+impl ModelAnimations {
+    /// Start reading the animations of the glTF model.
+    pub(crate) fn new(gltf: Handle<Gltf>) -> Self {
+        Self {
+            gltf: Some(gltf),
+            ..Default::default()
+        }
+    }
+
+    /// Get the selected animation.
+    pub(crate) fn get_selected(&self) -> Option<&ModelAnimation> {
+        self.items.get(self.selected)
+    }
+}
+
+/// Get the names of the animations, or their positions if they are not named.
+fn get_animation_names(gltf: &Gltf) -> Vec<String> {
+    gltf.animations
+        .iter()
+        .enumerate()
+        .map(|(index, clip)| {
+            gltf.named_animations
+                .iter()
+                .find(|(_, x)| *x == clip)
+                .map_or_else(
+                    || format!("Animation {}", index + 1),
+                    |(x, _)| x.to_string(),
+                )
+        })
+        .collect()
+}
+
+/// Read the animations of the glTF model when it is loaded, and give them to the animation
+/// players of the model when they are spawned with the scene.
+pub(crate) fn model_animation_system(
+    mut commands: Commands,
+    mut model_animations: ResMut<ModelAnimations>,
+    gltf_assets: Res<Assets<Gltf>>,
+    clip_assets: Res<Assets<AnimationClip>>,
+    mut graph_assets: ResMut<Assets<AnimationGraph>>,
+    player_query: Query<Entity, (With<AnimationPlayer>, Without<AnimationGraphHandle>)>,
+) {
+    if let Some(gltf) = model_animations
+        .gltf
+        .as_ref()
+        .and_then(|x| gltf_assets.get(x))
+    {
+        let (graph, nodes) = AnimationGraph::from_clips(gltf.animations.iter().cloned());
+
+        model_animations.items = get_animation_names(gltf)
+            .into_iter()
+            .zip(nodes)
+            .zip(&gltf.animations)
+            .map(|((name, node), clip)| ModelAnimation {
+                name,
+                node,
+                duration: clip_assets.get(clip).map_or(0.0, |x| x.duration()),
+            })
+            .collect();
+        model_animations.graph =
+            (!model_animations.items.is_empty()).then(|| graph_assets.add(graph));
+        model_animations.gltf = None;
+    }
+
+    let Some(graph) = &model_animations.graph else {
+        return;
+    };
+
+    for entity in &player_query {
+        commands
+            .entity(entity)
+            .insert(AnimationGraphHandle(graph.clone()));
+    }
+}
@@ -1,15 +1,25 @@
-use crate::camera::{main_camera::MainCamera3d, pan_orbit_state_3d::PanOrbitState3d};
+use crate::{
+    camera::{main_camera::MainCamera3d, pan_orbit_state_3d::PanOrbitState3d},
+    rendering::model_animation::ModelAnimations,
+};
 use bevy::{
     asset::AssetId,
     camera::primitives::{Aabb, Sphere},
+    color::Color,
+    mesh::PrimitiveTopology,
     prelude::{
-        Add, AssetServer, Camera, Commands, Component, Entity, EulerRot, GlobalTransform,
-        GltfAssetLabel, Mesh3d, MessageWriter, On, Quat, Query, Remove, Res, ResMut, Result,
-        SceneRoot, Single, Transform, Vec3, Vec3A, With, info, warn,
+        Add, AssetServer, Assets, Camera, Commands, Component, Entity, Gizmos, GlobalTransform,
+        GltfAssetLabel, Mesh, Mesh3d, MeshMaterial3d, MessageWriter, On, Query, Remove, Res,
+        ResMut, Resource, Result, SceneRoot, Single, StandardMaterial, Transform, Vec3, Vec3A,
+        With, info, warn,
     },
     scene::Scene,
     window::RequestRedraw,
 };
+use std::collections::HashSet;
+
+/// Colour of the bounding box of the model.
+const BOUNDING_BOX_COLOR: Color = Color::srgb(1.0, 0.8, 0.2);
 
 #[derive(Component)]
 pub(crate) struct ModelLoading(pub(crate) AssetId<Scene>);
@@ -27,6 +37,38 @@ impl ModelImage {
     }
 }
 
+/// Statistics of the loaded model to validate the asset.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct ModelStats {
+    pub(crate) meshes: usize,
+    pub(crate) materials: usize,
+    pub(crate) triangles: usize,
+    /// Approximate bounding box of the model in the world.
+    pub(crate) aabb: Option<Aabb>,
+}
+
+/// Inspection tools of the 3D model, e.g. the bounding box.
+#[derive(Resource, Default)]
+pub(crate) struct ModelInspection {
+    pub(crate) show_bounding_box: bool,
+    pub(crate) show_stats: bool,
+    /// Statistics of the model, `None` until it is loaded.
+    pub(crate) stats: Option<ModelStats>,
+}
+
+/// Get the number of triangles of the mesh, 0 if it is not made of triangles, e.g. lines.
+fn get_triangle_count(mesh: &Mesh) -> usize {
+    let count = mesh
+        .indices()
+        .map_or_else(|| mesh.count_vertices(), |x| x.len());
+
+    match mesh.primitive_topology() {
+        PrimitiveTopology::TriangleList => count / 3,
+        PrimitiveTopology::TriangleStrip => count.saturating_sub(2),
+        _ => 0,
+    }
+}
+
 /// Handler when adding the model image.
 pub(crate) fn on_add_model_image(
     add: On<Add, ModelImage>,
@@ -41,6 +83,11 @@ pub(crate) fn on_add_model_image(
     let asset_3d =
         asset_server.load(GltfAssetLabel::Scene(0).from_asset(model_image.url.to_string()));
 
+    // Read the animations from the whole glTF asset.
+    commands.insert_resource(ModelAnimations::new(
+        asset_server.load(model_image.url.to_string()),
+    ));
+
     commands.spawn(ModelLoading(asset_3d.id()));
 
     commands.spawn(SceneRoot(asset_3d));
@@ -56,9 +103,13 @@ pub(crate) fn on_remove_model_image(
     mut commands: Commands,
     scene: Single<Entity, With<SceneRoot>>,
     model_loading: Query<Entity, With<ModelLoading>>,
+    mut model_inspection: ResMut<ModelInspection>,
 ) -> Result {
     info!("Model image removed (model_image). {:?}", remove.entity);
 
+    model_inspection.stats = None;
+    commands.insert_resource(ModelAnimations::default());
+
     // Despawn the scene.
     commands.entity(*scene).despawn();
     // Despawn the loading.
@@ -74,11 +125,28 @@ pub(crate) fn on_remove_model_image(
 pub(crate) fn on_remove_model_loading(
     remove: On<Remove, ModelLoading>,
     meshes: Query<(&GlobalTransform, Option<&Aabb>), With<Mesh3d>>,
+    mesh_handles: Query<(&Mesh3d, Option<&MeshMaterial3d<StandardMaterial>>)>,
+    mesh_assets: Res<Assets<Mesh>>,
     camera3d_query: Single<(&mut Camera, &mut Transform), With<MainCamera3d>>,
     mut current_state: ResMut<PanOrbitState3d>,
+    mut model_inspection: ResMut<ModelInspection>,
 ) {
     info!("Model loading removed (model_image). {:?}", remove.entity);
 
+    let mut stats = ModelStats::default();
+    let mut materials = HashSet::new();
+
+    for (mesh, material) in &mesh_handles {
+        stats.meshes += 1;
+        stats.triangles += mesh_assets.get(&mesh.0).map_or(0, get_triangle_count);
+
+        if let Some(material) = material {
+            materials.insert(material.0.id());
+        }
+    }
+
+    stats.materials = materials.len();
+
     let pan_orbit_state =
         if !meshes.is_empty() && !meshes.iter().any(|(_, maybe_aabb)| maybe_aabb.is_none()) {
             // Find an approximate bounding box of the scene from its meshes
@@ -102,6 +170,8 @@ pub(crate) fn on_remove_model_loading(
             let size = (max - min).length();
             let aabb = Aabb::from_min_max(Vec3::from(min), Vec3::from(max));
 
+            stats.aabb = Some(aabb);
+
             // Size cannot be 0 in PanOrbitState3d.
             if size != 0.0 {
                 info!(
@@ -124,8 +194,26 @@ pub(crate) fn on_remove_model_loading(
     camera3d.is_active = true;
 
     *current_state = pan_orbit_state;
+    current_state.update_transform(&mut transform);
+
+    model_inspection.stats = Some(stats);
+}
 
-    transform.rotation =
-        Quat::from_euler(EulerRot::YXZ, current_state.yaw, current_state.pitch, 0.0);
-    transform.translation = current_state.center + transform.back() * current_state.radius;
+/// Draw the bounding box of the model if enabled.
+pub(crate) fn model_inspection_system(mut gizmos: Gizmos, model_inspection: Res<ModelInspection>) {
+    if !model_inspection.show_bounding_box {
+        return;
+    }
+
+    if let Some(aabb) = model_inspection
+        .stats
+        .as_ref()
+        .and_then(|x| x.aabb.as_ref())
+    {
+        gizmos.cuboid(
+            Transform::from_translation(Vec3::from(aabb.center))
+                .with_scale(Vec3::from(aabb.half_extents) * 2.0),
+            BOUNDING_BOX_COLOR,
+        );
+    }
 }
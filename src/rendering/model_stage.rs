@@ -0,0 +1,164 @@
+use crate::{
+    app::app_settings::{AppSettings, ModelStageSettings},
+    rendering::model_image::ModelInspection,
+};
+use bevy::{
+    camera::primitives::Aabb,
+    light::{CascadeShadowConfigBuilder, NotShadowCaster, NotShadowReceiver},
+    prelude::{
+        Assets, Color, Commands, Component, DirectionalLight, Entity, EulerRot, Local, Mesh,
+        Mesh3d, MeshMaterial3d, Meshable, Plane3d, Quat, Query, Res, ResMut, Sphere,
+        StandardMaterial, Transform, Vec3, With,
+    },
+};
+
+/// Colour of the backdrop at the top.
+const BACKDROP_TOP_COLOR: [f32; 4] = [0.55, 0.57, 0.6, 1.0];
+/// Colour of the backdrop at the bottom.
+const BACKDROP_BOTTOM_COLOR: [f32; 4] = [0.12, 0.12, 0.13, 1.0];
+/// Radius of the backdrop as a multiple of the model size, enclosing the camera as it zooms out.
+const BACKDROP_RADIUS_MULTIPLE: f32 = 30.0;
+/// Size of the ground as a multiple of the model size.
+const GROUND_SIZE_MULTIPLE: f32 = 4.0;
+/// Colour of the ground.
+const GROUND_COLOR: Color = Color::srgb(0.35, 0.35, 0.37);
+/// Illuminance of the light casting the shadow of the model on the ground.
+const SHADOW_LIGHT_ILLUMINANCE: f32 = 2000.0;
+
+/// Backdrop or ground under the model, spawned with the model and the settings.
+#[derive(Component)]
+pub(crate) struct ModelStage;
+
+/// Get the backdrop sphere with its colour graded from the bottom to the top.
+fn get_backdrop_mesh() -> Mesh {
+    let mut mesh = Sphere::new(1.0).mesh().uv(32, 18);
+    let colors = mesh
+        .attribute(Mesh::ATTRIBUTE_POSITION)
+        .and_then(|x| x.as_float3())
+        .unwrap_or_default()
+        .iter()
+        .map(|[_, y, _]| {
+            let t = (y + 1.0) / 2.0;
+
+            std::array::from_fn::<f32, 4, _>(|i| {
+                BACKDROP_BOTTOM_COLOR[i] + (BACKDROP_TOP_COLOR[i] - BACKDROP_BOTTOM_COLOR[i]) * t
+            })
+        })
+        .collect::<Vec<_>>();
+
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+    mesh
+}
+
+/// Spawn the stage of the model by the settings.
+fn spawn_stage(
+    commands: &mut Commands,
+    app_settings: &AppSettings,
+    aabb: &Aabb,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+) {
+    let center = Vec3::from(aabb.center);
+    let half_extents = Vec3::from(aabb.half_extents);
+    let size = (half_extents * 2.0).length().max(f32::EPSILON);
+
+    if app_settings.model_stage_settings.backdrop {
+        commands.spawn((
+            ModelStage,
+            Mesh3d(meshes.add(get_backdrop_mesh())),
+            MeshMaterial3d(materials.add(StandardMaterial {
+                unlit: true,
+                // Seen from the inside.
+                cull_mode: None,
+                ..Default::default()
+            })),
+            Transform::from_translation(center)
+                .with_scale(Vec3::splat(size * BACKDROP_RADIUS_MULTIPLE)),
+            NotShadowCaster,
+            NotShadowReceiver,
+        ));
+    }
+
+    if app_settings.model_stage_settings.ground_shadow {
+        let ground_size = size * GROUND_SIZE_MULTIPLE;
+
+        commands.spawn((
+            ModelStage,
+            Mesh3d(meshes.add(Plane3d::default().mesh().size(ground_size, ground_size))),
+            MeshMaterial3d(materials.add(StandardMaterial {
+                base_color: GROUND_COLOR,
+                perceptual_roughness: 1.0,
+                ..Default::default()
+            })),
+            // Just under the model to avoid overlapping its base.
+            Transform::from_translation(Vec3::new(
+                center.x,
+                center.y - half_extents.y - size * 0.001,
+                center.z,
+            )),
+            NotShadowCaster,
+        ));
+
+        // From above and slightly in front so that the shadow is mostly under the model.
+        commands.spawn((
+            ModelStage,
+            DirectionalLight {
+                illuminance: SHADOW_LIGHT_ILLUMINANCE,
+                shadows_enabled: true,
+                ..Default::default()
+            },
+            Transform::from_rotation(Quat::from_euler(EulerRot::YXZ, 0.3, -1.2, 0.0)),
+            CascadeShadowConfigBuilder {
+                num_cascades: 1,
+                minimum_distance: size * 0.01,
+                maximum_distance: size * BACKDROP_RADIUS_MULTIPLE,
+                ..Default::default()
+            }
+            .build(),
+        ));
+    }
+}
+
+/// Respawn the backdrop and the ground when the model is loaded or the settings are changed, and
+/// remove them with the model.
+pub(crate) fn model_stage_system(
+    mut commands: Commands,
+    app_settings: Res<AppSettings>,
+    model_inspection: Res<ModelInspection>,
+    stage_query: Query<Entity, With<ModelStage>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut last_settings: Local<Option<ModelStageSettings>>,
+) {
+    let settings_changed = *last_settings != Some(app_settings.model_stage_settings);
+
+    if !settings_changed && !model_inspection.is_changed() {
+        return;
+    }
+
+    *last_settings = Some(app_settings.model_stage_settings);
+
+    let aabb = model_inspection
+        .stats
+        .as_ref()
+        .and_then(|x| x.aabb.as_ref());
+
+    // Keep the stage if it is still the same, e.g. only the bounding box is toggled.
+    if !settings_changed && !stage_query.is_empty() && aabb.is_some() {
+        return;
+    }
+
+    for entity in &stage_query {
+        commands.entity(entity).despawn();
+    }
+
+    if let Some(aabb) = aabb {
+        spawn_stage(
+            &mut commands,
+            &app_settings,
+            aabb,
+            &mut meshes,
+            &mut materials,
+        );
+    }
+}
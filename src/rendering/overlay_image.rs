@@ -0,0 +1,27 @@
+use crate::rendering::tiled_image::TiledImage;
+use bevy::prelude::Component;
+
+/// Another image of the canvas drawn over the tiled image with an opacity, e.g. an X-ray or
+/// infrared capture over the visible-light image.
+#[derive(Component)]
+pub(crate) struct OverlayImage(pub(crate) TiledImage);
+
+impl OverlayImage {
+    /// Create the overlay aligned to the tiled image with the width in world space.
+    pub(crate) fn new(image: TiledImage, world_width: f32) -> Self {
+        let mut overlay = Self(image);
+
+        overlay.fit_width(world_width);
+        overlay
+    }
+
+    /// Scale the overlay to the width of the tiled image in world space,
+    /// as the images may have different resolutions.
+    pub(crate) fn fit_width(&mut self, world_width: f32) {
+        let image_width = self.0.get_image_max_size_rect().width();
+
+        if world_width > 0.0 && image_width > 0.0 {
+            self.0.set_world_scale(world_width / image_width);
+        }
+    }
+}
@@ -1,20 +1,44 @@
 use crate::{
-    AppState,
+    AppState, ViewportChanged,
     app::app_settings::AppSettings,
     camera::{camera_ext, main_camera::MainCamera2d},
-    rendering::tiled_image::TiledImage,
+    host_stats::HostStats,
+    iiif::image::ImageOrientation,
+    rendering::{overlay_image::OverlayImage, tiled_image::TiledImage},
+    web,
 };
 use bevy::{
-    asset::LoadState,
+    asset::{AssetId, LoadState},
+    math::Affine2,
     prelude::{
         AssetServer, Assets, Camera, Color, ColorMaterial, Commands, Component, Entity,
-        GlobalTransform, Handle, MeshMaterial2d, MessageWriter, On, Query, Rect, Remove, Res,
-        ResMut, Resource, Result, Single, Time, Transform, Vec2, Vec3, Visibility, With, debug,
-        info,
+        GlobalTransform, Handle, Local, MeshMaterial2d, MessageWriter, On, Projection, Query, Rect,
+        Remove, Res, ResMut, Resource, Result, Single, Time, Transform, Vec2, Vec3, Visibility,
+        With, debug, info,
     },
     window::RequestRedraw,
 };
-use std::{collections::HashMap, ops::RangeInclusive};
+use std::{
+    collections::{HashMap, HashSet},
+    ops::RangeInclusive,
+    time::Duration,
+};
+
+/// Seconds to keep the tiles of the previous images after they are last shown, e.g. for going
+/// back to the previous canvas instantly.
+const PREVIOUS_IMAGE_TILE_SECS: f64 = 30.0;
+
+/// Max number of the tile downloads at once in the data saver mode, so that the tiles in view are
+/// not held up by those left behind on a slow connection.
+const DATA_SAVER_MAX_REQUESTS: usize = 2;
+
+/// Margin around the viewport as a fraction of its size, in which the tiles of the gigapixel
+/// images are loaded ahead of panning.
+const GIGAPIXEL_LOAD_MARGIN: f32 = 0.25;
+
+/// Margin around the viewport as a fraction of its size, outside of which the tiles of the
+/// gigapixel images are despawned regardless of the cache.
+const GIGAPIXEL_KEEP_MARGIN: f32 = 1.5;
 
 #[derive(Resource)]
 /// Invalidate this to trigger the tile update.
@@ -77,24 +101,90 @@ impl From<Vec3> for TileIndex {
     }
 }
 
+/// The image layer of the tile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub(crate) enum TileLayer {
+    /// The tiled image of the canvas.
+    #[default]
+    Base,
+    /// The overlay image drawn over the tiled image.
+    Overlay,
+}
+
+/// Placement of the image of the tile in the world, which differs between the canvases sharing
+/// the image, e.g. rotated or painted on another region of the canvas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub(crate) struct TilePlacement {
+    pub(crate) orientation: ImageOrientation,
+    /// Rounded corners of the region of the canvas the image is painted on, if any.
+    pub(crate) canvas_region: Option<[i32; 4]>,
+    /// Bits of the scale from the image to the world space, as the floats are not hashable.
+    pub(crate) world_scale_bits: u32,
+}
+
+/// Key of the tile in the cache, scoped by the image and its placement so that the tiles of the
+/// different canvases are not mixed up.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct TileKey {
+    pub(crate) layer: TileLayer,
+    /// Endpoint of the image of the tile.
+    pub(crate) endpoint: String,
+    pub(crate) placement: TilePlacement,
+    pub(crate) index: TileIndex,
+}
+
 /// A tile of the image.
 #[derive(Debug, Component)]
 pub(crate) struct Tile {
+    pub(crate) layer: TileLayer,
+    /// Endpoint of the image of the tile, e.g. the IIIF image service URL.
+    pub(crate) endpoint: String,
+    /// Placement of the image of the tile in the world.
+    pub(crate) placement: TilePlacement,
     pub(crate) index: TileIndex,
     pub(crate) image_position: Rect,
+    /// Region requested in the full size image, larger than the tile by the overlap if any.
+    pub(crate) request_position: Rect,
     pub(crate) world_position: Rect,
+    /// Transform from the UV of the tile in the world to its texture, e.g. for a rotated image.
+    pub(crate) uv_transform: Affine2,
     pub(crate) bevy_image: Option<Handle<bevy::image::Image>>,
 }
 
 impl Tile {
-    pub(crate) fn new(index: TileIndex, image_position: Rect, world_position: Rect) -> Self {
+    pub(crate) fn new(
+        index: TileIndex,
+        image_position: Rect,
+        world_position: Rect,
+        uv_transform: Affine2,
+    ) -> Self {
         Self {
+            layer: TileLayer::default(),
+            endpoint: "".to_string(),
+            placement: TilePlacement::default(),
             index,
             image_position,
+            request_position: image_position,
             world_position,
+            uv_transform,
             bevy_image: None,
         }
     }
+
+    /// Get the key of the tile in the cache.
+    pub(crate) fn get_key(&self) -> TileKey {
+        TileKey {
+            layer: self.layer,
+            endpoint: self.endpoint.clone(),
+            placement: self.placement,
+            index: self.index,
+        }
+    }
+
+    /// Whether the tile is of the image in its current placement.
+    pub(crate) fn is_of_image(&self, image: &TiledImage) -> bool {
+        self.endpoint == image.get_endpoint() && self.placement == image.get_tile_placement()
+    }
 }
 
 #[derive(Component)]
@@ -108,22 +198,123 @@ struct TileCacheItem {
 
 #[derive(Resource)]
 pub(crate) struct TileCache {
-    cache: HashMap<TileIndex, TileCacheItem>,
+    cache: HashMap<TileKey, TileCacheItem>,
+    /// Images being downloaded by URL, to limit the number of the requests. The asset server
+    /// shares the identical requests, and drops the downloads of the tiles pruned meanwhile.
+    in_flight: HashMap<String, AssetId<bevy::image::Image>>,
+    /// Counts the requests to the hosts.
+    host_stats: HostStats,
+    /// Delay between the requests to a host in the polite mode. Zero if not polite.
+    polite_delay: Duration,
+    /// Whether any tiles are left for the polite delay in the last update.
+    deferred: bool,
 }
 
 impl TileCache {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(host_stats: HostStats) -> Self {
         Self {
             cache: HashMap::new(),
+            in_flight: HashMap::new(),
+            host_stats,
+            polite_delay: Duration::ZERO,
+            deferred: false,
         }
     }
 
-    pub(crate) fn clear(&mut self) {
-        self.cache.clear();
+    /// Load the image at the URL, sharing the download if the same URL is already in flight.
+    pub(crate) fn load_image(
+        &mut self,
+        asset_server: &AssetServer,
+        url: String,
+    ) -> Handle<bevy::image::Image> {
+        if self.in_flight.contains_key(&url) {
+            debug!("Share the in-flight request of {:?}", url);
+        } else {
+            self.host_stats.record_request(&url);
+        }
+
+        // Local files are outside of the asset folder, e.g. the exported tiles.
+        let handle: Handle<bevy::image::Image> = match web::get_file_path(&url) {
+            Some(path) => asset_server.load_override(path),
+            None => asset_server.load(url.to_string()),
+        };
+        self.in_flight.insert(url, handle.id());
+
+        handle
+    }
+
+    /// Forget the requests which have been loaded or failed, or dropped with the tiles pruned.
+    pub(crate) fn finish_requests(&mut self, asset_server: &AssetServer) {
+        self.in_flight.retain(|_, id| {
+            matches!(
+                asset_server.get_load_state(*id),
+                Some(LoadState::NotLoaded) | Some(LoadState::Loading)
+            )
+        });
     }
 
-    pub(crate) fn remove(&mut self, index: &TileIndex) {
-        self.cache.remove(index);
+    /// Whether any requests are in flight.
+    pub(crate) fn has_requests(&self) -> bool {
+        !self.in_flight.is_empty()
+    }
+
+    /// Whether the image at the URL can be requested with at most the number of the requests in
+    /// flight, which is always the case if it is already in flight.
+    ///
+    /// In the polite mode, the request waits for the delay after the previous one to its host.
+    fn can_request(&mut self, url: &str, max_requests: usize) -> bool {
+        if self.in_flight.contains_key(url) {
+            return true;
+        }
+
+        if self.in_flight.len() >= max_requests {
+            return false;
+        }
+
+        if !self.host_stats.can_request(url, self.polite_delay) {
+            self.deferred = true;
+            return false;
+        }
+
+        true
+    }
+
+    pub(crate) fn remove(&mut self, key: &TileKey) {
+        self.cache.remove(key);
+    }
+
+    /// Remove all the tiles of the layer.
+    pub(crate) fn remove_layer(&mut self, layer: TileLayer) {
+        self.cache.retain(|key, _| key.layer != layer);
+    }
+}
+
+/// Get the rect grown by the margin on each side as a fraction of its size.
+fn add_margin(rect: Rect, margin: f32) -> Rect {
+    Rect::from_center_size(rect.center(), rect.size() * (1.0 + 2.0 * margin))
+}
+
+/// Get the viewport in world space with the margin around it as a fraction of its size.
+fn get_world_viewport_rect(
+    camera: &Camera,
+    global_transform: &GlobalTransform,
+    margin: f32,
+) -> Option<Rect> {
+    let (world_pos_min, world_pos_max) =
+        camera_ext::get_world_viewport_rect(camera, global_transform)?;
+
+    Some(add_margin(
+        Rect::from_corners(world_pos_min.truncate(), world_pos_max.truncate()),
+        margin,
+    ))
+}
+
+/// Get the margin around the viewport in which the tiles of the image are loaded.
+fn get_load_margin(image: &TiledImage) -> f32 {
+    if image.is_gigapixel() {
+        GIGAPIXEL_LOAD_MARGIN
+    } else {
+        0.0
     }
 }
 
@@ -132,109 +323,486 @@ fn get_required_tiles(
     global_transform: &GlobalTransform,
     level: usize,
     image: &TiledImage,
+    margin: f32,
 ) -> Option<(Vec<Tile>, RangeInclusive<u32>, RangeInclusive<u32>)> {
-    let (world_pos_min, world_pos_max) =
-        camera_ext::get_world_viewport_rect(camera, global_transform)?;
+    let world_rect = get_world_viewport_rect(camera, global_transform, margin)?;
+
+    Some(image.get_required_tiles(
+        level,
+        world_rect.min.extend(0.0),
+        world_rect.max.extend(0.0),
+    ))
+}
+
+/// Get the zoom scale of the camera.
+fn get_zoom_scale(projection: &Projection) -> f32 {
+    match projection {
+        Projection::Orthographic(orthographic) => orthographic.scale,
+        _ => 1.0,
+    }
+}
+
+/// Clip the tile in world space to the right of the x position.
+/// Return the visible part and the UV transform of the texture, or `None` if it is not visible.
+fn clip_tile_left(world_position: Rect, clip_x: f32) -> Option<(Rect, Affine2)> {
+    if world_position.max.x <= clip_x {
+        return None;
+    }
+
+    if world_position.min.x >= clip_x {
+        return Some((world_position, Affine2::IDENTITY));
+    }
+
+    let start = (clip_x - world_position.min.x) / world_position.width();
+
+    Some((
+        Rect::new(
+            clip_x,
+            world_position.min.y,
+            world_position.max.x,
+            world_position.max.y,
+        ),
+        Affine2::from_scale_angle_translation(
+            Vec2::new(1.0 - start, 1.0),
+            0.0,
+            Vec2::new(start, 0.0),
+        ),
+    ))
+}
+
+/// Whether all the tiles of the image at the level in view are loaded. Until then, the tiles of
+/// the other levels are kept and shown underneath them, so that the view is not left blank by
+/// a zoom.
+fn is_level_loaded<'a>(
+    camera: &Camera,
+    global_transform: &GlobalTransform,
+    level: usize,
+    image: &TiledImage,
+    tiles: impl IntoIterator<Item = &'a Tile>,
+    asset_server: &AssetServer,
+) -> bool {
+    let Some((visible_tiles, _, _)) =
+        get_required_tiles(camera, global_transform, level, image, 0.0)
+    else {
+        return false;
+    };
+    let loaded_tiles = tiles
+        .into_iter()
+        .filter(|x| {
+            x.layer == TileLayer::Base
+                && x.is_of_image(image)
+                && x.index.level() == level
+                && x.bevy_image.as_ref().is_some_and(|x| {
+                    matches!(asset_server.get_load_state(x), Some(LoadState::Loaded))
+                })
+        })
+        .map(|x| x.index)
+        .collect::<HashSet<_>>();
+
+    visible_tiles
+        .iter()
+        .all(|x| loaded_tiles.contains(&x.index))
+}
+
+/// Get the max number of the tile downloads at once by the settings.
+fn get_max_requests(app_settings: &AppSettings) -> usize {
+    if app_settings.data_saver {
+        DATA_SAVER_MAX_REQUESTS
+    } else {
+        usize::MAX
+    }
+}
+
+/// Spawn the required tiles of the layer that are not in the cache yet, taking them as last
+/// visible at the time in seconds.
+///
+/// The tiles beyond the max number of the requests in flight are left for the next update.
+#[allow(clippy::too_many_arguments)]
+fn load_tiles(
+    commands: &mut Commands,
+    tile_cache: &mut TileCache,
+    asset_server: &AssetServer,
+    layer: TileLayer,
+    image: &TiledImage,
+    required_tiles: Vec<Tile>,
+    visible_secs: f64,
+    max_requests: usize,
+) {
+    for mut tile in required_tiles {
+        tile.layer = layer;
+        tile.endpoint = image.get_endpoint().to_string();
+        tile.placement = image.get_tile_placement();
+
+        let key = tile.get_key();
+
+        if !tile_cache.cache.contains_key(&key) {
+            let url = image.get_image_tile_url_at(tile.index, tile.request_position);
+
+            if !tile_cache.can_request(&url, max_requests) {
+                continue;
+            }
+
+            debug!("Load {:?} for {:?}", url, key);
+
+            let handle = tile_cache.load_image(asset_server, url);
+
+            tile.bevy_image = Some(handle.clone());
+
+            let id = commands.spawn((tile, TileLoading)).id();
+
+            tile_cache.cache.insert(
+                key,
+                TileCacheItem {
+                    entity: id,
+                    last_visible_secs: visible_secs,
+                },
+            );
+        }
+    }
+}
+
+/// Spawn the tiles of the lowest level of the image which is not shown yet, e.g. of the next
+/// canvas, so that their meshes are ready when the image is added.
+///
+/// They are kept like the tiles of a previous image until then.
+pub(crate) fn prefetch_tiles(
+    commands: &mut Commands,
+    tile_cache: &mut TileCache,
+    asset_server: &AssetServer,
+    image: &TiledImage,
+    now_secs: f64,
+) {
+    let world_rect = image.get_world_max_size_rect();
+    let (required_tiles, _, _) =
+        image.get_required_tiles(0, world_rect.min.extend(0.0), world_rect.max.extend(0.0));
 
-    Some(image.get_required_tiles(level, world_pos_min, world_pos_max))
+    load_tiles(
+        commands,
+        tile_cache,
+        asset_server,
+        TileLayer::Base,
+        image,
+        required_tiles,
+        now_secs,
+        usize::MAX,
+    );
 }
 
 #[allow(clippy::too_many_arguments)]
 pub(crate) fn update_tiles_system(
     mut commands: Commands,
     mut tile_cache: ResMut<TileCache>,
-    camera_query: Single<(&Camera, &GlobalTransform), With<MainCamera2d>>,
+    camera_query: Single<(&Camera, &GlobalTransform, &Projection), With<MainCamera2d>>,
     asset_server: Res<AssetServer>,
     tiles: Query<(Entity, &Tile, &mut MeshMaterial2d<ColorMaterial>), With<Tile>>,
     app_state: Res<AppState>,
+    app_settings: Res<AppSettings>,
     image: Single<&TiledImage>,
+    overlay_image: Option<Single<&OverlayImage>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
     time: Res<Time>,
     mut tile_prune_state: ResMut<TilePruneState>,
     mut tile_mod_state: ResMut<TileModState>,
     mut redraw_request_writer: MessageWriter<RequestRedraw>,
+    mut last_viewport: Local<Option<ViewportChanged>>,
+    mut viewport_changed_writer: MessageWriter<ViewportChanged>,
 ) {
-    let (camera, global_transform) = camera_query.into_inner();
+    let (camera, global_transform, projection) = camera_query.into_inner();
 
-    let Some((required_tiles, _, _)) =
-        get_required_tiles(camera, global_transform, app_state.level, *image)
-    else {
+    let Some((required_tiles, _, _)) = get_required_tiles(
+        camera,
+        global_transform,
+        app_state.level,
+        *image,
+        get_load_margin(*image),
+    ) else {
         // This is mainly for when the system is first up, some values seem to be not there yet.
         tile_mod_state.invalidate();
         redraw_request_writer.write(RequestRedraw);
         return;
     };
 
-    for mut tile in required_tiles {
-        let entry = tile_cache.cache.get(&tile.index);
+    if let Some(size) = camera.logical_viewport_size() {
+        let viewport = ViewportChanged {
+            size,
+            level: app_state.level,
+        };
 
-        if entry.is_none() {
-            let url = image.get_image_tile_url_at(tile.image_position);
+        if last_viewport.as_ref() != Some(&viewport) {
+            viewport_changed_writer.write(viewport.clone());
+            *last_viewport = Some(viewport);
+        }
+    }
 
-            debug!("Load {:?} for {:?}", url, tile.index);
+    tile_cache.polite_delay = Duration::from_millis(app_settings.polite_delay_ms as u64);
+    tile_cache.deferred = false;
 
-            let handle = asset_server.load(url);
-            let tile_index = tile.index;
+    load_tiles(
+        &mut commands,
+        &mut tile_cache,
+        &asset_server,
+        TileLayer::Base,
+        *image,
+        required_tiles,
+        0.0,
+        get_max_requests(&app_settings),
+    );
 
-            tile.bevy_image = Some(handle.clone());
+    // The overlay is shown or hidden in turn by the blink, or drawn separately for the
+    // difference, over the whole image.
+    let comparison_opacity = app_state
+        .comparison
+        .get_overlay_opacity(time.elapsed_secs_f64());
 
-            let id = commands.spawn((tile, TileLoading)).id();
+    // The overlay is fully opaque but only drawn to the right of the curtain, if any.
+    let curtain_x = app_state
+        .curtain_position
+        .filter(|_| comparison_opacity.is_none())
+        .and_then(|position| {
+            let (world_pos_min, world_pos_max) =
+                camera_ext::get_world_viewport_rect(camera, global_transform)?;
 
-            tile_cache.cache.insert(
-                tile_index,
-                TileCacheItem {
-                    entity: id,
-                    last_visible_secs: 0.0,
-                },
-            );
-        }
+            Some(world_pos_min.x + (world_pos_max.x - world_pos_min.x) * position)
+        });
+    let overlay_opacity = if curtain_x.is_some() {
+        1.0
+    } else {
+        comparison_opacity.unwrap_or(app_state.overlay_opacity)
+    };
+
+    // The overlay has its own level, as it may have a different resolution. Its tiles are kept
+    // while blinking.
+    let overlay_level = overlay_image
+        .as_ref()
+        .filter(|_| overlay_opacity > 0.0 || comparison_opacity.is_some())
+        .map(|overlay_image| {
+            overlay_image
+                .0
+                .get_level_at(get_zoom_scale(projection), app_state.scale_factor)
+        });
+
+    if let Some(overlay_image) = &overlay_image
+        && let Some(overlay_level) = overlay_level
+        && let Some((required_tiles, _, _)) = get_required_tiles(
+            camera,
+            global_transform,
+            overlay_level,
+            &overlay_image.0,
+            get_load_margin(&overlay_image.0),
+        )
+    {
+        load_tiles(
+            &mut commands,
+            &mut tile_cache,
+            &asset_server,
+            TileLayer::Overlay,
+            &overlay_image.0,
+            required_tiles,
+            0.0,
+            get_max_requests(&app_settings),
+        );
+    }
+
+    let overlay_tiled_image = overlay_image.as_ref().map(|x| &x.0);
+    let is_level_loaded = is_level_loaded(
+        camera,
+        global_transform,
+        app_state.level,
+        *image,
+        tiles.iter().map(|(_, tile, _)| tile),
+        &asset_server,
+    );
+
+    // Despawn the tiles left far behind by the move.
+    if image.is_gigapixel() || overlay_image.as_ref().is_some_and(|x| x.0.is_gigapixel()) {
+        tile_prune_state.invalidate();
     }
 
     for (entity, tile, material) in tiles.iter() {
+        let current_image = match tile.layer {
+            TileLayer::Base => Some(*image),
+            TileLayer::Overlay => overlay_tiled_image,
+        };
+
+        // Tiles of the previous canvases are kept hidden for going back to them.
+        if !current_image.is_some_and(|x| tile.is_of_image(x)) {
+            commands.entity(entity).insert(Visibility::Hidden);
+            continue;
+        }
+
         let color_material = materials
             .get_mut(material.id())
             .expect("tile should have a color material");
 
-        if tile.index.level() != app_state.level {
-            color_material.alpha_mode = bevy::sprite_render::AlphaMode2d::Blend;
-            color_material.color = Color::srgba(1.0, 1.0, 1.0, 0.75);
+        commands
+            .entity(entity)
+            .insert(app_state.comparison.get_render_layers(tile.layer));
 
-            commands.entity(entity).insert(Transform::from_translation(
-                tile.world_position
-                    .center()
-                    .extend(-100.0 + tile.index.z as f32),
-            ));
+        match tile.layer {
+            TileLayer::Base if tile.index.level() != app_state.level => {
+                let tile_level_settings = app_settings.tile_level_settings;
 
-            tile_prune_state.invalidate();
-        } else {
-            color_material.alpha_mode = bevy::sprite_render::AlphaMode2d::default();
-            color_material.color = Color::default();
-            tile_cache
-                .cache
-                .entry(tile.index)
-                .and_modify(|t| t.last_visible_secs = time.elapsed_secs_f64());
-
-            commands.entity(entity).insert((
-                Visibility::Visible,
-                Transform::from_translation(tile.world_position.center().extend(0.0)),
-            ));
+                // Opaque underneath the tiles of the level until they are all loaded, the finer
+                // levels over the coarser ones, or translucent to tell them apart.
+                if tile_level_settings.dim_other_levels {
+                    color_material.alpha_mode = bevy::sprite_render::AlphaMode2d::Blend;
+                    color_material.color =
+                        Color::srgba(1.0, 1.0, 1.0, tile_level_settings.other_level_alpha);
+                } else {
+                    color_material.alpha_mode = bevy::sprite_render::AlphaMode2d::default();
+                    color_material.color = Color::default();
+                }
+
+                commands.entity(entity).insert((
+                    if is_level_loaded {
+                        Visibility::Hidden
+                    } else {
+                        Visibility::Visible
+                    },
+                    Transform::from_translation(
+                        tile.world_position
+                            .center()
+                            .extend(tile_level_settings.other_level_z + tile.index.z as f32),
+                    ),
+                ));
+
+                tile_prune_state.invalidate();
+            }
+            TileLayer::Base => {
+                color_material.alpha_mode = bevy::sprite_render::AlphaMode2d::default();
+                color_material.color = Color::default();
+                tile_cache
+                    .cache
+                    .entry(tile.get_key())
+                    .and_modify(|t| t.last_visible_secs = time.elapsed_secs_f64());
+
+                commands.entity(entity).insert((
+                    Visibility::Visible,
+                    Transform::from_translation(tile.world_position.center().extend(0.0)),
+                ));
+            }
+            TileLayer::Overlay if overlay_level == Some(tile.index.level()) => {
+                let Some((visible_position, uv_transform)) = curtain_x.map_or(
+                    Some((tile.world_position, Affine2::IDENTITY)),
+                    |curtain_x| clip_tile_left(tile.world_position, curtain_x),
+                ) else {
+                    commands.entity(entity).insert(Visibility::Hidden);
+                    continue;
+                };
+
+                // Draw over the tiled image, including the tiles of the other levels.
+                color_material.alpha_mode = bevy::sprite_render::AlphaMode2d::Blend;
+                color_material.color = Color::srgba(1.0, 1.0, 1.0, overlay_opacity);
+                color_material.uv_transform = tile.uv_transform * uv_transform;
+                tile_cache
+                    .cache
+                    .entry(tile.get_key())
+                    .and_modify(|t| t.last_visible_secs = time.elapsed_secs_f64());
+
+                // The mesh has the full size of the tile, so scale it to the visible part.
+                commands.entity(entity).insert((
+                    Visibility::Visible,
+                    Transform::from_translation(visible_position.center().extend(1.0)).with_scale(
+                        Vec3::new(
+                            visible_position.width() / tile.world_position.width(),
+                            1.0,
+                            1.0,
+                        ),
+                    ),
+                ));
+            }
+            TileLayer::Overlay => {
+                // Blending the overlay levels would change the opacity, so hide the others.
+                commands.entity(entity).insert(Visibility::Hidden);
+
+                tile_prune_state.invalidate();
+            }
         }
     }
+
+    // Update again for the tiles left for the polite delay, waking up the event loop for it as no
+    // other event may come meanwhile in the reactive mode.
+    if tile_cache.deferred {
+        tile_mod_state.invalidate();
+        redraw_request_writer.write(RequestRedraw);
+        return;
+    }
+
     // Redraw the screen.
     redraw_request_writer.write(RequestRedraw);
 }
 
+/// Whether the tile in the cache is of the current image of its layer.
+fn is_current_image(key: &TileKey, image: &TiledImage, overlay_image: Option<&TiledImage>) -> bool {
+    let current_image = match key.layer {
+        TileLayer::Base => Some(image),
+        TileLayer::Overlay => overlay_image,
+    };
+
+    current_image.is_some_and(|x| {
+        x.get_endpoint() == key.endpoint && x.get_tile_placement() == key.placement
+    })
+}
+
 #[allow(clippy::too_many_arguments)]
 pub(crate) fn prune_tiles_system(
     mut commands: Commands,
     mut tile_cache: ResMut<TileCache>,
-    camera_query: Single<(&Camera, &GlobalTransform), With<MainCamera2d>>,
+    camera_query: Single<(&Camera, &GlobalTransform, &Projection), With<MainCamera2d>>,
     tiles: Query<&Tile>,
     app_settings: Res<AppSettings>,
     image: Single<&TiledImage>,
+    overlay_image: Option<Single<&OverlayImage>>,
     app_state: Res<AppState>,
     asset_server: Res<AssetServer>,
+    time: Res<Time>,
 ) {
+    let overlay_image = overlay_image.map(|x| &x.into_inner().0);
+    let now = time.elapsed_secs_f64();
+
+    // Remove the tiles of the previous images once they have not been shown for a while.
+    let expired_keys = tile_cache
+        .cache
+        .iter()
+        .filter(|(key, item)| {
+            !is_current_image(key, *image, overlay_image)
+                && now - item.last_visible_secs > PREVIOUS_IMAGE_TILE_SECS
+        })
+        .map(|(key, _)| key.clone())
+        .collect::<Vec<_>>();
+
+    for key in expired_keys {
+        if let Some(cache_item) = tile_cache.cache.remove(&key) {
+            debug!("Remove tile of previous image from cache {:?}", key);
+            commands.entity(cache_item.entity).despawn();
+        }
+    }
+
+    let (camera, global_transform, projection) = camera_query.into_inner();
+
+    // Despawn the tiles of the gigapixel images far outside of the view regardless of the cache,
+    // keeping the number of the entities bounded.
+    if let Some(keep_rect) =
+        get_world_viewport_rect(camera, global_transform, GIGAPIXEL_KEEP_MARGIN)
+    {
+        for tile in &tiles {
+            let is_gigapixel = match tile.layer {
+                TileLayer::Base => image.is_gigapixel(),
+                TileLayer::Overlay => overlay_image.is_some_and(|x| x.is_gigapixel()),
+            };
+            let key = tile.get_key();
+
+            if is_gigapixel
+                && is_current_image(&key, *image, overlay_image)
+                && keep_rect.intersect(tile.world_position).is_empty()
+                && let Some(cache_item) = tile_cache.cache.remove(&key)
+            {
+                debug!("Remove far-away tile from cache {:?}", key);
+                commands.entity(cache_item.entity).despawn();
+            }
+        }
+    }
+
     let num_cache_items = tile_cache.cache.len();
 
     if num_cache_items <= app_settings.max_cache_items {
@@ -243,17 +811,43 @@ pub(crate) fn prune_tiles_system(
     debug!("Pruning tiles at current level {}", app_state.level);
 
     let mut num_items_to_remove = num_cache_items - app_settings.max_cache_items;
-    let (camera, global_transform) = camera_query.into_inner();
-    // Only keep the tiles in view for this level and the lower-res levels.
-    let all_required_tiles: Vec<_> = (0..=app_state.level)
-        .map(|level| get_required_tiles(camera, global_transform, level, *image))
+    // Only keep the tiles in view for this level and the lower-res levels, and the higher-res
+    // levels still covering the view until the tiles of this level are loaded.
+    let max_level = if is_level_loaded(
+        camera,
+        global_transform,
+        app_state.level,
+        *image,
+        &tiles,
+        &asset_server,
+    ) {
+        app_state.level
+    } else {
+        image.get_levels().len() - 1
+    };
+    let base_required_tiles: Vec<_> = (0..=max_level)
+        .map(|level| get_required_tiles(camera, global_transform, level, *image, 0.0))
         .collect();
+    let overlay_required_tiles: Vec<_> = overlay_image
+        .map(|overlay_image| {
+            (0..=overlay_image.get_level_at(get_zoom_scale(projection), app_state.scale_factor))
+                .map(|level| {
+                    get_required_tiles(camera, global_transform, level, overlay_image, 0.0)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
     let mut out_of_view_tiles = Vec::new();
 
     for tile in tiles {
-        // Out of view if the tile has a higher res or outside the range.
-        let is_out_of_view =
-            all_required_tiles
+        let all_required_tiles = match tile.layer {
+            TileLayer::Base => &base_required_tiles,
+            TileLayer::Overlay => &overlay_required_tiles,
+        };
+        let key = tile.get_key();
+        // Out of view if the tile is of a previous image, has a higher res or is outside the range.
+        let is_out_of_view = !is_current_image(&key, *image, overlay_image)
+            || all_required_tiles
                 .get(tile.index.level())
                 .is_none_or(|required_tiles| {
                     required_tiles
@@ -264,20 +858,17 @@ pub(crate) fn prune_tiles_system(
                         })
                 });
 
-        if is_out_of_view && let Some(tile_in_cache) = tile_cache.cache.get(&tile.index) {
+        if is_out_of_view && let Some(tile_in_cache) = tile_cache.cache.get(&key) {
             match asset_server
                 .get_load_state(tile.bevy_image.as_ref().expect("tile should have an image"))
             {
                 Some(LoadState::Loaded) => {
-                    out_of_view_tiles.push((tile.index, tile_in_cache.clone()));
+                    out_of_view_tiles.push((key, tile_in_cache.clone()));
                 }
                 _ => {
-                    debug!(
-                        "Remove unloaded out-of-view tile from cache {:?}",
-                        tile.index
-                    );
+                    debug!("Remove unloaded out-of-view tile from cache {:?}", key);
                     commands.entity(tile_in_cache.entity).despawn();
-                    tile_cache.cache.remove(&tile.index);
+                    tile_cache.cache.remove(&key);
                     num_items_to_remove = num_items_to_remove.saturating_sub(1);
                 }
             }
@@ -295,9 +886,9 @@ pub(crate) fn prune_tiles_system(
             }
         });
 
-        for (tile_index, cache_item) in out_of_view_tiles.iter().take(num_items_to_remove) {
-            debug!("Remove loaded out-of-view tile from cache {:?}", tile_index);
-            tile_cache.cache.remove(tile_index);
+        for (key, cache_item) in out_of_view_tiles.iter().take(num_items_to_remove) {
+            debug!("Remove loaded out-of-view tile from cache {:?}", key);
+            tile_cache.cache.remove(key);
             commands.entity(cache_item.entity).despawn();
         }
     }
@@ -308,16 +899,39 @@ pub(crate) fn on_remove_tiled_image(
     remove: On<Remove, TiledImage>,
     mut commands: Commands,
     tiles: Query<(Entity, &Tile), With<Tile>>,
-    mut tile_cache: ResMut<TileCache>,
     mut tile_mod_state: ResMut<TileModState>,
     mut redraw_request_writer: MessageWriter<RequestRedraw>,
 ) -> Result {
     info!("Tiled image removed (tile). {:?}", remove.entity);
 
-    // Remove tile cache and despawn the tile entities.
-    tile_cache.clear();
+    // Keep the tiles hidden for going back to the image, until they are pruned.
     for (tile_entity, _tile) in tiles {
-        commands.entity(tile_entity).despawn();
+        commands.entity(tile_entity).insert(Visibility::Hidden);
+    }
+
+    // Trigger an update.
+    tile_mod_state.invalidate();
+    redraw_request_writer.write(RequestRedraw);
+
+    Ok(())
+}
+
+/// Triggered when the overlay image is removed to clean up and despawn its tiles.
+pub(crate) fn on_remove_overlay_image(
+    remove: On<Remove, OverlayImage>,
+    mut commands: Commands,
+    tiles: Query<(Entity, &Tile), With<Tile>>,
+    mut tile_cache: ResMut<TileCache>,
+    mut tile_mod_state: ResMut<TileModState>,
+    mut redraw_request_writer: MessageWriter<RequestRedraw>,
+) -> Result {
+    info!("Overlay image removed (tile). {:?}", remove.entity);
+
+    tile_cache.remove_layer(TileLayer::Overlay);
+    for (tile_entity, tile) in tiles {
+        if tile.layer == TileLayer::Overlay {
+            commands.entity(tile_entity).despawn();
+        }
     }
 
     // Trigger an update.
@@ -326,3 +940,97 @@ pub(crate) fn on_remove_tiled_image(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tile_key() {
+        let mut tile = Tile::new(
+            TileIndex::new(1, 2, 3),
+            Rect::new(0.0, 0.0, 256.0, 256.0),
+            Rect::new(0.0, -256.0, 256.0, 0.0),
+            Affine2::IDENTITY,
+        );
+        tile.endpoint = "https://example.org/iiif/a".to_string();
+
+        let key = tile.get_key();
+
+        tile.endpoint = "https://example.org/iiif/b".to_string();
+
+        // The same index of another image is another tile.
+        assert_ne!(key, tile.get_key());
+        assert_eq!(key.index, tile.get_key().index);
+        assert_eq!(key.layer, TileLayer::Base);
+
+        let key = tile.get_key();
+
+        tile.placement.orientation.rotation = 90;
+
+        // As is the same image placed on another canvas.
+        assert_ne!(key, tile.get_key());
+    }
+
+    #[test]
+    fn test_clip_tile_left() {
+        let world_position = Rect::new(100.0, -200.0, 200.0, -100.0);
+
+        assert_eq!(clip_tile_left(world_position, 200.0), None);
+        assert_eq!(clip_tile_left(world_position, 300.0), None);
+        assert_eq!(
+            clip_tile_left(world_position, 50.0),
+            Some((world_position, Affine2::IDENTITY))
+        );
+
+        let (visible_position, uv_transform) =
+            clip_tile_left(world_position, 125.0).expect("tile should be partly visible");
+
+        assert_eq!(visible_position, Rect::new(125.0, -200.0, 200.0, -100.0));
+        assert_eq!(
+            uv_transform.transform_point2(Vec2::ZERO),
+            Vec2::new(0.25, 0.0)
+        );
+        assert_eq!(
+            uv_transform.transform_point2(Vec2::ONE),
+            Vec2::new(1.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn test_can_request() {
+        let mut tile_cache = TileCache::new(HostStats::default());
+
+        tile_cache
+            .in_flight
+            .insert("https://example.org/a.jpg".to_string(), AssetId::default());
+
+        assert!(tile_cache.can_request("https://example.org/b.jpg", usize::MAX));
+        assert!(tile_cache.can_request("https://example.org/b.jpg", 2));
+        assert!(!tile_cache.can_request("https://example.org/b.jpg", 1));
+        // Shared with the request in flight.
+        assert!(tile_cache.can_request("https://example.org/a.jpg", 1));
+    }
+
+    #[test]
+    fn test_add_margin() {
+        let viewport = Rect::new(-100.0, -50.0, 100.0, 50.0);
+
+        assert_eq!(add_margin(viewport, 0.0), viewport);
+        assert_eq!(
+            add_margin(viewport, GIGAPIXEL_KEEP_MARGIN),
+            Rect::new(-400.0, -200.0, 400.0, 200.0)
+        );
+        // A tile just outside of the view is kept, unlike one far away.
+        assert!(
+            !add_margin(viewport, GIGAPIXEL_KEEP_MARGIN)
+                .intersect(Rect::new(150.0, 0.0, 250.0, 100.0))
+                .is_empty()
+        );
+        assert!(
+            add_margin(viewport, GIGAPIXEL_KEEP_MARGIN)
+                .intersect(Rect::new(500.0, 0.0, 600.0, 100.0))
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use crate::{
+    capability_cache::CapabilityCache,
+    rendering::{overlay_image::OverlayImage, tile::TileModState, tiled_image::TiledImage},
+};
+use bevy::{
+    asset::{AssetLoadError, io::AssetReaderError},
+    prelude::{Added, Query, Res, ResMut, Resource, info},
+};
+use std::collections::HashMap;
+
+/// Formats of the tiles working for the endpoints in this session, after the preferred ones
+/// failed.
+#[derive(Resource, Default)]
+pub(crate) struct TileFormats {
+    formats: HashMap<String, String>,
+}
+
+impl TileFormats {
+    /// Remember the format working for the endpoint.
+    pub(crate) fn insert(&mut self, endpoint: &str, format: &str) {
+        self.formats
+            .insert(endpoint.to_string(), format.to_string());
+    }
+
+    /// Get the format working for the endpoint if it is not the preferred one.
+    pub(crate) fn get(&self, endpoint: &str) -> Option<&str> {
+        self.formats.get(endpoint).map(|x| x.as_str())
+    }
+}
+
+/// Whether the tile failed as the server rejected the request, e.g. 404 for an unsupported
+/// format, rather than failing to respond.
+pub(crate) fn is_client_error(error: &AssetLoadError) -> bool {
+    match error {
+        AssetLoadError::AssetReaderError(AssetReaderError::NotFound(_)) => true,
+        AssetLoadError::AssetReaderError(AssetReaderError::HttpError(status)) => {
+            (400..500).contains(status)
+        }
+        _ => false,
+    }
+}
+
+/// Get the format of the tile from its URL, e.g. "png" for ".../0/default.png".
+pub(crate) fn get_url_format(url: &str) -> Option<&str> {
+    let (_, format) = url.rsplit_once('.')?;
+
+    (!format.is_empty() && format.chars().all(|x| x.is_ascii_alphanumeric())).then_some(format)
+}
+
+/// Request the tiles of the images added in the format working for their endpoints before, in
+/// this session or the previous ones.
+pub(crate) fn tile_format_system(
+    tile_formats: Res<TileFormats>,
+    capability_cache: Res<CapabilityCache>,
+    tiled_image_query: Query<&mut TiledImage, Added<TiledImage>>,
+    overlay_image_query: Query<&mut OverlayImage, Added<OverlayImage>>,
+    mut tile_mod_state: ResMut<TileModState>,
+) {
+    let images = tiled_image_query.into_iter().map(|x| x.into_inner()).chain(
+        overlay_image_query
+            .into_iter()
+            .map(|x| &mut x.into_inner().0),
+    );
+
+    for image in images {
+        let format = tile_formats
+            .get(image.get_endpoint())
+            .map(|x| x.to_string())
+            .or_else(|| {
+                capability_cache
+                    .get_working_format(&TiledImage::get_image_info_url(image.get_endpoint()))
+            });
+
+        if let Some(format) = format {
+            info!(
+                "Request the tiles of {} in {} as before",
+                image.get_endpoint(),
+                format
+            );
+            image.set_format(&format);
+            tile_mod_state.invalidate();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_url_format() {
+        assert_eq!(
+            get_url_format("https://example.org/iiif/1/0,0,512,512/512,512/0/default.png"),
+            Some("png")
+        );
+        assert_eq!(
+            get_url_format("https://proxy.example.org/?url=https%3A%2F%2Fexample.org%2F0_0.jpg"),
+            Some("jpg")
+        );
+        assert_eq!(get_url_format("https://example.org/tiles/1"), None);
+    }
+
+    #[test]
+    fn test_is_client_error() {
+        assert!(is_client_error(&AssetLoadError::AssetReaderError(
+            AssetReaderError::HttpError(415)
+        )));
+        assert!(!is_client_error(&AssetLoadError::AssetReaderError(
+            AssetReaderError::HttpError(503)
+        )));
+    }
+}
@@ -0,0 +1,293 @@
+use crate::{
+    app::{app_settings::AppSettings, app_state::AppState},
+    rendering::{
+        tile::{Tile, TileIndex, TileLayer, TileLoading},
+        tiled_image::TiledImage,
+    },
+};
+use bevy::prelude::{
+    Added, Assets, Color, ColorMaterial, Commands, Component, Entity, Image, LinearRgba, Mesh,
+    Mesh2d, MeshMaterial2d, Query, Rect, Rectangle, Res, ResMut, Resource, Single, Transform,
+    Visibility, With, Without,
+};
+use std::collections::{HashMap, HashSet};
+
+/// Number of the cells of the colors sampled from a tile along each axis.
+const COLOR_GRID_SIZE: usize = 4;
+
+/// Number of the pixels sampled along each axis of a cell for its average color.
+const SAMPLES_PER_CELL: usize = 4;
+
+/// Max number of the tiles with the colors kept, so that a gigapixel image explored for long does
+/// not grow them without bound. The coarser levels are loaded first and kept.
+const MAX_TILE_COLORS: usize = 10_000;
+
+/// Depth of the placeholders from the tiles of the other levels, underneath the tiles of all the
+/// levels so that any texture loaded is shown over them.
+const PLACEHOLDER_Z_OFFSET: f32 = -1.0;
+
+/// Average colors of the cells of a loaded tile, over the region of its texture.
+struct TileColorGrid {
+    /// Region of the full size image covered by the texture.
+    image_position: Rect,
+    colors: Vec<LinearRgba>,
+}
+
+/// Colors of the loaded tiles of the image, kept after the tiles are pruned so that the tiles
+/// loading in a part of the image seen before have a placeholder of about the right color.
+#[derive(Resource, Default)]
+pub(crate) struct TileColors {
+    /// Endpoint of the image of the colors.
+    endpoint: String,
+    grids: HashMap<TileIndex, TileColorGrid>,
+}
+
+/// Flat quad shown for a tile while it is loading, e.g. in the area panned into, instead of
+/// nothing.
+#[derive(Component)]
+pub(crate) struct TilePlaceholder {
+    /// Entity of the tile loading.
+    tile: Entity,
+}
+
+/// Get the average colors of the cells of the grid over the image, row by row. `None` if the
+/// pixels cannot be read, e.g. of a compressed texture.
+fn sample_colors(image: &Image) -> Option<Vec<LinearRgba>> {
+    let (width, height) = (image.width() as usize, image.height() as usize);
+
+    if width == 0 || height == 0 {
+        return None;
+    }
+
+    let samples = COLOR_GRID_SIZE * SAMPLES_PER_CELL;
+    let mut colors = vec![LinearRgba::NONE; COLOR_GRID_SIZE * COLOR_GRID_SIZE];
+
+    for y in 0..samples {
+        for x in 0..samples {
+            let color = image
+                .get_color_at(
+                    ((2 * x + 1) * width / (2 * samples)) as u32,
+                    ((2 * y + 1) * height / (2 * samples)) as u32,
+                )
+                .ok()?
+                .to_linear();
+            let cell = (y / SAMPLES_PER_CELL) * COLOR_GRID_SIZE + x / SAMPLES_PER_CELL;
+
+            colors[cell] += color * (1.0 / (SAMPLES_PER_CELL * SAMPLES_PER_CELL) as f32);
+        }
+    }
+
+    Some(colors)
+}
+
+/// Get the average color of the cells of the grid over the image position overlapping the
+/// region, opaque. `None` if the region is outside of the grid.
+fn get_region_color(
+    image_position: Rect,
+    colors: &[LinearRgba],
+    region: Rect,
+) -> Option<LinearRgba> {
+    let region = region.intersect(image_position);
+
+    if region.is_empty() || colors.len() != COLOR_GRID_SIZE * COLOR_GRID_SIZE {
+        return None;
+    }
+
+    // The cells from the one of the min to the one of the max, not the next one at its edge.
+    let to_cells = |min: f32, max: f32, grid_min: f32, grid_size: f32| {
+        let to_grid = |value: f32| (value - grid_min) / grid_size * COLOR_GRID_SIZE as f32;
+        let first = (to_grid(min) as usize).min(COLOR_GRID_SIZE - 1);
+        let last = (to_grid(max).ceil() as usize).clamp(first + 1, COLOR_GRID_SIZE) - 1;
+
+        first..=last
+    };
+    let range_x = to_cells(
+        region.min.x,
+        region.max.x,
+        image_position.min.x,
+        image_position.width(),
+    );
+    let range_y = to_cells(
+        region.min.y,
+        region.max.y,
+        image_position.min.y,
+        image_position.height(),
+    );
+    let cells = range_y
+        .flat_map(|y| range_x.clone().map(move |x| y * COLOR_GRID_SIZE + x))
+        .collect::<Vec<_>>();
+    let color = cells
+        .iter()
+        .fold(LinearRgba::NONE, |sum, cell| sum + colors[*cell])
+        * (1.0 / cells.len() as f32);
+
+    Some(color.with_alpha(1.0))
+}
+
+impl TileColors {
+    /// Get the color of the region of the tile from the loaded tile of the finest level below it
+    /// covering the region, if any.
+    fn get_color(&self, index: TileIndex, region: Rect) -> Option<LinearRgba> {
+        self.grids
+            .iter()
+            .filter(|(x, grid)| x.z < index.z && grid.image_position.contains(region.center()))
+            .max_by_key(|(x, _)| x.z)
+            .and_then(|(_, grid)| get_region_color(grid.image_position, &grid.colors, region))
+    }
+}
+
+/// Show a placeholder colored by the lower-res tiles for each tile of the image loading at the
+/// level, greatly reducing the flicker of the blank areas in fast pans. The colors are sampled
+/// from the textures of the tiles as they are loaded.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn tile_placeholder_system(
+    mut commands: Commands,
+    mut tile_colors: ResMut<TileColors>,
+    loaded_tiles: Query<&Tile, (Added<Mesh2d>, Without<TileLoading>)>,
+    loading_tiles: Query<(Entity, &Tile), With<TileLoading>>,
+    placeholders: Query<(Entity, &TilePlaceholder)>,
+    tiled_image: Option<Single<&TiledImage>>,
+    app_state: Res<AppState>,
+    app_settings: Res<AppSettings>,
+    images: Res<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let endpoint = tiled_image.as_ref().map(|x| x.get_endpoint()).unwrap_or("");
+
+    if tile_colors.endpoint != endpoint {
+        tile_colors.endpoint = endpoint.to_string();
+        tile_colors.grids.clear();
+    }
+
+    for tile in &loaded_tiles {
+        if tile.layer != TileLayer::Base
+            || tile.endpoint != endpoint
+            || tile_colors.grids.len() >= MAX_TILE_COLORS
+        {
+            continue;
+        }
+
+        if let Some(colors) = tile
+            .bevy_image
+            .as_ref()
+            .and_then(|x| images.get(x))
+            .and_then(sample_colors)
+        {
+            tile_colors.grids.insert(
+                tile.index,
+                TileColorGrid {
+                    image_position: tile.request_position,
+                    colors,
+                },
+            );
+        }
+    }
+
+    let is_placeholder_shown = |tile: &Tile| {
+        tile.layer == TileLayer::Base
+            && tile.endpoint == endpoint
+            && tile.index.level() == app_state.level
+    };
+    let mut tiles_with_placeholder = HashSet::new();
+
+    // Despawn the placeholders of the tiles loaded, failed or no longer in view.
+    for (entity, placeholder) in &placeholders {
+        match loading_tiles.get(placeholder.tile) {
+            Ok((_, tile)) if is_placeholder_shown(tile) => {
+                tiles_with_placeholder.insert(placeholder.tile);
+            }
+            _ => commands.entity(entity).despawn(),
+        }
+    }
+
+    for (entity, tile) in &loading_tiles {
+        if !is_placeholder_shown(tile) || tiles_with_placeholder.contains(&entity) {
+            continue;
+        }
+
+        let Some(color) = tile_colors.get_color(tile.index, tile.image_position) else {
+            continue;
+        };
+
+        commands.spawn((
+            TilePlaceholder { tile: entity },
+            Mesh2d(meshes.add(Rectangle::new(
+                tile.world_position.width(),
+                tile.world_position.height(),
+            ))),
+            MeshMaterial2d(materials.add(Color::from(color))),
+            Transform::from_translation(
+                tile.world_position
+                    .center()
+                    .extend(app_settings.tile_level_settings.other_level_z + PLACEHOLDER_Z_OFFSET),
+            ),
+            Visibility::Visible,
+            app_state.comparison.get_render_layers(TileLayer::Base),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_region_color() {
+        let image_position = Rect::new(0.0, 0.0, 400.0, 400.0);
+        let mut colors = vec![LinearRgba::BLACK; COLOR_GRID_SIZE * COLOR_GRID_SIZE];
+
+        colors[0] = LinearRgba::WHITE;
+
+        assert_eq!(
+            get_region_color(image_position, &colors, Rect::new(0.0, 0.0, 50.0, 50.0)),
+            Some(LinearRgba::WHITE)
+        );
+        assert_eq!(
+            get_region_color(
+                image_position,
+                &colors,
+                Rect::new(200.0, 200.0, 400.0, 400.0)
+            ),
+            Some(LinearRgba::BLACK)
+        );
+        // Averaged over the cells overlapping the region.
+        assert_eq!(
+            get_region_color(image_position, &colors, Rect::new(0.0, 0.0, 150.0, 50.0)),
+            Some(LinearRgba::rgb(0.5, 0.5, 0.5))
+        );
+        assert_eq!(
+            get_region_color(image_position, &colors, Rect::new(500.0, 0.0, 600.0, 100.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_get_color() {
+        let mut tile_colors = TileColors::default();
+        let mut colors = vec![LinearRgba::BLACK; COLOR_GRID_SIZE * COLOR_GRID_SIZE];
+
+        colors[COLOR_GRID_SIZE * COLOR_GRID_SIZE - 1] = LinearRgba::WHITE;
+        tile_colors.grids.insert(
+            TileIndex::new(0, 0, 0),
+            TileColorGrid {
+                image_position: Rect::new(0.0, 0.0, 1024.0, 1024.0),
+                colors,
+            },
+        );
+
+        // The bottom right tile of the next level.
+        assert_eq!(
+            tile_colors.get_color(
+                TileIndex::new(3, 3, 2),
+                Rect::new(768.0, 768.0, 1024.0, 1024.0)
+            ),
+            Some(LinearRgba::WHITE)
+        );
+        // Not from a tile of the same level.
+        assert_eq!(
+            tile_colors.get_color(TileIndex::new(0, 0, 0), Rect::new(0.0, 0.0, 256.0, 256.0)),
+            None
+        );
+    }
+}
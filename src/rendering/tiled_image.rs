@@ -1,23 +1,37 @@
 use crate::{
-    app::app_state::AppState,
+    app::{
+        app_settings::{AppSettings, FitMode},
+        app_state::AppState,
+        session::SessionState,
+    },
     camera::main_camera::MainCamera2d,
-    iiif::{
-        IiifError,
-        image::{IiifFeature, IiifImageFormat, IiifImageInfo},
+    iiif::{IiifError, image::ImageOrientation},
+    rendering::{
+        overlay_image::OverlayImage,
+        tile::{Tile, TileIndex, TileModState, TilePlacement},
     },
-    rendering::tile::{Tile, TileIndex, TileModState},
+    tilesource::{self, ServiceCapabilities, TileSource, single::SingleImageTileSource},
 };
 use bevy::{
+    math::Affine2,
     prelude::{
-        Add, Camera, Component, MessageWriter, On, Projection, Rect, ResMut, Result, Single,
-        Transform, Vec2, Vec3, With, info,
+        Add, Camera, Changed, Component, MessageWriter, On, OrthographicProjection, Projection,
+        Rect, Res, ResMut, Result, Single, Transform, Vec2, Vec3, With, info,
     },
-    window::{RequestRedraw, Window},
+    window::{PrimaryWindow, RequestRedraw, Window},
 };
 use serde::{Deserialize, Serialize};
-use std::{collections::HashSet, ops::RangeInclusive};
+use std::ops::RangeInclusive;
+
+/// Pixels of the full size image from which its tiles are culled aggressively, e.g. of a
+/// gigapixel image, as their entities would grow without bound otherwise.
+const GIGAPIXEL_PIXELS: f64 = 1.0e9;
+
+/// Factor of the zoom past the threshold of a level before the level is changed, so that the
+/// tiles are not reloaded back and forth while the zoom hovers around the threshold.
+const LEVEL_HYSTERESIS: f32 = 1.15;
 
-#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Serialize, Deserialize, Clone, Copy)]
 pub(crate) struct Size {
     pub(crate) width: u32,
     pub(crate) height: u32,
@@ -39,9 +53,12 @@ impl Size {
 pub(crate) fn on_add_tiled_image(
     add: On<Add, TiledImage>,
     tiled_image: Single<&TiledImage>,
-    window: Single<&mut Window>,
+    overlay_image: Option<Single<&mut OverlayImage>>,
+    window: Single<&mut Window, With<PrimaryWindow>>,
     camera2d_query: Single<(&mut Camera, &mut Transform, &mut Projection), With<MainCamera2d>>,
     mut app_state: ResMut<AppState>,
+    app_settings: Res<AppSettings>,
+    mut session_state: ResMut<SessionState>,
     mut tile_mod_state: ResMut<TileModState>,
     mut redraw_request_writer: MessageWriter<RequestRedraw>,
 ) -> Result {
@@ -56,23 +73,57 @@ pub(crate) fn on_add_tiled_image(
 
     let world_max_rect = tiled_image.get_world_max_size_rect();
 
-    // Fit the image to the viewport, or falling back to the window size.
-    let zoom = Vec2::new(world_max_rect.width(), world_max_rect.height())
-        / camera
-            .logical_viewport_size()
-            .unwrap_or_else(|| window.size());
-    let zoom_scale = zoom.max_element();
-    let initial_level = tiled_image.get_level_at(zoom_scale);
+    if let Some(view) = session_state.take_restoring_view() {
+        // Restore the view of the previous session.
+        view.apply_orthographic(
+            &mut transform,
+            orthogonal,
+            world_max_rect.size(),
+            app_state.world_origin,
+        );
+    } else if let Some(region) = app_state.target_region.take() {
+        // Fit the region to the viewport, or falling back to the window size.
+        tiled_image.zoom_camera_to_image_rect(
+            region,
+            camera
+                .logical_viewport_size()
+                .unwrap_or_else(|| window.size()),
+            &app_settings,
+            &mut transform,
+            orthogonal,
+        );
+    } else if app_state.preserve_view && app_state.world_image_max_size.x > 0.0 {
+        // Keep the same view by scaling the camera with the change in the image size,
+        // as the images of the choices may have different resolutions.
+        let ratio = world_max_rect.width() / app_state.world_image_max_size.x;
+        let translation = (transform.translation.truncate() + app_state.world_origin) * ratio
+            - app_state.world_origin;
+
+        orthogonal.scale *= ratio;
+        transform.translation = translation.extend(transform.translation.z);
+    } else {
+        // Fit the image to the viewport by the fit mode, or falling back to the window size.
+        tiled_image.fit_camera(
+            app_settings.fit_mode,
+            camera
+                .logical_viewport_size()
+                .unwrap_or_else(|| window.size()),
+            window.scale_factor(),
+            &mut transform,
+            orthogonal,
+        );
+    }
 
-    app_state.level = initial_level;
-    app_state.world_image_max_size = tiled_image.get_world_max_size_rect().size();
-    orthogonal.scale = zoom_scale;
+    app_state.preserve_view = false;
+    // The fitted view is in logical pixels, while the level is for the physical ones.
+    app_state.scale_factor = window.scale_factor();
+    app_state.level = tiled_image.get_level_at(orthogonal.scale, app_state.scale_factor);
+    app_state.world_image_max_size = world_max_rect.size();
 
-    transform.translation = Vec3::new(
-        world_max_rect.width() / 2.0,
-        -world_max_rect.height() / 2.0,
-        0.0,
-    );
+    // Keep the overlay aligned to the new image.
+    if let Some(mut overlay_image) = overlay_image {
+        overlay_image.fit_width(world_max_rect.width());
+    }
 
     tile_mod_state.invalidate();
     redraw_request_writer.write(RequestRedraw);
@@ -80,153 +131,459 @@ pub(crate) fn on_add_tiled_image(
     Ok(())
 }
 
+/// Keep the scale factor of the window, which may change when it is moved to another display,
+/// and update the level for it.
+pub(crate) fn scale_factor_system(
+    window: Single<&Window, (With<PrimaryWindow>, Changed<Window>)>,
+    projection: Single<&Projection, With<MainCamera2d>>,
+    tiled_image: Option<Single<&TiledImage>>,
+    mut app_state: ResMut<AppState>,
+    mut tile_mod_state: ResMut<TileModState>,
+) {
+    let scale_factor = window.scale_factor();
+
+    if scale_factor == app_state.scale_factor {
+        return;
+    }
+
+    info!("Window scale factor changed to {}", scale_factor);
+    app_state.scale_factor = scale_factor;
+
+    if let Some(tiled_image) = tiled_image
+        && let Projection::Orthographic(orthographic) = projection.into_inner()
+    {
+        app_state.level = tiled_image.get_level_at(orthographic.scale, scale_factor);
+        tile_mod_state.invalidate();
+    }
+}
+
+/// Apply the data saver setting to the images shown when it is changed, and update the level
+/// capped by it.
+pub(crate) fn data_saver_system(
+    app_settings: Res<AppSettings>,
+    tiled_image: Option<Single<&mut TiledImage>>,
+    overlay_image: Option<Single<&mut OverlayImage>>,
+    projection: Single<&Projection, With<MainCamera2d>>,
+    mut app_state: ResMut<AppState>,
+    mut tile_mod_state: ResMut<TileModState>,
+) {
+    let Some(mut tiled_image) = tiled_image else {
+        return;
+    };
+
+    if tiled_image.is_data_saver() == app_settings.data_saver {
+        return;
+    }
+
+    info!("Data saver {}", app_settings.data_saver);
+    tiled_image.set_data_saver(app_settings.data_saver);
+
+    if let Some(mut overlay_image) = overlay_image {
+        overlay_image.0.set_data_saver(app_settings.data_saver);
+    }
+
+    if let Projection::Orthographic(orthographic) = projection.into_inner() {
+        app_state.level = tiled_image.get_level_at(orthographic.scale, app_state.scale_factor);
+    }
+
+    tile_mod_state.invalidate();
+}
+
+/// Fit the camera to show the whole rect in world space in the viewport.
+pub(crate) fn fit_camera_to_rect(
+    world_rect: Rect,
+    viewport_size: Vec2,
+    transform: &mut Transform,
+    orthographic: &mut OrthographicProjection,
+) {
+    orthographic.scale = (world_rect.size() / viewport_size).max_element();
+    transform.translation = world_rect.center().extend(0.0);
+}
+
 /// Image.
 #[derive(Component)]
 pub(crate) struct TiledImage {
-    /// IFFF URL, e.g. "https://stacks.stanford.edu/image/iiif/hg676jb4964%2F0380_796-44"
-    iiif_endpoint: String,
-    /// The number of levels and sizes.
-    levels: Vec<Size>,
-    /// Tile size.
-    tile_size: Size,
-    /// Image format.
-    image_format: IiifImageFormat,
-    /// Supported features.
-    supported_features: HashSet<IiifFeature>,
-    /// Optional sizes when getting the full image.
-    optional_sizes: Vec<Size>,
+    /// Source of the tiles, e.g. an IIIF image service.
+    source: Box<dyn TileSource>,
+    /// Endpoint of the image info, e.g. the IIIF image service URL. Empty if not known.
+    endpoint: String,
+    /// Scale from the image to the world space.
+    world_scale: f32,
+    /// Prefix of the CORS proxy for the image URLs. Empty if not proxied.
+    proxy_prefix: String,
+    /// Orientation of the image on the canvas. The image space is of the canvas, while the
+    /// tiles are of the image of the source.
+    orientation: ImageOrientation,
+    /// Pixels of the overlap requested around the tiles at their level to hide the seams.
+    tile_overlap: u32,
+    /// Position of the world origin from the top left of the image, moved near the camera to keep
+    /// the world space precise at deep zoom.
+    world_origin: Vec2,
+    /// Whether the full size level is skipped to save the data.
+    data_saver: bool,
+    /// Whether the image stands in for the one of the image service which failed, e.g. the
+    /// thumbnail of the canvas.
+    is_fallback: bool,
+    /// Region of the canvas the image is painted on, as the image space is of the canvas. `None`
+    /// if the image is the whole canvas.
+    canvas_region: Option<Rect>,
 }
 
 impl TiledImage {
     /// Create a new image.
-    fn new(
-        iiif_endpoint: String,
-        tile_size: Size,
-        levels: Vec<Size>,
-        image_format: IiifImageFormat,
-        supported_features: HashSet<IiifFeature>,
-        optional_sizes: Vec<Size>,
-    ) -> Self {
+    fn new(source: Box<dyn TileSource>) -> Self {
         Self {
-            iiif_endpoint,
-            tile_size,
-            levels,
-            image_format,
-            supported_features,
-            optional_sizes,
+            source,
+            endpoint: "".to_string(),
+            world_scale: 1.0,
+            proxy_prefix: "".to_string(),
+            orientation: ImageOrientation::default(),
+            tile_overlap: 0,
+            world_origin: Vec2::ZERO,
+            data_saver: false,
+            is_fallback: false,
+            canvas_region: None,
+        }
+    }
+
+    /// Create the image standing in for the one of the image service which failed, from a single
+    /// image stretched to the size of the canvas, e.g. its thumbnail.
+    pub(crate) fn from_fallback_image(url: &str, canvas_size: Size) -> Self {
+        let mut image = Self::new(Box::new(SingleImageTileSource::new(url, canvas_size)));
+
+        image.endpoint = url.to_string();
+        image.is_fallback = true;
+
+        image
+    }
+
+    /// Whether the image stands in for the one of the image service which failed.
+    pub(crate) fn is_fallback(&self) -> bool {
+        self.is_fallback
+    }
+
+    /// Set the orientation of the image on the canvas, e.g. rotated by the manifest.
+    pub(crate) fn set_orientation(&mut self, orientation: ImageOrientation) {
+        self.orientation = orientation;
+    }
+
+    /// Get the orientation of the image on the canvas.
+    pub(crate) fn get_orientation(&self) -> ImageOrientation {
+        self.orientation
+    }
+
+    /// Get the placement of the tiles of the image in the world, which differs between the
+    /// canvases sharing the image.
+    pub(crate) fn get_tile_placement(&self) -> TilePlacement {
+        TilePlacement {
+            orientation: self.orientation,
+            canvas_region: self
+                .canvas_region
+                .map(|x| [x.min.x, x.min.y, x.max.x, x.max.y].map(|x| x.round() as i32)),
+            world_scale_bits: self.world_scale.to_bits(),
         }
     }
 
-    /// Create the image from the IFFF image info JSON.
-    pub(crate) fn try_from_json(
-        json: &str,
-        iiif_endpoint: &str,
+    /// Set the region of the canvas the image is painted on, e.g. the target of its annotation
+    /// or a canvas declaring another size than its image, so that the annotations on the canvas
+    /// align with the image.
+    pub(crate) fn set_canvas_region(&mut self, canvas_region: Option<Rect>) {
+        self.canvas_region = canvas_region;
+    }
+
+    /// Set the scale from the image to the world space, e.g. to align images of different resolutions.
+    pub(crate) fn set_world_scale(&mut self, world_scale: f32) {
+        self.world_scale = world_scale;
+    }
+
+    /// Set the position of the world origin from the top left of the image in world space, which
+    /// is shared by all the images.
+    pub(crate) fn set_world_origin(&mut self, world_origin: Vec2) {
+        self.world_origin = world_origin;
+    }
+
+    /// Set the pixels of the overlap requested around the tiles, which is ignored if the tiles
+    /// cannot be requested at any position.
+    pub(crate) fn set_tile_overlap(&mut self, tile_overlap: u32) {
+        self.tile_overlap = tile_overlap;
+    }
+
+    /// Skip the full size level and request the smaller images to save the data, e.g. on mobile.
+    pub(crate) fn set_data_saver(&mut self, data_saver: bool) {
+        self.data_saver = data_saver;
+        self.source.set_data_saver(data_saver);
+    }
+
+    /// Whether the full size level is skipped to save the data.
+    pub(crate) fn is_data_saver(&self) -> bool {
+        self.data_saver
+    }
+
+    /// Set the prefix of the CORS proxy for the image URLs.
+    pub(crate) fn set_proxy_prefix(&mut self, proxy_prefix: &str) {
+        self.proxy_prefix = proxy_prefix.to_string();
+    }
+
+    /// Create the image from the image info of the endpoint, e.g. an IIIF info.json.
+    pub(crate) fn try_from_info(
+        content: &str,
+        endpoint: &str,
     ) -> core::result::Result<Self, IiifError> {
-        let iiif_image_info = IiifImageInfo::try_from_json(json)?;
-
-        // Get tile size and levels.
-        // We require both region by px and size by width/height for the tiling.
-        // If not, we will only get the full image.
-        let supported_features: HashSet<_> = iiif_image_info
-            .get_profile_details()
-            .flat_map(|x| (*x).get_supported_features())
-            .collect();
-        let tile_size: Size;
-        let levels: Vec<Size>;
-
-        if supported_features.contains(&IiifFeature::RegionByPx)
-            && supported_features.contains(&IiifFeature::SizeByWh)
-        {
-            info!("RegionByPx and SizeByWh supported. Use tiling.");
-            tile_size = iiif_image_info.get_tile_size();
-            levels = iiif_image_info.get_tile_scaling_sizes();
-        } else {
-            info!("RegionByPx or SizeByWh not supported. Get the full image.");
-            tile_size = Size::new(iiif_image_info.get_width(), iiif_image_info.get_height());
-            levels = vec![tile_size];
-        };
+        let mut image = Self::new(tilesource::try_from_info(content, endpoint)?);
 
-        // Get optional sizes.
-        let optional_sizes = iiif_image_info.get_optional_sizes();
-
-        // Get the image format.
-        let image_format = iiif_image_info
-            .get_profile_details()
-            .next()
-            .ok_or(IiifError::IiifMissingInfo(format!(
-                "missing profile in '{}'",
-                iiif_endpoint
-            )))?
-            .get_formats()
-            .next()
-            .ok_or(IiifError::IiifMissingInfo(format!(
-                "missing image format in '{}'",
-                iiif_endpoint
-            )))?;
-
-        Ok(TiledImage::new(
-            iiif_endpoint.to_string(),
-            tile_size,
-            levels,
-            image_format,
-            supported_features,
-            optional_sizes,
-        ))
+        image.endpoint = endpoint.to_string();
+
+        Ok(image)
     }
 
-    /// Get URl and size of the thumbnail.
-    pub(crate) fn get_image_thumbnail(&self, size: u32) -> (String, Vec2) {
-        let max_size = self.get_max_size();
+    /// Whether the image is so large, e.g. a gigapixel image, that only the tiles near the view
+    /// are kept.
+    pub(crate) fn is_gigapixel(&self) -> bool {
+        let image_max_size = self.get_max_size();
 
-        // If size by width/height is not supported, we will pick from the suggested sizes.
-        let thumbnail_size = if self.supported_features.contains(&IiifFeature::SizeByWh) {
-            let pct = size as f32 / max_size.max_element();
+        image_max_size.x as f64 * image_max_size.y as f64 >= GIGAPIXEL_PIXELS
+    }
 
-            Size::new((pct * max_size.x) as u32, (pct * max_size.y) as u32)
-        } else {
-            self.optional_sizes
-                .iter()
-                .find(|x| x.width * x.height > size * size)
-                .map_or_else(
-                    || {
-                        *self
-                            .optional_sizes
-                            .first()
-                            .expect("should have at least one size")
-                    },
-                    |x| Size::new(x.width, x.height),
-                )
-        };
+    /// Whether the tiles fit in the textures of the max size, e.g. the limit of the graphics
+    /// backend.
+    pub(crate) fn fits_texture_size(&self, max_texture_size: u32) -> bool {
+        let tile_size = self.source.get_tile_size();
+
+        tile_size.width <= max_texture_size && tile_size.height <= max_texture_size
+    }
+
+    /// Get the endpoint of the image info, which identifies the tiles of the image in the cache.
+    pub(crate) fn get_endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Get the image sizes of the levels of the source, from the smallest to the full size.
+    pub(crate) fn get_levels(&self) -> &[Size] {
+        self.source.get_levels()
+    }
+
+    /// Get the tile size of the source.
+    #[cfg_attr(any(target_arch = "wasm32", target_os = "android"), allow(dead_code))]
+    pub(crate) fn get_tile_size(&self) -> Size {
+        self.source.get_tile_size()
+    }
+
+    /// Get the capabilities advertised by the image info.
+    pub(crate) fn get_capabilities(&self) -> ServiceCapabilities {
+        self.source.get_capabilities()
+    }
+
+    /// Get the image format of the tiles requested, e.g. "jpg".
+    pub(crate) fn get_format(&self) -> String {
+        self.source.get_format()
+    }
+
+    /// Request the tiles in the format, e.g. the one working for the endpoint before.
+    pub(crate) fn set_format(&mut self, format: &str) {
+        self.source.set_format(format);
+    }
+
+    /// Request the tiles in the next advertised format if the format failed and is still
+    /// requested. Get the format to request the tiles in, or `None` if no other format is left.
+    pub(crate) fn fall_back_format(&mut self, failed_format: &str) -> Option<String> {
+        let format = self.source.get_format();
+
+        // Already fallen back by another tile.
+        if format != failed_format {
+            return Some(format);
+        }
+
+        let formats = self.source.get_capabilities().formats;
+        let index = formats.iter().position(|x| *x == format)?;
+        let next_format = formats.get(index + 1)?;
+
+        self.source.set_format(next_format);
+
+        // The format may be fixed, e.g. by the data saver.
+        let format = self.source.get_format();
+
+        (format != failed_format).then_some(format)
+    }
+
+    /// Get the URL of the first tile of the smallest level, e.g. to check that the tiles are
+    /// served as advertised.
+    pub(crate) fn get_sample_tile_url(&self) -> String {
+        let image_position = Rect::from_corners(
+            self.tile_to_image(0, Vec2::ZERO),
+            self.tile_to_image(0, Vec2::ONE).min(self.get_max_size()),
+        );
+
+        self.get_image_tile_url_at(TileIndex::new(0, 0, 0), image_position)
+    }
+
+    /// Get URl and size of the thumbnail.
+    pub(crate) fn get_image_thumbnail(&self, size: u32) -> (String, Vec2) {
+        let (url, thumbnail_size) = self.source.get_thumbnail(size);
 
-        info!("Thumbnai {:?}", thumbnail_size);
         (
-            self.get_image_url(0, 0, max_size.x as u32, max_size.y as u32, thumbnail_size),
+            crate::web::get_proxy_url(&self.proxy_prefix, &url),
             Vec2::from(thumbnail_size),
         )
     }
 
-    /// Get URL for the image tile at the position.
-    pub(crate) fn get_image_tile_url_at(&self, image_position: Rect) -> String {
-        self.get_image_url(
-            image_position.min.x.round() as u32,
-            image_position.min.y.round() as u32,
-            (image_position.max.x - image_position.min.x.round()).round() as u32,
-            (image_position.max.y - image_position.min.y.round()).round() as u32,
-            self.tile_size,
+    /// Get URL for the image tile at the index and the position.
+    pub(crate) fn get_image_tile_url_at(&self, index: TileIndex, image_position: Rect) -> String {
+        crate::web::get_proxy_url(
+            &self.proxy_prefix,
+            &self.source.get_tile_url(index, image_position),
         )
     }
 
+    /// Get the image API URL of the region of the full size image in image space, clipped to
+    /// the image. `None` if the region is outside of the image or the source is not an IIIF
+    /// image service.
+    pub(crate) fn get_region_url(&self, rect: Rect) -> Option<String> {
+        let region = self.get_source_rect(rect);
+
+        if region.is_empty() {
+            return None;
+        }
+
+        self.source.get_region_url(region, self.orientation)
+    }
+
+    /// Get the rect of the full size image of the source for the rect in image space, i.e. before
+    /// the orientation on the canvas, clipped to the image.
+    pub(crate) fn get_source_rect(&self, rect: Rect) -> Rect {
+        Rect::from_corners(self.to_source(rect.min), self.to_source(rect.max))
+            .intersect(Rect::from_corners(Vec2::ZERO, self.get_max_size()))
+    }
+
+    /// Get the indices and the positions of the tiles of the full size level covering the rect of
+    /// the full size image of the source, e.g. to stitch them.
+    #[cfg_attr(any(target_arch = "wasm32", target_os = "android"), allow(dead_code))]
+    pub(crate) fn get_full_size_tiles(&self, source_rect: Rect) -> Vec<(TileIndex, Rect)> {
+        let level = self.source.get_levels().len() - 1;
+        let max_size = self.get_max_size();
+        let source_rect = source_rect.intersect(Rect::from_corners(Vec2::ZERO, max_size));
+
+        if source_rect.is_empty() {
+            return Vec::new();
+        }
+
+        let tile_min = self.image_to_tile(level, source_rect.min).floor();
+        let tile_max = self.image_to_tile(level, source_rect.max).ceil();
+        let mut tiles = Vec::new();
+
+        for y in tile_min.y as u32..tile_max.y as u32 {
+            for x in tile_min.x as u32..tile_max.x as u32 {
+                let position = Rect::from_corners(
+                    self.tile_to_image(level, Vec2::new(x as f32, y as f32)),
+                    self.tile_to_image(level, Vec2::new((x + 1) as f32, (y + 1) as f32))
+                        .min(max_size),
+                );
+
+                if position.width() > 0.5 && position.height() > 0.5 {
+                    tiles.push((TileIndex::new(x, y, level as u32), position));
+                }
+            }
+        }
+
+        tiles
+    }
+
     /// Get the image max size in world space.
     pub(crate) fn get_world_max_size_rect(&self) -> Rect {
+        self.image_to_world_rect(self.get_image_max_size_rect())
+    }
+
+    /// Get the size of a tile at the level in world space, e.g. to pan by a tile.
+    pub(crate) fn get_world_tile_size(&self, level: usize) -> Vec2 {
+        self.orientation
+            .to_canvas_size(self.tile_to_image(level, Vec2::ONE))
+            * self.get_canvas_scale()
+            * self.world_scale
+    }
+
+    /// Convert the rect from image to world space.
+    pub(crate) fn image_to_world_rect(&self, rect: Rect) -> Rect {
         Rect::from_corners(
-            self.image_to_world(Vec2::ZERO).truncate(),
-            self.image_to_world(self.get_max_size()).truncate(),
+            self.image_to_world(rect.min).truncate(),
+            self.image_to_world(rect.max).truncate(),
         )
     }
 
-    /// Get the image max size in image space.
+    /// Convert the rect from world to image space.
+    pub(crate) fn world_to_image_rect(&self, rect: Rect) -> Rect {
+        Rect::from_corners(
+            self.world_to_image(rect.min.extend(0.0)),
+            self.world_to_image(rect.max.extend(0.0)),
+        )
+    }
+
+    /// Fit the camera to the image in the viewport of the logical size by the fit mode.
+    pub(crate) fn fit_camera(
+        &self,
+        fit_mode: FitMode,
+        viewport_size: Vec2,
+        scale_factor: f32,
+        transform: &mut Transform,
+        orthographic: &mut OrthographicProjection,
+    ) {
+        let world_rect = self.get_world_max_size_rect();
+        let ratio = world_rect.size() / viewport_size;
+
+        orthographic.scale = match fit_mode {
+            FitMode::Whole => ratio.max_element(),
+            FitMode::Width => ratio.x,
+            FitMode::Height => ratio.y,
+            FitMode::Fill => ratio.min_element(),
+            // A pixel of the image on a physical pixel, of which there are more than the logical
+            // ones on a high DPI display.
+            FitMode::Actual => self.world_scale * scale_factor,
+        };
+
+        let mut centre = world_rect.center();
+        let half_viewport_height = viewport_size.y * orthographic.scale / 2.0;
+
+        // Start from the top of a tall image fitted to its width, e.g. a page of a newspaper.
+        if fit_mode == FitMode::Width && world_rect.height() > half_viewport_height * 2.0 {
+            centre.y = world_rect.max.y - half_viewport_height;
+        }
+
+        transform.translation = centre.extend(0.0);
+    }
+
+    /// Fit the camera to show the region of the full size image in the viewport of the logical
+    /// size, clamped to the zoom limits of the settings.
+    pub(crate) fn zoom_camera_to_image_rect(
+        &self,
+        image_rect: Rect,
+        viewport_size: Vec2,
+        app_settings: &AppSettings,
+        transform: &mut Transform,
+        orthographic: &mut OrthographicProjection,
+    ) {
+        let max_camera_zoom_scale =
+            self.get_world_max_size_rect().size().max_element() / app_settings.min_image_size;
+
+        fit_camera_to_rect(
+            self.image_to_world_rect(image_rect),
+            viewport_size,
+            transform,
+            orthographic,
+        );
+
+        orthographic.scale = orthographic
+            .scale
+            .max(app_settings.min_camera_zoom_scale)
+            .min(max_camera_zoom_scale);
+    }
+
+    /// Get the image max size in image space, i.e. the region of the canvas the image is painted
+    /// on.
     pub(crate) fn get_image_max_size_rect(&self) -> Rect {
-        Rect::from_corners(Vec2::ZERO, self.get_max_size())
+        self.canvas_region.unwrap_or_else(|| {
+            Rect::from_corners(
+                Vec2::ZERO,
+                self.orientation.to_canvas_size(self.get_max_size()),
+            )
+        })
     }
 
     // /// Get number of resolution levels.
@@ -234,15 +591,26 @@ impl TiledImage {
     //     self.levels.len()
     // }
 
-    /// Get the resolution level given the world zoom scale.
-    pub(crate) fn get_level_at(&self, world_zoom_scale: f32) -> usize {
-        let max_level = self.levels.len() - 1;
+    /// Get the resolution level given the world zoom scale, i.e. the world size of a logical
+    /// pixel, and the scale factor from the logical to the physical pixels of the window.
+    pub(crate) fn get_level_at(&self, world_zoom_scale: f32, scale_factor: f32) -> usize {
+        let levels = self.source.get_levels();
+        let max_level = if self.data_saver {
+            (levels.len() - 1).saturating_sub(1)
+        } else {
+            levels.len() - 1
+        };
         let image_zoom_scale =
             self.world_to_image(Vec3::splat(world_zoom_scale)) - self.world_to_image(Vec3::ZERO);
-        let image_size = self.get_max_size() / image_zoom_scale;
+        // From the canvas to the axes of the source.
+        let image_zoom_scale = self
+            .orientation
+            .to_canvas_size(image_zoom_scale / self.get_canvas_scale());
+        // Image size in the physical pixels of the screen.
+        let image_size = self.get_max_size() / image_zoom_scale * scale_factor;
 
         for level in 0..=max_level {
-            if image_size.x.abs() as u32 <= self.levels[level].width {
+            if image_size.x.abs() as u32 <= levels[level].width {
                 return level;
             }
         }
@@ -250,6 +618,53 @@ impl TiledImage {
         max_level
     }
 
+    /// Get the resolution level at the zoom like `get_level_at`, but keep the current level until
+    /// the zoom is past the threshold of another level by a margin.
+    pub(crate) fn get_level_with_hysteresis(
+        &self,
+        level: usize,
+        world_zoom_scale: f32,
+        scale_factor: f32,
+    ) -> usize {
+        let min_level = self.get_level_at(world_zoom_scale * LEVEL_HYSTERESIS, scale_factor);
+        let max_level = self.get_level_at(world_zoom_scale / LEVEL_HYSTERESIS, scale_factor);
+
+        level.clamp(min_level, max_level)
+    }
+
+    /// Get the region to request for the tile at the position in the full size image, larger by
+    /// the overlap at the level, and the UV transform cropping its texture back to the tile.
+    ///
+    /// The static tiles of the sources with the overlap, e.g. a DZI, always include it.
+    fn get_overlapped_region(&self, level: usize, image_position: Rect) -> Option<(Rect, Affine2)> {
+        let tile_overlap = match self.source.get_tile_overlap() {
+            0 if self.source.supports_any_region() => self.tile_overlap,
+            0 => 0,
+            source_overlap => source_overlap,
+        };
+
+        if tile_overlap == 0 {
+            return None;
+        }
+
+        let image_max_size = self.get_max_size();
+        let level_scale = image_max_size.x / self.source.get_levels()[level].width as f32;
+        let overlap = tile_overlap as f32 * level_scale;
+        let region = Rect::from_corners(
+            (image_position.min - overlap).max(Vec2::ZERO),
+            (image_position.max + overlap).min(image_max_size),
+        );
+
+        Some((
+            region,
+            Affine2::from_scale_angle_translation(
+                image_position.size() / region.size(),
+                0.0,
+                (image_position.min - region.min) / region.size(),
+            ),
+        ))
+    }
+
     /// Get the required tile range to display between the world min and max.
     pub(crate) fn get_required_tiles(
         &self,
@@ -257,14 +672,16 @@ impl TiledImage {
         world_pos_min: Vec3,
         world_pos_max: Vec3,
     ) -> (Vec<Tile>, RangeInclusive<u32>, RangeInclusive<u32>) {
-        // Convert from the world space to the image space, and clamp using the max image size.
+        // Convert from the world space to the image space of the source, and clamp using the
+        // max image size.
         let image_max_size = self.get_max_size();
+        let uv_transform = self.orientation.get_uv_transform();
 
         let image_p0 = self
-            .world_to_image(world_pos_min)
+            .to_source(self.world_to_image(world_pos_min))
             .clamp(Vec2::ZERO, image_max_size - 1.0);
         let image_p1 = self
-            .world_to_image(world_pos_max)
+            .to_source(self.world_to_image(world_pos_max))
             .clamp(Vec2::ZERO, image_max_size - 1.0);
 
         // Get them in the correct order.
@@ -276,8 +693,8 @@ impl TiledImage {
         let tile_max = self.image_to_tile(level, image_max);
 
         let mut tiles = Vec::new();
-        let mut tile_min_x = 0;
-        let mut tile_min_y = 0;
+        let mut tile_min_x = u32::MAX;
+        let mut tile_min_y = u32::MAX;
         let mut tile_max_x = 0;
         let mut tile_max_y = 0;
 
@@ -295,15 +712,26 @@ impl TiledImage {
 
                 if image_position.width() > 0.5 && image_position.height() > 0.5 {
                     let world_position = Rect::from_corners(
-                        self.image_to_world(image_top_left).truncate(),
-                        self.image_to_world(image_bot_rght).truncate(),
+                        self.image_to_world(self.to_canvas(image_top_left))
+                            .truncate(),
+                        self.image_to_world(self.to_canvas(image_bot_rght))
+                            .truncate(),
                     );
 
                     tile_min_x = tile_min_x.min(x);
                     tile_max_x = tile_max_x.max(x);
                     tile_min_y = tile_min_y.min(y);
                     tile_max_y = tile_max_y.max(y);
-                    tiles.push(Tile::new(tile_index, image_position, world_position));
+                    let mut tile =
+                        Tile::new(tile_index, image_position, world_position, uv_transform);
+
+                    if let Some((request_position, crop)) =
+                        self.get_overlapped_region(level, image_position)
+                    {
+                        tile.request_position = request_position;
+                        tile.uv_transform = crop * uv_transform;
+                    }
+                    tiles.push(tile);
                 }
             }
         }
@@ -313,31 +741,67 @@ impl TiledImage {
 
     /// Convert from world to image space.
     pub(crate) fn world_to_image(&self, p: Vec3) -> Vec2 {
-        p.reflect(Vec3::Y).truncate()
+        (p.truncate() + self.world_origin).reflect(Vec2::Y) / self.world_scale
     }
 
     /// Convert from image to world space.
     pub(crate) fn image_to_world(&self, p: Vec2) -> Vec3 {
-        p.extend(0.0).reflect(Vec3::Y)
+        ((p * self.world_scale).reflect(Vec2::Y) - self.world_origin).extend(0.0)
+    }
+
+    /// Convert from world space to the full size image of the source, e.g. to find its pixel.
+    pub(crate) fn world_to_source(&self, p: Vec3) -> Vec2 {
+        self.to_source(self.world_to_image(p))
+    }
+
+    /// Convert from the image space, i.e. the canvas, to the full size image of the source.
+    fn to_source(&self, p: Vec2) -> Vec2 {
+        self.orientation.to_image(
+            (p - self.get_canvas_offset()) / self.get_canvas_scale(),
+            self.get_max_size(),
+        )
+    }
+
+    /// Convert from the full size image of the source to the image space, i.e. the canvas.
+    fn to_canvas(&self, p: Vec2) -> Vec2 {
+        self.orientation.to_canvas(p, self.get_max_size()) * self.get_canvas_scale()
+            + self.get_canvas_offset()
+    }
+
+    /// Get the scale from the image on the canvas to the canvas, which may differ by the axis.
+    fn get_canvas_scale(&self) -> Vec2 {
+        let image_size = self.orientation.to_canvas_size(self.get_max_size());
+
+        self.canvas_region
+            .map_or(Vec2::ONE, |x| x.size() / image_size)
+    }
+
+    /// Get the top left of the image on the canvas.
+    fn get_canvas_offset(&self) -> Vec2 {
+        self.canvas_region.map_or(Vec2::ZERO, |x| x.min)
     }
 
     /// Convert from image to tile space.
     fn image_to_tile(&self, level: usize, p: Vec2) -> Vec2 {
         let scale = self.world_to_image_scale(level);
 
-        p / (Vec2::from(self.tile_size) * scale)
+        p / (Vec2::from(self.source.get_tile_size()) * scale)
     }
 
     /// Convert from the tile to image space.
     fn tile_to_image(&self, level: usize, p: Vec2) -> Vec2 {
         let scale = self.world_to_image_scale(level);
 
-        p * Vec2::from(self.tile_size) * scale
+        p * Vec2::from(self.source.get_tile_size()) * scale
     }
 
     /// Get the max size of the image.
     fn get_max_size(&self) -> Vec2 {
-        let last_level = self.levels.last().expect("should have at least one level");
+        let last_level = self
+            .source
+            .get_levels()
+            .last()
+            .expect("should have at least one level");
 
         Vec2::from(*last_level)
     }
@@ -346,47 +810,34 @@ impl TiledImage {
     fn world_to_image_scale(&self, level: usize) -> f32 {
         let image_max_size = self.get_max_size();
 
-        image_max_size.x / self.levels[level].width as f32
+        image_max_size.x / self.source.get_levels()[level].width as f32
     }
 
-    /// Get the image URL.
-    fn get_image_url(&self, left: u32, top: u32, width: u32, height: u32, size: Size) -> String {
-        let iiif_endpoint = &self.iiif_endpoint;
-        let image_format = &self.image_format;
-        let max_size = self.get_max_size();
-
-        let region =
-            if left == 0 && top == 0 && width == max_size.x as u32 && height == max_size.y as u32 {
-                "full".into()
-            } else {
-                format!("{left},{top},{width},{height}")
-            };
-
-        let size = format!("{},{}", size.width, size.height);
-
-        // E.g. "https://stacks.stanford.edu/image/iiif/hg676jb4964%2F0380_796-44/{},{},{},{}/pct:25/0/default.png"
-        format!("{iiif_endpoint}/{region}/{size}/0/default.{image_format}")
-    }
-
-    /// Get the image info end point.
-    pub(crate) fn get_image_info_url(iiif_endpoint: &str) -> String {
-        format!("{iiif_endpoint}/info.json")
+    /// Get the image info URL of the endpoint.
+    pub(crate) fn get_image_info_url(endpoint: &str) -> String {
+        tilesource::get_info_url(endpoint)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        iiif::image::{IiifFeature, IiifImageFormat},
+        tilesource::iiif::IiifTileSource,
+    };
     use std::collections::HashSet;
 
     const TILE_SIZE: f32 = 1024.0;
 
     fn setup() -> TiledImage {
-        let mut supported_features = HashSet::new();
+        setup_with_features(&[IiifFeature::SizeByWhListed])
+    }
 
-        supported_features.insert(IiifFeature::SizeByWhListed);
+    fn setup_with_features(features: &[IiifFeature]) -> TiledImage {
+        let supported_features: HashSet<_> = features.iter().cloned().collect();
 
-        TiledImage::new(
+        TiledImage::new(Box::new(IiifTileSource::new(
             "https://iiif_end_point/uuid".into(),
             Size::new(TILE_SIZE as u32, TILE_SIZE as u32),
             vec![
@@ -401,16 +852,92 @@ mod tests {
                 Size::new(1357, 955),
                 Size::new(2713, 1910),
             ],
+        )))
+    }
+
+    #[test]
+    fn test_get_image_url_with_proxy() {
+        let mut image = setup();
+        image.set_proxy_prefix("https://proxy/");
+
+        assert_eq!(
+            image.get_image_tile_url_at(
+                TileIndex::new(0, 0, 2),
+                Rect::from_corners(Vec2::new(1.0, 2.0), Vec2::new(4.0, 6.0))
+            ),
+            "https://proxy/https://iiif_end_point/uuid/1,2,3,4/1024,1024/0/default.png"
+        );
+        assert_eq!(
+            image.get_image_thumbnail(256).0,
+            "https://proxy/https://iiif_end_point/uuid/full/678,478/0/default.png"
+        );
+    }
+
+    #[test]
+    fn test_get_sample_tile_url() {
+        let image = setup();
+
+        assert_eq!(
+            image.get_sample_tile_url(),
+            "https://iiif_end_point/uuid/full/1024,1024/0/default.png"
+        );
+    }
+
+    #[test]
+    fn test_fall_back_format() {
+        let mut image = TiledImage::try_from_info(
+            r#"{
+                "@context": "http://iiif.io/api/image/3/context.json",
+                "id": "https://example.org/image",
+                "type": "ImageService3",
+                "protocol": "http://iiif.io/api/image",
+                "profile": "level2",
+                "width": 1000,
+                "height": 1000,
+                "extraFormats": ["webp"]
+            }"#,
+            "https://example.org/image",
         )
+        .expect("should parse the image info");
+
+        assert_eq!(image.get_format(), "jpg");
+        assert_eq!(image.fall_back_format("jpg"), Some("png".to_string()));
+        // Another tile of the format failed after the fallback.
+        assert_eq!(image.fall_back_format("jpg"), Some("png".to_string()));
+        assert!(image.get_sample_tile_url().ends_with("/default.png"));
+        assert_eq!(image.fall_back_format("png"), Some("webp".to_string()));
+        assert_eq!(image.fall_back_format("webp"), None);
     }
 
     #[test]
-    fn test_get_image_url() {
+    fn test_get_full_size_tiles() {
         let image = setup();
 
         assert_eq!(
-            image.get_image_url(1, 2, 3, 4, Size::new(1, 2)),
-            "https://iiif_end_point/uuid/1,2,3,4/1,2/0/default.png"
+            image.get_full_size_tiles(Rect::new(1000.0, 10.0, 1100.0, 1500.0)),
+            vec![
+                (
+                    TileIndex::new(0, 0, 2),
+                    Rect::new(0.0, 0.0, TILE_SIZE, TILE_SIZE)
+                ),
+                (
+                    TileIndex::new(1, 0, 2),
+                    Rect::new(TILE_SIZE, 0.0, 2.0 * TILE_SIZE, TILE_SIZE)
+                ),
+                (
+                    TileIndex::new(0, 1, 2),
+                    Rect::new(0.0, TILE_SIZE, TILE_SIZE, 1910.0)
+                ),
+                (
+                    TileIndex::new(1, 1, 2),
+                    Rect::new(TILE_SIZE, TILE_SIZE, 2.0 * TILE_SIZE, 1910.0)
+                ),
+            ]
+        );
+        assert!(
+            image
+                .get_full_size_tiles(Rect::new(3000.0, 0.0, 3100.0, 10.0))
+                .is_empty()
         );
     }
 
@@ -454,9 +981,48 @@ mod tests {
     fn test_get_level_at() {
         let image = setup();
 
-        assert_eq!(image.get_level_at(1.0), 2);
-        assert_eq!(image.get_level_at(2.0), 1);
-        assert_eq!(image.get_level_at(4.0), 0);
+        assert_eq!(image.get_level_at(1.0, 1.0), 2);
+        assert_eq!(image.get_level_at(2.0, 1.0), 1);
+        assert_eq!(image.get_level_at(4.0, 1.0), 0);
+
+        // Higher resolution for the same logical size on a high-DPI display.
+        assert_eq!(image.get_level_at(2.0, 2.0), 2);
+        assert_eq!(image.get_level_at(4.0, 2.0), 1);
+        assert_eq!(image.get_level_at(8.0, 2.0), 0);
+    }
+
+    #[test]
+    fn test_get_level_with_hysteresis() {
+        let image = setup();
+
+        // Level 2 from the zoom of 2, but kept at 1 until zoomed in by the margin.
+        assert_eq!(image.get_level_with_hysteresis(1, 1.9, 1.0), 1);
+        assert_eq!(image.get_level_with_hysteresis(1, 1.5, 1.0), 2);
+
+        // Kept at 2 until zoomed out by the margin.
+        assert_eq!(image.get_level_with_hysteresis(2, 2.1, 1.0), 2);
+        assert_eq!(image.get_level_with_hysteresis(2, 2.5, 1.0), 1);
+
+        // Another image may have fewer levels.
+        assert_eq!(image.get_level_with_hysteresis(5, 1.0, 1.0), 2);
+        assert_eq!(image.get_level_with_hysteresis(0, 1.0, 1.0), 2);
+    }
+
+    #[test]
+    fn test_get_level_at_with_data_saver() {
+        let mut image = setup();
+
+        image.set_data_saver(true);
+
+        // Not the full size level.
+        assert_eq!(image.get_level_at(1.0, 1.0), 1);
+        assert_eq!(image.get_level_at(0.25, 2.0), 1);
+        assert_eq!(image.get_level_at(4.0, 1.0), 0);
+        assert!(
+            image
+                .get_image_tile_url_at(TileIndex::new(0, 0, 1), Rect::new(0.0, 0.0, 1.0, 1.0))
+                .ends_with(".jpg")
+        );
     }
 
     #[test]
@@ -475,33 +1041,289 @@ mod tests {
         assert_eq!(image.image_to_world(p), p.extend(0.0).reflect(Vec3::Y));
     }
 
+    #[test]
+    fn test_image_to_world_rect() {
+        let image = setup();
+        let rect = Rect::new(10.0, 20.0, 110.0, 70.0);
+
+        assert_eq!(
+            image.image_to_world_rect(rect),
+            Rect::new(10.0, -70.0, 110.0, -20.0)
+        );
+        assert_eq!(
+            image.world_to_image_rect(image.image_to_world_rect(rect)),
+            rect
+        );
+
+        let mut transform = Transform::default();
+        let mut orthographic = OrthographicProjection::default_2d();
+
+        fit_camera_to_rect(
+            image.image_to_world_rect(rect),
+            Vec2::new(200.0, 200.0),
+            &mut transform,
+            &mut orthographic,
+        );
+
+        assert_eq!(orthographic.scale, 0.5);
+        assert_eq!(transform.translation, Vec3::new(60.0, -45.0, 0.0));
+    }
+
+    #[test]
+    fn test_fit_camera() {
+        let image = setup();
+        let viewport_size = Vec2::new(1000.0, 500.0);
+        let mut transform = Transform::default();
+        let mut orthographic = OrthographicProjection::default_2d();
+
+        image.fit_camera(
+            FitMode::Whole,
+            viewport_size,
+            1.0,
+            &mut transform,
+            &mut orthographic,
+        );
+        assert_eq!(orthographic.scale, 1910.0 / 500.0);
+        assert_eq!(transform.translation, Vec3::new(2713.0 / 2.0, -955.0, 0.0));
+
+        image.fit_camera(
+            FitMode::Width,
+            viewport_size,
+            1.0,
+            &mut transform,
+            &mut orthographic,
+        );
+        assert_eq!(orthographic.scale, 2.713);
+        // From the top of the image.
+        assert!(
+            transform
+                .translation
+                .abs_diff_eq(Vec3::new(1356.5, -678.25, 0.0), 1e-3)
+        );
+
+        image.fit_camera(
+            FitMode::Fill,
+            viewport_size,
+            1.0,
+            &mut transform,
+            &mut orthographic,
+        );
+        assert_eq!(orthographic.scale, 2.713);
+        assert_eq!(transform.translation, Vec3::new(2713.0 / 2.0, -955.0, 0.0));
+
+        image.fit_camera(
+            FitMode::Actual,
+            viewport_size,
+            2.0,
+            &mut transform,
+            &mut orthographic,
+        );
+        assert_eq!(orthographic.scale, 2.0);
+    }
+
+    #[test]
+    fn test_zoom_camera_to_image_rect() {
+        let image = setup();
+        let app_settings = AppSettings::default();
+        let viewport_size = Vec2::new(400.0, 200.0);
+        let mut transform = Transform::default();
+        let mut orthographic = OrthographicProjection::default_2d();
+
+        image.zoom_camera_to_image_rect(
+            Rect::new(100.0, 100.0, 900.0, 300.0),
+            viewport_size,
+            &app_settings,
+            &mut transform,
+            &mut orthographic,
+        );
+        assert_eq!(orthographic.scale, 2.0);
+        assert_eq!(transform.translation, Vec3::new(500.0, -200.0, 0.0));
+
+        // Not zooming in beyond the limit for a tiny region.
+        image.zoom_camera_to_image_rect(
+            Rect::new(100.0, 100.0, 101.0, 101.0),
+            viewport_size,
+            &app_settings,
+            &mut transform,
+            &mut orthographic,
+        );
+        assert_eq!(orthographic.scale, app_settings.min_camera_zoom_scale);
+        assert_eq!(transform.translation, Vec3::new(100.5, -100.5, 0.0));
+    }
+
+    #[test]
+    fn test_world_scale() {
+        let mut image = setup();
+
+        image.set_world_scale(2.0);
+
+        assert_eq!(
+            image.image_to_world(Vec2::new(1.0, 2.0)),
+            Vec3::new(2.0, -4.0, 0.0)
+        );
+        assert_eq!(
+            image.world_to_image(Vec3::new(2.0, -4.0, 0.0)),
+            Vec2::new(1.0, 2.0)
+        );
+        assert_eq!(image.get_level_at(2.0, 1.0), 2);
+        assert_eq!(
+            image.get_world_max_size_rect(),
+            Rect::from_corners(Vec2::ZERO, Vec2::new(2713.0 * 2.0, -1910.0 * 2.0))
+        );
+    }
+
+    #[test]
+    fn test_world_origin() {
+        let mut image = setup();
+
+        image.set_world_origin(Vec2::new(2000.0, -1000.0));
+
+        assert_eq!(
+            image.image_to_world(Vec2::new(2000.5, 1000.25)),
+            Vec3::new(0.5, -0.25, 0.0)
+        );
+        assert_eq!(
+            image.world_to_image(Vec3::new(0.5, -0.25, 0.0)),
+            Vec2::new(2000.5, 1000.25)
+        );
+        // The levels are by the size, not the position.
+        assert_eq!(image.get_level_at(2.0, 1.0), setup().get_level_at(2.0, 1.0));
+        assert_eq!(
+            image.get_world_max_size_rect(),
+            Rect::from_corners(Vec2::new(-2000.0, 1000.0), Vec2::new(713.0, -910.0))
+        );
+    }
+
+    #[test]
+    fn test_get_region_url() {
+        let mut image = setup();
+
+        assert_eq!(
+            image.get_region_url(Rect::new(-10.0, 100.0, 200.0, 300.0)),
+            Some("https://iiif_end_point/uuid/0,100,200,200/max/0/default.jpg".to_string())
+        );
+        assert_eq!(
+            image.get_region_url(Rect::new(3000.0, 0.0, 3100.0, 10.0)),
+            None
+        );
+
+        // In the image of the source, rotated back from the canvas.
+        image.set_orientation(ImageOrientation {
+            rotation: 90,
+            mirrored: false,
+        });
+
+        assert_eq!(
+            image.get_region_url(Rect::new(0.0, 0.0, 100.0, 200.0)),
+            Some("https://iiif_end_point/uuid/0,1810,200,100/max/90/default.jpg".to_string())
+        );
+    }
+
     #[test]
     fn test_get_image_tile_at() {
         let image = setup();
 
         assert_eq!(
-            image.get_image_tile_url_at(Rect::from_corners(
-                Vec2::new(10.3, 20.5),
-                Vec2::new(200.5, 300.1)
-            )),
+            image.get_image_tile_url_at(
+                TileIndex::new(0, 0, 2),
+                Rect::from_corners(Vec2::new(10.3, 20.5), Vec2::new(200.5, 300.1))
+            ),
             "https://iiif_end_point/uuid/10,21,191,279/1024,1024/0/default.png"
         );
         assert_eq!(
-            image.get_image_tile_url_at(Rect::from_corners(
-                Vec2::new(10.3, 20.5),
-                Vec2::new(200.5, 300.1)
-            )),
+            image.get_image_tile_url_at(
+                TileIndex::new(0, 0, 2),
+                Rect::from_corners(Vec2::new(10.3, 20.5), Vec2::new(200.5, 300.1))
+            ),
             "https://iiif_end_point/uuid/10,21,191,279/1024,1024/0/default.png"
         );
         assert_eq!(
-            image.get_image_tile_url_at(Rect::from_corners(
-                Vec2::new(10.3, 20.5),
-                Vec2::new(200.5, 300.1)
-            )),
+            image.get_image_tile_url_at(
+                TileIndex::new(0, 0, 2),
+                Rect::from_corners(Vec2::new(10.3, 20.5), Vec2::new(200.5, 300.1))
+            ),
             "https://iiif_end_point/uuid/10,21,191,279/1024,1024/0/default.png"
         );
     }
 
+    #[test]
+    fn test_get_required_tiles_with_overlap() {
+        let mut image = setup_with_features(&[IiifFeature::RegionByPx, IiifFeature::SizeByWh]);
+        image.set_tile_overlap(2);
+
+        let world_pos_min = Vec3::new(-4000.0, -4000.0, 0.0);
+        let world_pos_max = Vec3::new(4000.0, 4000.0, 0.0);
+        let (tiles, _, _) = image.get_required_tiles(1, world_pos_min, world_pos_max);
+        let tile_width = TILE_SIZE * 2713.0 / 1357.0;
+        // 2 pixels at the level of about half the full size.
+        let overlap = 2.0 * 2713.0 / 1357.0;
+
+        assert_eq!(
+            tiles[1].request_position,
+            Rect::from_corners(
+                Vec2::new(tile_width - overlap, 0.0),
+                Vec2::new(2713.0, 1910.0)
+            )
+        );
+        // The texture of the larger region is cropped back to the tile.
+        assert!(
+            tiles[1]
+                .uv_transform
+                .transform_point2(Vec2::ZERO)
+                .abs_diff_eq(
+                    Vec2::new(overlap / tiles[1].request_position.width(), 0.0),
+                    1e-6
+                )
+        );
+        assert!(
+            tiles[1]
+                .uv_transform
+                .transform_point2(Vec2::ONE)
+                .abs_diff_eq(Vec2::ONE, 1e-6)
+        );
+
+        // Not for the static tiles.
+        let mut image = setup();
+        image.set_tile_overlap(2);
+
+        let (tiles, _, _) = image.get_required_tiles(1, world_pos_min, world_pos_max);
+
+        assert_eq!(tiles[1].request_position, tiles[1].image_position);
+    }
+
+    #[test]
+    fn test_get_required_tiles_with_source_overlap() {
+        let xml = r#"<Image Format="jpg" Overlap="1" TileSize="254">
+                <Size Width="1000" Height="600"/>
+            </Image>"#;
+        let image = TiledImage::new(Box::new(
+            tilesource::dzi::DziTileSource::try_from_xml(xml, "https://example.org/image.dzi")
+                .expect("should parse the DZI"),
+        ));
+
+        let (tiles, _, _) =
+            image.get_required_tiles(10, Vec3::new(0.0, -600.0, 0.0), Vec3::new(1000.0, 0.0, 0.0));
+
+        // The static tiles include the overlap on their inner sides only.
+        assert_eq!(tiles[0].request_position, Rect::new(0.0, 0.0, 255.0, 255.0));
+        assert_eq!(
+            tiles[1].request_position,
+            Rect::new(253.0, 0.0, 509.0, 255.0)
+        );
+        assert!(
+            tiles[1]
+                .uv_transform
+                .transform_point2(Vec2::ZERO)
+                .abs_diff_eq(Vec2::new(1.0 / 256.0, 0.0), 1e-6)
+        );
+        assert!(
+            tiles[1]
+                .uv_transform
+                .transform_point2(Vec2::ONE)
+                .abs_diff_eq(Vec2::new(255.0 / 256.0, 254.0 / 255.0), 1e-6)
+        );
+    }
+
     #[test]
     fn test_get_required_tiles() {
         let image = setup();
@@ -610,6 +1432,63 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_get_required_tiles_range() {
+        let image = setup();
+
+        // Only the bottom right tile.
+        let (tiles, tile_range_x, tile_range_y) = image.get_required_tiles(
+            2,
+            Vec3::new(1500.0, -1500.0, 0.0),
+            Vec3::new(1600.0, -1600.0, 0.0),
+        );
+
+        assert_eq!(tiles.len(), 1);
+        assert_eq!(tiles[0].index, TileIndex::new(1, 1, 2));
+        assert_eq!(tile_range_x, 1..=1);
+        assert_eq!(tile_range_y, 1..=1);
+        assert!(!image.is_gigapixel());
+    }
+
+    #[test]
+    fn test_get_required_tiles_rotated() {
+        let mut image = setup();
+
+        image.set_orientation(ImageOrientation {
+            rotation: 90,
+            mirrored: false,
+        });
+
+        assert_eq!(
+            image.get_image_max_size_rect(),
+            Rect::from_corners(Vec2::ZERO, Vec2::new(1910.0, 2713.0))
+        );
+
+        // Only the top right of the canvas, which is the top left of the source image.
+        let (tiles, tile_range_x, tile_range_y) = image.get_required_tiles(
+            2,
+            Vec3::new(1500.0, -10.0, 0.0),
+            Vec3::new(1900.0, -500.0, 0.0),
+        );
+
+        assert_eq!(tiles.len(), 1);
+        assert_eq!(tiles[0].index, TileIndex::new(0, 0, 2));
+        assert_eq!(
+            tiles[0].image_position,
+            Rect::from_corners(Vec2::ZERO, Vec2::new(1024.0, 1024.0))
+        );
+        assert_eq!(
+            tiles[0].world_position,
+            Rect::from_corners(Vec2::new(886.0, 0.0), Vec2::new(1910.0, -1024.0))
+        );
+        assert_eq!(
+            tiles[0].uv_transform,
+            image.get_orientation().get_uv_transform()
+        );
+        assert_eq!(tile_range_x, 0..=0);
+        assert_eq!(tile_range_y, 0..=0);
+    }
+
     // #[test]
     // fn test_get_num_levels() {
     //     let image = setup();
@@ -628,9 +1507,22 @@ mod tests {
     }
 
     #[test]
-    fn test_get_image_thumbail() {
+    fn test_get_world_tile_size() {
         let mut image = setup();
 
+        assert_eq!(
+            image.get_world_tile_size(1),
+            Vec2::splat(TILE_SIZE * 2713.0 / 1357.0)
+        );
+
+        image.set_world_scale(0.5);
+        assert_eq!(image.get_world_tile_size(2), Vec2::splat(TILE_SIZE * 0.5));
+    }
+
+    #[test]
+    fn test_get_image_thumbail() {
+        let image = setup();
+
         let (url, size) = image.get_image_thumbnail(256);
 
         assert_eq!(
@@ -639,7 +1531,7 @@ mod tests {
         );
         assert_eq!(size, Vec2::new(678.0, 478.0));
 
-        image.supported_features.insert(IiifFeature::SizeByWh);
+        let image = setup_with_features(&[IiifFeature::SizeByWhListed, IiifFeature::SizeByWh]);
         let (url, size) = image.get_image_thumbnail(256);
 
         assert_eq!(
@@ -658,11 +1550,53 @@ mod tests {
         assert_eq!(rect, Rect::new(0.0, 0.0, 2713.0, 1910.0));
     }
 
+    #[test]
+    fn test_canvas_size() {
+        let mut image = setup();
+
+        // The canvas is twice the size of the image.
+        image.set_canvas_region(Some(Rect::new(0.0, 0.0, 5426.0, 3820.0)));
+
+        assert_eq!(
+            image.get_image_max_size_rect(),
+            Rect::new(0.0, 0.0, 5426.0, 3820.0)
+        );
+        assert_eq!(
+            image.get_source_rect(Rect::new(100.0, 200.0, 5426.0, 3820.0)),
+            Rect::new(50.0, 100.0, 2713.0, 1910.0)
+        );
+        assert_eq!(image.get_world_tile_size(2), Vec2::splat(TILE_SIZE * 2.0));
+        assert_eq!(image.get_level_at(2.0, 1.0), 2);
+        assert_eq!(image.get_level_at(4.0, 1.0), 1);
+    }
+
+    #[test]
+    fn test_canvas_region() {
+        let mut image = setup();
+
+        // The image is painted at its size on the right of the canvas.
+        image.set_canvas_region(Some(Rect::new(1000.0, 500.0, 3713.0, 2410.0)));
+
+        assert_eq!(
+            image.get_image_max_size_rect(),
+            Rect::new(1000.0, 500.0, 3713.0, 2410.0)
+        );
+        assert_eq!(
+            image.get_source_rect(Rect::new(1100.0, 700.0, 3713.0, 2410.0)),
+            Rect::new(100.0, 200.0, 2713.0, 1910.0)
+        );
+        assert_eq!(image.get_world_tile_size(2), Vec2::splat(TILE_SIZE));
+    }
+
     #[test]
     fn test_get_image_info_url() {
         assert_eq!(
             TiledImage::get_image_info_url("https://example.com/uuid"),
             "https://example.com/uuid/info.json"
         );
+        assert_eq!(
+            TiledImage::get_image_info_url("https://example.com/image.dzi"),
+            "https://example.com/image.dzi"
+        );
     }
 }
@@ -0,0 +1,100 @@
+use crate::{
+    app::app_state::AppState,
+    presentation::ui::EguiUiState,
+    rendering::{
+        model_image::{ModelImage, ModelLoading},
+        tile::{TileCache, TileLoading},
+        tiled_image::TiledImage,
+    },
+};
+use bevy::{
+    prelude::{
+        AppExit, Commands, Local, MessageWriter, On, Or, Query, Res, ResMut, Resource, Time, With,
+        info, warn,
+    },
+    render::view::screenshot::{Screenshot, ScreenshotCaptured, save_to_disk},
+    window::RequestRedraw,
+};
+use std::path::PathBuf;
+
+/// Seconds to wait for the tiles before the screenshot is taken anyway, e.g. if a tile fails.
+const SCREENSHOT_TIMEOUT_SECS: f64 = 60.0;
+/// Frames rendered after everything is loaded, so that the textures of the tiles are shown.
+const SCREENSHOT_SETTLE_FRAMES: u32 = 5;
+
+/// Screenshot of the first canvas written to the file before the app exits.
+#[derive(Resource)]
+pub(crate) struct ScreenshotRequest {
+    path: PathBuf,
+    taken: bool,
+}
+
+impl ScreenshotRequest {
+    pub(crate) fn new(path: PathBuf) -> Self {
+        Self { path, taken: false }
+    }
+}
+
+/// Exit once the screenshot is written.
+fn exit_on_screenshot_captured(
+    _screenshot_captured: On<ScreenshotCaptured>,
+    mut app_exit_writer: MessageWriter<AppExit>,
+) {
+    app_exit_writer.write(AppExit::Success);
+}
+
+/// Take the screenshot when the image and the tiles at the fitted view are loaded, and keep the
+/// app updating until then.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn screenshot_system(
+    mut commands: Commands,
+    mut request: ResMut<ScreenshotRequest>,
+    mut egui_ui_state: ResMut<EguiUiState>,
+    app_state: Res<AppState>,
+    tile_cache: Res<TileCache>,
+    image_query: Query<(), Or<(With<TiledImage>, With<ModelImage>)>>,
+    loading_query: Query<(), Or<(With<TileLoading>, With<ModelLoading>)>>,
+    time: Res<Time>,
+    mut redraw_request_writer: MessageWriter<RequestRedraw>,
+    mut settled_frames: Local<u32>,
+) {
+    if request.taken {
+        redraw_request_writer.write(RequestRedraw);
+        return;
+    }
+
+    // Only the image is wanted in the preview.
+    egui_ui_state.open_left_panel = false;
+
+    let is_loading = image_query.is_empty()
+        || !loading_query.is_empty()
+        || tile_cache.has_requests()
+        || app_state
+            .manifest_json_download_state
+            .lock()
+            .is_ok_and(|x| x.is_in_progress())
+        || app_state
+            .image_json_download_state
+            .lock()
+            .is_ok_and(|x| x.is_in_progress());
+
+    *settled_frames = if is_loading { 0 } else { *settled_frames + 1 };
+
+    let timed_out = time.elapsed_secs_f64() > SCREENSHOT_TIMEOUT_SECS;
+
+    if *settled_frames >= SCREENSHOT_SETTLE_FRAMES || timed_out {
+        if timed_out {
+            warn!("Take the screenshot before everything is loaded");
+        }
+
+        info!("Take the screenshot to {:?}", request.path);
+
+        commands
+            .spawn(Screenshot::primary_window())
+            .observe(save_to_disk(request.path.clone()))
+            .observe(exit_on_screenshot_captured);
+        request.taken = true;
+    }
+
+    redraw_request_writer.write(RequestRedraw);
+}
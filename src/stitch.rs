@@ -0,0 +1,416 @@
+use crate::{
+    camera::{camera_ext::get_world_viewport_rect, main_camera::MainCamera2d},
+    export,
+    iiif::{IiifError, image::ImageOrientation},
+    notification::UserNotification,
+    rendering::tiled_image::TiledImage,
+    web,
+};
+use bevy::{
+    prelude::{
+        Camera, GlobalTransform, MessageWriter, Rect, ResMut, Resource, Single, With, info, warn,
+    },
+    window::RequestRedraw,
+};
+use image::{DynamicImage, RgbImage, imageops::FilterType};
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+};
+
+/// Maximum number of the pixels of the stitched image, which is kept in memory until saved.
+const MAX_STITCH_PIXELS: f64 = 5.0e8;
+
+/// Tiles downloaded of the stitching in progress, and whether it is cancelled. Also of the other
+/// exports downloading in the background, e.g. the thumbnails of the contact sheet.
+#[derive(Default)]
+pub(crate) struct StitchProgress {
+    pub(crate) done: AtomicUsize,
+    pub(crate) total: AtomicUsize,
+    pub(crate) cancelled: AtomicBool,
+}
+
+impl StitchProgress {
+    /// Get the fraction of the tiles downloaded.
+    pub(crate) fn get_fraction(&self) -> f32 {
+        let total = self.total.load(Ordering::Relaxed);
+
+        if total == 0 {
+            return 0.0;
+        }
+
+        self.done.load(Ordering::Relaxed) as f32 / total as f32
+    }
+
+    /// Get the number of the tiles downloaded and of all of them.
+    pub(crate) fn get_counts(&self) -> (usize, usize) {
+        (
+            self.done.load(Ordering::Relaxed),
+            self.total.load(Ordering::Relaxed),
+        )
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Result of the stitching finished in the background.
+#[derive(Debug)]
+enum StitchResult {
+    Saved(PathBuf),
+    Cancelled,
+    Failed(IiifError),
+}
+
+/// Action requested from the print export window.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum StitchRequest {
+    /// Set the region to the view.
+    UseView,
+    Start,
+    Cancel,
+}
+
+/// Region of the image to stitch from the full size tiles and save for printing, and the
+/// stitching in progress.
+#[derive(Resource)]
+pub(crate) struct StitchExport {
+    /// Region in image space, i.e. as shown on the canvas. The whole image if `None`.
+    pub(crate) region: Option<Rect>,
+    /// Size in pixels of the stitched image of the region.
+    pub(crate) size: Option<(u32, u32)>,
+    /// Path of the saved image, a TIFF or PNG by its extension.
+    pub(crate) file_path: String,
+    /// Progress of the stitching in progress.
+    pub(crate) progress: Option<Arc<StitchProgress>>,
+    /// Path of the image saved last.
+    pub(crate) saved_path: Option<PathBuf>,
+    result: Arc<Mutex<Option<StitchResult>>>,
+    pub(crate) request: Option<StitchRequest>,
+}
+
+impl Default for StitchExport {
+    fn default() -> Self {
+        Self {
+            region: None,
+            size: None,
+            file_path: "export.tif".to_string(),
+            progress: None,
+            saved_path: None,
+            result: Arc::new(Mutex::new(None)),
+            request: None,
+        }
+    }
+}
+
+/// Tile of the full size level to download, and its position in the full size image.
+struct StitchTile {
+    url: String,
+    position: Rect,
+}
+
+/// Get the size in pixels of the rect of the full size image.
+fn get_pixel_size(source_rect: Rect) -> (u32, u32) {
+    (
+        source_rect.width().round() as u32,
+        source_rect.height().round() as u32,
+    )
+}
+
+/// Download the tile, or read it if exported locally.
+fn fetch_tile(url: &str) -> Result<DynamicImage, IiifError> {
+    let bytes = match web::get_file_path(url) {
+        Some(path) => std::fs::read(path)?,
+        // The proxy prefix is already in the URL of the tile.
+        None => export::fetch_bytes(url, "")?,
+    };
+
+    Ok(image::load_from_memory(&bytes)?)
+}
+
+/// Stitch the tiles into the image of the rect of the full size image, cropping the tiles across
+/// its edges. `None` if cancelled.
+fn stitch_tiles(
+    tiles: &[StitchTile],
+    source_rect: Rect,
+    progress: &StitchProgress,
+    fetch: impl Fn(&str) -> Result<DynamicImage, IiifError>,
+) -> Result<Option<RgbImage>, IiifError> {
+    let (width, height) = get_pixel_size(source_rect);
+    let mut stitched = RgbImage::new(width, height);
+
+    progress.total.store(tiles.len(), Ordering::Relaxed);
+
+    for tile in tiles {
+        if progress.is_cancelled() {
+            return Ok(None);
+        }
+
+        let (tile_width, tile_height) = get_pixel_size(tile.position);
+        let mut tile_image = fetch(&tile.url)?.to_rgb8();
+
+        // The server may return the tile of a slightly different size, e.g. by rounding.
+        if tile_image.dimensions() != (tile_width, tile_height) {
+            tile_image =
+                image::imageops::resize(&tile_image, tile_width, tile_height, FilterType::Triangle);
+        }
+
+        let offset = (tile.position.min - source_rect.min).round();
+
+        image::imageops::replace(&mut stitched, &tile_image, offset.x as i64, offset.y as i64);
+        progress.done.fetch_add(1, Ordering::Relaxed);
+    }
+
+    Ok(Some(stitched))
+}
+
+/// Orient the image as shown on the canvas, mirrored before rotated as in the image API.
+fn orient(image: RgbImage, orientation: ImageOrientation) -> RgbImage {
+    let image = if orientation.mirrored {
+        image::imageops::flip_horizontal(&image)
+    } else {
+        image
+    };
+
+    match orientation.rotation {
+        90 => image::imageops::rotate90(&image),
+        180 => image::imageops::rotate180(&image),
+        270 => image::imageops::rotate270(&image),
+        _ => image,
+    }
+}
+
+/// Stitch the tiles, orient the image and save it, in the format of the extension of the path.
+fn stitch_and_save(
+    tiles: &[StitchTile],
+    source_rect: Rect,
+    orientation: ImageOrientation,
+    path: &Path,
+    progress: &StitchProgress,
+) -> StitchResult {
+    let stitched = match stitch_tiles(tiles, source_rect, progress, fetch_tile) {
+        Ok(Some(stitched)) => stitched,
+        Ok(None) => return StitchResult::Cancelled,
+        Err(e) => return StitchResult::Failed(e),
+    };
+
+    match orient(stitched, orientation).save(path) {
+        Ok(_) => StitchResult::Saved(path.to_path_buf()),
+        Err(e) => StitchResult::Failed(e.into()),
+    }
+}
+
+/// Start stitching the region of the image from the full size tiles in the background.
+fn start(stitch: &mut StitchExport, tiled_image: &TiledImage) -> Result<(), IiifError> {
+    let source_rect = tiled_image.get_source_rect(
+        stitch
+            .region
+            .unwrap_or_else(|| tiled_image.get_image_max_size_rect()),
+    );
+
+    if source_rect.is_empty() {
+        return Err(IiifError::IiifMissingInfo(
+            "region of the image to export".to_string(),
+        ));
+    }
+
+    let (width, height) = get_pixel_size(source_rect);
+
+    if width as f64 * height as f64 > MAX_STITCH_PIXELS {
+        return Err(IiifError::IiifUnsupportedError(format!(
+            "region of {}x{} pixels over the limit of {} megapixels",
+            width,
+            height,
+            MAX_STITCH_PIXELS / 1.0e6
+        )));
+    }
+
+    let tiles = tiled_image
+        .get_full_size_tiles(source_rect)
+        .into_iter()
+        .map(|(index, position)| StitchTile {
+            url: tiled_image.get_image_tile_url_at(index, position),
+            position,
+        })
+        .collect::<Vec<_>>();
+    let orientation = tiled_image.get_orientation();
+    let path = PathBuf::from(stitch.file_path.trim());
+    let progress = Arc::new(StitchProgress::default());
+    let result = Arc::clone(&stitch.result);
+
+    info!(
+        "Stitch {} tiles of {} into {}x{} pixels to {:?}",
+        tiles.len(),
+        tiled_image.get_endpoint(),
+        width,
+        height,
+        path
+    );
+
+    stitch.progress = Some(Arc::clone(&progress));
+
+    std::thread::spawn(move || {
+        let stitch_result = stitch_and_save(&tiles, source_rect, orientation, &path, &progress);
+
+        if let Ok(mut x) = result.lock() {
+            *x = Some(stitch_result);
+        }
+    });
+
+    Ok(())
+}
+
+/// Handle the action requested from the print export window, and report the stitching when it
+/// finishes.
+pub(crate) fn stitch_system(
+    mut stitch: ResMut<StitchExport>,
+    tiled_image: Option<Single<&TiledImage>>,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera2d>>,
+    mut messages: MessageWriter<UserNotification>,
+    mut redraw_request_writer: MessageWriter<RequestRedraw>,
+) {
+    match stitch.request.take() {
+        Some(StitchRequest::UseView) => {
+            let (camera, global_transform) = camera.into_inner();
+
+            stitch.region = tiled_image
+                .as_ref()
+                .zip(get_world_viewport_rect(camera, global_transform))
+                .map(|(tiled_image, (world_pos_min, world_pos_max))| {
+                    tiled_image
+                        .world_to_image_rect(Rect::from_corners(
+                            world_pos_min.truncate(),
+                            world_pos_max.truncate(),
+                        ))
+                        .intersect(tiled_image.get_image_max_size_rect())
+                });
+        }
+        Some(StitchRequest::Start) if stitch.progress.is_none() => {
+            let Some(tiled_image) = tiled_image.as_ref() else {
+                return;
+            };
+
+            if let Err(e) = start(&mut stitch, tiled_image) {
+                messages.write(UserNotification::Error(format!(
+                    "Unable to export the region for printing.\n{}",
+                    e
+                )));
+            }
+        }
+        Some(StitchRequest::Cancel) => {
+            if let Some(progress) = &stitch.progress {
+                progress.cancelled.store(true, Ordering::Relaxed);
+            }
+        }
+        _ => {}
+    }
+
+    // Size of the region, or of the whole image, as shown in the window.
+    stitch.size = tiled_image.as_ref().map(|tiled_image| {
+        get_pixel_size(
+            tiled_image.get_source_rect(
+                stitch
+                    .region
+                    .unwrap_or_else(|| tiled_image.get_image_max_size_rect()),
+            ),
+        )
+    });
+
+    if stitch.progress.is_none() {
+        return;
+    }
+
+    let result = stitch.result.lock().ok().and_then(|mut x| x.take());
+
+    match result {
+        Some(StitchResult::Saved(path)) => {
+            info!("Exported the region for printing to {:?}", path);
+            stitch.saved_path = Some(path);
+        }
+        Some(StitchResult::Cancelled) => warn!("Cancelled the export of the region for printing"),
+        Some(StitchResult::Failed(e)) => {
+            messages.write(UserNotification::Error(format!(
+                "Failed to export the region for printing.\n{}",
+                e
+            )));
+        }
+        // Keep the progress updated while stitching.
+        None => {
+            redraw_request_writer.write(RequestRedraw);
+            return;
+        }
+    }
+
+    stitch.progress = None;
+    redraw_request_writer.write(RequestRedraw);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+
+    #[test]
+    fn test_stitch_tiles() {
+        let tiles = [
+            StitchTile {
+                url: "0".to_string(),
+                position: Rect::new(0.0, 0.0, 4.0, 4.0),
+            },
+            StitchTile {
+                url: "1".to_string(),
+                position: Rect::new(4.0, 0.0, 6.0, 4.0),
+            },
+        ];
+        let progress = StitchProgress::default();
+        // The second tile is served smaller than its position.
+        let fetch = |url: &str| {
+            let (width, value) = if url == "0" { (4, 10) } else { (1, 20) };
+
+            Ok::<_, IiifError>(DynamicImage::ImageRgb8(RgbImage::from_pixel(
+                width,
+                4,
+                Rgb([value; 3]),
+            )))
+        };
+        let stitched = stitch_tiles(&tiles, Rect::new(2.0, 1.0, 6.0, 3.0), &progress, fetch)
+            .expect("should stitch the tiles")
+            .expect("should not be cancelled");
+
+        assert_eq!(stitched.dimensions(), (4, 2));
+        assert_eq!(stitched.get_pixel(1, 0), &Rgb([10; 3]));
+        assert_eq!(stitched.get_pixel(2, 1), &Rgb([20; 3]));
+        assert_eq!(progress.get_counts(), (2, 2));
+
+        progress.cancelled.store(true, Ordering::Relaxed);
+
+        assert!(
+            stitch_tiles(&tiles, Rect::new(2.0, 1.0, 6.0, 3.0), &progress, fetch)
+                .expect("should not fail")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_orient() {
+        let mut image = RgbImage::new(2, 1);
+
+        image.put_pixel(0, 0, Rgb([1; 3]));
+
+        let oriented = orient(
+            image,
+            ImageOrientation {
+                rotation: 90,
+                mirrored: true,
+            },
+        );
+
+        // Mirrored to the right, and then rotated to the bottom.
+        assert_eq!(oriented.dimensions(), (1, 2));
+        assert_eq!(oriented.get_pixel(0, 1), &Rgb([1; 3]));
+    }
+}
@@ -0,0 +1,225 @@
+use crate::{
+    iiif::{IiifError, image::ImageOrientation},
+    rendering::{tile::TileIndex, tile_format::get_url_format, tiled_image::Size},
+};
+use bevy::prelude::Rect;
+
+pub(crate) mod dzi;
+pub(crate) mod iiif;
+pub(crate) mod single;
+pub(crate) mod zoomify;
+
+/// Capabilities advertised by the descriptor of the tile source, e.g. to show to the user.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ServiceCapabilities {
+    /// Protocol and its version if any, e.g. "IIIF 3" or "DZI".
+    pub(crate) protocol: String,
+    /// Compliance level of the IIIF image service if declared.
+    pub(crate) level: Option<u32>,
+    /// Whether the image is served in tiles instead of the full image only.
+    pub(crate) tiles: bool,
+    /// Image formats, the one requested first.
+    pub(crate) formats: Vec<String>,
+}
+
+/// A pyramid of image tiles at the resolution levels, e.g. an IIIF image service or a DeepZoom image.
+pub(crate) trait TileSource: Send + Sync {
+    /// Get the tile size.
+    fn get_tile_size(&self) -> Size;
+
+    /// Get the image sizes of the levels, from the smallest to the full size.
+    fn get_levels(&self) -> &[Size];
+
+    /// Get the URL of the tile at the index, which is at the position in the full size image.
+    fn get_tile_url(&self, index: TileIndex, image_position: Rect) -> String;
+
+    /// Get the URL and the size of a thumbnail of about the size.
+    fn get_thumbnail(&self, size: u32) -> (String, Size);
+
+    /// Whether the tiles can be requested at any position, e.g. larger than the tile to overlap
+    /// the others.
+    fn supports_any_region(&self) -> bool;
+
+    /// Get the pixels of the overlap included around the tiles by the source at their level,
+    /// e.g. of a DZI, to be cropped when drawn.
+    fn get_tile_overlap(&self) -> u32 {
+        0
+    }
+
+    /// Get the capabilities advertised by the descriptor.
+    fn get_capabilities(&self) -> ServiceCapabilities;
+
+    /// Get the image format of the tiles requested, e.g. "jpg".
+    fn get_format(&self) -> String;
+
+    /// Request the tiles in another advertised format, e.g. if the preferred one fails. Ignored
+    /// by the sources with the format fixed by their descriptor.
+    fn set_format(&mut self, _format: &str) {}
+
+    /// Request the smaller images to save the data, e.g. JPEG instead of PNG. Ignored by the
+    /// sources with the format fixed by their descriptor.
+    fn set_data_saver(&mut self, _data_saver: bool) {}
+
+    /// Get the image API URL of the region of the full size image in the orientation, e.g. to
+    /// cite it. `None` if the source is not an IIIF image service.
+    fn get_region_url(&self, _region: Rect, _orientation: ImageOrientation) -> Option<String> {
+        None
+    }
+}
+
+/// Whether the endpoint is the URL of a DZI or Zoomify descriptor instead of an IIIF image service.
+pub(crate) fn is_descriptor_url(endpoint: &str) -> bool {
+    let endpoint = endpoint.to_lowercase();
+
+    endpoint.ends_with(".dzi") || endpoint.ends_with(".xml")
+}
+
+/// Extensions of the plain images opened without an image service.
+const IMAGE_EXTENSIONS: [&str; 7] = ["jpg", "jpeg", "png", "tif", "tiff", "webp", "gif"];
+
+/// Whether the endpoint is the URL of a plain image, e.g. a JPEG, instead of an image service.
+pub(crate) fn is_image_url(endpoint: &str) -> bool {
+    let path = endpoint.split(['?', '#']).next().unwrap_or_default();
+
+    get_url_format(path).is_some_and(|format| {
+        IMAGE_EXTENSIONS
+            .iter()
+            .any(|x| x.eq_ignore_ascii_case(format))
+    })
+}
+
+/// Get the URL of the image info of the endpoint.
+///
+/// The info of a plain image is its size, measured from the image itself.
+pub(crate) fn get_info_url(endpoint: &str) -> String {
+    if is_descriptor_url(endpoint) || is_image_url(endpoint) {
+        endpoint.to_string()
+    } else {
+        format!("{endpoint}/info.json")
+    }
+}
+
+/// Create the tile source from the image info fetched for the endpoint, either an IIIF info.json,
+/// a DZI XML, a Zoomify `ImageProperties.xml` or the size of a plain image.
+pub(crate) fn try_from_info(
+    content: &str,
+    endpoint: &str,
+) -> core::result::Result<Box<dyn TileSource>, IiifError> {
+    let content = content.trim_start();
+
+    if is_image_url(endpoint) {
+        Ok(Box::new(single::SingleImageTileSource::try_from_json(
+            content, endpoint,
+        )?))
+    } else if !content.starts_with('<') {
+        Ok(Box::new(iiif::IiifTileSource::try_from_json(
+            content, endpoint,
+        )?))
+    } else if get_xml_attribute(content, "IMAGE_PROPERTIES", "WIDTH").is_some() {
+        Ok(Box::new(zoomify::ZoomifyTileSource::try_from_xml(
+            content, endpoint,
+        )?))
+    } else {
+        Ok(Box::new(dzi::DziTileSource::try_from_xml(
+            content, endpoint,
+        )?))
+    }
+}
+
+/// Get the value of the attribute of the first element with the name in the XML.
+///
+/// Only the simple descriptors of the tile sources are expected, so this is not a full XML parser.
+pub(crate) fn get_xml_attribute<'a>(xml: &'a str, element: &str, name: &str) -> Option<&'a str> {
+    let start_tag = format!("<{}", element);
+    let mut rest = xml;
+
+    let tag = loop {
+        let index = rest.find(&start_tag)?;
+        let after = &rest[index + start_tag.len()..];
+
+        if after.starts_with(|c: char| c.is_whitespace() || c == '/' || c == '>') {
+            break &after[..after.find('>')?];
+        }
+        rest = after;
+    };
+
+    let mut attributes = tag;
+
+    loop {
+        let index = attributes.find(name)?;
+        let is_start = attributes[..index].ends_with(char::is_whitespace);
+        let after = attributes[index + name.len()..].trim_start();
+
+        if is_start && let Some(value) = after.strip_prefix('=') {
+            let value = value.trim_start();
+            let quote = value.chars().next().filter(|x| *x == '"' || *x == '\'')?;
+            let value = &value[1..];
+
+            return value.find(quote).map(|end| &value[..end]);
+        }
+        attributes = &attributes[index + name.len()..];
+    }
+}
+
+/// Get the attribute of the XML element as a number.
+fn get_xml_number(xml: &str, element: &str, name: &str) -> core::result::Result<u32, IiifError> {
+    get_xml_attribute(xml, element, name)
+        .ok_or(IiifError::IiifMissingInfo(format!(
+            "missing '{}' of '{}'",
+            name, element
+        )))?
+        .trim()
+        .parse::<u32>()
+        .map_err(IiifError::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_xml_attribute() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <Image TileSize="254" Overlap='1' Format="jpg"
+                xmlns="http://schemas.microsoft.com/deepzoom/2008">
+                <SizeX Width="1"/>
+                <Size Width="4000" Height = "3000"/>
+            </Image>"#;
+
+        assert_eq!(get_xml_attribute(xml, "Image", "TileSize"), Some("254"));
+        assert_eq!(get_xml_attribute(xml, "Image", "Overlap"), Some("1"));
+        assert_eq!(get_xml_attribute(xml, "Image", "Size"), None);
+        assert_eq!(get_xml_attribute(xml, "Size", "Width"), Some("4000"));
+        assert_eq!(get_xml_attribute(xml, "Size", "Height"), Some("3000"));
+        assert_eq!(get_xml_attribute(xml, "Tile", "Width"), None);
+        assert_eq!(get_xml_number(xml, "Size", "Width").ok(), Some(4000));
+        assert!(get_xml_number(xml, "Image", "Format").is_err());
+    }
+
+    #[test]
+    fn test_get_info_url() {
+        assert_eq!(
+            get_info_url("https://example.org/iiif/1"),
+            "https://example.org/iiif/1/info.json"
+        );
+        assert_eq!(
+            get_info_url("https://example.org/a.dzi"),
+            "https://example.org/a.dzi"
+        );
+        assert_eq!(
+            get_info_url("https://example.org/a/ImageProperties.xml"),
+            "https://example.org/a/ImageProperties.xml"
+        );
+        assert_eq!(get_info_url("file:///scans/p1.TIF"), "file:///scans/p1.TIF");
+    }
+
+    #[test]
+    fn test_is_image_url() {
+        assert!(is_image_url("https://example.org/scans/p1.jpg"));
+        assert!(is_image_url("https://example.org/scans/p1.PNG?size=full"));
+        assert!(is_image_url("file:///scans/p1.tiff"));
+        assert!(!is_image_url("https://example.org/iiif/p1"));
+        assert!(!is_image_url("https://example.org/a.dzi"));
+        assert!(!is_image_url("https://example.org/iiif/p1.jpg/info.json"));
+    }
+}
@@ -0,0 +1,173 @@
+use crate::{
+    iiif::IiifError,
+    rendering::{tile::TileIndex, tiled_image::Size},
+    tilesource::{ServiceCapabilities, TileSource, get_xml_attribute, get_xml_number},
+};
+use bevy::prelude::Rect;
+
+/// DeepZoom image, with the levels from 1x1 to the full size, each half of the next one.
+pub(crate) struct DziTileSource {
+    /// URL of the tiles folder, e.g. "https://example.org/image_files".
+    tiles_url: String,
+    /// Image format, e.g. "jpg".
+    format: String,
+    tile_size: Size,
+    /// Pixels of the neighbouring tiles included on each inner side of the tiles.
+    overlap: u32,
+    levels: Vec<Size>,
+}
+
+impl DziTileSource {
+    /// Create the image from the DZI XML fetched from the URL.
+    pub(crate) fn try_from_xml(xml: &str, url: &str) -> core::result::Result<Self, IiifError> {
+        let tile_size = get_xml_number(xml, "Image", "TileSize")?;
+        let format = get_xml_attribute(xml, "Image", "Format")
+            .ok_or(IiifError::IiifMissingInfo(
+                "missing 'Format' of 'Image'".to_string(),
+            ))?
+            .to_string();
+        let width = get_xml_number(xml, "Size", "Width")?;
+        let height = get_xml_number(xml, "Size", "Height")?;
+        let overlap = get_xml_attribute(xml, "Image", "Overlap")
+            .map(|_| get_xml_number(xml, "Image", "Overlap"))
+            .transpose()?
+            .unwrap_or_default();
+
+        if tile_size == 0 || width == 0 || height == 0 {
+            return Err(IiifError::IiifFormatError(format!(
+                "invalid DZI size in '{}'",
+                url
+            )));
+        }
+
+        // The tiles are in the folder named after the descriptor, e.g. "image.dzi" -> "image_files".
+        let (folder, name) = url.rsplit_once('/').unwrap_or(("", url));
+        let name = name.rsplit_once('.').map_or(name, |(name, _)| name);
+        let tiles_url = if folder.is_empty() {
+            format!("{}_files", name)
+        } else {
+            format!("{}/{}_files", folder, name)
+        };
+
+        Ok(Self {
+            tiles_url,
+            format,
+            tile_size: Size::new(tile_size, tile_size),
+            overlap,
+            levels: get_levels(width, height),
+        })
+    }
+}
+
+/// Get the level sizes from 1x1 to the full size.
+fn get_levels(width: u32, height: u32) -> Vec<Size> {
+    let max_level = width.max(height).next_power_of_two().trailing_zeros();
+
+    (0..=max_level)
+        .map(|level| {
+            let scale = 1u64 << (max_level - level);
+
+            Size::new(
+                (width as u64).div_ceil(scale) as u32,
+                (height as u64).div_ceil(scale) as u32,
+            )
+        })
+        .collect()
+}
+
+impl TileSource for DziTileSource {
+    fn get_tile_size(&self) -> Size {
+        self.tile_size
+    }
+
+    fn get_levels(&self) -> &[Size] {
+        &self.levels
+    }
+
+    fn get_tile_url(&self, index: TileIndex, _image_position: Rect) -> String {
+        format!(
+            "{}/{}/{}_{}.{}",
+            self.tiles_url, index.z, index.x, index.y, self.format
+        )
+    }
+
+    fn get_thumbnail(&self, _size: u32) -> (String, Size) {
+        // The largest level in a single tile.
+        let level = self
+            .levels
+            .iter()
+            .rposition(|x| x.width <= self.tile_size.width && x.height <= self.tile_size.height)
+            .unwrap_or_default();
+
+        (
+            self.get_tile_url(TileIndex::new(0, 0, level as u32), Rect::default()),
+            self.levels[level],
+        )
+    }
+
+    fn supports_any_region(&self) -> bool {
+        false
+    }
+
+    fn get_tile_overlap(&self) -> u32 {
+        self.overlap
+    }
+
+    fn get_capabilities(&self) -> ServiceCapabilities {
+        ServiceCapabilities {
+            protocol: "DZI".to_string(),
+            level: None,
+            tiles: true,
+            formats: vec![self.format.to_string()],
+        }
+    }
+
+    fn get_format(&self) -> String {
+        self.format.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dzi_tile_source() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <Image xmlns="http://schemas.microsoft.com/deepzoom/2008"
+                Format="jpg" Overlap="1" TileSize="254">
+                <Size Width="1000" Height="600"/>
+            </Image>"#;
+
+        let image = DziTileSource::try_from_xml(xml, "https://example.org/dzi/image.dzi")
+            .expect("should parse the DZI");
+
+        assert_eq!(image.get_tile_size(), Size::new(254, 254));
+        assert_eq!(image.get_tile_overlap(), 1);
+        assert_eq!(image.get_levels().len(), 11);
+        assert_eq!(image.get_levels()[0], Size::new(1, 1));
+        assert_eq!(image.get_levels()[8], Size::new(250, 150));
+        assert_eq!(image.get_levels()[9], Size::new(500, 300));
+        assert_eq!(image.get_levels()[10], Size::new(1000, 600));
+        assert_eq!(
+            image.get_tile_url(TileIndex::new(1, 2, 10), Rect::default()),
+            "https://example.org/dzi/image_files/10/1_2.jpg"
+        );
+        assert_eq!(
+            image.get_thumbnail(256),
+            (
+                "https://example.org/dzi/image_files/8/0_0.jpg".to_string(),
+                Size::new(250, 150)
+            )
+        );
+
+        assert!(DziTileSource::try_from_xml("<Image/>", "https://example.org/a.dzi").is_err());
+
+        // No overlap if not given.
+        let xml = r#"<Image Format="png" TileSize="256"><Size Width="512" Height="512"/></Image>"#;
+        let image = DziTileSource::try_from_xml(xml, "https://example.org/dzi/image.dzi")
+            .expect("should parse the DZI");
+
+        assert_eq!(image.get_tile_overlap(), 0);
+    }
+}
@@ -0,0 +1,556 @@
+use crate::{
+    iiif::{
+        IiifError,
+        image::{IiifFeature, IiifImageFormat, IiifImageInfo, ImageOrientation},
+    },
+    rendering::{tile::TileIndex, tiled_image::Size},
+    tilesource::{ServiceCapabilities, TileSource},
+};
+use bevy::prelude::{Rect, Vec2, info};
+use std::collections::HashSet;
+
+/// Max multiple of the thumbnail size of an advertised size to download instead of requesting
+/// the size by width/height.
+const MAX_THUMBNAIL_OVERSIZE: u32 = 2;
+
+/// IIIF image service.
+pub(crate) struct IiifTileSource {
+    /// IFFF URL, e.g. "https://stacks.stanford.edu/image/iiif/hg676jb4964%2F0380_796-44"
+    iiif_endpoint: String,
+    /// The number of levels and sizes.
+    levels: Vec<Size>,
+    /// Tile size.
+    tile_size: Size,
+    /// Image format.
+    image_format: IiifImageFormat,
+    /// Supported features.
+    supported_features: HashSet<IiifFeature>,
+    /// Optional sizes when getting the full image.
+    optional_sizes: Vec<Size>,
+    /// Request the tiles at their scaled size instead of the tile size, e.g. for the static
+    /// tiles of level 0 which can only be requested at the listed sizes.
+    exact_tile_sizes: bool,
+    /// Request JPEG, which all the IIIF image services support, to save the data.
+    data_saver: bool,
+    /// Major version of the image API, e.g. 3, if known.
+    api_version: Option<u32>,
+    /// Compliance level declared by the profile, e.g. 2.
+    compliance_level: Option<u32>,
+    /// All the image formats of the profiles.
+    formats: Vec<IiifImageFormat>,
+}
+
+impl IiifTileSource {
+    /// Create a new image service.
+    pub(crate) fn new(
+        iiif_endpoint: String,
+        tile_size: Size,
+        levels: Vec<Size>,
+        image_format: IiifImageFormat,
+        supported_features: HashSet<IiifFeature>,
+        optional_sizes: Vec<Size>,
+    ) -> Self {
+        Self {
+            iiif_endpoint,
+            tile_size,
+            levels,
+            formats: vec![image_format.clone()],
+            image_format,
+            supported_features,
+            optional_sizes,
+            exact_tile_sizes: false,
+            data_saver: false,
+            api_version: None,
+            compliance_level: None,
+        }
+    }
+
+    /// Create the image service from the IFFF image info JSON.
+    pub(crate) fn try_from_json(
+        json: &str,
+        iiif_endpoint: &str,
+    ) -> core::result::Result<Self, IiifError> {
+        let iiif_image_info = IiifImageInfo::try_from_json(json)?;
+
+        // Get tile size and levels.
+        // We require both region by px and size by width/height for the tiling.
+        // If not, we will only get the full image.
+        let supported_features: HashSet<_> = iiif_image_info
+            .get_profile_details()
+            .flat_map(|x| (*x).get_supported_features())
+            .collect();
+        let tile_size: Size;
+        let levels: Vec<Size>;
+        let mut exact_tile_sizes = false;
+
+        let size_supported = supported_features.contains(&IiifFeature::SizeByWh)
+            || supported_features.contains(&IiifFeature::SizeByW);
+
+        if supported_features.contains(&IiifFeature::RegionByPx) && size_supported {
+            tile_size = iiif_image_info.get_tile_size();
+            levels = iiif_image_info.get_tile_scaling_sizes();
+
+            if levels.len() > 1 {
+                info!("RegionByPx and SizeByWh supported. Use tiling.");
+            } else {
+                info!(
+                    "RegionByPx supported without tiles listed. Use tiling at synthesized levels."
+                );
+                levels = get_synthesized_levels(
+                    Size::new(iiif_image_info.get_width(), iiif_image_info.get_height()),
+                    tile_size,
+                );
+            }
+        } else if iiif_image_info.get_tile_scaling_sizes().len() > 1 {
+            info!("Static tiles listed. Use tiling at the scaled tile sizes.");
+            tile_size = iiif_image_info.get_tile_size();
+            levels = iiif_image_info.get_tile_scaling_sizes();
+            exact_tile_sizes = true;
+        } else {
+            info!("RegionByPx or SizeByWh not supported. Get the full image.");
+            tile_size = Size::new(iiif_image_info.get_width(), iiif_image_info.get_height());
+            levels = vec![tile_size];
+        };
+
+        // Get optional sizes.
+        let optional_sizes = iiif_image_info.get_optional_sizes();
+
+        // Get the image format.
+        let image_format = iiif_image_info
+            .get_profile_details()
+            .next()
+            .ok_or(IiifError::IiifMissingInfo(format!(
+                "missing profile in '{}'",
+                iiif_endpoint
+            )))?
+            .get_formats()
+            .next()
+            .ok_or(IiifError::IiifMissingInfo(format!(
+                "missing image format in '{}'",
+                iiif_endpoint
+            )))?;
+
+        let mut formats = vec![image_format.clone()];
+
+        for format in iiif_image_info
+            .get_profile_details()
+            .flat_map(|x| x.get_formats())
+        {
+            if !formats.contains(&format) {
+                formats.push(format);
+            }
+        }
+
+        Ok(Self {
+            exact_tile_sizes,
+            api_version: iiif_image_info.get_api_version(),
+            compliance_level: iiif_image_info.get_compliance_level(),
+            formats,
+            ..Self::new(
+                iiif_endpoint.to_string(),
+                tile_size,
+                levels,
+                image_format,
+                supported_features,
+                optional_sizes,
+            )
+        })
+    }
+
+    /// Get the max size of the image.
+    fn get_max_size(&self) -> Vec2 {
+        let last_level = self.levels.last().expect("should have at least one level");
+
+        Vec2::from(*last_level)
+    }
+
+    /// Get the image URL.
+    fn get_image_url(&self, left: u32, top: u32, width: u32, height: u32, size: Size) -> String {
+        let iiif_endpoint = &self.iiif_endpoint;
+        let image_format = if self.data_saver {
+            &IiifImageFormat::Jpg
+        } else {
+            &self.image_format
+        };
+        let max_size = self.get_max_size();
+
+        let region =
+            if left == 0 && top == 0 && width == max_size.x as u32 && height == max_size.y as u32 {
+                "full".into()
+            } else {
+                format!("{left},{top},{width},{height}")
+            };
+
+        // Only the width if the server cannot scale to both, e.g. level 1.
+        let size = if !self.supported_features.contains(&IiifFeature::SizeByWh)
+            && self.supported_features.contains(&IiifFeature::SizeByW)
+        {
+            format!("{},", size.width)
+        } else {
+            format!("{},{}", size.width, size.height)
+        };
+
+        // E.g. "https://stacks.stanford.edu/image/iiif/hg676jb4964%2F0380_796-44/{},{},{},{}/pct:25/0/default.png"
+        format!("{iiif_endpoint}/{region}/{size}/0/default.{image_format}")
+    }
+}
+
+/// Get the image sizes of the levels halving the full size until the image fits in a tile, from
+/// the smallest to the full size, for the servers without the tiles listed in the image info.
+fn get_synthesized_levels(full_size: Size, tile_size: Size) -> Vec<Size> {
+    let mut levels = vec![];
+    let mut scale_factor = 1;
+
+    loop {
+        let size = Size::new(
+            (full_size.width / scale_factor).max(1),
+            (full_size.height / scale_factor).max(1),
+        );
+
+        levels.push(size);
+
+        if size.width <= tile_size.width && size.height <= tile_size.height {
+            break;
+        }
+
+        scale_factor *= 2;
+    }
+
+    levels.reverse();
+    levels
+}
+
+/// Get the smallest of the sizes with the longer side at least the size, or the largest one if
+/// all of them are smaller.
+fn get_closest_size(sizes: &[Size], size: u32) -> Option<Size> {
+    sizes
+        .iter()
+        .filter(|x| x.width.max(x.height) >= size)
+        .min_by_key(|x| x.width.max(x.height))
+        .or_else(|| sizes.iter().max_by_key(|x| x.width.max(x.height)))
+        .copied()
+}
+
+/// Whether the longer side of the size is at least the size without being oversized to download.
+fn is_close_size(candidate: Size, size: u32) -> bool {
+    let longer_side = candidate.width.max(candidate.height);
+
+    longer_side >= size && longer_side <= size.saturating_mul(MAX_THUMBNAIL_OVERSIZE)
+}
+
+impl TileSource for IiifTileSource {
+    fn get_tile_size(&self) -> Size {
+        self.tile_size
+    }
+
+    fn get_levels(&self) -> &[Size] {
+        &self.levels
+    }
+
+    fn get_tile_url(&self, index: TileIndex, image_position: Rect) -> String {
+        let left = image_position.min.x.round() as u32;
+        let top = image_position.min.y.round() as u32;
+        let width = (image_position.max.x - image_position.min.x.round()).round() as u32;
+        let height = (image_position.max.y - image_position.min.y.round()).round() as u32;
+
+        let size = if self.exact_tile_sizes {
+            // The same scaled size of the region as the scale factor in the image info, e.g. 2 or 4.
+            let scale_factor = (self.get_max_size().x / self.levels[index.level()].width as f32)
+                .round()
+                .max(1.0) as u32;
+
+            Size::new(width.div_ceil(scale_factor), height.div_ceil(scale_factor))
+        } else {
+            self.tile_size
+        };
+
+        self.get_image_url(left, top, width, height, size)
+    }
+
+    fn get_thumbnail(&self, size: u32) -> (String, Size) {
+        let max_size = self.get_max_size();
+        let get_scaled_size = || {
+            let pct = size as f32 / max_size.max_element();
+
+            Size::new((pct * max_size.x) as u32, (pct * max_size.y) as u32)
+        };
+
+        // Prefer the advertised sizes, which the service may have rendered already, unless they
+        // are far from the size and any size can be requested by width/height.
+        let thumbnail_size = match get_closest_size(&self.optional_sizes, size) {
+            Some(x) if is_close_size(x, size) => x,
+            _ if self.supported_features.contains(&IiifFeature::SizeByWh) => get_scaled_size(),
+            Some(x) => x,
+            None => get_scaled_size(),
+        };
+
+        info!("Thumbnai {:?}", thumbnail_size);
+        (
+            self.get_image_url(0, 0, max_size.x as u32, max_size.y as u32, thumbnail_size),
+            thumbnail_size,
+        )
+    }
+
+    fn get_region_url(&self, region: Rect, orientation: ImageOrientation) -> Option<String> {
+        let left = region.min.x.round() as u32;
+        let top = region.min.y.round() as u32;
+        let width = (region.max.x.round() as u32).saturating_sub(left).max(1);
+        let height = (region.max.y.round() as u32).saturating_sub(top).max(1);
+
+        // At the full size in JPEG, which all the image services support.
+        Some(format!(
+            "{}/{left},{top},{width},{height}/max/{}/default.jpg",
+            self.iiif_endpoint,
+            orientation.get_rotation()
+        ))
+    }
+
+    fn supports_any_region(&self) -> bool {
+        // The static tiles can only be requested at their own positions.
+        !self.exact_tile_sizes
+            && self.supported_features.contains(&IiifFeature::RegionByPx)
+            && self.levels.len() > 1
+    }
+
+    fn set_data_saver(&mut self, data_saver: bool) {
+        self.data_saver = data_saver;
+    }
+
+    fn get_capabilities(&self) -> ServiceCapabilities {
+        ServiceCapabilities {
+            protocol: match self.api_version {
+                Some(version) => format!("IIIF {}", version),
+                None => "IIIF".to_string(),
+            },
+            level: self.compliance_level,
+            tiles: self.levels.len() > 1,
+            formats: self.formats.iter().map(|x| x.to_string()).collect(),
+        }
+    }
+
+    fn get_format(&self) -> String {
+        if self.data_saver {
+            IiifImageFormat::Jpg.to_string()
+        } else {
+            self.image_format.to_string()
+        }
+    }
+
+    fn set_format(&mut self, format: &str) {
+        if let Some(image_format) = self.formats.iter().find(|x| x.to_string() == format) {
+            self.image_format = image_format.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_image_url() {
+        let image = IiifTileSource::new(
+            "https://iiif_end_point/uuid".into(),
+            Size::new(1024, 1024),
+            vec![Size::new(678, 478), Size::new(2713, 1910)],
+            IiifImageFormat::Png,
+            HashSet::new(),
+            vec![Size::new(678, 478)],
+        );
+
+        assert_eq!(
+            image.get_image_url(1, 2, 3, 4, Size::new(1, 2)),
+            "https://iiif_end_point/uuid/1,2,3,4/1,2/0/default.png"
+        );
+        assert_eq!(
+            image.get_image_url(0, 0, 2713, 1910, Size::new(1, 2)),
+            "https://iiif_end_point/uuid/full/1,2/0/default.png"
+        );
+        assert_eq!(
+            image.get_thumbnail(256),
+            (
+                "https://iiif_end_point/uuid/full/678,478/0/default.png".to_string(),
+                Size::new(678, 478)
+            )
+        );
+    }
+
+    #[test]
+    fn test_get_thumbnail_from_sizes() {
+        let sizes = vec![
+            Size::new(150, 100),
+            Size::new(600, 400),
+            Size::new(300, 200),
+        ];
+        let image = IiifTileSource::new(
+            "https://iiif_end_point/uuid".into(),
+            Size::new(1024, 1024),
+            vec![Size::new(600, 400), Size::new(2400, 1600)],
+            IiifImageFormat::Jpg,
+            [IiifFeature::SizeByWh].into_iter().collect(),
+            sizes.clone(),
+        );
+
+        assert_eq!(get_closest_size(&sizes, 256), Some(Size::new(300, 200)));
+        assert_eq!(get_closest_size(&sizes, 1000), Some(Size::new(600, 400)));
+        assert_eq!(get_closest_size(&[], 256), None);
+
+        // The advertised size on a high-DPI screen.
+        assert_eq!(image.get_thumbnail(392).1, Size::new(600, 400));
+        // Too small, so the size is requested by width/height.
+        assert_eq!(image.get_thumbnail(800).1, Size::new(800, 533));
+    }
+
+    #[test]
+    fn test_get_region_url() {
+        let image = IiifTileSource::new(
+            "https://iiif_end_point/uuid".into(),
+            Size::new(1024, 1024),
+            vec![Size::new(678, 478), Size::new(2713, 1910)],
+            IiifImageFormat::Png,
+            HashSet::new(),
+            vec![Size::new(678, 478)],
+        );
+
+        assert_eq!(
+            image.get_region_url(
+                Rect::new(10.4, 20.6, 110.4, 220.6),
+                ImageOrientation::default()
+            ),
+            Some("https://iiif_end_point/uuid/10,21,100,200/max/0/default.jpg".to_string())
+        );
+        assert_eq!(
+            image.get_region_url(
+                Rect::new(0.0, 0.0, 10.0, 10.0),
+                ImageOrientation {
+                    rotation: 90,
+                    mirrored: true
+                }
+            ),
+            Some("https://iiif_end_point/uuid/0,0,10,10/max/!90/default.jpg".to_string())
+        );
+    }
+
+    #[test]
+    fn test_get_image_url_with_data_saver() {
+        let mut image = IiifTileSource::new(
+            "https://iiif_end_point/uuid".into(),
+            Size::new(1024, 1024),
+            vec![Size::new(678, 478), Size::new(2713, 1910)],
+            IiifImageFormat::Png,
+            HashSet::new(),
+            vec![Size::new(678, 478)],
+        );
+
+        image.set_data_saver(true);
+
+        assert_eq!(
+            image.get_image_url(1, 2, 3, 4, Size::new(1, 2)),
+            "https://iiif_end_point/uuid/1,2,3,4/1,2/0/default.jpg"
+        );
+    }
+
+    #[test]
+    fn test_get_capabilities() {
+        let json = r#"{
+            "@context" : "http://iiif.io/api/image/2/context.json",
+            "@id" : "https://example.org/image",
+            "protocol" : "http://iiif.io/api/image",
+            "width" : 4000,
+            "height" : 3000,
+            "tiles" : [{ "width" : 512, "scaleFactors" : [ 1, 2, 4, 8 ] }],
+            "profile" : [
+                "http://iiif.io/api/image/2/level1.json",
+                { "formats" : [ "png", "webp" ] }
+            ]
+        }"#;
+
+        let image = IiifTileSource::try_from_json(json, "https://example.org/image")
+            .expect("should parse the image info");
+
+        assert_eq!(
+            image.get_capabilities(),
+            ServiceCapabilities {
+                protocol: "IIIF 2".to_string(),
+                level: Some(1),
+                tiles: true,
+                formats: vec!["jpg".to_string(), "png".to_string(), "webp".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_synthesized_levels() {
+        let json = r#"{
+            "@context": "http://iiif.io/api/image/3/context.json",
+            "id": "https://example.org/image",
+            "type": "ImageService3",
+            "protocol": "http://iiif.io/api/image",
+            "profile": "level1",
+            "width": 3000,
+            "height": 1000
+        }"#;
+
+        let image = IiifTileSource::try_from_json(json, "https://example.org/image")
+            .expect("should parse the image info");
+
+        assert_eq!(image.get_tile_size(), Size::new(512, 512));
+        assert_eq!(
+            image.get_levels(),
+            &[
+                Size::new(375, 125),
+                Size::new(750, 250),
+                Size::new(1500, 500),
+                Size::new(3000, 1000)
+            ]
+        );
+        assert_eq!(
+            image.get_tile_url(
+                TileIndex::new(1, 0, 2),
+                Rect::from_corners(Vec2::new(1024.0, 0.0), Vec2::new(2048.0, 1000.0))
+            ),
+            "https://example.org/image/1024,0,1024,1000/512,/0/default.jpg"
+        );
+        assert_eq!(
+            get_synthesized_levels(Size::new(400, 300), Size::new(512, 512)),
+            vec![Size::new(400, 300)]
+        );
+    }
+
+    #[test]
+    fn test_level0_tiles() {
+        let json = r#"{
+            "@context": "http://iiif.io/api/image/3/context.json",
+            "id": "file:///tiles",
+            "type": "ImageService3",
+            "protocol": "http://iiif.io/api/image",
+            "profile": "level0",
+            "width": 700,
+            "height": 300,
+            "sizes": [{ "width": 350, "height": 150 }],
+            "tiles": [{ "width": 512, "height": 512, "scaleFactors": [1, 2] }]
+        }"#;
+
+        let image = IiifTileSource::try_from_json(json, "file:///tiles")
+            .expect("should parse the image info");
+
+        assert_eq!(
+            image.get_levels(),
+            &[Size::new(350, 150), Size::new(700, 300)]
+        );
+        assert_eq!(
+            image.get_tile_url(
+                TileIndex::new(1, 0, 1),
+                Rect::from_corners(Vec2::new(512.0, 0.0), Vec2::new(700.0, 300.0))
+            ),
+            "file:///tiles/512,0,188,300/188,300/0/default.jpg"
+        );
+        assert_eq!(
+            image.get_tile_url(
+                TileIndex::new(0, 0, 0),
+                Rect::from_corners(Vec2::new(0.0, 0.0), Vec2::new(700.0, 300.0))
+            ),
+            "file:///tiles/full/350,150/0/default.jpg"
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use crate::{
+    iiif::IiifError,
+    rendering::{tile::TileIndex, tile_format::get_url_format, tiled_image::Size},
+    tilesource::{ServiceCapabilities, TileSource},
+};
+use bevy::prelude::Rect;
+
+/// Image without an image service, e.g. the thumbnail of a canvas whose image service fails,
+/// drawn as a single tile stretched to the size of the canvas.
+pub(crate) struct SingleImageTileSource {
+    url: String,
+    /// Size of the canvas, as the size of the image is only known once it is loaded, or the size
+    /// measured from a plain image opened directly.
+    levels: [Size; 1],
+}
+
+/// Size of a plain image measured when it is fetched, standing for its image info.
+#[derive(serde::Serialize, serde::Deserialize)]
+pub(crate) struct ImageSize {
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+}
+
+impl SingleImageTileSource {
+    pub(crate) fn new(url: &str, size: Size) -> Self {
+        Self {
+            url: url.to_string(),
+            levels: [size],
+        }
+    }
+
+    /// Create from the size of the plain image in JSON, e.g. `{"width": 800, "height": 600}`.
+    pub(crate) fn try_from_json(content: &str, url: &str) -> Result<Self, IiifError> {
+        let size = serde_json::from_str::<ImageSize>(content)?;
+
+        if size.width == 0 || size.height == 0 {
+            return Err(IiifError::IiifFormatError(format!(
+                "empty image {} × {}",
+                size.width, size.height
+            )));
+        }
+
+        Ok(Self::new(url, Size::new(size.width, size.height)))
+    }
+}
+
+impl TileSource for SingleImageTileSource {
+    fn get_tile_size(&self) -> Size {
+        self.levels[0]
+    }
+
+    fn get_levels(&self) -> &[Size] {
+        &self.levels
+    }
+
+    fn get_tile_url(&self, _index: TileIndex, _image_position: Rect) -> String {
+        self.url.to_string()
+    }
+
+    fn get_thumbnail(&self, _size: u32) -> (String, Size) {
+        (self.url.to_string(), self.levels[0])
+    }
+
+    fn supports_any_region(&self) -> bool {
+        false
+    }
+
+    fn get_capabilities(&self) -> ServiceCapabilities {
+        ServiceCapabilities {
+            protocol: "Image".to_string(),
+            level: None,
+            tiles: false,
+            formats: vec![self.get_format()],
+        }
+    }
+
+    /// Get the format by the extension of the URL, which is the only one available.
+    fn get_format(&self) -> String {
+        get_url_format(&self.url).unwrap_or_default().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_image_tile_source() {
+        let image =
+            SingleImageTileSource::new("https://example.org/thumbs/p1.png", Size::new(750, 1000));
+
+        assert_eq!(image.get_levels(), &[Size::new(750, 1000)]);
+        assert_eq!(image.get_tile_size(), Size::new(750, 1000));
+        assert_eq!(
+            image.get_tile_url(TileIndex::new(0, 0, 0), Rect::default()),
+            "https://example.org/thumbs/p1.png"
+        );
+        assert_eq!(image.get_format(), "png");
+
+        let image = SingleImageTileSource::try_from_json(
+            r#"{"width": 800, "height": 600}"#,
+            "https://example.org/scans/p1.jpg",
+        )
+        .expect("should create from the size");
+
+        assert_eq!(image.get_levels(), &[Size::new(800, 600)]);
+        assert!(
+            SingleImageTileSource::try_from_json(r#"{"width": 0, "height": 600}"#, "a.jpg")
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,162 @@
+use crate::{
+    iiif::IiifError,
+    rendering::{tile::TileIndex, tiled_image::Size},
+    tilesource::{ServiceCapabilities, TileSource, get_xml_number},
+};
+use bevy::prelude::Rect;
+
+/// Number of tiles in each tile group folder.
+const TILES_PER_GROUP: u32 = 256;
+
+/// Zoomify image, with the tiers from a single tile to the full size, each half of the next one.
+pub(crate) struct ZoomifyTileSource {
+    /// URL of the folder with `ImageProperties.xml`.
+    base_url: String,
+    tile_size: Size,
+    levels: Vec<Size>,
+}
+
+impl ZoomifyTileSource {
+    /// Create the image from the `ImageProperties.xml` fetched from the URL.
+    pub(crate) fn try_from_xml(xml: &str, url: &str) -> core::result::Result<Self, IiifError> {
+        let tile_size = get_xml_number(xml, "IMAGE_PROPERTIES", "TILESIZE")?;
+        let width = get_xml_number(xml, "IMAGE_PROPERTIES", "WIDTH")?;
+        let height = get_xml_number(xml, "IMAGE_PROPERTIES", "HEIGHT")?;
+
+        if tile_size == 0 || width == 0 || height == 0 {
+            return Err(IiifError::IiifFormatError(format!(
+                "invalid Zoomify size in '{}'",
+                url
+            )));
+        }
+
+        let base_url = url
+            .rsplit_once('/')
+            .map_or("", |(folder, _)| folder)
+            .to_string();
+
+        Ok(Self {
+            base_url,
+            tile_size: Size::new(tile_size, tile_size),
+            levels: get_levels(width, height, tile_size),
+        })
+    }
+
+    /// Get the number of tiles in the tier.
+    fn get_num_tiles(&self, level: usize) -> u32 {
+        let size = self.levels[level];
+
+        size.width.div_ceil(self.tile_size.width) * size.height.div_ceil(self.tile_size.height)
+    }
+}
+
+/// Get the tier sizes from the one in a single tile to the full size.
+fn get_levels(width: u32, height: u32, tile_size: u32) -> Vec<Size> {
+    let mut size = Size::new(width, height);
+    let mut levels = vec![size];
+
+    while size.width > tile_size || size.height > tile_size {
+        size = Size::new(size.width / 2, size.height / 2);
+        levels.push(size);
+    }
+
+    levels.reverse();
+    levels
+}
+
+impl TileSource for ZoomifyTileSource {
+    fn get_tile_size(&self) -> Size {
+        self.tile_size
+    }
+
+    fn get_levels(&self) -> &[Size] {
+        &self.levels
+    }
+
+    fn get_tile_url(&self, index: TileIndex, _image_position: Rect) -> String {
+        let level = index.level();
+        let columns = self.levels[level].width.div_ceil(self.tile_size.width);
+
+        // The tiles are numbered across the tiers from the smallest one, and grouped by the number.
+        let tile_number =
+            (0..level).map(|x| self.get_num_tiles(x)).sum::<u32>() + index.y * columns + index.x;
+
+        format!(
+            "{}/TileGroup{}/{}-{}-{}.jpg",
+            self.base_url,
+            tile_number / TILES_PER_GROUP,
+            index.z,
+            index.x,
+            index.y
+        )
+    }
+
+    fn get_thumbnail(&self, _size: u32) -> (String, Size) {
+        (
+            self.get_tile_url(TileIndex::new(0, 0, 0), Rect::default()),
+            self.levels[0],
+        )
+    }
+
+    fn supports_any_region(&self) -> bool {
+        false
+    }
+
+    fn get_capabilities(&self) -> ServiceCapabilities {
+        ServiceCapabilities {
+            protocol: "Zoomify".to_string(),
+            level: None,
+            tiles: true,
+            formats: vec!["jpg".to_string()],
+        }
+    }
+
+    fn get_format(&self) -> String {
+        "jpg".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zoomify_tile_source() {
+        let xml = r#"<IMAGE_PROPERTIES WIDTH="2000" HEIGHT="1500" NUMTILES="65" NUMIMAGES="1" VERSION="1.8" TILESIZE="256" />"#;
+
+        let image =
+            ZoomifyTileSource::try_from_xml(xml, "https://example.org/z/ImageProperties.xml")
+                .expect("should parse the Zoomify properties");
+
+        assert_eq!(
+            image.get_levels(),
+            &[
+                Size::new(250, 187),
+                Size::new(500, 375),
+                Size::new(1000, 750),
+                Size::new(2000, 1500)
+            ]
+        );
+        // 1 + 4 + 12 + 48 tiles in the tiers.
+        assert_eq!(image.get_num_tiles(3), 48);
+        assert_eq!(
+            image.get_tile_url(TileIndex::new(0, 0, 0), Rect::default()),
+            "https://example.org/z/TileGroup0/0-0-0.jpg"
+        );
+        assert_eq!(
+            image.get_tile_url(TileIndex::new(1, 2, 2), Rect::default()),
+            "https://example.org/z/TileGroup0/2-1-2.jpg"
+        );
+
+        let xml = r#"<IMAGE_PROPERTIES WIDTH="8192" HEIGHT="8192" TILESIZE="256" />"#;
+        let image =
+            ZoomifyTileSource::try_from_xml(xml, "https://example.org/z/ImageProperties.xml")
+                .expect("should parse the Zoomify properties");
+
+        // 1 + 4 + 16 + 64 + 256 tiles before the last tier.
+        assert_eq!(
+            image.get_tile_url(TileIndex::new(3, 0, 5), Rect::default()),
+            "https://example.org/z/TileGroup1/5-3-0.jpg"
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use crate::camera::main_camera::MainCamera2d;
+use bevy::{
+    camera::{ClearColorConfig, RenderTarget, ScalingMode},
+    image::Image,
+    prelude::{
+        Assets, Camera, Camera2d, ChildOf, Color, Commands, Component, Entity, Handle,
+        MessageWriter, On, Projection, ResMut, Resource, Single, With, info, warn,
+    },
+    render::{
+        render_resource::TextureFormat,
+        view::screenshot::{Screenshot, ScreenshotCaptured},
+    },
+    window::RequestRedraw,
+};
+use bevy_egui::egui;
+
+/// Order of the camera drawing the view for the clipboard, before the other cameras.
+const VIEW_CLIPBOARD_CAMERA_ORDER: isize = -3;
+
+/// Camera drawing the view for the clipboard.
+#[derive(Component)]
+pub(crate) struct ViewClipboardCamera;
+
+/// Copy of the tiles in the viewport to the clipboard, without the UI over them.
+#[derive(Resource, Default)]
+pub(crate) struct ViewClipboard {
+    /// Whether the view is requested to be copied.
+    pub(crate) requested: bool,
+    /// Camera drawing the view into the texture, and the texture.
+    camera: Option<(Entity, Handle<Image>)>,
+    /// View captured to be placed on the clipboard by the UI.
+    image: Option<egui::ColorImage>,
+}
+
+impl ViewClipboard {
+    /// Take the view captured to place it on the clipboard.
+    pub(crate) fn take_image(&mut self) -> Option<egui::ColorImage> {
+        self.image.take()
+    }
+}
+
+/// Convert the captured view to the image for the clipboard.
+fn to_color_image(image: Image) -> Option<egui::ColorImage> {
+    let image = image
+        .try_into_dynamic()
+        .inspect_err(|e| warn!("Unable to convert the view for the clipboard. {}", e))
+        .ok()?
+        .to_rgba8();
+
+    Some(egui::ColorImage::from_rgba_unmultiplied(
+        [image.width() as usize, image.height() as usize],
+        image.as_raw(),
+    ))
+}
+
+/// Draw the view of the main camera into a texture of the size of the viewport when the copy is
+/// requested, and capture it in the next frame once it is drawn.
+pub(crate) fn view_clipboard_system(
+    mut commands: Commands,
+    mut view_clipboard: ResMut<ViewClipboard>,
+    main_camera: Single<(Entity, &Camera, &Projection), With<MainCamera2d>>,
+    mut images: ResMut<Assets<Image>>,
+    mut redraw_request_writer: MessageWriter<RequestRedraw>,
+) {
+    if !view_clipboard.requested {
+        return;
+    }
+
+    view_clipboard.requested = false;
+
+    if let Some((camera_entity, target)) = view_clipboard.camera.clone() {
+        commands.spawn(Screenshot::image(target)).observe(
+            move |captured: On<ScreenshotCaptured>,
+                  mut commands: Commands,
+                  mut view_clipboard: ResMut<ViewClipboard>,
+                  mut redraw_request_writer: MessageWriter<RequestRedraw>| {
+                view_clipboard.image = to_color_image(captured.image.clone());
+                view_clipboard.camera = None;
+                commands.entity(camera_entity).despawn();
+                redraw_request_writer.write(RequestRedraw);
+            },
+        );
+        return;
+    }
+
+    let (main_camera_entity, main_camera, main_projection) = main_camera.into_inner();
+    let (Projection::Orthographic(main_projection), Some(viewport_size)) =
+        (main_projection, main_camera.physical_viewport_size())
+    else {
+        return;
+    };
+
+    if !main_camera.is_active {
+        return;
+    }
+
+    info!("Copy the view of {:?} to the clipboard", viewport_size);
+
+    let target = images.add(Image::new_target_texture(
+        viewport_size.x,
+        viewport_size.y,
+        TextureFormat::Rgba8UnormSrgb,
+    ));
+    let mut projection = main_projection.clone();
+
+    // The same area of the world as the main camera, regardless of the size of the texture.
+    projection.scaling_mode = ScalingMode::Fixed {
+        width: main_projection.area.width(),
+        height: main_projection.area.height(),
+    };
+    projection.scale = 1.0;
+
+    let camera_entity = commands
+        .spawn((
+            ViewClipboardCamera,
+            Camera2d,
+            Camera {
+                target: RenderTarget::Image(target.clone().into()),
+                order: VIEW_CLIPBOARD_CAMERA_ORDER,
+                clear_color: ClearColorConfig::Custom(Color::BLACK),
+                ..Default::default()
+            },
+            Projection::Orthographic(projection),
+            ChildOf(main_camera_entity),
+        ))
+        .id();
+
+    // Capture the texture in the next frame, once the camera has drawn it.
+    view_clipboard.camera = Some((camera_entity, target));
+    view_clipboard.requested = true;
+    redraw_request_writer.write(RequestRedraw);
+}
@@ -0,0 +1,240 @@
+use crate::{
+    app::{
+        app_settings::AppSettings,
+        app_state::{AppState, DownloadState, ImageDownloadInfo},
+    },
+    web,
+};
+use bevy::{
+    prelude::{MessageWriter, Res, ResMut, Resource, warn},
+    window::RequestRedraw,
+};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// Download of the manifest or the image info which timed out after all the retries, waiting for
+/// the user to retry or cancel it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum StalledDownload {
+    Manifest,
+    ImageInfo,
+}
+
+/// Action requested for the stalled download.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum WatchdogRequest {
+    Retry,
+    Cancel,
+}
+
+/// Retries of a download timing out.
+#[derive(Default)]
+struct DownloadWatch {
+    retries: u32,
+    /// URL of the download which timed out after all the retries.
+    stalled_url: Option<String>,
+}
+
+impl DownloadWatch {
+    /// Check the download, and get the URL and the info to request it again if it has timed out
+    /// with the retries left.
+    fn check<T: Clone>(
+        &mut self,
+        download_state: &Mutex<DownloadState<T>>,
+        timeout: Duration,
+        max_retries: u32,
+    ) -> Option<(String, T)> {
+        let Ok(download_state) = download_state.lock() else {
+            return None;
+        };
+        let DownloadState::InProgress { url, started, info } = &*download_state else {
+            // Finished, or cancelled.
+            self.retries = 0;
+            self.stalled_url = None;
+            return None;
+        };
+
+        if started.elapsed() < timeout {
+            self.stalled_url = None;
+            return None;
+        }
+
+        if self.stalled_url.is_some() {
+            return None;
+        }
+
+        if self.retries < max_retries {
+            self.retries += 1;
+            Some((url.to_string(), info.clone()))
+        } else {
+            self.stalled_url = Some(url.to_string());
+            None
+        }
+    }
+}
+
+/// Watchdog of the manifest and the image info downloads, which may never finish if the server
+/// hangs.
+#[derive(Resource, Default)]
+pub(crate) struct DownloadWatchdog {
+    manifest: DownloadWatch,
+    image_info: DownloadWatch,
+    pub(crate) request: Option<WatchdogRequest>,
+}
+
+impl DownloadWatchdog {
+    /// Get the download timed out after all the retries, and its URL.
+    pub(crate) fn get_stalled(&self) -> Option<(StalledDownload, &str)> {
+        self.manifest
+            .stalled_url
+            .as_deref()
+            .map(|x| (StalledDownload::Manifest, x))
+            .or_else(|| {
+                self.image_info
+                    .stalled_url
+                    .as_deref()
+                    .map(|x| (StalledDownload::ImageInfo, x))
+            })
+    }
+}
+
+/// Get the info of the download in progress.
+fn get_in_progress_info<T: Clone>(download_state: &Mutex<DownloadState<T>>) -> Option<T> {
+    match &*download_state.lock().ok()? {
+        DownloadState::InProgress { info, .. } => Some(info.clone()),
+        _ => None,
+    }
+}
+
+/// Request the manifest again.
+fn retry_manifest(app_state: &mut ResMut<AppState>, app_settings: &AppSettings, url: &str) {
+    warn!("Request the manifest {} again after timing out", url);
+    web::load_presentation(app_state, app_settings, url);
+}
+
+/// Request the image info again with the same info of the canvas.
+fn retry_image_info(
+    app_state: &AppState,
+    app_settings: &AppSettings,
+    download_state: &Arc<Mutex<DownloadState<ImageDownloadInfo>>>,
+    url: &str,
+    info: ImageDownloadInfo,
+) {
+    warn!("Request the image info {} again after timing out", url);
+    web::load(
+        url,
+        &app_settings.proxy_prefix,
+        &app_state.http_cache,
+        &app_state.host_stats,
+        Arc::clone(download_state),
+        info,
+    );
+}
+
+/// Request the manifest and the image info again when they time out, and let the user retry or
+/// cancel them once the retries run out.
+pub(crate) fn download_watchdog_system(
+    mut watchdog: ResMut<DownloadWatchdog>,
+    mut app_state: ResMut<AppState>,
+    app_settings: Res<AppSettings>,
+    mut redraw_request_writer: MessageWriter<RequestRedraw>,
+) {
+    let timeout = Duration::from_secs_f32(app_settings.download_timeout_secs.max(1.0));
+    let manifest_state = Arc::clone(&app_state.manifest_json_download_state);
+    let image_state = Arc::clone(&app_state.image_json_download_state);
+
+    match watchdog.request.take() {
+        Some(WatchdogRequest::Retry) => {
+            if let Some(url) = watchdog.manifest.stalled_url.take() {
+                watchdog.manifest.retries = 0;
+                retry_manifest(&mut app_state, &app_settings, &url);
+            }
+
+            if let Some(url) = watchdog.image_info.stalled_url.take()
+                && let Some(info) = get_in_progress_info(&image_state)
+            {
+                watchdog.image_info.retries = 0;
+                retry_image_info(&app_state, &app_settings, &image_state, &url, info);
+            }
+        }
+        Some(WatchdogRequest::Cancel) => {
+            // The response is ignored if it comes afterwards.
+            if watchdog.manifest.stalled_url.take().is_some() {
+                *manifest_state
+                    .lock()
+                    .expect("should be able to lock the presentation download state mutex") =
+                    DownloadState::None;
+            }
+
+            if watchdog.image_info.stalled_url.take().is_some() {
+                *image_state
+                    .lock()
+                    .expect("should be able to lock the image download state mutex") =
+                    DownloadState::None;
+            }
+        }
+        None => {}
+    }
+
+    if let Some((url, _)) =
+        watchdog
+            .manifest
+            .check(&manifest_state, timeout, app_settings.download_retries)
+    {
+        retry_manifest(&mut app_state, &app_settings, &url);
+    }
+
+    if let Some((url, info)) =
+        watchdog
+            .image_info
+            .check(&image_state, timeout, app_settings.download_retries)
+    {
+        retry_image_info(&app_state, &app_settings, &image_state, &url, info);
+    }
+
+    if watchdog.get_stalled().is_some() {
+        redraw_request_writer.write(RequestRedraw);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::platform::time::Instant;
+
+    #[test]
+    fn test_download_watch() {
+        let download_state = Mutex::new(DownloadState::InProgress {
+            url: "https://example.org/info.json".to_string(),
+            started: Instant::now(),
+            info: 1,
+        });
+        let mut watch = DownloadWatch::default();
+
+        // Not timed out yet.
+        assert_eq!(
+            watch.check(&download_state, Duration::from_secs(60), 1),
+            None
+        );
+
+        // Requested again, and then stalled once the retries run out.
+        assert_eq!(
+            watch.check(&download_state, Duration::ZERO, 1),
+            Some(("https://example.org/info.json".to_string(), 1))
+        );
+        assert_eq!(watch.check(&download_state, Duration::ZERO, 1), None);
+        assert_eq!(
+            watch.stalled_url.as_deref(),
+            Some("https://example.org/info.json")
+        );
+
+        // Reset when finished.
+        *download_state.lock().unwrap() = DownloadState::None;
+
+        assert_eq!(watch.check(&download_state, Duration::ZERO, 1), None);
+        assert_eq!(watch.retries, 0);
+        assert_eq!(watch.stalled_url, None);
+    }
+}
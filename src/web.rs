@@ -1,26 +1,469 @@
 use crate::{
-    UserNotification,
+    CanvasLoaded, ManifestLoaded,
+    app::app_settings::AppSettings,
     app::app_state::{AppState, DownloadState, ImageDownloadInfo, ManifestDownloadInfo},
-    presentation::{manifest::Manifest, ui::EguiUiState},
-    rendering::{model_image::ModelImage, tiled_image::TiledImage},
+    app::manifest_prefs::ManifestPrefsStore,
+    app::session::SessionState,
+    capability_cache::is_auth_error,
+    host_stats::HostStats,
+    http_cache::HttpCache,
+    iiif::{IiifError, image::ImageOrientation},
+    notification::UserNotification,
+    presentation::{
+        bookmarks::parse_xywh,
+        collection::{Collection, CollectionSearch},
+        error_page::ManifestErrorPage,
+        html,
+        manifest::Manifest,
+        model::IsCanvas,
+        reload::ManifestReload,
+        source::{ManifestSources, get_synthetic_collection_json, get_synthetic_manifest_json},
+        ui::{EguiUiState, get_canvas_announcement},
+    },
+    rendering::{
+        gpu_capabilities::GpuCapabilities,
+        model_image::ModelImage,
+        overlay_image::OverlayImage,
+        tile::TileModState,
+        tiled_image::{Size, TiledImage},
+    },
+    tilesource::{self, single::ImageSize},
 };
 use bevy::{
-    prelude::{Commands, Entity, MessageWriter, Query, ResMut, Result, Single, With},
+    platform::time::Instant,
+    prelude::{
+        Commands, Entity, MessageWriter, Query, Rect, Res, ResMut, Result, Single, With, debug,
+        info, info_span,
+    },
     window::RequestRedraw,
 };
-use std::sync::{Arc, Mutex};
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+/// Get the URL through the CORS proxy.
+///
+/// The URL is appended to the prefix as is, or percent-encoded if the prefix ends with a query
+/// parameter, e.g. "https://proxy.example.org/?url=".
+pub(crate) fn get_proxy_url(proxy_prefix: &str, url: &str) -> String {
+    if proxy_prefix.is_empty() || url.starts_with(proxy_prefix) || url.starts_with("file://") {
+        return url.to_string();
+    }
+
+    if proxy_prefix.ends_with('=') {
+        format!("{}{}", proxy_prefix, percent_encode(url, b""))
+    } else {
+        format!("{}{}", proxy_prefix, url)
+    }
+}
+
+/// Percent-encode the string but the unreserved characters and the ones kept, e.g. b"/" for a
+/// path.
+pub(crate) fn percent_encode(value: &str, kept: &[u8]) -> String {
+    let mut encoded = String::with_capacity(value.len() * 3);
+
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) || kept.contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+
+    encoded
+}
+
+/// Decode the percent-encoded string.
+pub(crate) fn percent_decode(value: &str) -> core::result::Result<String, IiifError> {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut rest = value.as_bytes();
+
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' && tail.len() >= 2 {
+            let hex = std::str::from_utf8(&tail[..2])
+                .map_err(|e| IiifError::IiifFormatError(e.to_string()))?;
+
+            bytes.push(u8::from_str_radix(hex, 16)?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+
+    Ok(String::from_utf8(bytes)?)
+}
+
+/// Get the file URL of the absolute path, e.g. "file:///C:/scans/p%201.tif" of
+/// "C:\\scans\\p 1.tif" on Windows.
+pub(crate) fn get_file_url(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    // The drive of a Windows path follows the empty host.
+    let separator = if path.starts_with('/') { "" } else { "/" };
+
+    format!("file://{}{}", separator, percent_encode(&path, b"/:"))
+}
+
+/// Get the path of the file URL, e.g. of the exported tiles. `None` if not a local file URL.
+pub(crate) fn get_file_path(url: &str) -> Option<PathBuf> {
+    let rest = url.strip_prefix("file://")?;
+    // Only the local host, which may be named.
+    let rest = rest.strip_prefix("localhost").unwrap_or(rest);
+
+    if !rest.starts_with('/') {
+        return None;
+    }
+
+    let path = percent_decode(rest).ok()?;
+
+    // The drive of a Windows path follows the empty host, e.g. "/C:/scans".
+    if cfg!(target_os = "windows")
+        && let [b'/', drive, b':', ..] = path.as_bytes()
+        && drive.is_ascii_alphabetic()
+    {
+        return Some(PathBuf::from(&path[1..]));
+    }
+
+    Some(PathBuf::from(path))
+}
+
+/// Resolve the URLs relative to the folder of the local JSON file at the URL, i.e. "." and the
+/// ones starting with "./" as written by the export for offline, so that the folder can be moved.
+/// The JSON is kept as is if it cannot be parsed.
+#[cfg(not(target_arch = "wasm32"))]
+fn resolve_relative_urls(json: &str, url: &str) -> String {
+    fn resolve(value: &mut serde_json::Value, folder_url: &str) {
+        match value {
+            serde_json::Value::String(x) if x == "." => *x = folder_url.to_string(),
+            serde_json::Value::String(x) => {
+                if let Some(rest) = x.strip_prefix("./") {
+                    *x = format!("{}/{}", folder_url, rest);
+                }
+            }
+            serde_json::Value::Array(x) => x.iter_mut().for_each(|x| resolve(x, folder_url)),
+            serde_json::Value::Object(x) => x.values_mut().for_each(|x| resolve(x, folder_url)),
+            _ => {}
+        }
+    }
+
+    let Ok(mut value) = serde_json::from_str::<serde_json::Value>(json) else {
+        return json.to_string();
+    };
+    let folder_url = url.rsplit_once('/').map_or(url, |(x, _)| x);
+
+    resolve(&mut value, folder_url);
+
+    value.to_string()
+}
+
+/// Get the host of the URL, e.g. "iiif.example.org", to compare the servers.
+pub(crate) fn get_host(url: &str) -> &str {
+    let url = url.split_once("://").map_or(url, |(_, x)| x);
+
+    url.split(['/', '?', '#']).next().unwrap_or_default()
+}
+
+/// `Accept` header of the manifest request, preferring the presentation API 3 to 2.
+#[cfg(not(target_arch = "wasm32"))]
+const PRESENTATION_ACCEPT: &str = concat!(
+    "application/ld+json;profile=\"http://iiif.io/api/presentation/3/context.json\", ",
+    "application/ld+json;profile=\"http://iiif.io/api/presentation/2/context.json\";q=0.9, ",
+    "application/json;q=0.8, */*;q=0.7"
+);
+
+/// `Accept` header of the manifest request in wasm, without the profiles whose quotes would
+/// require a CORS preflight the IIIF servers may not allow.
+#[cfg(target_arch = "wasm32")]
+const PRESENTATION_ACCEPT: &str = "application/ld+json, application/json;q=0.9, */*;q=0.8";
+
+/// What to do when the request fails.
+enum Fallback {
+    None,
+    /// Send the request instead, e.g. without the preferred types the server does not accept.
+    Request(ehttp::Request),
+    /// Use the JSON of the previous response instead, e.g. when its alternate fails.
+    Json(String),
+}
+
+/// Start to fetch the URL, through the CORS proxy if any, and handle state transition.
+///
+/// The fresh response in the cache is used without a request, and the stale one is revalidated.
+pub(crate) fn load<T: Clone + Send + 'static>(
+    url: &str,
+    proxy_prefix: &str,
+    http_cache: &HttpCache,
+    host_stats: &HostStats,
+    download_state: Arc<Mutex<DownloadState<T>>>,
+    info: T,
+) {
+    load_with_accept(
+        url,
+        proxy_prefix,
+        http_cache,
+        host_stats,
+        download_state,
+        info,
+        None,
+    );
+}
+
+/// Start to fetch the image info of the endpoint like [`load`], revalidating the one kept in the
+/// capability cache so that it is not downloaded again if unchanged.
+///
+/// The request is not sent if the image service refused it for the lack of authentication
+/// recently.
+pub(crate) fn load_image_info(
+    app_state: &AppState,
+    proxy_prefix: &str,
+    download_state: Arc<Mutex<DownloadState<ImageDownloadInfo>>>,
+    info: ImageDownloadInfo,
+) {
+    let url = TiledImage::get_image_info_url(&info.iiif_endpoint);
+
+    if app_state.capability_cache.is_auth_required(&url) {
+        *download_state
+            .lock()
+            .expect("should be able to lock the image download state mutex") =
+            DownloadState::Error {
+                url,
+                msg: "The image service required the authentication recently.".to_string(),
+            };
+        return;
+    }
+
+    if let Some(cached) = app_state.capability_cache.get_info(&url) {
+        debug!(
+            "Revalidate the image info of {} from the capability cache",
+            url
+        );
+
+        app_state
+            .http_cache
+            .store_stale(&url, &cached.json, &cached.validators);
+    }
+
+    if tilesource::is_image_url(&info.iiif_endpoint) {
+        load_image_size(&url, proxy_prefix, download_state, info);
+        return;
+    }
+
+    load(
+        &url,
+        proxy_prefix,
+        &app_state.http_cache,
+        &app_state.host_stats,
+        download_state,
+        info,
+    );
+}
+
+/// Bytes requested of a remote plain image to measure its size, enough for the headers of the
+/// common formats.
+const IMAGE_HEADER_BYTES: usize = 64 * 1024;
+
+/// Start to fetch the plain image at the URL, or read it if local, to measure its size as its
+/// image info.
+fn load_image_size(
+    url: &str,
+    proxy_prefix: &str,
+    download_state: Arc<Mutex<DownloadState<ImageDownloadInfo>>>,
+    info: ImageDownloadInfo,
+) {
+    // Only the header of the local file is read.
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(path) = get_file_path(url) {
+        let size = image::image_dimensions(path).map_err(IiifError::from);
+
+        *download_state.lock().unwrap() = get_image_size_state(url.to_string(), size, info);
+        return;
+    }
+
+    *download_state.lock().unwrap() = DownloadState::InProgress {
+        url: url.to_string(),
+        started: Instant::now(),
+        info: info.clone(),
+    };
+
+    fetch_image_size(
+        url.to_string(),
+        proxy_prefix.to_string(),
+        true,
+        download_state,
+        info,
+    );
+}
+
+/// Get the state of the download of the plain image by its size measured.
+fn get_image_size_state(
+    url: String,
+    size: Result<(u32, u32), IiifError>,
+    info: ImageDownloadInfo,
+) -> DownloadState<ImageDownloadInfo> {
+    match size {
+        Ok((width, height)) => DownloadState::Done {
+            json: serde_json::json!(ImageSize { width, height }).to_string(),
+            info,
+        },
+        Err(e) => DownloadState::Error {
+            url,
+            msg: e.to_string(),
+        },
+    }
+}
+
+/// Fetch the size of the plain image at the URL, only its first bytes with the headers if ranged,
+/// as the whole image is downloaded again as its tile.
+///
+/// The whole image is fetched if its headers are beyond the range, e.g. a TIFF with its
+/// directory at the end.
+fn fetch_image_size(
+    url: String,
+    proxy_prefix: String,
+    is_ranged: bool,
+    download_state: Arc<Mutex<DownloadState<ImageDownloadInfo>>>,
+    info: ImageDownloadInfo,
+) {
+    let mut request = ehttp::Request::get(get_proxy_url(&proxy_prefix, &url));
+
+    if is_ranged {
+        request
+            .headers
+            .insert("Range", format!("bytes=0-{}", IMAGE_HEADER_BYTES - 1));
+    }
+
+    ehttp::fetch(request, move |result| {
+        let size = match result {
+            Ok(response) if response.ok => {
+                let size = image::ImageReader::new(std::io::Cursor::new(response.bytes))
+                    .with_guessed_format()
+                    .map_err(IiifError::from)
+                    .and_then(|x| x.into_dimensions().map_err(IiifError::from));
+
+                // Only part of the image, without the headers.
+                if size.is_err() && response.status == 206 {
+                    fetch_image_size(url, proxy_prefix, false, download_state, info);
+                    return;
+                }
+
+                size
+            }
+            Ok(response) => Err(IiifError::IiifHttpError(format!(
+                "{} {}",
+                response.status, response.status_text
+            ))),
+            Err(e) => Err(IiifError::IiifHttpError(e)),
+        };
+        let mut download_state_mutex = download_state
+            .lock()
+            .expect("should be able to lock the image download state mutex");
+
+        // Skip the response of an earlier request.
+        if matches!(&*download_state_mutex, DownloadState::InProgress { url: x, .. } if *x == url) {
+            *download_state_mutex = get_image_size_state(url, size, info);
+        }
+    });
+}
+
+/// Start to fetch the URL like [`load`], asking for the types in the `Accept` header if any.
+///
+/// The request is sent again without the `Accept` header if the server does not accept it, and
+/// the alternate representation in the `Link` header is followed for the presentation API 3.
+#[allow(clippy::too_many_arguments)]
+fn load_with_accept<T: Clone + Send + 'static>(
+    url: &str,
+    proxy_prefix: &str,
+    http_cache: &HttpCache,
+    host_stats: &HostStats,
+    download_state: Arc<Mutex<DownloadState<T>>>,
+    info: T,
+    accept: Option<&str>,
+) {
+    if let Some(json) = http_cache.get_fresh(url) {
+        debug!("Use the cached response of {}", url);
 
-/// Start to fetch the URL and handle state transition.
-fn load<T: Send + 'static>(url: &str, download_state: Arc<Mutex<DownloadState<T>>>, info: T) {
-    let request = ehttp::Request::get(url);
-    let url = url.to_string();
+        *download_state.lock().unwrap() = DownloadState::Done { json, info };
+        return;
+    }
+
+    // Local files, e.g. the exported tiles, are read directly.
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(path) = get_file_path(url) {
+        *download_state.lock().unwrap() = match std::fs::read_to_string(path) {
+            Ok(json) => DownloadState::Done {
+                json: resolve_relative_urls(&json, url),
+                info,
+            },
+            Err(e) => DownloadState::Error {
+                url: url.to_string(),
+                msg: e.to_string(),
+            },
+        };
+        return;
+    }
+
+    let mut request = ehttp::Request::get(get_proxy_url(proxy_prefix, url));
+
+    http_cache.add_conditional_headers(url, &mut request);
+
+    let fallback = match accept {
+        Some(accept) => {
+            let fallback = request.clone();
+
+            request
+                .headers
+                .headers
+                .retain(|(key, _)| !key.eq_ignore_ascii_case("Accept"));
+            request.headers.insert("Accept", accept);
+
+            Fallback::Request(fallback)
+        }
+        None => Fallback::None,
+    };
 
     // In progress now.
     *download_state.lock().unwrap() = DownloadState::InProgress {
         url: url.to_string(),
+        started: Instant::now(),
+        info: info.clone(),
     };
 
+    fetch(
+        request,
+        fallback,
+        url.to_string(),
+        proxy_prefix.to_string(),
+        http_cache.clone(),
+        host_stats.clone(),
+        download_state,
+        info,
+    );
+}
+
+/// Send the request for the URL and handle the state transition of the response.
+#[allow(clippy::too_many_arguments)]
+fn fetch<T: Send + 'static>(
+    request: ehttp::Request,
+    fallback: Fallback,
+    url: String,
+    proxy_prefix: String,
+    http_cache: HttpCache,
+    host_stats: HostStats,
+    download_state: Arc<Mutex<DownloadState<T>>>,
+    info: T,
+) {
+    let request_url = request.url.to_string();
+
+    host_stats.record_request(&request_url);
+
     ehttp::fetch(request, move |result| {
+        match &result {
+            Ok(response) if response.ok || response.status == 304 => {
+                host_stats.record_bytes(&request_url, response.bytes.len());
+            }
+            _ => host_stats.record_error(&request_url),
+        }
+
         // Lock the mutex to make sure consistent state transition.
         let mut download_state_mutex = download_state
             .lock()
@@ -29,13 +472,80 @@ fn load<T: Send + 'static>(url: &str, download_state: Arc<Mutex<DownloadState<T>
         // Check if the response corresponds to the latest request. If not, we will skip it.
         if let DownloadState::InProgress {
             url: in_progress_url,
+            ..
         } = &(*download_state_mutex)
             && in_progress_url == &url
         {
             match result {
+                Ok(response) if response.status == 304 => {
+                    match http_cache.revalidate(&url, &response.headers) {
+                        Some(json) => {
+                            *download_state_mutex = DownloadState::Done { json, info };
+                        }
+                        None => {
+                            *download_state_mutex = DownloadState::Error {
+                                url,
+                                msg: "not modified but not in the cache".to_string(),
+                            };
+                        }
+                    }
+                }
+                Ok(response) if !response.ok => match fallback {
+                    Fallback::Request(request) => {
+                        debug!("Request {} again without the preferred types", url);
+
+                        fetch(
+                            request,
+                            Fallback::None,
+                            url,
+                            proxy_prefix,
+                            http_cache,
+                            host_stats,
+                            Arc::clone(&download_state),
+                            info,
+                        );
+                    }
+                    Fallback::Json(json) => {
+                        *download_state_mutex = DownloadState::Done { json, info };
+                    }
+                    Fallback::None => {
+                        *download_state_mutex = DownloadState::Error {
+                            url,
+                            msg: format!("{} {}", response.status, response.status_text),
+                        };
+                    }
+                },
                 Ok(response) => match String::from_utf8(response.bytes) {
                     Ok(json) => {
-                        *download_state_mutex = DownloadState::Done { json, info };
+                        // The web page is tried as the manifests it refers to otherwise.
+                        let alternate_url = match fallback {
+                            Fallback::Request(_) => {
+                                get_presentation_3_alternate(&response.headers, &json)
+                                    .or_else(|| {
+                                        get_page_json_ld_alternate(&response.headers, &json)
+                                    })
+                                    .filter(|x| *x != url)
+                            }
+                            _ => None,
+                        };
+
+                        if let Some(alternate_url) = alternate_url {
+                            debug!("Follow the alternate {} of {}", alternate_url, url);
+
+                            fetch(
+                                ehttp::Request::get(get_proxy_url(&proxy_prefix, &alternate_url)),
+                                Fallback::Json(json),
+                                url,
+                                proxy_prefix,
+                                http_cache,
+                                host_stats,
+                                Arc::clone(&download_state),
+                                info,
+                            );
+                        } else {
+                            http_cache.store(&url, &response.headers, &json);
+                            *download_state_mutex = DownloadState::Done { json, info };
+                        }
                     }
                     Err(e) => {
                         *download_state_mutex = DownloadState::Error {
@@ -44,26 +554,144 @@ fn load<T: Send + 'static>(url: &str, download_state: Arc<Mutex<DownloadState<T>
                         };
                     }
                 },
-                Err(e) => {
-                    *download_state_mutex = DownloadState::Error { url, msg: e };
-                }
+                Err(e) => match fallback {
+                    Fallback::Json(json) => {
+                        *download_state_mutex = DownloadState::Done { json, info };
+                    }
+                    _ => {
+                        *download_state_mutex = DownloadState::Error { url, msg: e };
+                    }
+                },
             }
         }
     });
 }
 
-/// Begin loading the IIIF presentation from remote URL.
-pub(crate) fn load_presentation(app_state: &mut ResMut<AppState>, presentation_url: &str) {
-    load(
+/// Get the URL of the alternate representation in the presentation API 3 from the `Link`
+/// headers, if the JSON is not already in that version.
+fn get_presentation_3_alternate(headers: &ehttp::Headers, json: &str) -> Option<String> {
+    /// Only the context of the JSON is needed.
+    #[derive(serde::Deserialize)]
+    struct JsonLd {
+        #[serde(rename = "@context", default)]
+        context: serde_json::Value,
+    }
+
+    let is_presentation_3 = serde_json::from_str::<JsonLd>(json).is_ok_and(|x| {
+        x.context
+            .to_string()
+            .contains("iiif.io/api/presentation/3/")
+    });
+
+    if is_presentation_3 {
+        return None;
+    }
+
+    get_alternate_links(headers)
+        .find(|(_, params)| params.contains("iiif.io/api/presentation/3/"))
+        .map(|(url, _)| url)
+}
+
+/// Get the URL of the JSON-LD alternate representation in the `Link` headers of the web page,
+/// e.g. the manifest of a catalog page, like its `<link rel="alternate">` tags.
+fn get_page_json_ld_alternate(headers: &ehttp::Headers, content: &str) -> Option<String> {
+    if !html::is_html_page(content) {
+        return None;
+    }
+
+    get_alternate_links(headers)
+        .find(|(_, params)| params.to_ascii_lowercase().contains("application/ld+json"))
+        .map(|(url, _)| url)
+}
+
+/// Get the absolute URLs of the alternate representations in the `Link` headers with their
+/// parameters, e.g.
+/// `<https://example.org/manifest3>; rel="alternate"; type="application/ld+json;profile=..."`.
+fn get_alternate_links(headers: &ehttp::Headers) -> impl Iterator<Item = (String, &str)> {
+    headers
+        .get_all("Link")
+        .flat_map(|x| x.split('<').skip(1))
+        .filter_map(|x| x.split_once('>'))
+        .filter(|(_, params)| {
+            params.split(';').any(|x| {
+                x.trim().strip_prefix("rel=").is_some_and(|rel| {
+                    rel.trim_matches('"')
+                        .split_whitespace()
+                        .any(|x| x == "alternate")
+                })
+            })
+        })
+        .map(|(url, params)| (url.trim().to_string(), params))
+        .filter(|(url, _)| url.starts_with("http://") || url.starts_with("https://"))
+}
+
+/// Begin loading the IIIF presentation from remote URL, preferring the presentation API 3 if
+/// the server has both versions.
+pub(crate) fn load_presentation(
+    app_state: &mut ResMut<AppState>,
+    app_settings: &AppSettings,
+    presentation_url: &str,
+) {
+    load_presentation_to(
+        app_state,
+        app_settings,
         presentation_url,
         Arc::clone(&app_state.manifest_json_download_state),
+    );
+}
+
+/// Begin loading the IIIF presentation like [`load_presentation`] into the download state, e.g.
+/// of another window.
+pub(crate) fn load_presentation_to(
+    app_state: &AppState,
+    app_settings: &AppSettings,
+    presentation_url: &str,
+    download_state: Arc<Mutex<DownloadState<ManifestDownloadInfo>>>,
+) {
+    // Paths are read as local files natively, e.g. of an image.
+    let presentation_url = &if presentation_url.contains("://") || cfg!(target_arch = "wasm32") {
+        presentation_url.to_string()
+    } else {
+        get_file_url(
+            &std::path::absolute(presentation_url)
+                .unwrap_or_else(|_| PathBuf::from(presentation_url)),
+        )
+    };
+
+    // A plain image is opened as a manifest with a single canvas for it, without a request.
+    if tilesource::is_image_url(presentation_url) {
+        *download_state.lock().unwrap() = DownloadState::Done {
+            json: get_synthetic_manifest_json(
+                presentation_url,
+                std::iter::once(presentation_url.as_str()),
+            )
+            .to_string(),
+            info: ManifestDownloadInfo {
+                url: presentation_url.to_string(),
+            },
+        };
+        return;
+    }
+
+    load_with_accept(
+        presentation_url,
+        &app_settings.proxy_prefix,
+        &app_state.http_cache,
+        &app_state.host_stats,
+        download_state,
         ManifestDownloadInfo {
             url: presentation_url.to_string(),
         },
+        Some(PRESENTATION_ACCEPT),
     );
 }
 
-/// Load presentation system to handle the status of JSON fetch.
+/// Load presentation system to handle the status of the fetch.
+///
+/// The content is converted into a manifest by the manifest sources, and a collection is loaded
+/// if it is not a manifest. The canvas of the session being restored is loaded instead of the
+/// first one, or else the canvas and the preferences the manifest was last viewed with.
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn load_presentation_system(
     mut commands: Commands,
     mut app_state: ResMut<AppState>,
@@ -72,6 +700,15 @@ pub(crate) fn load_presentation_system(
     mut redraw_request_writer: MessageWriter<'_, RequestRedraw>,
     mut messages: MessageWriter<UserNotification>,
     model_image_query: Query<Entity, With<ModelImage>>,
+    collection_query: Query<Entity, With<Collection>>,
+    mut collection_search: ResMut<CollectionSearch>,
+    mut app_settings: ResMut<AppSettings>,
+    manifest_sources: Res<ManifestSources>,
+    mut session_state: ResMut<SessionState>,
+    mut manifest_prefs: ResMut<ManifestPrefsStore>,
+    mut manifest_loaded_writer: MessageWriter<ManifestLoaded>,
+    mut manifest_reload: ResMut<ManifestReload>,
+    mut error_page: ResMut<ManifestErrorPage>,
 ) -> Result {
     let download_state = Arc::clone(&app_state.manifest_json_download_state);
     let mut download_state_mutex = download_state
@@ -79,45 +716,228 @@ pub(crate) fn load_presentation_system(
         .expect("should be able to lock the presentation download state mutex");
 
     match &(*download_state_mutex) {
+        DownloadState::Done { info, json } if manifest_reload.is_unchanged(&info.url, json) => {
+            debug!("Keep the manifest {} which is unchanged", info.url);
+
+            *download_state_mutex = DownloadState::None;
+        }
         DownloadState::Done { info, json } => {
-            match Manifest::try_from_json(json) {
+            let _span = info_span!("parse_manifest", url = %info.url).entered();
+            let reload_target = manifest_reload.finish(&info.url, json);
+
+            match manifest_sources.try_into_manifest(&info.url, json) {
                 Ok(presentation) => {
                     app_state.presentation_url = info.url.to_string();
 
-                    for (presentation_entity, _) in presentation_query {
-                        commands.entity(presentation_entity).despawn();
+                    let manifest_id = match presentation.model().get_id() {
+                        id if id.is_empty() => info.url.to_string(),
+                        id => id.into_owned(),
+                    };
+                    // Ignore the sequence which is no longer in the manifest.
+                    let prefs = manifest_prefs
+                        .open(&manifest_id)
+                        .filter(|x| x.sequence_index < presentation.model().get_sequences().len());
+
+                    if let Some(prefs) = prefs {
+                        app_settings.fit_mode = prefs.fit_mode;
+                        app_settings.data_saver = prefs.data_saver;
+                    }
+
+                    let (sequence_index, canvas_index, choice_index, open_left_panel) =
+                        match session_state.get_restoring_canvas(&info.url) {
+                            Some(x) => (
+                                x.sequence_index,
+                                x.canvas_index,
+                                x.choice_index,
+                                x.open_left_panel,
+                            ),
+                            None => prefs.map_or((0, 0, 0, true), |x| {
+                                (x.sequence_index, x.canvas_index, 0, true)
+                            }),
+                        };
+                    // Stay on the canvas shown when reloaded, by its ID in case the canvases are
+                    // reordered, or at its position otherwise.
+                    let (sequence_index, canvas_index, choice_index, open_left_panel) =
+                        match &reload_target {
+                            Some(target) => {
+                                let num_canvases = presentation
+                                    .model()
+                                    .get_sequence(app_state.sequence_index)
+                                    .map_or(0, |x| x.get_canvases().len());
+                                let (sequence_index, canvas_index) = get_canvas_index_by_id(
+                                    &presentation,
+                                    app_state.sequence_index,
+                                    &target.canvas_id,
+                                )
+                                .unwrap_or((
+                                    app_state.sequence_index,
+                                    app_state.canvas_index.min(num_canvases.saturating_sub(1)),
+                                ));
+
+                                (
+                                    sequence_index,
+                                    canvas_index,
+                                    app_state.choice_index,
+                                    egui_ui_state.open_left_panel,
+                                )
+                            }
+                            None => (sequence_index, canvas_index, choice_index, open_left_panel),
+                        };
+
+                    // Load the target canvas instead, e.g. of a bookmark or from the command line.
+                    let target_canvas_index = app_state.target_canvas_index.take();
+                    let (sequence_index, canvas_index) = match app_state.target_canvas_id.take() {
+                        Some(canvas_id) => {
+                            match get_canvas_index_by_id(&presentation, sequence_index, &canvas_id)
+                            {
+                                Some(index) => index,
+                                None => {
+                                    app_state.target_region = None;
+                                    (sequence_index, canvas_index)
+                                }
+                            }
+                        }
+                        None => (sequence_index, target_canvas_index.unwrap_or(canvas_index)),
+                    };
+
+                    // Skip the canvas which cannot be shown, e.g. without any image.
+                    let canvas_index =
+                        presentation.get_usable_canvas_index(sequence_index, canvas_index);
+
+                    if let Some(summary) = presentation.get_canvas_errors_summary() {
+                        messages.write(UserNotification::Parse {
+                            url: info.url.to_string(),
+                            pointer: None,
+                            msg: summary,
+                        });
                     }
 
-                    app_state.canvas_index = 0;
+                    // Keep the image and the view if the canvas shown is reloaded with the same
+                    // image, or else show the same region of the new one.
+                    let keep_image = reload_target.as_ref().is_some_and(|target| {
+                        !target.endpoint.is_empty()
+                            && get_image_service(
+                                &presentation,
+                                sequence_index,
+                                canvas_index,
+                                choice_index,
+                            )
+                            .is_some_and(|x| x == target.endpoint)
+                    });
+
+                    if let Some(target) = reload_target.filter(|_| !keep_image) {
+                        app_state.target_region = target.region;
+                    }
+
+                    app_state.sequence_index = sequence_index;
+                    app_state.canvas_index = canvas_index;
+                    app_state.choice_index = choice_index;
+                    if !keep_image {
+                        app_state.overlay_choice_index = None;
+                        app_state.curtain_position = None;
+                    }
                     egui_ui_state.presentation_url = app_state.presentation_url.to_string();
                     egui_ui_state.canvas_index =
                         (app_state.canvas_index.saturating_add(1)).to_string();
 
-                    match load_canvas(
-                        &mut commands,
-                        &presentation,
-                        &mut app_state,
-                        0,
-                        &model_image_query,
-                    ) {
+                    let presentation_entity = presentation_query.iter().next().map(|(x, _)| x);
+
+                    if !keep_image {
+                        for (presentation_entity, _) in presentation_query {
+                            commands.entity(presentation_entity).despawn();
+                        }
+                    }
+
+                    let loaded = if keep_image {
+                        Ok(())
+                    } else {
+                        load_canvas(
+                            &mut commands,
+                            &presentation,
+                            &mut app_state,
+                            &app_settings,
+                            sequence_index,
+                            canvas_index,
+                            choice_index,
+                            &model_image_query,
+                        )
+                    };
+
+                    match loaded {
                         Ok(_) => {
-                            commands.spawn(presentation);
-                            egui_ui_state.open_left_panel = true;
+                            manifest_loaded_writer.write(ManifestLoaded {
+                                manifest_url: app_state.presentation_url.to_string(),
+                                title: presentation
+                                    .model()
+                                    .get_title(&app_settings.language)
+                                    .to_string(),
+                            });
+
+                            // Replace the manifest in place, as removing it removes the images.
+                            match presentation_entity.filter(|_| keep_image) {
+                                Some(entity) => {
+                                    commands.entity(entity).insert(presentation);
+                                }
+                                None => {
+                                    commands.spawn(presentation);
+                                }
+                            }
+                            egui_ui_state.open_left_panel = open_left_panel;
+                            error_page.clear();
                         }
                         Err(e) => {
-                            messages.write(UserNotification(format!(
-                                "failed to load first canvas of manifest '{}'.\n{:?}",
-                                app_state.presentation_url, e
-                            )));
+                            session_state.restoring = None;
+
+                            // The manifest shown before is removed, e.g. for one without any
+                            // canvas.
+                            let error = UserNotification::Error(format!(
+                                "Failed to load canvas {} of manifest '{}'.\n{}",
+                                canvas_index + 1,
+                                app_state.presentation_url,
+                                e
+                            ));
+
+                            if let Some(error) = error_page.report(error, !keep_image) {
+                                messages.write(error);
+                            }
                         }
                     }
                 }
-                Err(e) => {
-                    messages.write(UserNotification(format!(
-                        "failed to processing manifest from '{}'.\n{:?}",
-                        info.url, e
-                    )));
-                }
+                Err(e) => match Collection::try_from_json(json, &info.url).or_else(|x| {
+                    // A web page, e.g. of a catalog, is offered as a collection of the manifests
+                    // it refers to.
+                    let manifest_urls = html::find_manifest_urls(json, &info.url);
+
+                    if manifest_urls.is_empty() {
+                        return Err(x);
+                    }
+
+                    let title = html::get_page_title(json).unwrap_or_else(|| info.url.to_string());
+
+                    Collection::try_from_json(
+                        &get_synthetic_collection_json(&info.url, &title, &manifest_urls)
+                            .to_string(),
+                        &info.url,
+                    )
+                }) {
+                    Ok(collection) => {
+                        for collection_entity in collection_query {
+                            commands.entity(collection_entity).despawn();
+                        }
+
+                        commands.spawn(collection);
+                        collection_search.clear();
+                        error_page.clear();
+                    }
+                    Err(_) => {
+                        let error = UserNotification::from_iiif_error(&info.url, json, &e);
+
+                        if let Some(error) = error_page.report(error, presentation_query.is_empty())
+                        {
+                            messages.write(error);
+                        }
+                    }
+                },
             };
 
             *download_state_mutex = DownloadState::None;
@@ -127,10 +947,10 @@ pub(crate) fn load_presentation_system(
             redraw_request_writer.write(RequestRedraw);
         }
         DownloadState::Error { url, msg } => {
-            messages.write(UserNotification(format!(
-                "failed to load manifest from '{}'.\n{}",
-                url, msg
-            )));
+            messages.write(UserNotification::Network {
+                url: url.to_string(),
+                msg: msg.to_string(),
+            });
             *download_state_mutex = DownloadState::None;
             redraw_request_writer.write(RequestRedraw);
         }
@@ -140,20 +960,146 @@ pub(crate) fn load_presentation_system(
     Ok(())
 }
 
+/// Get the image service of the image choice of the canvas, e.g. to compare it with the one shown.
+fn get_image_service(
+    presentation: &Manifest,
+    sequence_index: usize,
+    canvas_index: usize,
+    choice_index: usize,
+) -> Option<String> {
+    let canvas = presentation
+        .model()
+        .get_sequence(sequence_index)
+        .ok()?
+        .get_canvas(canvas_index)
+        .map(get_displayed_canvas)
+        .ok()?;
+
+    canvas
+        .get_image_choices(0)
+        .ok()?
+        .nth(choice_index)
+        .map(|x| x.get_service().to_string())
+}
+
+/// Get the indices of the sequence and the canvas with the ID, looking in the preferred sequence
+/// first as the same canvas can be in more than one sequence.
+pub(crate) fn get_canvas_index_by_id(
+    presentation: &Manifest,
+    sequence_index: usize,
+    canvas_id: &str,
+) -> Option<(usize, usize)> {
+    let model = presentation.model();
+    let num_sequences = model.get_sequences().len();
+
+    std::iter::once(sequence_index)
+        .chain((0..num_sequences).filter(|x| *x != sequence_index))
+        .find_map(|index| {
+            model
+                .get_sequence(index)
+                .ok()?
+                .get_canvases()
+                .position(|x| x.get_id() == canvas_id)
+                .map(|canvas_index| (index, canvas_index))
+        })
+}
+
+/// Get the canvas to display for the canvas, which is its placeholder canvas if the content is
+/// time-based media or missing, e.g. the poster image of a video.
+pub(crate) fn get_displayed_canvas(canvas: &dyn IsCanvas) -> &dyn IsCanvas {
+    let is_time_based = match canvas.get_image(0) {
+        Ok(image) => matches!(image.get_type().as_ref(), "Sound" | "Video"),
+        Err(_) => true,
+    };
+
+    match canvas.get_placeholder_canvas() {
+        Some(placeholder) if is_time_based => placeholder,
+        _ => canvas,
+    }
+}
+
+/// Get the size of the canvas displayed at the indices, e.g. to draw its extent.
+pub(crate) fn get_canvas_size(
+    presentation: &Manifest,
+    sequence_index: usize,
+    canvas_index: usize,
+) -> Option<Size> {
+    let sequence = presentation.model().get_sequence(sequence_index).ok()?;
+    let (width, height) =
+        get_displayed_canvas(sequence.get_canvas(canvas_index).ok()?).get_size()?;
+
+    Some(Size::new(width, height))
+}
+
+/// Get the region of the canvas displayed at the indices which its image is painted on, by the
+/// target of the image or else the whole canvas. `None` if the canvas has no size.
+pub(crate) fn get_image_canvas_region(
+    presentation: &Manifest,
+    sequence_index: usize,
+    canvas_index: usize,
+) -> Option<Rect> {
+    let sequence = presentation.model().get_sequence(sequence_index).ok()?;
+    let canvas = get_displayed_canvas(sequence.get_canvas(canvas_index).ok()?);
+    let (width, height) = canvas.get_size()?;
+
+    Some(
+        canvas
+            .get_image_target_fragment(0)
+            .and_then(|x| parse_xywh(&x))
+            .filter(|x| x.width() > 0.0 && x.height() > 0.0)
+            .unwrap_or_else(|| Rect::new(0.0, 0.0, width as f32, height as f32)),
+    )
+}
+
+/// Set up the image of the canvas of the info by the settings, the same whether it is shown or
+/// prefetched, so that the tiles of the prefetched image are found when it is shown.
+pub(crate) fn setup_canvas_image(
+    image: &mut TiledImage,
+    info: &ImageDownloadInfo,
+    presentation: &Manifest,
+    app_state: &AppState,
+    app_settings: &AppSettings,
+) {
+    image.set_proxy_prefix(&app_settings.proxy_prefix);
+    image.set_orientation(info.orientation);
+    image.set_tile_overlap(app_settings.tile_overlap);
+    image.set_world_origin(app_state.world_origin);
+    image.set_data_saver(app_settings.data_saver);
+    image.set_canvas_region(get_image_canvas_region(
+        presentation,
+        info.sequence_index,
+        info.canvas_index,
+    ));
+}
+
 /// Begin loading the IIIF image from remote URL.
+///
+/// The camera view is preserved when switching to another choice of the current canvas.
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn load_canvas(
     commands: &mut Commands,
     presentation: &Manifest,
     app_state: &mut ResMut<AppState>,
+    app_settings: &AppSettings,
+    sequence_index: usize,
     canvas_index: usize,
+    choice_index: usize,
     model_image_query: &Query<Entity, With<ModelImage>>,
 ) -> Result {
     let canvas = presentation
         .model()
-        .get_sequence(0)?
-        .get_canvas(canvas_index)?;
+        .get_sequence(sequence_index)?
+        .get_canvas(canvas_index)
+        .map(get_displayed_canvas)?;
 
-    let image = canvas.get_image(0)?;
+    let image =
+        canvas
+            .get_image_choices(0)?
+            .nth(choice_index)
+            .ok_or(IiifError::IiifMissingInfo(format!(
+                "image choice not found at pos '{}'",
+                choice_index
+            )))?;
 
     if image.get_type() == "Model" {
         for image_entity in model_image_query {
@@ -161,16 +1107,27 @@ pub(crate) fn load_canvas(
         }
 
         commands.spawn(ModelImage::new(&image.get_id()));
+
+        // No image info to wait for, so that the page controls follow the model at once.
+        app_state.sequence_index = sequence_index;
+        app_state.canvas_index = canvas_index;
+        app_state.choice_index = choice_index;
     } else {
         let iiif_endpoint = &image.get_service();
-        let image_url = TiledImage::get_image_info_url(iiif_endpoint);
 
-        load(
-            &image_url,
+        load_image_info(
+            app_state,
+            &app_settings.proxy_prefix,
             Arc::clone(&app_state.image_json_download_state),
             ImageDownloadInfo {
                 iiif_endpoint: iiif_endpoint.to_string(),
+                sequence_index,
                 canvas_index,
+                choice_index,
+                preserve_view: sequence_index == app_state.sequence_index
+                    && canvas_index == app_state.canvas_index
+                    && choice_index != app_state.choice_index,
+                orientation: canvas.get_image_orientation(0, choice_index),
             },
         );
     }
@@ -181,36 +1138,289 @@ pub(crate) fn load_canvas(
 /// Load image system to handle the status of JSON fetch.
 #[allow(clippy::too_many_arguments)]
 pub(crate) fn load_canvas_system(
-    _presentation: Single<&Manifest>,
+    presentation: Single<&Manifest>,
     mut commands: Commands,
     tiled_image_query: Query<Entity, With<TiledImage>>,
+    overlay_image_query: Query<Entity, With<OverlayImage>>,
     mut app_state: ResMut<AppState>,
     mut egui_ui_state: ResMut<EguiUiState>,
     mut redraw_request_writer: MessageWriter<'_, RequestRedraw>,
     mut messages: MessageWriter<UserNotification>,
+    app_settings: Res<AppSettings>,
+    gpu_capabilities: Option<Res<GpuCapabilities>>,
+    mut canvas_loaded_writer: MessageWriter<CanvasLoaded>,
 ) -> Result {
     let download_state = Arc::clone(&app_state.image_json_download_state);
     let mut download_state_mutex = download_state.lock().expect("msg");
 
-    match &(*download_state_mutex) {
+    let loaded_image = match &(*download_state_mutex) {
         DownloadState::Done { json, info } => {
-            match TiledImage::try_from_json(json, &info.iiif_endpoint) {
-                Ok(image) => {
-                    app_state.canvas_index = info.canvas_index;
+            let _span = info_span!("parse_image_info", endpoint = %info.iiif_endpoint).entered();
 
-                    for image_entity in tiled_image_query {
-                        commands.entity(image_entity).despawn();
-                    }
-                    commands.spawn(image);
+            match TiledImage::try_from_info(json, &info.iiif_endpoint) {
+                // The textures of the tiles would fail to be created.
+                Ok(image)
+                    if gpu_capabilities
+                        .as_ref()
+                        .is_some_and(|x| !image.fits_texture_size(x.max_texture_size)) =>
+                {
+                    messages.write(UserNotification::Unsupported {
+                        url: TiledImage::get_image_info_url(&info.iiif_endpoint),
+                        msg: format!(
+                            "The image tiles are larger than the {} px textures supported by the \
+                            graphics.",
+                            gpu_capabilities.as_ref().map_or(0, |x| x.max_texture_size)
+                        ),
+                    });
+                    None
+                }
+                Ok(image) => {
+                    let url = TiledImage::get_image_info_url(&info.iiif_endpoint);
 
-                    egui_ui_state.canvas_index =
-                        (app_state.canvas_index.saturating_add(1)).to_string();
+                    app_state.capability_cache.store_info(
+                        &url,
+                        json,
+                        app_state.http_cache.get_validators(&url),
+                    );
+                    Some((image, info.clone()))
                 }
                 Err(e) => {
-                    messages.write(UserNotification(format!(
-                        "failed to parse image JSON from '{}'.\n{:?}",
-                        info.iiif_endpoint, e
-                    )));
+                    let url = TiledImage::get_image_info_url(&info.iiif_endpoint);
+
+                    app_state.capability_cache.remove_info(&url);
+                    messages.write(UserNotification::from_iiif_error(&url, json, &e));
+                    get_fallback_image(&presentation, app_state.sequence_index, &url)
+                }
+            }
+        }
+        DownloadState::InProgress { .. } => {
+            redraw_request_writer.write(RequestRedraw);
+            return Ok(());
+        }
+        DownloadState::Error { url, msg } => {
+            if is_auth_error(msg) {
+                app_state.capability_cache.set_auth_required(url);
+            }
+
+            messages.write(UserNotification::Network {
+                url: url.to_string(),
+                msg: msg.to_string(),
+            });
+            get_fallback_image(&presentation, app_state.sequence_index, url)
+        }
+        DownloadState::None => return Ok(()),
+    };
+
+    *download_state_mutex = DownloadState::None;
+    redraw_request_writer.write(RequestRedraw);
+
+    let Some((mut image, info)) = loaded_image else {
+        return Ok(());
+    };
+
+    setup_canvas_image(&mut image, &info, &presentation, &app_state, &app_settings);
+
+    // The overlay belongs to the previous canvas.
+    if info.sequence_index != app_state.sequence_index
+        || info.canvas_index != app_state.canvas_index
+    {
+        for overlay_entity in overlay_image_query {
+            commands.entity(overlay_entity).despawn();
+        }
+        app_state.overlay_choice_index = None;
+        app_state.curtain_position = None;
+        *app_state
+            .overlay_json_download_state
+            .lock()
+            .expect("should be able to lock the overlay download state mutex") =
+            DownloadState::None;
+    }
+
+    app_state.sequence_index = info.sequence_index;
+    app_state.canvas_index = info.canvas_index;
+    app_state.choice_index = info.choice_index;
+    app_state.preserve_view = info.preserve_view;
+
+    for image_entity in tiled_image_query {
+        commands.entity(image_entity).despawn();
+    }
+    canvas_loaded_writer.write(CanvasLoaded {
+        manifest_url: app_state.presentation_url.to_string(),
+        canvas_index: app_state.canvas_index,
+        image_size: image.get_image_max_size_rect().size(),
+    });
+    commands.spawn(image);
+
+    egui_ui_state.canvas_index = (app_state.canvas_index.saturating_add(1)).to_string();
+    egui_ui_state.announcement = get_canvas_announcement(
+        &presentation,
+        app_state.sequence_index,
+        app_state.canvas_index,
+        &app_settings.language,
+    );
+
+    Ok(())
+}
+
+/// Get the image to show instead when the image info at the URL fails, e.g. blocked by CORS or
+/// by a server error, with the canvas and the image choice of the image service.
+///
+/// It is the thumbnail of the canvas, or the image of the annotation otherwise, stretched to the
+/// size of the canvas. `None` if neither is known or the size of the canvas is not given.
+fn get_fallback_image(
+    presentation: &Manifest,
+    sequence_index: usize,
+    info_url: &str,
+) -> Option<(TiledImage, ImageDownloadInfo)> {
+    let model = presentation.model();
+    let num_sequences = model.get_sequences().len();
+
+    std::iter::once(sequence_index)
+        .chain((0..num_sequences).filter(|x| *x != sequence_index))
+        .find_map(|sequence_index| {
+            model
+                .get_sequence(sequence_index)
+                .ok()?
+                .get_canvases()
+                .enumerate()
+                .find_map(|(canvas_index, canvas)| {
+                    let canvas = get_displayed_canvas(canvas);
+                    let (choice_index, image) = canvas
+                        .get_image_choices(0)
+                        .ok()?
+                        .enumerate()
+                        .find(|(_, x)| {
+                            TiledImage::get_image_info_url(&x.get_service()) == info_url
+                        })?;
+                    let (width, height) = canvas.get_size()?;
+                    let service = image.get_service();
+                    let thumbnail = canvas.get_thumbnail();
+
+                    // The thumbnail from the image service would fail as well.
+                    let (url, orientation) =
+                        if !thumbnail.is_empty() && !thumbnail.starts_with(service.as_ref()) {
+                            (thumbnail.to_string(), ImageOrientation::default())
+                        } else if !image.get_id().is_empty() {
+                            (
+                                image.get_id().to_string(),
+                                canvas.get_image_orientation(0, choice_index),
+                            )
+                        } else {
+                            return None;
+                        };
+
+                    info!("Show {} in place of the image service {}", url, service);
+
+                    Some((
+                        TiledImage::from_fallback_image(&url, Size::new(width, height)),
+                        ImageDownloadInfo {
+                            iiif_endpoint: url,
+                            sequence_index,
+                            canvas_index,
+                            choice_index,
+                            preserve_view: false,
+                            orientation,
+                        },
+                    ))
+                })
+        })
+}
+
+/// Begin loading the image choice of the current canvas to draw over the current image.
+pub(crate) fn load_overlay(
+    presentation: &Manifest,
+    app_state: &mut ResMut<AppState>,
+    app_settings: &AppSettings,
+    choice_index: usize,
+) -> Result {
+    let sequence_index = app_state.sequence_index;
+    let canvas_index = app_state.canvas_index;
+    let canvas = presentation
+        .model()
+        .get_sequence(sequence_index)?
+        .get_canvas(canvas_index)
+        .map(get_displayed_canvas)?;
+
+    let image =
+        canvas
+            .get_image_choices(0)?
+            .nth(choice_index)
+            .ok_or(IiifError::IiifMissingInfo(format!(
+                "image choice not found at pos '{}'",
+                choice_index
+            )))?;
+
+    if image.get_type() == "Model" {
+        return Err(IiifError::IiifMissingInfo(format!(
+            "image choice at pos '{}' is a model and cannot be an overlay",
+            choice_index
+        ))
+        .into());
+    }
+
+    let iiif_endpoint = &image.get_service();
+
+    load_image_info(
+        app_state,
+        &app_settings.proxy_prefix,
+        Arc::clone(&app_state.overlay_json_download_state),
+        ImageDownloadInfo {
+            iiif_endpoint: iiif_endpoint.to_string(),
+            sequence_index,
+            canvas_index,
+            choice_index,
+            preserve_view: false,
+            orientation: canvas.get_image_orientation(0, choice_index),
+        },
+    );
+
+    Ok(())
+}
+
+/// Load overlay image system to handle the status of JSON fetch.
+pub(crate) fn load_overlay_system(
+    mut commands: Commands,
+    overlay_image_query: Query<Entity, With<OverlayImage>>,
+    mut app_state: ResMut<AppState>,
+    mut tile_mod_state: ResMut<TileModState>,
+    mut redraw_request_writer: MessageWriter<'_, RequestRedraw>,
+    mut messages: MessageWriter<UserNotification>,
+    app_settings: Res<AppSettings>,
+) -> Result {
+    let download_state = Arc::clone(&app_state.overlay_json_download_state);
+    let mut download_state_mutex = download_state
+        .lock()
+        .expect("should be able to lock the overlay download state mutex");
+
+    match &(*download_state_mutex) {
+        DownloadState::Done { json, info } => {
+            // Skip if the canvas has changed since the request.
+            if info.canvas_index == app_state.canvas_index {
+                match TiledImage::try_from_info(json, &info.iiif_endpoint) {
+                    Ok(mut image) => {
+                        image.set_proxy_prefix(&app_settings.proxy_prefix);
+                        image.set_orientation(info.orientation);
+                        image.set_tile_overlap(app_settings.tile_overlap);
+                        image.set_world_origin(app_state.world_origin);
+                        image.set_data_saver(app_settings.data_saver);
+                        app_state
+                            .capability_cache
+                            .store_info(&TiledImage::get_image_info_url(&info.iiif_endpoint), json);
+                        app_state.overlay_choice_index = Some(info.choice_index);
+
+                        for overlay_entity in overlay_image_query {
+                            commands.entity(overlay_entity).despawn();
+                        }
+                        commands.spawn(OverlayImage::new(image, app_state.world_image_max_size.x));
+
+                        tile_mod_state.invalidate();
+                    }
+                    Err(e) => {
+                        let url = TiledImage::get_image_info_url(&info.iiif_endpoint);
+
+                        app_state.capability_cache.remove_info(&url);
+                        messages.write(UserNotification::from_iiif_error(&url, json, &e));
+                    }
                 }
             }
 
@@ -221,10 +1431,14 @@ pub(crate) fn load_canvas_system(
             redraw_request_writer.write(RequestRedraw);
         }
         DownloadState::Error { url, msg } => {
-            messages.write(UserNotification(format!(
-                "failed to load image from '{}'.\n{}",
-                url, msg
-            )));
+            if is_auth_error(msg) {
+                app_state.capability_cache.set_auth_required(url);
+            }
+
+            messages.write(UserNotification::Network {
+                url: url.to_string(),
+                msg: msg.to_string(),
+            });
             *download_state_mutex = DownloadState::None;
             redraw_request_writer.write(RequestRedraw);
         }
@@ -233,3 +1447,179 @@ pub(crate) fn load_canvas_system(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_proxy_url() {
+        let url = "https://example.org/iiif/a b/info.json";
+
+        assert_eq!(get_proxy_url("", url), url);
+        assert_eq!(
+            get_proxy_url("https://proxy.example.org/", url),
+            "https://proxy.example.org/https://example.org/iiif/a b/info.json"
+        );
+        assert_eq!(
+            get_proxy_url("https://proxy.example.org/?url=", url),
+            "https://proxy.example.org/?url=https%3A%2F%2Fexample.org%2Fiiif%2Fa%20b%2Finfo.json"
+        );
+        assert_eq!(
+            get_proxy_url(
+                "https://proxy.example.org/",
+                "https://proxy.example.org/https://example.org/"
+            ),
+            "https://proxy.example.org/https://example.org/"
+        );
+    }
+
+    #[test]
+    fn test_file_url() {
+        let path = std::env::temp_dir().join("a b%#.tif");
+        let url = get_file_url(&path);
+
+        assert!(url.starts_with("file:///"));
+        assert!(url.ends_with("/a%20b%25%23.tif"));
+        assert_eq!(get_file_path(&url), Some(path));
+        assert_eq!(
+            get_file_path("file://localhost/scans/p%201.tif"),
+            Some(PathBuf::from("/scans/p 1.tif"))
+        );
+        assert_eq!(get_file_path("file://server/scans/p1.tif"), None);
+        assert_eq!(get_file_path("https://example.org/p1.tif"), None);
+
+        #[cfg(target_os = "windows")]
+        assert_eq!(
+            get_file_url(Path::new("C:\\scans\\p 1.tif")),
+            "file:///C:/scans/p%201.tif"
+        );
+        #[cfg(target_os = "windows")]
+        assert_eq!(
+            get_file_path("file:///C:/scans/p%201.tif"),
+            Some(PathBuf::from("C:/scans/p 1.tif"))
+        );
+    }
+
+    #[test]
+    fn test_resolve_relative_urls() {
+        let json = r#"{ "id": ".", "service": [{ "id": "./canvas-1", "profile": "level0" }] }"#;
+        let resolved: serde_json::Value = serde_json::from_str(&resolve_relative_urls(
+            json,
+            "file:///exports/book/manifest.json",
+        ))
+        .unwrap();
+
+        assert_eq!(resolved["id"], "file:///exports/book");
+        assert_eq!(
+            resolved["service"][0]["id"],
+            "file:///exports/book/canvas-1"
+        );
+        assert_eq!(resolved["service"][0]["profile"], "level0");
+        assert_eq!(
+            resolve_relative_urls("not json", "file:///a.json"),
+            "not json"
+        );
+    }
+
+    #[test]
+    fn test_get_presentation_3_alternate() {
+        let headers = ehttp::Headers::new(&[(
+            "Link",
+            r#"<https://example.org/v3/manifest>; rel="alternate"; type="application/ld+json;profile=\"http://iiif.io/api/presentation/3/context.json\"", <https://example.org/about>; rel="describedby""#,
+        )]);
+        let json_v2 = r#"{ "@context": "http://iiif.io/api/presentation/2/context.json" }"#;
+        let json_v3 = r#"{ "@context": ["http://iiif.io/api/presentation/3/context.json"] }"#;
+
+        assert_eq!(
+            get_presentation_3_alternate(&headers, json_v2),
+            Some("https://example.org/v3/manifest".to_string())
+        );
+        assert_eq!(get_presentation_3_alternate(&headers, json_v3), None);
+        assert_eq!(
+            get_presentation_3_alternate(&ehttp::Headers::default(), json_v2),
+            None
+        );
+    }
+
+    #[test]
+    fn test_get_page_json_ld_alternate() {
+        let headers = ehttp::Headers::new(&[(
+            "Link",
+            r#"<https://example.org/style.css>; rel="stylesheet", <https://example.org/iiif/book/manifest>; rel="alternate"; type="application/ld+json""#,
+        )]);
+        let page = "<!DOCTYPE html><html><head><title>Book</title></head></html>";
+
+        assert_eq!(
+            get_page_json_ld_alternate(&headers, page),
+            Some("https://example.org/iiif/book/manifest".to_string())
+        );
+        assert_eq!(get_page_json_ld_alternate(&headers, "{}"), None);
+        assert_eq!(
+            get_page_json_ld_alternate(&ehttp::Headers::default(), page),
+            None
+        );
+    }
+
+    #[test]
+    fn test_get_canvas_index_by_id() {
+        let json = r#"{
+          "@context":"http://iiif.io/api/presentation/2/context.json",
+          "@type":"sc:Manifest",
+          "@id":"https://example.org/manifest",
+          "label":"Book",
+          "sequences": [
+            {"@type":"sc:Sequence", "canvases": [
+              {"@id":"https://example.org/c1", "@type":"sc:Canvas", "label":"1", "images":[
+                {"resource": {
+                  "@id":"https://example.org/images/c1/full/full/0/default.jpg",
+                  "@type":"dctypes:Image",
+                  "service": {"@id":"https://example.org/images/c1", "profile":"level1"}
+                }}
+              ]},
+              {"@id":"https://example.org/c2", "@type":"sc:Canvas", "label":"2", "images":[
+                {"resource": {
+                  "@id":"https://example.org/images/c2/full/full/0/default.jpg",
+                  "@type":"dctypes:Image",
+                  "service": {"@id":"https://example.org/images/c2", "profile":"level1"}
+                }}
+              ]}
+            ]},
+            {"@type":"sc:Sequence", "canvases": [
+              {"@id":"https://example.org/c2", "@type":"sc:Canvas", "label":"2", "images":[
+                {"resource": {
+                  "@id":"https://example.org/images/c2/full/full/0/default.jpg",
+                  "@type":"dctypes:Image",
+                  "service": {"@id":"https://example.org/images/c2", "profile":"level1"}
+                }}
+              ]},
+              {"@id":"https://example.org/c3", "@type":"sc:Canvas", "label":"3", "images":[
+                {"resource": {
+                  "@id":"https://example.org/images/c3/full/full/0/default.jpg",
+                  "@type":"dctypes:Image",
+                  "service": {"@id":"https://example.org/images/c3", "profile":"level1"}
+                }}
+              ]}
+            ]}
+          ]
+        }"#;
+        let presentation = Manifest::try_from_json(json).expect("should parse the manifest");
+
+        assert_eq!(
+            get_canvas_index_by_id(&presentation, 0, "https://example.org/c2"),
+            Some((0, 1))
+        );
+        assert_eq!(
+            get_canvas_index_by_id(&presentation, 1, "https://example.org/c2"),
+            Some((1, 0))
+        );
+        assert_eq!(
+            get_canvas_index_by_id(&presentation, 0, "https://example.org/c3"),
+            Some((1, 1))
+        );
+        assert_eq!(
+            get_canvas_index_by_id(&presentation, 0, "https://example.org/c4"),
+            None
+        );
+    }
+}
@@ -0,0 +1,573 @@
+use crate::{
+    OpenWindow,
+    app::{
+        app_settings::AppSettings,
+        app_state::{AppState, DownloadState, ImageDownloadInfo, ManifestDownloadInfo},
+    },
+    camera::{camera_ext, main_camera::MainCamera2d, relative_view::RelativeView},
+    input::mouse::{WHEEL_STEP_ZOOM, get_wheel_steps},
+    notification::UserNotification,
+    presentation::{manifest::Manifest, source::ManifestSources},
+    rendering::{
+        tile::{TileCache, TileIndex, TileModState},
+        tiled_image::TiledImage,
+    },
+    web, window_title,
+};
+use bevy::{
+    camera::{RenderTarget, visibility::RenderLayers},
+    input::mouse::MouseWheel,
+    prelude::{
+        AssetServer, Assets, ButtonInput, Camera, Camera2d, Changed, ColorMaterial, Commands,
+        Component, Entity, GlobalTransform, Mesh, Mesh2d, MeshMaterial2d, MessageReader,
+        MessageWriter, MouseButton, On, Or, Projection, Query, Rect, Rectangle, Remove, Res,
+        ResMut, Resource, Single, Transform, Vec2, Window, With, Without, default, info, warn,
+    },
+    window::{CursorMoved, RequestRedraw, WindowRef},
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+/// Render layer of the tiles of the first window opened, the next windows taking the next
+/// layers so that each camera only draws the tiles of its window.
+const FIRST_WINDOW_RENDER_LAYER: usize = 4;
+
+/// Windows opened so far, to give each one its own render layer.
+#[derive(Resource, Default)]
+pub(crate) struct ViewerWindows {
+    num_opened: usize,
+}
+
+/// Another window of the app showing a manifest with its own camera and tiles, optionally
+/// following the camera of the main window.
+#[derive(Component)]
+pub(crate) struct ViewerWindow {
+    /// Follow the camera of the main window, which follows this one in turn.
+    follow: bool,
+    camera: Entity,
+    render_layers: RenderLayers,
+    manifest_download_state: Arc<Mutex<DownloadState<ManifestDownloadInfo>>>,
+    image_download_state: Arc<Mutex<DownloadState<ImageDownloadInfo>>>,
+    presentation: Option<Manifest>,
+    /// Image of the first canvas of the manifest, in the world space of the window from the
+    /// origin.
+    image: Option<TiledImage>,
+    /// Tiles of the image spawned for the window, with their rects in world space.
+    tiles: HashMap<TileIndex, (Entity, Rect)>,
+}
+
+/// Camera drawing the tiles of a viewer window into the window.
+#[derive(Component)]
+pub(crate) struct ViewerWindowCamera;
+
+/// Get the translation of the camera zooming by the factor about the offset of the cursor from
+/// the centre of the viewport, so that the point under the cursor stays in place.
+fn get_zoomed_translation(translation: Vec2, scale: f32, cursor_offset: Vec2, zoom: f32) -> Vec2 {
+    // The cursor is down from the top, while the world is up.
+    let offset = Vec2::new(cursor_offset.x, -cursor_offset.y);
+
+    translation + offset * scale * (1.0 - zoom)
+}
+
+/// Apply the view of the main camera to the camera of the window, relative to the image sizes.
+fn follow_main_view(
+    view: RelativeView,
+    image: &TiledImage,
+    transform: &mut Transform,
+    projection: &mut Projection,
+) {
+    view.apply(
+        transform,
+        projection,
+        image.get_world_max_size_rect().size(),
+        Vec2::ZERO,
+    );
+}
+
+/// Open the manifest in another window with its own camera, and start to load it.
+pub(crate) fn open_window_system(
+    mut commands: Commands,
+    mut open_window_reader: MessageReader<OpenWindow>,
+    mut viewer_windows: ResMut<ViewerWindows>,
+    app_state: Res<AppState>,
+    app_settings: Res<AppSettings>,
+) {
+    for OpenWindow {
+        manifest_url,
+        follow,
+    } in open_window_reader.read()
+    {
+        let render_layers =
+            RenderLayers::layer(FIRST_WINDOW_RENDER_LAYER + viewer_windows.num_opened);
+        let window = commands
+            .spawn(Window {
+                title: window_title::get_window_title(manifest_url, ""),
+                ..default()
+            })
+            .id();
+        let camera = commands
+            .spawn((
+                Camera2d,
+                Camera {
+                    target: RenderTarget::Window(WindowRef::Entity(window)),
+                    ..default()
+                },
+                render_layers.clone(),
+                ViewerWindowCamera,
+            ))
+            .id();
+        let manifest_download_state = Arc::new(Mutex::new(DownloadState::None));
+
+        info!("Open {} in another window {:?}", manifest_url, window);
+        viewer_windows.num_opened += 1;
+
+        web::load_presentation_to(
+            &app_state,
+            &app_settings,
+            manifest_url,
+            Arc::clone(&manifest_download_state),
+        );
+
+        commands.entity(window).insert(ViewerWindow {
+            follow: *follow,
+            camera,
+            render_layers,
+            manifest_download_state,
+            image_download_state: Arc::new(Mutex::new(DownloadState::None)),
+            presentation: None,
+            image: None,
+            tiles: HashMap::new(),
+        });
+    }
+}
+
+/// Load the manifest of each window and then the image of its first canvas, fitting the camera
+/// of the window to it or following the main camera.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn load_window_system(
+    mut windows: Query<(&mut Window, &mut ViewerWindow)>,
+    mut cameras: Query<(&Camera, &mut Transform, &mut Projection), With<ViewerWindowCamera>>,
+    main_camera: Single<
+        (&Transform, &Projection),
+        (With<MainCamera2d>, Without<ViewerWindowCamera>),
+    >,
+    manifest_sources: Res<ManifestSources>,
+    app_state: Res<AppState>,
+    app_settings: Res<AppSettings>,
+    mut messages: MessageWriter<UserNotification>,
+    mut redraw_request_writer: MessageWriter<RequestRedraw>,
+) {
+    let (main_transform, main_projection) = main_camera.into_inner();
+
+    for (mut window, mut viewer_window) in &mut windows {
+        let download_state = Arc::clone(&viewer_window.manifest_download_state);
+        let mut download_state = download_state
+            .lock()
+            .expect("should be able to lock the window manifest download state mutex");
+
+        match &*download_state {
+            DownloadState::Done { json, info } => {
+                match manifest_sources.try_into_manifest(&info.url, json) {
+                    Ok(presentation) => {
+                        let canvas_index = presentation.get_usable_canvas_index(0, 0);
+                        let canvas = presentation
+                            .model()
+                            .get_sequence(0)
+                            .and_then(|x| x.get_canvas(canvas_index))
+                            .map(web::get_displayed_canvas);
+
+                        match canvas.and_then(|canvas| Ok((canvas, canvas.get_image(0)?))) {
+                            Ok((_, image)) if image.get_type() == "Model" => {
+                                messages.write(UserNotification::Error(format!(
+                                    "3D models of '{}' are only shown in the main window.",
+                                    info.url
+                                )));
+                            }
+                            Ok((canvas, image)) => {
+                                web::load_image_info(
+                                    &app_state,
+                                    &app_settings.proxy_prefix,
+                                    Arc::clone(&viewer_window.image_download_state),
+                                    ImageDownloadInfo {
+                                        iiif_endpoint: image.get_service().to_string(),
+                                        sequence_index: 0,
+                                        canvas_index,
+                                        choice_index: 0,
+                                        preserve_view: false,
+                                        orientation: canvas.get_image_orientation(0, 0),
+                                    },
+                                );
+                            }
+                            Err(e) => {
+                                messages.write(UserNotification::Error(format!(
+                                    "Failed to show '{}' in another window.\n{}",
+                                    info.url, e
+                                )));
+                            }
+                        }
+
+                        window.title = window_title::get_window_title(
+                            &presentation.model().get_title(&app_settings.language),
+                            "",
+                        );
+                        viewer_window.presentation = Some(presentation);
+                    }
+                    Err(e) => {
+                        messages.write(UserNotification::Error(format!(
+                            "Failed to show '{}' in another window.\n{}",
+                            info.url, e
+                        )));
+                    }
+                }
+                *download_state = DownloadState::None;
+            }
+            DownloadState::Error { url, msg } => {
+                messages.write(UserNotification::Error(format!(
+                    "Failed to load '{}' in another window.\n{}",
+                    url, msg
+                )));
+                *download_state = DownloadState::None;
+            }
+            DownloadState::InProgress { .. } => {
+                redraw_request_writer.write(RequestRedraw);
+            }
+            DownloadState::None => {}
+        }
+
+        drop(download_state);
+
+        let download_state = Arc::clone(&viewer_window.image_download_state);
+        let mut download_state = download_state
+            .lock()
+            .expect("should be able to lock the window image download state mutex");
+
+        match &*download_state {
+            DownloadState::Done { json, info } => {
+                match TiledImage::try_from_info(json, &info.iiif_endpoint) {
+                    Ok(mut image) => {
+                        if let Some(presentation) = &viewer_window.presentation {
+                            web::setup_canvas_image(
+                                &mut image,
+                                info,
+                                presentation,
+                                &app_state,
+                                &app_settings,
+                            );
+                        }
+                        // The window has its own world space, not recentred with the main one.
+                        image.set_world_origin(Vec2::ZERO);
+
+                        if let Ok((camera, mut transform, mut projection)) =
+                            cameras.get_mut(viewer_window.camera)
+                        {
+                            let main_view = RelativeView::from_camera(
+                                main_transform,
+                                main_projection,
+                                app_state.world_image_max_size,
+                                app_state.world_origin,
+                            );
+
+                            match main_view.filter(|_| viewer_window.follow) {
+                                Some(view) => {
+                                    follow_main_view(view, &image, &mut transform, &mut projection)
+                                }
+                                None => {
+                                    if let Projection::Orthographic(orthographic) =
+                                        projection.as_mut()
+                                    {
+                                        image.fit_camera(
+                                            app_settings.fit_mode,
+                                            camera
+                                                .logical_viewport_size()
+                                                .unwrap_or_else(|| window.size()),
+                                            window.scale_factor(),
+                                            &mut transform,
+                                            orthographic,
+                                        );
+                                    }
+                                }
+                            }
+                        }
+
+                        viewer_window.image = Some(image);
+                        redraw_request_writer.write(RequestRedraw);
+                    }
+                    Err(e) => {
+                        messages.write(UserNotification::Error(format!(
+                            "Failed to show '{}' in another window.\n{}",
+                            info.iiif_endpoint, e
+                        )));
+                    }
+                }
+                *download_state = DownloadState::None;
+            }
+            DownloadState::Error { url, msg } => {
+                messages.write(UserNotification::Error(format!(
+                    "Failed to load '{}' in another window.\n{}",
+                    url, msg
+                )));
+                *download_state = DownloadState::None;
+            }
+            DownloadState::InProgress { .. } => {
+                redraw_request_writer.write(RequestRedraw);
+            }
+            DownloadState::None => {}
+        }
+    }
+}
+
+/// Pan the camera of the window by dragging in it and zoom it by the wheel, and move the main
+/// camera with it if the window follows the main one.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn window_input_system(
+    windows: Query<(&Window, &ViewerWindow)>,
+    mut cameras: Query<
+        (&mut Transform, &mut Projection),
+        (With<ViewerWindowCamera>, Without<MainCamera2d>),
+    >,
+    main_camera: Single<
+        (&mut Transform, &mut Projection),
+        (With<MainCamera2d>, Without<ViewerWindowCamera>),
+    >,
+    tiled_image: Option<Single<&TiledImage>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut evr_motion: MessageReader<CursorMoved>,
+    mut evr_scroll: MessageReader<MouseWheel>,
+    app_settings: Res<AppSettings>,
+    mut app_state: ResMut<AppState>,
+    mut tile_mod_state: ResMut<TileModState>,
+    mut redraw_request_writer: MessageWriter<RequestRedraw>,
+) {
+    let motions = evr_motion
+        .read()
+        .filter(|_| mouse.pressed(MouseButton::Left))
+        .map(|ev| (ev.window, ev.delta.unwrap_or_default(), 1.0));
+    let scrolls = evr_scroll.read().map(|ev| {
+        let steps = get_wheel_steps(ev.unit, ev.y, &app_settings.wheel_settings);
+
+        (
+            ev.window,
+            Vec2::ZERO,
+            (1.0 - steps * WHEEL_STEP_ZOOM).clamp(0.5, 2.0),
+        )
+    });
+    let (mut main_transform, mut main_projection) = main_camera.into_inner();
+
+    for (window_entity, delta_move, zoom) in motions.chain(scrolls).collect::<Vec<_>>() {
+        let Ok((window, viewer_window)) = windows.get(window_entity) else {
+            continue;
+        };
+        let Some(image) = &viewer_window.image else {
+            continue;
+        };
+        let Ok((mut transform, mut projection)) = cameras.get_mut(viewer_window.camera) else {
+            continue;
+        };
+        let Projection::Orthographic(orthographic) = projection.as_mut() else {
+            continue;
+        };
+
+        let scale = orthographic.scale;
+        let cursor_offset = window
+            .cursor_position()
+            .map_or(Vec2::ZERO, |x| x - window.size() / 2.0);
+        let translation =
+            get_zoomed_translation(transform.translation.truncate(), scale, cursor_offset, zoom)
+                + Vec2::new(-delta_move.x, delta_move.y) * scale;
+
+        orthographic.scale = (scale * zoom).max(app_settings.min_camera_zoom_scale);
+        transform.translation = translation.extend(transform.translation.z);
+
+        if !viewer_window.follow {
+            continue;
+        }
+
+        // Lead the main camera, which the other windows following it follow in turn.
+        let Some(view) = RelativeView::from_camera(
+            &transform,
+            &projection,
+            image.get_world_max_size_rect().size(),
+            Vec2::ZERO,
+        ) else {
+            continue;
+        };
+
+        view.apply(
+            &mut main_transform,
+            &mut main_projection,
+            app_state.world_image_max_size,
+            app_state.world_origin,
+        );
+
+        if let Some(tiled_image) = &tiled_image
+            && let Projection::Orthographic(orthographic) = main_projection.as_ref()
+        {
+            app_state.level = tiled_image.get_level_at(orthographic.scale, app_state.scale_factor);
+        }
+
+        tile_mod_state.invalidate();
+        redraw_request_writer.write(RequestRedraw);
+    }
+}
+
+/// Apply the view of the main camera to the cameras of the windows following it when it
+/// changes.
+pub(crate) fn follow_main_camera_system(
+    main_camera: Query<
+        (&Transform, &Projection),
+        (
+            With<MainCamera2d>,
+            Without<ViewerWindowCamera>,
+            Or<(Changed<Transform>, Changed<Projection>)>,
+        ),
+    >,
+    windows: Query<&ViewerWindow>,
+    mut cameras: Query<
+        (&mut Transform, &mut Projection),
+        (With<ViewerWindowCamera>, Without<MainCamera2d>),
+    >,
+    app_state: Res<AppState>,
+) {
+    let Ok((main_transform, main_projection)) = main_camera.single() else {
+        return;
+    };
+    let Some(view) = RelativeView::from_camera(
+        main_transform,
+        main_projection,
+        app_state.world_image_max_size,
+        app_state.world_origin,
+    ) else {
+        return;
+    };
+
+    for viewer_window in windows.iter().filter(|x| x.follow) {
+        let Some(image) = &viewer_window.image else {
+            continue;
+        };
+
+        if let Ok((mut transform, mut projection)) = cameras.get_mut(viewer_window.camera) {
+            follow_main_view(view, image, &mut transform, &mut projection);
+        }
+    }
+}
+
+/// Spawn the tiles of the level of each window in its view, drawn only by the camera of the
+/// window, and despawn the finer ones and those out of view.
+///
+/// The coarser tiles in view are kept underneath, so that the view is not left blank by a zoom.
+pub(crate) fn update_window_tiles_system(
+    mut commands: Commands,
+    mut windows: Query<(&Window, &mut ViewerWindow)>,
+    cameras: Query<(&Camera, &GlobalTransform, &Projection), With<ViewerWindowCamera>>,
+    mut tile_cache: ResMut<TileCache>,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (window, mut viewer_window) in &mut windows {
+        let viewer_window = viewer_window.as_mut();
+        let Some(image) = &viewer_window.image else {
+            continue;
+        };
+        let Ok((camera, global_transform, Projection::Orthographic(orthographic))) =
+            cameras.get(viewer_window.camera)
+        else {
+            continue;
+        };
+        let Some((world_pos_min, world_pos_max)) =
+            camera_ext::get_world_viewport_rect(camera, global_transform)
+        else {
+            continue;
+        };
+
+        let level = image.get_level_at(orthographic.scale, window.scale_factor());
+        let world_rect = Rect::from_corners(world_pos_min.truncate(), world_pos_max.truncate());
+        let (required_tiles, _, _) = image.get_required_tiles(level, world_pos_min, world_pos_max);
+
+        viewer_window.tiles.retain(|index, (entity, rect)| {
+            let is_kept = index.level() <= level && !rect.intersect(world_rect).is_empty();
+
+            if !is_kept {
+                commands.entity(*entity).despawn();
+            }
+
+            is_kept
+        });
+
+        for tile in required_tiles {
+            if viewer_window.tiles.contains_key(&tile.index) {
+                continue;
+            }
+
+            let url = image.get_image_tile_url_at(tile.index, tile.request_position);
+            // Shares the downloads and the limits of the hosts with the main window.
+            let handle = tile_cache.load_image(&asset_server, url);
+            // Drawn once the image is loaded, the finer levels over the coarser ones.
+            let entity = commands
+                .spawn((
+                    Transform::from_translation(
+                        tile.world_position.center().extend(tile.index.z as f32),
+                    ),
+                    Mesh2d(meshes.add(Rectangle::new(
+                        tile.world_position.width(),
+                        tile.world_position.height(),
+                    ))),
+                    MeshMaterial2d(materials.add(ColorMaterial {
+                        texture: Some(handle),
+                        uv_transform: tile.uv_transform,
+                        ..default()
+                    })),
+                    viewer_window.render_layers.clone(),
+                ))
+                .id();
+
+            viewer_window
+                .tiles
+                .insert(tile.index, (entity, tile.world_position));
+        }
+    }
+}
+
+/// Triggered when a viewer window is closed to despawn its camera and its tiles.
+pub(crate) fn on_remove_viewer_window(
+    remove: On<Remove, ViewerWindow>,
+    mut commands: Commands,
+    windows: Query<&ViewerWindow>,
+) {
+    let Ok(viewer_window) = windows.get(remove.entity) else {
+        warn!("Viewer window {:?} not found when removed.", remove.entity);
+        return;
+    };
+
+    info!("Viewer window closed. {:?}", remove.entity);
+
+    commands.entity(viewer_window.camera).despawn();
+    for (entity, _) in viewer_window.tiles.values() {
+        commands.entity(*entity).despawn();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_zoomed_translation() {
+        let translation = Vec2::new(100.0, -50.0);
+
+        // Zoomed about the centre of the viewport.
+        assert_eq!(
+            get_zoomed_translation(translation, 2.0, Vec2::ZERO, 0.5),
+            translation
+        );
+
+        // The point under the cursor stays in place.
+        let cursor_offset = Vec2::new(40.0, 20.0);
+        let point = translation + Vec2::new(40.0, -20.0) * 2.0;
+        let zoomed = get_zoomed_translation(translation, 2.0, cursor_offset, 0.5);
+
+        assert_eq!(zoomed + Vec2::new(40.0, -20.0) * 2.0 * 0.5, point);
+    }
+}
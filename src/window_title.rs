@@ -0,0 +1,246 @@
+use crate::{
+    app::{app_settings::AppSettings, app_state::AppState},
+    presentation::manifest::Manifest,
+};
+use bevy::{
+    ecs::system::NonSendMarker,
+    prelude::{Entity, MessageWriter, Res, ResMut, Resource, Single, With, debug, warn},
+    window::{PrimaryWindow, RequestRedraw, Window},
+    winit::WINIT_WINDOWS,
+};
+use image::imageops::FilterType;
+use std::sync::{Arc, Mutex};
+
+/// Title of the window before any manifest is shown, as the name of the app.
+const APP_TITLE: &str = "RS IIIF Browser";
+
+/// Size in pixels of the square the icon of the window is fitted in.
+const ICON_SIZE: u32 = 64;
+
+/// State of the download of the image of the icon of the window.
+enum IconDownload {
+    Loading,
+    Loaded(Vec<u8>),
+    Failed(String),
+}
+
+/// Title and icon of the window, following the manifest and the canvas shown.
+#[derive(Resource, Default)]
+pub(crate) struct WindowTitle {
+    /// Title of the command line instead of the one of the manifest. `None` if not given.
+    override_title: Option<String>,
+    /// URL of the image of the icon of the manifest shown. Empty if none.
+    icon_url: String,
+    icon: Arc<Mutex<Option<IconDownload>>>,
+    /// Whether the icon of the manifest is to be set or removed.
+    is_icon_changed: bool,
+}
+
+impl WindowTitle {
+    pub(crate) fn new(override_title: Option<String>) -> Self {
+        Self {
+            override_title,
+            ..Default::default()
+        }
+    }
+}
+
+/// Get the title of the window of the canvas of the manifest, e.g. "Book of Hours — p. f. 1r".
+pub(crate) fn get_window_title(manifest_title: &str, canvas_label: &str) -> String {
+    match (manifest_title.trim(), canvas_label.trim()) {
+        ("", _) => APP_TITLE.to_string(),
+        (manifest_title, "") => manifest_title.to_string(),
+        (manifest_title, canvas_label) => format!("{} — p. {}", manifest_title, canvas_label),
+    }
+}
+
+/// Get the URL of the image of the icon of the manifest, its logo or its thumbnail. Empty if
+/// none.
+fn get_icon_url(presentation: &Manifest) -> String {
+    presentation
+        .model()
+        .get_logo()
+        .next()
+        .filter(|x| !x.is_empty())
+        .unwrap_or_else(|| presentation.model().get_thumbnail())
+        .to_string()
+}
+
+/// Decode the image of the icon fitted in its size into the RGBA pixels with the size.
+fn decode_icon(bytes: &[u8]) -> Result<(Vec<u8>, u32, u32), String> {
+    let icon = image::load_from_memory(bytes)
+        .map_err(|e| e.to_string())?
+        .resize(ICON_SIZE, ICON_SIZE, FilterType::Triangle)
+        .to_rgba8();
+    let (width, height) = icon.dimensions();
+
+    Ok((icon.into_raw(), width, height))
+}
+
+/// Set the title of the window to the manifest and the canvas shown as they are navigated, and
+/// start to download the icon of the manifest when another one is shown.
+pub(crate) fn window_title_system(
+    mut window_title: ResMut<WindowTitle>,
+    mut window: Single<&mut Window, With<PrimaryWindow>>,
+    presentation: Option<Single<&Manifest>>,
+    app_state: Res<AppState>,
+    app_settings: Res<AppSettings>,
+    mut redraw_request_writer: MessageWriter<RequestRedraw>,
+) {
+    let title = match (&window_title.override_title, presentation.as_ref()) {
+        (Some(title), _) => title.to_string(),
+        (None, Some(presentation)) => {
+            let canvas_label = presentation
+                .model()
+                .get_sequence(app_state.sequence_index)
+                .and_then(|x| x.get_canvas(app_state.canvas_index))
+                .map(|x| {
+                    x.get_label(&app_settings.language)
+                        .collect::<Vec<_>>()
+                        .join(",")
+                })
+                .unwrap_or_default();
+
+            get_window_title(
+                &presentation.model().get_title(&app_settings.language),
+                &canvas_label,
+            )
+        }
+        (None, None) => APP_TITLE.to_string(),
+    };
+
+    if window.title != title {
+        window.title = title;
+    }
+
+    let icon_url = presentation
+        .as_ref()
+        .map(|x| get_icon_url(x))
+        .unwrap_or_default();
+
+    if window_title.icon_url != icon_url {
+        debug!("Window icon {:?}", icon_url);
+
+        window_title.icon_url = icon_url.clone();
+        window_title.is_icon_changed = true;
+
+        // The download of the icon of the previous manifest is dropped.
+        window_title.icon = Arc::new(Mutex::new(None));
+
+        if !icon_url.is_empty() {
+            let icon = Arc::clone(&window_title.icon);
+            let request = ehttp::Request::get(crate::web::get_proxy_url(
+                &app_settings.proxy_prefix,
+                &icon_url,
+            ));
+
+            if let Ok(mut x) = icon.lock() {
+                *x = Some(IconDownload::Loading);
+            }
+
+            ehttp::fetch(request, move |result| {
+                let download = match result {
+                    Ok(response) if response.ok => IconDownload::Loaded(response.bytes),
+                    Ok(response) => IconDownload::Failed(format!(
+                        "{} {}",
+                        response.status, response.status_text
+                    )),
+                    Err(e) => IconDownload::Failed(e),
+                };
+
+                if let Ok(mut x) = icon.lock() {
+                    *x = Some(download);
+                }
+            });
+        }
+    }
+
+    // Keep polling until the icon is downloaded.
+    if window_title
+        .icon
+        .lock()
+        .is_ok_and(|x| matches!(*x, Some(IconDownload::Loading)))
+    {
+        redraw_request_writer.write(RequestRedraw);
+    }
+}
+
+/// Set the icon of the window to the one of the manifest once downloaded, or back to the default
+/// one if the manifest has none. On the main thread of the windows.
+pub(crate) fn window_icon_system(
+    mut window_title: ResMut<WindowTitle>,
+    window: Single<Entity, With<PrimaryWindow>>,
+    _non_send_marker: NonSendMarker,
+) {
+    if !window_title.is_icon_changed {
+        return;
+    }
+
+    let icon = match window_title.icon.lock().as_deref() {
+        Ok(Some(IconDownload::Loading)) | Err(_) => return,
+        Ok(Some(IconDownload::Loaded(bytes))) => {
+            match decode_icon(bytes).and_then(|(rgba, width, height)| {
+                winit::window::Icon::from_rgba(rgba, width, height).map_err(|e| e.to_string())
+            }) {
+                Ok(icon) => Some(icon),
+                Err(e) => {
+                    warn!(
+                        "Failed to decode the window icon {:?}. {}",
+                        window_title.icon_url, e
+                    );
+                    None
+                }
+            }
+        }
+        Ok(Some(IconDownload::Failed(e))) => {
+            warn!(
+                "Failed to download the window icon {:?}. {}",
+                window_title.icon_url, e
+            );
+            None
+        }
+        Ok(None) => None,
+    };
+
+    WINIT_WINDOWS.with_borrow(|winit_windows| {
+        if let Some(winit_window) = winit_windows.get_window(*window) {
+            winit_window.set_window_icon(icon);
+        }
+    });
+
+    window_title.is_icon_changed = false;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_window_title() {
+        assert_eq!(get_window_title("", "f. 1r"), APP_TITLE);
+        assert_eq!(get_window_title("Book of Hours ", ""), "Book of Hours");
+        assert_eq!(
+            get_window_title("Book of Hours", "f. 1r"),
+            "Book of Hours — p. f. 1r"
+        );
+    }
+
+    #[test]
+    fn test_decode_icon() {
+        let mut bytes = Vec::new();
+
+        image::DynamicImage::ImageRgb8(image::RgbImage::new(256, 128))
+            .write_to(
+                &mut std::io::Cursor::new(&mut bytes),
+                image::ImageFormat::Png,
+            )
+            .expect("should encode the image");
+
+        let (rgba, width, height) = decode_icon(&bytes).expect("should decode the icon");
+
+        // Fitted in the size of the icon.
+        assert_eq!((width, height), (ICON_SIZE, ICON_SIZE / 2));
+        assert_eq!(rgba.len(), (width * height * 4) as usize);
+        assert!(decode_icon(b"not an image").is_err());
+    }
+}